@@ -2,33 +2,114 @@
 //!
 //! This module is responsible for:
 //! 1. Initializing AWS configuration (region, credentials).
-//! 2. Creating AWS service clients (CloudWatch Logs, CloudFormation, STS).
-//! 3. Discovering relevant log group names based on user-provided patterns or
-//!    CloudFormation stack names.
+//! 2. Creating AWS service clients (CloudWatch Logs, CloudFormation, Lambda, STS).
+//! 3. Discovering relevant log group names based on user-provided patterns,
+//!    CloudFormation stack names or Lambda function tags.
 //! 4. Validating the existence of these log groups, including handling common
 //!    Lambda@Edge naming conventions.
 //! 5. Constructing ARNs for the validated log groups.
+//! 6. Assuming IAM roles to do the same in other accounts.
+//! 7. Keeping the discovery sources of each account, to discover again the log groups created
+//!    during the session.
 
 use anyhow::{Context, Result};
 use aws_config::meta::region::RegionProviderChain;
+use aws_config::sts::AssumeRoleProvider;
+use aws_config::SdkConfig;
 use aws_sdk_cloudformation::Client as CfnClient;
 use aws_sdk_cloudwatchlogs::Client as CwlClient;
+use aws_sdk_lambda::Client as LambdaClient;
 use aws_sdk_sts::Client as StsClient;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use indexmap::{IndexMap, IndexSet};
+use std::collections::HashMap;
+
+use crate::metrics::{CwlApi, METRICS};
+
+// Number of Lambda functions whose tags are fetched concurrently during tag discovery
+const LIST_TAGS_CONCURRENCY: usize = 8;
+
+// Session name of the roles assumed to tail the log groups of other accounts
+const ROLE_SESSION_NAME: &str = "livetrace";
 
 // AWS Setup Public Function
 
-pub struct AwsSetupResult {
+/// Log groups to tail in one account, with the client to tail them
+pub struct AccountLogGroups {
+    pub cwl_client: CwlClient,
+    pub account_id: String,
+    pub role_arn: Option<String>,
+    pub resolved_arns: Vec<String>,
+    pub discovery: LogGroupDiscovery,
+}
+
+/// The clients and discovery sources of an account, to run the log group discovery again
+#[derive(Clone)]
+pub struct LogGroupDiscovery {
     pub cwl_client: CwlClient,
+    cfn_client: CfnClient,
+    lambda_client: LambdaClient,
+    account_id: String,
+    region_str: String,
+    partition: String,
+    log_group_pattern: Option<Vec<String>>,
+    stack_names: Option<Vec<String>>,
+    tags: Option<Vec<String>>,
+}
+
+impl LogGroupDiscovery {
+    /// Discovers the log groups of the stacks, patterns and tags, and returns the names of the
+    /// existing ones.
+    pub async fn discover_log_group_names(&self) -> Result<Vec<String>> {
+        let resolved_log_group_names = discover_log_group_names(
+            &self.cfn_client,
+            &self.cwl_client,
+            &self.lambda_client,
+            &self.log_group_pattern,
+            &self.stack_names,
+            &self.tags,
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to discover log groups in account {}",
+                self.account_id
+            )
+        })?;
+
+        tracing::debug!("Validating discovered log group names...");
+        validate_log_groups(&self.cwl_client, resolved_log_group_names, &self.region_str).await
+    }
+
+    /// Returns the ARN of a log group of the account
+    pub fn log_group_arn(&self, name: &str) -> String {
+        format!(
+            "arn:{}:logs:{}:{}:log-group:{}",
+            self.partition, self.region_str, self.account_id, name
+        )
+    }
+}
+
+pub struct AwsSetupResult {
     pub account_id: String,
     pub region_str: String,
     #[allow(dead_code)]
     pub partition: String,
-    pub resolved_arns: Vec<String>,
+    pub accounts: Vec<AccountLogGroups>,
+}
+
+/// An IAM role to assume, with the log group patterns to tail in its account
+#[derive(Debug, PartialEq)]
+struct RoleTarget {
+    role_arn: String,
+    patterns: Option<Vec<String>>,
 }
 
 pub async fn setup_aws_resources(
     log_group_pattern: &Option<Vec<String>>,
-    stack_name: &Option<String>,
+    stack_names: &Option<Vec<String>>,
+    tags: &Option<Vec<String>>,
+    role_arns: &Option<Vec<String>>,
     aws_region: &Option<String>,
     aws_profile: &Option<String>,
 ) -> Result<AwsSetupResult> {
@@ -51,41 +132,137 @@ pub async fn setup_aws_resources(
         aws_config.region()
     );
 
-    // 2. Create AWS Clients
-    let cwl_client = CwlClient::new(&aws_config);
-    tracing::debug!("CloudWatch Logs client created.");
-    let cfn_client = CfnClient::new(&aws_config);
-    tracing::debug!("CloudFormation client created.");
-    let sts_client = StsClient::new(&aws_config);
-    tracing::debug!("STS client created.");
-
     // Get Account ID and Region for ARN construction
     let region_str = aws_config
         .region()
         .ok_or_else(|| anyhow::anyhow!("Could not determine AWS region from config"))?
         .to_string();
-    let caller_identity = sts_client
-        .get_caller_identity()
-        .send()
+    let account_id = get_account_id(&aws_config)
         .await
         .context("Failed to get caller identity from STS")?;
-    let account_id = caller_identity
+    let partition = "aws"; // Assuming standard AWS partition
+    tracing::debug!(region = %region_str, account_id = %account_id, partition = %partition, "Determined region, account ID, and partition");
+
+    // The caller's account is tailed when discovery options are given, and each role's account
+    // with its own patterns, or else the same discovery options
+    let has_discovery_sources =
+        log_group_pattern.is_some() || stack_names.is_some() || tags.is_some();
+    let role_targets = parse_role_targets(role_arns.iter().flatten())?;
+    let mut accounts = Vec::new();
+    if has_discovery_sources {
+        accounts.push(
+            resolve_account_log_groups(
+                &aws_config,
+                account_id.clone(),
+                None,
+                &region_str,
+                partition,
+                log_group_pattern,
+                stack_names,
+                tags,
+            )
+            .await?,
+        );
+    }
+    for target in role_targets {
+        if target.patterns.is_none() && !has_discovery_sources {
+            return Err(anyhow::anyhow!(
+                "Role '{}' has no log group patterns, and no --log-group-pattern, --stack-name or --tag was provided",
+                target.role_arn
+            ));
+        }
+        let role_config = assume_role_config(&aws_config, &target.role_arn, &region_str).await;
+        let role_account_id = get_account_id(&role_config)
+            .await
+            .with_context(|| format!("Failed to assume role '{}'", target.role_arn))?;
+        tracing::debug!(role_arn = %target.role_arn, account_id = %role_account_id, "Assumed role");
+        let (role_patterns, role_stacks, role_tags) = match &target.patterns {
+            Some(patterns) => (Some(patterns.clone()), None, None),
+            None => (log_group_pattern.clone(), stack_names.clone(), tags.clone()),
+        };
+        accounts.push(
+            resolve_account_log_groups(
+                &role_config,
+                role_account_id,
+                Some(target.role_arn),
+                &region_str,
+                partition,
+                &role_patterns,
+                &role_stacks,
+                &role_tags,
+            )
+            .await?,
+        );
+    }
+
+    Ok(AwsSetupResult {
+        account_id,
+        region_str,
+        partition: partition.to_string(), // Convert &str to String
+        accounts,
+    })
+}
+
+// Private Helper Functions
+
+/// Returns the account ID of the credentials of a config.
+async fn get_account_id(aws_config: &SdkConfig) -> Result<String> {
+    let caller_identity = StsClient::new(aws_config)
+        .get_caller_identity()
+        .send()
+        .await?;
+    Ok(caller_identity
         .account()
         .ok_or_else(|| {
             anyhow::anyhow!("Could not determine AWS Account ID from STS caller identity")
         })?
-        .to_string();
-    let partition = "aws"; // Assuming standard AWS partition
-    tracing::debug!(region = %region_str, account_id = %account_id, partition = %partition, "Determined region, account ID, and partition");
+        .to_string())
+}
 
-    // 5. Discover Log Groups based on pattern or stack name
-    let resolved_log_group_names =
-        discover_log_group_names(&cfn_client, &cwl_client, log_group_pattern, stack_name).await?;
+/// Loads a config with the credentials of a role, assumed with the credentials of the base config.
+async fn assume_role_config(aws_config: &SdkConfig, role_arn: &str, region_str: &str) -> SdkConfig {
+    let provider = AssumeRoleProvider::builder(role_arn)
+        .configure(aws_config)
+        .session_name(ROLE_SESSION_NAME)
+        .build()
+        .await;
+    aws_config::defaults(aws_config::BehaviorVersion::latest())
+        .region(aws_config::Region::new(region_str.to_string()))
+        .credentials_provider(provider)
+        .load()
+        .await
+}
 
-    // Add validation step
-    tracing::debug!("Validating discovered log group names...");
-    let validated_log_group_names =
-        validate_log_groups(&cwl_client, resolved_log_group_names, &region_str).await?;
+/// Discovers and validates the log groups to tail in the account of a config.
+#[allow(clippy::too_many_arguments)]
+async fn resolve_account_log_groups(
+    aws_config: &SdkConfig,
+    account_id: String,
+    role_arn: Option<String>,
+    region_str: &str,
+    partition: &str,
+    log_group_pattern: &Option<Vec<String>>,
+    stack_names: &Option<Vec<String>>,
+    tags: &Option<Vec<String>>,
+) -> Result<AccountLogGroups> {
+    // 2. Create AWS Clients
+    let cwl_client = CwlClient::new(aws_config);
+    tracing::debug!("CloudWatch Logs client created.");
+    let discovery = LogGroupDiscovery {
+        cwl_client: cwl_client.clone(),
+        cfn_client: CfnClient::new(aws_config),
+        lambda_client: LambdaClient::new(aws_config),
+        account_id: account_id.clone(),
+        region_str: region_str.to_string(),
+        partition: partition.to_string(),
+        log_group_pattern: log_group_pattern.clone(),
+        stack_names: stack_names.clone(),
+        tags: tags.clone(),
+    };
+    tracing::debug!("CloudFormation and Lambda clients created.");
+
+    // 5. Discover and validate Log Groups based on pattern, stack name or tags
+    let validated_log_group_names = discovery.discover_log_group_names().await?;
     tracing::debug!(
         "Validation complete. Valid names: {:?}",
         validated_log_group_names
@@ -94,34 +271,19 @@ pub async fn setup_aws_resources(
     // Validate count of *validated* names
     let group_count = validated_log_group_names.len(); // Use validated count
     if group_count == 0 {
-        let error_msg = if stack_name.is_some() {
-            format!("Stack '{}' contained 0 discoverable and valid LogGroup resources (checked Lambda@Edge variants).", stack_name.as_deref().unwrap_or("N/A"))
-        } else {
-            format!(
-                "Log Groups Patterns {:?} matched 0 valid log groups (checked Lambda@Edge variants).",
-                log_group_pattern.as_ref().map_or(vec!["N/A".to_string()], |v| v.to_vec())
-            )
-        };
-        return Err(anyhow::anyhow!(error_msg));
+        return Err(anyhow::anyhow!(
+            "{} matched 0 valid log groups in account {} (checked Lambda@Edge variants).",
+            describe_discovery_sources(log_group_pattern, stack_names, tags),
+            account_id
+        ));
     } else if group_count > 10 {
-        let (method, value) = if let Some(stack) = stack_name.as_deref() {
-            ("Stack", stack.to_string())
-        } else {
-            (
-                "Log Groups Patterns",
-                format!(
-                    "{:?}",
-                    log_group_pattern
-                        .as_ref()
-                        .map_or(vec!["N/A".to_string()], |v| v.to_vec())
-                ),
-            )
-        };
-        let error_msg = format!(
-            "{} {} resulted in {} valid log groups (max 10 allowed for live tail). Found: {:?}",
-            method, value, group_count, validated_log_group_names
-        );
-        return Err(anyhow::anyhow!(error_msg));
+        return Err(anyhow::anyhow!(
+            "{} resulted in {} valid log groups in account {} (max 10 allowed for live tail). Found: {:?}",
+            describe_discovery_sources(log_group_pattern, stack_names, tags),
+            group_count,
+            account_id,
+            validated_log_group_names
+        ));
     } else {
         tracing::debug!(
             "Proceeding with {} validated log group name(s): {:?}",
@@ -133,63 +295,123 @@ pub async fn setup_aws_resources(
     // Construct ARNs from *validated* names
     let resolved_log_group_arns: Vec<String> = validated_log_group_names
         .iter()
-        .map(|name| {
-            format!(
-                "arn:{}:logs:{}:{}:log-group:{}",
-                partition, region_str, account_id, name
-            )
-        })
+        .map(|name| discovery.log_group_arn(name))
         .collect();
     tracing::debug!("Constructed ARNs: {:?}", resolved_log_group_arns);
 
-    Ok(AwsSetupResult {
+    Ok(AccountLogGroups {
         cwl_client, // Return the CWL client for starting the tail
         account_id,
-        region_str,
-        partition: partition.to_string(), // Convert &str to String
+        role_arn,
         resolved_arns: resolved_log_group_arns,
+        discovery,
     })
 }
 
-// Private Helper Functions
+/// Parses `ARN[:PATTERN,...]` role arguments. Log group names cannot contain colons, so the
+/// patterns are what follows the six colon-separated parts of the role ARN.
+fn parse_role_targets<'a>(role_arns: impl Iterator<Item = &'a String>) -> Result<Vec<RoleTarget>> {
+    let mut targets: Vec<RoleTarget> = Vec::new();
+    for value in role_arns {
+        let parts: Vec<&str> = value.splitn(7, ':').collect();
+        if parts.len() < 6
+            || parts[0] != "arn"
+            || parts[2] != "iam"
+            || !parts[5].starts_with("role/")
+        {
+            return Err(anyhow::anyhow!(
+                "Invalid role '{}', expected arn:<partition>:iam::<account>:role/<name>[:<pattern>,...]",
+                value
+            ));
+        }
+        let role_arn = parts[..6].join(":");
+        let patterns: Option<Vec<String>> = parts.get(6).map(|patterns| {
+            patterns
+                .split(',')
+                .filter(|pattern| !pattern.is_empty())
+                .map(str::to_string)
+                .collect()
+        });
+        let patterns = patterns.filter(|patterns| !patterns.is_empty());
+        if targets.iter().any(|target| target.role_arn == role_arn) {
+            return Err(anyhow::anyhow!(
+                "Role '{}' is specified more than once",
+                role_arn
+            ));
+        }
+        targets.push(RoleTarget { role_arn, patterns });
+    }
+    Ok(targets)
+}
+
+/// Describes the discovery sources in use, for error messages.
+fn describe_discovery_sources(
+    log_group_pattern: &Option<Vec<String>>,
+    stack_names: &Option<Vec<String>>,
+    tags: &Option<Vec<String>>,
+) -> String {
+    let sources: Vec<String> = [
+        ("Stacks", stack_names),
+        ("Log Groups Patterns", log_group_pattern),
+        ("Tags", tags),
+    ]
+    .into_iter()
+    .filter_map(|(name, values)| {
+        values
+            .as_ref()
+            .map(|values| format!("{} {:?}", name, values))
+    })
+    .collect();
+    sources.join(" and ")
+}
 
-/// Discovers log group names based on stack or pattern arguments.
+/// Discovers log group names based on stack, pattern or tag arguments.
+///
+/// Stacks, patterns and tags can be combined; a log group found by several of them is returned
+/// once, in the order it was first discovered.
 async fn discover_log_group_names(
     cfn_client: &CfnClient,
     cwl_client: &CwlClient,
+    lambda_client: &LambdaClient,
     log_group_pattern: &Option<Vec<String>>,
-    stack_name: &Option<String>,
+    stack_names: &Option<Vec<String>>,
+    tags: &Option<Vec<String>>,
 ) -> Result<Vec<String>> {
-    // Create a HashSet to collect all log groups and avoid duplicates
-    let mut all_log_groups = std::collections::HashSet::new();
+    // Create an IndexSet to collect all log groups and avoid duplicates
+    let mut all_log_groups = IndexSet::new();
 
-    // Process stack name if provided
-    if let Some(stack) = stack_name.as_deref() {
+    // Process stack names if provided
+    for stack in stack_names.iter().flatten() {
         let stack_groups = discover_log_groups_from_stack(cfn_client, stack).await?;
-        for group in stack_groups {
-            all_log_groups.insert(group);
-        }
+        all_log_groups.extend(stack_groups);
     }
 
     // Process log group patterns if provided
     if let Some(patterns) = log_group_pattern {
         if !patterns.is_empty() {
             let pattern_groups = discover_log_groups_by_patterns(cwl_client, patterns).await?;
-            for group in pattern_groups {
-                all_log_groups.insert(group);
-            }
+            all_log_groups.extend(pattern_groups);
+        }
+    }
+
+    // Process tag filters if provided
+    if let Some(tags) = tags {
+        if !tags.is_empty() {
+            let tag_filters = parse_tag_filters(tags)?;
+            let tagged_groups = discover_log_groups_by_tags(lambda_client, &tag_filters).await?;
+            all_log_groups.extend(tagged_groups);
         }
     }
 
     // Return error if neither was provided or both were empty
     if all_log_groups.is_empty() {
-        if stack_name.is_none() && log_group_pattern.is_none() {
+        if stack_names.is_none() && log_group_pattern.is_none() && tags.is_none() {
             return Err(anyhow::anyhow!(
-                "Internal error: No log group pattern or stack name provided."
+                "Internal error: No log group pattern, stack name or tag provided."
             ));
         } else {
             return Err(anyhow::anyhow!(
-                "No log groups found with the provided pattern(s) and/or stack name."
+                "No log groups found with the provided pattern(s), stack name(s) and/or tag(s)."
             ));
         }
     }
@@ -205,17 +427,15 @@ async fn discover_log_groups_by_patterns(
 ) -> Result<Vec<String>> {
     tracing::debug!("Discovering log groups matching patterns: {:?}", patterns);
 
-    // Use a HashSet to avoid duplicates when multiple patterns match the same log group
-    let mut discovered_groups = std::collections::HashSet::new();
+    // Use an IndexSet to avoid duplicates when multiple patterns match the same log group
+    let mut discovered_groups = IndexSet::new();
 
     // Process each pattern in sequence
     for pattern in patterns {
         // Call the existing function that handles a single pattern
         let groups = discover_log_groups_by_pattern(cwl_client, pattern).await?;
         // Add results to our set
-        for group in groups {
-            discovered_groups.insert(group);
-        }
+        discovered_groups.extend(groups);
     }
 
     // Convert back to Vec for the return value
@@ -228,6 +448,7 @@ async fn discover_log_groups_by_pattern(
     pattern: &str,
 ) -> Result<Vec<String>> {
     tracing::debug!("Discovering log groups matching pattern: '{}'", pattern);
+    METRICS.record_cwl_api_call(CwlApi::DescribeLogGroups);
     let describe_output = cwl_client
         .describe_log_groups()
         .log_group_name_pattern(pattern)
@@ -292,6 +513,90 @@ async fn discover_log_groups_from_stack(
     Ok(discovered_groups)
 }
 
+/// Parses `key=value` tag filters, grouping the accepted values of each key.
+fn parse_tag_filters(tags: &[String]) -> Result<IndexMap<String, Vec<String>>> {
+    let mut filters: IndexMap<String, Vec<String>> = IndexMap::new();
+    for tag in tags {
+        let (key, value) = tag
+            .split_once('=')
+            .filter(|(key, _)| !key.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Invalid tag '{}', expected key=value", tag))?;
+        filters
+            .entry(key.to_string())
+            .or_default()
+            .push(value.to_string());
+    }
+    Ok(filters)
+}
+
+/// Returns true if the tags have every key of the filters, with one of its accepted values.
+fn tags_match(filters: &IndexMap<String, Vec<String>>, tags: &HashMap<String, String>) -> bool {
+    filters
+        .iter()
+        .all(|(key, values)| tags.get(key).is_some_and(|value| values.contains(value)))
+}
+
+/// Discovers the log groups of the Lambda functions whose tags match the filters.
+async fn discover_log_groups_by_tags(
+    lambda_client: &LambdaClient,
+    tag_filters: &IndexMap<String, Vec<String>>,
+) -> Result<Vec<String>> {
+    tracing::debug!(
+        ?tag_filters,
+        "Discovering log groups from Lambda function tags"
+    );
+    let mut functions = Vec::new();
+    let mut marker: Option<String> = None;
+
+    loop {
+        let output = lambda_client
+            .list_functions()
+            .set_marker(marker)
+            .send()
+            .await
+            .context("Failed to list Lambda functions")?;
+
+        for function in output.functions.unwrap_or_default() {
+            if let (Some(name), Some(arn)) = (function.function_name, function.function_arn) {
+                // Functions can log to a custom log group instead of /aws/lambda/<name>
+                let log_group_name = function
+                    .logging_config
+                    .and_then(|logging_config| logging_config.log_group)
+                    .unwrap_or_else(|| format!("/aws/lambda/{}", name));
+                functions.push((arn, log_group_name));
+            }
+        }
+
+        marker = output.next_marker;
+        if marker.is_none() {
+            break;
+        }
+    }
+
+    // Fetch the tags of the functions concurrently, keeping their listing order
+    let matched: Vec<Option<String>> = stream::iter(functions)
+        .map(|(arn, log_group_name)| async move {
+            let output = lambda_client
+                .list_tags()
+                .resource(&arn)
+                .send()
+                .await
+                .with_context(|| format!("Failed to list tags of Lambda function '{}'", arn))?;
+            let matches = output
+                .tags
+                .is_some_and(|tags| tags_match(tag_filters, &tags));
+            if matches {
+                tracing::debug!(lambda_function = %arn, log_group = %log_group_name, "Adding log group of tagged Lambda function");
+            }
+            Ok::<_, anyhow::Error>(matches.then_some(log_group_name))
+        })
+        .buffered(LIST_TAGS_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    Ok(matched.into_iter().flatten().collect())
+}
+
 /// Validates a list of potential log group names, prioritizing Lambda@Edge patterns.
 pub async fn validate_log_groups(
     cwl_client: &CwlClient,
@@ -350,12 +655,15 @@ pub async fn validate_log_groups(
 
     let results = futures::future::join_all(checks).await;
 
-    let mut validated_names = Vec::new();
+    // Several names can resolve to the same log group (e.g. a Lambda@Edge variant)
+    let mut validated_names = IndexSet::new();
     let mut errors = Vec::new(); // Collect errors to potentially report them all
 
     for result in results {
         match result {
-            Ok(Some(name)) => validated_names.push(name),
+            Ok(Some(name)) => {
+                validated_names.insert(name);
+            }
             Ok(None) => {}            // Logged within the check, skip
             Err(e) => errors.push(e), // Collect error
         }
@@ -366,11 +674,12 @@ pub async fn validate_log_groups(
         return Err(first_error);
     }
 
-    Ok(validated_names)
+    Ok(validated_names.into_iter().collect())
 }
 
 /// Helper to describe a single log group by exact name.
 async fn describe_exact_log_group(client: &CwlClient, name: &str) -> Result<Option<String>> {
+    METRICS.record_cwl_api_call(CwlApi::DescribeLogGroups);
     match client
         .describe_log_groups()
         .log_group_name_prefix(name) // Use prefix for API
@@ -402,3 +711,70 @@ async fn describe_exact_log_group(client: &CwlClient, name: &str) -> Result<Opti
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_filters_match_every_key() {
+        let filters =
+            parse_tag_filters(&["team=payments".into(), "env=dev".into(), "env=qa".into()])
+                .unwrap();
+        let tags = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+
+        assert!(tags_match(
+            &filters,
+            &tags(&[("team", "payments"), ("env", "qa"), ("owner", "x")])
+        ));
+        assert!(!tags_match(&filters, &tags(&[("team", "payments")])));
+        assert!(!tags_match(
+            &filters,
+            &tags(&[("team", "payments"), ("env", "prod")])
+        ));
+
+        let error = parse_tag_filters(&["payments".into()]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid tag 'payments', expected key=value"
+        );
+    }
+
+    #[test]
+    fn test_parse_role_targets() {
+        let role_arns = [
+            "arn:aws:iam::111111111111:role/reader".to_string(),
+            "arn:aws:iam::222222222222:role/path/reader:/aws/lambda/orders-,/aws/lambda/cart-"
+                .to_string(),
+        ];
+        assert_eq!(
+            parse_role_targets(role_arns.iter()).unwrap(),
+            vec![
+                RoleTarget {
+                    role_arn: "arn:aws:iam::111111111111:role/reader".to_string(),
+                    patterns: None,
+                },
+                RoleTarget {
+                    role_arn: "arn:aws:iam::222222222222:role/path/reader".to_string(),
+                    patterns: Some(vec![
+                        "/aws/lambda/orders-".to_string(),
+                        "/aws/lambda/cart-".to_string(),
+                    ]),
+                },
+            ]
+        );
+
+        let invalid = ["arn:aws:iam::111111111111:user/reader".to_string()];
+        assert!(parse_role_targets(invalid.iter()).is_err());
+        let duplicate = [
+            role_arns[0].clone(),
+            format!("{}:/aws/lambda/x", role_arns[0]),
+        ];
+        assert!(parse_role_targets(duplicate.iter()).is_err());
+    }
+}
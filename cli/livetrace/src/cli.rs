@@ -10,6 +10,8 @@ use clap::{crate_authors, crate_description, Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 // Made public for use in lib.rs
@@ -53,6 +55,68 @@ pub const DEFAULT_TRACE_STRAGGLERS_WAIT_MS: u64 = 0; // 0ms
 pub const DEFAULT_EVENT_SEVERITY_ATTRIBUTE: &str = "event.severity";
 pub const DEFAULT_EVENTS_ONLY: bool = true;
 pub const DEFAULT_COLOR_BY: ColoringMode = ColoringMode::Span;
+pub const DEFAULT_STATS_INTERVAL_MS: u64 = 10 * 1000; // 10s
+
+/// Output format of the flushed traces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default)]
+pub enum OutputFormat {
+    /// Colored waterfall and timeline log (default)
+    #[default]
+    Console,
+    /// One JSON object per span and line
+    Json,
+    /// One OTLP/JSON ExportTraceServiceRequest per trace and line
+    OtlpJson,
+}
+
+/// Transport of the traces forwarded to the OTLP endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default, Serialize, Deserialize)]
+pub enum OtlpProtocol {
+    /// Protobuf over HTTP, to port 4318 of a collector (default)
+    #[default]
+    #[value(name = "http/protobuf")]
+    #[serde(rename = "http/protobuf")]
+    HttpProtobuf,
+    /// gRPC, to port 4317 of a collector
+    #[value(name = "grpc")]
+    #[serde(rename = "grpc")]
+    Grpc,
+}
+
+/// File format of the traces written by `--export-dir`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Default)]
+pub enum ExportFormat {
+    /// Jaeger query API JSON, which the Jaeger UI can open (default)
+    #[default]
+    Jaeger,
+    /// Zipkin v2 JSON list of spans
+    Zipkin,
+    /// OTLP/JSON ExportTraceServiceRequest
+    Otlp,
+}
+
+/// Span status selected by the `--status` filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusFilter {
+    /// Spans whose status was not set
+    Unset,
+    /// Spans with an OK status
+    Ok,
+    /// Spans with an error status
+    Error,
+}
+
+/// Level of an OTLP/stdout record, set by the `level` field of the exporter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordLevel {
+    Debug,
+    Info,
+    #[value(alias = "warning")]
+    Warn,
+    Error,
+}
 
 /// Defines coloring strategies for the console output
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize, Default)]
@@ -79,7 +143,30 @@ EXAMPLES:
     livetrace --log-group-pattern \"/aws/lambda/user-service-\" --save-profile dev
 
     # Load configuration from the \"dev\" profile and override the OTLP endpoint
-    livetrace --config-profile dev -e http://localhost:4319";
+    livetrace --config-profile dev -e http://localhost:4319
+
+    # Record the telemetry of a session, then replay it to a local OTLP collector
+    livetrace --stack-name my-api-stack --stack-name my-auth-stack --pattern /aws/lambda/legacy-
+    livetrace --tag team=payments --tag env=dev
+    livetrace --pattern /aws/lambda/orders- --role-arn arn:aws:iam::123456789012:role/livetrace-reader
+    livetrace --stack-name my-api-stack --refresh-log-groups 1m
+    livetrace --stack-name my-api-stack --stats
+    livetrace --stack-name my-api-stack --sample-ratio 0.1
+    livetrace --stack-name my-api-stack --correlate-logs
+    livetrace --stack-name my-api-stack --notify-on-error --grep order-1234
+    livetrace --stack-name my-api-stack --service-map --service-map-dot services.dot
+    livetrace --stack-name my-api-stack --heatmap
+    livetrace --stack-name my-api-stack --color-rule \"http.response.status_code>=500:red\"
+    livetrace --stack-name my-api-stack --link-template \"https://my-backend/trace/{trace_id}\"
+    livetrace --stack-name my-api-stack --min-level warn
+    livetrace --stack-name my-api-stack --no-color --ascii
+    livetrace --stack-name my-api-stack --session-timeout 8h --metrics-port 9464
+    livetrace --stack-name my-api-stack --export-dir ./traces --export-format zipkin
+    livetrace --stack-name my-api-stack --html-out ./reports
+    livetrace --stack-name my-api-stack --record session.jsonl
+    livetrace -e http://localhost:4318 replay session.jsonl
+    livetrace --stack-name my-api-stack --store traces.db
+    livetrace query traces.db --service checkout --status error --since 1h";
 
 /// livetrace: Tail CloudWatch Logs for OTLP/stdout traces and forward them.
 #[derive(Parser, Debug, Clone)]
@@ -87,12 +174,27 @@ EXAMPLES:
 // Removed the `mode_selector` group as it no longer serves its original purpose.
 pub struct CliArgs {
     /// Log group name pattern(s) for discovery (case-sensitive substring search). Can be specified multiple times.
-    #[arg(short = 'g', long = "log-group-pattern", num_args(1..))]
+    #[arg(short = 'g', long = "log-group-pattern", visible_alias = "pattern", num_args(1..))]
     pub log_group_pattern: Option<Vec<String>>,
 
-    /// CloudFormation stack name for log group discovery.
-    #[arg(short = 's', long = "stack-name")]
-    pub stack_name: Option<String>,
+    /// CloudFormation stack name(s) for log group discovery. Can be specified multiple times and combined with log group patterns.
+    #[arg(short = 's', long = "stack-name", num_args(1..))]
+    pub stack_name: Option<Vec<String>>,
+
+    /// Lambda function tag(s) for log group discovery, as key=value (e.g., "team=payments"). Can be specified multiple times; functions must match every key.
+    #[arg(long = "tag", num_args(1..))]
+    pub tags: Option<Vec<String>>,
+
+    /// IAM role(s) to assume to tail the log groups of other accounts in the same session, each
+    /// optionally followed by its own log group patterns (e.g., "arn:aws:iam::123456789012:role/reader:/aws/lambda/orders-").
+    /// Roles without patterns use the other discovery options. Can be specified multiple times.
+    #[arg(long = "role-arn", value_name = "ARN[:PATTERN,...]", num_args(1..))]
+    pub role_arns: Option<Vec<String>>,
+
+    /// Run the log group discovery again at this interval, and tail the log groups created since,
+    /// so freshly deployed functions appear without restarting livetrace.
+    #[arg(long, value_parser = parse_duration_to_millis, help = "Run the log group discovery again at this interval (e.g., '1m'), and tail the new log groups. Requires suffix: ms, s, m, h.")]
+    pub refresh_log_groups: Option<u64>, // Stores milliseconds
 
     /// The OTLP HTTP endpoint URL to send traces to (e.g., http://localhost:4318/v1/traces).
     #[arg(short = 'e', long)]
@@ -102,6 +204,10 @@ pub struct CliArgs {
     #[arg(short = 'H', long = "otlp-header")]
     pub otlp_headers: Vec<String>,
 
+    /// Protocol of the OTLP endpoint. Defaults to OTEL_EXPORTER_OTLP_TRACES_PROTOCOL/OTEL_EXPORTER_OTLP_PROTOCOL, or http/protobuf.
+    #[arg(long, value_enum)]
+    pub otlp_protocol: Option<OtlpProtocol>,
+
     /// AWS Region to use. Defaults to environment/profile configuration.
     #[arg(short = 'r', long = "aws-region")]
     pub aws_region: Option<String>,
@@ -127,6 +233,21 @@ pub struct CliArgs {
     #[arg(long, group = "mode_selector", value_parser = parse_duration_to_millis, help = "Polling interval (e.g., '10s', '1m'). Requires suffix: ms, s, m, h.")]
     pub poll_interval: Option<u64>, // Stores milliseconds
 
+    /// Maximum number of FilterLogEvents calls per minute in polling mode, across all log groups.
+    /// Polling pauses when the budget is spent, keeping the CloudWatch API costs of long sessions predictable.
+    #[arg(long, value_name = "CALLS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_api_calls_per_minute: Option<u32>,
+
+    /// Serve Prometheus metrics of the session (received events, parse failures, forwarded
+    /// requests and their latency, CloudWatch Logs API calls) at /metrics on this port.
+    #[arg(long, value_name = "PORT")]
+    pub metrics_port: Option<u16>,
+
+    /// Address to serve the metrics of `--metrics-port` on. Defaults to 127.0.0.1; use 0.0.0.0
+    /// to let other hosts scrape them.
+    #[arg(long, value_name = "IP")]
+    pub metrics_addr: Option<IpAddr>,
+
     /// Overall session duration after which livetrace will automatically exit.
     /// Applies to both LiveTail and Polling modes.
     #[arg(long, value_parser = parse_duration_to_millis, help = "Overall session duration (e.g., '30m', '1h'). Requires suffix: ms, s, m, h. [default: 30m]")]
@@ -175,6 +296,33 @@ pub struct CliArgs {
     )]
     pub color_by: Option<ColoringMode>, // Changed to Option<ColoringMode>
 
+    /// Color the spans with an attribute matching a condition, as <key><op><value>:<color>
+    /// (e.g., "http.response.status_code>=500:red"). Operators: =, !=, >, >=, <, <=. Colors: red,
+    /// green, yellow, blue, magenta, cyan, orange, white, gray or #rrggbb. The first matching
+    /// rule applies. Can be specified multiple times.
+    #[arg(
+        long = "color-rule",
+        value_name = "RULE",
+        help_heading = "Display Options"
+    )]
+    pub color_rules: Option<Vec<String>>,
+
+    /// URL of a trace in a tracing backend, with a {trace_id} placeholder (e.g.,
+    /// "https://my-backend/trace/{trace_id}"). The trace ID in the header of each trace is printed
+    /// as a hyperlink to it, in terminals supporting OSC 8 hyperlinks.
+    #[arg(long, value_name = "URL", help_heading = "Display Options")]
+    pub link_template: Option<String>,
+
+    /// Disable the colors of the console output. Colors are also disabled when NO_COLOR is set
+    /// or stdout is not a terminal.
+    #[arg(long, help_heading = "Display Options")]
+    pub no_color: bool,
+
+    /// Draw the console output with ASCII characters instead of Unicode glyphs. Detected for
+    /// dumb terminals, locales without UTF-8 and the legacy Windows console.
+    #[arg(long, help_heading = "Display Options")]
+    pub ascii: bool,
+
     /// Only display events, hiding span start information in the timeline log view.
     #[arg(
         long,
@@ -186,6 +334,93 @@ pub struct CliArgs {
     )]
     pub events_only: Option<bool>, // Changed to Option<bool>
 
+    /// Also read the application log lines of the tailed log streams, and show those carrying
+    /// the trace ID of a displayed trace in its timeline, under the span they were logged in.
+    #[arg(long, help_heading = "Display Options")]
+    pub correlate_logs: bool,
+
+    /// Fire a desktop notification when a span with an error status, or an attribute matching
+    /// `--grep`, arrives, at most once per trace.
+    #[arg(long, help_heading = "Display Options")]
+    pub notify_on_error: bool,
+
+    /// Also POST a JSON description of each notification to this webhook URL.
+    #[arg(long, value_name = "URL", help_heading = "Display Options")]
+    pub notify_webhook: Option<String>,
+
+    /// Output format of the traces. JSON formats print nothing but the traces to stdout, for piping.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Console,
+        help_heading = "Display Options"
+    )]
+    pub output: OutputFormat,
+
+    /// Record the rendered console output to an asciinema (asciicast v2) file, or the received
+    /// telemetry to a session file for `livetrace replay` when FILE ends with `.jsonl`.
+    #[arg(
+        long,
+        value_name = "FILE",
+        help_heading = "Display Options",
+        help = "Record the console output with timing to an asciinema file (e.g., 'session.cast'), or the received telemetry to a session file for 'livetrace replay' (e.g., 'session.jsonl')."
+    )]
+    pub record: Option<PathBuf>,
+
+    /// Write all received spans to a SQLite database, to be queried and re-rendered with `livetrace query`.
+    #[arg(
+        long,
+        value_name = "FILE",
+        help_heading = "Display Options",
+        help = "Write all received spans to a SQLite database (e.g., 'traces.db'), created if needed, for 'livetrace query'."
+    )]
+    pub store: Option<PathBuf>,
+
+    /// Only print a periodic summary table of the request rate, error rate and latency
+    /// percentiles of each service, instead of the traces.
+    #[arg(long, help_heading = "Display Options")]
+    pub stats: bool,
+
+    /// Interval at which the summary table of the request rate, error rate and latency
+    /// percentiles of each service is printed.
+    #[arg(long, value_parser = parse_duration_to_millis, help_heading = "Display Options", help = "Print a summary table of request rate, error rate and latency percentiles per service at this interval (e.g., '10s', '1m'). Requires suffix: ms, s, m, h. [default with --stats: 10s]")]
+    pub stats_interval: Option<u64>, // Stores milliseconds
+
+    /// Print a graph of the calls between services, with their count and p95 latency, instead
+    /// of the traces. It is reprinted at the stats interval when it changes.
+    #[arg(long, help_heading = "Display Options")]
+    pub service_map: bool,
+
+    /// Write the graph of the calls between services to a Graphviz DOT file, kept up to date
+    /// during the session.
+    #[arg(long, value_name = "FILE", help_heading = "Display Options")]
+    pub service_map_dot: Option<PathBuf>,
+
+    /// Print a heatmap of the request latencies of each service over time, one column per
+    /// second, instead of the traces. It is redrawn every second.
+    #[arg(long, help_heading = "Display Options")]
+    pub heatmap: bool,
+
+    /// Write each displayed trace to its own file in this directory, e.g. to open it in the
+    /// Jaeger UI when no collector is running.
+    #[arg(long, value_name = "DIR", help_heading = "Display Options")]
+    pub export_dir: Option<PathBuf>,
+
+    /// File format of the traces written to the export directory.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ExportFormat::Jaeger,
+        requires = "export_dir",
+        help_heading = "Display Options"
+    )]
+    pub export_format: ExportFormat,
+
+    /// Write each displayed trace to a static HTML report in this directory, with its waterfall,
+    /// span attributes and events, to share it in a pull request or ticket.
+    #[arg(long, value_name = "DIR", help_heading = "Display Options")]
+    pub html_out: Option<PathBuf>,
+
     /// Maximum time to wait for spans belonging to a trace before displaying/forwarding it.
     #[arg(long, value_parser = parse_duration_to_millis, help_heading = "Processing Options", help = "Max trace buffering time (e.g., '5s', '500ms'). Requires suffix: ms, s, m, h. [default: 5s]")]
     pub trace_timeout: Option<u64>, // Changed to Option<u64>, removed default_value
@@ -194,6 +429,10 @@ pub struct CliArgs {
     #[arg(long, value_parser = parse_duration_to_millis, help_heading = "Processing Options", help = "Time to wait for straggler spans after last trace activity (if root is present). (e.g., '500ms', '1s'). Requires suffix: ms, s, m, h. [default: 0ms]")]
     pub trace_stragglers_wait: Option<u64>, // Changed to Option<u64>, removed default_value
 
+    /// Only display and forward this ratio of the traces, selected by trace ID, always keeping the traces with an error span.
+    #[arg(long, value_name = "RATIO", value_parser = parse_sample_ratio, help_heading = "Processing Options", help = "Only display and forward this ratio of the traces (e.g., '0.1'), selected by trace ID. Traces with an error span are always kept.")]
+    pub sample_ratio: Option<f64>,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 
@@ -201,11 +440,69 @@ pub struct CliArgs {
     #[arg(long, help_heading = "Filtering Options")]
     pub grep: Option<String>,
 
+    /// Only keep traces with a span of this service (`service.name` resource attribute)
+    #[arg(long, help_heading = "Filtering Options")]
+    pub service: Option<String>,
+
+    /// Only keep traces with a span of this name
+    #[arg(long, help_heading = "Filtering Options")]
+    pub span_name: Option<String>,
+
+    /// Only keep traces with a span of this status
+    #[arg(long, value_enum, help_heading = "Filtering Options")]
+    pub status: Option<StatusFilter>,
+
+    /// Only keep traces with a span lasting at least this long
+    #[arg(long, value_parser = parse_duration_to_millis, help_heading = "Filtering Options", help = "Only keep traces with a span lasting at least this long (e.g., '200ms', '1s'). Requires suffix: ms, s, m, h.")]
+    pub min_duration: Option<u64>, // Stores milliseconds
+
+    /// Drop the spans of this name, with their child spans, from display and forwarding. Can be specified multiple times.
+    #[arg(
+        long = "exclude-span-name",
+        value_name = "NAME",
+        help_heading = "Filtering Options"
+    )]
+    pub exclude_span_names: Option<Vec<String>>,
+
+    /// Drop the spans of this service, with their child spans, from display and forwarding. Can be specified multiple times.
+    #[arg(
+        long = "exclude-service",
+        value_name = "SERVICE",
+        help_heading = "Filtering Options"
+    )]
+    pub exclude_services: Option<Vec<String>>,
+
+    /// Drop the spans with an attribute value matching a glob, as key=glob (e.g., "http.route=/health*"), with their child spans, from display and forwarding. Can be specified multiple times.
+    #[arg(
+        long = "exclude-attr",
+        value_name = "KEY=GLOB",
+        help_heading = "Filtering Options"
+    )]
+    pub exclude_attrs: Option<Vec<String>>,
+
+    /// Skip the OTLP/stdout records with a lower `level` than this, before decoding their payload.
+    /// Records without a level are always processed.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "LEVEL",
+        help_heading = "Filtering Options"
+    )]
+    pub min_level: Option<RecordLevel>,
+
     /// Go back in time for initial log poll (e.g., 30, 120s, 3m)
     #[arg(long, value_parser = parse_duration_to_millis, help_heading = "Filtering Options")]
     pub backtrace: Option<u64>, // Stores milliseconds
 }
 
+// Parses a sampling ratio between 0 and 1
+fn parse_sample_ratio(s: &str) -> Result<f64, String> {
+    f64::from_str(s)
+        .ok()
+        .filter(|ratio| (0.0..=1.0).contains(ratio))
+        .ok_or_else(|| format!("Invalid sample ratio '{}'. Must be between 0 and 1.", s))
+}
+
 // Custom parser for duration strings into milliseconds
 // Made pub(crate) so it can be called from config.rs
 pub(crate) fn parse_duration_to_millis(s: &str) -> Result<u64, String> {
@@ -242,6 +539,62 @@ pub enum Commands {
         #[arg(value_enum)]
         shell: Shell,
     },
+    /// Re-render, and forward if an OTLP endpoint is configured, a session recorded with `--record <FILE>.jsonl`
+    Replay {
+        /// Session file to replay
+        file: PathBuf,
+
+        /// Replay the session as fast as possible instead of with its original timing
+        #[arg(long)]
+        no_timing: bool,
+    },
+    /// Re-render, and forward if an OTLP endpoint is configured, the traces stored with `--store <FILE>`
+    Query {
+        /// Trace store to query
+        file: PathBuf,
+
+        /// Only select traces with a span of this service (`service.name` resource attribute)
+        #[arg(long)]
+        service: Option<String>,
+
+        /// Only select traces with a span of this status
+        #[arg(long, value_enum)]
+        status: Option<StatusFilter>,
+
+        /// Only select traces with a span lasting at least this long (e.g., '200ms', '1s')
+        #[arg(long, value_parser = parse_duration_to_millis)]
+        min_duration: Option<u64>,
+
+        /// Only select traces started within this long before now (e.g., '30m', '2h')
+        #[arg(long, value_parser = parse_duration_to_millis, conflicts_with = "from")]
+        since: Option<u64>,
+
+        /// Only select traces started at or after this time (RFC 3339, e.g., '2025-06-01T12:00:00Z')
+        #[arg(long, value_parser = parse_timestamp_to_unix_nano)]
+        from: Option<u64>,
+
+        /// Only select traces started at or before this time (RFC 3339)
+        #[arg(long, value_parser = parse_timestamp_to_unix_nano)]
+        to: Option<u64>,
+
+        /// Only select the most recent traces
+        #[arg(long, value_name = "TRACES")]
+        limit: Option<usize>,
+    },
+}
+
+// Parses an RFC 3339 timestamp into nanoseconds since the Unix epoch
+fn parse_timestamp_to_unix_nano(s: &str) -> Result<u64, String> {
+    chrono::DateTime::parse_from_rfc3339(s)
+        .ok()
+        .and_then(|timestamp| timestamp.timestamp_nanos_opt())
+        .and_then(|nanos| u64::try_from(nanos).ok())
+        .ok_or_else(|| {
+            format!(
+                "Invalid timestamp '{}'. Must be in RFC 3339 format, e.g., '2025-06-01T12:00:00Z'.",
+                s
+            )
+        })
 }
 
 /// Parses attribute glob patterns from a string pattern.
@@ -0,0 +1,214 @@
+//! Colors the spans matching user-defined conditions on their attributes.
+//!
+//! A rule, given with `--color-rule`, is written `<key><op><value>:<color>`, e.g.
+//! `http.response.status_code>=500:red`. The operators `>`, `>=`, `<` and `<=` compare numbers,
+//! `=` and `!=` compare numbers or strings. The color is a name or an `#rrggbb` hex value. The
+//! first rule matching an attribute of a span sets the color of its span ID, name and timeline
+//! bar in the waterfall, and of its items in the timeline log.
+
+use anyhow::{anyhow, bail, Result};
+use opentelemetry_proto::tonic::common::v1::KeyValue;
+use std::cmp::Ordering;
+
+use crate::console_display::get_string_value_for_grep;
+
+// Named colors, matching the usual terminal palettes
+const NAMED_COLORS: [(&str, (u8, u8, u8)); 9] = [
+    ("red", (205, 49, 49)),
+    ("green", (13, 188, 121)),
+    ("yellow", (229, 229, 16)),
+    ("blue", (36, 114, 200)),
+    ("magenta", (188, 63, 188)),
+    ("cyan", (17, 168, 205)),
+    ("orange", (255, 140, 0)),
+    ("white", (229, 229, 229)),
+    ("gray", (118, 118, 118)),
+];
+
+// Longer operators first, so `>=` is not read as `>`
+const OPERATORS: [(&str, Comparison); 6] = [
+    (">=", Comparison::GreaterOrEqual),
+    ("<=", Comparison::LessOrEqual),
+    ("!=", Comparison::NotEqual),
+    (">", Comparison::Greater),
+    ("<", Comparison::Less),
+    ("=", Comparison::Equal),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+/// A condition on a span attribute, and the color of the spans matching it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorRule {
+    key: String,
+    comparison: Comparison,
+    value: String,
+    pub color: (u8, u8, u8),
+}
+
+impl ColorRule {
+    /// Parses a rule written `<key><op><value>:<color>`
+    pub fn parse(rule: &str) -> Result<Self> {
+        let (condition, color) = rule.rsplit_once(':').ok_or_else(|| {
+            anyhow!(
+                "Invalid color rule '{}', expected <key><op><value>:<color>",
+                rule
+            )
+        })?;
+        let operator_start = condition
+            .find(['=', '!', '<', '>'])
+            .filter(|&index| index > 0)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Invalid color rule '{}', expected one of =, !=, >, >=, <, <= after the attribute key",
+                    rule
+                )
+            })?;
+        let (key, rest) = condition.split_at(operator_start);
+        let (operator, comparison) = OPERATORS
+            .iter()
+            .find(|(operator, _)| rest.starts_with(operator))
+            .ok_or_else(|| anyhow!("Invalid operator in color rule '{}'", rule))?;
+        let value = &rest[operator.len()..];
+        if !matches!(comparison, Comparison::Equal | Comparison::NotEqual)
+            && value.parse::<f64>().is_err()
+        {
+            bail!(
+                "Invalid color rule '{}', {} compares numbers",
+                rule,
+                operator
+            );
+        }
+        Ok(Self {
+            key: key.trim().to_string(),
+            comparison: *comparison,
+            value: value.trim().to_string(),
+            color: parse_color(color.trim())
+                .ok_or_else(|| anyhow!("Invalid color in color rule '{}'", rule))?,
+        })
+    }
+
+    /// Returns true if the attribute of the rule is set and satisfies its condition
+    pub fn matches(&self, attributes: &[KeyValue]) -> bool {
+        let Some(attribute) = attributes.iter().find(|kv| kv.key == self.key) else {
+            return false;
+        };
+        let actual = get_string_value_for_grep(&attribute.value);
+        let ordering = match (actual.parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(actual), Ok(expected)) => actual.partial_cmp(&expected),
+            _ => match self.comparison {
+                Comparison::Equal | Comparison::NotEqual => Some(actual.cmp(&self.value)),
+                _ => None,
+            },
+        };
+        let Some(ordering) = ordering else {
+            return false;
+        };
+        match self.comparison {
+            Comparison::Equal => ordering == Ordering::Equal,
+            Comparison::NotEqual => ordering != Ordering::Equal,
+            Comparison::Greater => ordering == Ordering::Greater,
+            Comparison::GreaterOrEqual => ordering != Ordering::Less,
+            Comparison::Less => ordering == Ordering::Less,
+            Comparison::LessOrEqual => ordering != Ordering::Greater,
+        }
+    }
+}
+
+/// Parses the color rules given on the command line or in a profile
+pub fn parse_color_rules(values: &[String]) -> Result<Vec<ColorRule>> {
+    values.iter().map(|value| ColorRule::parse(value)).collect()
+}
+
+/// Returns the color of the first rule matching the attributes of a span
+pub fn color_for_attributes(rules: &[ColorRule], attributes: &[KeyValue]) -> Option<(u8, u8, u8)> {
+    rules
+        .iter()
+        .find(|rule| rule.matches(attributes))
+        .map(|rule| rule.color)
+}
+
+fn parse_color(color: &str) -> Option<(u8, u8, u8)> {
+    if let Some(hex) = color.strip_prefix('#') {
+        let bytes = hex::decode(hex).ok()?;
+        return match bytes[..] {
+            [r, g, b] => Some((r, g, b)),
+            _ => None,
+        };
+    }
+    NAMED_COLORS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(color))
+        .map(|(_, rgb)| *rgb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue};
+
+    fn attribute(key: &str, value: any_value::Value) -> KeyValue {
+        KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue { value: Some(value) }),
+        }
+    }
+
+    #[test]
+    fn test_color_rules() {
+        let rules = parse_color_rules(&[
+            "http.response.status_code>=500:red".to_string(),
+            "http.response.status_code>=400:#ffa500".to_string(),
+            "tenant.tier=gold:yellow".to_string(),
+        ])
+        .unwrap();
+        let status = |code| {
+            vec![attribute(
+                "http.response.status_code",
+                any_value::Value::IntValue(code),
+            )]
+        };
+        assert_eq!(
+            color_for_attributes(&rules, &status(503)),
+            Some((205, 49, 49))
+        );
+        assert_eq!(
+            color_for_attributes(&rules, &status(404)),
+            Some((255, 165, 0))
+        );
+        assert_eq!(color_for_attributes(&rules, &status(200)), None);
+        assert_eq!(
+            color_for_attributes(
+                &rules,
+                &[attribute(
+                    "tenant.tier",
+                    any_value::Value::StringValue("gold".to_string())
+                )]
+            ),
+            Some((229, 229, 16))
+        );
+        assert_eq!(color_for_attributes(&rules, &[]), None);
+
+        let not_equal = ColorRule::parse("cold_start!=false:cyan").unwrap();
+        assert!(not_equal.matches(&[attribute("cold_start", any_value::Value::BoolValue(true))]));
+        assert!(!not_equal.matches(&[attribute("cold_start", any_value::Value::BoolValue(false))]));
+
+        for invalid in [
+            "http.response.status_code>=500",
+            ">=500:red",
+            "http.route>/api:red",
+            "http.response.status_code>=500:chartreuse",
+            "http.response.status_code>=500:#ff00",
+        ] {
+            assert!(ColorRule::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -12,13 +12,15 @@
 //! - Saving CLI arguments to a named profile in the configuration file.
 
 use crate::cli::{
-    CliArgs, ColoringMode, DEFAULT_COLOR_BY, DEFAULT_EVENTS_ONLY, DEFAULT_EVENT_SEVERITY_ATTRIBUTE,
-    DEFAULT_SESSION_TIMEOUT_MS, DEFAULT_TRACE_STRAGGLERS_WAIT_MS, DEFAULT_TRACE_TIMEOUT_MS,
+    CliArgs, ColoringMode, OtlpProtocol, RecordLevel, StatusFilter, DEFAULT_COLOR_BY,
+    DEFAULT_EVENTS_ONLY, DEFAULT_EVENT_SEVERITY_ATTRIBUTE, DEFAULT_SESSION_TIMEOUT_MS,
+    DEFAULT_TRACE_STRAGGLERS_WAIT_MS, DEFAULT_TRACE_TIMEOUT_MS,
 };
 use crate::console_display::Theme;
 use anyhow::{Context, Result};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 use std::{fs, io::Write, path::Path, path::PathBuf};
 
 // Default filename for the configuration
@@ -42,6 +44,27 @@ pub(crate) fn format_millis_to_duration_string(millis: u64) -> String {
     }
 }
 
+// Deserializes a setting given either as a single string or as a list of strings.
+// Keeps profiles written when `stack-name` only accepted a single stack loading.
+fn deserialize_string_or_vec<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrVec {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(
+        Option::<StringOrVec>::deserialize(deserializer)?.map(|value| match value {
+            StringOrVec::One(value) => vec![value],
+            StringOrVec::Many(values) => values,
+        }),
+    )
+}
+
 /// Represents the entire structure of the livetrace.toml file.
 #[derive(Debug, Deserialize, Serialize, Default)]
 #[serde(deny_unknown_fields)]
@@ -64,14 +87,29 @@ pub struct ProfileConfig {
     // Discovery (Mirroring CliArgs groups)
     #[serde(rename = "log-group-pattern")]
     pub log_group_pattern: Option<Vec<String>>,
-    #[serde(rename = "stack-name")]
-    pub stack_name: Option<String>,
+    #[serde(
+        rename = "stack-name",
+        default,
+        deserialize_with = "deserialize_string_or_vec"
+    )]
+    pub stack_name: Option<Vec<String>>,
+    #[serde(rename = "tag", skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(
+        rename = "role-arn",
+        default,
+        deserialize_with = "deserialize_string_or_vec",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub role_arns: Option<Vec<String>>,
 
     // Forwarding (Mirroring CliArgs)
     #[serde(rename = "otlp-endpoint")]
     pub otlp_endpoint: Option<String>,
     #[serde(rename = "otlp-header")]
     pub otlp_headers: Option<Vec<String>>,
+    #[serde(rename = "otlp-protocol", skip_serializing_if = "Option::is_none")]
+    pub otlp_protocol: Option<OtlpProtocol>,
 
     // AWS (Mirroring CliArgs)
     #[serde(rename = "aws-region")]
@@ -94,6 +132,17 @@ pub struct ProfileConfig {
     // Mode (Mirroring CliArgs groups)
     #[serde(rename = "poll-interval")]
     pub poll_interval: Option<String>, // Changed to Option<String>
+    #[serde(
+        rename = "max-api-calls-per-minute",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_api_calls_per_minute: Option<u32>,
+    #[serde(rename = "metrics-port", skip_serializing_if = "Option::is_none")]
+    pub metrics_port: Option<u16>,
+    #[serde(rename = "metrics-addr", skip_serializing_if = "Option::is_none")]
+    pub metrics_addr: Option<IpAddr>,
+    #[serde(rename = "sample-ratio", skip_serializing_if = "Option::is_none")]
+    pub sample_ratio: Option<f64>,
     #[serde(rename = "session-timeout")]
     pub session_timeout: Option<String>, // Changed to Option<String>
     // Note: Verbosity (`verbose`) is generally not configured via file.
@@ -108,11 +157,53 @@ pub struct ProfileConfig {
     )]
     pub trace_stragglers_wait: Option<String>, // New field
 
+    // Stats
+    #[serde(rename = "stats", skip_serializing_if = "Option::is_none")]
+    pub stats: Option<bool>,
+    #[serde(rename = "stats-interval", skip_serializing_if = "Option::is_none")]
+    pub stats_interval: Option<String>,
+    #[serde(rename = "refresh-log-groups", skip_serializing_if = "Option::is_none")]
+    pub refresh_log_groups: Option<String>,
+    #[serde(rename = "service-map", skip_serializing_if = "Option::is_none")]
+    pub service_map: Option<bool>,
+    #[serde(rename = "heatmap", skip_serializing_if = "Option::is_none")]
+    pub heatmap: Option<bool>,
+    #[serde(rename = "correlate-logs", skip_serializing_if = "Option::is_none")]
+    pub correlate_logs: Option<bool>,
+    #[serde(rename = "notify-on-error", skip_serializing_if = "Option::is_none")]
+    pub notify_on_error: Option<bool>,
+    #[serde(rename = "notify-webhook", skip_serializing_if = "Option::is_none")]
+    pub notify_webhook: Option<String>,
+
     // Filtering Options
     #[serde(rename = "grep", skip_serializing_if = "Option::is_none")]
     pub grep: Option<String>,
     #[serde(rename = "backtrace", skip_serializing_if = "Option::is_none")]
     pub backtrace: Option<String>, // Changed to Option<String>
+    #[serde(rename = "service", skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    #[serde(rename = "span-name", skip_serializing_if = "Option::is_none")]
+    pub span_name: Option<String>,
+    #[serde(rename = "status", skip_serializing_if = "Option::is_none")]
+    pub status: Option<StatusFilter>,
+    #[serde(rename = "min-duration", skip_serializing_if = "Option::is_none")]
+    pub min_duration: Option<String>,
+    #[serde(rename = "exclude-span-name", skip_serializing_if = "Option::is_none")]
+    pub exclude_span_names: Option<Vec<String>>,
+    #[serde(rename = "exclude-service", skip_serializing_if = "Option::is_none")]
+    pub exclude_services: Option<Vec<String>>,
+    #[serde(rename = "exclude-attr", skip_serializing_if = "Option::is_none")]
+    pub exclude_attrs: Option<Vec<String>>,
+    #[serde(rename = "color-rule", skip_serializing_if = "Option::is_none")]
+    pub color_rules: Option<Vec<String>>,
+    #[serde(rename = "link-template", skip_serializing_if = "Option::is_none")]
+    pub link_template: Option<String>,
+    #[serde(rename = "min-level", skip_serializing_if = "Option::is_none")]
+    pub min_level: Option<RecordLevel>,
+    #[serde(rename = "no-color", skip_serializing_if = "Option::is_none")]
+    pub no_color: Option<bool>,
+    #[serde(rename = "ascii", skip_serializing_if = "Option::is_none")]
+    pub ascii: Option<bool>,
 }
 
 /// Represents the final, merged configuration after applying precedence rules.
@@ -120,11 +211,14 @@ pub struct ProfileConfig {
 pub struct EffectiveConfig {
     // Discovery
     pub log_group_pattern: Option<Vec<String>>,
-    pub stack_name: Option<String>,
+    pub stack_name: Option<Vec<String>>,
+    pub tags: Option<Vec<String>>,
+    pub role_arns: Option<Vec<String>>,
 
     // Forwarding
     pub otlp_endpoint: Option<String>,
     pub otlp_headers: Vec<String>, // Merged headers
+    pub otlp_protocol: Option<OtlpProtocol>,
 
     // AWS
     pub aws_region: Option<String>,
@@ -138,9 +232,21 @@ pub struct EffectiveConfig {
     pub events_only: bool,
     pub trace_timeout_ms: u64,
     pub trace_stragglers_wait_ms: u64,
+    pub stats: bool,
+    pub stats_interval_ms: Option<u64>,
+    pub refresh_log_groups_ms: Option<u64>,
+    pub service_map: bool,
+    pub heatmap: bool,
+    pub correlate_logs: bool,
+    pub notify_on_error: bool,
+    pub notify_webhook: Option<String>,
 
     // Mode
     pub poll_interval_ms: Option<u64>,
+    pub max_api_calls_per_minute: Option<u32>,
+    pub metrics_port: Option<u16>,
+    pub metrics_addr: Option<IpAddr>,
+    pub sample_ratio: Option<f64>,
     pub session_timeout_ms: u64,
 
     // Execution Control
@@ -150,6 +256,18 @@ pub struct EffectiveConfig {
     // Filtering Options
     pub grep: Option<String>,
     pub backtrace_ms: Option<u64>,
+    pub service: Option<String>,
+    pub span_name: Option<String>,
+    pub status: Option<StatusFilter>,
+    pub min_duration_ms: Option<u64>,
+    pub exclude_span_names: Option<Vec<String>>,
+    pub exclude_services: Option<Vec<String>>,
+    pub exclude_attrs: Option<Vec<String>>,
+    pub color_rules: Option<Vec<String>>,
+    pub link_template: Option<String>,
+    pub min_level: Option<RecordLevel>,
+    pub no_color: bool,
+    pub ascii: bool,
 }
 
 impl ProfileConfig {
@@ -163,17 +281,24 @@ impl ProfileConfig {
         ProfileConfig {
             log_group_pattern: args.log_group_pattern.clone().filter(|v| !v.is_empty()),
             stack_name: args.stack_name.clone(),
+            tags: args.tags.clone().filter(|v| !v.is_empty()),
+            role_arns: args.role_arns.clone().filter(|v| !v.is_empty()),
             otlp_endpoint: args.otlp_endpoint.clone(),
             otlp_headers: if args.otlp_headers.is_empty() {
                 None
             } else {
                 Some(args.otlp_headers.clone())
             },
+            otlp_protocol: args.otlp_protocol,
             aws_region: args.aws_region.clone(),
             aws_profile: args.aws_profile.clone(),
             forward_only: Some(args.forward_only).filter(|&f| f),
             attrs: args.attrs.clone(),
             poll_interval: args.poll_interval.map(format_millis_to_duration_string),
+            max_api_calls_per_minute: args.max_api_calls_per_minute,
+            metrics_port: args.metrics_port,
+            metrics_addr: args.metrics_addr,
+            sample_ratio: args.sample_ratio,
             session_timeout: args
                 .session_timeout
                 .map(format_millis_to_duration_string)
@@ -193,8 +318,30 @@ impl ProfileConfig {
                 .trace_stragglers_wait
                 .map(format_millis_to_duration_string)
                 .filter(|s| s != DEFAULT_TRACE_STRAGGLERS_WAIT_STR),
+            stats: Some(args.stats).filter(|&s| s),
+            stats_interval: args.stats_interval.map(format_millis_to_duration_string),
+            refresh_log_groups: args
+                .refresh_log_groups
+                .map(format_millis_to_duration_string),
+            service_map: Some(args.service_map).filter(|&s| s),
+            heatmap: Some(args.heatmap).filter(|&h| h),
+            correlate_logs: Some(args.correlate_logs).filter(|&c| c),
+            notify_on_error: Some(args.notify_on_error).filter(|&n| n),
+            notify_webhook: args.notify_webhook.clone(),
             grep: args.grep.clone(),
             backtrace: args.backtrace.map(format_millis_to_duration_string),
+            service: args.service.clone(),
+            span_name: args.span_name.clone(),
+            status: args.status,
+            min_duration: args.min_duration.map(format_millis_to_duration_string),
+            exclude_span_names: args.exclude_span_names.clone().filter(|v| !v.is_empty()),
+            exclude_services: args.exclude_services.clone().filter(|v| !v.is_empty()),
+            exclude_attrs: args.exclude_attrs.clone().filter(|v| !v.is_empty()),
+            color_rules: args.color_rules.clone().filter(|v| !v.is_empty()),
+            link_template: args.link_template.clone(),
+            min_level: args.min_level,
+            no_color: Some(args.no_color).filter(|&n| n),
+            ascii: Some(args.ascii).filter(|&a| a),
         }
     }
 }
@@ -211,14 +358,21 @@ pub fn load_and_resolve_config(
     let mut effective = EffectiveConfig {
         log_group_pattern: None,
         stack_name: None,
+        tags: None,
+        role_arns: None,
         otlp_endpoint: None,
         otlp_headers: Vec::new(),
+        otlp_protocol: None,
         aws_region: None,
         aws_profile: None,
         forward_only: false,
         attrs: None,
         event_severity_attribute: DEFAULT_EVENT_SEVERITY_ATTRIBUTE.to_string(),
         poll_interval_ms: None,
+        max_api_calls_per_minute: None,
+        metrics_port: None,
+        metrics_addr: None,
+        sample_ratio: None,
         session_timeout_ms: DEFAULT_SESSION_TIMEOUT_MS,
         verbose: 0,
         theme: Theme::Default,
@@ -226,8 +380,28 @@ pub fn load_and_resolve_config(
         events_only: DEFAULT_EVENTS_ONLY,
         trace_timeout_ms: DEFAULT_TRACE_TIMEOUT_MS,
         trace_stragglers_wait_ms: DEFAULT_TRACE_STRAGGLERS_WAIT_MS,
+        stats: false,
+        stats_interval_ms: None,
+        refresh_log_groups_ms: None,
+        service_map: false,
+        heatmap: false,
+        correlate_logs: false,
+        notify_on_error: false,
+        notify_webhook: None,
         grep: None,
         backtrace_ms: None,
+        service: None,
+        span_name: None,
+        status: None,
+        min_duration_ms: None,
+        exclude_span_names: None,
+        exclude_services: None,
+        exclude_attrs: None,
+        color_rules: None,
+        link_template: None,
+        min_level: None,
+        no_color: false,
+        ascii: false,
     };
 
     if config_profile_name.is_none() {
@@ -285,12 +459,21 @@ fn apply_cli_args_to_effective(cli_args: &CliArgs, effective: &mut EffectiveConf
     if cli_args.stack_name.is_some() {
         effective.stack_name = cli_args.stack_name.clone();
     }
+    if cli_args.tags.is_some() {
+        effective.tags = cli_args.tags.clone();
+    }
+    if cli_args.role_arns.is_some() {
+        effective.role_arns = cli_args.role_arns.clone();
+    }
     if cli_args.otlp_endpoint.is_some() {
         effective.otlp_endpoint = cli_args.otlp_endpoint.clone();
     }
     if !cli_args.otlp_headers.is_empty() {
         effective.otlp_headers = cli_args.otlp_headers.clone();
     }
+    if cli_args.otlp_protocol.is_some() {
+        effective.otlp_protocol = cli_args.otlp_protocol;
+    }
     if cli_args.aws_region.is_some() {
         effective.aws_region = cli_args.aws_region.clone();
     }
@@ -306,9 +489,57 @@ fn apply_cli_args_to_effective(cli_args: &CliArgs, effective: &mut EffectiveConf
     if cli_args.backtrace.is_some() {
         effective.backtrace_ms = cli_args.backtrace;
     }
+    if cli_args.service.is_some() {
+        effective.service = cli_args.service.clone();
+    }
+    if cli_args.span_name.is_some() {
+        effective.span_name = cli_args.span_name.clone();
+    }
+    if cli_args.status.is_some() {
+        effective.status = cli_args.status;
+    }
+    if cli_args.min_duration.is_some() {
+        effective.min_duration_ms = cli_args.min_duration;
+    }
+    if cli_args.exclude_span_names.is_some() {
+        effective.exclude_span_names = cli_args.exclude_span_names.clone();
+    }
+    if cli_args.exclude_services.is_some() {
+        effective.exclude_services = cli_args.exclude_services.clone();
+    }
+    if cli_args.exclude_attrs.is_some() {
+        effective.exclude_attrs = cli_args.exclude_attrs.clone();
+    }
+    if cli_args.color_rules.is_some() {
+        effective.color_rules = cli_args.color_rules.clone();
+    }
+    if cli_args.link_template.is_some() {
+        effective.link_template = cli_args.link_template.clone();
+    }
+    if cli_args.min_level.is_some() {
+        effective.min_level = cli_args.min_level;
+    }
+    if cli_args.no_color {
+        effective.no_color = true;
+    }
+    if cli_args.ascii {
+        effective.ascii = true;
+    }
     if cli_args.poll_interval.is_some() {
         effective.poll_interval_ms = cli_args.poll_interval;
     }
+    if cli_args.max_api_calls_per_minute.is_some() {
+        effective.max_api_calls_per_minute = cli_args.max_api_calls_per_minute;
+    }
+    if cli_args.metrics_port.is_some() {
+        effective.metrics_port = cli_args.metrics_port;
+    }
+    if cli_args.metrics_addr.is_some() {
+        effective.metrics_addr = cli_args.metrics_addr;
+    }
+    if cli_args.sample_ratio.is_some() {
+        effective.sample_ratio = cli_args.sample_ratio;
+    }
     if cli_args.forward_only {
         effective.forward_only = true;
     }
@@ -330,6 +561,30 @@ fn apply_cli_args_to_effective(cli_args: &CliArgs, effective: &mut EffectiveConf
     if let Some(val) = cli_args.trace_stragglers_wait {
         effective.trace_stragglers_wait_ms = val;
     }
+    if cli_args.stats {
+        effective.stats = true;
+    }
+    if cli_args.stats_interval.is_some() {
+        effective.stats_interval_ms = cli_args.stats_interval;
+    }
+    if cli_args.refresh_log_groups.is_some() {
+        effective.refresh_log_groups_ms = cli_args.refresh_log_groups;
+    }
+    if cli_args.service_map {
+        effective.service_map = true;
+    }
+    if cli_args.heatmap {
+        effective.heatmap = true;
+    }
+    if cli_args.correlate_logs {
+        effective.correlate_logs = true;
+    }
+    if cli_args.notify_on_error {
+        effective.notify_on_error = true;
+    }
+    if cli_args.notify_webhook.is_some() {
+        effective.notify_webhook = cli_args.notify_webhook.clone();
+    }
     if let Some(val) = cli_args.theme {
         effective.theme = val;
     }
@@ -387,12 +642,21 @@ fn apply_profile_to_effective(profile: &ProfileConfig, effective: &mut Effective
     if let Some(val) = &profile.stack_name {
         effective.stack_name = Some(val.clone());
     }
+    if let Some(val) = &profile.tags {
+        effective.tags = Some(val.clone());
+    }
+    if let Some(val) = &profile.role_arns {
+        effective.role_arns = Some(val.clone());
+    }
     if let Some(val) = &profile.otlp_endpoint {
         effective.otlp_endpoint = Some(val.clone());
     }
     if let Some(val) = &profile.otlp_headers {
         effective.otlp_headers = val.clone();
     }
+    if let Some(val) = profile.otlp_protocol {
+        effective.otlp_protocol = Some(val);
+    }
     if let Some(val) = &profile.aws_region {
         effective.aws_region = Some(val.clone());
     }
@@ -418,6 +682,26 @@ fn apply_profile_to_effective(profile: &ProfileConfig, effective: &mut Effective
             ),
         }
     }
+    if let Some(val) = profile.max_api_calls_per_minute {
+        effective.max_api_calls_per_minute = Some(val);
+    }
+    if let Some(val) = profile.metrics_port {
+        effective.metrics_port = Some(val);
+    }
+    if let Some(val) = profile.metrics_addr {
+        effective.metrics_addr = Some(val);
+    }
+    if let Some(val) = profile.sample_ratio {
+        if (0.0..=1.0).contains(&val) {
+            effective.sample_ratio = Some(val);
+        } else {
+            tracing::warn!(
+                profile_key = "sample-ratio",
+                value = val,
+                "Ignoring sample-ratio from profile, it must be between 0 and 1."
+            );
+        }
+    }
     if let Some(s_val) = &profile.session_timeout {
         match crate::cli::parse_duration_to_millis(s_val.as_str()) {
             Ok(ms_val) => effective.session_timeout_ms = ms_val,
@@ -458,6 +742,43 @@ fn apply_profile_to_effective(profile: &ProfileConfig, effective: &mut Effective
         }
     }
 
+    if let Some(val) = profile.stats {
+        effective.stats = val;
+    }
+    if let Some(val) = profile.service_map {
+        effective.service_map = val;
+    }
+    if let Some(val) = profile.heatmap {
+        effective.heatmap = val;
+    }
+    if let Some(val) = profile.correlate_logs {
+        effective.correlate_logs = val;
+    }
+    if let Some(val) = profile.notify_on_error {
+        effective.notify_on_error = val;
+    }
+    if let Some(val) = &profile.notify_webhook {
+        effective.notify_webhook = Some(val.clone());
+    }
+    if let Some(s_val) = &profile.stats_interval {
+        match crate::cli::parse_duration_to_millis(s_val.as_str()) {
+            Ok(ms_val) => effective.stats_interval_ms = Some(ms_val),
+            Err(e) => tracing::warn!(
+                profile_key = "stats-interval", value = %s_val, error = %e,
+                "Failed to parse duration from profile for stats-interval. Effective value: {:?}", effective.stats_interval_ms.map(format_millis_to_duration_string)
+            ),
+        }
+    }
+    if let Some(s_val) = &profile.refresh_log_groups {
+        match crate::cli::parse_duration_to_millis(s_val.as_str()) {
+            Ok(ms_val) => effective.refresh_log_groups_ms = Some(ms_val),
+            Err(e) => tracing::warn!(
+                profile_key = "refresh-log-groups", value = %s_val, error = %e,
+                "Failed to parse duration from profile for refresh-log-groups. Effective value: {:?}", effective.refresh_log_groups_ms.map(format_millis_to_duration_string)
+            ),
+        }
+    }
+
     if let Some(val) = &profile.grep {
         effective.grep = Some(val.clone());
     }
@@ -470,6 +791,48 @@ fn apply_profile_to_effective(profile: &ProfileConfig, effective: &mut Effective
             ),
         }
     }
+    if let Some(val) = &profile.service {
+        effective.service = Some(val.clone());
+    }
+    if let Some(val) = &profile.span_name {
+        effective.span_name = Some(val.clone());
+    }
+    if let Some(val) = profile.status {
+        effective.status = Some(val);
+    }
+    if let Some(s_val) = &profile.min_duration {
+        match crate::cli::parse_duration_to_millis(s_val.as_str()) {
+            Ok(ms_val) => effective.min_duration_ms = Some(ms_val),
+            Err(e) => tracing::warn!(
+                profile_key = "min-duration", value = %s_val, error = %e,
+                "Failed to parse duration from profile for min-duration. Effective value: {}", effective.min_duration_ms.map_or_else(|| "None".to_string(), format_millis_to_duration_string)
+            ),
+        }
+    }
+    if let Some(val) = &profile.exclude_span_names {
+        effective.exclude_span_names = Some(val.clone());
+    }
+    if let Some(val) = &profile.exclude_services {
+        effective.exclude_services = Some(val.clone());
+    }
+    if let Some(val) = &profile.exclude_attrs {
+        effective.exclude_attrs = Some(val.clone());
+    }
+    if let Some(val) = &profile.color_rules {
+        effective.color_rules = Some(val.clone());
+    }
+    if let Some(val) = &profile.link_template {
+        effective.link_template = Some(val.clone());
+    }
+    if let Some(val) = profile.min_level {
+        effective.min_level = Some(val);
+    }
+    if let Some(val) = profile.no_color {
+        effective.no_color = val;
+    }
+    if let Some(val) = profile.ascii {
+        effective.ascii = val;
+    }
 }
 
 pub fn merge_into_profile_config(base: &ProfileConfig, overrides: &ProfileConfig) -> ProfileConfig {
@@ -482,6 +845,11 @@ pub fn merge_into_profile_config(base: &ProfileConfig, overrides: &ProfileConfig
             .stack_name
             .clone()
             .or_else(|| base.stack_name.clone()),
+        tags: overrides.tags.clone().or_else(|| base.tags.clone()),
+        role_arns: overrides
+            .role_arns
+            .clone()
+            .or_else(|| base.role_arns.clone()),
         otlp_endpoint: overrides
             .otlp_endpoint
             .clone()
@@ -490,6 +858,7 @@ pub fn merge_into_profile_config(base: &ProfileConfig, overrides: &ProfileConfig
             .otlp_headers
             .clone()
             .or_else(|| base.otlp_headers.clone()),
+        otlp_protocol: overrides.otlp_protocol.or(base.otlp_protocol),
         aws_region: overrides
             .aws_region
             .clone()
@@ -508,6 +877,12 @@ pub fn merge_into_profile_config(base: &ProfileConfig, overrides: &ProfileConfig
             .poll_interval
             .clone()
             .or_else(|| base.poll_interval.clone()),
+        max_api_calls_per_minute: overrides
+            .max_api_calls_per_minute
+            .or(base.max_api_calls_per_minute),
+        metrics_port: overrides.metrics_port.or(base.metrics_port),
+        metrics_addr: overrides.metrics_addr.or(base.metrics_addr),
+        sample_ratio: overrides.sample_ratio.or(base.sample_ratio),
         session_timeout: overrides
             .session_timeout
             .clone()
@@ -523,17 +898,68 @@ pub fn merge_into_profile_config(base: &ProfileConfig, overrides: &ProfileConfig
             .trace_stragglers_wait
             .clone()
             .or_else(|| base.trace_stragglers_wait.clone()),
+        stats: overrides.stats.or(base.stats),
+        stats_interval: overrides
+            .stats_interval
+            .clone()
+            .or_else(|| base.stats_interval.clone()),
+        refresh_log_groups: overrides
+            .refresh_log_groups
+            .clone()
+            .or_else(|| base.refresh_log_groups.clone()),
+        service_map: overrides.service_map.or(base.service_map),
+        heatmap: overrides.heatmap.or(base.heatmap),
+        correlate_logs: overrides.correlate_logs.or(base.correlate_logs),
+        notify_on_error: overrides.notify_on_error.or(base.notify_on_error),
+        notify_webhook: overrides
+            .notify_webhook
+            .clone()
+            .or_else(|| base.notify_webhook.clone()),
         grep: overrides.grep.clone().or_else(|| base.grep.clone()),
         backtrace: overrides
             .backtrace
             .clone()
             .or_else(|| base.backtrace.clone()),
+        service: overrides.service.clone().or_else(|| base.service.clone()),
+        span_name: overrides
+            .span_name
+            .clone()
+            .or_else(|| base.span_name.clone()),
+        status: overrides.status.or(base.status),
+        min_duration: overrides
+            .min_duration
+            .clone()
+            .or_else(|| base.min_duration.clone()),
+        exclude_span_names: overrides
+            .exclude_span_names
+            .clone()
+            .or_else(|| base.exclude_span_names.clone()),
+        exclude_services: overrides
+            .exclude_services
+            .clone()
+            .or_else(|| base.exclude_services.clone()),
+        exclude_attrs: overrides
+            .exclude_attrs
+            .clone()
+            .or_else(|| base.exclude_attrs.clone()),
+        color_rules: overrides
+            .color_rules
+            .clone()
+            .or_else(|| base.color_rules.clone()),
+        link_template: overrides
+            .link_template
+            .clone()
+            .or_else(|| base.link_template.clone()),
+        min_level: overrides.min_level.or(base.min_level),
+        no_color: overrides.no_color.or(base.no_color),
+        ascii: overrides.ascii.or(base.ascii),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cli::{ExportFormat, OutputFormat};
     use std::fs;
     use std::path::PathBuf;
     use tempfile::tempdir;
@@ -546,14 +972,21 @@ mod tests {
                 "test-pattern-2".to_string(),
             ]),
             stack_name: None,
+            tags: None,
+            role_arns: None,
             otlp_endpoint: Some("http://localhost:4318".to_string()),
             otlp_headers: vec!["Auth=Bearer xyz".to_string()],
+            otlp_protocol: Some(OtlpProtocol::Grpc),
             aws_region: Some("us-west-2".to_string()),
             aws_profile: Some("test-profile".to_string()),
             verbose: 1,
             forward_only: true,
             attrs: Some("http.*,db.*".to_string()),
-            poll_interval: Some(30 * 1000),        // 30s in ms
+            poll_interval: Some(30 * 1000), // 30s in ms
+            max_api_calls_per_minute: Some(120),
+            metrics_port: Some(9464),
+            metrics_addr: Some("0.0.0.0".parse().unwrap()),
+            sample_ratio: Some(0.1),
             session_timeout: Some(45 * 60 * 1000), // 45m in ms
             event_severity_attribute: Some("custom.severity".to_string()),
             config_profile: None,
@@ -564,9 +997,36 @@ mod tests {
             events_only: Some(false),
             trace_timeout: Some(10 * 1000),   // 10s in ms
             trace_stragglers_wait: Some(500), // 500ms
+            stats: false,
+            stats_interval: Some(30 * 1000),     // 30s in ms
+            refresh_log_groups: Some(60 * 1000), // 1m in ms
+            service_map: true,
+            service_map_dot: None,
+            heatmap: true,
+            correlate_logs: true,
+            notify_on_error: true,
+            notify_webhook: Some("http://localhost:8080/hooks/livetrace".to_string()),
+            export_dir: None,
+            export_format: ExportFormat::Jaeger,
+            html_out: None,
             command: None,
             grep: None,
             backtrace: Some(60 * 1000), // 60s or 1m in ms
+            service: Some("checkout".to_string()),
+            span_name: None,
+            status: Some(StatusFilter::Error),
+            min_duration: Some(200),
+            exclude_span_names: Some(vec!["GET /healthz".to_string()]),
+            exclude_services: None,
+            exclude_attrs: None,
+            color_rules: Some(vec!["http.response.status_code>=500:red".to_string()]),
+            link_template: Some("https://backend/trace/{trace_id}".to_string()),
+            min_level: Some(RecordLevel::Warn),
+            no_color: true,
+            ascii: false,
+            output: OutputFormat::Console,
+            record: None,
+            store: None,
         }
     }
 
@@ -583,11 +1043,14 @@ trace-timeout = "7s" # Example global duration as string
 
 [profiles.default]
 log-group-pattern = ["default-pattern-a", "default-pattern-b"]
+stack-name = "default-stack" # A single stack, as written before stack-name accepted a list
 forward-only = true
 session-timeout = "1h" # Example profile duration as string
 
 [profiles.dev]
 log-group-pattern = ["/aws/lambda/dev-func", "specific-dev-group"]
+stack-name = ["dev-api", "dev-auth"]
+role-arn = "arn:aws:iam::123456789012:role/reader" # A single role
 otlp-endpoint = "http://dev-collector:4318"
 aws-region = "us-west-1"
 poll-interval = "20s"  # Example profile duration as string
@@ -610,6 +1073,7 @@ backtrace = "5m"       # Example profile duration as string
             ])
         );
         assert_eq!(profile.stack_name, None);
+        assert_eq!(profile.tags, None);
         assert_eq!(
             profile.otlp_endpoint,
             Some("http://localhost:4318".to_string())
@@ -618,11 +1082,16 @@ backtrace = "5m"       # Example profile duration as string
             profile.otlp_headers,
             Some(vec!["Auth=Bearer xyz".to_string()])
         );
+        assert_eq!(profile.otlp_protocol, Some(OtlpProtocol::Grpc));
         assert_eq!(profile.aws_region, Some("us-west-2".to_string()));
         assert_eq!(profile.aws_profile, Some("test-profile".to_string()));
         assert_eq!(profile.forward_only, Some(true));
         assert_eq!(profile.attrs, Some("http.*,db.*".to_string()));
         assert_eq!(profile.poll_interval, Some("30s".to_string()));
+        assert_eq!(profile.max_api_calls_per_minute, Some(120));
+        assert_eq!(profile.metrics_port, Some(9464));
+        assert_eq!(profile.metrics_addr, Some("0.0.0.0".parse().unwrap()));
+        assert_eq!(profile.sample_ratio, Some(0.1));
         assert_eq!(profile.session_timeout, Some("45m".to_string()));
         assert_eq!(
             profile.event_severity_attribute,
@@ -633,7 +1102,37 @@ backtrace = "5m"       # Example profile duration as string
         assert_eq!(profile.events_only, Some(false));
         assert_eq!(profile.trace_timeout, Some("10s".to_string()));
         assert_eq!(profile.trace_stragglers_wait, Some("500ms".to_string()));
+        assert_eq!(profile.stats, None);
+        assert_eq!(profile.stats_interval, Some("30s".to_string()));
+        assert_eq!(profile.refresh_log_groups, Some("1m".to_string()));
+        assert_eq!(profile.service_map, Some(true));
+        assert_eq!(profile.heatmap, Some(true));
+        assert_eq!(
+            profile.color_rules,
+            Some(vec!["http.response.status_code>=500:red".to_string()])
+        );
+        assert_eq!(
+            profile.link_template,
+            Some("https://backend/trace/{trace_id}".to_string())
+        );
+        assert_eq!(profile.min_level, Some(RecordLevel::Warn));
+        assert_eq!(profile.no_color, Some(true));
+        assert_eq!(profile.ascii, None);
+        assert_eq!(profile.correlate_logs, Some(true));
+        assert_eq!(profile.notify_on_error, Some(true));
+        assert_eq!(
+            profile.notify_webhook,
+            Some("http://localhost:8080/hooks/livetrace".to_string())
+        );
         assert_eq!(profile.backtrace, Some("1m".to_string()));
+        assert_eq!(profile.service, Some("checkout".to_string()));
+        assert_eq!(profile.span_name, None);
+        assert_eq!(profile.status, Some(StatusFilter::Error));
+        assert_eq!(profile.min_duration, Some("200ms".to_string()));
+        assert_eq!(
+            profile.exclude_span_names,
+            Some(vec!["GET /healthz".to_string()])
+        );
 
         // Test case where session_timeout and trace_timeout are default
         let mut args_with_defaults = mock_cli_args();
@@ -693,15 +1192,22 @@ backtrace = "5m"       # Example profile duration as string
 
         let mut effective = EffectiveConfig {
             log_group_pattern: Some(vec!["initial-pattern".to_string()]),
-            stack_name: Some("original-stack".to_string()),
+            stack_name: Some(vec!["original-stack".to_string()]),
+            tags: None,
+            role_arns: None,
             otlp_endpoint: None,
             otlp_headers: Vec::new(),
+            otlp_protocol: None,
             aws_region: Some("us-east-1".to_string()),
             aws_profile: None,
             forward_only: false,
             attrs: None,
             event_severity_attribute: "default.severity".to_string(),
             poll_interval_ms: None,
+            max_api_calls_per_minute: None,
+            metrics_port: None,
+            metrics_addr: None,
+            sample_ratio: None,
             session_timeout_ms: DEFAULT_SESSION_TIMEOUT_MS, // Default in ms
             verbose: 0,
             theme: Theme::Default,
@@ -709,8 +1215,28 @@ backtrace = "5m"       # Example profile duration as string
             events_only: false,
             trace_timeout_ms: DEFAULT_TRACE_TIMEOUT_MS, // Default in ms
             trace_stragglers_wait_ms: DEFAULT_TRACE_STRAGGLERS_WAIT_MS, // Default in ms
+            stats: false,
+            stats_interval_ms: None,
+            refresh_log_groups_ms: None,
+            service_map: false,
+            heatmap: false,
+            correlate_logs: false,
+            notify_on_error: false,
+            notify_webhook: None,
             grep: None,
             backtrace_ms: None,
+            service: None,
+            span_name: None,
+            status: None,
+            min_duration_ms: None,
+            exclude_span_names: None,
+            exclude_services: None,
+            exclude_attrs: None,
+            color_rules: None,
+            link_template: None,
+            min_level: None,
+            no_color: false,
+            ascii: false,
         };
         let profile = ProfileConfig {
             // Durations as Option<String>
@@ -719,22 +1245,51 @@ backtrace = "5m"       # Example profile duration as string
                 "profile-pattern-2".to_string(),
             ]),
             stack_name: None, // Will keep effective.stack_name
+            tags: Some(vec!["team=payments".to_string()]),
+            role_arns: Some(vec![
+                "arn:aws:iam::123456789012:role/reader:/aws/lambda/orders-".to_string(),
+            ]),
             otlp_endpoint: Some("http://profile-endpoint:4318".to_string()),
             otlp_headers: Some(vec!["Profile-Auth=token123".to_string()]),
+            otlp_protocol: Some(OtlpProtocol::Grpc),
             aws_region: None, // Will keep effective.aws_region
             aws_profile: Some("profile-aws-profile".to_string()),
             forward_only: Some(true),
             attrs: Some("profile.*".to_string()),
             event_severity_attribute: Some("profile.severity".to_string()),
             poll_interval: Some("45s".to_string()), // String duration
+            max_api_calls_per_minute: Some(60),
+            metrics_port: Some(9100),
+            metrics_addr: None,
+            sample_ratio: Some(0.25),
             session_timeout: Some("1h".to_string()), // String duration, different from effective default
             theme: Some(Theme::Solarized),
             color_by: None, // Will keep effective.color_by
             events_only: Some(true),
             trace_timeout: Some("10000ms".to_string()), // String duration (10s), different from effective default
             trace_stragglers_wait: Some("2s".to_string()), // String duration (2s)
+            stats: Some(true),
+            stats_interval: Some("1m".to_string()),
+            refresh_log_groups: Some("2m".to_string()),
+            service_map: None,
+            heatmap: Some(true),
+            correlate_logs: None,
+            notify_on_error: Some(true),
+            notify_webhook: None,
             grep: Some("test-grep".to_string()),
             backtrace: Some("60s".to_string()), // String duration
+            service: Some("checkout".to_string()),
+            span_name: Some("GET /cart".to_string()),
+            status: Some(StatusFilter::Error),
+            min_duration: Some("1s".to_string()),
+            exclude_span_names: None,
+            exclude_services: Some(vec!["canary".to_string()]),
+            exclude_attrs: Some(vec!["http.route=/health*".to_string()]),
+            color_rules: Some(vec!["tenant.tier=gold:yellow".to_string()]),
+            link_template: Some("https://backend/trace/{trace_id}".to_string()),
+            min_level: Some(RecordLevel::Error),
+            no_color: Some(true),
+            ascii: Some(true),
         };
         apply_profile_to_effective(&profile, &mut effective);
         assert_eq!(
@@ -744,12 +1299,23 @@ backtrace = "5m"       # Example profile duration as string
                 "profile-pattern-2".to_string()
             ])
         );
-        assert_eq!(effective.stack_name, Some("original-stack".to_string())); // Unchanged by profile
+        assert_eq!(
+            effective.stack_name,
+            Some(vec!["original-stack".to_string()])
+        ); // Unchanged by profile
         assert_eq!(
             effective.otlp_endpoint,
             Some("http://profile-endpoint:4318".to_string())
         );
         assert_eq!(effective.otlp_headers, vec!["Profile-Auth=token123"]);
+        assert_eq!(effective.otlp_protocol, Some(OtlpProtocol::Grpc));
+        assert_eq!(effective.tags, Some(vec!["team=payments".to_string()]));
+        assert_eq!(
+            effective.role_arns,
+            Some(vec![
+                "arn:aws:iam::123456789012:role/reader:/aws/lambda/orders-".to_string()
+            ])
+        );
         assert_eq!(effective.aws_region, Some("us-east-1".to_string())); // Unchanged by profile
         assert_eq!(
             effective.aws_profile,
@@ -759,14 +1325,42 @@ backtrace = "5m"       # Example profile duration as string
         assert_eq!(effective.attrs, Some("profile.*".to_string()));
         assert_eq!(effective.event_severity_attribute, "profile.severity");
         assert_eq!(effective.poll_interval_ms, Some(45 * 1000)); // Check for ms
+        assert_eq!(effective.max_api_calls_per_minute, Some(60));
+        assert_eq!(effective.metrics_port, Some(9100));
+        assert_eq!(effective.sample_ratio, Some(0.25));
+        assert!(effective.notify_on_error);
         assert_eq!(effective.session_timeout_ms, 60 * 60 * 1000); // Check for ms (1h)
         assert_eq!(effective.theme, Theme::Solarized);
         assert_eq!(effective.color_by, ColoringMode::Service); // Unchanged by profile
         assert!(effective.events_only);
         assert_eq!(effective.trace_timeout_ms, 10_000); // Check for ms (10s)
         assert_eq!(effective.trace_stragglers_wait_ms, 2_000); // Check for ms (2s)
+        assert!(effective.stats);
+        assert_eq!(effective.stats_interval_ms, Some(60_000));
+        assert_eq!(effective.refresh_log_groups_ms, Some(120_000));
+        assert!(effective.heatmap);
         assert_eq!(effective.grep, Some("test-grep".to_string()));
         assert_eq!(effective.backtrace_ms, Some(60 * 1000)); // Check for ms
+        assert_eq!(effective.service, Some("checkout".to_string()));
+        assert_eq!(effective.span_name, Some("GET /cart".to_string()));
+        assert_eq!(effective.status, Some(StatusFilter::Error));
+        assert_eq!(effective.min_duration_ms, Some(1000));
+        assert_eq!(effective.exclude_services, Some(vec!["canary".to_string()]));
+        assert_eq!(
+            effective.exclude_attrs,
+            Some(vec!["http.route=/health*".to_string()])
+        );
+        assert_eq!(
+            effective.color_rules,
+            Some(vec!["tenant.tier=gold:yellow".to_string()])
+        );
+        assert_eq!(
+            effective.link_template,
+            Some("https://backend/trace/{trace_id}".to_string())
+        );
+        assert_eq!(effective.min_level, Some(RecordLevel::Error));
+        assert!(effective.no_color);
+        assert!(effective.ascii);
     }
 
     #[test]
@@ -784,14 +1378,21 @@ backtrace = "5m"       # Example profile duration as string
         let mut effective = EffectiveConfig {
             log_group_pattern: None,
             stack_name: None,
+            tags: None,
+            role_arns: None,
             otlp_endpoint: None,
             otlp_headers: Vec::new(),
+            otlp_protocol: None,
             aws_region: None,
             aws_profile: None,
             forward_only: false,
             attrs: None,
             event_severity_attribute: "event.severity".to_string(),
             poll_interval_ms: None,
+            max_api_calls_per_minute: None,
+            metrics_port: None,
+            metrics_addr: None,
+            sample_ratio: None,
             session_timeout_ms: DEFAULT_EFFECTIVE_SESSION_TIMEOUT_MS,
             verbose: 0,
             theme: Theme::Default,
@@ -799,13 +1400,37 @@ backtrace = "5m"       # Example profile duration as string
             events_only: false,
             trace_timeout_ms: DEFAULT_EFFECTIVE_TRACE_TIMEOUT_MS,
             trace_stragglers_wait_ms: DEFAULT_EFFECTIVE_TRACE_STRAGGLERS_WAIT_MS, // Initialize new field
+            stats: false,
+            stats_interval_ms: None,
+            refresh_log_groups_ms: None,
+            service_map: false,
+            heatmap: false,
+            correlate_logs: false,
+            notify_on_error: false,
+            notify_webhook: None,
             grep: None,
             backtrace_ms: None,
+            service: None,
+            span_name: None,
+            status: None,
+            min_duration_ms: None,
+            exclude_span_names: None,
+            exclude_services: None,
+            exclude_attrs: None,
+            color_rules: None,
+            link_template: None,
+            min_level: None,
+            no_color: false,
+            ascii: false,
         };
 
         // Load the specific test config file
         let config_file =
             load_config_file(&config_path).expect("Test: Failed to load test config file");
+        assert_eq!(
+            config_file.profiles["default"].stack_name,
+            Some(vec!["default-stack".to_string()])
+        );
 
         // Apply global config from the test file
         if let Some(global_config) = &config_file.global {
@@ -825,6 +1450,14 @@ backtrace = "5m"       # Example profile duration as string
         // Assertions based on precedence: CLI > Profile > Global > EffectiveConfig_default
         assert_eq!(effective.log_group_pattern, cli_args_mock.log_group_pattern); // CLI
         assert_eq!(effective.otlp_endpoint, cli_args_mock.otlp_endpoint); // CLI
+        assert_eq!(
+            effective.stack_name,
+            Some(vec!["dev-api".to_string(), "dev-auth".to_string()])
+        ); // Profile 'dev', not set on the CLI
+        assert_eq!(
+            effective.role_arns,
+            Some(vec!["arn:aws:iam::123456789012:role/reader".to_string()])
+        ); // Profile 'dev', not set on the CLI
         assert_eq!(effective.aws_region, cli_args_mock.aws_region); // CLI overrides profile 'dev' ("us-west-1") and global ("us-east-1")
 
         // Define constants for programmatic defaults to use in assertions
@@ -872,43 +1505,97 @@ backtrace = "5m"       # Example profile duration as string
     fn test_merge_into_profile_config() {
         let base = ProfileConfig {
             log_group_pattern: Some(vec!["base-pattern".to_string()]),
-            stack_name: Some("base-stack".to_string()),
+            stack_name: Some(vec!["base-stack".to_string()]),
+            tags: Some(vec!["team=base".to_string()]),
+            role_arns: None,
             otlp_endpoint: Some("http://base:4318".to_string()),
             otlp_headers: None,
+            otlp_protocol: Some(OtlpProtocol::Grpc),
             aws_region: Some("us-east-1".to_string()),
             aws_profile: None,
             forward_only: Some(false),
             attrs: Some("base.*".to_string()),
             event_severity_attribute: None,
             poll_interval: Some("10s".to_string()), // String duration
-            session_timeout: None,                  // String duration (None)
+            max_api_calls_per_minute: Some(60),
+            metrics_port: Some(9464),
+            metrics_addr: None,
+            sample_ratio: Some(0.25),
+            session_timeout: None, // String duration (None)
             theme: Some(Theme::Material),
             color_by: Some(ColoringMode::Service),
             events_only: Some(false),
             trace_timeout: Some("7000ms".to_string()), // String duration
             trace_stragglers_wait: Some("1s".to_string()), // String duration
+            stats: None,
+            stats_interval: Some("5s".to_string()),
+            refresh_log_groups: Some("1m".to_string()),
+            service_map: Some(false),
+            heatmap: Some(true),
+            correlate_logs: Some(true),
+            notify_on_error: Some(true),
+            notify_webhook: Some("http://base:8080/hook".to_string()),
             grep: None,
             backtrace: Some("2m".to_string()), // String duration
+            service: Some("base-service".to_string()),
+            span_name: None,
+            status: Some(StatusFilter::Ok),
+            min_duration: None,
+            exclude_span_names: Some(vec!["GET /healthz".to_string()]),
+            exclude_services: None,
+            exclude_attrs: None,
+            color_rules: Some(vec!["tenant.tier=gold:yellow".to_string()]),
+            link_template: Some("https://base/trace/{trace_id}".to_string()),
+            min_level: Some(RecordLevel::Info),
+            no_color: Some(false),
+            ascii: Some(true),
         };
         let overrides = ProfileConfig {
             log_group_pattern: None,
-            stack_name: Some("override-stack".to_string()),
+            stack_name: Some(vec!["override-stack".to_string()]),
+            tags: None,
+            role_arns: None,
             otlp_endpoint: None,
             otlp_headers: Some(vec!["override-header".to_string()]),
+            otlp_protocol: None,
             aws_region: Some("us-west-2".to_string()),
             aws_profile: None,
             forward_only: Some(true),
             attrs: None,
             event_severity_attribute: Some("override.severity".to_string()),
             poll_interval: Some("15s".to_string()), // Override string duration
+            max_api_calls_per_minute: None,
+            metrics_port: Some(9200),
+            metrics_addr: None,
+            sample_ratio: None,
             session_timeout: Some("90m".to_string()), // Override string duration
             theme: None,
             color_by: Some(ColoringMode::Span),
             events_only: None,
             trace_timeout: Some("20s".to_string()), // Override string duration
             trace_stragglers_wait: Some("0ms".to_string()), // Override string duration (to default)
+            stats: Some(true),
+            stats_interval: None,
+            refresh_log_groups: None,
+            service_map: None,
+            heatmap: None,
+            correlate_logs: None,
+            notify_on_error: None,
+            notify_webhook: None,
             grep: Some("override-grep".to_string()),
             backtrace: None, // Override with None
+            service: None,
+            span_name: Some("override-span".to_string()),
+            status: Some(StatusFilter::Error),
+            min_duration: Some("200ms".to_string()),
+            exclude_span_names: None,
+            exclude_services: None,
+            exclude_attrs: None,
+            color_rules: None,
+            link_template: None,
+            min_level: Some(RecordLevel::Warn),
+            no_color: Some(true),
+            ascii: None,
         };
         let merged = merge_into_profile_config(&base, &overrides);
 
@@ -916,17 +1603,35 @@ backtrace = "5m"       # Example profile duration as string
         // Durations are asserted as Option<String>.
         assert_eq!(merged.log_group_pattern, base.log_group_pattern); // Override is None
         assert_eq!(merged.stack_name, overrides.stack_name);
+        assert_eq!(merged.tags, base.tags); // Override is None
         assert_eq!(merged.otlp_endpoint, base.otlp_endpoint); // Override is None
         assert_eq!(merged.otlp_headers, overrides.otlp_headers);
+        assert_eq!(merged.otlp_protocol, base.otlp_protocol); // Override is None
         assert_eq!(merged.aws_region, overrides.aws_region);
         assert_eq!(merged.aws_profile, base.aws_profile); // Override is None
         assert_eq!(merged.forward_only, overrides.forward_only);
+        assert_eq!(merged.stats, overrides.stats);
+        assert_eq!(merged.stats_interval, base.stats_interval); // Override is None
+        assert_eq!(merged.refresh_log_groups, base.refresh_log_groups); // Override is None
+        assert_eq!(merged.service_map, base.service_map); // Override is None
+        assert_eq!(merged.heatmap, base.heatmap); // Override is None
+        assert_eq!(merged.color_rules, base.color_rules); // Override is None
+        assert_eq!(merged.link_template, base.link_template); // Override is None
+        assert_eq!(merged.min_level, overrides.min_level);
+        assert_eq!(merged.no_color, Some(true));
+        assert_eq!(merged.ascii, base.ascii); // Override is None
+        assert_eq!(merged.correlate_logs, Some(true));
+        assert_eq!(merged.notify_on_error, Some(true)); // Override is None
+        assert_eq!(merged.notify_webhook, base.notify_webhook); // Override is None
         assert_eq!(merged.attrs, base.attrs); // Override is None
         assert_eq!(
             merged.event_severity_attribute,
             overrides.event_severity_attribute
         );
         assert_eq!(merged.poll_interval, overrides.poll_interval);
+        assert_eq!(merged.max_api_calls_per_minute, Some(60)); // Override is None
+        assert_eq!(merged.metrics_port, Some(9200));
+        assert_eq!(merged.sample_ratio, Some(0.25)); // Override is None
         assert_eq!(merged.session_timeout, overrides.session_timeout);
         assert_eq!(merged.theme, base.theme); // Override is None
         assert_eq!(merged.color_by, overrides.color_by);
@@ -938,5 +1643,10 @@ backtrace = "5m"       # Example profile duration as string
         );
         assert_eq!(merged.grep, overrides.grep);
         assert_eq!(merged.backtrace, base.backtrace); // Override is None
+        assert_eq!(merged.service, base.service); // Override is None
+        assert_eq!(merged.span_name, overrides.span_name);
+        assert_eq!(merged.exclude_span_names, base.exclude_span_names); // Override is None
+        assert_eq!(merged.status, overrides.status);
+        assert_eq!(merged.min_duration, overrides.min_duration);
     }
 }
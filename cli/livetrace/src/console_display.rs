@@ -4,12 +4,21 @@
 //! - Defining color themes and palettes.
 //! - Structuring trace data into a hierarchical, readable format (waterfall view).
 //! - Formatting individual spans and events with appropriate colors and indentation.
+//! - Highlighting the spans matching the user-defined color rules.
+//! - Linking each trace to a tracing backend with an OSC 8 terminal hyperlink.
 //! - Generating a timeline scale for trace visualization.
 //! - Displaying span attributes and event attributes, with optional filtering.
+//! - Interleaving the log lines correlated to a trace with its events in the timeline log.
+//! - Printing a footnote with the suspicious conditions found in each trace.
 //! - Managing terminal width for responsive output.
 
 use crate::cli::ColoringMode;
+use crate::color_rules::{color_for_attributes, ColorRule};
+use crate::diagnostics::{diagnose_trace, print_diagnostics};
+use crate::log_correlation::CorrelatedLog;
 use crate::processing::TelemetryData;
+use crate::recorder::console_println;
+use crate::terminal::{colors_enabled, glyphs};
 use anyhow::Result;
 use chrono::{TimeZone, Utc};
 use colored::*;
@@ -222,6 +231,7 @@ struct ConsoleSpan {
 enum ItemType {
     SpanStart,
     Event,
+    Log,
 }
 
 #[derive(Debug)]
@@ -230,7 +240,7 @@ struct TimelineItem {
     item_type: ItemType,
     service_name: String,
     span_id: String,
-    name: String,              // Span Name or Event Name
+    name: String,              // Span Name, Event Name or "log"
     level_or_status: String,   // Formatted Level (e.g., INFO) or Status (e.g., OK)
     attributes: Vec<KeyValue>, // Filtered attributes for the specific item
     // Optional: Store parent span attributes separately *only* for events
@@ -246,22 +256,32 @@ pub fn get_terminal_width(default_width: usize) -> usize {
     }
 }
 
+// Function to get terminal height with a default fallback
+pub fn get_terminal_height(default_height: usize) -> usize {
+    if let Some((Width(_w), Height(h))) = terminal_size::terminal_size() {
+        h as usize
+    } else {
+        default_height // Fallback if terminal size can't be determined
+    }
+}
+
 fn format_duration_for_scale(duration_ns: u64) -> String {
+    let marker = glyphs().scale_marker;
     if duration_ns == 0 {
-        return "▾0ms".to_string();
+        return format!("{}0ms", marker);
     }
 
     let ms = duration_ns as f64 / 1_000_000.0;
     if ms < 1.0 {
         // Show microseconds for very small durations
         let us = duration_ns as f64 / 1_000.0;
-        format!("▾{:.0}μs", us)
+        format!("{}{:.0}{}", marker, us, glyphs().micros)
     } else if ms < 1000.0 {
         // Show milliseconds for normal durations
-        format!("▾{:.0}ms", ms)
+        format!("{}{:.0}ms", marker, ms)
     } else {
         // Show seconds for large durations
-        format!("▾{:.1}s", ms / 1000.0)
+        format!("{}{:.1}s", marker, ms / 1000.0)
     }
 }
 
@@ -318,7 +338,7 @@ fn generate_timeline_scale(trace_duration_ns: u64, timeline_width: usize) -> Str
 }
 
 // Helper function to convert AnyValue to a comprehensive string for grep matching
-fn get_string_value_for_grep(value_opt: &Option<AnyValue>) -> String {
+pub(crate) fn get_string_value_for_grep(value_opt: &Option<AnyValue>) -> String {
     if let Some(any_value) = value_opt {
         if let Some(ref val_type) = any_value.value {
             return match val_type {
@@ -432,8 +452,23 @@ fn calculate_layout_widths(default_terminal_width: usize) -> (usize, usize, usiz
     (terminal_width, calculated_timeline_width, terminal_width)
 }
 
+/// Returns the URL of a trace in a tracing backend, from a template with a {trace_id} placeholder
+pub fn trace_link(link_template: &str, trace_id: &str) -> String {
+    link_template.replace("{trace_id}", trace_id)
+}
+
+// Wraps the text in an OSC 8 hyperlink, shown as plain text by terminals not supporting them
+fn terminal_hyperlink(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
 // Helper function to print the trace header
-fn print_trace_header(trace_id: &str, root_span_received: bool, total_table_width: usize) {
+fn print_trace_header(
+    trace_id: &str,
+    root_span_received: bool,
+    total_table_width: usize,
+    link_template: Option<&str>,
+) {
     // Construct the base heading for the trace.
     let base_heading = format!("Trace ID: {}", trace_id);
     // Add a suffix if the root span for this trace was not received.
@@ -442,12 +477,26 @@ fn print_trace_header(trace_id: &str, root_span_received: bool, total_table_widt
     } else {
         " (Missing Root)"
     };
+    // The trace ID links to the trace in the backend, marked with an arrow.
+    let link_marker = match link_template {
+        Some(_) => format!(" {}", glyphs().link_marker),
+        None => String::new(),
+    };
 
-    // Calculate the visible length of the heading (base + suffix).
-    let visible_heading_len = base_heading.len() + suffix.len();
+    // Calculate the visible length of the heading (base + suffix + marker).
+    let visible_heading_len = base_heading.len() + suffix.len() + link_marker.chars().count();
 
     // Style the heading: bold for the base, dimmed for the suffix.
-    let styled_heading = format!("{}{}", base_heading.bold(), suffix.dimmed());
+    let styled_base_heading = base_heading.bold().to_string();
+    let styled_heading = match link_template {
+        Some(link_template) => format!(
+            "{}{}{}",
+            terminal_hyperlink(&trace_link(link_template, trace_id), &styled_base_heading),
+            link_marker,
+            suffix.dimmed()
+        ),
+        None => format!("{}{}", styled_base_heading, suffix.dimmed()),
+    };
 
     // Calculate the number of dashes needed for padding around the heading
     // to make the header line span the `total_table_width`.
@@ -456,11 +505,11 @@ fn print_trace_header(trace_id: &str, root_span_received: bool, total_table_widt
     let right_dashes = total_dash_len.saturating_sub(left_dashes);
 
     // Print the formatted trace header.
-    println!(
+    console_println!(
         "\n{} {} {}\n\n",
-        "─".repeat(left_dashes).dimmed(),
+        glyphs().rule.repeat(left_dashes).dimmed(),
         styled_heading,
-        "─".repeat(right_dashes).dimmed()
+        glyphs().rule.repeat(right_dashes).dimmed()
     );
 }
 
@@ -599,6 +648,52 @@ fn collect_and_filter_timeline_items_for_trace(
     timeline_items
 }
 
+// Helper function to turn the log lines correlated to a trace into timeline items, each under
+// the span it names, or else the innermost span running when it was logged
+fn collect_log_items_for_trace(
+    logs: &[&CorrelatedLog],
+    spans_in_trace_with_service: &[(Span, String)],
+    grep_regex: Option<&Regex>,
+) -> Vec<TimelineItem> {
+    logs.iter()
+        .filter(|log| grep_regex.is_none_or(|re| re.is_match(&log.message)))
+        .filter_map(|log| {
+            let timestamp_ns = u64::try_from(log.timestamp_ms).unwrap_or_default() * 1_000_000;
+            let named_span = log.span_id.as_ref().and_then(|span_id| {
+                spans_in_trace_with_service
+                    .iter()
+                    .find(|(span, _)| hex::encode(&span.span_id) == *span_id)
+            });
+            let (span, service_name) = named_span
+                .or_else(|| {
+                    spans_in_trace_with_service
+                        .iter()
+                        .filter(|(span, _)| {
+                            (span.start_time_unix_nano..=span.end_time_unix_nano)
+                                .contains(&timestamp_ns)
+                        })
+                        .max_by_key(|(span, _)| span.start_time_unix_nano)
+                })
+                .or_else(|| spans_in_trace_with_service.first())?;
+            Some(TimelineItem {
+                timestamp_ns,
+                item_type: ItemType::Log,
+                service_name: service_name.clone(),
+                span_id: hex::encode(&span.span_id),
+                name: "log".to_string(),
+                level_or_status: log.level.clone().unwrap_or_else(|| "INFO".to_string()),
+                attributes: vec![KeyValue {
+                    key: "message".to_string(),
+                    value: Some(AnyValue {
+                        value: Some(ProtoValue::StringValue(log.message.clone())),
+                    }),
+                }],
+                parent_span_attributes: None,
+            })
+        })
+        .collect()
+}
+
 // Helper function to build waterfall hierarchy and gather metadata
 fn build_waterfall_hierarchy_and_meta(
     spans_in_trace_with_service: &[(Span, String)],
@@ -682,16 +777,26 @@ fn render_waterfall_table(
     theme: Theme,
     color_by: ColoringMode,
     span_map: &HashMap<String, Span>, // For add_span_to_table
+    rule_colors: &HashMap<String, (u8, u8, u8)>,
 ) -> Result<()> {
     let mut table = Table::new();
     table
         .load_preset(presets::NOTHING)
         .set_content_arrangement(ContentArrangement::DynamicFullWidth)
         .set_width(total_table_width as u16)
-        .set_style(TableComponent::MiddleHeaderIntersections, '┴')
-        .set_style(TableComponent::BottomBorder, '─')
-        .set_style(TableComponent::BottomBorderIntersections, '─')
-        .set_style(TableComponent::HeaderLines, '─');
+        .set_style(
+            TableComponent::MiddleHeaderIntersections,
+            glyphs().header_intersection,
+        )
+        .set_style(TableComponent::BottomBorder, glyphs().rule_char)
+        .set_style(
+            TableComponent::BottomBorderIntersections,
+            glyphs().rule_char,
+        )
+        .set_style(TableComponent::HeaderLines, glyphs().rule_char);
+    if !colors_enabled() {
+        table.force_no_tty();
+    }
 
     table.set_header(vec![
         Cell::new("Service").add_attribute(Attribute::Bold),
@@ -745,10 +850,11 @@ fn render_waterfall_table(
             theme,
             span_map, // Pass the original `Span` map
             color_by,
+            rule_colors,
         )?;
     }
 
-    println!("{}", table);
+    console_println!("{}", table);
     Ok(())
 }
 
@@ -758,6 +864,7 @@ fn print_timeline_log(
     color_by: ColoringMode,
     theme: Theme,
     grep_regex: Option<&Regex>,
+    rule_colors: &HashMap<String, (u8, u8, u8)>,
 ) {
     if timeline_items.is_empty() {
         return;
@@ -779,10 +886,12 @@ fn print_timeline_log(
         let timestamp = Utc.timestamp_nanos(item.timestamp_ns as i64);
         let formatted_time = timestamp.format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string();
 
-        // Get color for the span ID prefix based on `color_by` setting (service or span ID).
-        let (prefix_r, prefix_g, prefix_b) = match color_by {
-            ColoringMode::Service => theme.get_color_for_service(&item.service_name),
-            ColoringMode::Span => theme.get_color_for_span(&item.span_id),
+        // Get color for the span ID prefix based on `color_by` setting (service or span ID),
+        // unless a color rule matches the span.
+        let (prefix_r, prefix_g, prefix_b) = match (rule_colors.get(&item.span_id), color_by) {
+            (Some(&rule_color), _) => rule_color,
+            (None, ColoringMode::Service) => theme.get_color_for_service(&item.service_name),
+            (None, ColoringMode::Span) => theme.get_color_for_span(&item.span_id),
         };
 
         // Take the first 8 characters of the span ID for display.
@@ -792,6 +901,7 @@ fn print_timeline_log(
         let type_tag = match item.item_type {
             ItemType::SpanStart => "SPAN".to_string(),
             ItemType::Event => "EVENT".to_string(),
+            ItemType::Log => "LOG".to_string(),
         };
 
         // Logic to get raw status/level text for consistent processing
@@ -849,7 +959,7 @@ fn print_timeline_log(
             String::new()
         };
 
-        println!(
+        console_println!(
             "{}  {:12} {} {} {} {}", // Adjusted for padded names and attrs_suffix structure
             formatted_time.dimmed(),
             level_status_colored,
@@ -871,6 +981,9 @@ pub fn display_console(
     events_only: bool, // If true, only events are shown in the timeline log (spans are hidden).
     root_span_received: bool, // Indicates if the root span for the trace was found.
     grep_regex: Option<&Regex>, // Optional regex for filtering timeline items by attribute values.
+    logs: &[CorrelatedLog], // Log lines correlated to the traces of the batch, shown in their timeline log.
+    color_rules: &[ColorRule], // User-defined colors of the spans matching a condition on their attributes.
+    link_template: Option<&str>, // URL of a trace in a tracing backend, linked from the trace header.
 ) -> Result<()> {
     // Debug logging with theme and coloring mode
     tracing::debug!("Display console called with theme={:?}, color_by={:?}, events_only={}, root_span_received={}, has_grep_regex={}",
@@ -894,7 +1007,12 @@ pub fn display_console(
     // `spans_in_trace_with_service` is a vector of (Span, ServiceName) for this trace.
     for (trace_id, spans_in_trace_with_service) in traces {
         // ---- Print Trace Header ----
-        print_trace_header(&trace_id, root_span_received, total_table_width);
+        print_trace_header(
+            &trace_id,
+            root_span_received,
+            total_table_width,
+            link_template,
+        );
 
         // If there are no spans in this particular trace (e.g., after filtering or if data was empty),
         // skip to the next trace.
@@ -903,13 +1021,32 @@ pub fn display_console(
         }
 
         // Collect and filter timeline items (span starts and events) for the current trace.
-        let timeline_items = collect_and_filter_timeline_items_for_trace(
+        let mut timeline_items = collect_and_filter_timeline_items_for_trace(
             &spans_in_trace_with_service,
             attr_globs,
             event_severity_attribute_name,
             events_only,
             grep_regex,
         );
+        let trace_logs: Vec<&CorrelatedLog> =
+            logs.iter().filter(|log| log.trace_id == trace_id).collect();
+        if !trace_logs.is_empty() {
+            timeline_items.extend(collect_log_items_for_trace(
+                &trace_logs,
+                &spans_in_trace_with_service,
+                grep_regex,
+            ));
+            timeline_items.sort_by_key(|item| item.timestamp_ns);
+        }
+
+        // Find the color of the spans matching a color rule.
+        let rule_colors: HashMap<String, (u8, u8, u8)> = spans_in_trace_with_service
+            .iter()
+            .filter_map(|(span, _)| {
+                color_for_attributes(color_rules, &span.attributes)
+                    .map(|color| (hex::encode(&span.span_id), color))
+            })
+            .collect();
 
         // Build the waterfall hierarchy (ConsoleSpans) and get timing metadata.
         let (roots, min_start_time_ns, trace_duration_ns, span_map) =
@@ -925,13 +1062,17 @@ pub fn display_console(
             theme,
             color_by,
             &span_map,
+            &rule_colors,
         )?;
 
         // ---- Print Timeline Log ----
         // If there are sorted timeline items (span starts or events), print them.
         if !timeline_items.is_empty() {
-            print_timeline_log(&timeline_items, color_by, theme, grep_regex);
+            print_timeline_log(&timeline_items, color_by, theme, grep_regex, &rule_colors);
         }
+
+        // ---- Print Diagnostics ----
+        print_diagnostics(&diagnose_trace(&spans_in_trace_with_service));
     }
     // End of loop for each trace.
 
@@ -1019,13 +1160,16 @@ fn add_span_to_table(
     theme: Theme,
     span_map: &HashMap<String, Span>,
     color_by: ColoringMode,
+    rule_colors: &HashMap<String, (u8, u8, u8)>,
 ) -> Result<()> {
     let indent = "  ".repeat(depth);
 
-    // Get Color (still needed for timeline bar)
-    let (r, g, b) = match color_by {
-        ColoringMode::Service => theme.get_color_for_service(&node.service_name),
-        ColoringMode::Span => theme.get_color_for_span(&node.id),
+    // Get Color (still needed for timeline bar), a matching color rule taking precedence
+    let rule_color = rule_colors.get(&node.id).copied();
+    let (r, g, b) = match (rule_color, color_by) {
+        (Some(rule_color), _) => rule_color,
+        (None, ColoringMode::Service) => theme.get_color_for_service(&node.service_name),
+        (None, ColoringMode::Span) => theme.get_color_for_span(&node.id),
     };
 
     // Create Cell Content
@@ -1064,9 +1208,14 @@ fn add_span_to_table(
     let status_content_str = format_span_status(node.status_code);
     let formatted_duration = format!("{:.2}", node.duration_ns as f64 / 1_000_000.0);
 
+    let mut span_name_cell = Cell::new(span_name_cell_content);
+    if rule_color.is_some() {
+        span_name_cell = span_name_cell.fg(TableColor::Rgb { r, g, b });
+    }
+
     table.add_row(vec![
         Cell::new(service_name_content),
-        Cell::new(span_name_cell_content),
+        span_name_cell,
         Cell::new(kind_cell_content),
         Cell::new(formatted_duration).set_alignment(CellAlignment::Right), // Right-align duration
         Cell::new(span_id_prefix).fg(TableColor::Rgb { r, g, b }),
@@ -1088,6 +1237,7 @@ fn add_span_to_table(
             theme,
             span_map,
             color_by,
+            rule_colors,
         )?;
     }
 
@@ -1118,7 +1268,7 @@ fn render_bar(
     let mut bar_content = String::with_capacity(timeline_width);
     for i in 0..timeline_width {
         if i >= start_pos && i < end_pos.min(timeline_width) {
-            bar_content.push('▄');
+            bar_content.push(glyphs().bar);
         } else {
             bar_content.push(' ');
         }
@@ -1127,7 +1277,7 @@ fn render_bar(
     bar_content
 }
 
-fn format_span_kind(kind: i32) -> String {
+pub(crate) fn format_span_kind(kind: i32) -> String {
     match kind {
         1 => "INTERNAL".to_string(),
         2 => "SERVER".to_string(),
@@ -1173,7 +1323,7 @@ fn format_anyvalue(av: &Option<AnyValue>) -> String {
     }
 }
 
-fn format_span_status(status_code: status::StatusCode) -> String {
+pub(crate) fn format_span_status(status_code: status::StatusCode) -> String {
     match status_code {
         status::StatusCode::Ok => "OK",
         status::StatusCode::Error => "ERROR",
@@ -1189,3 +1339,24 @@ fn format_cell_level_color(value: &str) -> Cell {
         _ => Cell::new(value).fg(TableColor::DarkGrey),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_link() {
+        let url = trace_link(
+            "https://ui.example.com/trace/{trace_id}?view=waterfall",
+            "0af7651916cd43dd8448eb211c80319c",
+        );
+        assert_eq!(
+            url,
+            "https://ui.example.com/trace/0af7651916cd43dd8448eb211c80319c?view=waterfall"
+        );
+        assert_eq!(
+            terminal_hyperlink(&url, "Trace"),
+            format!("\x1b]8;;{}\x1b\\Trace\x1b]8;;\x1b\\", url)
+        );
+    }
+}
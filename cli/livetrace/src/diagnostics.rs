@@ -0,0 +1,197 @@
+//! Detects the suspicious conditions of a displayed trace, to help debug instrumentation bugs.
+//!
+//! After the waterfall and timeline log of each trace, a footnote lists:
+//! - the spans starting before their parent, usually from clock skew between functions or a
+//!   context propagated to the wrong span;
+//! - the spans lasting longer than the maximum Lambda timeout, usually from a span not ended
+//!   before the end of an invocation, or ended with a wrong end time;
+//! - the parent spans referenced by the spans of the trace but not received, either still in
+//!   flight or never exported.
+
+use colored::*;
+use opentelemetry_proto::tonic::trace::v1::Span;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::recorder::console_println;
+use crate::terminal::glyphs;
+
+/// Maximum duration of a Lambda invocation
+const LAMBDA_MAX_TIMEOUT_NS: u64 = 900 * 1_000_000_000;
+
+/// A suspicious condition found in a trace
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    StartsBeforeParent {
+        span_id: String,
+        name: String,
+        skew_ns: u64,
+    },
+    ExceedsLambdaTimeout {
+        span_id: String,
+        name: String,
+        duration_ns: u64,
+    },
+    MissingParent {
+        parent_span_id: String,
+        children: usize,
+    },
+}
+
+impl Diagnostic {
+    fn message(&self) -> String {
+        match self {
+            Diagnostic::StartsBeforeParent {
+                span_id,
+                name,
+                skew_ns,
+            } => format!(
+                "Span '{}' ({}) starts {} before its parent (clock skew?)",
+                name,
+                short_id(span_id),
+                format_ms(*skew_ns)
+            ),
+            Diagnostic::ExceedsLambdaTimeout {
+                span_id,
+                name,
+                duration_ns,
+            } => format!(
+                "Span '{}' ({}) lasts {}, longer than the 15 minute Lambda timeout",
+                name,
+                short_id(span_id),
+                format_ms(*duration_ns)
+            ),
+            Diagnostic::MissingParent {
+                parent_span_id,
+                children,
+            } => format!(
+                "Parent span {} of {} span(s) not received",
+                short_id(parent_span_id),
+                children
+            ),
+        }
+    }
+}
+
+/// Returns the suspicious conditions of the spans of a trace, in order of the span start times
+pub fn diagnose_trace(spans: &[(Span, String)]) -> Vec<Diagnostic> {
+    let span_map: HashMap<&[u8], &Span> = spans
+        .iter()
+        .map(|(span, _)| (span.span_id.as_slice(), span))
+        .collect();
+    let mut ordered: Vec<&Span> = spans.iter().map(|(span, _)| span).collect();
+    ordered.sort_by_key(|span| span.start_time_unix_nano);
+
+    let mut diagnostics = Vec::new();
+    // Ordered by parent span ID, for a stable footnote
+    let mut missing_parents: BTreeMap<String, usize> = BTreeMap::new();
+    for span in ordered {
+        let span_id = hex::encode(&span.span_id);
+        if !span.parent_span_id.is_empty() {
+            match span_map.get(span.parent_span_id.as_slice()) {
+                Some(parent) if span.start_time_unix_nano < parent.start_time_unix_nano => {
+                    diagnostics.push(Diagnostic::StartsBeforeParent {
+                        span_id: span_id.clone(),
+                        name: span.name.clone(),
+                        skew_ns: parent.start_time_unix_nano - span.start_time_unix_nano,
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    *missing_parents
+                        .entry(hex::encode(&span.parent_span_id))
+                        .or_default() += 1;
+                }
+            }
+        }
+        let duration_ns = span
+            .end_time_unix_nano
+            .saturating_sub(span.start_time_unix_nano);
+        if duration_ns > LAMBDA_MAX_TIMEOUT_NS {
+            diagnostics.push(Diagnostic::ExceedsLambdaTimeout {
+                span_id,
+                name: span.name.clone(),
+                duration_ns,
+            });
+        }
+    }
+    diagnostics.extend(
+        missing_parents
+            .into_iter()
+            .map(|(parent_span_id, children)| Diagnostic::MissingParent {
+                parent_span_id,
+                children,
+            }),
+    );
+    diagnostics
+}
+
+/// Prints the diagnostics footnote of a trace, if any
+pub fn print_diagnostics(diagnostics: &[Diagnostic]) {
+    if diagnostics.is_empty() {
+        return;
+    }
+    console_println!("\n{}", "Diagnostics:".yellow().bold());
+    for diagnostic in diagnostics {
+        console_println!(
+            "  {} {}",
+            glyphs().warning.yellow(),
+            diagnostic.message().dimmed()
+        );
+    }
+}
+
+fn short_id(span_id: &str) -> &str {
+    span_id.get(..8).unwrap_or(span_id)
+}
+
+fn format_ms(duration_ns: u64) -> String {
+    format!("{:.2}ms", duration_ns as f64 / 1_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(id: u8, parent: Option<u8>, start_ms: u64, end_ms: u64) -> (Span, String) {
+        let span = Span {
+            span_id: vec![id; 8],
+            parent_span_id: parent.map(|parent| vec![parent; 8]).unwrap_or_default(),
+            name: format!("span-{}", id),
+            start_time_unix_nano: start_ms * 1_000_000,
+            end_time_unix_nano: end_ms * 1_000_000,
+            ..Default::default()
+        };
+        (span, "checkout".to_string())
+    }
+
+    #[test]
+    fn test_diagnose_trace() {
+        let spans = vec![
+            span(1, None, 100, 200),
+            span(2, Some(1), 110, 150),
+            span(3, Some(1), 95, 120),
+            span(4, Some(9), 130, 140),
+            span(5, Some(9), 140, 1_000_000),
+        ];
+        assert_eq!(
+            diagnose_trace(&spans),
+            vec![
+                Diagnostic::StartsBeforeParent {
+                    span_id: "0303030303030303".to_string(),
+                    name: "span-3".to_string(),
+                    skew_ns: 5_000_000,
+                },
+                Diagnostic::ExceedsLambdaTimeout {
+                    span_id: "0505050505050505".to_string(),
+                    name: "span-5".to_string(),
+                    duration_ns: 999_860 * 1_000_000,
+                },
+                Diagnostic::MissingParent {
+                    parent_span_id: "0909090909090909".to_string(),
+                    children: 2,
+                },
+            ]
+        );
+        assert!(diagnose_trace(&spans[..2]).is_empty());
+    }
+}
@@ -0,0 +1,356 @@
+//! Writes each flushed trace to its own JSON file, for viewing without a running collector.
+//!
+//! With `--export-dir`, every trace displayed is also written to `<trace id>.json` in the
+//! directory, in the format selected by `--export-format`:
+//!
+//! - `jaeger`: the JSON of the Jaeger query API, which the Jaeger UI can open from its
+//!   "Upload JSON" search tab.
+//! - `zipkin`: a Zipkin v2 JSON list of spans, which the Zipkin UI can open.
+//! - `otlp`: an OTLP/JSON `ExportTraceServiceRequest`.
+//!
+//! Spans received for a trace after it was flushed are written to `<trace id>-2.json`, and so on.
+
+use anyhow::{Context, Result};
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+use opentelemetry_proto::tonic::trace::v1::{span::SpanKind, status::StatusCode, Span};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::ExportFormat;
+use crate::json_output::{any_value_to_json, merge_batch};
+use crate::processing::{resource_service_name, TelemetryData};
+
+/// Writes traces to files in a directory
+#[derive(Debug)]
+pub struct TraceExporter {
+    dir: PathBuf,
+    format: ExportFormat,
+}
+
+impl TraceExporter {
+    /// Creates the export directory if needed
+    pub fn create(dir: &Path, format: ExportFormat) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create export directory {}", dir.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            format,
+        })
+    }
+
+    /// Writes a trace to a new file named after its id
+    pub fn export(&self, trace_id: &str, batch: &[TelemetryData]) -> Result<PathBuf> {
+        let request = merge_batch(batch);
+        let document = match self.format {
+            ExportFormat::Jaeger => to_jaeger(&request),
+            ExportFormat::Zipkin => to_zipkin(&request),
+            ExportFormat::Otlp => {
+                serde_json::to_value(&request).context("Failed to serialize trace as OTLP/JSON")?
+            }
+        };
+
+        let mut path = self.dir.join(format!("{}.json", trace_id));
+        let mut part = 1;
+        while path.exists() {
+            part += 1;
+            path = self.dir.join(format!("{}-{}.json", trace_id, part));
+        }
+        let contents =
+            serde_json::to_vec_pretty(&document).context("Failed to serialize exported trace")?;
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write exported trace {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+// Spans of a request with the name of their service
+fn spans_with_service(request: &ExportTraceServiceRequest) -> Vec<(&str, &Span)> {
+    request
+        .resource_spans
+        .iter()
+        .flat_map(|resource_spans| {
+            let service = resource_service_name(resource_spans.resource.as_ref())
+                .unwrap_or("unknown-service");
+            resource_spans
+                .scope_spans
+                .iter()
+                .flat_map(|scope_spans| &scope_spans.spans)
+                .map(move |span| (service, span))
+        })
+        .collect()
+}
+
+fn micros(unix_nanos: u64) -> u64 {
+    unix_nanos / 1_000
+}
+
+fn duration_micros(span: &Span) -> u64 {
+    micros(
+        span.end_time_unix_nano
+            .saturating_sub(span.start_time_unix_nano),
+    )
+}
+
+fn status_code(span: &Span) -> StatusCode {
+    span.status.as_ref().map_or(StatusCode::Unset, |status| {
+        StatusCode::try_from(status.code).unwrap_or(StatusCode::Unset)
+    })
+}
+
+// Attribute value as a string, for formats with untyped tags
+fn any_value_to_string(value: Option<&AnyValue>) -> String {
+    match value.and_then(|value| value.value.as_ref()) {
+        Some(any_value::Value::StringValue(s)) => s.clone(),
+        _ => any_value_to_json(value).to_string(),
+    }
+}
+
+fn jaeger_tag(key: &str, value: Option<&AnyValue>) -> Value {
+    let (tag_type, value) = match value.and_then(|value| value.value.as_ref()) {
+        Some(any_value::Value::BoolValue(b)) => ("bool", json!(b)),
+        Some(any_value::Value::IntValue(i)) => ("int64", json!(i)),
+        Some(any_value::Value::DoubleValue(d)) => ("float64", json!(d)),
+        _ => ("string", json!(any_value_to_string(value))),
+    };
+    json!({ "key": key, "type": tag_type, "value": value })
+}
+
+fn jaeger_string_tag(key: &str, value: &str) -> Value {
+    json!({ "key": key, "type": "string", "value": value })
+}
+
+fn jaeger_tags(attributes: &[KeyValue]) -> Vec<Value> {
+    attributes
+        .iter()
+        .map(|attribute| jaeger_tag(&attribute.key, attribute.value.as_ref()))
+        .collect()
+}
+
+/// Converts a trace to the JSON of the Jaeger query API
+fn to_jaeger(request: &ExportTraceServiceRequest) -> Value {
+    let mut processes: BTreeMap<&str, String> = BTreeMap::new();
+    let mut traces: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+    for (service, span) in spans_with_service(request) {
+        let next_process_id = format!("p{}", processes.len() + 1);
+        let process_id = processes.entry(service).or_insert(next_process_id).clone();
+        let trace_id = hex::encode(&span.trace_id);
+        let references: Vec<Value> = if span.parent_span_id.is_empty() {
+            Vec::new()
+        } else {
+            vec![json!({
+                "refType": "CHILD_OF",
+                "traceID": trace_id,
+                "spanID": hex::encode(&span.parent_span_id),
+            })]
+        };
+
+        let mut tags = jaeger_tags(&span.attributes);
+        let kind = SpanKind::try_from(span.kind).unwrap_or(SpanKind::Unspecified);
+        if !matches!(kind, SpanKind::Unspecified | SpanKind::Internal) {
+            tags.push(jaeger_string_tag(
+                "span.kind",
+                &kind
+                    .as_str_name()
+                    .trim_start_matches("SPAN_KIND_")
+                    .to_lowercase(),
+            ));
+        }
+        let status_code = status_code(span);
+        if status_code != StatusCode::Unset {
+            tags.push(jaeger_string_tag(
+                "otel.status_code",
+                status_code.as_str_name().trim_start_matches("STATUS_CODE_"),
+            ));
+        }
+        if status_code == StatusCode::Error {
+            tags.push(json!({ "key": "error", "type": "bool", "value": true }));
+        }
+
+        let logs: Vec<Value> = span
+            .events
+            .iter()
+            .map(|event| {
+                let mut fields = vec![jaeger_string_tag("event", &event.name)];
+                fields.extend(jaeger_tags(&event.attributes));
+                json!({ "timestamp": micros(event.time_unix_nano), "fields": fields })
+            })
+            .collect();
+
+        traces.entry(trace_id.clone()).or_default().push(json!({
+            "traceID": trace_id,
+            "spanID": hex::encode(&span.span_id),
+            "operationName": span.name,
+            "references": references,
+            "startTime": micros(span.start_time_unix_nano),
+            "duration": duration_micros(span),
+            "tags": tags,
+            "logs": logs,
+            "processID": process_id,
+            "warnings": null,
+        }));
+    }
+
+    let processes: Map<String, Value> = processes
+        .into_iter()
+        .map(|(service, process_id)| (process_id, json!({ "serviceName": service, "tags": [] })))
+        .collect();
+    let data: Vec<Value> = traces
+        .into_iter()
+        .map(|(trace_id, spans)| {
+            json!({
+                "traceID": trace_id,
+                "spans": spans,
+                "processes": processes,
+                "warnings": null,
+            })
+        })
+        .collect();
+    json!({ "data": data })
+}
+
+/// Converts a trace to a Zipkin v2 JSON list of spans
+fn to_zipkin(request: &ExportTraceServiceRequest) -> Value {
+    let spans: Vec<Value> = spans_with_service(request)
+        .into_iter()
+        .map(|(service, span)| {
+            let mut tags: Map<String, Value> = span
+                .attributes
+                .iter()
+                .map(|attribute| {
+                    (
+                        attribute.key.clone(),
+                        json!(any_value_to_string(attribute.value.as_ref())),
+                    )
+                })
+                .collect();
+            let status_code = status_code(span);
+            if status_code != StatusCode::Unset {
+                tags.insert(
+                    "otel.status_code".to_string(),
+                    json!(status_code.as_str_name().trim_start_matches("STATUS_CODE_")),
+                );
+            }
+            if status_code == StatusCode::Error {
+                let message = span
+                    .status
+                    .as_ref()
+                    .map(|status| status.message.as_str())
+                    .filter(|message| !message.is_empty())
+                    .unwrap_or("true");
+                tags.insert("error".to_string(), json!(message));
+            }
+
+            let mut zipkin_span = json!({
+                "traceId": hex::encode(&span.trace_id),
+                "id": hex::encode(&span.span_id),
+                "name": span.name,
+                "timestamp": micros(span.start_time_unix_nano),
+                "duration": duration_micros(span),
+                "localEndpoint": { "serviceName": service },
+                "annotations": span
+                    .events
+                    .iter()
+                    .map(|event| json!({ "timestamp": micros(event.time_unix_nano), "value": event.name }))
+                    .collect::<Vec<_>>(),
+                "tags": tags,
+            });
+            if !span.parent_span_id.is_empty() {
+                zipkin_span["parentId"] = json!(hex::encode(&span.parent_span_id));
+            }
+            let kind = match SpanKind::try_from(span.kind) {
+                Ok(SpanKind::Client) => Some("CLIENT"),
+                Ok(SpanKind::Server) => Some("SERVER"),
+                Ok(SpanKind::Producer) => Some("PRODUCER"),
+                Ok(SpanKind::Consumer) => Some("CONSUMER"),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                zipkin_span["kind"] = json!(kind);
+            }
+            zipkin_span
+        })
+        .collect();
+    Value::Array(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Status};
+
+    fn request() -> ExportTraceServiceRequest {
+        let attribute = |key: &str, value: any_value::Value| KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue { value: Some(value) }),
+        };
+        ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource {
+                    attributes: vec![attribute(
+                        "service.name",
+                        any_value::Value::StringValue("checkout".to_string()),
+                    )],
+                    ..Default::default()
+                }),
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![Span {
+                        trace_id: vec![1; 16],
+                        span_id: vec![3; 8],
+                        parent_span_id: vec![2; 8],
+                        name: "charge".to_string(),
+                        kind: SpanKind::Client as i32,
+                        start_time_unix_nano: 2_000_000,
+                        end_time_unix_nano: 5_000_000,
+                        attributes: vec![attribute(
+                            "http.response.status_code",
+                            any_value::Value::IntValue(502),
+                        )],
+                        status: Some(Status {
+                            code: StatusCode::Error as i32,
+                            message: "declined".to_string(),
+                        }),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn test_jaeger_and_zipkin_conversion() {
+        let jaeger = to_jaeger(&request());
+        let trace = &jaeger["data"][0];
+        assert_eq!(trace["traceID"], "01010101010101010101010101010101");
+        assert_eq!(trace["processes"]["p1"]["serviceName"], "checkout");
+        let span = &trace["spans"][0];
+        assert_eq!(span["operationName"], "charge");
+        assert_eq!(span["references"][0]["spanID"], "0202020202020202");
+        assert_eq!(span["startTime"], 2_000);
+        assert_eq!(span["duration"], 3_000);
+        assert_eq!(span["processID"], "p1");
+        assert_eq!(
+            span["tags"],
+            json!([
+                { "key": "http.response.status_code", "type": "int64", "value": 502 },
+                { "key": "span.kind", "type": "string", "value": "client" },
+                { "key": "otel.status_code", "type": "string", "value": "ERROR" },
+                { "key": "error", "type": "bool", "value": true },
+            ])
+        );
+
+        let zipkin = to_zipkin(&request());
+        let span = &zipkin[0];
+        assert_eq!(span["id"], "0303030303030303");
+        assert_eq!(span["parentId"], "0202020202020202");
+        assert_eq!(span["kind"], "CLIENT");
+        assert_eq!(span["localEndpoint"]["serviceName"], "checkout");
+        assert_eq!(span["tags"]["http.response.status_code"], "502");
+        assert_eq!(span["tags"]["error"], "declined");
+    }
+}
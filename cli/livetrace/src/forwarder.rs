@@ -6,14 +6,22 @@
 //!   `ExportTraceServiceRequest` by merging resource spans. This is done before compression
 //!   and sending.
 //! - Sending the (potentially compacted and then gzipped) OTLP payload via HTTP POST
-//!   to the specified OTLP receiver.
+//!   to the specified OTLP receiver, or as a gzipped gRPC `Export` call with `--otlp-protocol grpc`.
 
 use anyhow::{Context, Result};
+use opentelemetry_proto::tonic::collector::trace::v1::trace_service_client::TraceServiceClient;
 use reqwest::{
     header::{HeaderMap, HeaderName, HeaderValue},
     Client as ReqwestClient,
 };
 use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tonic::codec::CompressionEncoding;
+use tonic::metadata::MetadataMap;
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+
+use crate::json_output::merge_batch;
+use crate::metrics::METRICS;
 
 // Need CliArgs for headers
 use crate::processing::{
@@ -84,6 +92,60 @@ pub async fn send_batch(
     Ok(())
 }
 
+/// gRPC client of the OTLP trace service
+pub type GrpcTraceClient = TraceServiceClient<Channel>;
+
+/// Creates a gRPC client for the OTLP endpoint, which connects on its first export.
+pub fn create_grpc_client(endpoint: &str) -> Result<GrpcTraceClient> {
+    let mut grpc_endpoint = Endpoint::from_shared(endpoint.to_string())
+        .context("Invalid OTLP gRPC endpoint URL")?
+        .timeout(Duration::from_secs(30));
+    if endpoint.starts_with("https://") {
+        grpc_endpoint = grpc_endpoint
+            .tls_config(ClientTlsConfig::new().with_webpki_roots())
+            .context("Failed to configure TLS for the OTLP gRPC endpoint")?;
+    }
+    Ok(TraceServiceClient::new(grpc_endpoint.connect_lazy())
+        .send_compressed(CompressionEncoding::Gzip))
+}
+
+/// Sends a batch of telemetry data to the OTLP endpoint over gRPC, merged into one request.
+pub async fn send_batch_grpc(
+    mut client: GrpcTraceClient,
+    batch: Vec<TelemetryData>,
+    headers: HeaderMap,
+) -> Result<()> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+
+    let mut request = tonic::Request::new(merge_batch(&batch));
+    *request.metadata_mut() = MetadataMap::from_headers(headers);
+    tracing::debug!("Sending batch of {} item(s) over gRPC...", batch.len());
+    let started = Instant::now();
+    let result = client.export(request).await;
+    METRICS.record_forward(result.is_ok(), started.elapsed());
+    match result {
+        Ok(response) => {
+            if let Some(partial_success) = response.into_inner().partial_success {
+                if partial_success.rejected_spans > 0 {
+                    tracing::warn!(
+                        rejected_spans = partial_success.rejected_spans,
+                        "OTLP endpoint rejected some spans."
+                    );
+                }
+            }
+            tracing::debug!("OTLP gRPC request sent successfully.");
+        }
+        Err(status) => {
+            tracing::error!(code = ?status.code(), "Failed to send batch over gRPC");
+            // Log and continue
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,4 +210,11 @@ mod tests {
             .to_string()
             .contains("Invalid OTLP header name"));
     }
+
+    #[tokio::test]
+    async fn test_create_grpc_client() {
+        assert!(create_grpc_client("http://localhost:4317").is_ok());
+        assert!(create_grpc_client("https://collector.example.com:4317").is_ok());
+        assert!(create_grpc_client("not a url").is_err());
+    }
 }
@@ -0,0 +1,246 @@
+//! Draws a heatmap of the request latencies of each service over time.
+//!
+//! With `--heatmap`, the entry spans of each service are counted into latency buckets, one column
+//! per second, and the heatmap is redrawn every second with the oldest columns on the left. The
+//! shade of a cell is the share of the requests of the service in that bucket, relative to the
+//! busiest cell of the service, so a shift of the shaded band upwards shows a latency regression,
+//! e.g. while load testing.
+
+use colored::*;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::console_display::get_terminal_width;
+use crate::processing::resource_service_name;
+use crate::recorder::{console_print, console_println};
+use crate::stats::is_entry_span;
+use crate::terminal::glyphs;
+
+/// Upper bounds of the latency buckets, the last bucket holding the slower requests
+const BUCKET_BOUNDS_MS: [f64; 9] = [5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0];
+const BUCKETS: usize = BUCKET_BOUNDS_MS.len() + 1;
+const LABEL_WIDTH: usize = 8;
+
+/// Number of seconds kept, the heatmap shows as many as fit the terminal
+pub const HEATMAP_HISTORY: usize = 300;
+
+type Column = [usize; BUCKETS];
+
+/// Counts of requests per latency bucket of each service, one column per tick
+#[derive(Debug)]
+pub struct LatencyHeatmap {
+    history: usize,
+    current: BTreeMap<String, Column>,
+    columns: BTreeMap<String, VecDeque<Column>>,
+}
+
+impl LatencyHeatmap {
+    pub fn new(history: usize) -> Self {
+        Self {
+            history,
+            current: BTreeMap::new(),
+            columns: BTreeMap::new(),
+        }
+    }
+
+    /// Counts the entry spans of a received request in the current column
+    pub fn record(&mut self, request: &ExportTraceServiceRequest) {
+        for resource_spans in &request.resource_spans {
+            let service = resource_service_name(resource_spans.resource.as_ref())
+                .unwrap_or("unknown-service");
+            for span in resource_spans
+                .scope_spans
+                .iter()
+                .flat_map(|scope_spans| &scope_spans.spans)
+                .filter(|span| is_entry_span(span))
+            {
+                let duration_ms =
+                    span.end_time_unix_nano
+                        .saturating_sub(span.start_time_unix_nano) as f64
+                        / 1_000_000.0;
+                let bucket = BUCKET_BOUNDS_MS
+                    .iter()
+                    .position(|&bound| duration_ms <= bound)
+                    .unwrap_or(BUCKETS - 1);
+                self.current
+                    .entry(service.to_string())
+                    .or_insert([0; BUCKETS])[bucket] += 1;
+            }
+        }
+    }
+
+    /// Closes the current column, dropping the services without requests in the history
+    pub fn tick(&mut self) {
+        let mut current = std::mem::take(&mut self.current);
+        for service in current.keys() {
+            self.columns.entry(service.clone()).or_default();
+        }
+        self.columns.retain(|service, columns| {
+            columns.push_back(current.remove(service).unwrap_or([0; BUCKETS]));
+            while columns.len() > self.history {
+                columns.pop_front();
+            }
+            columns.iter().flatten().any(|&count| count > 0)
+        });
+    }
+
+    /// Renders the heatmap of each service to lines of at most `width` characters
+    pub fn render(&self, width: usize) -> Vec<String> {
+        let visible = width.saturating_sub(LABEL_WIDTH + 2).max(1);
+        let mut lines = Vec::new();
+        for (service, columns) in &self.columns {
+            let shown: Vec<&Column> = columns
+                .iter()
+                .skip(columns.len().saturating_sub(visible))
+                .collect();
+            let requests: usize = shown.iter().copied().flatten().sum();
+            let busiest = shown
+                .iter()
+                .copied()
+                .flatten()
+                .copied()
+                .max()
+                .unwrap_or(0)
+                .max(1);
+            lines.push(format!(
+                "{} {}",
+                service.bold(),
+                format!("({} requests in {}s)", requests, shown.len()).dimmed()
+            ));
+            // The slowest bucket is drawn on top
+            for bucket in (0..BUCKETS).rev() {
+                let cells: String = std::iter::repeat_n(' ', visible - shown.len())
+                    .chain(shown.iter().map(|column| {
+                        let count = column[bucket];
+                        let shades = glyphs().shades;
+                        shades[(count * (shades.len() - 1)).div_ceil(busiest)]
+                    }))
+                    .collect();
+                let cells = match bucket {
+                    0..=4 => cells.green(),
+                    5..=7 => cells.yellow(),
+                    _ => cells.red(),
+                };
+                lines.push(format!(
+                    "{:>LABEL_WIDTH$} {}{}",
+                    bucket_label(bucket).dimmed(),
+                    glyphs().vertical.dimmed(),
+                    cells
+                ));
+            }
+            lines.push(format!(
+                "{:>LABEL_WIDTH$} {}{}",
+                "",
+                glyphs().corner.dimmed(),
+                glyphs().rule.repeat(visible).dimmed()
+            ));
+        }
+        lines
+    }
+
+    /// Clears the terminal and draws the heatmap
+    pub fn print(&self) {
+        console_print!("\x1b[2J\x1b[H");
+        let width = get_terminal_width(80);
+        let heading = "Latency Heatmap (1 column per second)";
+        console_println!(
+            "{} {} {}\n",
+            glyphs().rule.dimmed(),
+            heading.bold(),
+            glyphs()
+                .rule
+                .repeat(width.saturating_sub(heading.len() + 3))
+                .dimmed()
+        );
+        let lines = self.render(width);
+        if lines.is_empty() {
+            console_println!("  {}", "No requests received.".dimmed());
+            return;
+        }
+        for line in lines {
+            console_println!("{}", line);
+        }
+    }
+}
+
+fn bucket_label(bucket: usize) -> String {
+    let format_ms = |ms: f64| {
+        if ms >= 1000.0 {
+            format!("{}s", ms / 1000.0)
+        } else {
+            format!("{}ms", ms)
+        }
+    };
+    match BUCKET_BOUNDS_MS.get(bucket) {
+        Some(&bound) => format!("{}{}", glyphs().at_most, format_ms(bound)),
+        None => format!(">{}", format_ms(BUCKET_BOUNDS_MS[BUCKETS - 2])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+
+    fn request(service: &str, durations_ms: &[u64]) -> ExportTraceServiceRequest {
+        ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource {
+                    attributes: vec![KeyValue {
+                        key: "service.name".to_string(),
+                        value: Some(AnyValue {
+                            value: Some(any_value::Value::StringValue(service.to_string())),
+                        }),
+                    }],
+                    ..Default::default()
+                }),
+                scope_spans: vec![ScopeSpans {
+                    spans: durations_ms
+                        .iter()
+                        .map(|&duration_ms| Span {
+                            end_time_unix_nano: duration_ms * 1_000_000,
+                            ..Default::default()
+                        })
+                        .collect(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn test_latency_heatmap() {
+        let mut heatmap = LatencyHeatmap::new(3);
+        heatmap.record(&request("checkout", &[3, 4, 5, 40]));
+        heatmap.tick();
+        heatmap.record(&request("checkout", &[3000]));
+        heatmap.record(&request("cart", &[1]));
+        heatmap.tick();
+
+        let ansi = regex::Regex::new("\x1b\\[[0-9;]*m").unwrap();
+        let lines: Vec<String> = heatmap
+            .render(LABEL_WIDTH + 2 + 4)
+            .iter()
+            .map(|line| ansi.replace_all(line, "").into_owned())
+            .collect();
+        // A header, the buckets and the time axis of each service, ordered by name
+        assert_eq!(lines.len(), 2 * (BUCKETS + 2));
+        assert_eq!(lines[0], "cart (1 requests in 1s)");
+        assert_eq!(lines[BUCKETS], "    ≤5ms │   █");
+        let checkout = &lines[BUCKETS + 2..];
+        assert_eq!(checkout[0], "checkout (5 requests in 2s)");
+        assert_eq!(checkout[1], "   >2.5s │   ▒");
+        assert_eq!(checkout[BUCKETS - 3], "   ≤50ms │  ▒ ");
+        assert_eq!(checkout[BUCKETS], "    ≤5ms │  █ ");
+        assert_eq!(checkout[BUCKETS + 1], "         └────");
+
+        // Services without requests in the history are dropped
+        heatmap.tick();
+        heatmap.tick();
+        heatmap.tick();
+        assert!(heatmap.render(80).is_empty());
+    }
+}
@@ -0,0 +1,346 @@
+//! Writes each flushed trace to a static HTML report, for sharing in a pull request or ticket.
+//!
+//! With `--html-out`, every trace displayed is also written to `<trace id>.html` in the
+//! directory: a waterfall of its spans, colored by service with the `--theme` palette, where
+//! each span can be expanded to show its attributes and events. The report is rendered from a
+//! template embedded in the binary and has no external dependencies.
+//!
+//! Spans received for a trace after it was flushed are written to `<trace id>-2.html`, and so on.
+
+use anyhow::{Context as _, Result};
+use chrono::{TimeZone, Utc};
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::common::v1::KeyValue;
+use opentelemetry_proto::tonic::trace::v1::{status::StatusCode, Span};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tera::{Context, Tera};
+
+use crate::console_display::{format_span_kind, format_span_status, Theme};
+use crate::json_output::{any_value_to_json, merge_batch};
+use crate::processing::{resource_service_name, TelemetryData};
+
+const TEMPLATE_NAME: &str = "trace.html";
+
+#[derive(Serialize)]
+struct ReportAttribute {
+    key: String,
+    value: String,
+}
+
+#[derive(Serialize)]
+struct ReportEvent {
+    name: String,
+    offset_ms: String,
+    offset_percent: String,
+    attributes: Vec<ReportAttribute>,
+}
+
+#[derive(Serialize)]
+struct ReportSpan {
+    service: String,
+    name: String,
+    span_id: String,
+    parent_span_id: Option<String>,
+    kind: String,
+    status: String,
+    status_message: Option<String>,
+    duration_ms: String,
+    depth: usize,
+    offset_percent: String,
+    width_percent: String,
+    color: String,
+    attributes: Vec<ReportAttribute>,
+    events: Vec<ReportEvent>,
+}
+
+/// Writes traces to HTML reports in a directory
+pub struct HtmlReporter {
+    dir: PathBuf,
+    theme: Theme,
+    tera: Tera,
+}
+
+impl HtmlReporter {
+    /// Creates the report directory if needed
+    pub fn create(dir: &Path, theme: Theme) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create HTML report directory {}", dir.display()))?;
+        let mut tera = Tera::default();
+        tera.add_raw_template(TEMPLATE_NAME, include_str!("templates/trace.html"))
+            .context("Failed to load the HTML report template")?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            theme,
+            tera,
+        })
+    }
+
+    /// Writes a trace to a new report named after its id
+    pub fn write(&self, trace_id: &str, batch: &[TelemetryData]) -> Result<PathBuf> {
+        let html = self.render(trace_id, &merge_batch(batch))?;
+        let mut path = self.dir.join(format!("{}.html", trace_id));
+        let mut part = 1;
+        while path.exists() {
+            part += 1;
+            path = self.dir.join(format!("{}-{}.html", trace_id, part));
+        }
+        fs::write(&path, html)
+            .with_context(|| format!("Failed to write HTML report {}", path.display()))?;
+        Ok(path)
+    }
+
+    fn render(&self, trace_id: &str, request: &ExportTraceServiceRequest) -> Result<String> {
+        let spans: Vec<(&str, &Span)> = request
+            .resource_spans
+            .iter()
+            .flat_map(|resource_spans| {
+                let service = resource_service_name(resource_spans.resource.as_ref())
+                    .unwrap_or("unknown-service");
+                resource_spans
+                    .scope_spans
+                    .iter()
+                    .flat_map(|scope_spans| &scope_spans.spans)
+                    .map(move |span| (service, span))
+            })
+            .collect();
+        let trace_start = spans
+            .iter()
+            .map(|(_, span)| span.start_time_unix_nano)
+            .min()
+            .unwrap_or(0);
+        let trace_end = spans
+            .iter()
+            .map(|(_, span)| span.end_time_unix_nano)
+            .max()
+            .unwrap_or(0);
+        let trace_duration = trace_end.saturating_sub(trace_start).max(1) as f64;
+        let percent = |unix_nano: u64| {
+            (unix_nano.saturating_sub(trace_start) as f64 / trace_duration * 100.0).min(100.0)
+        };
+
+        let report_spans: Vec<ReportSpan> = waterfall_order(&spans)
+            .into_iter()
+            .map(|(depth, service, span)| {
+                let (r, g, b) = self.theme.get_color_for_service(service);
+                let status_code = span.status.as_ref().map_or(StatusCode::Unset, |status| {
+                    StatusCode::try_from(status.code).unwrap_or(StatusCode::Unset)
+                });
+                ReportSpan {
+                    service: service.to_string(),
+                    name: span.name.clone(),
+                    span_id: hex::encode(&span.span_id),
+                    parent_span_id: (!span.parent_span_id.is_empty())
+                        .then(|| hex::encode(&span.parent_span_id)),
+                    kind: format_span_kind(span.kind),
+                    status: format_span_status(status_code),
+                    status_message: span
+                        .status
+                        .as_ref()
+                        .map(|status| status.message.clone())
+                        .filter(|message| !message.is_empty()),
+                    duration_ms: format_millis(
+                        span.end_time_unix_nano
+                            .saturating_sub(span.start_time_unix_nano),
+                    ),
+                    depth,
+                    offset_percent: format!("{:.2}", percent(span.start_time_unix_nano)),
+                    width_percent: format!(
+                        "{:.2}",
+                        percent(span.end_time_unix_nano) - percent(span.start_time_unix_nano)
+                    ),
+                    color: format!("rgb({}, {}, {})", r, g, b),
+                    attributes: report_attributes(&span.attributes),
+                    events: span
+                        .events
+                        .iter()
+                        .map(|event| ReportEvent {
+                            name: event.name.clone(),
+                            offset_ms: format_millis(
+                                event
+                                    .time_unix_nano
+                                    .saturating_sub(span.start_time_unix_nano),
+                            ),
+                            offset_percent: format!("{:.2}", percent(event.time_unix_nano)),
+                            attributes: report_attributes(&event.attributes),
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+
+        let services: BTreeSet<&str> = spans.iter().map(|(service, _)| *service).collect();
+        let mut context = Context::new();
+        context.insert("trace_id", trace_id);
+        context.insert(
+            "start_time",
+            &Utc.timestamp_nanos(trace_start as i64)
+                .format("%Y-%m-%d %H:%M:%S%.3f UTC")
+                .to_string(),
+        );
+        context.insert(
+            "duration_ms",
+            &format_millis(trace_end.saturating_sub(trace_start)),
+        );
+        context.insert("services", &services);
+        context.insert(
+            "error_count",
+            &report_spans
+                .iter()
+                .filter(|span| span.status == "ERROR")
+                .count(),
+        );
+        context.insert("spans", &report_spans);
+        self.tera
+            .render(TEMPLATE_NAME, &context)
+            .context("Failed to render HTML report")
+    }
+}
+
+// Orders the spans depth-first from the roots, children by start time, with their depth. Spans
+// whose parent was not received are shown as roots.
+fn waterfall_order<'a>(spans: &[(&'a str, &'a Span)]) -> Vec<(usize, &'a str, &'a Span)> {
+    let span_ids: HashSet<&[u8]> = spans
+        .iter()
+        .map(|(_, span)| span.span_id.as_slice())
+        .collect();
+    let mut children: HashMap<&[u8], Vec<(&str, &Span)>> = HashMap::new();
+    let mut roots = Vec::new();
+    for &(service, span) in spans {
+        if span_ids.contains(span.parent_span_id.as_slice()) {
+            children
+                .entry(span.parent_span_id.as_slice())
+                .or_default()
+                .push((service, span));
+        } else {
+            roots.push((service, span));
+        }
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|(_, span)| span.start_time_unix_nano);
+    }
+    roots.sort_by_key(|(_, span)| span.start_time_unix_nano);
+
+    let mut ordered = Vec::with_capacity(spans.len());
+    let mut stack: Vec<(usize, &str, &Span)> = roots
+        .into_iter()
+        .rev()
+        .map(|(service, span)| (0, service, span))
+        .collect();
+    while let Some((depth, service, span)) = stack.pop() {
+        ordered.push((depth, service, span));
+        if let Some(siblings) = children.remove(span.span_id.as_slice()) {
+            stack.extend(
+                siblings
+                    .into_iter()
+                    .rev()
+                    .map(|(service, span)| (depth + 1, service, span)),
+            );
+        }
+    }
+    ordered
+}
+
+fn report_attributes(attributes: &[KeyValue]) -> Vec<ReportAttribute> {
+    attributes
+        .iter()
+        .map(|attribute| ReportAttribute {
+            key: attribute.key.clone(),
+            value: match any_value_to_json(attribute.value.as_ref()) {
+                Value::String(value) => value,
+                value => value.to_string(),
+            },
+        })
+        .collect()
+}
+
+fn format_millis(nanos: u64) -> String {
+    format!("{:.2}", nanos as f64 / 1_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue};
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+    use opentelemetry_proto::tonic::trace::v1::{span::Event, ResourceSpans, ScopeSpans};
+    use prost::Message;
+    use tempfile::tempdir;
+
+    fn string_attribute(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value.to_string())),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_html_report() {
+        let request = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource {
+                    attributes: vec![string_attribute("service.name", "checkout")],
+                    ..Default::default()
+                }),
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![
+                        Span {
+                            trace_id: vec![1; 16],
+                            span_id: vec![2; 8],
+                            parent_span_id: vec![1; 8],
+                            name: "charge".to_string(),
+                            start_time_unix_nano: 1_010_000_000,
+                            end_time_unix_nano: 1_060_000_000,
+                            attributes: vec![string_attribute("card", "<visa>")],
+                            events: vec![Event {
+                                name: "declined".to_string(),
+                                time_unix_nano: 1_050_000_000,
+                                ..Default::default()
+                            }],
+                            ..Default::default()
+                        },
+                        Span {
+                            trace_id: vec![1; 16],
+                            span_id: vec![1; 8],
+                            name: "POST /orders".to_string(),
+                            start_time_unix_nano: 1_000_000_000,
+                            end_time_unix_nano: 1_100_000_000,
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                }],
+                schema_url: String::new(),
+            }],
+        };
+        let batch = [TelemetryData {
+            payload: request.encode_to_vec(),
+            original_endpoint: "/v1/traces".to_string(),
+            original_source: "checkout".to_string(),
+        }];
+
+        let dir = tempdir().unwrap();
+        let reporter = HtmlReporter::create(dir.path(), Theme::Default).unwrap();
+        let trace_id = "01".repeat(16);
+        let path = reporter.write(&trace_id, &batch).unwrap();
+        assert_eq!(path, dir.path().join(format!("{}.html", trace_id)));
+        assert_eq!(
+            reporter.write(&trace_id, &batch).unwrap(),
+            dir.path().join(format!("{}-2.html", trace_id))
+        );
+
+        let html = fs::read_to_string(path).unwrap();
+        // The root span is listed before its child, with the child's attributes escaped
+        let root = html.find("<summary>POST &#x2F;orders</summary>").unwrap();
+        let child = html.find("<summary>charge</summary>").unwrap();
+        assert!(root < child);
+        assert!(html.contains("&lt;visa&gt;"));
+        assert!(html.contains("event: declined (+40.00 ms)"));
+        assert!(html.contains("left: 10.00%; width: 50.00%"));
+    }
+}
@@ -0,0 +1,229 @@
+//! Renders flushed traces as JSON lines instead of the colored console view.
+//!
+//! With `--output json`, every span of a trace is printed as one JSON object per line, with its
+//! ids, name, service, kind, status, timing, attributes and events, ready for `jq`. With
+//! `--output otlp-json`, every trace is printed as one line holding an OTLP/JSON
+//! `ExportTraceServiceRequest`, which other OpenTelemetry tools can read back.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat};
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+use opentelemetry_proto::tonic::trace::v1::{span::SpanKind, status::StatusCode, Span};
+use prost::Message;
+use serde_json::{json, Map, Value};
+
+use crate::cli::OutputFormat;
+use crate::processing::TelemetryData;
+use crate::recorder::console_println;
+
+/// Prints the spans of a trace in the given JSON output format
+pub fn print_trace(batch: &[TelemetryData], format: OutputFormat) -> Result<()> {
+    let request = merge_batch(batch);
+    match format {
+        OutputFormat::Console => {}
+        OutputFormat::Json => {
+            for span in spans_to_json(&request) {
+                console_println!("{}", span);
+            }
+        }
+        OutputFormat::OtlpJson => {
+            let line = serde_json::to_string(&request)
+                .context("Failed to serialize trace as OTLP/JSON")?;
+            console_println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+/// Merges the requests of a batch into a single request
+pub(crate) fn merge_batch(batch: &[TelemetryData]) -> ExportTraceServiceRequest {
+    let mut merged = ExportTraceServiceRequest::default();
+    for item in batch {
+        match ExportTraceServiceRequest::decode(item.payload.as_slice()) {
+            Ok(request) => merged.resource_spans.extend(request.resource_spans),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to decode payload for JSON output, skipping item.")
+            }
+        }
+    }
+    merged
+}
+
+/// Converts the spans of a request to flat JSON objects, ordered by start time
+fn spans_to_json(request: &ExportTraceServiceRequest) -> Vec<Value> {
+    let mut spans: Vec<(u64, Value)> = Vec::new();
+    for resource_spans in &request.resource_spans {
+        let resource_attributes = resource_spans
+            .resource
+            .as_ref()
+            .map_or(&[][..], |resource| &resource.attributes);
+        let service = resource_attributes
+            .iter()
+            .find(|attribute| attribute.key == "service.name")
+            .map(|attribute| any_value_to_json(attribute.value.as_ref()));
+        for scope_spans in &resource_spans.scope_spans {
+            let scope = scope_spans.scope.as_ref().map(|scope| scope.name.as_str());
+            for span in &scope_spans.spans {
+                spans.push((
+                    span.start_time_unix_nano,
+                    span_to_json(span, service.clone(), scope),
+                ));
+            }
+        }
+    }
+    spans.sort_by_key(|(start_time, _)| *start_time);
+    spans.into_iter().map(|(_, span)| span).collect()
+}
+
+fn span_to_json(span: &Span, service: Option<Value>, scope: Option<&str>) -> Value {
+    let status = span.status.as_ref();
+    let status_code = status.map_or(StatusCode::Unset, |status| {
+        StatusCode::try_from(status.code).unwrap_or(StatusCode::Unset)
+    });
+    let kind = SpanKind::try_from(span.kind).unwrap_or(SpanKind::Unspecified);
+    let events: Vec<Value> = span
+        .events
+        .iter()
+        .map(|event| {
+            json!({
+                "name": event.name,
+                "time": format_unix_nanos(event.time_unix_nano),
+                "attributes": attributes_to_json(&event.attributes),
+            })
+        })
+        .collect();
+    json!({
+        "trace_id": hex::encode(&span.trace_id),
+        "span_id": hex::encode(&span.span_id),
+        "parent_span_id": (!span.parent_span_id.is_empty()).then(|| hex::encode(&span.parent_span_id)),
+        "name": span.name,
+        "service": service,
+        "scope": scope,
+        "kind": kind.as_str_name().trim_start_matches("SPAN_KIND_"),
+        "status": status_code.as_str_name().trim_start_matches("STATUS_CODE_"),
+        "status_message": status.map(|status| status.message.as_str()).filter(|message| !message.is_empty()),
+        "start_time": format_unix_nanos(span.start_time_unix_nano),
+        "duration_ms": span.end_time_unix_nano.saturating_sub(span.start_time_unix_nano) as f64 / 1_000_000.0,
+        "attributes": attributes_to_json(&span.attributes),
+        "events": events,
+    })
+}
+
+fn format_unix_nanos(unix_nanos: u64) -> String {
+    DateTime::from_timestamp_nanos(unix_nanos as i64).to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+fn attributes_to_json(attributes: &[KeyValue]) -> Value {
+    Value::Object(
+        attributes
+            .iter()
+            .map(|attribute| {
+                (
+                    attribute.key.clone(),
+                    any_value_to_json(attribute.value.as_ref()),
+                )
+            })
+            .collect::<Map<String, Value>>(),
+    )
+}
+
+pub(crate) fn any_value_to_json(value: Option<&AnyValue>) -> Value {
+    match value.and_then(|value| value.value.as_ref()) {
+        Some(any_value::Value::StringValue(s)) => json!(s),
+        Some(any_value::Value::BoolValue(b)) => json!(b),
+        Some(any_value::Value::IntValue(i)) => json!(i),
+        Some(any_value::Value::DoubleValue(d)) => json!(d),
+        Some(any_value::Value::ArrayValue(array)) => Value::Array(
+            array
+                .values
+                .iter()
+                .map(|value| any_value_to_json(Some(value)))
+                .collect(),
+        ),
+        Some(any_value::Value::KvlistValue(list)) => attributes_to_json(&list.values),
+        Some(any_value::Value::BytesValue(bytes)) => json!(hex::encode(bytes)),
+        None => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Status};
+
+    fn string_attribute(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value.to_string())),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_spans_to_json() {
+        let request = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource {
+                    attributes: vec![string_attribute("service.name", "checkout")],
+                    ..Default::default()
+                }),
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![
+                        Span {
+                            trace_id: vec![1; 16],
+                            span_id: vec![3; 8],
+                            parent_span_id: vec![2; 8],
+                            name: "charge".to_string(),
+                            start_time_unix_nano: 2_000_000,
+                            end_time_unix_nano: 5_000_000,
+                            status: Some(Status {
+                                code: StatusCode::Error as i32,
+                                message: "declined".to_string(),
+                            }),
+                            ..Default::default()
+                        },
+                        Span {
+                            trace_id: vec![1; 16],
+                            span_id: vec![2; 8],
+                            name: "GET /cart".to_string(),
+                            kind: SpanKind::Server as i32,
+                            start_time_unix_nano: 1_000_000,
+                            end_time_unix_nano: 10_500_000,
+                            attributes: vec![string_attribute("http.route", "/cart")],
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+
+        let spans = spans_to_json(&request);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(
+            spans[0],
+            json!({
+                "trace_id": "01010101010101010101010101010101",
+                "span_id": "0202020202020202",
+                "parent_span_id": null,
+                "name": "GET /cart",
+                "service": "checkout",
+                "scope": null,
+                "kind": "SERVER",
+                "status": "UNSET",
+                "status_message": null,
+                "start_time": "1970-01-01T00:00:00.001000000Z",
+                "duration_ms": 9.5,
+                "attributes": { "http.route": "/cart" },
+                "events": [],
+            })
+        );
+        assert_eq!(spans[1]["parent_span_id"], "0202020202020202");
+        assert_eq!(spans[1]["status"], "ERROR");
+        assert_eq!(spans[1]["status_message"], "declined");
+    }
+}
@@ -9,30 +9,51 @@
 // Module declarations
 pub mod aws_setup;
 pub mod cli;
+pub mod color_rules;
 pub mod config;
 pub mod console_display;
+pub mod diagnostics;
+pub mod export;
 pub mod forwarder;
+pub mod heatmap;
+pub mod html_report;
+pub mod json_output;
 pub mod live_tail_adapter;
+pub mod log_correlation;
+pub mod log_group_refresh;
+pub mod metrics;
+pub mod notifier;
 pub mod poller;
 pub mod processing;
+pub mod recorder;
+pub mod service_map;
+pub mod session;
+pub mod stats;
+pub mod store;
+pub mod terminal;
 
 // Standard Library
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 
 // External Crates
 use anyhow::{Context, Result};
+use clap::ValueEnum;
 use colored::*;
 use futures::future::join_all;
+use globset::GlobSet;
 use indicatif::{ProgressBar, ProgressStyle};
 use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
 use prost::Message;
 use regex::Regex;
+use reqwest::header::HeaderMap;
 use reqwest::Client as ReqwestClient;
 use tokio::sync::mpsc;
-use tokio::time::{interval, Instant};
+use tokio::time::{interval, interval_at, Instant};
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
@@ -42,30 +63,232 @@ use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 // Ensure these items are public in their respective modules.
 use aws_setup::setup_aws_resources;
 use cli::{
-    parse_attr_globs, ColoringMode, AVAILABLE_THEMES_INFO, DEFAULT_COLOR_BY, DEFAULT_EVENTS_ONLY,
-    DEFAULT_EVENT_SEVERITY_ATTRIBUTE, DEFAULT_SESSION_TIMEOUT_MS, DEFAULT_TRACE_STRAGGLERS_WAIT_MS,
+    parse_attr_globs, ColoringMode, OtlpProtocol, OutputFormat, AVAILABLE_THEMES_INFO,
+    DEFAULT_COLOR_BY, DEFAULT_EVENTS_ONLY, DEFAULT_EVENT_SEVERITY_ATTRIBUTE,
+    DEFAULT_SESSION_TIMEOUT_MS, DEFAULT_STATS_INTERVAL_MS, DEFAULT_TRACE_STRAGGLERS_WAIT_MS,
     DEFAULT_TRACE_TIMEOUT_MS,
 };
 pub use cli::{CliArgs, Commands};
+use color_rules::{parse_color_rules, ColorRule};
 use config::{
     format_millis_to_duration_string, load_and_resolve_config, load_or_default_config_file,
     merge_into_profile_config, save_profile_config, EffectiveConfig, ProfileConfig,
 };
-use console_display::{display_console, get_terminal_width, Theme};
-use forwarder::{parse_otlp_headers_from_vec, send_batch};
+use console_display::{display_console, get_terminal_height, get_terminal_width, Theme};
+use export::TraceExporter;
+use forwarder::{
+    create_grpc_client, parse_otlp_headers_from_vec, send_batch, send_batch_grpc, GrpcTraceClient,
+};
+use heatmap::{LatencyHeatmap, HEATMAP_HISTORY};
+use html_report::HtmlReporter;
 use live_tail_adapter::start_live_tail_task;
-use poller::start_polling_task;
-use processing::{SpanCompactionConfig, TelemetryData};
+use log_correlation::CorrelatedLog;
+use log_group_refresh::{start_log_group_refresh_task, RefreshSettings, RefreshedAccount};
+use metrics::{start_metrics_server, DEFAULT_METRICS_ADDR};
+use notifier::{find_notification, ErrorNotifier};
+use poller::{start_polling_task, ApiBudget};
+use processing::{
+    parse_exclude_attrs, SpanCompactionConfig, TelemetryData, TraceFilter, TraceSampler,
+};
+use recorder::{console_print, console_println, DEFAULT_TERMINAL_HEIGHT};
+use service_map::ServiceMap;
+use session::{is_session_file, read_session, start_replay_task, SessionWriter};
+use stats::{print_stats, RedStats, STATS_WINDOW};
+use store::{StoreQuery, TraceStore};
+use terminal::{configure_terminal, glyphs, no_color_env};
 
 // Structure to hold state for traces being buffered
 #[derive(Debug)]
 struct TraceBufferState {
     buffered_payloads: Vec<TelemetryData>,
+    correlated_logs: Vec<CorrelatedLog>,
     has_received_root: bool,
+    // IDs of the buffered spans and of their parents, to tell when the tree is assembled
+    span_ids: HashSet<Vec<u8>>,
+    parent_span_ids: HashSet<Vec<u8>>,
     first_message_received_at: Instant,
     last_message_received_at: Instant,
 }
 
+impl TraceBufferState {
+    fn new(now: Instant) -> Self {
+        Self {
+            buffered_payloads: Vec::new(),
+            correlated_logs: Vec::new(),
+            has_received_root: false,
+            span_ids: HashSet::new(),
+            parent_span_ids: HashSet::new(),
+            first_message_received_at: now,
+            last_message_received_at: now,
+        }
+    }
+
+    /// Buffers a batch of spans of the trace
+    fn push(
+        &mut self,
+        telemetry: TelemetryData,
+        request: &ExportTraceServiceRequest,
+        now: Instant,
+    ) {
+        for span in request
+            .resource_spans
+            .iter()
+            .flat_map(|resource_spans| &resource_spans.scope_spans)
+            .flat_map(|scope_spans| &scope_spans.spans)
+        {
+            if span.parent_span_id.is_empty() {
+                self.has_received_root = true;
+            } else {
+                self.parent_span_ids.insert(span.parent_span_id.clone());
+            }
+            self.span_ids.insert(span.span_id.clone());
+        }
+        self.buffered_payloads.push(telemetry);
+        self.last_message_received_at = now;
+    }
+
+    /// Returns true once the root span and the parents of all the buffered spans were received,
+    /// so the trace renders as a single tree
+    fn is_assembled(&self) -> bool {
+        self.has_received_root
+            && self
+                .parent_span_ids
+                .iter()
+                .all(|parent_span_id| self.span_ids.contains(parent_span_id))
+    }
+}
+
+// Display and forwarding settings applied to the traces flushed from the buffer
+struct FlushContext<'a> {
+    config: &'a EffectiveConfig,
+    console_enabled: bool,
+    output: OutputFormat,
+    attr_globs: &'a Option<GlobSet>,
+    color_rules: &'a [ColorRule],
+    grep_regex: Option<&'a Regex>,
+    trace_filter: &'a TraceFilter,
+    exporter: Option<&'a TraceExporter>,
+    html_reporter: Option<&'a HtmlReporter>,
+    endpoint: Option<&'a str>,
+    http_client: &'a ReqwestClient,
+    grpc_client: Option<&'a GrpcTraceClient>,
+    otlp_header_map: &'a HeaderMap,
+    compaction_config: &'a SpanCompactionConfig,
+}
+
+/// Displays and forwards the given buffered traces, removing them from the buffer, and adds
+/// them to the service map if one is built.
+async fn flush_traces(
+    trace_buffers: &mut HashMap<String, TraceBufferState>,
+    trace_ids_to_flush: &[String],
+    context: &FlushContext<'_>,
+    mut service_map: Option<&mut ServiceMap>,
+    spinner: &ProgressBar,
+) -> Result<()> {
+    let mut futures_vec = Vec::new();
+    for trace_id in trace_ids_to_flush {
+        let Some(mut state) = trace_buffers.remove(trace_id) else {
+            continue;
+        };
+        if state.buffered_payloads.is_empty() {
+            tracing::debug!(trace_id, "No spans received for correlated logs, skipping.");
+            continue;
+        }
+        state.buffered_payloads = context.trace_filter.exclude_spans(state.buffered_payloads);
+        if state.buffered_payloads.is_empty() {
+            tracing::debug!(trace_id, "All spans of the trace are excluded, skipping.");
+            continue;
+        }
+        if !context.trace_filter.matches(&state.buffered_payloads) {
+            tracing::debug!(
+                trace_id,
+                "Trace does not match the trace filters, skipping."
+            );
+            continue;
+        }
+        spinner.set_message(format!("Flushing trace {}...", &trace_id[..8]));
+        if let Some(service_map) = service_map.as_deref_mut() {
+            service_map.record(&state.buffered_payloads);
+        }
+        if context.console_enabled && context.output != OutputFormat::Console {
+            json_output::print_trace(&state.buffered_payloads, context.output)?;
+        } else if context.console_enabled {
+            display_console(
+                &state.buffered_payloads,
+                context.attr_globs,
+                &context.config.event_severity_attribute,
+                context.config.theme,
+                context.config.color_by,
+                context.config.events_only,
+                state.has_received_root,
+                context.grep_regex,
+                &state.correlated_logs,
+                context.color_rules,
+                context.config.link_template.as_deref(),
+            )?;
+        }
+        if let Some(exporter) = context.exporter {
+            match exporter.export(trace_id, &state.buffered_payloads) {
+                Ok(path) => tracing::debug!(trace_id, path = %path.display(), "Exported trace."),
+                Err(e) => tracing::warn!(trace_id, error = %e, "Failed to export trace."),
+            }
+        }
+        if let Some(html_reporter) = context.html_reporter {
+            match html_reporter.write(trace_id, &state.buffered_payloads) {
+                Ok(path) => {
+                    tracing::debug!(trace_id, path = %path.display(), "Wrote HTML report.")
+                }
+                Err(e) => tracing::warn!(trace_id, error = %e, "Failed to write HTML report."),
+            }
+        }
+
+        if let Some(grpc_client) = context.grpc_client {
+            futures_vec.push(tokio::spawn(send_batch_grpc(
+                grpc_client.clone(),
+                state.buffered_payloads,
+                context.otlp_header_map.clone(),
+            )));
+        } else if let Some(endpoint_url) = context.endpoint {
+            let client_clone = context.http_client.clone();
+            let endpoint_clone = endpoint_url.to_string();
+            let headers_clone = context.otlp_header_map.clone();
+            let compaction_config_clone = context.compaction_config.clone(); // Clone for the async task
+
+            futures_vec.push(tokio::spawn(async move {
+                send_batch(
+                    &client_clone,
+                    &endpoint_clone,
+                    state.buffered_payloads,
+                    &compaction_config_clone,
+                    headers_clone,
+                )
+                .await
+            }));
+        }
+        spinner.set_message("Waiting for telemetry events...");
+    }
+    join_all(futures_vec).await; // Wait for all forwarding tasks to complete
+    Ok(())
+}
+
+/// Prints the service map and writes it to its DOT file, as requested.
+fn output_service_map(service_map: &ServiceMap, print: bool, dot_path: Option<&Path>) {
+    if print {
+        service_map.print();
+    }
+    if let Some(path) = dot_path {
+        if let Err(e) = service_map.write_dot(path) {
+            tracing::warn!(error = %e, "Failed to write the service map DOT file.");
+        }
+    }
+}
+
+/// Returns the address the Prometheus metrics are served on, on the loopback interface unless
+/// another address is configured.
+fn metrics_socket_addr(config: &EffectiveConfig, port: u16) -> SocketAddr {
+    SocketAddr::new(config.metrics_addr.unwrap_or(DEFAULT_METRICS_ADDR), port)
+}
+
 /// Main entry point for the livetrace application logic.
 ///
 /// This function takes the parsed command-line arguments and executes the
@@ -109,8 +332,11 @@ pub async fn run_livetrace(args: CliArgs) -> Result<()> {
         EffectiveConfig {
             log_group_pattern: args.log_group_pattern.clone(),
             stack_name: args.stack_name.clone(),
+            tags: args.tags.clone(),
+            role_arns: args.role_arns.clone(),
             otlp_endpoint: args.otlp_endpoint.clone(),
             otlp_headers: args.otlp_headers.clone(),
+            otlp_protocol: args.otlp_protocol,
             aws_region: args.aws_region.clone(),
             aws_profile: args.aws_profile.clone(),
             forward_only: args.forward_only,
@@ -120,6 +346,10 @@ pub async fn run_livetrace(args: CliArgs) -> Result<()> {
                 .clone()
                 .unwrap_or_else(|| DEFAULT_EVENT_SEVERITY_ATTRIBUTE.to_string()),
             poll_interval_ms: args.poll_interval,
+            max_api_calls_per_minute: args.max_api_calls_per_minute,
+            metrics_port: args.metrics_port,
+            metrics_addr: args.metrics_addr,
+            sample_ratio: args.sample_ratio,
             session_timeout_ms: args.session_timeout.unwrap_or(DEFAULT_SESSION_TIMEOUT_MS),
             verbose: args.verbose,
             theme: args.theme.unwrap_or(Theme::Default),
@@ -129,8 +359,28 @@ pub async fn run_livetrace(args: CliArgs) -> Result<()> {
             trace_stragglers_wait_ms: args
                 .trace_stragglers_wait
                 .unwrap_or(DEFAULT_TRACE_STRAGGLERS_WAIT_MS),
+            stats: args.stats,
+            stats_interval_ms: args.stats_interval,
+            refresh_log_groups_ms: args.refresh_log_groups,
+            service_map: args.service_map,
+            heatmap: args.heatmap,
+            correlate_logs: args.correlate_logs,
+            notify_on_error: args.notify_on_error,
+            notify_webhook: args.notify_webhook.clone(),
             grep: args.grep.clone(),
             backtrace_ms: args.backtrace,
+            service: args.service.clone(),
+            span_name: args.span_name.clone(),
+            status: args.status,
+            min_duration_ms: args.min_duration,
+            exclude_span_names: args.exclude_span_names.clone(),
+            exclude_services: args.exclude_services.clone(),
+            exclude_attrs: args.exclude_attrs.clone(),
+            color_rules: args.color_rules.clone(),
+            link_template: args.link_template.clone(),
+            min_level: args.min_level,
+            no_color: args.no_color,
+            ascii: args.ascii,
         }
     };
 
@@ -153,13 +403,60 @@ pub async fn run_livetrace(args: CliArgs) -> Result<()> {
         None
     };
 
+    // Load the session to replay, or the stored traces to re-render, if any
+    let replay = match &args.command {
+        Some(Commands::Replay { file, no_timing }) => {
+            Some((file.clone(), read_session(file)?, !*no_timing))
+        }
+        Some(Commands::Query {
+            file,
+            service,
+            status,
+            min_duration,
+            since,
+            from,
+            to,
+            limit,
+        }) => {
+            let now_unix_nano = chrono::Utc::now()
+                .timestamp_nanos_opt()
+                .map_or(0, |nanos| nanos as u64);
+            let query = StoreQuery {
+                service: service.clone(),
+                status: *status,
+                min_duration_ms: *min_duration,
+                from_unix_nano: since
+                    .map(|since_ms| now_unix_nano.saturating_sub(since_ms * 1_000_000))
+                    .or(*from),
+                to_unix_nano: *to,
+                limit: *limit,
+            };
+            let traces = TraceStore::open_read_only(file)?.query(&query)?;
+            let batches = traces
+                .into_iter()
+                .flatten()
+                .map(|telemetry| (Duration::ZERO, telemetry))
+                .collect();
+            Some((file.clone(), batches, false))
+        }
+        _ => None,
+    };
+
     // Validate discovery parameters
-    if config.log_group_pattern.is_none() && config.stack_name.is_none() {
+    if replay.is_none()
+        && config.log_group_pattern.is_none()
+        && config.stack_name.is_none()
+        && config.tags.is_none()
+        && config.role_arns.is_none()
+    {
         return Err(anyhow::anyhow!(
-            "Either --log-group-pattern or --stack-name must be provided on the command line or in the configuration profile"
+            "One of --log-group-pattern, --stack-name, --tag or --role-arn must be provided on the command line or in the configuration profile"
         ));
     }
 
+    // Adapt the console output to the terminal
+    configure_terminal(config.no_color, config.ascii);
+
     // Initialize Logging
     let log_level = match config.verbose {
         0 => LevelFilter::INFO,
@@ -167,7 +464,11 @@ pub async fn run_livetrace(args: CliArgs) -> Result<()> {
         _ => LevelFilter::TRACE,
     };
     tracing_subscriber::registry()
-        .with(fmt::layer())
+        .with(
+            fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_ansi(!config.no_color && !no_color_env()),
+        )
         .with(
             EnvFilter::builder()
                 .with_default_directive(LevelFilter::WARN.into())
@@ -209,6 +510,24 @@ pub async fn run_livetrace(args: CliArgs) -> Result<()> {
         Vec::new()
     };
 
+    // Resolve OTLP Protocol
+    let otlp_protocol = config
+        .otlp_protocol
+        .or_else(|| {
+            let (variable, value) = [
+                "OTEL_EXPORTER_OTLP_TRACES_PROTOCOL",
+                "OTEL_EXPORTER_OTLP_PROTOCOL",
+            ]
+            .into_iter()
+            .find_map(|variable| Some((variable, env::var(variable).ok()?)))?;
+            OtlpProtocol::from_str(value.trim(), true)
+                .inspect_err(|_| {
+                    tracing::warn!(variable, "Unsupported OTLP protocol, using http/protobuf.")
+                })
+                .ok()
+        })
+        .unwrap_or_default();
+
     // Post-Resolution Validation
     if config.forward_only && endpoint_opt.is_none() {
         return Err(anyhow::anyhow!("
@@ -217,19 +536,30 @@ pub async fn run_livetrace(args: CliArgs) -> Result<()> {
     if !config.forward_only && endpoint_opt.is_none() {
         tracing::debug!("Running in console-only mode. No OTLP endpoint configured.");
     }
+    let record_session_path = args.record.as_deref().filter(|path| is_session_file(path));
+    let record_console_path = args.record.as_deref().filter(|path| !is_session_file(path));
+    if config.forward_only && record_console_path.is_some() {
+        return Err(anyhow::anyhow!(
+            "--forward-only cannot record the console output; use a .jsonl --record file to record the telemetry instead"
+        ));
+    }
 
-    // AWS Setup
-    let aws_result = setup_aws_resources(
-        &config.log_group_pattern,
-        &config.stack_name,
-        &config.aws_region,
-        &config.aws_profile,
-    )
-    .await?;
-    let cwl_client = aws_result.cwl_client;
-    let account_id = aws_result.account_id;
-    let region_str = aws_result.region_str;
-    let resolved_log_group_arns = aws_result.resolved_arns;
+    // AWS Setup (not needed to replay a session)
+    let aws_result = if replay.is_none() {
+        Some(
+            setup_aws_resources(
+                &config.log_group_pattern,
+                &config.stack_name,
+                &config.tags,
+                &config.role_arns,
+                &config.aws_region,
+                &config.aws_profile,
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
 
     // Setup HTTP Client & Parse Resolved OTLP Headers
     let http_client = ReqwestClient::builder()
@@ -238,222 +568,580 @@ pub async fn run_livetrace(args: CliArgs) -> Result<()> {
         .context("Failed to build Reqwest client")?;
     tracing::debug!("Reqwest HTTP client created with 30s timeout.");
     let otlp_header_map = parse_otlp_headers_from_vec(&resolved_headers_vec)?;
+    if config.notify_webhook.is_some() && !config.notify_on_error {
+        tracing::warn!("--notify-webhook requires --notify-on-error, ignoring it.");
+    }
+    let mut error_notifier = config
+        .notify_on_error
+        .then(|| ErrorNotifier::new(true, config.notify_webhook.clone(), http_client.clone()));
+    let grpc_client = match (otlp_protocol, endpoint_opt) {
+        (OtlpProtocol::Grpc, Some(endpoint)) => Some(create_grpc_client(endpoint)?),
+        _ => None,
+    };
     let compaction_config = SpanCompactionConfig::default();
 
     // Prepare Console Display
     let console_enabled = !config.forward_only;
     let attr_globs = parse_attr_globs(&config.attrs);
-
-    // Preamble Output
-    let preamble_width: usize = get_terminal_width(80);
-    let config_heading = "Livetrace Configuration";
-    let config_padding = preamble_width.saturating_sub(config_heading.len() + 3);
-
-    println!("\n");
-    println!(
-        "{} {} {}\n",
-        "─".dimmed(),
-        config_heading.bold(),
-        "─".repeat(config_padding).dimmed()
-    );
-    println!("  {:<18}: {}", "AWS Account ID".dimmed(), account_id);
-    println!("  {:<18}: {}", "AWS Region".dimmed(), region_str);
-    if let Some(profile) = &config.aws_profile {
-        println!("  {:<18}: {}", "AWS Profile".dimmed(), profile);
-    }
-    if let Some(patterns) = &config.log_group_pattern {
-        println!("  {:<18}: {:?}", "Pattern".dimmed(), patterns);
-    }
-    if let Some(stack) = &config.stack_name {
-        println!("  {:<18}: {}", "CloudFormation".dimmed(), stack);
+    let color_rules = parse_color_rules(config.color_rules.as_deref().unwrap_or_default())?;
+    if config
+        .link_template
+        .as_ref()
+        .is_some_and(|link_template| !link_template.contains("{trace_id}"))
+    {
+        return Err(anyhow::anyhow!(
+            "--link-template must contain the {{trace_id}} placeholder"
+        ));
     }
-    println!();
-    if let Some(poll_interval_value_ms) = config.poll_interval_ms {
-        println!("  {:<18}: Polling", "Mode".dimmed());
-        println!(
-            "  {:<18}: {}",
-            "Poll Interval".dimmed(),
-            format_millis_to_duration_string(poll_interval_value_ms)
-        );
-    } else {
-        println!("  {:<18}: Live Tail", "Mode".dimmed());
+    let trace_filter = TraceFilter {
+        service: config.service.clone(),
+        span_name: config.span_name.clone(),
+        status: config.status,
+        min_duration_ms: config.min_duration_ms,
+        exclude_span_names: config.exclude_span_names.clone().unwrap_or_default(),
+        exclude_services: config.exclude_services.clone().unwrap_or_default(),
+        exclude_attrs: parse_exclude_attrs(config.exclude_attrs.as_deref().unwrap_or_default())?,
+    };
+    let trace_sampler = config.sample_ratio.map(TraceSampler::new);
+
+    // Prepare RED Metrics Summary
+    let stats_interval_ms = config
+        .stats_interval_ms
+        .or(config.stats.then_some(DEFAULT_STATS_INTERVAL_MS));
+    if stats_interval_ms.is_some() && args.output != OutputFormat::Console {
+        tracing::warn!("The stats summary is only printed with the console output, ignoring it.");
     }
-    println!(
-        "  {:<18}: {}",
-        "Session Timeout".dimmed(),
-        format_millis_to_duration_string(config.session_timeout_ms)
-    );
-    println!(
-        "  {:<18}: {}",
-        "Forward Only".dimmed(),
-        if config.forward_only { "Yes" } else { "No" }
-    );
-    if let Some(endpoint) = &resolved_endpoint {
-        println!("  {:<18}: {}", "OTLP Endpoint".dimmed(), endpoint);
-    } else {
-        println!("  {:<18}: Not configured", "OTLP Endpoint".dimmed());
+    let mut red_stats = stats_interval_ms
+        .filter(|_| args.output == OutputFormat::Console)
+        .map(|_| RedStats::new(STATS_WINDOW));
+
+    // Prepare Latency Heatmap
+    if config.heatmap && args.output != OutputFormat::Console {
+        tracing::warn!("The latency heatmap is only drawn with the console output, ignoring it.");
     }
-    if !resolved_headers_vec.is_empty() {
-        println!(
-            "  {:<18}: {} headers",
-            "OTLP Headers".dimmed(),
-            resolved_headers_vec.len()
-        );
+    let mut latency_heatmap = (config.heatmap && args.output == OutputFormat::Console)
+        .then(|| LatencyHeatmap::new(HEATMAP_HISTORY));
+
+    // Prepare the API call budget of polling mode
+    if config.max_api_calls_per_minute.is_some() && config.poll_interval_ms.is_none() {
+        tracing::warn!("--max-api-calls-per-minute only applies to polling mode (--poll-interval), ignoring it.");
     }
-    println!("  {:<18}: {}", "Theme".dimmed(), config.theme);
-    println!(
-        "  {:<18}: {}",
-        "Color By".dimmed(),
-        match config.color_by {
-            ColoringMode::Service => "Service",
-            ColoringMode::Span => "Span ID",
+    let (api_budget, mut api_throttled_rx) = match config
+        .max_api_calls_per_minute
+        .filter(|_| config.poll_interval_ms.is_some())
+    {
+        Some(max_calls) => {
+            let (budget, throttled_rx) = ApiBudget::new(max_calls);
+            (Some(Arc::new(budget)), Some(throttled_rx))
         }
-    );
-    if let Some(attrs) = &config.attrs {
-        println!("  {:<18}: {}", "Attributes".dimmed(), attrs);
-    } else {
-        println!("  {:<18}: All", "Attributes".dimmed());
+        None => (None, None),
+    };
+
+    if config.correlate_logs && args.output != OutputFormat::Console {
+        tracing::warn!("Correlated logs are only shown with the console output.");
     }
-    println!(
-        "  {:<18}: {}",
-        "Severity Attr".dimmed(),
-        config.event_severity_attribute
-    );
-    println!(
-        "  {:<18}: {}",
-        "Events Only".dimmed(),
-        if config.events_only { "Yes" } else { "No" }
-    );
-    println!(
-        "  {:<18}: {} seconds",
-        "Trace Timeout".dimmed(),
-        config.trace_timeout_ms / 1000 // Display as seconds for readability
-    );
-    println!(
-        "  {:<18}: {}",
-        "Stragglers Wait".dimmed(), // New preamble line
-        format_millis_to_duration_string(config.trace_stragglers_wait_ms)  // Use formatter
-    );
-    if let Some(profile) = &args.config_profile {
-        // Use args here as config doesn't store it
-        println!("  {:<18}: {}", "Config Profile".dimmed(), profile);
+
+    // Prepare Trace Export
+    let exporter = args
+        .export_dir
+        .as_deref()
+        .map(|dir| TraceExporter::create(dir, args.export_format))
+        .transpose()?;
+    let html_reporter = args
+        .html_out
+        .as_deref()
+        .map(|dir| HtmlReporter::create(dir, config.theme))
+        .transpose()?;
+
+    // Prepare Service Map
+    let mut service_map =
+        (config.service_map || args.service_map_dot.is_some()).then(ServiceMap::default);
+    if config.service_map && args.output != OutputFormat::Console {
+        tracing::warn!("The service map is only printed with the console output.");
     }
-    let verbosity_str = match config.verbose {
-        0 => "Normal",
-        1 => "Debug (-v)",
-        _ => {
-            let v_str = format!("Trace (-v{})", "v".repeat(config.verbose as usize - 1));
-            Box::leak(v_str.into_boxed_str())
+    let print_service_map = config.service_map && args.output == OutputFormat::Console;
+
+    // Start Session Recording
+    if let Some(record_path) = record_console_path {
+        recorder::start_recording(
+            record_path,
+            get_terminal_width(80),
+            get_terminal_height(DEFAULT_TERMINAL_HEIGHT),
+        )?;
+        tracing::debug!(path = %record_path.display(), "Recording console output.");
+    }
+    let mut session_writer = match record_session_path {
+        Some(record_path) => {
+            tracing::debug!(path = %record_path.display(), "Recording received telemetry.");
+            Some(SessionWriter::create(record_path)?)
         }
+        None => None,
     };
-    println!("  {:<18}: {}", "Verbosity".dimmed(), verbosity_str);
-    println!();
-    let validated_log_group_names_for_display: Vec<String> = resolved_log_group_arns
-        .iter()
-        .map(|arn| {
-            arn.split(':')
-                .next_back()
-                .unwrap_or("unknown-name")
-                .to_string()
-        })
-        .collect();
-    print!("  {:<18}: ", "Log Groups".dimmed());
-    if let Some((first, rest)) = validated_log_group_names_for_display.split_first() {
-        println!("{}", first);
-        for name in rest {
-            println!("{:<22}{}", "", name);
+    let mut trace_store = match &args.store {
+        Some(store_path) => {
+            tracing::debug!(path = %store_path.display(), "Storing received spans.");
+            Some(TraceStore::open(store_path)?)
         }
-    } else {
-        println!("None");
+        None => None,
+    };
+    if let Some(port) = config.metrics_port {
+        start_metrics_server(metrics_socket_addr(&config, port)).await?;
     }
-    println!("\n");
 
-    // Create MPSC Channel and Spawn Event Source Task
-    let (tx, mut rx) = mpsc::channel::<Result<TelemetryData>>(100);
-    let task_tx = tx.clone(); // Clone the sender for the task that will produce events
+    // Preamble Output
+    if args.output == OutputFormat::Console {
+        let preamble_width: usize = get_terminal_width(80);
+        let config_heading = "Livetrace Configuration";
+        let config_padding = preamble_width.saturating_sub(config_heading.len() + 3);
 
-    if let Some(interval_secs) = config.poll_interval_ms {
-        tracing::debug!(
-            interval = interval_secs,
-            backtrace_s = ?config.backtrace_ms,
-            "Using FilterLogEvents polling mode."
+        console_println!("\n");
+        console_println!(
+            "{} {} {}\n",
+            glyphs().rule.dimmed(),
+            config_heading.bold(),
+            glyphs().rule.repeat(config_padding).dimmed()
+        );
+        if let Some(aws) = &aws_result {
+            console_println!("  {:<18}: {}", "AWS Account ID".dimmed(), aws.account_id);
+            console_println!("  {:<18}: {}", "AWS Region".dimmed(), aws.region_str);
+        }
+        if let Some(profile) = &config.aws_profile {
+            console_println!("  {:<18}: {}", "AWS Profile".dimmed(), profile);
+        }
+        if let Some(patterns) = &config.log_group_pattern {
+            console_println!("  {:<18}: {:?}", "Pattern".dimmed(), patterns);
+        }
+        if let Some(stacks) = &config.stack_name {
+            console_println!("  {:<18}: {:?}", "CloudFormation".dimmed(), stacks);
+        }
+        if let Some(tags) = &config.tags {
+            console_println!("  {:<18}: {:?}", "Tags".dimmed(), tags);
+        }
+        if let Some(role_arns) = &config.role_arns {
+            console_println!("  {:<18}: {:?}", "Roles".dimmed(), role_arns);
+        }
+        console_println!();
+        if let (Some((file, batches, _)), Some(Commands::Query { .. })) = (&replay, &args.command) {
+            console_println!("  {:<18}: Query", "Mode".dimmed());
+            console_println!("  {:<18}: {}", "Trace Store".dimmed(), file.display());
+            console_println!("  {:<18}: {}", "Spans".dimmed(), batches.len());
+        } else if let Some((file, batches, original_timing)) = &replay {
+            console_println!("  {:<18}: Replay", "Mode".dimmed());
+            console_println!("  {:<18}: {}", "Session File".dimmed(), file.display());
+            console_println!("  {:<18}: {}", "Batches".dimmed(), batches.len());
+            console_println!(
+                "  {:<18}: {}",
+                "Timing".dimmed(),
+                if *original_timing {
+                    "Original"
+                } else {
+                    "As fast as possible"
+                }
+            );
+        } else if let Some(poll_interval_value_ms) = config.poll_interval_ms {
+            console_println!("  {:<18}: Polling", "Mode".dimmed());
+            console_println!(
+                "  {:<18}: {}",
+                "Poll Interval".dimmed(),
+                format_millis_to_duration_string(poll_interval_value_ms)
+            );
+            if let Some(max_calls) = config.max_api_calls_per_minute {
+                console_println!("  {:<18}: {} calls/min", "API Budget".dimmed(), max_calls);
+            }
+        } else {
+            console_println!("  {:<18}: Live Tail", "Mode".dimmed());
+        }
+        console_println!(
+            "  {:<18}: {}",
+            "Session Timeout".dimmed(),
+            format_millis_to_duration_string(config.session_timeout_ms)
         );
-        start_polling_task(
-            cwl_client,
-            resolved_log_group_arns,
-            config
-                .poll_interval_ms
-                .expect("Poll interval must be Some if poll_interval is configured, this is a bug"),
-            task_tx,
-            config.backtrace_ms,
-            config.session_timeout_ms,
+        if let Some(port) = config.metrics_port {
+            console_println!(
+                "  {:<18}: http://{}/metrics",
+                "Metrics".dimmed(),
+                metrics_socket_addr(&config, port)
+            );
+        }
+        if let Some(refresh_ms) = config.refresh_log_groups_ms.filter(|_| replay.is_none()) {
+            console_println!(
+                "  {:<18}: Every {}",
+                "Log Group Refresh".dimmed(),
+                format_millis_to_duration_string(refresh_ms)
+            );
+        }
+        console_println!(
+            "  {:<18}: {}",
+            "Forward Only".dimmed(),
+            if config.forward_only { "Yes" } else { "No" }
         );
-    } else {
-        tracing::debug!(
-            timeout_millis = config.session_timeout_ms,
-            "Using StartLiveTail streaming mode with timeout."
+        if let Some(endpoint) = &resolved_endpoint {
+            console_println!(
+                "  {:<18}: {} ({})",
+                "OTLP Endpoint".dimmed(),
+                endpoint,
+                match otlp_protocol {
+                    OtlpProtocol::HttpProtobuf => "http/protobuf",
+                    OtlpProtocol::Grpc => "grpc",
+                }
+            );
+        } else {
+            console_println!("  {:<18}: Not configured", "OTLP Endpoint".dimmed());
+        }
+        if !resolved_headers_vec.is_empty() {
+            console_println!(
+                "  {:<18}: {} headers",
+                "OTLP Headers".dimmed(),
+                resolved_headers_vec.len()
+            );
+        }
+        console_println!("  {:<18}: {}", "Theme".dimmed(), config.theme);
+        console_println!(
+            "  {:<18}: {}",
+            "Color By".dimmed(),
+            match config.color_by {
+                ColoringMode::Service => "Service",
+                ColoringMode::Span => "Span ID",
+            }
         );
-        start_live_tail_task(
-            cwl_client,
-            resolved_log_group_arns,
-            task_tx,
-            config.session_timeout_ms,
+        if let Some(color_rules) = &config.color_rules {
+            console_println!("  {:<18}: {:?}", "Color Rules".dimmed(), color_rules);
+        }
+        if let Some(link_template) = &config.link_template {
+            console_println!("  {:<18}: {}", "Trace Links".dimmed(), link_template);
+        }
+        if let Some(attrs) = &config.attrs {
+            console_println!("  {:<18}: {}", "Attributes".dimmed(), attrs);
+        } else {
+            console_println!("  {:<18}: All", "Attributes".dimmed());
+        }
+        console_println!(
+            "  {:<18}: {}",
+            "Severity Attr".dimmed(),
+            config.event_severity_attribute
+        );
+        console_println!(
+            "  {:<18}: {}",
+            "Events Only".dimmed(),
+            if config.events_only { "Yes" } else { "No" }
         );
+        console_println!(
+            "  {:<18}: {} seconds",
+            "Trace Timeout".dimmed(),
+            config.trace_timeout_ms / 1000 // Display as seconds for readability
+        );
+        console_println!(
+            "  {:<18}: {}",
+            "Stragglers Wait".dimmed(), // New preamble line
+            format_millis_to_duration_string(config.trace_stragglers_wait_ms)  // Use formatter
+        );
+        if let Some(sample_ratio) = config.sample_ratio {
+            console_println!(
+                "  {:<18}: {}% of traces, and traces with errors",
+                "Sampling".dimmed(),
+                sample_ratio * 100.0
+            );
+        }
+        if let Some(service) = &config.service {
+            console_println!("  {:<18}: {}", "Service".dimmed(), service);
+        }
+        if let Some(span_name) = &config.span_name {
+            console_println!("  {:<18}: {}", "Span Name".dimmed(), span_name);
+        }
+        if let Some(status) = config.status {
+            console_println!("  {:<18}: {:?}", "Status".dimmed(), status);
+        }
+        if let Some(min_duration_ms) = config.min_duration_ms {
+            console_println!(
+                "  {:<18}: {}",
+                "Min Duration".dimmed(),
+                format_millis_to_duration_string(min_duration_ms)
+            );
+        }
+        for (label, excluded) in [
+            ("Exclude Span Name", &config.exclude_span_names),
+            ("Exclude Service", &config.exclude_services),
+            ("Exclude Attr", &config.exclude_attrs),
+        ] {
+            if let Some(excluded) = excluded {
+                console_println!("  {:<18}: {:?}", label.dimmed(), excluded);
+            }
+        }
+        if let Some(min_level) = config.min_level {
+            console_println!("  {:<18}: {:?}", "Min Level".dimmed(), min_level);
+        }
+        if let Some(profile) = &args.config_profile {
+            // Use args here as config doesn't store it
+            console_println!("  {:<18}: {}", "Config Profile".dimmed(), profile);
+        }
+        let verbosity_str = match config.verbose {
+            0 => "Normal",
+            1 => "Debug (-v)",
+            _ => {
+                let v_str = format!("Trace (-v{})", "v".repeat(config.verbose as usize - 1));
+                Box::leak(v_str.into_boxed_str())
+            }
+        };
+        console_println!("  {:<18}: {}", "Verbosity".dimmed(), verbosity_str);
+        if let Some(record_path) = &args.record {
+            console_println!("  {:<18}: {}", "Recording".dimmed(), record_path.display());
+        }
+        if let Some(store_path) = &args.store {
+            console_println!("  {:<18}: {}", "Storing".dimmed(), store_path.display());
+        }
+        if let Some(stats_interval_ms) = stats_interval_ms {
+            console_println!(
+                "  {:<18}: {}every {}",
+                "Stats".dimmed(),
+                if config.stats { "Only, " } else { "" },
+                format_millis_to_duration_string(stats_interval_ms)
+            );
+        }
+        if config.service_map {
+            console_println!("  {:<18}: Yes", "Service Map".dimmed());
+        }
+        if config.heatmap {
+            console_println!("  {:<18}: Yes", "Latency Heatmap".dimmed());
+        }
+        if config.correlate_logs {
+            console_println!("  {:<18}: Yes", "Correlated Logs".dimmed());
+        }
+        if config.notify_on_error {
+            console_println!(
+                "  {:<18}: Desktop{}",
+                "Notify on Error".dimmed(),
+                if config.notify_webhook.is_some() {
+                    ", webhook"
+                } else {
+                    ""
+                }
+            );
+        }
+        if let Some(dot_path) = &args.service_map_dot {
+            console_println!(
+                "  {:<18}: {}",
+                "Service Map File".dimmed(),
+                dot_path.display()
+            );
+        }
+        if let Some(export_dir) = &args.export_dir {
+            console_println!(
+                "  {:<18}: {} ({:?})",
+                "Export".dimmed(),
+                export_dir.display(),
+                args.export_format
+            );
+        }
+        if let Some(html_out) = &args.html_out {
+            console_println!("  {:<18}: {}", "HTML Reports".dimmed(), html_out.display());
+        }
+        console_println!();
+        if let Some(aws) = &aws_result {
+            // Log groups are listed with their account when tailing several accounts
+            let multi_account = aws.accounts.len() > 1
+                || aws
+                    .accounts
+                    .iter()
+                    .any(|account| account.role_arn.is_some());
+            let validated_log_group_names_for_display: Vec<String> = aws
+                .accounts
+                .iter()
+                .flat_map(|account| {
+                    account.resolved_arns.iter().map(move |arn| {
+                        let name = arn.split(':').next_back().unwrap_or("unknown-name");
+                        if multi_account {
+                            format!("{} ({})", name, account.account_id)
+                        } else {
+                            name.to_string()
+                        }
+                    })
+                })
+                .collect();
+            console_print!("  {:<18}: ", "Log Groups".dimmed());
+            if let Some((first, rest)) = validated_log_group_names_for_display.split_first() {
+                console_println!("{}", first);
+                for name in rest {
+                    console_println!("{:<22}{}", "", name);
+                }
+            } else {
+                console_println!("None");
+            }
+        }
+        console_println!("\n");
+    }
+
+    // Create MPSC Channel and Spawn Event Source Task
+    let (tx, mut rx) = mpsc::channel::<Result<TelemetryData>>(100);
+    let task_tx = tx.clone(); // Clone the sender for the task that will produce events
+    let (log_tx, mut log_rx) = mpsc::channel::<CorrelatedLog>(100);
+    let task_log_tx = config.correlate_logs.then(|| log_tx.clone());
+
+    if let Some((_, batches, original_timing)) = replay {
+        tracing::debug!(original_timing, "Replaying recorded session.");
+        start_replay_task(batches, task_tx, original_timing);
+    } else if let Some(aws) = aws_result {
+        // The log groups discovered again during the session are tailed by new tasks
+        if let Some(refresh_interval_ms) = config.refresh_log_groups_ms {
+            let accounts = aws
+                .accounts
+                .iter()
+                .map(|account| RefreshedAccount {
+                    discovery: account.discovery.clone(),
+                    tailed_arns: account.resolved_arns.iter().cloned().collect(),
+                })
+                .collect();
+            start_log_group_refresh_task(
+                accounts,
+                RefreshSettings {
+                    refresh_interval_ms,
+                    session_timeout_ms: config.session_timeout_ms,
+                    poll_interval_ms: config.poll_interval_ms,
+                    min_level: config.min_level,
+                    api_budget: api_budget.clone(),
+                },
+                task_tx.clone(),
+                task_log_tx.clone(),
+            );
+        }
+        // Each account is tailed by its own task, sending to the same channel
+        for account in aws.accounts {
+            if let Some(interval_secs) = config.poll_interval_ms {
+                tracing::debug!(
+                    account_id = %account.account_id,
+                    interval = interval_secs,
+                    backtrace_s = ?config.backtrace_ms,
+                    "Using FilterLogEvents polling mode."
+                );
+                start_polling_task(
+                    account.cwl_client,
+                    account.resolved_arns,
+                    interval_secs,
+                    task_tx.clone(),
+                    task_log_tx.clone(),
+                    config.min_level,
+                    config.backtrace_ms,
+                    config.session_timeout_ms,
+                    api_budget.clone(),
+                );
+            } else {
+                tracing::debug!(
+                    account_id = %account.account_id,
+                    timeout_millis = config.session_timeout_ms,
+                    "Using StartLiveTail streaming mode with timeout."
+                );
+                start_live_tail_task(
+                    account.cwl_client,
+                    account.resolved_arns,
+                    task_tx.clone(),
+                    task_log_tx.clone(),
+                    config.min_level,
+                    config.session_timeout_ms,
+                );
+            }
+        }
+        drop(task_tx);
+        drop(task_log_tx);
     }
     drop(tx); // Drop the original sender from run_livetrace, leaving only the task's sender active
+    drop(log_tx);
 
     // Main Event Processing Loop
     tracing::debug!("Waiting for telemetry events...");
     let mut trace_buffers: HashMap<String, TraceBufferState> = HashMap::new();
     let mut ticker = interval(Duration::from_secs(1));
+    let stats_period = Duration::from_millis(
+        stats_interval_ms
+            .unwrap_or(DEFAULT_STATS_INTERVAL_MS)
+            .max(1),
+    );
+    let mut stats_ticker = interval_at(Instant::now() + stats_period, stats_period);
 
-    let spinner = ProgressBar::new_spinner();
+    // JSON output is meant for piping, without the spinner
+    let spinner = if args.output == OutputFormat::Console {
+        ProgressBar::new_spinner()
+    } else {
+        ProgressBar::hidden()
+    };
     spinner.set_style(
         ProgressStyle::default_spinner()
-            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
+            .tick_chars(glyphs().spinner)
             .template("{spinner} {msg}")
             .unwrap(),
     );
     spinner.set_message("Waiting for telemetry events...");
     spinner.enable_steady_tick(Duration::from_millis(100));
 
+    let flush_context = FlushContext {
+        config: &config,
+        // The stats, service map and heatmap views are printed instead of the traces
+        console_enabled: console_enabled && !config.stats && !config.service_map && !config.heatmap,
+        output: args.output,
+        attr_globs: &attr_globs,
+        color_rules: &color_rules,
+        grep_regex: grep_regex_arc.as_deref(),
+        trace_filter: &trace_filter,
+        exporter: exporter.as_ref(),
+        html_reporter: html_reporter.as_ref(),
+        endpoint: endpoint_opt,
+        http_client: &http_client,
+        grpc_client: grpc_client.as_ref(),
+        otlp_header_map: &otlp_header_map,
+        compaction_config: &compaction_config,
+    };
+
     loop {
         tokio::select! {
             received = rx.recv() => {
                 match received {
                     Some(Ok(telemetry)) => {
+                        if let Some(writer) = session_writer.as_mut() {
+                            if let Err(e) = writer.record(&telemetry) {
+                                tracing::warn!(error = %e, "Failed to write session file; recording stopped.");
+                                session_writer = None;
+                            }
+                        }
+                        if let Some(store) = trace_store.as_mut() {
+                            if let Err(e) = store.insert(&telemetry) {
+                                tracing::warn!(error = %e, "Failed to write to trace store; storing stopped.");
+                                trace_store = None;
+                            }
+                        }
                         match ExportTraceServiceRequest::decode(telemetry.payload.as_slice()) {
                             Ok(request) => {
                                 spinner.set_message("Processing telemetry data...");
-                                let mut trace_id_hex_opt: Option<String> = None;
-                                let mut is_root_present_in_req = false;
-
-                                for resource_span in &request.resource_spans {
-                                    for scope_span in &resource_span.scope_spans {
-                                        for span in &scope_span.spans {
-                                            if trace_id_hex_opt.is_none() {
-                                                trace_id_hex_opt = Some(hex::encode(&span.trace_id));
-                                            }
-                                            if span.parent_span_id.is_empty() {
-                                                is_root_present_in_req = true;
-                                            }
-                                        }
+                                if let Some(red_stats) = red_stats.as_mut() {
+                                    red_stats.record(&request, Instant::now());
+                                }
+                                if let Some(heatmap) = latency_heatmap.as_mut() {
+                                    heatmap.record(&request);
+                                }
+                                if let Some(notifier) = error_notifier.as_mut() {
+                                    if let Some(notification) = find_notification(&request, grep_regex_arc.as_deref()) {
+                                        notifier.notify(notification, Instant::now());
                                     }
                                 }
+                                let trace_id_hex_opt = request
+                                    .resource_spans
+                                    .iter()
+                                    .flat_map(|resource_span| &resource_span.scope_spans)
+                                    .flat_map(|scope_span| &scope_span.spans)
+                                    .next()
+                                    .map(|span| hex::encode(&span.trace_id));
 
-                                if let Some(tid) = trace_id_hex_opt {
+                                // Batches of unsampled traces are dropped, unless they have an error
+                                // span, and the later batches of a trace kept for an error follow it
+                                let sampled_out = trace_sampler.is_some_and(|sampler| !sampler.keeps(&request))
+                                    && trace_id_hex_opt.as_ref().is_none_or(|tid| {
+                                        trace_buffers.get(tid).is_none_or(|state| state.buffered_payloads.is_empty())
+                                    });
+                                if sampled_out {
+                                    tracing::trace!(trace_id = ?trace_id_hex_opt, "Trace not sampled, skipping batch.");
+                                } else if let Some(tid) = trace_id_hex_opt {
                                     let now = Instant::now();
-                                    let state = trace_buffers
+                                    trace_buffers
                                         .entry(tid)
-                                        .or_insert_with(|| TraceBufferState {
-                                            buffered_payloads: Vec::new(),
-                                            has_received_root: false,
-                                            first_message_received_at: now,
-                                            last_message_received_at: now,
-                                        });
-                                    state.buffered_payloads.push(telemetry);
-                                    state.has_received_root |= is_root_present_in_req;
-                                    state.last_message_received_at = now;
+                                        .or_insert_with(|| TraceBufferState::new(now))
+                                        .push(telemetry, &request, now);
                                 } else {
                                     tracing::warn!("Received OTLP request with no spans, cannot determine trace ID.");
                                 }
@@ -470,21 +1158,60 @@ pub async fn run_livetrace(args: CliArgs) -> Result<()> {
                         tracing::error!(error = %e, "Error received from event source task");
                     }
                     None => {
+                        // Flush the traces still buffered, such as at the end of a replay
+                        let trace_ids_to_flush: Vec<String> = trace_buffers.keys().cloned().collect();
+                        flush_traces(&mut trace_buffers, &trace_ids_to_flush, &flush_context, service_map.as_mut(), &spinner).await?;
+                        if let Some(red_stats) = red_stats.as_mut() {
+                            print_stats(&red_stats.summarize(Instant::now()), STATS_WINDOW);
+                        }
+                        if let Some(heatmap) = latency_heatmap.as_mut() {
+                            heatmap.tick();
+                            heatmap.print();
+                        }
+                        if let Some(service_map) = &service_map {
+                            output_service_map(service_map, print_service_map, args.service_map_dot.as_deref());
+                        }
                         spinner.finish_with_message("Event source channel closed");
                         tracing::info!("Event source channel closed. Exiting.");
                         break;
                     }
                 }
             }
+            // Log lines usually arrive before the spans of their invocation, so they open the buffer
+            Some(log) = log_rx.recv() => {
+                let now = Instant::now();
+                let state = trace_buffers
+                    .entry(log.trace_id.clone())
+                    .or_insert_with(|| TraceBufferState::new(now));
+                state.correlated_logs.push(log);
+                state.last_message_received_at = now;
+            }
             _ = ticker.tick() => {
+                if let Some(throttled_rx) = api_throttled_rx.as_mut() {
+                    let changed = throttled_rx.has_changed().unwrap_or(false);
+                    if *throttled_rx.borrow_and_update() {
+                        spinner.set_message(format!(
+                            "API budget of {} calls/min reached, polling paused...",
+                            config.max_api_calls_per_minute.unwrap_or_default()
+                        ));
+                    } else if changed {
+                        spinner.set_message("Waiting for telemetry events...");
+                    }
+                }
+                if let Some(heatmap) = latency_heatmap.as_mut() {
+                    heatmap.tick();
+                    heatmap.print();
+                }
                 let now = Instant::now();
                 let mut trace_ids_to_flush: Vec<String> = Vec::new();
 
                 for (trace_id, state) in trace_buffers.iter() {
                     let time_since_last = now.duration_since(state.last_message_received_at);
                     let time_since_first = now.duration_since(state.first_message_received_at);
+                    // Spans of a trace arrive across ticks, from several log groups, so it is only
+                    // rendered once assembled, or when its window expires
                     let should_flush =
-                        (state.has_received_root && time_since_last > Duration::from_millis(config.trace_stragglers_wait_ms)) // Use new config value
+                        (state.is_assembled() && time_since_last > Duration::from_millis(config.trace_stragglers_wait_ms))
                         || (time_since_first > Duration::from_millis(config.trace_timeout_ms));
                     if should_flush {
                         trace_ids_to_flush.push(trace_id.clone());
@@ -492,63 +1219,80 @@ pub async fn run_livetrace(args: CliArgs) -> Result<()> {
                 }
 
                 if !trace_ids_to_flush.is_empty() {
-                    let mut batches_to_process: Vec<(String, Vec<TelemetryData>, bool)> = Vec::new();
-                    for trace_id in &trace_ids_to_flush {
-                        if let Some(state) = trace_buffers.get(trace_id) {
-                            batches_to_process.push((
-                                trace_id.clone(),
-                                state.buffered_payloads.clone(),
-                                state.has_received_root,
-                            ));
-                        }
-                    }
-                    // Remove flushed traces from buffer *after* collecting data
-                    for trace_id in &trace_ids_to_flush {
-                        trace_buffers.remove(trace_id);
-                    }
-
-                    let mut futures_vec = Vec::new();
-                    for (trace_id, payloads_to_process, root_seen) in batches_to_process {
-                        spinner.set_message(format!("Flushing trace {}...", &trace_id[..8]));
-                        if console_enabled {
-                            display_console(
-                                // &trace_id, // trace_id is not a direct parameter
-                                &payloads_to_process,
-                                &attr_globs,
-                                &config.event_severity_attribute,
-                                config.theme,
-                                config.color_by,
-                                config.events_only,
-                                root_seen,
-                                grep_regex_arc.as_deref(), // Pass Option<&Regex>
-                            )?;
-                        }
-
-                        if let Some(endpoint_url) = endpoint_opt {
-                            let client_clone = http_client.clone();
-                            let endpoint_clone = endpoint_url.to_string();
-                            let headers_clone = otlp_header_map.clone();
-                            let payloads_clone = payloads_to_process.clone(); // Clone for async task
-                            let compaction_config_clone = compaction_config.clone(); // Clone for the async task
-
-                            futures_vec.push(tokio::spawn(async move {
-                                send_batch(
-                                    &client_clone,
-                                    &endpoint_clone,
-                                    payloads_clone, // Use cloned payloads
-                                    &compaction_config_clone, // Use the cloned config
-                                    headers_clone,
-                                )
-                                .await
-                            }));
-                        }
-                        spinner.set_message("Waiting for telemetry events...");
+                    flush_traces(&mut trace_buffers, &trace_ids_to_flush, &flush_context, service_map.as_mut(), &spinner).await?;
+                }
+            }
+            _ = stats_ticker.tick(), if red_stats.is_some() || service_map.is_some() => {
+                if let Some(red_stats) = red_stats.as_mut() {
+                    print_stats(&red_stats.summarize(Instant::now()), STATS_WINDOW);
+                }
+                if let Some(service_map) = service_map.as_mut() {
+                    if service_map.take_changed() {
+                        output_service_map(service_map, print_service_map, args.service_map_dot.as_deref());
                     }
-                    join_all(futures_vec).await; // Wait for all forwarding tasks to complete
                 }
             }
         }
     }
     spinner.finish_and_clear();
+    recorder::stop_recording();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+
+    fn batch(spans: &[(u8, u8)]) -> (TelemetryData, ExportTraceServiceRequest) {
+        let request = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                scope_spans: vec![ScopeSpans {
+                    spans: spans
+                        .iter()
+                        .map(|&(span_id, parent_span_id)| Span {
+                            trace_id: vec![1; 16],
+                            span_id: vec![span_id; 8],
+                            parent_span_id: if parent_span_id == 0 {
+                                Vec::new()
+                            } else {
+                                vec![parent_span_id; 8]
+                            },
+                            ..Default::default()
+                        })
+                        .collect(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let telemetry = TelemetryData {
+            payload: request.encode_to_vec(),
+            original_endpoint: "/v1/traces".to_string(),
+            original_source: "checkout".to_string(),
+        };
+        (telemetry, request)
+    }
+
+    #[test]
+    fn test_trace_buffer_assembly() {
+        let now = Instant::now();
+        let mut state = TraceBufferState::new(now);
+
+        // A downstream span arrives first
+        let (telemetry, request) = batch(&[(3, 2)]);
+        state.push(telemetry, &request, now);
+        assert!(!state.is_assembled());
+
+        // The root arrives, but the parent of the downstream span is still missing
+        let (telemetry, request) = batch(&[(1, 0)]);
+        state.push(telemetry, &request, now);
+        assert!(state.has_received_root);
+        assert!(!state.is_assembled());
+
+        let (telemetry, request) = batch(&[(2, 1)]);
+        state.push(telemetry, &request, now);
+        assert!(state.is_assembled());
+        assert_eq!(state.buffered_payloads.len(), 3);
+    }
+}
@@ -7,6 +7,8 @@
 //! - Processing log event messages from the stream using functions from the `processing` module.
 //! - Sending the resulting `TelemetryData` (or errors) over an MPSC channel to the main
 //!   application logic.
+//! - Sending the other log lines that carry a trace ID over a second channel, when logs are
+//!   correlated.
 //! - Handling session timeouts.
 
 use anyhow::Result;
@@ -16,21 +18,33 @@ use tokio::pin;
 use tokio::sync::mpsc;
 use tokio::time::sleep;
 
+use crate::cli::RecordLevel;
+use crate::log_correlation::{correlate_log_line, CorrelatedLog};
+use crate::metrics::{CwlApi, METRICS};
 use crate::processing::{process_log_event_message, TelemetryData};
 
 /// Spawns a task that runs StartLiveTail and sends processed TelemetryData over an MPSC channel.
+/// The log lines correlated to a trace are sent to `log_sender` when one is given, and the records
+/// below `min_level` are skipped.
 pub fn start_live_tail_task(
     cwl_client: CwlClient,
     arns: Vec<String>,
     sender: mpsc::Sender<Result<TelemetryData>>,
+    log_sender: Option<mpsc::Sender<CorrelatedLog>>,
+    min_level: Option<RecordLevel>,
     timeout_millis: u64,
 ) {
     tokio::spawn(async move {
         tracing::debug!("Live Tail Adapter: Attempting to start Live Tail stream...");
+        // Application log lines are only received without the OTLP/stdout filter pattern
+        let filter_pattern = log_sender
+            .is_none()
+            .then(|| "{ $.__otel_otlp_stdout = * }".to_string());
+        METRICS.record_cwl_api_call(CwlApi::StartLiveTail);
         let live_tail_result = cwl_client
             .start_live_tail()
             .set_log_group_identifiers(Some(arns))
-            .log_event_filter_pattern("{ $.__otel_otlp_stdout = * }")
+            .set_log_event_filter_pattern(filter_pattern)
             .send()
             .await;
 
@@ -80,15 +94,27 @@ pub fn start_live_tail_task(
                                     tracing::trace!("Live Tail Adapter: Received update with {} log events.", log_events.len());
                                     for log_event in log_events {
                                         if let Some(msg) = log_event.message() {
-                                            match process_log_event_message(msg) {
+                                            match process_log_event_message(msg, min_level) {
                                                 Ok(Some(telemetry)) => {
+                                                    METRICS.record_event_received();
                                                     if sender.send(Ok(telemetry)).await.is_err() {
                                                         tracing::warn!("Live Tail Adapter: MPSC channel closed by receiver while sending data.");
                                                         return; // Exit task
                                                     }
                                                 }
-                                                Ok(None) => {} // Ignore
+                                                Ok(None) => {
+                                                    let correlated = log_sender.as_ref().zip(
+                                                        correlate_log_line(msg, log_event.timestamp().unwrap_or_default()),
+                                                    );
+                                                    if let Some((log_sender, log)) = correlated {
+                                                        if log_sender.send(log).await.is_err() {
+                                                            tracing::warn!("Live Tail Adapter: Log channel closed by receiver while sending data.");
+                                                            return; // Exit task
+                                                        }
+                                                    }
+                                                }
                                                 Err(e) => {
+                                                    METRICS.record_parse_failure();
                                                     tracing::warn!(message = ?msg, error = %e, "Live Tail Adapter: Failed to process log event");
                                                 }
                                             }
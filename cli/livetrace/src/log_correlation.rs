@@ -0,0 +1,164 @@
+//! Correlates the application log lines of the tailed log streams with the received traces.
+//!
+//! With `--correlate-logs`, the log lines that are not OTLP/stdout records are kept when they
+//! carry a trace ID, so they can be shown in the timeline of their trace. Structured (JSON) log
+//! lines are read from the usual trace and span ID fields, including the `otelTraceID` fields of
+//! the OpenTelemetry logging instrumentations and the `xray_trace_id` field of Powertools for AWS
+//! Lambda. Plain text lines are matched for `trace_id=<id>` style pairs or a W3C `traceparent`.
+
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::sync::LazyLock;
+
+// JSON fields holding the trace ID, span ID, message and level of a log line, in order of preference
+const TRACE_ID_FIELDS: [&str; 5] = ["trace_id", "traceId", "traceID", "otelTraceID", "trace.id"];
+const XRAY_TRACE_ID_FIELDS: [&str; 2] = ["xray_trace_id", "xrayTraceId"];
+const SPAN_ID_FIELDS: [&str; 5] = ["span_id", "spanId", "spanID", "otelSpanID", "span.id"];
+const MESSAGE_FIELDS: [&str; 3] = ["message", "msg", "body"];
+const LEVEL_FIELDS: [&str; 5] = [
+    "level",
+    "severity",
+    "levelname",
+    "log.level",
+    "severity_text",
+];
+
+static TRACEPARENT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b[0-9a-f]{2}-([0-9a-f]{32})-([0-9a-f]{16})-[0-9a-f]{2}\b").unwrap()
+});
+static TRACE_ID_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\btrace[_.-]?id["']?\s*[=:]\s*["']?([0-9a-f]{32})\b"#).unwrap()
+});
+static SPAN_ID_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\bspan[_.-]?id["']?\s*[=:]\s*["']?([0-9a-f]{16})\b"#).unwrap()
+});
+
+/// A log line carrying the ID of a trace
+#[derive(Clone, Debug, PartialEq)]
+pub struct CorrelatedLog {
+    pub trace_id: String,
+    pub span_id: Option<String>,
+    pub timestamp_ms: i64,
+    pub level: Option<String>,
+    pub message: String,
+}
+
+/// Returns the log line as a correlated log if it carries a trace ID
+pub fn correlate_log_line(line: &str, timestamp_ms: i64) -> Option<CorrelatedLog> {
+    let line = line.trim();
+    if let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(line) {
+        return correlate_json_log(&fields, line, timestamp_ms);
+    }
+
+    let (trace_id, span_id) = match TRACEPARENT_RE.captures(line) {
+        Some(captures) => (captures[1].to_string(), Some(captures[2].to_string())),
+        None => (
+            TRACE_ID_RE.captures(line)?[1].to_lowercase(),
+            SPAN_ID_RE
+                .captures(line)
+                .map(|captures| captures[1].to_lowercase()),
+        ),
+    };
+    valid_trace_id(&trace_id).then(|| CorrelatedLog {
+        trace_id,
+        span_id,
+        timestamp_ms,
+        level: None,
+        message: line.to_string(),
+    })
+}
+
+fn correlate_json_log(
+    fields: &Map<String, Value>,
+    line: &str,
+    timestamp_ms: i64,
+) -> Option<CorrelatedLog> {
+    let trace_id = string_field(fields, &TRACE_ID_FIELDS)
+        .map(str::to_lowercase)
+        .or_else(|| string_field(fields, &XRAY_TRACE_ID_FIELDS).and_then(xray_to_trace_id))
+        .filter(|trace_id| valid_trace_id(trace_id))?;
+    let span_id = string_field(fields, &SPAN_ID_FIELDS)
+        .map(str::to_lowercase)
+        .filter(|span_id| span_id.len() == 16 && span_id.chars().all(|c| c.is_ascii_hexdigit()));
+    Some(CorrelatedLog {
+        trace_id,
+        span_id,
+        timestamp_ms,
+        level: string_field(fields, &LEVEL_FIELDS).map(str::to_uppercase),
+        message: string_field(fields, &MESSAGE_FIELDS)
+            .unwrap_or(line)
+            .to_string(),
+    })
+}
+
+fn string_field<'a>(fields: &'a Map<String, Value>, names: &[&str]) -> Option<&'a str> {
+    names
+        .iter()
+        .find_map(|name| fields.get(*name).and_then(Value::as_str))
+}
+
+// An X-Ray trace ID such as `1-5759e988-bd862e3fe1be46a994272793` holds the 32 hex digits of
+// the OpenTelemetry trace ID after its version
+fn xray_to_trace_id(xray_trace_id: &str) -> Option<String> {
+    let xray_trace_id = xray_trace_id.split(';').next()?.trim_start_matches("Root=");
+    let (_, id) = xray_trace_id.split_once('-')?;
+    Some(id.replace('-', "").to_lowercase())
+}
+
+fn valid_trace_id(trace_id: &str) -> bool {
+    trace_id.len() == 32
+        && trace_id.chars().all(|c| c.is_ascii_hexdigit())
+        && trace_id.chars().any(|c| c != '0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correlate_log_line() {
+        let log = correlate_log_line(
+            r#"{"level":"warn","message":"card declined","trace_id":"4BF92F3577B34DA6A3CE929D0E0E4736","span_id":"00f067aa0ba902b7"}"#,
+            1000,
+        )
+        .unwrap();
+        assert_eq!(log.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(log.span_id.as_deref(), Some("00f067aa0ba902b7"));
+        assert_eq!(log.level.as_deref(), Some("WARN"));
+        assert_eq!(log.message, "card declined");
+
+        let log = correlate_log_line(
+            r#"{"message":"order placed","xray_trace_id":"1-5759e988-bd862e3fe1be46a994272793"}"#,
+            1000,
+        )
+        .unwrap();
+        assert_eq!(log.trace_id, "5759e988bd862e3fe1be46a994272793");
+        assert_eq!(log.span_id, None);
+
+        let log = correlate_log_line(
+            "2024-05-01 INFO charging card traceparent=00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            1000,
+        )
+        .unwrap();
+        assert_eq!(log.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(log.span_id.as_deref(), Some("00f067aa0ba902b7"));
+        assert_eq!(
+            correlate_log_line(
+                "charging card trace_id=4bf92f3577b34da6a3ce929d0e0e4736",
+                1000
+            )
+            .unwrap()
+            .span_id,
+            None
+        );
+
+        assert_eq!(
+            correlate_log_line("START RequestId: 8f5c Version: $LATEST", 1000),
+            None
+        );
+        assert_eq!(
+            correlate_log_line(r#"{"trace_id":"00000000000000000000000000000000"}"#, 1000),
+            None
+        );
+    }
+}
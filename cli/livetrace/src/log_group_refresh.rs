@@ -0,0 +1,143 @@
+//! Attaches the log groups created during a session, such as those of freshly deployed functions.
+//!
+//! With `--refresh-log-groups <DURATION>`, the discovery of each account (stacks, patterns and
+//! tags) runs again at this interval. The log groups it finds that are not tailed yet are tailed
+//! by a new task, in the same mode as the others, until the end of the session: a new Live Tail
+//! session per 10 log groups, or a new polling task starting one refresh interval back, so the
+//! events written before the log group was discovered are not missed. Log groups removed during
+//! the session are left as they are.
+
+use anyhow::Result;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::{interval_at, Instant};
+
+use crate::aws_setup::LogGroupDiscovery;
+use crate::cli::RecordLevel;
+use crate::live_tail_adapter::start_live_tail_task;
+use crate::log_correlation::CorrelatedLog;
+use crate::poller::{start_polling_task, ApiBudget};
+use crate::processing::TelemetryData;
+
+// Maximum number of log groups of a Live Tail session
+const MAX_LIVE_TAIL_LOG_GROUPS: usize = 10;
+
+/// An account whose discovery runs again during the session, with the ARNs already tailed
+pub struct RefreshedAccount {
+    pub discovery: LogGroupDiscovery,
+    pub tailed_arns: HashSet<String>,
+}
+
+/// How the log groups found during the session are tailed
+pub struct RefreshSettings {
+    pub refresh_interval_ms: u64,
+    pub session_timeout_ms: u64,
+    pub poll_interval_ms: Option<u64>,
+    pub min_level: Option<RecordLevel>,
+    pub api_budget: Option<Arc<ApiBudget>>,
+}
+
+/// Spawns a task that runs the discovery of the accounts at the refresh interval, and starts
+/// tailing the log groups found that are not tailed yet.
+pub fn start_log_group_refresh_task(
+    mut accounts: Vec<RefreshedAccount>,
+    settings: RefreshSettings,
+    sender: mpsc::Sender<Result<TelemetryData>>,
+    log_sender: Option<mpsc::Sender<CorrelatedLog>>,
+) {
+    tokio::spawn(async move {
+        let started_at = Instant::now();
+        let session_end = started_at + Duration::from_millis(settings.session_timeout_ms);
+        let refresh_period = Duration::from_millis(settings.refresh_interval_ms.max(1));
+        let mut refresh_ticker = interval_at(started_at + refresh_period, refresh_period);
+        let session_timer = tokio::time::sleep_until(session_end);
+        tokio::pin!(session_timer);
+
+        loop {
+            tokio::select! {
+                _ = refresh_ticker.tick() => {
+                    for account in accounts.iter_mut() {
+                        let names = match account.discovery.discover_log_group_names().await {
+                            Ok(names) => names,
+                            Err(e) => {
+                                tracing::warn!(error = %e, "Log Group Refresh: Failed to discover log groups.");
+                                continue;
+                            }
+                        };
+                        let new_arns = new_log_group_arns(
+                            names.iter().map(|name| account.discovery.log_group_arn(name)),
+                            &mut account.tailed_arns,
+                        );
+                        if new_arns.is_empty() {
+                            continue;
+                        }
+                        tracing::info!(log_groups = ?new_arns, "Log Group Refresh: Tailing new log groups.");
+                        let remaining_ms = session_end
+                            .saturating_duration_since(Instant::now())
+                            .as_millis() as u64;
+                        if let Some(poll_interval_ms) = settings.poll_interval_ms {
+                            start_polling_task(
+                                account.discovery.cwl_client.clone(),
+                                new_arns,
+                                poll_interval_ms,
+                                sender.clone(),
+                                log_sender.clone(),
+                                settings.min_level,
+                                Some(settings.refresh_interval_ms),
+                                remaining_ms,
+                                settings.api_budget.clone(),
+                            );
+                        } else {
+                            for arns in new_arns.chunks(MAX_LIVE_TAIL_LOG_GROUPS) {
+                                start_live_tail_task(
+                                    account.discovery.cwl_client.clone(),
+                                    arns.to_vec(),
+                                    sender.clone(),
+                                    log_sender.clone(),
+                                    settings.min_level,
+                                    remaining_ms,
+                                );
+                            }
+                        }
+                    }
+                }
+                _ = &mut session_timer => {
+                    tracing::debug!("Log Group Refresh: Session timeout reached. Stopping refresh task.");
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// Returns the discovered ARNs that are not tailed yet, adding them to the tailed ARNs
+fn new_log_group_arns(
+    discovered_arns: impl Iterator<Item = String>,
+    tailed_arns: &mut HashSet<String>,
+) -> Vec<String> {
+    discovered_arns
+        .filter(|arn| tailed_arns.insert(arn.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_log_group_arns() {
+        let mut tailed_arns: HashSet<String> = ["orders".to_string()].into();
+        let discovered = || {
+            ["orders", "payments", "payments"]
+                .map(String::from)
+                .into_iter()
+        };
+        assert_eq!(
+            new_log_group_arns(discovered(), &mut tailed_arns),
+            vec!["payments".to_string()]
+        );
+        assert!(new_log_group_arns(discovered(), &mut tailed_arns).is_empty());
+    }
+}
@@ -40,6 +40,7 @@ async fn main() -> Result<()> {
                 generate(*shell, &mut cmd, bin_name, &mut stdout()); // Dereference shell
                 return Ok(()); // Exit after generating completions
             }
+            Commands::Replay { .. } | Commands::Query { .. } => {} // Handled by run_livetrace
         }
     }
 
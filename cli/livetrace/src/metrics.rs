@@ -0,0 +1,314 @@
+//! Exposes Prometheus metrics of the session, for livetrace running for hours as a shared utility.
+//!
+//! With `--metrics-port <PORT>`, the metrics are served in the Prometheus text format at
+//! `/metrics` on this port, on the loopback interface unless `--metrics-addr` is set:
+//! - `livetrace_events_received_total`: OTLP/stdout records received from CloudWatch Logs.
+//! - `livetrace_parse_failures_total`: OTLP/stdout records that could not be parsed or decoded.
+//! - `livetrace_forward_requests_total`: export requests to the OTLP endpoint, by `result`.
+//! - `livetrace_forward_duration_seconds`: histogram of the duration of the export requests.
+//! - `livetrace_cwl_api_calls_total`: CloudWatch Logs API calls, by `api`.
+
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::header::CONTENT_TYPE;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use std::fmt::Write as _;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+
+/// Metrics of the session, recorded by the tailing tasks and the forwarder
+pub static METRICS: Metrics = Metrics::new();
+
+/// Upper bounds of the forward duration buckets, in seconds, as the Prometheus defaults
+const FORWARD_DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Address the metrics are served on without `--metrics-addr`
+pub const DEFAULT_METRICS_ADDR: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+// Maximum size of a request head, and time to receive it
+const MAX_REQUEST_HEAD_BYTES: usize = 8192;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The CloudWatch Logs APIs called by livetrace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CwlApi {
+    DescribeLogGroups,
+    FilterLogEvents,
+    StartLiveTail,
+}
+
+impl CwlApi {
+    const ALL: [CwlApi; 3] = [
+        CwlApi::DescribeLogGroups,
+        CwlApi::FilterLogEvents,
+        CwlApi::StartLiveTail,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            CwlApi::DescribeLogGroups => "DescribeLogGroups",
+            CwlApi::FilterLogEvents => "FilterLogEvents",
+            CwlApi::StartLiveTail => "StartLiveTail",
+        }
+    }
+}
+
+/// Counters of the session, with a histogram of the forward durations
+#[derive(Debug)]
+pub struct Metrics {
+    events_received: AtomicU64,
+    parse_failures: AtomicU64,
+    forward_successes: AtomicU64,
+    forward_failures: AtomicU64,
+    // Requests per bucket, the last one counting the requests slower than all bounds
+    forward_duration_buckets: [AtomicU64; FORWARD_DURATION_BUCKETS.len() + 1],
+    forward_duration_sum_micros: AtomicU64,
+    cwl_api_calls: [AtomicU64; CwlApi::ALL.len()],
+}
+
+impl Metrics {
+    pub const fn new() -> Self {
+        Self {
+            events_received: AtomicU64::new(0),
+            parse_failures: AtomicU64::new(0),
+            forward_successes: AtomicU64::new(0),
+            forward_failures: AtomicU64::new(0),
+            forward_duration_buckets: [const { AtomicU64::new(0) };
+                FORWARD_DURATION_BUCKETS.len() + 1],
+            forward_duration_sum_micros: AtomicU64::new(0),
+            cwl_api_calls: [const { AtomicU64::new(0) }; CwlApi::ALL.len()],
+        }
+    }
+
+    pub fn record_event_received(&self) {
+        self.events_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_parse_failure(&self) {
+        self.parse_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records an export request to the OTLP endpoint, and its duration
+    pub fn record_forward(&self, success: bool, duration: Duration) {
+        if success {
+            self.forward_successes.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.forward_failures.fetch_add(1, Ordering::Relaxed);
+        }
+        let seconds = duration.as_secs_f64();
+        let bucket = FORWARD_DURATION_BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(FORWARD_DURATION_BUCKETS.len());
+        self.forward_duration_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.forward_duration_sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_cwl_api_call(&self, api: CwlApi) {
+        // The APIs are listed in declaration order
+        self.cwl_api_calls[api as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# HELP livetrace_events_received_total OTLP/stdout records received from CloudWatch Logs.\n\
+             # TYPE livetrace_events_received_total counter\n\
+             livetrace_events_received_total {}",
+            load(&self.events_received)
+        );
+        let _ = writeln!(
+            out,
+            "# HELP livetrace_parse_failures_total OTLP/stdout records that could not be parsed or decoded.\n\
+             # TYPE livetrace_parse_failures_total counter\n\
+             livetrace_parse_failures_total {}",
+            load(&self.parse_failures)
+        );
+        let _ = writeln!(
+            out,
+            "# HELP livetrace_forward_requests_total Export requests to the OTLP endpoint.\n\
+             # TYPE livetrace_forward_requests_total counter\n\
+             livetrace_forward_requests_total{{result=\"success\"}} {}\n\
+             livetrace_forward_requests_total{{result=\"failure\"}} {}",
+            load(&self.forward_successes),
+            load(&self.forward_failures)
+        );
+        let _ = writeln!(
+            out,
+            "# HELP livetrace_forward_duration_seconds Duration of the export requests to the OTLP endpoint.\n\
+             # TYPE livetrace_forward_duration_seconds histogram"
+        );
+        let mut cumulative = 0;
+        for (index, bucket) in self.forward_duration_buckets.iter().enumerate() {
+            cumulative += load(bucket);
+            let bound = FORWARD_DURATION_BUCKETS
+                .get(index)
+                .map_or_else(|| "+Inf".to_string(), |bound| bound.to_string());
+            let _ = writeln!(
+                out,
+                "livetrace_forward_duration_seconds_bucket{{le=\"{}\"}} {}",
+                bound, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "livetrace_forward_duration_seconds_sum {}\n\
+             livetrace_forward_duration_seconds_count {}",
+            load(&self.forward_duration_sum_micros) as f64 / 1_000_000.0,
+            cumulative
+        );
+        let _ = writeln!(
+            out,
+            "# HELP livetrace_cwl_api_calls_total CloudWatch Logs API calls.\n\
+             # TYPE livetrace_cwl_api_calls_total counter"
+        );
+        for (api, calls) in CwlApi::ALL.iter().zip(&self.cwl_api_calls) {
+            let _ = writeln!(
+                out,
+                "livetrace_cwl_api_calls_total{{api=\"{}\"}} {}",
+                api.name(),
+                load(calls)
+            );
+        }
+        out
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Binds the metrics address and spawns a task serving the metrics until the end of the process
+pub async fn start_metrics_server(addr: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind the metrics address {}", addr))?;
+    tracing::debug!(%addr, "Metrics: Serving Prometheus metrics.");
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(serve_connection(stream));
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Metrics: Failed to accept a connection.");
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn serve_connection(stream: TcpStream) {
+    let service = service_fn(|request: Request<Incoming>| async move {
+        let (status, body) = respond(request.method(), request.uri().path(), &METRICS);
+        Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")
+            .body(Full::new(Bytes::from(body)))
+    });
+    let served = http1::Builder::new()
+        .timer(TokioTimer::new())
+        .header_read_timeout(REQUEST_TIMEOUT)
+        .max_buf_size(MAX_REQUEST_HEAD_BYTES)
+        .serve_connection(TokioIo::new(stream), service)
+        .await;
+    if let Err(e) = served {
+        tracing::debug!(error = %e, "Metrics: Failed to serve a connection.");
+    }
+}
+
+// Returns the status and body of the response to a request
+fn respond(method: &Method, path: &str, metrics: &Metrics) -> (StatusCode, String) {
+    match (method, path) {
+        (&Method::GET, "/metrics") => (StatusCode::OK, metrics.render()),
+        (&Method::GET, _) => (StatusCode::NOT_FOUND, "Not Found\n".to_string()),
+        _ => (
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Method Not Allowed\n".to_string(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_render() {
+        let metrics = Metrics::new();
+        metrics.record_event_received();
+        metrics.record_event_received();
+        metrics.record_parse_failure();
+        metrics.record_forward(true, Duration::from_millis(20));
+        metrics.record_forward(false, Duration::from_secs(30));
+        metrics.record_cwl_api_call(CwlApi::FilterLogEvents);
+
+        let (status, body) = respond(&Method::GET, "/metrics", &metrics);
+        assert_eq!(status, StatusCode::OK);
+        for line in [
+            "livetrace_events_received_total 2",
+            "livetrace_parse_failures_total 1",
+            "livetrace_forward_requests_total{result=\"success\"} 1",
+            "livetrace_forward_requests_total{result=\"failure\"} 1",
+            "livetrace_forward_duration_seconds_bucket{le=\"0.01\"} 0",
+            "livetrace_forward_duration_seconds_bucket{le=\"0.025\"} 1",
+            "livetrace_forward_duration_seconds_bucket{le=\"10\"} 1",
+            "livetrace_forward_duration_seconds_bucket{le=\"+Inf\"} 2",
+            "livetrace_forward_duration_seconds_sum 30.02",
+            "livetrace_forward_duration_seconds_count 2",
+            "livetrace_cwl_api_calls_total{api=\"FilterLogEvents\"} 1",
+            "livetrace_cwl_api_calls_total{api=\"StartLiveTail\"} 0",
+        ] {
+            assert!(body.lines().any(|l| l == line), "{}", line);
+        }
+
+        assert_eq!(
+            respond(&Method::GET, "/", &metrics).0,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            respond(&Method::POST, "/metrics", &metrics).0,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_serve_connection() {
+        let listener = TcpListener::bind((DEFAULT_METRICS_ADDR, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            serve_connection(stream).await;
+        });
+
+        let response = reqwest::get(format!("http://{addr}/metrics"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(
+            response.headers()[reqwest::header::CONTENT_TYPE],
+            "text/plain; version=0.0.4; charset=utf-8"
+        );
+        assert!(response
+            .text()
+            .await
+            .unwrap()
+            .contains("# TYPE livetrace_events_received_total counter"));
+    }
+}
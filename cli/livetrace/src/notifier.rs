@@ -0,0 +1,280 @@
+//! Notifies of error spans as they arrive, so livetrace can be kept in the background.
+//!
+//! With `--notify-on-error`, a desktop notification is fired when a span with an error status,
+//! or with an attribute value matching `--grep`, is received, at most once per trace and once
+//! every few seconds. Desktop notifications are shown with `osascript` on macOS and `notify-send`
+//! on Linux. With `--notify-webhook`, a JSON description of the span is also POSTed to the URL.
+
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::trace::v1::status::StatusCode;
+use regex::Regex;
+use reqwest::Client as ReqwestClient;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time::Instant;
+
+use crate::console_display::get_string_value_for_grep;
+use crate::processing::resource_service_name;
+
+/// Minimum time between two notifications
+const MIN_NOTIFICATION_INTERVAL: Duration = Duration::from_secs(5);
+/// Number of notified trace IDs remembered to notify once per trace
+const MAX_NOTIFIED_TRACES: usize = 10_000;
+
+/// Why a span was notified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationReason {
+    Error,
+    GrepMatch,
+}
+
+/// A span worth a notification
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    pub trace_id: String,
+    pub service: String,
+    pub span_name: String,
+    pub reason: NotificationReason,
+}
+
+impl Notification {
+    fn title(&self) -> String {
+        match self.reason {
+            NotificationReason::Error => format!("livetrace: error in {}", self.service),
+            NotificationReason::GrepMatch => format!("livetrace: match in {}", self.service),
+        }
+    }
+
+    fn body(&self) -> String {
+        let short_trace_id = self.trace_id.get(..8).unwrap_or(&self.trace_id);
+        format!("{} (trace {short_trace_id})", self.span_name)
+    }
+}
+
+/// Returns the first span of the request with an error status, or else with an attribute value
+/// matching the grep regex, as a notification
+pub fn find_notification(
+    request: &ExportTraceServiceRequest,
+    grep_regex: Option<&Regex>,
+) -> Option<Notification> {
+    let spans = || {
+        request.resource_spans.iter().flat_map(|resource_spans| {
+            let service = resource_service_name(resource_spans.resource.as_ref())
+                .unwrap_or("unknown-service");
+            resource_spans
+                .scope_spans
+                .iter()
+                .flat_map(|scope_spans| &scope_spans.spans)
+                .map(move |span| (service, span))
+        })
+    };
+    let error = spans()
+        .find(|(_, span)| {
+            span.status
+                .as_ref()
+                .is_some_and(|status| status.code == StatusCode::Error as i32)
+        })
+        .map(|span| (span, NotificationReason::Error));
+    let grep_match = || {
+        let regex = grep_regex?;
+        spans()
+            .find(|(_, span)| {
+                span.attributes
+                    .iter()
+                    .chain(span.events.iter().flat_map(|event| &event.attributes))
+                    .any(|attribute| regex.is_match(&get_string_value_for_grep(&attribute.value)))
+            })
+            .map(|span| (span, NotificationReason::GrepMatch))
+    };
+    let ((service, span), reason) = error.or_else(grep_match)?;
+    Some(Notification {
+        trace_id: hex::encode(&span.trace_id),
+        service: service.to_string(),
+        span_name: span.name.clone(),
+        reason,
+    })
+}
+
+/// Sends the notifications, once per trace and at most one every few seconds
+pub struct ErrorNotifier {
+    desktop: bool,
+    webhook: Option<String>,
+    http_client: ReqwestClient,
+    notified_traces: HashSet<String>,
+    last_notified_at: Option<Instant>,
+}
+
+impl ErrorNotifier {
+    pub fn new(desktop: bool, webhook: Option<String>, http_client: ReqwestClient) -> Self {
+        Self {
+            desktop,
+            webhook,
+            http_client,
+            notified_traces: HashSet::new(),
+            last_notified_at: None,
+        }
+    }
+
+    /// Sends the notification unless its trace was already notified or the last notification
+    /// was sent too recently. Returns true if it was sent.
+    pub fn notify(&mut self, notification: Notification, now: Instant) -> bool {
+        if self.notified_traces.contains(&notification.trace_id)
+            || self
+                .last_notified_at
+                .is_some_and(|last| now.duration_since(last) < MIN_NOTIFICATION_INTERVAL)
+        {
+            tracing::debug!(trace_id = %notification.trace_id, "Notification suppressed.");
+            return false;
+        }
+        if self.notified_traces.len() >= MAX_NOTIFIED_TRACES {
+            self.notified_traces.clear();
+        }
+        self.notified_traces.insert(notification.trace_id.clone());
+        self.last_notified_at = Some(now);
+
+        if self.desktop {
+            show_desktop_notification(&notification.title(), &notification.body());
+        }
+        if let Some(webhook) = &self.webhook {
+            let request = self.http_client.post(webhook).json(&notification);
+            tokio::spawn(async move {
+                match request.send().await {
+                    Ok(response) if !response.status().is_success() => {
+                        tracing::warn!(status = %response.status(), "Notification webhook returned an error status.");
+                    }
+                    Ok(_) => tracing::debug!("Notification webhook called."),
+                    Err(_) => tracing::warn!("Failed to call the notification webhook."),
+                }
+            });
+        }
+        true
+    }
+}
+
+// Shows a desktop notification with the notifier of the platform, without waiting for it
+fn show_desktop_notification(title: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(title)
+        ));
+        command
+    } else if cfg!(target_os = "linux") {
+        let mut command = Command::new("notify-send");
+        command.arg("--app-name=livetrace").arg(title).arg(body);
+        command
+    } else {
+        tracing::debug!("Desktop notifications are not supported on this platform.");
+        return;
+    };
+    match command
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+    {
+        Ok(mut child) => {
+            tokio::spawn(async move {
+                let _ = child.wait().await;
+            });
+        }
+        Err(e) => tracing::warn!(error = %e, "Failed to show desktop notification."),
+    }
+}
+
+fn applescript_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span, Status};
+
+    #[tokio::test]
+    async fn test_error_notifications() {
+        let string_value = |value: &str| {
+            Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value.to_string())),
+            })
+        };
+        let mut request = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource {
+                    attributes: vec![KeyValue {
+                        key: "service.name".to_string(),
+                        value: string_value("checkout"),
+                    }],
+                    ..Default::default()
+                }),
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![Span {
+                        trace_id: vec![0xab; 16],
+                        name: "GET /cart".to_string(),
+                        attributes: vec![KeyValue {
+                            key: "order.id".to_string(),
+                            value: string_value("order-1234"),
+                        }],
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                schema_url: String::new(),
+            }],
+        };
+        assert_eq!(find_notification(&request, None), None);
+        let grep_regex = Regex::new("order-12").unwrap();
+        let notification = find_notification(&request, Some(&grep_regex)).unwrap();
+        assert_eq!(notification.reason, NotificationReason::GrepMatch);
+        assert_eq!(notification.service, "checkout");
+
+        request.resource_spans[0].scope_spans[0].spans.push(Span {
+            trace_id: vec![0xab; 16],
+            name: "charge".to_string(),
+            status: Some(Status {
+                code: StatusCode::Error as i32,
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let notification = find_notification(&request, Some(&grep_regex)).unwrap();
+        assert_eq!(notification.reason, NotificationReason::Error);
+        assert_eq!(notification.span_name, "charge");
+        assert_eq!(notification.trace_id, "ab".repeat(16));
+        assert_eq!(notification.body(), "charge (trace abababab)");
+
+        // Notified once per trace, and at most once every few seconds
+        let mut notifier = ErrorNotifier::new(false, None, ReqwestClient::new());
+        let now = Instant::now();
+        assert!(notifier.notify(notification.clone(), now));
+        assert!(!notifier.notify(notification.clone(), now + Duration::from_secs(60)));
+        let other_trace = Notification {
+            trace_id: "cd".repeat(16),
+            ..notification
+        };
+        assert!(!notifier.notify(other_trace.clone(), now + Duration::from_secs(1)));
+        assert!(notifier.notify(other_trace, now + MIN_NOTIFICATION_INTERVAL));
+    }
+
+    #[test]
+    fn test_notification_body_with_short_trace_id() {
+        let notification = Notification {
+            trace_id: String::new(),
+            service: "checkout".to_string(),
+            span_name: "charge".to_string(),
+            reason: NotificationReason::Error,
+        };
+        assert_eq!(notification.body(), "charge (trace )");
+        let notification = Notification {
+            trace_id: "abc".to_string(),
+            ..notification
+        };
+        assert_eq!(notification.body(), "charge (trace abc)");
+    }
+}
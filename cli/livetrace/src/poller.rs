@@ -3,37 +3,141 @@
 //! This module is responsible for:
 //! - Spawning an asynchronous task that periodically polls a set of log group ARNs.
 //! - Managing timestamps for each log group to fetch only new events since the last poll.
+//! - Backing off while polls find no new events, up to `MAX_BACKOFF_FACTOR` times the interval.
+//! - Keeping the number of `FilterLogEvents` calls within an optional per-minute budget.
 //! - Handling pagination for `FilterLogEvents` responses.
 //! - Processing log event messages from the polled data using functions from the
 //!   `processing` module.
 //! - Sending the resulting `TelemetryData` (or errors) over an MPSC channel to the main
 //!   application logic.
+//! - Sending the other log lines that carry a trace ID over a second channel, when logs are
+//!   correlated.
 
 use anyhow::Result;
 use aws_sdk_cloudwatchlogs::Client as CwlClient;
 use chrono::Utc;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::pin;
-use tokio::sync::mpsc;
-use tokio::time::interval;
-use tokio::time::sleep;
+use tokio::sync::{mpsc, watch};
+use tokio::time::{sleep, Instant};
 
+use crate::cli::RecordLevel;
+use crate::log_correlation::{correlate_log_line, CorrelatedLog};
+use crate::metrics::{CwlApi, METRICS};
 use crate::processing::{process_log_event_message, TelemetryData};
 
+/// Maximum factor of the poll interval reached by the backoff while no new events are found
+const MAX_BACKOFF_FACTOR: u32 = 8;
+
+/// Period over which the API call budget is counted
+const API_BUDGET_PERIOD: Duration = Duration::from_secs(60);
+
+/// Budget of FilterLogEvents calls per minute, shared by the polling tasks of all accounts.
+#[derive(Debug)]
+pub struct ApiBudget {
+    max_calls: usize,
+    calls: Mutex<VecDeque<Instant>>,
+    throttled: watch::Sender<bool>,
+}
+
+impl ApiBudget {
+    /// Creates a budget, with a receiver telling whether polling is paused by it
+    pub fn new(max_calls_per_minute: u32) -> (Self, watch::Receiver<bool>) {
+        let (throttled, throttled_rx) = watch::channel(false);
+        let budget = Self {
+            max_calls: max_calls_per_minute as usize,
+            calls: Mutex::new(VecDeque::new()),
+            throttled,
+        };
+        (budget, throttled_rx)
+    }
+
+    /// Waits until a call fits in the budget, and counts it
+    async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+                reserve_call(
+                    &mut calls,
+                    self.max_calls,
+                    API_BUDGET_PERIOD,
+                    Instant::now(),
+                )
+            };
+            let Some(wait) = wait else {
+                self.throttled.send_if_modified(std::mem::take);
+                return;
+            };
+            if self
+                .throttled
+                .send_if_modified(|throttled| !std::mem::replace(throttled, true))
+            {
+                tracing::debug!(
+                    wait_ms = wait.as_millis() as u64,
+                    "Polling Adapter: API call budget reached, pausing."
+                );
+            }
+            sleep(wait).await;
+        }
+    }
+}
+
+/// Counts a call made at `now` if fewer than `max_calls` were made during the period, or
+/// returns the time until the oldest call leaves the period.
+fn reserve_call(
+    calls: &mut VecDeque<Instant>,
+    max_calls: usize,
+    period: Duration,
+    now: Instant,
+) -> Option<Duration> {
+    while calls
+        .front()
+        .is_some_and(|&call| now.duration_since(call) >= period)
+    {
+        calls.pop_front();
+    }
+    match calls.front() {
+        Some(&oldest) if calls.len() >= max_calls => Some(period - now.duration_since(oldest)),
+        _ => {
+            calls.push_back(now);
+            None
+        }
+    }
+}
+
+/// Returns the delay before the next poll: the poll interval once events are found, or else
+/// twice the previous delay, up to `MAX_BACKOFF_FACTOR` times the interval.
+fn next_poll_delay(previous: Duration, poll_interval: Duration, events_found: bool) -> Duration {
+    if events_found {
+        poll_interval
+    } else {
+        (previous * 2).min(poll_interval * MAX_BACKOFF_FACTOR)
+    }
+}
+
 /// Spawns a task that polls FilterLogEvents for multiple log groups and sends results over a channel.
+/// The log lines correlated to a trace are sent to `log_sender` when one is given, and the records
+/// below `min_level` are skipped.
+#[allow(clippy::too_many_arguments)]
 pub fn start_polling_task(
     cwl_client: CwlClient,
     arns: Vec<String>,
     interval_millis: u64,
     sender: mpsc::Sender<Result<TelemetryData>>,
+    log_sender: Option<mpsc::Sender<CorrelatedLog>>,
+    min_level: Option<RecordLevel>,
     backtrace_ms: Option<u64>,
     session_timeout_millis: u64,
+    api_budget: Option<Arc<ApiBudget>>,
 ) {
     tokio::spawn(async move {
         let mut last_timestamps: HashMap<String, i64> = HashMap::new();
         let poll_duration = Duration::from_millis(interval_millis);
-        let mut ticker = interval(poll_duration);
+        let mut poll_delay = poll_duration;
+        let next_poll = sleep(Duration::ZERO);
+        pin!(next_poll);
 
         let session_duration = Duration::from_millis(session_timeout_millis);
         let session_timer = sleep(session_duration);
@@ -66,8 +170,9 @@ pub fn start_polling_task(
 
         loop {
             tokio::select! {
-                _ = ticker.tick() => {
+                _ = &mut next_poll => {
                     tracing::trace!("Polling Adapter: Tick");
+                    let mut events_found = false;
 
                     for arn in &arns {
                         let start_time = *last_timestamps.get(arn).unwrap_or(&initial_start_time_ms);
@@ -82,11 +187,15 @@ pub fn start_polling_task(
                             arn_clone.clone(),
                             start_time,
                             sender_clone.clone(),
+                            log_sender.as_ref(),
+                            min_level,
+                            api_budget.as_deref(),
                         )
                         .await
                         {
                             Ok(Some(new_timestamp)) => {
                                 tracing::trace!(log_group_arn=%arn_clone, %new_timestamp, "Polling Adapter: Updating timestamp.");
+                                events_found = true;
                                 last_timestamps.insert(arn_clone, new_timestamp);
                             }
                             Ok(None) => {
@@ -97,6 +206,12 @@ pub fn start_polling_task(
                             }
                         }
                     }
+
+                    poll_delay = next_poll_delay(poll_delay, poll_duration, events_found);
+                    if poll_delay > poll_duration {
+                        tracing::debug!(delay_ms = poll_delay.as_millis() as u64, "Polling Adapter: No new events, backing off.");
+                    }
+                    next_poll.as_mut().reset(Instant::now() + poll_delay);
                 }
                 _ = &mut session_timer => {
                     tracing::info!(timeout_ms = session_timeout_millis, "Polling Adapter: Session timeout reached. Stopping polling task.");
@@ -116,6 +231,9 @@ async fn filter_log_events_for_group(
     log_group_identifier: String,
     start_time_ms: i64,
     sender: mpsc::Sender<Result<TelemetryData>>,
+    log_sender: Option<&mpsc::Sender<CorrelatedLog>>,
+    min_level: Option<RecordLevel>,
+    api_budget: Option<&ApiBudget>,
 ) -> Result<Option<i64>> {
     let mut next_token: Option<String> = None;
     let mut latest_event_timestamp = start_time_ms;
@@ -131,6 +249,10 @@ async fn filter_log_events_for_group(
             request_builder = request_builder.next_token(token);
         }
 
+        if let Some(api_budget) = api_budget {
+            api_budget.acquire().await;
+        }
+        METRICS.record_cwl_api_call(CwlApi::FilterLogEvents);
         match request_builder.send().await {
             Ok(output) => {
                 if let Some(events) = output.events {
@@ -143,16 +265,29 @@ async fn filter_log_events_for_group(
                         }
 
                         if let Some(msg) = event.message {
-                            match process_log_event_message(&msg) {
+                            match process_log_event_message(&msg, min_level) {
                                 // Process the log event message and send telemetry data if available
                                 Ok(Some(telemetry)) => {
+                                    METRICS.record_event_received();
                                     if sender.send(Ok(telemetry)).await.is_err() {
                                         tracing::warn!("Polling Adapter: MPSC channel closed by receiver while sending data.");
                                         return Err(anyhow::anyhow!("MPSC receiver closed"));
                                     }
                                 }
-                                Ok(None) => {}
+                                Ok(None) => {
+                                    let correlated = log_sender.zip(correlate_log_line(
+                                        &msg,
+                                        event.timestamp.unwrap_or_default(),
+                                    ));
+                                    if let Some((log_sender, log)) = correlated {
+                                        if log_sender.send(log).await.is_err() {
+                                            tracing::warn!("Polling Adapter: Log channel closed by receiver while sending data.");
+                                            return Err(anyhow::anyhow!("MPSC receiver closed"));
+                                        }
+                                    }
+                                }
                                 Err(e) => {
+                                    METRICS.record_parse_failure();
                                     tracing::warn!(message = ?msg, error = %e, "Polling Adapter: Failed to process polled log event");
                                 }
                             }
@@ -190,3 +325,41 @@ async fn filter_log_events_for_group(
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poll_delay_backs_off_until_events() {
+        let interval = Duration::from_secs(10);
+        let mut delay = interval;
+        for expected_secs in [20, 40, 80, 80] {
+            delay = next_poll_delay(delay, interval, false);
+            assert_eq!(delay, Duration::from_secs(expected_secs));
+        }
+        assert_eq!(next_poll_delay(delay, interval, true), interval);
+    }
+
+    #[test]
+    fn test_reserve_call_within_budget() {
+        let period = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut calls = VecDeque::new();
+        assert_eq!(reserve_call(&mut calls, 2, period, start), None);
+        assert_eq!(
+            reserve_call(&mut calls, 2, period, start + Duration::from_secs(20)),
+            None
+        );
+        // The budget is spent until the first call is a minute old
+        assert_eq!(
+            reserve_call(&mut calls, 2, period, start + Duration::from_secs(45)),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(
+            reserve_call(&mut calls, 2, period, start + Duration::from_secs(60)),
+            None
+        );
+        assert_eq!(calls.len(), 2);
+    }
+}
@@ -10,16 +10,25 @@
 //! - Compressing payloads using Gzip.
 //! - Sending telemetry payloads to an OTLP HTTP endpoint.
 
+use crate::cli::{RecordLevel, StatusFilter};
+use crate::metrics::METRICS;
 use anyhow::{anyhow, Context, Result};
 use base64::{engine::general_purpose, Engine};
+use clap::ValueEnum;
 use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use globset::{Glob, GlobMatcher};
 use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::common::v1::any_value;
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use opentelemetry_proto::tonic::trace::v1::{status::StatusCode, Span};
 use otlp_stdout_span_exporter::ExporterOutput;
 use prost::Message;
 use reqwest::header::HeaderMap;
 use reqwest::Client as ReqwestClient;
 use reqwest::Url;
+use std::collections::{HashMap, HashSet};
 use std::io::{Read, Write};
+use std::time::Instant;
 
 /// Represents a processed OTLP payload ready for potential compaction or sending.
 #[derive(Clone, Debug)]
@@ -43,8 +52,273 @@ impl Default for SpanCompactionConfig {
     }
 }
 
-/// Processes a single CloudWatch Live Tail log event message string.
-pub fn process_log_event_message(message: &str) -> Result<Option<TelemetryData>> {
+/// Trace filters applied before display and forwarding.
+///
+/// A trace is kept when at least one of its spans matches every filter that is set, so the
+/// whole trace of a slow or failing span is shown. Excluded spans are dropped from the trace
+/// first, along with their child spans.
+#[derive(Debug, Clone, Default)]
+pub struct TraceFilter {
+    pub service: Option<String>,
+    pub span_name: Option<String>,
+    pub status: Option<StatusFilter>,
+    pub min_duration_ms: Option<u64>,
+    pub exclude_span_names: Vec<String>,
+    pub exclude_services: Vec<String>,
+    pub exclude_attrs: Vec<(String, GlobMatcher)>,
+}
+
+/// Head sampler keeping a ratio of the traces, and every batch with an error span.
+///
+/// Like the `TraceIdRatioBased` sampler of OpenTelemetry, a trace is sampled when the last 8
+/// bytes of its ID fall below the ratio, so all the batches of a trace get the same decision.
+#[derive(Debug, Clone, Copy)]
+pub struct TraceSampler {
+    threshold: u64,
+}
+
+impl TraceSampler {
+    pub fn new(ratio: f64) -> Self {
+        Self {
+            threshold: (ratio.clamp(0.0, 1.0) * (1u64 << 63) as f64) as u64,
+        }
+    }
+
+    /// Returns true if the trace of this ID is sampled
+    pub fn samples_trace_id(&self, trace_id: &[u8]) -> bool {
+        let Some(bytes) = trace_id
+            .get(8..16)
+            .and_then(|bytes| <[u8; 8]>::try_from(bytes).ok())
+        else {
+            return true;
+        };
+        u64::from_be_bytes(bytes) >> 1 < self.threshold
+    }
+
+    /// Returns true if the batch of spans should be kept: its trace is sampled, or one of its
+    /// spans has an error status
+    pub fn keeps(&self, request: &ExportTraceServiceRequest) -> bool {
+        let mut spans = request
+            .resource_spans
+            .iter()
+            .flat_map(|resource_spans| &resource_spans.scope_spans)
+            .flat_map(|scope_spans| &scope_spans.spans);
+        let Some(first_span) = spans.next() else {
+            return true;
+        };
+        self.samples_trace_id(&first_span.trace_id)
+            || std::iter::once(first_span).chain(spans).any(|span| {
+                span.status
+                    .as_ref()
+                    .is_some_and(|status| status.code == StatusCode::Error as i32)
+            })
+    }
+}
+
+/// Parses `key=glob` attribute exclusions.
+pub fn parse_exclude_attrs(values: &[String]) -> Result<Vec<(String, GlobMatcher)>> {
+    values
+        .iter()
+        .map(|value| {
+            let (key, glob) = value
+                .split_once('=')
+                .filter(|(key, _)| !key.is_empty())
+                .ok_or_else(|| {
+                    anyhow!("Invalid attribute exclusion '{}', expected key=glob", value)
+                })?;
+            let matcher = Glob::new(glob)
+                .with_context(|| format!("Invalid glob in attribute exclusion '{}'", value))?
+                .compile_matcher();
+            Ok((key.to_string(), matcher))
+        })
+        .collect()
+}
+
+impl TraceFilter {
+    /// Returns true if no filter is set
+    pub fn is_empty(&self) -> bool {
+        self.service.is_none()
+            && self.span_name.is_none()
+            && self.status.is_none()
+            && self.min_duration_ms.is_none()
+    }
+
+    /// Returns true if a span of the batch matches every filter
+    pub fn matches(&self, batch: &[TelemetryData]) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        batch.iter().any(|telemetry| {
+            let Ok(request) = decode_otlp_payload(&telemetry.payload) else {
+                return false;
+            };
+            request.resource_spans.iter().any(|resource_spans| {
+                let service_matches = self.service.as_ref().is_none_or(|service| {
+                    resource_service_name(resource_spans.resource.as_ref())
+                        == Some(service.as_str())
+                });
+                service_matches
+                    && resource_spans
+                        .scope_spans
+                        .iter()
+                        .flat_map(|scope_spans| &scope_spans.spans)
+                        .any(|span| self.matches_span(span))
+            })
+        })
+    }
+
+    /// Returns true if an exclusion is set
+    pub fn has_exclusions(&self) -> bool {
+        !self.exclude_span_names.is_empty()
+            || !self.exclude_services.is_empty()
+            || !self.exclude_attrs.is_empty()
+    }
+
+    /// Drops the excluded spans of a batch and their descendants, and the items left empty
+    pub fn exclude_spans(&self, batch: Vec<TelemetryData>) -> Vec<TelemetryData> {
+        if !self.has_exclusions() {
+            return batch;
+        }
+        let mut requests: Vec<(TelemetryData, ExportTraceServiceRequest)> = batch
+            .into_iter()
+            .filter_map(|telemetry| {
+                let request = decode_otlp_payload(&telemetry.payload).ok()?;
+                Some((telemetry, request))
+            })
+            .collect();
+
+        // Spans are excluded when they or one of their ancestors match an exclusion
+        let mut parents: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        let mut excluded: HashSet<Vec<u8>> = HashSet::new();
+        for (_, request) in &requests {
+            for resource_spans in &request.resource_spans {
+                let service = resource_service_name(resource_spans.resource.as_ref());
+                let service_excluded = service.is_some_and(|service| {
+                    self.exclude_services.iter().any(|name| name == service)
+                });
+                for span in resource_spans
+                    .scope_spans
+                    .iter()
+                    .flat_map(|scope_spans| &scope_spans.spans)
+                {
+                    parents.insert(span.span_id.clone(), span.parent_span_id.clone());
+                    if service_excluded || self.excludes_span(span) {
+                        excluded.insert(span.span_id.clone());
+                    }
+                }
+            }
+        }
+        if excluded.is_empty() {
+            return requests
+                .into_iter()
+                .map(|(telemetry, _)| telemetry)
+                .collect();
+        }
+        loop {
+            let descendants: Vec<Vec<u8>> = parents
+                .iter()
+                .filter(|(span_id, parent_id)| {
+                    !excluded.contains(*span_id) && excluded.contains(*parent_id)
+                })
+                .map(|(span_id, _)| span_id.clone())
+                .collect();
+            if descendants.is_empty() {
+                break;
+            }
+            excluded.extend(descendants);
+        }
+
+        for (telemetry, request) in &mut requests {
+            for resource_spans in &mut request.resource_spans {
+                for scope_spans in &mut resource_spans.scope_spans {
+                    scope_spans
+                        .spans
+                        .retain(|span| !excluded.contains(&span.span_id));
+                }
+                resource_spans
+                    .scope_spans
+                    .retain(|scope_spans| !scope_spans.spans.is_empty());
+            }
+            request
+                .resource_spans
+                .retain(|resource_spans| !resource_spans.scope_spans.is_empty());
+            telemetry.payload = request.encode_to_vec();
+        }
+        requests
+            .into_iter()
+            .filter(|(_, request)| !request.resource_spans.is_empty())
+            .map(|(telemetry, _)| telemetry)
+            .collect()
+    }
+
+    fn excludes_span(&self, span: &Span) -> bool {
+        self.exclude_span_names.contains(&span.name)
+            || self.exclude_attrs.iter().any(|(key, matcher)| {
+                span.attributes.iter().any(|attribute| {
+                    attribute.key == *key
+                        && attribute
+                            .value
+                            .as_ref()
+                            .and_then(|value| value.value.as_ref())
+                            .and_then(any_value_to_string)
+                            .is_some_and(|value| matcher.is_match(value))
+                })
+            })
+    }
+
+    fn matches_span(&self, span: &Span) -> bool {
+        let status_code = span.status.as_ref().map_or(0, |status| status.code);
+        let duration_ms = span
+            .end_time_unix_nano
+            .saturating_sub(span.start_time_unix_nano)
+            / 1_000_000;
+        self.span_name
+            .as_ref()
+            .is_none_or(|name| span.name == *name)
+            && self.status.is_none_or(|status| {
+                status_code
+                    == match status {
+                        StatusFilter::Unset => StatusCode::Unset,
+                        StatusFilter::Ok => StatusCode::Ok,
+                        StatusFilter::Error => StatusCode::Error,
+                    } as i32
+            })
+            && self
+                .min_duration_ms
+                .is_none_or(|min_duration_ms| duration_ms >= min_duration_ms)
+    }
+}
+
+fn any_value_to_string(value: &any_value::Value) -> Option<String> {
+    match value {
+        any_value::Value::StringValue(value) => Some(value.clone()),
+        any_value::Value::BoolValue(value) => Some(value.to_string()),
+        any_value::Value::IntValue(value) => Some(value.to_string()),
+        any_value::Value::DoubleValue(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Returns the `service.name` attribute of a resource
+pub(crate) fn resource_service_name(resource: Option<&Resource>) -> Option<&str> {
+    resource?
+        .attributes
+        .iter()
+        .find(|attribute| attribute.key == "service.name")
+        .and_then(
+            |attribute| match attribute.value.as_ref()?.value.as_ref()? {
+                any_value::Value::StringValue(name) => Some(name.as_str()),
+                _ => None,
+            },
+        )
+}
+
+/// Processes a single CloudWatch Live Tail log event message string. The records with a lower
+/// level than `min_level` are skipped before their payload is decoded.
+pub fn process_log_event_message(
+    message: &str,
+    min_level: Option<RecordLevel>,
+) -> Result<Option<TelemetryData>> {
     tracing::trace!(message, "Processing log event message");
     let record: ExporterOutput = match serde_json::from_str::<ExporterOutput>(message) {
         Ok(output) => {
@@ -65,6 +339,18 @@ pub fn process_log_event_message(message: &str) -> Result<Option<TelemetryData>>
 
     tracing::debug!(source = %record.source, endpoint = %record.endpoint, "Parsed OTLP/stdout record");
 
+    if let Some(min_level) = min_level {
+        // Records with a level unknown to livetrace are not skipped
+        let level = record
+            .level
+            .as_deref()
+            .and_then(|level| RecordLevel::from_str(level, true).ok());
+        if level.is_some_and(|level| level < min_level) {
+            tracing::trace!(source = %record.source, ?level, "Record below the minimum level, skipping.");
+            return Ok(None);
+        }
+    }
+
     let raw_payload = if record.base64 {
         general_purpose::STANDARD
             .decode(&record.payload)
@@ -74,6 +360,10 @@ pub fn process_log_event_message(message: &str) -> Result<Option<TelemetryData>>
         record.payload.as_bytes().to_vec()
     };
 
+    if !record.verify_payload_sha256(&raw_payload) {
+        return Err(anyhow!("Payload checksum mismatch"));
+    }
+
     let protobuf_payload = convert_to_protobuf(
         raw_payload,
         &record.content_type,
@@ -219,7 +509,7 @@ pub fn compact_telemetry_payloads(
     })
 }
 
-fn decode_otlp_payload(payload: &[u8]) -> Result<ExportTraceServiceRequest> {
+pub(crate) fn decode_otlp_payload(payload: &[u8]) -> Result<ExportTraceServiceRequest> {
     ExportTraceServiceRequest::decode(payload).context("Failed to decode OTLP protobuf payload")
 }
 
@@ -262,6 +552,7 @@ pub async fn send_telemetry_payload(
 
     tracing::debug!(url = %target_url, payload_size=payload.len(), "Sending OTLP HTTP request");
 
+    let started = Instant::now();
     let response = http_client
         .post(target_url.clone()) // Clone target_url for potential use in context
         .headers(headers)
@@ -269,8 +560,15 @@ pub async fn send_telemetry_payload(
         .header("Content-Encoding", "gzip")
         .body(payload)
         .send()
-        .await
-        .with_context(|| format!("Failed to send OTLP request to {}", target_url))?;
+        .await;
+    METRICS.record_forward(
+        response
+            .as_ref()
+            .is_ok_and(|response| response.status().is_success()),
+        started.elapsed(),
+    );
+    let response =
+        response.with_context(|| format!("Failed to send OTLP request to {}", target_url))?;
 
     if !response.status().is_success() {
         let status = response.status();
@@ -341,14 +639,29 @@ mod tests {
             method: String::new(), // Assume method is not Option based on previous error
             headers: Some(std::collections::HashMap::new()), // Wrap in Some()
             level: Some("info".to_string()), // Wrap in Some()
+            payload_sha256: None,
+            batch_id: None,
+            compression_backend: None,
         };
 
         let json_message = serde_json::to_string(&exporter_output).unwrap();
 
-        let result = process_log_event_message(&json_message); // grep_regex argument removed
+        let result = process_log_event_message(&json_message, None); // grep_regex argument removed
         assert!(result.is_ok());
         let result = result.unwrap();
 
+        // The record is skipped below its level
+        assert!(
+            process_log_event_message(&json_message, Some(RecordLevel::Warn))
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            process_log_event_message(&json_message, Some(RecordLevel::Info))
+                .unwrap()
+                .is_some()
+        );
+
         assert!(result.is_some());
         let telemetry_data = result.unwrap();
         assert_eq!(telemetry_data.original_source, "test_source");
@@ -464,7 +777,7 @@ mod tests {
     #[test]
     fn test_process_log_event_message_invalid_json() {
         let invalid_json_message = "{ not json \"";
-        let result = process_log_event_message(invalid_json_message); // grep_regex argument removed
+        let result = process_log_event_message(invalid_json_message, None); // grep_regex argument removed
         assert!(result.is_ok());
         assert!(result.unwrap().is_none()); // Expect Ok(None) for parsing errors
     }
@@ -484,12 +797,15 @@ mod tests {
             method: String::new(),
             headers: Some(std::collections::HashMap::new()),
             level: Some("info".to_string()),
+            payload_sha256: None,
+            batch_id: None,
+            compression_backend: None,
         };
 
         let json_message = serde_json::to_string(&exporter_output).unwrap();
 
         // Expect an Err result because base64 decoding fails
-        let result = process_log_event_message(&json_message); // grep_regex argument removed
+        let result = process_log_event_message(&json_message, None); // grep_regex argument removed
         assert!(result.is_err());
         // Optionally check the error message content
         assert!(result
@@ -514,12 +830,15 @@ mod tests {
             method: String::new(),
             headers: Some(std::collections::HashMap::new()),
             level: Some("info".to_string()),
+            payload_sha256: None,
+            batch_id: None,
+            compression_backend: None,
         };
 
         let json_message = serde_json::to_string(&exporter_output).unwrap();
 
         // Expect an Err result because gzip decoding fails
-        let result = process_log_event_message(&json_message); // grep_regex argument removed
+        let result = process_log_event_message(&json_message, None); // grep_regex argument removed
         assert!(result.is_err()); // Just check that it errors, context might be less specific
                                   // assert!(result.unwrap_err().to_string().contains("Failed to decompress Gzip payload")); // Removed specific context check
     }
@@ -556,6 +875,152 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_trace_filter_matches_any_span() {
+        use opentelemetry_proto::tonic::trace::v1::Status;
+
+        let mut request = create_dummy_request_with_service("checkout");
+        request.resource_spans[0].scope_spans[0].spans = vec![
+            Span {
+                name: "GET /cart".to_string(),
+                start_time_unix_nano: 0,
+                end_time_unix_nano: 50_000_000,
+                ..Default::default()
+            },
+            Span {
+                name: "charge".to_string(),
+                start_time_unix_nano: 0,
+                end_time_unix_nano: 300_000_000,
+                status: Some(Status {
+                    code: StatusCode::Error as i32,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        ];
+        let batch = vec![TelemetryData {
+            payload: request.encode_to_vec(),
+            original_endpoint: String::new(),
+            original_source: String::new(),
+        }];
+
+        let filter =
+            |service: Option<&str>, span_name: Option<&str>, min_duration_ms| TraceFilter {
+                service: service.map(str::to_string),
+                span_name: span_name.map(str::to_string),
+                status: Some(StatusFilter::Error),
+                min_duration_ms,
+                ..Default::default()
+            };
+        assert!(TraceFilter::default().matches(&batch));
+        assert!(filter(Some("checkout"), None, Some(200)).matches(&batch));
+        assert!(filter(None, Some("charge"), None).matches(&batch));
+        // Filters must all match the same span
+        assert!(!filter(None, Some("GET /cart"), None).matches(&batch));
+        assert!(!filter(Some("checkout"), None, Some(500)).matches(&batch));
+        assert!(!filter(Some("payments"), None, None).matches(&batch));
+    }
+
+    #[test]
+    fn test_trace_sampler() {
+        use opentelemetry_proto::tonic::trace::v1::Status;
+
+        let trace_id = |last: u64| [[0u8; 8], last.to_be_bytes()].concat();
+        let sampler = TraceSampler::new(0.25);
+        assert!(sampler.samples_trace_id(&trace_id(0)));
+        assert!(sampler.samples_trace_id(&trace_id(u64::MAX / 4 - 2)));
+        assert!(!sampler.samples_trace_id(&trace_id(u64::MAX / 4 + 2)));
+        assert!(!sampler.samples_trace_id(&trace_id(u64::MAX)));
+        assert!(TraceSampler::new(1.0).samples_trace_id(&trace_id(u64::MAX)));
+        assert!(!TraceSampler::new(0.0).samples_trace_id(&trace_id(0)));
+
+        let sampled_count = (0..1000u64)
+            .filter(|i| sampler.samples_trace_id(&trace_id(i.wrapping_mul(0x9E37_79B9_7F4A_7C15))))
+            .count();
+        assert!((200..300).contains(&sampled_count), "{}", sampled_count);
+
+        // Batches of unsampled traces are kept when they have an error span
+        let mut request = create_dummy_request_with_service("checkout");
+        request.resource_spans[0].scope_spans[0].spans = vec![Span {
+            trace_id: trace_id(u64::MAX),
+            ..Default::default()
+        }];
+        assert!(!sampler.keeps(&request));
+        request.resource_spans[0].scope_spans[0].spans.push(Span {
+            trace_id: trace_id(u64::MAX),
+            status: Some(Status {
+                code: StatusCode::Error as i32,
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        assert!(sampler.keeps(&request));
+    }
+
+    #[test]
+    fn test_trace_filter_excludes_spans_and_children() {
+        let span = |id: u8, parent: Option<u8>, name: &str| Span {
+            span_id: vec![id; 8],
+            parent_span_id: parent.map(|parent| vec![parent; 8]).unwrap_or_default(),
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let mut describe_table = span(3, Some(1), "DynamoDB.DescribeTable");
+        describe_table.attributes.push(KeyValue {
+            key: "rpc.method".to_string(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue("DescribeTable".to_string())),
+            }),
+        });
+        let mut checkout = create_dummy_request_with_service("checkout");
+        checkout.resource_spans[0].scope_spans[0].spans = vec![
+            span(1, None, "GET /cart"),
+            span(2, Some(1), "GET /healthz"),
+            describe_table,
+        ];
+        let mut cache = create_dummy_request_with_service("cache");
+        cache.resource_spans[0].scope_spans[0].spans = vec![span(4, Some(2), "lookup")];
+        let batch: Vec<TelemetryData> = [checkout, cache]
+            .iter()
+            .map(|request| TelemetryData {
+                payload: request.encode_to_vec(),
+                original_endpoint: String::new(),
+                original_source: String::new(),
+            })
+            .collect();
+        let span_names = |batch: &[TelemetryData]| -> Vec<String> {
+            batch
+                .iter()
+                .flat_map(|telemetry| {
+                    decode_otlp_payload(&telemetry.payload)
+                        .unwrap()
+                        .resource_spans
+                })
+                .flat_map(|resource_spans| resource_spans.scope_spans)
+                .flat_map(|scope_spans| scope_spans.spans)
+                .map(|span| span.name)
+                .collect()
+        };
+
+        let filter = TraceFilter {
+            exclude_span_names: vec!["GET /healthz".to_string()],
+            exclude_attrs: parse_exclude_attrs(&["rpc.method=Describe*".to_string()]).unwrap(),
+            ..Default::default()
+        };
+        // The cache span is dropped as a child of the health check
+        let kept = filter.exclude_spans(batch.clone());
+        assert_eq!(kept.len(), 1);
+        assert_eq!(span_names(&kept), vec!["GET /cart"]);
+
+        let filter = TraceFilter {
+            exclude_services: vec!["checkout".to_string()],
+            ..Default::default()
+        };
+        assert!(filter.exclude_spans(batch.clone()).is_empty());
+        assert_eq!(TraceFilter::default().exclude_spans(batch).len(), 2);
+        assert!(parse_exclude_attrs(&["http.route".to_string()]).is_err());
+    }
+
     // TODO: Add tests for process_log_event_message (errors)
     // TODO: Add tests for convert_to_protobuf
 }
@@ -0,0 +1,160 @@
+//! Records the rendered console output of a session as an asciinema (asciicast v2) file.
+//!
+//! The recording captures exactly what livetrace prints to stdout (the configuration preamble,
+//! trace waterfalls and timeline logs, including colors) together with the time at which each
+//! chunk was printed, so a debugging session can be replayed with `asciinema play` or embedded
+//! in docs and tickets. It is independent of the telemetry itself: nothing is re-rendered on
+//! playback.
+//!
+//! Output goes through the `console_println!` and `console_print!` macros, which print to
+//! stdout and, when a recording is active, append the same text to the cast file.
+
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Terminal height used in the header when it cannot be determined
+pub const DEFAULT_TERMINAL_HEIGHT: usize = 24;
+
+static ACTIVE_RECORDER: Mutex<Option<SessionRecorder>> = Mutex::new(None);
+
+/// Writer for a single asciicast v2 recording
+pub struct SessionRecorder {
+    writer: BufWriter<File>,
+    started_at: Instant,
+}
+
+impl SessionRecorder {
+    /// Creates the cast file and writes the asciicast header
+    pub fn create(path: &Path, width: usize, height: usize) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create recording file {}", path.display()))?;
+        let mut recorder = Self {
+            writer: BufWriter::new(file),
+            started_at: Instant::now(),
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let header = json!({
+            "version": 2,
+            "width": width,
+            "height": height,
+            "timestamp": timestamp,
+            "title": "livetrace",
+            "env": { "TERM": std::env::var("TERM").unwrap_or_else(|_| "xterm-256color".to_string()) },
+        });
+        writeln!(recorder.writer, "{}", header).context("Failed to write recording header")?;
+        recorder
+            .writer
+            .flush()
+            .context("Failed to write recording header")?;
+        Ok(recorder)
+    }
+
+    /// Appends an output event with the time elapsed since the recording started
+    pub fn record_output(&mut self, text: &str) -> Result<()> {
+        self.record_output_at(self.started_at.elapsed().as_secs_f64(), text)
+    }
+
+    fn record_output_at(&mut self, elapsed_secs: f64, text: &str) -> Result<()> {
+        // A terminal translates "\n" into "\r\n"; players expect the translated form.
+        let data = text.replace("\r\n", "\n").replace('\n', "\r\n");
+        let event = json!([
+            (elapsed_secs * 1_000_000.0).round() / 1_000_000.0,
+            "o",
+            data
+        ]);
+        writeln!(self.writer, "{}", event).context("Failed to write recording event")?;
+        // Flush every event so the recording survives the session being interrupted.
+        self.writer
+            .flush()
+            .context("Failed to write recording event")
+    }
+}
+
+/// Starts recording the console output of this session to `path`
+pub fn start_recording(path: &Path, width: usize, height: usize) -> Result<()> {
+    let recorder = SessionRecorder::create(path, width, height)?;
+    *ACTIVE_RECORDER.lock().unwrap_or_else(|e| e.into_inner()) = Some(recorder);
+    Ok(())
+}
+
+/// Stops the active recording, if any
+pub fn stop_recording() {
+    ACTIVE_RECORDER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take();
+}
+
+/// Prints `text` to stdout and appends it to the active recording
+pub fn emit(text: &str) {
+    print!("{}", text);
+    let mut active = ACTIVE_RECORDER.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(recorder) = active.as_mut() {
+        if let Err(e) = recorder.record_output(text) {
+            tracing::warn!(error = %e, "Failed to write session recording; recording stopped.");
+            *active = None;
+        }
+    }
+}
+
+/// Like `println!`, but also records the output when a session recording is active.
+macro_rules! console_println {
+    () => {
+        $crate::recorder::emit("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::recorder::emit(&format!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+/// Like `print!`, but also records the output when a session recording is active.
+macro_rules! console_print {
+    ($($arg:tt)*) => {
+        $crate::recorder::emit(&format!($($arg)*))
+    };
+}
+
+pub(crate) use {console_print, console_println};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_recording_writes_asciicast_v2() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("session.cast");
+
+        let mut recorder = SessionRecorder::create(&path, 120, 40).unwrap();
+        recorder.record_output_at(0.0, "Trace ID: abc\n").unwrap();
+        recorder
+            .record_output_at(1.5, "\x1b[1mbold\x1b[0m")
+            .unwrap();
+        drop(recorder);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 120);
+        assert_eq!(lines[0]["height"], 40);
+
+        assert_eq!(lines[1], json!([0.0, "o", "Trace ID: abc\r\n"]));
+        assert_eq!(lines[2], json!([1.5, "o", "\x1b[1mbold\x1b[0m"]));
+    }
+}
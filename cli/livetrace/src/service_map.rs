@@ -0,0 +1,295 @@
+//! Builds a service dependency graph from the client/server relationships of flushed traces.
+//!
+//! A call from one service to another is found where a span has a parent span of another
+//! service, and its latency is the duration of the caller span when it is a client or producer
+//! span. Client spans with no span of another service beneath them, such as calls to AWS
+//! services or other uninstrumented dependencies, are attributed to the service named by their
+//! `peer.service`, `rpc.service` or `server.address` attribute. `--service-map` prints the graph
+//! as a list of calls per service, and `--service-map-dot` writes it as a Graphviz DOT file.
+
+use anyhow::{Context, Result};
+use colored::*;
+use opentelemetry_proto::tonic::common::v1::any_value;
+use opentelemetry_proto::tonic::trace::v1::{span::SpanKind, Span};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::console_display::get_terminal_width;
+use crate::processing::{decode_otlp_payload, resource_service_name, TelemetryData};
+use crate::recorder::console_println;
+use crate::terminal::glyphs;
+
+// Attributes naming the service called by a client span, in order of preference
+const PEER_SERVICE_ATTRIBUTES: [&str; 3] = ["peer.service", "rpc.service", "server.address"];
+
+/// Calls from one service to another
+#[derive(Debug, Default)]
+struct Edge {
+    durations_ms: Vec<f64>,
+}
+
+impl Edge {
+    fn p95_ms(&self) -> f64 {
+        let mut durations = self.durations_ms.clone();
+        durations.sort_by(f64::total_cmp);
+        let rank = (0.95 * durations.len() as f64).ceil() as usize;
+        durations
+            .get(rank.clamp(1, durations.len().max(1)) - 1)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Service dependency graph aggregated over the flushed traces
+#[derive(Debug, Default)]
+pub struct ServiceMap {
+    edges: BTreeMap<(String, String), Edge>,
+    changed: bool,
+}
+
+impl ServiceMap {
+    /// Adds the calls between services of a trace to the graph
+    pub fn record(&mut self, batch: &[TelemetryData]) {
+        let mut spans: HashMap<&[u8], (&str, &Span)> = HashMap::new();
+        let requests: Vec<_> = batch
+            .iter()
+            .filter_map(|telemetry| decode_otlp_payload(&telemetry.payload).ok())
+            .collect();
+        for resource_spans in requests.iter().flat_map(|request| &request.resource_spans) {
+            let service = resource_service_name(resource_spans.resource.as_ref())
+                .unwrap_or("unknown-service");
+            for span in resource_spans
+                .scope_spans
+                .iter()
+                .flat_map(|scope_spans| &scope_spans.spans)
+            {
+                spans.insert(span.span_id.as_slice(), (service, span));
+            }
+        }
+
+        let mut callers_with_remote_child: HashSet<&[u8]> = HashSet::new();
+        for (service, span) in spans.values() {
+            let Some((parent_service, parent)) = spans.get(span.parent_span_id.as_slice()) else {
+                continue;
+            };
+            if parent_service == service {
+                continue;
+            }
+            callers_with_remote_child.insert(parent.span_id.as_slice());
+            let caller_is_client =
+                parent.kind == SpanKind::Client as i32 || parent.kind == SpanKind::Producer as i32;
+            let duration_span = if caller_is_client { parent } else { span };
+            self.add_call(parent_service, service, duration_ms(duration_span));
+        }
+
+        for (service, span) in spans.values() {
+            if span.kind != SpanKind::Client as i32
+                || callers_with_remote_child.contains(span.span_id.as_slice())
+            {
+                continue;
+            }
+            if let Some(peer) = peer_service(span).filter(|peer| peer != service) {
+                self.add_call(service, &peer, duration_ms(span));
+            }
+        }
+    }
+
+    fn add_call(&mut self, caller: &str, callee: &str, duration_ms: f64) {
+        self.edges
+            .entry((caller.to_string(), callee.to_string()))
+            .or_default()
+            .durations_ms
+            .push(duration_ms);
+        self.changed = true;
+    }
+
+    /// Returns true if calls were recorded since the last call, and resets the flag
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// Prints the calls of each service
+    pub fn print(&self) {
+        let width = get_terminal_width(80);
+        let heading = "Service Map";
+        console_println!(
+            "\n{} {} {}\n",
+            glyphs().rule.dimmed(),
+            heading.bold(),
+            glyphs()
+                .rule
+                .repeat(width.saturating_sub(heading.len() + 3))
+                .dimmed()
+        );
+        if self.edges.is_empty() {
+            console_println!("  {}\n", "No calls between services received.".dimmed());
+            return;
+        }
+
+        let callee_width = self
+            .edges
+            .keys()
+            .map(|(_, callee)| callee.chars().count())
+            .max()
+            .unwrap_or_default();
+        let mut current_caller: Option<&str> = None;
+        let mut edges = self.edges.iter().peekable();
+        while let Some(((caller, callee), edge)) = edges.next() {
+            if current_caller != Some(caller.as_str()) {
+                console_println!("  {}", caller.bold());
+                current_caller = Some(caller.as_str());
+            }
+            let is_last = edges
+                .peek()
+                .is_none_or(|((next_caller, _), _)| next_caller != caller);
+            console_println!(
+                "  {} {:<callee_width$}  {}",
+                if is_last {
+                    glyphs().last_branch
+                } else {
+                    glyphs().branch
+                }
+                .dimmed(),
+                callee,
+                format!(
+                    "{:>6} calls  p95 {:.1} ms",
+                    edge.durations_ms.len(),
+                    edge.p95_ms()
+                )
+                .dimmed(),
+            );
+        }
+        console_println!();
+    }
+
+    /// Renders the graph in the Graphviz DOT format
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph services {\n  rankdir=LR;\n  node [shape=box];\n");
+        for ((caller, callee), edge) in &self.edges {
+            let _ = writeln!(
+                dot,
+                "  \"{}\" -> \"{}\" [label=\"{} calls\\np95 {:.1} ms\"];",
+                escape_dot(caller),
+                escape_dot(callee),
+                edge.durations_ms.len(),
+                edge.p95_ms()
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Writes the graph to a Graphviz DOT file
+    pub fn write_dot(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_dot())
+            .with_context(|| format!("Failed to write service map to {}", path.display()))
+    }
+}
+
+fn duration_ms(span: &Span) -> f64 {
+    span.end_time_unix_nano
+        .saturating_sub(span.start_time_unix_nano) as f64
+        / 1_000_000.0
+}
+
+fn peer_service(span: &Span) -> Option<String> {
+    PEER_SERVICE_ATTRIBUTES.iter().find_map(|key| {
+        span.attributes
+            .iter()
+            .find(|attribute| attribute.key == *key)
+            .and_then(
+                |attribute| match attribute.value.as_ref()?.value.as_ref()? {
+                    any_value::Value::StringValue(value) if !value.is_empty() => {
+                        Some(value.clone())
+                    }
+                    _ => None,
+                },
+            )
+    })
+}
+
+fn escape_dot(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+    use opentelemetry_proto::tonic::common::v1::{AnyValue, KeyValue};
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans};
+    use prost::Message;
+
+    fn string_attribute(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value.to_string())),
+            }),
+        }
+    }
+
+    fn span(id: u8, parent: Option<u8>, kind: SpanKind, duration_ms: u64) -> Span {
+        Span {
+            span_id: vec![id; 8],
+            parent_span_id: parent.map(|parent| vec![parent; 8]).unwrap_or_default(),
+            kind: kind as i32,
+            end_time_unix_nano: duration_ms * 1_000_000,
+            ..Default::default()
+        }
+    }
+
+    fn telemetry(service: &str, spans: Vec<Span>) -> TelemetryData {
+        let request = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource {
+                    attributes: vec![string_attribute("service.name", service)],
+                    ..Default::default()
+                }),
+                scope_spans: vec![ScopeSpans {
+                    spans,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        TelemetryData {
+            payload: request.encode_to_vec(),
+            original_endpoint: String::new(),
+            original_source: service.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_service_map_records_calls() {
+        let mut dynamodb_call = span(3, Some(1), SpanKind::Client, 8);
+        dynamodb_call
+            .attributes
+            .push(string_attribute("rpc.service", "DynamoDB"));
+        let batch = vec![
+            telemetry(
+                "checkout",
+                vec![
+                    span(1, None, SpanKind::Server, 100),
+                    span(2, Some(1), SpanKind::Client, 40),
+                    dynamodb_call,
+                ],
+            ),
+            telemetry("payments", vec![span(4, Some(2), SpanKind::Server, 30)]),
+        ];
+
+        let mut service_map = ServiceMap::default();
+        service_map.record(&batch);
+        service_map.record(&batch);
+        assert!(service_map.take_changed());
+        assert!(!service_map.take_changed());
+        assert_eq!(
+            service_map.to_dot(),
+            "digraph services {\n  rankdir=LR;\n  node [shape=box];\n\
+             \x20 \"checkout\" -> \"DynamoDB\" [label=\"2 calls\\np95 8.0 ms\"];\n\
+             \x20 \"checkout\" -> \"payments\" [label=\"2 calls\\np95 40.0 ms\"];\n}\n"
+        );
+    }
+}
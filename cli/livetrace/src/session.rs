@@ -0,0 +1,164 @@
+//! Records the telemetry received during a session to a JSON Lines file, and replays it.
+//!
+//! Each line of a session file holds one `TelemetryData` batch as it was received from
+//! CloudWatch Logs: the time elapsed since the recording started, its original source and
+//! endpoint, and its protobuf payload encoded as base64. Unlike an asciinema recording (see the
+//! `recorder` module), a session is re-rendered on replay, so it can be viewed with other display
+//! options or forwarded to a collector, which makes it suited to sharing bug reproductions.
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose, Engine};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::{sleep_until, Instant};
+
+use crate::processing::TelemetryData;
+
+/// Extension of the files `--record` writes telemetry sessions to
+pub const SESSION_FILE_EXTENSION: &str = "jsonl";
+
+/// A line of a session file
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionRecord {
+    elapsed_ms: u64,
+    source: String,
+    endpoint: String,
+    payload: String,
+}
+
+/// Returns true if `--record` should write the telemetry of the session to `path`, rather than
+/// an asciinema recording of the console output
+pub fn is_session_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(SESSION_FILE_EXTENSION))
+}
+
+/// Writer for a telemetry session file
+pub struct SessionWriter {
+    writer: BufWriter<File>,
+    started_at: Instant,
+}
+
+impl SessionWriter {
+    /// Creates the session file
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create session file {}", path.display()))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            started_at: Instant::now(),
+        })
+    }
+
+    /// Appends a received batch with the time elapsed since the recording started
+    pub fn record(&mut self, telemetry: &TelemetryData) -> Result<()> {
+        self.record_at(self.started_at.elapsed(), telemetry)
+    }
+
+    fn record_at(&mut self, elapsed: Duration, telemetry: &TelemetryData) -> Result<()> {
+        let record = SessionRecord {
+            elapsed_ms: elapsed.as_millis() as u64,
+            source: telemetry.original_source.clone(),
+            endpoint: telemetry.original_endpoint.clone(),
+            payload: general_purpose::STANDARD.encode(&telemetry.payload),
+        };
+        serde_json::to_writer(&mut self.writer, &record)
+            .context("Failed to write session record")?;
+        writeln!(self.writer).context("Failed to write session record")?;
+        // Flush every record so the session survives being interrupted.
+        self.writer
+            .flush()
+            .context("Failed to write session record")
+    }
+}
+
+/// Reads the batches of a session file, with the time at which each was received
+pub fn read_session(path: &Path) -> Result<Vec<(Duration, TelemetryData)>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open session file {}", path.display()))?;
+    let mut batches = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.context("Failed to read session file")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: SessionRecord = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid session record on line {}", index + 1))?;
+        let payload = general_purpose::STANDARD
+            .decode(&record.payload)
+            .with_context(|| format!("Invalid session payload on line {}", index + 1))?;
+        batches.push((
+            Duration::from_millis(record.elapsed_ms),
+            TelemetryData {
+                payload,
+                original_endpoint: record.endpoint,
+                original_source: record.source,
+            },
+        ));
+    }
+    Ok(batches)
+}
+
+/// Spawns a task that sends the batches of a session over a channel, at the time each was
+/// received in the original session unless `original_timing` is false.
+pub fn start_replay_task(
+    batches: Vec<(Duration, TelemetryData)>,
+    sender: mpsc::Sender<Result<TelemetryData>>,
+    original_timing: bool,
+) {
+    tokio::spawn(async move {
+        let started_at = Instant::now();
+        tracing::debug!(batches = batches.len(), "Replay: Starting replay.");
+        for (elapsed, telemetry) in batches {
+            if original_timing {
+                sleep_until(started_at + elapsed).await;
+            }
+            if sender.send(Ok(telemetry)).await.is_err() {
+                tracing::debug!("Replay: Receiver dropped, stopping replay.");
+                return;
+            }
+        }
+        tracing::debug!("Replay: All batches sent.");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_session_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        assert!(is_session_file(&path));
+        assert!(!is_session_file(&dir.path().join("session.cast")));
+
+        let batch = TelemetryData {
+            payload: vec![0, 1, 2, 255],
+            original_endpoint: "http://localhost:4318/v1/traces".to_string(),
+            original_source: "checkout".to_string(),
+        };
+        let mut writer = SessionWriter::create(&path).unwrap();
+        writer.record_at(Duration::ZERO, &batch).unwrap();
+        writer
+            .record_at(Duration::from_millis(1500), &batch)
+            .unwrap();
+        drop(writer);
+
+        let batches = read_session(&path).unwrap();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[1].0, Duration::from_millis(1500));
+        assert_eq!(batches[1].1.payload, batch.payload);
+        assert_eq!(batches[1].1.original_endpoint, batch.original_endpoint);
+        assert_eq!(batches[1].1.original_source, batch.original_source);
+
+        std::fs::write(&path, "{\"elapsed_ms\": 0}\n").unwrap();
+        let error = read_session(&path).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid session record on line 1");
+    }
+}
@@ -0,0 +1,276 @@
+//! Computes rolling RED (rate, errors, duration) metrics per service from the received spans.
+//!
+//! The entry spans of a service (server and consumer spans, and root spans) are counted as its
+//! requests, whether or not their trace matches the trace filters. `--stats-interval` prints a
+//! summary table of the requests received during the last minute while tailing, and `--stats`
+//! prints nothing but this table, so livetrace can be used as a quick health check.
+
+use colored::*;
+use comfy_table::{presets, Attribute, Cell, CellAlignment, ContentArrangement, Table};
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::trace::v1::{span::SpanKind, status::StatusCode, Span};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::console_display::get_terminal_width;
+use crate::processing::resource_service_name;
+use crate::recorder::console_println;
+use crate::terminal::{colors_enabled, glyphs};
+
+/// Period over which the metrics are computed
+pub const STATS_WINDOW: Duration = Duration::from_secs(60);
+
+// A request received for a service
+#[derive(Debug)]
+struct RequestSample {
+    received_at: Instant,
+    duration_ms: f64,
+    is_error: bool,
+}
+
+/// RED metrics of a service over the stats window
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceStats {
+    pub service: String,
+    pub requests: usize,
+    pub requests_per_sec: f64,
+    pub error_rate: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+}
+
+/// Rolling window of the requests received for each service
+#[derive(Debug)]
+pub struct RedStats {
+    window: Duration,
+    started_at: Instant,
+    samples: BTreeMap<String, VecDeque<RequestSample>>,
+}
+
+impl RedStats {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            started_at: Instant::now(),
+            samples: BTreeMap::new(),
+        }
+    }
+
+    /// Records the entry spans of a received request
+    pub fn record(&mut self, request: &ExportTraceServiceRequest, received_at: Instant) {
+        for resource_spans in &request.resource_spans {
+            let service = resource_service_name(resource_spans.resource.as_ref())
+                .unwrap_or("unknown-service");
+            for span in resource_spans
+                .scope_spans
+                .iter()
+                .flat_map(|scope_spans| &scope_spans.spans)
+                .filter(|span| is_entry_span(span))
+            {
+                let duration_ms =
+                    span.end_time_unix_nano
+                        .saturating_sub(span.start_time_unix_nano) as f64
+                        / 1_000_000.0;
+                let is_error = span
+                    .status
+                    .as_ref()
+                    .is_some_and(|status| status.code == StatusCode::Error as i32);
+                self.samples
+                    .entry(service.to_string())
+                    .or_default()
+                    .push_back(RequestSample {
+                        received_at,
+                        duration_ms,
+                        is_error,
+                    });
+            }
+        }
+    }
+
+    /// Drops the requests older than the window and returns the metrics of each service
+    pub fn summarize(&mut self, now: Instant) -> Vec<ServiceStats> {
+        let cutoff = now.checked_sub(self.window);
+        self.samples.retain(|_, samples| {
+            while samples
+                .front()
+                .is_some_and(|sample| cutoff.is_some_and(|cutoff| sample.received_at < cutoff))
+            {
+                samples.pop_front();
+            }
+            !samples.is_empty()
+        });
+
+        // Rates are computed over the time elapsed until the window is full
+        let period_secs = now
+            .duration_since(self.started_at)
+            .min(self.window)
+            .as_secs_f64()
+            .max(1.0);
+        self.samples
+            .iter()
+            .map(|(service, samples)| {
+                let mut durations: Vec<f64> =
+                    samples.iter().map(|sample| sample.duration_ms).collect();
+                durations.sort_by(f64::total_cmp);
+                let errors = samples.iter().filter(|sample| sample.is_error).count();
+                ServiceStats {
+                    service: service.clone(),
+                    requests: samples.len(),
+                    requests_per_sec: samples.len() as f64 / period_secs,
+                    error_rate: errors as f64 / samples.len() as f64,
+                    p50_ms: percentile(&durations, 0.50),
+                    p95_ms: percentile(&durations, 0.95),
+                    p99_ms: percentile(&durations, 0.99),
+                }
+            })
+            .collect()
+    }
+}
+
+// Server and consumer spans, and root spans, are the requests handled by a service
+pub(crate) fn is_entry_span(span: &Span) -> bool {
+    span.parent_span_id.is_empty()
+        || span.kind == SpanKind::Server as i32
+        || span.kind == SpanKind::Consumer as i32
+}
+
+// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f64], quantile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (quantile * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Prints the metrics of each service as a table
+pub fn print_stats(stats: &[ServiceStats], window: Duration) {
+    let width = get_terminal_width(80);
+    let heading = format!("RED Metrics (last {}s)", window.as_secs());
+    console_println!(
+        "\n{} {} {}\n",
+        glyphs().rule.dimmed(),
+        heading.bold(),
+        glyphs()
+            .rule
+            .repeat(width.saturating_sub(heading.len() + 3))
+            .dimmed()
+    );
+    if stats.is_empty() {
+        console_println!("  {}\n", "No requests received.".dimmed());
+        return;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(presets::NOTHING)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_width(width as u16);
+    if !colors_enabled() {
+        table.force_no_tty();
+    }
+    table.set_header(
+        [
+            "Service", "Requests", "Req/s", "Errors", "p50 (ms)", "p95 (ms)", "p99 (ms)",
+        ]
+        .map(|header| Cell::new(header).add_attribute(Attribute::Bold)),
+    );
+    for service_stats in stats {
+        let error_rate = format!("{:.1}%", service_stats.error_rate * 100.0);
+        let error_rate = if service_stats.error_rate > 0.0 {
+            error_rate.red().to_string()
+        } else {
+            error_rate
+        };
+        table.add_row(vec![
+            Cell::new(&service_stats.service),
+            Cell::new(service_stats.requests).set_alignment(CellAlignment::Right),
+            Cell::new(format!("{:.2}", service_stats.requests_per_sec))
+                .set_alignment(CellAlignment::Right),
+            Cell::new(error_rate).set_alignment(CellAlignment::Right),
+            Cell::new(format!("{:.1}", service_stats.p50_ms)).set_alignment(CellAlignment::Right),
+            Cell::new(format!("{:.1}", service_stats.p95_ms)).set_alignment(CellAlignment::Right),
+            Cell::new(format!("{:.1}", service_stats.p99_ms)).set_alignment(CellAlignment::Right),
+        ]);
+    }
+    console_println!("{}\n", table);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Status};
+
+    fn request(service: &str, spans: Vec<Span>) -> ExportTraceServiceRequest {
+        ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource {
+                    attributes: vec![KeyValue {
+                        key: "service.name".to_string(),
+                        value: Some(AnyValue {
+                            value: Some(any_value::Value::StringValue(service.to_string())),
+                        }),
+                    }],
+                    ..Default::default()
+                }),
+                scope_spans: vec![ScopeSpans {
+                    spans,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    fn server_span(duration_ms: u64, is_error: bool) -> Span {
+        Span {
+            parent_span_id: vec![1; 8],
+            kind: SpanKind::Server as i32,
+            end_time_unix_nano: duration_ms * 1_000_000,
+            status: is_error.then(|| Status {
+                code: StatusCode::Error as i32,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_red_stats_rolling_window() {
+        let mut stats = RedStats::new(STATS_WINDOW);
+        let started_at = stats.started_at;
+        let internal_span = Span {
+            parent_span_id: vec![1; 8],
+            kind: SpanKind::Internal as i32,
+            ..Default::default()
+        };
+        let mut spans: Vec<Span> = (1..=9).map(|ms| server_span(ms * 10, false)).collect();
+        spans.push(server_span(1000, true));
+        spans.push(internal_span);
+        stats.record(&request("checkout", spans), started_at);
+        stats.record(
+            &request("cart", vec![server_span(5, false)]),
+            started_at + Duration::from_secs(30),
+        );
+
+        let summary = stats.summarize(started_at + Duration::from_secs(40));
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].service, "cart");
+        let checkout = &summary[1];
+        assert_eq!(checkout.requests, 10);
+        assert_eq!(checkout.requests_per_sec, 0.25);
+        assert_eq!(checkout.error_rate, 0.1);
+        assert_eq!(checkout.p50_ms, 50.0);
+        assert_eq!(checkout.p95_ms, 1000.0);
+
+        // The checkout requests leave the window
+        let summary = stats.summarize(started_at + Duration::from_secs(70));
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].service, "cart");
+        assert_eq!(summary[0].requests_per_sec, 1.0 / 60.0);
+    }
+}
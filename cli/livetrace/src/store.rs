@@ -0,0 +1,338 @@
+//! Stores the received spans in a local SQLite database, and queries them back.
+//!
+//! With `--store <FILE>`, every span received during a session is written to a `spans` table,
+//! keyed by its trace and span IDs, with the columns `livetrace query` filters on (service, name,
+//! status, start and end times, duration) and the span itself as an OTLP protobuf payload holding
+//! its resource and scope. A query selects the traces with a matching span and returns all their
+//! spans, so the traces can be re-rendered, with any display options, after the session ended.
+
+use anyhow::{Context, Result};
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans};
+use prost::Message;
+use rusqlite::{params, Connection, OpenFlags};
+use std::path::Path;
+
+use crate::cli::StatusFilter;
+use crate::processing::{decode_otlp_payload, resource_service_name, TelemetryData};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS spans (
+    trace_id TEXT NOT NULL,
+    span_id TEXT NOT NULL,
+    parent_span_id TEXT,
+    service TEXT,
+    name TEXT NOT NULL,
+    status INTEGER NOT NULL,
+    start_time_unix_nano INTEGER NOT NULL,
+    end_time_unix_nano INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    endpoint TEXT NOT NULL,
+    payload BLOB NOT NULL,
+    PRIMARY KEY (trace_id, span_id)
+);
+CREATE INDEX IF NOT EXISTS spans_start_time ON spans (start_time_unix_nano);
+";
+
+/// Filters of `livetrace query`. A trace is selected when it started within the time range and
+/// one of its spans matches every other filter that is set.
+#[derive(Debug, Clone, Default)]
+pub struct StoreQuery {
+    pub service: Option<String>,
+    pub status: Option<StatusFilter>,
+    pub min_duration_ms: Option<u64>,
+    pub from_unix_nano: Option<u64>,
+    pub to_unix_nano: Option<u64>,
+    /// Only return the most recent traces
+    pub limit: Option<usize>,
+}
+
+/// A SQLite database of spans
+pub struct TraceStore {
+    connection: Connection,
+}
+
+impl TraceStore {
+    /// Opens the database, creating it and its schema if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open trace store {}", path.display()))?;
+        connection
+            .execute_batch(SCHEMA)
+            .with_context(|| format!("Failed to initialize trace store {}", path.display()))?;
+        Ok(Self { connection })
+    }
+
+    /// Opens an existing database to query it
+    pub fn open_read_only(path: &Path) -> Result<Self> {
+        let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open trace store {}", path.display()))?;
+        Ok(Self { connection })
+    }
+
+    /// Writes the spans of a received batch, returning how many were stored. A span received
+    /// again replaces the stored one.
+    pub fn insert(&mut self, telemetry: &TelemetryData) -> Result<usize> {
+        let request = decode_otlp_payload(&telemetry.payload)?;
+        let transaction = self
+            .connection
+            .transaction()
+            .context("Failed to write to trace store")?;
+        let mut stored = 0;
+        {
+            let mut statement = transaction
+                .prepare_cached(
+                    "INSERT OR REPLACE INTO spans (trace_id, span_id, parent_span_id, service, name, status, start_time_unix_nano, end_time_unix_nano, duration_ms, source, endpoint, payload)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                )
+                .context("Failed to write to trace store")?;
+            for resource_spans in &request.resource_spans {
+                let service = resource_service_name(resource_spans.resource.as_ref());
+                for scope_spans in &resource_spans.scope_spans {
+                    for span in &scope_spans.spans {
+                        // Each span is stored with its resource and scope, as a request of its own
+                        let payload = ExportTraceServiceRequest {
+                            resource_spans: vec![ResourceSpans {
+                                resource: resource_spans.resource.clone(),
+                                scope_spans: vec![ScopeSpans {
+                                    scope: scope_spans.scope.clone(),
+                                    spans: vec![span.clone()],
+                                    schema_url: scope_spans.schema_url.clone(),
+                                }],
+                                schema_url: resource_spans.schema_url.clone(),
+                            }],
+                        }
+                        .encode_to_vec();
+                        let duration_ms = span
+                            .end_time_unix_nano
+                            .saturating_sub(span.start_time_unix_nano)
+                            / 1_000_000;
+                        statement
+                            .execute(params![
+                                hex::encode(&span.trace_id),
+                                hex::encode(&span.span_id),
+                                (!span.parent_span_id.is_empty())
+                                    .then(|| hex::encode(&span.parent_span_id)),
+                                service,
+                                span.name,
+                                span.status.as_ref().map_or(0, |status| status.code),
+                                span.start_time_unix_nano as i64,
+                                span.end_time_unix_nano as i64,
+                                duration_ms as i64,
+                                telemetry.original_source,
+                                telemetry.original_endpoint,
+                                payload,
+                            ])
+                            .context("Failed to write span to trace store")?;
+                        stored += 1;
+                    }
+                }
+            }
+        }
+        transaction
+            .commit()
+            .context("Failed to write to trace store")?;
+        Ok(stored)
+    }
+
+    /// Returns the spans of the traces selected by the query, one item per span, grouped by
+    /// trace in the order the traces started
+    pub fn query(&self, query: &StoreQuery) -> Result<Vec<Vec<TelemetryData>>> {
+        let status = query.status.map(|status| match status {
+            StatusFilter::Unset => 0,
+            StatusFilter::Ok => 1,
+            StatusFilter::Error => 2,
+        });
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT trace_id, MIN(start_time_unix_nano) AS started FROM spans
+                 GROUP BY trace_id
+                 HAVING (?1 IS NULL OR started >= ?1) AND (?2 IS NULL OR started <= ?2)
+                    AND trace_id IN (
+                        SELECT trace_id FROM spans
+                        WHERE (?3 IS NULL OR service = ?3)
+                          AND (?4 IS NULL OR status = ?4)
+                          AND (?5 IS NULL OR duration_ms >= ?5)
+                    )
+                 ORDER BY started DESC
+                 LIMIT ?6",
+            )
+            .context("Failed to query trace store")?;
+        let mut trace_ids = statement
+            .query_map(
+                params![
+                    query.from_unix_nano.map(|nanos| nanos as i64),
+                    query.to_unix_nano.map(|nanos| nanos as i64),
+                    query.service,
+                    status,
+                    query.min_duration_ms.map(|millis| millis as i64),
+                    // A negative limit is no limit in SQLite
+                    query.limit.map_or(-1, |limit| limit as i64),
+                ],
+                |row| row.get::<_, String>(0),
+            )
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .context("Failed to query trace store")?;
+        trace_ids.reverse();
+
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT source, endpoint, payload FROM spans WHERE trace_id = ?1
+                 ORDER BY start_time_unix_nano",
+            )
+            .context("Failed to query trace store")?;
+        trace_ids
+            .iter()
+            .map(|trace_id| {
+                statement
+                    .query_map(params![trace_id], |row| {
+                        Ok(TelemetryData {
+                            original_source: row.get(0)?,
+                            original_endpoint: row.get(1)?,
+                            payload: row.get(2)?,
+                        })
+                    })
+                    .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+                    .context("Failed to read spans from trace store")
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+    use opentelemetry_proto::tonic::trace::v1::{Span, Status};
+    use tempfile::tempdir;
+
+    fn span(trace_id: u8, span_id: u8, start_ms: u64, duration_ms: u64, status: i32) -> Span {
+        Span {
+            trace_id: vec![trace_id; 16],
+            span_id: vec![span_id; 8],
+            name: format!("span-{}", span_id),
+            start_time_unix_nano: start_ms * 1_000_000,
+            end_time_unix_nano: (start_ms + duration_ms) * 1_000_000,
+            status: Some(Status {
+                code: status,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn batch(service: &str, spans: Vec<Span>) -> TelemetryData {
+        let request = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource {
+                    attributes: vec![KeyValue {
+                        key: "service.name".to_string(),
+                        value: Some(AnyValue {
+                            value: Some(any_value::Value::StringValue(service.to_string())),
+                        }),
+                    }],
+                    ..Default::default()
+                }),
+                scope_spans: vec![ScopeSpans {
+                    spans,
+                    ..Default::default()
+                }],
+                schema_url: String::new(),
+            }],
+        };
+        TelemetryData {
+            payload: request.encode_to_vec(),
+            original_endpoint: "/v1/traces".to_string(),
+            original_source: "checkout".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_store_and_query_traces() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("traces.db");
+        let mut store = TraceStore::open(&path).unwrap();
+        assert_eq!(
+            store
+                .insert(&batch(
+                    "checkout",
+                    vec![span(1, 1, 1000, 300, 2), span(1, 2, 1100, 50, 0)]
+                ))
+                .unwrap(),
+            2
+        );
+        store
+            .insert(&batch("payments", vec![span(1, 3, 1200, 20, 1)]))
+            .unwrap();
+        store
+            .insert(&batch("checkout", vec![span(2, 4, 5000, 10, 1)]))
+            .unwrap();
+        // Spans received again are not duplicated
+        store
+            .insert(&batch("checkout", vec![span(2, 4, 5000, 10, 1)]))
+            .unwrap();
+        drop(store);
+
+        let store = TraceStore::open(&path).unwrap();
+        let traces = store.query(&StoreQuery::default()).unwrap();
+        assert_eq!(traces.iter().map(Vec::len).collect::<Vec<_>>(), vec![3, 1]);
+        let request = decode_otlp_payload(&traces[0][0].payload).unwrap();
+        assert_eq!(
+            resource_service_name(request.resource_spans[0].resource.as_ref()),
+            Some("checkout")
+        );
+        assert_eq!(
+            request.resource_spans[0].scope_spans[0].spans[0].span_id,
+            vec![1; 8]
+        );
+
+        let count = |query: StoreQuery| store.query(&query).unwrap().len();
+        assert_eq!(
+            count(StoreQuery {
+                service: Some("payments".to_string()),
+                ..Default::default()
+            }),
+            1
+        );
+        assert_eq!(
+            count(StoreQuery {
+                status: Some(StatusFilter::Error),
+                ..Default::default()
+            }),
+            1
+        );
+        assert_eq!(
+            count(StoreQuery {
+                min_duration_ms: Some(100),
+                ..Default::default()
+            }),
+            1
+        );
+        assert_eq!(
+            count(StoreQuery {
+                from_unix_nano: Some(2000 * 1_000_000),
+                ..Default::default()
+            }),
+            1
+        );
+        assert_eq!(
+            count(StoreQuery {
+                to_unix_nano: Some(2000 * 1_000_000),
+                ..Default::default()
+            }),
+            1
+        );
+        let latest = store
+            .query(&StoreQuery {
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].len(), 1);
+    }
+}
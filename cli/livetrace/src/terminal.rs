@@ -0,0 +1,154 @@
+//! Adapts the console output to the capabilities of the terminal.
+//!
+//! Colors are disabled with `--no-color`, when `NO_COLOR` is set, when stdout is not a terminal
+//! (e.g. in CI logs), or in a Windows console without ANSI escape sequences support. The Unicode
+//! glyphs of the console output (rules, timeline bars, heatmap shades, spinner) are replaced with
+//! ASCII with `--ascii`, or when the terminal is not known to render them: a `dumb` terminal, a
+//! locale without UTF-8, or the legacy Windows console.
+
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// The glyphs of the console output
+#[derive(Debug)]
+pub struct Glyphs {
+    pub rule: &'static str,
+    pub vertical: &'static str,
+    pub corner: &'static str,
+    pub branch: &'static str,
+    pub last_branch: &'static str,
+    pub at_most: &'static str,
+    pub scale_marker: &'static str,
+    pub micros: &'static str,
+    pub link_marker: &'static str,
+    pub warning: &'static str,
+    pub spinner: &'static str,
+    pub header_intersection: char,
+    pub rule_char: char,
+    pub bar: char,
+    pub shades: [char; 5],
+}
+
+const UNICODE_GLYPHS: Glyphs = Glyphs {
+    rule: "─",
+    vertical: "│",
+    corner: "└",
+    branch: "├─▶",
+    last_branch: "└─▶",
+    at_most: "≤",
+    scale_marker: "▾",
+    micros: "μs",
+    link_marker: "↗",
+    warning: "⚠",
+    spinner: "⠁⠂⠄⡀⢀⠠⠐⠈ ",
+    header_intersection: '┴',
+    rule_char: '─',
+    bar: '▄',
+    shades: [' ', '░', '▒', '▓', '█'],
+};
+
+const ASCII_GLYPHS: Glyphs = Glyphs {
+    rule: "-",
+    vertical: "|",
+    corner: "+",
+    branch: "|->",
+    last_branch: "`->",
+    at_most: "<=",
+    scale_marker: "v",
+    micros: "us",
+    link_marker: "^",
+    warning: "!",
+    spinner: "|/-\\ ",
+    header_intersection: '+',
+    rule_char: '-',
+    bar: '#',
+    shades: [' ', '.', ':', '+', '#'],
+};
+
+/// Returns the glyphs of the console output, ASCII if the terminal does not support Unicode
+pub fn glyphs() -> &'static Glyphs {
+    if ASCII.load(Ordering::Relaxed) {
+        &ASCII_GLYPHS
+    } else {
+        &UNICODE_GLYPHS
+    }
+}
+
+/// Disables the colors and Unicode glyphs of the console output, as requested or detected
+pub fn configure_terminal(no_color: bool, ascii: bool) {
+    let ascii = ascii || !detect_unicode_support();
+    // The legacy Windows console renders neither ANSI escape sequences nor Unicode glyphs
+    #[cfg(windows)]
+    let ascii = if colored::control::set_virtual_terminal(true).is_err() {
+        colored::control::set_override(false);
+        true
+    } else {
+        ascii
+    };
+    if no_color || no_color_env() {
+        colored::control::set_override(false);
+    }
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+/// Returns true if the console output is colored
+pub fn colors_enabled() -> bool {
+    colored::control::SHOULD_COLORIZE.should_colorize()
+}
+
+/// Returns true if the `NO_COLOR` environment variable is set to a non-empty value
+pub fn no_color_env() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+fn detect_unicode_support() -> bool {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()));
+    supports_unicode(
+        env::var("TERM").ok().as_deref(),
+        locale.as_deref(),
+        cfg!(windows)
+            && ["WT_SESSION", "TERM", "TERM_PROGRAM"]
+                .iter()
+                .all(|name| env::var_os(name).is_none()),
+    )
+}
+
+// The legacy Windows console is the one run without Windows Terminal or a terminal emulator
+fn supports_unicode(
+    term: Option<&str>,
+    locale: Option<&str>,
+    legacy_windows_console: bool,
+) -> bool {
+    if legacy_windows_console || term == Some("dumb") {
+        return false;
+    }
+    // Without a locale, the terminal is assumed to render UTF-8, as most do
+    locale.is_none_or(|locale| {
+        let locale = locale.to_ascii_lowercase();
+        locale.contains("utf-8") || locale.contains("utf8")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supports_unicode() {
+        assert!(supports_unicode(
+            Some("xterm-256color"),
+            Some("en_US.UTF-8"),
+            false
+        ));
+        assert!(supports_unicode(Some("xterm"), Some("C.utf8"), false));
+        assert!(supports_unicode(None, None, false));
+        assert!(!supports_unicode(Some("xterm"), Some("C"), false));
+        assert!(!supports_unicode(Some("xterm"), Some("POSIX"), false));
+        assert!(!supports_unicode(Some("dumb"), Some("en_US.UTF-8"), false));
+        assert!(!supports_unicode(None, None, true));
+    }
+}
@@ -7,15 +7,24 @@ use indicatif::{ProgressBar, ProgressStyle};
 use regex::Regex;
 use std::{
     fs::{self, File},
+    future::Future,
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::atomic::{AtomicBool, Ordering},
     sync::Arc,
 };
 use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use tracing::Instrument;
 
+use crate::cloudwatch::CloudWatchPublisher;
 use crate::console;
+use crate::discovery::discover_functions;
 use crate::lambda;
+use crate::limits::{InvocationLimits, RateLimiter};
+use crate::metrics::record_benchmark_metrics;
+use crate::restore::ConfigRestoreGuard;
+use crate::schema::REPORT_SCHEMA_VERSION;
 use crate::types::*;
 
 static INTERRUPTED: AtomicBool = AtomicBool::new(false);
@@ -39,6 +48,10 @@ struct FunctionBenchmarkConfig {
     output_dir: String,
     environment: Vec<(String, String)>,
     proxy_function: Option<String>,
+    exclude_failed: bool,
+    sustained_load: Option<SustainedLoad>,
+    response_streaming: bool,
+    warm_pacing: Option<WarmPacing>,
 }
 
 impl FunctionBenchmarkConfig {
@@ -52,6 +65,10 @@ impl FunctionBenchmarkConfig {
         output_dir: impl Into<String>,
         environment: Vec<(String, String)>,
         proxy_function: Option<String>,
+        exclude_failed: bool,
+        sustained_load: Option<SustainedLoad>,
+        response_streaming: bool,
+        warm_pacing: Option<WarmPacing>,
     ) -> Self {
         Self {
             function_name: function_name.into(),
@@ -62,6 +79,10 @@ impl FunctionBenchmarkConfig {
             output_dir: output_dir.into(),
             environment,
             proxy_function,
+            exclude_failed,
+            sustained_load,
+            response_streaming,
+            warm_pacing,
         }
     }
 }
@@ -75,8 +96,11 @@ pub async fn save_report(
     let output_path = PathBuf::from(output_dir).join(&memory_dir);
     fs::create_dir_all(&output_path)?;
 
-    let function_name = report.config.function_name.clone();
-    let filename = format!("{}.json", function_name);
+    // The variants of a function are saved side by side, so the report compares them
+    let filename = match &report.config.variant {
+        Some(variant) => format!("{}-{}.json", report.config.function_name, variant),
+        None => format!("{}.json", report.config.function_name),
+    };
     let output_path = output_path.join(filename);
 
     let json = serde_json::to_string_pretty(&report)?;
@@ -93,6 +117,69 @@ pub struct BenchmarkResults {
     pub cold_starts: Vec<InvocationMetrics>,
     pub warm_starts: Vec<InvocationMetrics>,
     pub client_measurements: Vec<InvocationMetrics>,
+    pub errors: Vec<InvocationError>,
+    pub environment_recycles: Vec<EnvironmentRecycle>,
+}
+
+/// Returns an invocation of the function, in the span of the benchmark
+fn invocation(
+    client: &LambdaClient,
+    config: &FunctionBenchmarkConfig,
+    client_metrics_mode: bool,
+) -> impl Future<Output = Result<InvocationMetrics>> + Send + 'static {
+    let client = client.clone();
+    let function_name = config.function_name.clone();
+    let payload = config.payload.clone();
+    let environment = config.environment.clone();
+    let memory_size = config.memory_size;
+    let proxy_function = config.proxy_function.clone();
+    let response_streaming = config.response_streaming;
+
+    async move {
+        lambda::invoke_function(
+            &client,
+            &function_name,
+            memory_size,
+            payload.as_deref(),
+            &environment,
+            client_metrics_mode,
+            proxy_function.as_deref(),
+            response_streaming,
+        )
+        .await
+    }
+    .in_current_span()
+}
+
+/// Records a failed invocation, and keeps its measurements in the samples unless the failed
+/// samples are excluded. Without a phase, the invocation is only counted as a failure.
+fn record_failure(
+    error: anyhow::Error,
+    phase: Option<InvocationPhase>,
+    exclude_failed: bool,
+    samples: &mut Vec<InvocationMetrics>,
+    errors: &mut Vec<InvocationError>,
+) {
+    let Some(phase) = phase else {
+        return;
+    };
+    let (error_type, throttled, metrics) = match error.downcast::<lambda::InvocationFailure>() {
+        Ok(failure) => (failure.error_type, failure.throttled, failure.metrics),
+        Err(_) => ("Error".to_string(), false, None),
+    };
+    let sampled = metrics.is_some() && !exclude_failed;
+    if let Some(metrics) = metrics.filter(|_| sampled) {
+        samples.push(*metrics);
+    }
+    errors.push(InvocationError {
+        timestamp: chrono::Utc::now()
+            .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+            .to_string(),
+        phase,
+        error_type,
+        throttled,
+        sampled,
+    });
 }
 
 async fn run_benchmark_pass(
@@ -100,6 +187,7 @@ async fn run_benchmark_pass(
     config: &FunctionBenchmarkConfig,
     client_metrics_mode: bool,
     quiet_mode: bool,
+    limits: &InvocationLimits,
 ) -> Result<(BenchmarkResults, usize, usize, Vec<String>)> {
     use tokio::signal;
 
@@ -107,33 +195,28 @@ async fn run_benchmark_pass(
         cold_starts: Vec::new(),
         warm_starts: Vec::new(),
         client_measurements: Vec::new(),
+        errors: Vec::new(),
+        environment_recycles: Vec::new(),
     };
     let mut successes = 0;
     let mut failures = 0;
     let mut errors = Vec::new();
 
+    let mut rate_limiter = limits.rate_limiter();
+    // With --concurrent-environments, every round invokes each environment held warm
+    let round_invocations = WarmPacing::round_invocations(config.warm_pacing, config.concurrent);
+
     // Cold starts - run concurrently
+    let round_permit = limits
+        .start_round(round_invocations, &mut rate_limiter)
+        .await;
     let mut handles = Vec::new();
-    for _ in 0..config.concurrent {
-        let client = client.clone();
-        let function_name = config.function_name.clone();
-        let payload = config.payload.clone();
-        let environment = config.environment.clone();
-        let memory_size = config.memory_size;
-        let proxy_function = config.proxy_function.clone();
-
-        handles.push(tokio::spawn(async move {
-            lambda::invoke_function(
-                &client,
-                &function_name,
-                memory_size,
-                payload.as_deref(),
-                &environment,
-                client_metrics_mode,
-                proxy_function.as_deref(),
-            )
-            .await
-        }));
+    for _ in 0..round_invocations {
+        handles.push(tokio::spawn(invocation(
+            client,
+            config,
+            client_metrics_mode,
+        )));
     }
 
     // Wait for cold starts with Ctrl-C handling
@@ -147,6 +230,14 @@ async fn run_benchmark_pass(
                 Err(e) => {
                     failures += 1;
                     errors.push(format!("Cold start error: {e}"));
+                    // The first round of the client pass only warms the functions up
+                    record_failure(
+                        e,
+                        (!client_metrics_mode).then_some(InvocationPhase::ColdStart),
+                        config.exclude_failed,
+                        &mut results.cold_starts,
+                        &mut results.errors,
+                    );
                 }
             }
         }
@@ -164,6 +255,25 @@ async fn run_benchmark_pass(
     } {
         return Ok((results, successes, failures, errors));
     }
+    drop(round_permit);
+
+    if let Some(load) = config.sustained_load {
+        run_sustained_load(
+            client,
+            config,
+            load,
+            client_metrics_mode,
+            quiet_mode,
+            limits,
+            &mut rate_limiter,
+            &mut results,
+            &mut successes,
+            &mut failures,
+            &mut errors,
+        )
+        .await;
+        return Ok((results, successes, failures, errors));
+    }
 
     // Setup progress bar for warm starts
     let progress = if !quiet_mode && config.number > 1 {
@@ -181,35 +291,66 @@ async fn run_benchmark_pass(
         None
     };
 
+    // With --warm-interval, the rounds start on a fixed schedule, the first one an interval after
+    // the cold starts; a round taking longer than the interval delays the next one
+    let mut round_ticker = config
+        .warm_pacing
+        .and_then(|pacing| pacing.interval_secs)
+        .map(|interval_secs| {
+            let interval = tokio::time::Duration::from_secs(interval_secs);
+            let mut ticker =
+                tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            ticker
+        });
+
+    // The failures of the invocations that only keep environments warm are not measured, but an
+    // environment they failed to reach may have been recycled before the next measured round
+    let mut warm_keeping_failures = 0usize;
+    let mut warm_keeping_throttles = 0usize;
+
     // Warm starts with Ctrl-C handling
     for _round in 1..=config.number {
+        if let Some(ticker) = &mut round_ticker {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = signal::ctrl_c() => {
+                    println!("\n\nReceived Ctrl-C, interrupting...");
+                    if let Some(pb) = &progress {
+                        pb.finish_and_clear();
+                    }
+                    INTERRUPTED.store(true, Ordering::SeqCst);
+                    return Ok((results, successes, failures, errors));
+                }
+            }
+        }
+        let round_permit = limits
+            .start_round(round_invocations, &mut rate_limiter)
+            .await;
         let mut handles = Vec::new();
-        for _ in 0..config.concurrent {
-            let client = client.clone();
-            let function_name = config.function_name.clone();
-            let payload = config.payload.clone();
-            let environment = config.environment.clone();
-            let memory_size = config.memory_size;
-            let proxy_function = config.proxy_function.clone();
-
-            handles.push(tokio::spawn(async move {
-                lambda::invoke_function(
-                    &client,
-                    &function_name,
-                    memory_size,
-                    payload.as_deref(),
-                    &environment,
-                    client_metrics_mode,
-                    proxy_function.as_deref(),
-                )
-                .await
-            }));
+        for _ in 0..round_invocations {
+            handles.push(tokio::spawn(invocation(
+                client,
+                config,
+                client_metrics_mode,
+            )));
         }
 
-        // Handle Ctrl-C for each round of warm starts
+        // Handle Ctrl-C for each round of warm starts; the invocations past --concurrent only
+        // keep the other environments warm, and are not measured
         let warm_start_future = async {
-            for handle in handles {
+            for (index, handle) in handles.into_iter().enumerate() {
+                let measured = index < config.concurrent as usize;
                 match handle.await? {
+                    Ok(_) if !measured => {}
+                    Err(e) if !measured => {
+                        warm_keeping_failures += 1;
+                        if e.downcast_ref::<lambda::InvocationFailure>()
+                            .is_some_and(|failure| failure.throttled)
+                        {
+                            warm_keeping_throttles += 1;
+                        }
+                    }
                     Ok(metrics) => {
                         results.warm_starts.push(metrics);
                         successes += 1;
@@ -217,6 +358,17 @@ async fn run_benchmark_pass(
                     Err(e) => {
                         failures += 1;
                         errors.push(format!("Warm start error: {e}"));
+                        record_failure(
+                            e,
+                            Some(if client_metrics_mode {
+                                InvocationPhase::Client
+                            } else {
+                                InvocationPhase::WarmStart
+                            }),
+                            config.exclude_failed,
+                            &mut results.warm_starts,
+                            &mut results.errors,
+                        );
                     }
                 }
             }
@@ -236,6 +388,7 @@ async fn run_benchmark_pass(
         } {
             return Ok((results, successes, failures, errors));
         }
+        drop(round_permit);
 
         if !quiet_mode {
             if let Some(pb) = &progress {
@@ -249,11 +402,126 @@ async fn run_benchmark_pass(
         if let Some(pb) = progress {
             pb.finish_and_clear();
         }
+        if warm_keeping_failures > 0 {
+            eprintln!(
+                "Warning: {} warm-keeping invocations failed ({} throttled); the environments they missed may have been recycled",
+                warm_keeping_failures, warm_keeping_throttles
+            );
+        }
     }
 
     Ok((results, successes, failures, errors))
 }
 
+/// Sustains the target request rate of the warm starts for the duration of the load, recording
+/// the cold starts of the environments initialized on the way as environment recycles
+#[allow(clippy::too_many_arguments)]
+async fn run_sustained_load(
+    client: &LambdaClient,
+    config: &FunctionBenchmarkConfig,
+    load: SustainedLoad,
+    client_metrics_mode: bool,
+    quiet_mode: bool,
+    limits: &InvocationLimits,
+    rate_limiter: &mut RateLimiter,
+    results: &mut BenchmarkResults,
+    successes: &mut usize,
+    failures: &mut usize,
+    errors: &mut Vec<String>,
+) {
+    use tokio::signal;
+    use tokio::time::{Duration, Instant, MissedTickBehavior};
+
+    let start = Instant::now();
+    let deadline = start + Duration::from_secs(load.duration_secs);
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / load.rps));
+    // Invocations delayed by the limits are not caught up with in a burst
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let phase = if client_metrics_mode {
+        InvocationPhase::Client
+    } else {
+        InvocationPhase::WarmStart
+    };
+
+    let progress = (!quiet_mode).then(|| {
+        let pb = ProgressBar::new(load.duration_secs);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len}s")
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+        pb
+    });
+
+    let mut in_flight = JoinSet::new();
+    let ctrl_c = signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    loop {
+        let now = Instant::now();
+        if now >= deadline && in_flight.is_empty() {
+            break;
+        }
+        tokio::select! {
+            _ = &mut ctrl_c => {
+                println!("\n\nReceived Ctrl-C, interrupting...");
+                if let Some(pb) = &progress {
+                    pb.finish_and_clear();
+                }
+                INTERRUPTED.store(true, Ordering::SeqCst);
+                return;
+            }
+            _ = ticker.tick(), if now < deadline => {
+                let permit = limits.start_round(1, rate_limiter).await;
+                let invocation = invocation(client, config, client_metrics_mode);
+                in_flight.spawn(async move {
+                    let _permit = permit;
+                    invocation.await
+                });
+            }
+            Some(joined) = in_flight.join_next() => match joined {
+                Ok(Ok(metrics)) => {
+                    *successes += 1;
+                    match metrics.init_duration {
+                        // Only the server measurements tell the cold starts apart
+                        Some(init_duration) if !client_metrics_mode => {
+                            results.environment_recycles.push(EnvironmentRecycle {
+                                timestamp: metrics.timestamp.clone(),
+                                elapsed_secs: start.elapsed().as_secs_f64(),
+                                init_duration,
+                            });
+                            results.cold_starts.push(metrics);
+                        }
+                        _ => results.warm_starts.push(metrics),
+                    }
+                }
+                Ok(Err(e)) => {
+                    *failures += 1;
+                    errors.push(format!("Warm start error: {e}"));
+                    record_failure(
+                        e,
+                        Some(phase),
+                        config.exclude_failed,
+                        &mut results.warm_starts,
+                        &mut results.errors,
+                    );
+                }
+                Err(e) => {
+                    *failures += 1;
+                    errors.push(format!("Warm start error: {e}"));
+                }
+            },
+        }
+        if let Some(pb) = &progress {
+            pb.set_position(start.elapsed().as_secs().min(load.duration_secs));
+        }
+    }
+
+    if let Some(pb) = progress {
+        pb.finish_and_clear();
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn run_function_benchmark(
     client: &LambdaClient,
@@ -268,6 +536,14 @@ pub async fn run_function_benchmark(
     proxy_function: Option<&str>,
     quiet_mode: bool,
     console_mutex: Option<Arc<Mutex<()>>>,
+    state_file: &Path,
+    cloudwatch_publisher: Option<&CloudWatchPublisher>,
+    limits: &InvocationLimits,
+    exclude_failed: bool,
+    sustained_load: Option<SustainedLoad>,
+    warm_pacing: Option<WarmPacing>,
+    response_streaming: bool,
+    variant: Option<&str>,
 ) -> Result<()> {
     // Get function configuration to extract runtime and architecture FIRST
     let function_config_details = client
@@ -313,6 +589,9 @@ pub async fn run_function_benchmark(
 
         println!("\nConfiguration:");
         println!("  {:20}: {}", "Function Name".dimmed(), function_name);
+        if let Some(variant) = variant {
+            println!("  {:20}: {}", "Variant".dimmed(), variant);
+        }
         println!("  {:20}: {} MB", "Memory Size".dimmed(), memory_size);
         println!(
             "  {:20}: {}",
@@ -325,10 +604,27 @@ pub async fn run_function_benchmark(
             architecture.as_deref().unwrap_or("unknown")
         );
         println!("  {:20}: {}", "Concurrency".dimmed(), concurrent);
-        println!("  {:20}: {}", "Rounds".dimmed(), number);
+        match sustained_load {
+            Some(load) => println!(
+                "  {:20}: {} rps for {}s",
+                "Sustained Load".dimmed(),
+                load.rps,
+                load.duration_secs
+            ),
+            None => println!("  {:20}: {}", "Rounds".dimmed(), number),
+        }
+        if let Some(interval_secs) = warm_pacing.and_then(|pacing| pacing.interval_secs) {
+            println!("  {:20}: {}s", "Warm Interval".dimmed(), interval_secs);
+        }
+        if let Some(environments) = warm_pacing.and_then(|pacing| pacing.environments) {
+            println!("  {:20}: {}", "Warm Environments".dimmed(), environments);
+        }
         if let Some(proxy) = proxy_function {
             println!("  {:20}: {}", "Using Proxy Function".dimmed(), proxy);
         }
+        if response_streaming {
+            println!("  {:20}: enabled", "Response Streaming".dimmed());
+        }
         if !environment.is_empty() {
             println!("  {:20}:", "Environment".dimmed());
             for (key, value) in environment {
@@ -361,9 +657,10 @@ pub async fn run_function_benchmark(
         .map(|(k, v)| (k.to_string(), v.to_string()))
         .collect();
 
-    // Save original configuration if we're going to modify it
-    let original_config_to_restore =
-        Some(lambda::get_function_config(client, function_name).await?);
+    // Save original configuration before modifying it; the guard restores it even if this
+    // function panics or returns early
+    let restore_guard =
+        ConfigRestoreGuard::capture(client, function_name, state_file, quiet_mode).await?;
 
     // Update function configuration if needed
     if !quiet_mode {
@@ -384,6 +681,24 @@ pub async fn run_function_benchmark(
         output_dir.unwrap_or("default").to_string(),
         env_owned, // Use the owned Vec<(String, String)>
         proxy_function.map(|s| s.to_string()),
+        exclude_failed,
+        sustained_load,
+        response_streaming,
+        warm_pacing,
+    );
+
+    // Parent span of the invocations of both passes, so the client and proxy measurements are
+    // linked to the server measurements of the same run
+    let benchmark_span = tracing::info_span!(
+        "benchmark",
+        otel.name = %format!("benchmark {}", function_name),
+        function.name = function_name,
+        function.memory_size = memory_size,
+        function.architecture = architecture.as_deref().unwrap_or("unknown"),
+        function.runtime = runtime.as_deref().unwrap_or("unknown"),
+        function.proxy = proxy_function.unwrap_or_default(),
+        benchmark.concurrency = concurrent,
+        benchmark.rounds = number,
     );
 
     let result = async {
@@ -396,6 +711,7 @@ pub async fn run_function_benchmark(
             &function_benchmark_config_instance,
             false,
             quiet_mode,
+            limits,
         )
         .await?;
         if !quiet_mode {
@@ -414,9 +730,11 @@ pub async fn run_function_benchmark(
                     &function_benchmark_config_instance,
                     true,
                     quiet_mode,
+                    limits,
                 )
                 .await?;
             results.client_measurements = client_results.warm_starts;
+            results.errors.extend(client_results.errors);
             successes += client_successes;
             failures += client_failures;
             errors.extend(client_errors);
@@ -428,6 +746,12 @@ pub async fn run_function_benchmark(
         // Print results
         if !quiet_mode {
             console::print_benchmark_results(function_name, &results);
+            if !results.environment_recycles.is_empty() {
+                println!(
+                    "Environment recycles: {}",
+                    results.environment_recycles.len()
+                );
+            }
         }
 
         // Calculate and print success rate
@@ -447,6 +771,10 @@ pub async fn run_function_benchmark(
                 if errors.len() > 10 {
                     println!("...and {} more errors.", errors.len() - 10);
                 }
+                let throttled = results.errors.iter().filter(|e| e.throttled).count();
+                if throttled > 0 {
+                    println!("Throttled invocations: {}", throttled);
+                }
                 println!("--- End Errors ---\n");
             }
         }
@@ -462,65 +790,81 @@ pub async fn run_function_benchmark(
             // For now, rely on the overall summary.
         }
 
+        // The extensions register during the initialization, so only the cold starts log them
+        let mut extensions: Vec<String> = Vec::new();
+        for extension in results.cold_starts.iter().flat_map(|m| &m.extensions) {
+            if !extensions.contains(extension) {
+                extensions.push(extension.clone());
+            }
+        }
+
+        let report = BenchmarkReport {
+            schema_version: REPORT_SCHEMA_VERSION,
+            config: BenchmarkConfig {
+                function_name: function_name.to_string(),
+                memory_size,
+                concurrent_invocations: concurrent,
+                number,
+                timestamp: Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+                runtime,
+                architecture,
+                environment: environment
+                    .iter()
+                    .map(|(k, v)| EnvVar {
+                        key: k.to_string(),
+                        value: v.to_string(),
+                    })
+                    .collect(),
+                sustained_load,
+                response_streaming,
+                variant: variant.map(str::to_string),
+                warm_pacing,
+            },
+            cold_starts: results
+                .cold_starts
+                .iter()
+                .filter_map(|m| m.to_cold_start(&extensions))
+                .collect(),
+            warm_starts: results
+                .warm_starts
+                .iter()
+                .map(|m| m.to_warm_start(&extensions))
+                .collect(),
+            client_measurements: results
+                .client_measurements
+                .iter()
+                .map(|m| m.to_client_metrics())
+                .collect(),
+            errors: results.errors,
+            environment_recycles: results.environment_recycles,
+        };
+        record_benchmark_metrics(&report);
+        if let Some(publisher) = cloudwatch_publisher {
+            if publisher.publish(&report).await.is_err() {
+                eprintln!(
+                    "Warning: Failed to publish the CloudWatch metrics of {}",
+                    function_name
+                );
+            }
+        }
+
         // Save results
         if let Some(dir) = output_dir {
-            save_report(
-                BenchmarkReport {
-                    config: BenchmarkConfig {
-                        function_name: function_name.to_string(),
-                        memory_size,
-                        concurrent_invocations: concurrent,
-                        number,
-                        timestamp: Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
-                        runtime,
-                        architecture,
-                        environment: environment
-                            .iter()
-                            .map(|(k, v)| EnvVar {
-                                key: k.to_string(),
-                                value: v.to_string(),
-                            })
-                            .collect(),
-                    },
-                    cold_starts: results
-                        .cold_starts
-                        .iter()
-                        .filter_map(|m| m.to_cold_start())
-                        .collect(),
-                    warm_starts: results
-                        .warm_starts
-                        .iter()
-                        .map(|m| m.to_warm_start())
-                        .collect(),
-                    client_measurements: results
-                        .client_measurements
-                        .iter()
-                        .map(|m| m.to_client_metrics())
-                        .collect(),
-                },
-                dir,
-                quiet_mode,
-            )
-            .await?;
+            save_report(report, dir, quiet_mode).await?;
         }
 
         Ok(())
     }
+    .instrument(benchmark_span)
     .await;
 
-    // Restore original configuration if we modified it
-    if let Some(original) = original_config_to_restore {
-        // Always try to restore, even if the benchmark failed or was interrupted
-        if let Err(e) =
-            lambda::restore_function_config(client, function_name, &original, quiet_mode).await
-        {
-            if !quiet_mode {
-                eprintln!(
-                    "Warning: Failed to restore function configuration for {}: {}",
-                    function_name, e
-                );
-            }
-            // Note: The error itself will propagate if restore_function_config returns Err, handled by run_stack_benchmark summary.
+    // Always try to restore, even if the benchmark failed or was interrupted
+    if let Err(e) = restore_guard.restore().await {
+        if !quiet_mode {
+            eprintln!(
+                "Warning: Failed to restore function configuration for {}: {:#}",
+                function_name, e
+            );
         }
     }
 
@@ -528,70 +872,64 @@ pub async fn run_function_benchmark(
     result
 }
 
+/// Returns the functions of the stacks and tags matching `--select` or `--select-regex`
+pub async fn select_stack_functions(
+    lambda_client: &LambdaClient,
+    cf_client: &CloudFormationClient,
+    stack_names: &[String],
+    tags: &[String],
+    select_pattern: Option<&str>,
+    select_regex: Option<&str>,
+) -> Result<Vec<String>> {
+    let discovered_functions =
+        discover_functions(lambda_client, cf_client, stack_names, tags).await?;
+
+    let select_regex = select_regex
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| anyhow!("Invalid --select-regex: {}", e))?;
+    Ok(discovered_functions
+        .into_iter()
+        .filter(|function| match (&select_regex, select_pattern) {
+            (Some(re), _) => re.is_match(function),
+            (None, Some(pattern)) => function.contains(pattern),
+            (None, None) => true,
+        })
+        .collect())
+}
+
 pub async fn run_stack_benchmark(
     lambda_client: &LambdaClient,
     cf_client: &CloudFormationClient,
     config: StackBenchmarkConfig,
 ) -> Result<()> {
+    let mut sources: Vec<String> = config
+        .stack_names
+        .iter()
+        .map(|stack_name| format!("stack {}", stack_name))
+        .collect();
+    sources.extend(config.tags.iter().map(|tag| format!("tag {}", tag)));
     println!(
-        "Analyzing stack: {}. This might take a moment...",
-        config.stack_name
+        "Discovering functions from {}. This might take a moment...",
+        sources.join(", ")
     );
 
-    // Use list_stack_resources to get all resources in the stack
-    let mut all_stack_resources = Vec::new();
-    let mut next_token: Option<String> = None;
-    loop {
-        let resp = cf_client
-            .list_stack_resources()
-            .stack_name(&config.stack_name)
-            .set_next_token(next_token)
-            .send()
-            .await?;
-
-        // Correctly handle the slice returned by stack_resource_summaries()
-        let summaries_slice: &[aws_sdk_cloudformation::types::StackResourceSummary] =
-            resp.stack_resource_summaries();
-        all_stack_resources.extend(summaries_slice.to_vec());
-
-        next_token = resp.next_token().map(|s| s.to_string());
-        if next_token.is_none() {
-            break;
-        }
-    }
-
-    let mut function_identifiers_to_benchmark = Vec::new();
-
-    for resource_summary in all_stack_resources {
-        if resource_summary.resource_type() == Some("AWS::Lambda::Function") {
-            if let Some(physical_id) = resource_summary.physical_resource_id() {
-                let id_matches = config.select_regex.as_ref().map_or_else(
-                    || physical_id.contains(&config.select_pattern),
-                    |re_str| match Regex::new(re_str) {
-                        Ok(re) => re.is_match(physical_id),
-                        Err(e) => {
-                            eprintln!(
-                                "Invalid regex '{}': {}. Skipping match for this resource.",
-                                re_str, e
-                            );
-                            false
-                        }
-                    },
-                );
-
-                if id_matches {
-                    function_identifiers_to_benchmark.push(physical_id.to_string());
-                }
-            }
-        }
-    }
+    let function_identifiers_to_benchmark = select_stack_functions(
+        lambda_client,
+        cf_client,
+        &config.stack_names,
+        &config.tags,
+        config.select_pattern.as_deref(),
+        config.select_regex.as_deref(),
+    )
+    .await?;
 
     if function_identifiers_to_benchmark.is_empty() {
         println!(
             "{}",
             format!(
-                "No Lambda functions found in stack '{}' matching select criteria '{}' (or regex: {:?}). Searched all 'AWS::Lambda::Function' resources.",
-                config.stack_name,
+                "No Lambda functions found from {} matching select criteria {:?} (or regex: {:?}).",
+                sources.join(", "),
                 config.select_pattern,
                 config.select_regex
             )
@@ -669,6 +1007,13 @@ pub async fn run_stack_benchmark(
             let proxy_function_clone = config.proxy_function.clone();
             let pb_clone = pb.clone();
             let mutex_clone = Arc::clone(&console_mutex);
+            let state_file_clone = config.state_file.clone();
+            let cloudwatch_publisher_clone = config.cloudwatch_publisher.clone();
+            let limits_clone = config.limits.clone();
+            let exclude_failed = config.exclude_failed;
+            let sustained_load = config.sustained_load;
+            let warm_pacing = config.warm_pacing;
+            let response_streaming = config.response_streaming;
 
             benchmark_futures.push(tokio::spawn(async move {
                 let result = run_function_benchmark(
@@ -687,6 +1032,14 @@ pub async fn run_stack_benchmark(
                     proxy_function_clone.as_deref(),
                     true,
                     Some(mutex_clone),
+                    &state_file_clone,
+                    cloudwatch_publisher_clone.as_ref(),
+                    &limits_clone,
+                    exclude_failed,
+                    sustained_load,
+                    warm_pacing,
+                    response_streaming,
+                    None,
                 )
                 .await;
                 pb_clone.inc(1);
@@ -771,6 +1124,14 @@ pub async fn run_stack_benchmark(
                 config.proxy_function.as_deref(),
                 false, // quiet_mode is false for sequential execution
                 None,  // No mutex needed for sequential printing
+                &config.state_file,
+                config.cloudwatch_publisher.as_ref(),
+                &config.limits,
+                config.exclude_failed,
+                config.sustained_load,
+                config.warm_pacing,
+                config.response_streaming,
+                None,
             )
             .await
             {
@@ -821,6 +1182,16 @@ mod tests {
             output_dir,
             environment.clone(),
             proxy_function.clone(),
+            true,
+            Some(SustainedLoad {
+                duration_secs: 600,
+                rps: 5.0,
+            }),
+            true,
+            Some(WarmPacing {
+                interval_secs: Some(30),
+                environments: Some(20),
+            }),
         );
 
         assert_eq!(config.function_name, function_name);
@@ -831,6 +1202,16 @@ mod tests {
         assert_eq!(config.output_dir, output_dir);
         assert_eq!(config.environment, environment);
         assert_eq!(config.proxy_function, proxy_function);
+        assert!(config.exclude_failed);
+        assert!(config.response_streaming);
+        assert_eq!(
+            config.sustained_load.map(|load| load.duration_secs),
+            Some(600)
+        );
+        assert_eq!(
+            WarmPacing::round_invocations(config.warm_pacing, config.concurrent),
+            20
+        );
     }
 
     #[tokio::test]
@@ -840,6 +1221,7 @@ mod tests {
         let output_dir_str = output_dir_path.to_str().unwrap();
 
         let report = BenchmarkReport {
+            schema_version: REPORT_SCHEMA_VERSION,
             config: BenchmarkConfig {
                 function_name: "my_test_lambda".to_string(),
                 memory_size: 256,
@@ -852,6 +1234,10 @@ mod tests {
                     key: "TEST_ENV".to_string(),
                     value: "TEST_VAL".to_string(),
                 }],
+                sustained_load: None,
+                response_streaming: false,
+                variant: None,
+                warm_pacing: None,
             },
             cold_starts: vec![ColdStartMetrics {
                 timestamp: "ts_cold".to_string(),
@@ -867,6 +1253,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             }],
             warm_starts: vec![WarmStartMetrics {
                 timestamp: "ts_warm".to_string(),
@@ -880,12 +1268,19 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             }],
             client_measurements: vec![ClientMetrics {
                 timestamp: "ts_client".to_string(),
                 client_duration: 30.0,
                 memory_size: 256,
+                time_to_first_byte: None,
+                stream_duration: None,
+                trace_id: None,
             }],
+            errors: vec![],
+            environment_recycles: vec![],
         };
 
         let save_result = save_report(report.clone(), output_dir_str, false).await;
@@ -944,6 +1339,7 @@ mod tests {
         let specific_memory_for_test = 128; // Provide a specific memory
 
         let report = BenchmarkReport {
+            schema_version: REPORT_SCHEMA_VERSION,
             config: BenchmarkConfig {
                 function_name: "my_specific_mem_lambda".to_string(),
                 memory_size: specific_memory_for_test, // Use specific memory
@@ -953,10 +1349,16 @@ mod tests {
                 runtime: Some("python3.9".to_string()),
                 architecture: Some("x86_64".to_string()),
                 environment: vec![],
+                sustained_load: None,
+                response_streaming: false,
+                variant: None,
+                warm_pacing: None,
             },
             cold_starts: vec![],
             warm_starts: vec![],
             client_measurements: vec![],
+            errors: vec![],
+            environment_recycles: vec![],
         };
 
         let save_result = save_report(report.clone(), output_dir_str, false).await;
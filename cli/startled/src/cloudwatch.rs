@@ -0,0 +1,237 @@
+//! Publishes the summary statistics of each benchmark run as CloudWatch custom metrics.
+//!
+//! With `--publish-cloudwatch-namespace <NAMESPACE>`, the P50, P95 and P99 of the cold and warm
+//! start metrics of each run are written to a CloudWatch Logs log group in the Embedded Metric
+//! Format, from which CloudWatch extracts them as custom metrics of the namespace, e.g.
+//! `ColdStartInitDuration` with the `FunctionName`, `MemorySize` and `Percentile` dimensions.
+//! Dashboards and alarms can then follow the benchmark trends over time.
+
+use anyhow::{Context, Result};
+use aws_sdk_cloudwatchlogs::types::InputLogEvent;
+use aws_sdk_cloudwatchlogs::Client as CloudWatchLogsClient;
+use serde_json::{json, Map, Value};
+
+use crate::diff::{Stat, METRICS};
+use crate::stats::calculate_stats;
+use crate::types::BenchmarkReport;
+
+/// Default log group of the published metrics
+pub const DEFAULT_LOG_GROUP: &str = "/startled/metrics";
+
+// The published percentiles, one metric event each
+const PERCENTILES: [Stat; 3] = [Stat::P50, Stat::P95, Stat::P99];
+
+/// Writes the metric events of the benchmark runs to a log stream of the session
+#[derive(Debug, Clone)]
+pub struct CloudWatchPublisher {
+    client: CloudWatchLogsClient,
+    namespace: String,
+    log_group: String,
+    log_stream: String,
+}
+
+impl CloudWatchPublisher {
+    /// Creates the log group if needed, and a log stream for the metric events of this session
+    pub async fn new(
+        client: CloudWatchLogsClient,
+        namespace: impl Into<String>,
+        log_group: impl Into<String>,
+    ) -> Result<Self> {
+        let log_group = log_group.into();
+        let log_stream = format!(
+            "startled-{}-{}",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S"),
+            std::process::id()
+        );
+
+        if let Err(err) = client
+            .create_log_group()
+            .log_group_name(&log_group)
+            .send()
+            .await
+        {
+            if !err
+                .as_service_error()
+                .is_some_and(|e| e.is_resource_already_exists_exception())
+            {
+                return Err(err)
+                    .with_context(|| format!("Failed to create the log group {}", log_group));
+            }
+        }
+        client
+            .create_log_stream()
+            .log_group_name(&log_group)
+            .log_stream_name(&log_stream)
+            .send()
+            .await
+            .with_context(|| format!("Failed to create a log stream in {}", log_group))?;
+
+        Ok(Self {
+            client,
+            namespace: namespace.into(),
+            log_group,
+            log_stream,
+        })
+    }
+
+    /// Publishes the percentiles of the metrics of a benchmark run
+    pub async fn publish(&self, report: &BenchmarkReport) -> Result<()> {
+        let timestamp_ms = chrono::Utc::now().timestamp_millis();
+        let events = metric_events(&self.namespace, report, timestamp_ms)
+            .into_iter()
+            .map(|event| {
+                InputLogEvent::builder()
+                    .timestamp(timestamp_ms)
+                    .message(event.to_string())
+                    .build()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.client
+            .put_log_events()
+            .log_group_name(&self.log_group)
+            .log_stream_name(&self.log_stream)
+            .set_log_events(Some(events))
+            .send()
+            .await
+            .context("Failed to put the metric events")?;
+        Ok(())
+    }
+}
+
+/// Returns the Embedded Metric Format events of a benchmark run, one per percentile
+fn metric_events(namespace: &str, report: &BenchmarkReport, timestamp_ms: i64) -> Vec<Value> {
+    let stats: Vec<_> = METRICS
+        .iter()
+        .filter_map(|metric| {
+            let values = (metric.extract)(report);
+            (!values.is_empty()).then(|| (metric, calculate_stats(&values)))
+        })
+        .collect();
+    if stats.is_empty() {
+        return Vec::new();
+    }
+    let definitions: Vec<Value> = stats
+        .iter()
+        .map(|(metric, _)| json!({ "Name": metric_name(metric.name), "Unit": unit(metric.unit) }))
+        .collect();
+
+    PERCENTILES
+        .iter()
+        .map(|percentile| {
+            let mut event = Map::new();
+            event.insert(
+                "_aws".to_string(),
+                json!({
+                    "Timestamp": timestamp_ms,
+                    "CloudWatchMetrics": [{
+                        "Namespace": namespace,
+                        "Dimensions": [["FunctionName", "MemorySize", "Percentile"]],
+                        "Metrics": definitions,
+                    }],
+                }),
+            );
+            event.insert(
+                "FunctionName".to_string(),
+                json!(report.config.function_name),
+            );
+            event.insert(
+                "MemorySize".to_string(),
+                json!(report.config.memory_size.to_string()),
+            );
+            event.insert("Percentile".to_string(), json!(percentile.name()));
+            for (metric, metric_stats) in &stats {
+                event.insert(
+                    metric_name(metric.name),
+                    json!(percentile.value(metric_stats)),
+                );
+            }
+            Value::Object(event)
+        })
+        .collect()
+}
+
+// "Cold Start Init Duration" is published as "ColdStartInitDuration"
+fn metric_name(name: &str) -> String {
+    name.split_whitespace().collect()
+}
+
+fn unit(unit: &str) -> &'static str {
+    match unit {
+        "ms" => "Milliseconds",
+        "MB" => "Megabytes",
+        _ => "None",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::REPORT_SCHEMA_VERSION;
+    use crate::types::{BenchmarkConfig, ColdStartMetrics};
+
+    #[test]
+    fn test_metric_events() {
+        let cold_start = |init_duration: f64| ColdStartMetrics {
+            timestamp: String::new(),
+            init_duration,
+            duration: 5.0,
+            extension_overhead: 0.0,
+            total_cold_start_duration: Some(init_duration + 5.0),
+            billed_duration: 6,
+            max_memory_used: 40,
+            memory_size: 512,
+            response_latency_ms: None,
+            response_duration_ms: None,
+            runtime_overhead_ms: None,
+            produced_bytes: None,
+            runtime_done_metrics_duration_ms: None,
+            extension_overheads: Default::default(),
+            trace_id: None,
+        };
+        let mut report = BenchmarkReport {
+            schema_version: REPORT_SCHEMA_VERSION,
+            config: BenchmarkConfig {
+                function_name: "rust-otel".to_string(),
+                memory_size: 512,
+                concurrent_invocations: 1,
+                number: 1,
+                timestamp: String::new(),
+                runtime: None,
+                architecture: None,
+                environment: Vec::new(),
+                sustained_load: None,
+                response_streaming: false,
+                variant: None,
+                warm_pacing: None,
+            },
+            cold_starts: vec![cold_start(100.0)],
+            warm_starts: Vec::new(),
+            client_measurements: Vec::new(),
+            errors: Vec::new(),
+            environment_recycles: vec![],
+        };
+
+        let events = metric_events("Startled", &report, 1_700_000_000_000);
+        assert_eq!(events.len(), 3);
+        let event = &events[1];
+        assert_eq!(event["Percentile"], "p95");
+        assert_eq!(event["FunctionName"], "rust-otel");
+        assert_eq!(event["MemorySize"], "512");
+        assert_eq!(event["ColdStartInitDuration"], 100.0);
+        assert_eq!(event["ColdStartTotalDuration"], 105.0);
+        assert!(event.get("WarmStartServerDuration").is_none());
+        let directive = &event["_aws"]["CloudWatchMetrics"][0];
+        assert_eq!(directive["Namespace"], "Startled");
+        assert_eq!(
+            directive["Metrics"][0],
+            json!({ "Name": "ColdStartInitDuration", "Unit": "Milliseconds" })
+        );
+        assert_eq!(directive["Metrics"].as_array().unwrap().len(), 3);
+
+        report.cold_starts.clear();
+        assert!(metric_events("Startled", &report, 0).is_empty());
+    }
+}
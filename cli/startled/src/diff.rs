@@ -0,0 +1,1082 @@
+//! Comparison of two benchmark runs (`startled diff`).
+//!
+//! The JSON results of a baseline and a candidate directory are matched by their path relative
+//! to each directory (e.g. `my-app/512mb/rust-otel.json`), and the mean, P50, P95 and P99 of the
+//! key cold and warm start metrics are compared. Thresholds such as
+//! `cold-start-total-duration:p95:10%` set the maximum increase allowed for a statistic; when a
+//! candidate exceeds one, the command fails, so it can be used as a regression gate in CI.
+//!
+//! A run can be pinned as a named baseline (`startled baseline`), a single JSON file that is
+//! compared by name instead of keeping its results directory around. Each metric change also
+//! gets a p-value from Welch's t-test or the Mann-Whitney U test on the raw samples, so changes
+//! that are within the noise of small samples are told apart from significant ones.
+//!
+//! For CI test reporting, every threshold evaluated for a result can be written as a JUnit XML
+//! test case (`--junit`), and regressions printed as GitHub Actions annotations
+//! (`--github-annotations`).
+
+use crate::stats::{calculate_stats, mann_whitney_u_test, welch_t_test, MetricsStats};
+use crate::types::BenchmarkReport;
+use crate::utils::validate_fs_safe_name;
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use comfy_table::{
+    presets::*, Attribute, Cell, CellAlignment, ContentArrangement, Table, TableComponent,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory of the named baselines written by `startled baseline`
+pub const DEFAULT_BASELINE_DIR: &str = ".startled-baselines";
+
+/// A metric compared between runs
+pub struct MetricDefinition {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub unit: &'static str,
+    pub(crate) extract: fn(&BenchmarkReport) -> Vec<f64>,
+}
+
+/// The compared metrics, with IDs matching the report chart pages
+pub static METRICS: [MetricDefinition; 8] = [
+    MetricDefinition {
+        id: "cold-start-init",
+        name: "Cold Start Init Duration",
+        unit: "ms",
+        extract: |r| r.cold_starts.iter().map(|cs| cs.init_duration).collect(),
+    },
+    MetricDefinition {
+        id: "cold-start-server",
+        name: "Cold Start Server Duration",
+        unit: "ms",
+        extract: |r| r.cold_starts.iter().map(|cs| cs.duration).collect(),
+    },
+    MetricDefinition {
+        id: "cold-start-total-duration",
+        name: "Cold Start Total Duration",
+        unit: "ms",
+        extract: |r| {
+            r.cold_starts
+                .iter()
+                .filter_map(|cs| cs.total_cold_start_duration)
+                .collect()
+        },
+    },
+    MetricDefinition {
+        id: "warm-start-client-duration",
+        name: "Warm Start Client Duration",
+        unit: "ms",
+        extract: |r| {
+            r.client_measurements
+                .iter()
+                .map(|cm| cm.client_duration)
+                .collect()
+        },
+    },
+    MetricDefinition {
+        id: "warm-start-server-duration",
+        name: "Warm Start Server Duration",
+        unit: "ms",
+        extract: |r| r.warm_starts.iter().map(|ws| ws.duration).collect(),
+    },
+    MetricDefinition {
+        id: "warm-start-extension-overhead",
+        name: "Warm Start Extension Overhead",
+        unit: "ms",
+        extract: |r| {
+            r.warm_starts
+                .iter()
+                .map(|ws| ws.extension_overhead)
+                .collect()
+        },
+    },
+    MetricDefinition {
+        id: "warm-start-billed-duration",
+        name: "Warm Start Billed Duration",
+        unit: "ms",
+        extract: |r| {
+            r.warm_starts
+                .iter()
+                .map(|ws| ws.billed_duration as f64)
+                .collect()
+        },
+    },
+    MetricDefinition {
+        id: "warm-start-memory-usage",
+        name: "Warm Start Memory Usage",
+        unit: "MB",
+        extract: |r| {
+            r.warm_starts
+                .iter()
+                .map(|ws| ws.max_memory_used as f64)
+                .collect()
+        },
+    },
+];
+
+/// A compared statistic of a metric
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stat {
+    Mean,
+    P50,
+    P95,
+    P99,
+}
+
+impl Stat {
+    const ALL: [Stat; 4] = [Stat::Mean, Stat::P50, Stat::P95, Stat::P99];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Stat::Mean => "mean",
+            Stat::P50 => "p50",
+            Stat::P95 => "p95",
+            Stat::P99 => "p99",
+        }
+    }
+
+    pub(crate) fn value(self, stats: &MetricsStats) -> f64 {
+        match self {
+            Stat::Mean => stats.mean,
+            Stat::P50 => stats.p50,
+            Stat::P95 => stats.p95,
+            Stat::P99 => stats.p99,
+        }
+    }
+}
+
+/// Maximum increase of a statistic allowed by `--threshold <METRIC:STAT:PERCENT>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Threshold {
+    /// Metric ID, or `*` for all metrics
+    pub metric: String,
+    pub stat: Stat,
+    pub max_increase_pct: f64,
+}
+
+impl std::str::FromStr for Threshold {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [metric, stat, percent] = s.split(':').collect::<Vec<_>>()[..] else {
+            anyhow::bail!(
+                "Invalid threshold '{}'. Must be METRIC:STAT:PERCENT, e.g. cold-start-init:p95:10%",
+                s
+            );
+        };
+        if metric != "*" && !METRICS.iter().any(|m| m.id == metric) {
+            anyhow::bail!(
+                "Unknown metric '{}' in threshold. Must be * or one of: {}",
+                metric,
+                METRICS.iter().map(|m| m.id).collect::<Vec<_>>().join(", ")
+            );
+        }
+        let stat = Stat::ALL
+            .into_iter()
+            .find(|st| st.name().eq_ignore_ascii_case(stat))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown statistic '{}' in threshold. Must be mean, p50, p95 or p99",
+                    stat
+                )
+            })?;
+        let max_increase_pct = percent
+            .trim_start_matches('+')
+            .trim_end_matches('%')
+            .parse::<f64>()
+            .ok()
+            .filter(|pct| pct.is_finite())
+            .ok_or_else(|| anyhow!("Invalid percentage '{}' in threshold", percent))?;
+        Ok(Threshold {
+            metric: metric.to_string(),
+            stat,
+            max_increase_pct,
+        })
+    }
+}
+
+/// Statistical test deciding whether a metric change is significant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignificanceTest {
+    /// Welch's t-test, comparing the means without assuming equal variances
+    Welch,
+    /// Mann-Whitney U test, comparing the distributions without assuming normality
+    MannWhitney,
+}
+
+impl SignificanceTest {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            SignificanceTest::Welch => "welch",
+            SignificanceTest::MannWhitney => "mann-whitney",
+        }
+    }
+}
+
+impl std::str::FromStr for SignificanceTest {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [SignificanceTest::Welch, SignificanceTest::MannWhitney]
+            .into_iter()
+            .find(|test| test.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown significance test '{}'. Must be welch or mann-whitney",
+                    s
+                )
+            })
+    }
+}
+
+/// How metric changes are tested for significance, from `--significance-test`, `--alpha` and
+/// `--require-significance`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignificanceOptions {
+    pub test: SignificanceTest,
+    /// A change is significant when its p-value is below this level
+    pub alpha: f64,
+    /// Only changes that are significant can exceed a threshold
+    pub required: bool,
+}
+
+impl Default for SignificanceOptions {
+    fn default() -> Self {
+        Self {
+            test: SignificanceTest::MannWhitney,
+            alpha: 0.05,
+            required: false,
+        }
+    }
+}
+
+impl SignificanceOptions {
+    pub fn new(test: SignificanceTest, alpha: f64, required: bool) -> Result<Self> {
+        if !(alpha > 0.0 && alpha < 1.0) {
+            anyhow::bail!(
+                "Invalid significance level {}: must be between 0 and 1",
+                alpha
+            );
+        }
+        Ok(Self {
+            test,
+            alpha,
+            required,
+        })
+    }
+}
+
+/// Statistics of a metric in both runs
+pub struct MetricDelta {
+    pub metric: &'static MetricDefinition,
+    pub baseline: MetricsStats,
+    pub candidate: MetricsStats,
+    /// P-value of Welch's t-test, if both runs have at least two values
+    pub welch_p: Option<f64>,
+    /// P-value of the Mann-Whitney U test
+    pub mann_whitney_p: Option<f64>,
+}
+
+impl MetricDelta {
+    /// Relative change of a statistic in percent, if the baseline is not zero
+    pub fn change_pct(&self, stat: Stat) -> Option<f64> {
+        let baseline = stat.value(&self.baseline);
+        let candidate = stat.value(&self.candidate);
+        (baseline != 0.0 && baseline.is_finite() && candidate.is_finite())
+            .then(|| (candidate - baseline) / baseline * 100.0)
+    }
+
+    /// P-value of the change according to a test
+    pub fn p_value(&self, test: SignificanceTest) -> Option<f64> {
+        match test {
+            SignificanceTest::Welch => self.welch_p,
+            SignificanceTest::MannWhitney => self.mann_whitney_p,
+        }
+    }
+
+    /// Returns true if the change is significant at the level of the options
+    pub fn is_significant(&self, options: &SignificanceOptions) -> bool {
+        self.p_value(options.test)
+            .is_some_and(|p| p < options.alpha)
+    }
+}
+
+/// A run pinned under a name by `startled baseline`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Baseline {
+    pub name: String,
+    /// When the baseline was pinned, in RFC 3339
+    pub created: String,
+    /// Benchmark results, keyed by path relative to the results directory
+    pub results: BTreeMap<String, BenchmarkReport>,
+}
+
+/// Comparison of the results of both runs
+#[derive(Default)]
+pub struct RunDiff {
+    /// Metric deltas of the results found in both runs, by relative path
+    pub results: BTreeMap<String, Vec<MetricDelta>>,
+    pub only_in_baseline: Vec<String>,
+    pub only_in_candidate: Vec<String>,
+}
+
+/// A statistic whose increase exceeds a threshold
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub result: String,
+    pub metric: &'static str,
+    pub stat: Stat,
+    pub change_pct: f64,
+    pub max_increase_pct: f64,
+}
+
+/// A threshold evaluated for a metric of a result, reported as a JUnit test case
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThresholdCheck {
+    pub result: String,
+    pub metric: &'static str,
+    pub stat: Stat,
+    pub max_increase_pct: f64,
+    /// Change of the statistic, None if the baseline is zero
+    pub change_pct: Option<f64>,
+    /// The change is not statistically significant, and `--require-significance` is set
+    pub ignored: bool,
+}
+
+impl ThresholdCheck {
+    /// Returns true if the change exceeds the threshold, ignoring the significance
+    pub fn is_exceeded(&self) -> bool {
+        self.change_pct
+            .is_some_and(|change_pct| change_pct > self.max_increase_pct)
+    }
+
+    /// Returns true if the check fails
+    pub fn is_violation(&self) -> bool {
+        !self.ignored && self.is_exceeded()
+    }
+
+    fn name(&self) -> String {
+        format!(
+            "{} {} <= +{}%",
+            self.metric,
+            self.stat.name(),
+            self.max_increase_pct
+        )
+    }
+
+    fn message(&self) -> String {
+        format!(
+            "{} {} {} (threshold +{}%)",
+            self.metric,
+            self.stat.name(),
+            format_change(self.change_pct),
+            self.max_increase_pct
+        )
+    }
+}
+
+/// Loads the benchmark results of a directory and its subdirectories, keyed by relative path
+pub fn load_results(directory: &Path) -> Result<BTreeMap<String, BenchmarkReport>> {
+    fn visit(
+        root: &Path,
+        directory: &Path,
+        results: &mut BTreeMap<String, BenchmarkReport>,
+    ) -> Result<()> {
+        let entries = fs::read_dir(directory)
+            .with_context(|| format!("Failed to read directory {}", directory.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                visit(root, &path, results)?;
+            } else if path.extension().and_then(|s| s.to_str()) == Some("json") {
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let report: BenchmarkReport = serde_json::from_str(&content)
+                    .with_context(|| format!("Invalid benchmark result {}", path.display()))?;
+                let key = path
+                    .strip_prefix(root)
+                    .unwrap_or(&path)
+                    .with_extension("")
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                results.insert(key, report);
+            }
+        }
+        Ok(())
+    }
+
+    let mut results = BTreeMap::new();
+    visit(directory, directory, &mut results)?;
+    Ok(results)
+}
+
+/// Returns the path of a named baseline in the default baseline directory
+pub fn baseline_path(name: &str) -> PathBuf {
+    Path::new(DEFAULT_BASELINE_DIR).join(format!("{}.json", name))
+}
+
+/// Pins the results of a directory as a named baseline, returning the written file
+pub fn save_baseline(results_dir: &Path, name: &str, output: Option<&Path>) -> Result<PathBuf> {
+    validate_fs_safe_name(name).context("Invalid baseline name")?;
+    let results = load_results(results_dir)?;
+    if results.is_empty() {
+        return Err(anyhow!(
+            "No benchmark results found in {}",
+            results_dir.display()
+        ));
+    }
+    let baseline = Baseline {
+        name: name.to_string(),
+        created: chrono::Utc::now().to_rfc3339(),
+        results,
+    };
+
+    let path = output.map_or_else(|| baseline_path(name), Path::to_path_buf);
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(&baseline)?)
+        .with_context(|| format!("Failed to write baseline {}", path.display()))?;
+    println!(
+        "📌 Baseline '{}' with {} result(s) written to {}",
+        name,
+        baseline.results.len(),
+        path.display()
+    );
+    Ok(path)
+}
+
+/// Loads the results of a run from a results directory, a baseline file or the name of a baseline
+pub fn load_run(run: &Path) -> Result<BTreeMap<String, BenchmarkReport>> {
+    if run.is_dir() {
+        return load_results(run);
+    }
+    let path = if run.is_file() {
+        run.to_path_buf()
+    } else {
+        let name = run.to_string_lossy();
+        let path = baseline_path(&name);
+        if validate_fs_safe_name(&name).is_err() || !path.is_file() {
+            return Err(anyhow!(
+                "{} is not a results directory, a baseline file or a baseline name",
+                run.display()
+            ));
+        }
+        path
+    };
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let baseline: Baseline = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid baseline {}", path.display()))?;
+    Ok(baseline.results)
+}
+
+/// Compares the metrics of the results found in both runs
+pub fn compare_runs(
+    baseline: &BTreeMap<String, BenchmarkReport>,
+    candidate: &BTreeMap<String, BenchmarkReport>,
+) -> RunDiff {
+    let mut diff = RunDiff::default();
+    for (key, baseline_report) in baseline {
+        let Some(candidate_report) = candidate.get(key) else {
+            diff.only_in_baseline.push(key.clone());
+            continue;
+        };
+        let deltas = METRICS
+            .iter()
+            .filter_map(|metric| {
+                let baseline_values = (metric.extract)(baseline_report);
+                let candidate_values = (metric.extract)(candidate_report);
+                if baseline_values.is_empty() || candidate_values.is_empty() {
+                    return None;
+                }
+                Some(MetricDelta {
+                    metric,
+                    baseline: calculate_stats(&baseline_values),
+                    candidate: calculate_stats(&candidate_values),
+                    welch_p: welch_t_test(&baseline_values, &candidate_values),
+                    mann_whitney_p: mann_whitney_u_test(&baseline_values, &candidate_values),
+                })
+            })
+            .collect();
+        diff.results.insert(key.clone(), deltas);
+    }
+    diff.only_in_candidate = candidate
+        .keys()
+        .filter(|key| !baseline.contains_key(*key))
+        .cloned()
+        .collect();
+    diff
+}
+
+/// Evaluates the thresholds matching each compared metric of each result
+pub fn check_thresholds(
+    diff: &RunDiff,
+    thresholds: &[Threshold],
+    significance: &SignificanceOptions,
+) -> Vec<ThresholdCheck> {
+    let mut checks = Vec::new();
+    for (result, deltas) in &diff.results {
+        for delta in deltas {
+            let ignored = significance.required && !delta.is_significant(significance);
+            for threshold in thresholds
+                .iter()
+                .filter(|t| t.metric == "*" || t.metric == delta.metric.id)
+            {
+                checks.push(ThresholdCheck {
+                    result: result.clone(),
+                    metric: delta.metric.id,
+                    stat: threshold.stat,
+                    max_increase_pct: threshold.max_increase_pct,
+                    change_pct: delta.change_pct(threshold.stat),
+                    ignored,
+                });
+            }
+        }
+    }
+    checks
+}
+
+/// Returns the statistics whose increase exceeds a threshold, only considering significant
+/// changes if the options require it
+pub fn find_violations(
+    diff: &RunDiff,
+    thresholds: &[Threshold],
+    significance: &SignificanceOptions,
+) -> Vec<Violation> {
+    violations(&check_thresholds(diff, thresholds, significance))
+}
+
+fn violations(checks: &[ThresholdCheck]) -> Vec<Violation> {
+    checks
+        .iter()
+        .filter(|check| check.is_violation())
+        .filter_map(|check| {
+            Some(Violation {
+                result: check.result.clone(),
+                metric: check.metric,
+                stat: check.stat,
+                change_pct: check.change_pct?,
+                max_increase_pct: check.max_increase_pct,
+            })
+        })
+        .collect()
+}
+
+/// Compares two runs, prints the deltas and fails if a threshold is exceeded.
+///
+/// Each run is a results directory, a baseline file or the name of a baseline.
+pub fn run_diff(
+    baseline_run: &Path,
+    candidate_run: &Path,
+    thresholds: &[Threshold],
+    significance: &SignificanceOptions,
+    output: Option<&Path>,
+    junit: Option<&Path>,
+    github_annotations: bool,
+) -> Result<()> {
+    let baseline = load_run(baseline_run)?;
+    let candidate = load_run(candidate_run)?;
+    let diff = compare_runs(&baseline, &candidate);
+    if diff.results.is_empty() {
+        return Err(anyhow!(
+            "No benchmark results found in both {} and {}",
+            baseline_run.display(),
+            candidate_run.display()
+        ));
+    }
+
+    let checks = check_thresholds(&diff, thresholds, significance);
+    let violations = violations(&checks);
+    print_diff(&diff, &violations, significance);
+    if let Some(output) = output {
+        fs::write(output, render_markdown(&diff, &violations, significance))
+            .with_context(|| format!("Failed to write diff report {}", output.display()))?;
+        println!("📄 Diff report written to {}", output.display());
+    }
+    if let Some(junit) = junit {
+        fs::write(junit, render_junit(&checks))
+            .with_context(|| format!("Failed to write JUnit report {}", junit.display()))?;
+        println!("📄 JUnit report written to {}", junit.display());
+    }
+    if github_annotations {
+        for annotation in github_annotation_lines(&checks) {
+            println!("{}", annotation);
+        }
+    }
+
+    if !violations.is_empty() {
+        println!("\n{}", "Regressions:".red().bold());
+        for violation in &violations {
+            println!(
+                "  ❌ {}: {} {} {:+.1}% (threshold +{}%)",
+                violation.result,
+                violation.metric,
+                violation.stat.name(),
+                violation.change_pct,
+                violation.max_increase_pct
+            );
+        }
+        return Err(anyhow!("{} threshold(s) exceeded", violations.len()));
+    }
+    if !thresholds.is_empty() {
+        println!("\n{}", "✓ No threshold exceeded.".green());
+    }
+    Ok(())
+}
+
+fn is_violation(violations: &[Violation], result: &str, metric: &str, stat: Stat) -> bool {
+    violations
+        .iter()
+        .any(|v| v.result == result && v.metric == metric && v.stat == stat)
+}
+
+fn format_change(change_pct: Option<f64>) -> String {
+    change_pct.map_or_else(|| "n/a".to_string(), |pct| format!("{:+.1}%", pct))
+}
+
+fn format_p_value(p_value: Option<f64>) -> String {
+    match p_value {
+        None => "n/a".to_string(),
+        Some(p) if p < 0.001 => "<0.001".to_string(),
+        Some(p) => format!("{:.3}", p),
+    }
+}
+
+fn print_diff(diff: &RunDiff, violations: &[Violation], significance: &SignificanceOptions) {
+    const TABLE_WIDTH: u16 = 110;
+
+    for (result, deltas) in &diff.results {
+        println!("\n{}", format!("Result: {}", result).bright_blue().bold());
+        println!("{}", "─".repeat(TABLE_WIDTH as usize).bright_black());
+        let mut table = Table::new();
+        table
+            .load_preset(NOTHING)
+            .set_style(TableComponent::MiddleHeaderIntersections, '┴')
+            .set_style(TableComponent::BottomBorder, '─')
+            .set_style(TableComponent::BottomBorderIntersections, '─')
+            .set_style(TableComponent::HeaderLines, '─')
+            .set_content_arrangement(ContentArrangement::DynamicFullWidth)
+            .set_width(TABLE_WIDTH)
+            .set_header(
+                std::iter::once(Cell::new("Metric").add_attribute(Attribute::Bold)).chain(
+                    Stat::ALL
+                        .map(|stat| {
+                            Cell::new(stat.name().to_uppercase()).add_attribute(Attribute::Bold)
+                        })
+                        .into_iter()
+                        .chain(std::iter::once(
+                            Cell::new(format!("P ({})", significance.test.name()))
+                                .add_attribute(Attribute::Bold),
+                        )),
+                ),
+            );
+        for delta in deltas {
+            let mut row = vec![Cell::new(format!(
+                "{} ({})",
+                delta.metric.name, delta.metric.unit
+            ))];
+            for stat in Stat::ALL {
+                let text = format!(
+                    "{:.2} ({})",
+                    stat.value(&delta.candidate),
+                    format_change(delta.change_pct(stat))
+                );
+                let text = if is_violation(violations, result, delta.metric.id, stat) {
+                    text.red().bold().to_string()
+                } else {
+                    text
+                };
+                row.push(Cell::new(text).set_alignment(CellAlignment::Right));
+            }
+            let p_value = format_p_value(delta.p_value(significance.test));
+            let p_value = if delta.is_significant(significance) {
+                format!("{} *", p_value).bold().to_string()
+            } else {
+                p_value.bright_black().to_string()
+            };
+            row.push(Cell::new(p_value).set_alignment(CellAlignment::Right));
+            table.add_row(row);
+        }
+        println!("{}", table);
+    }
+
+    for (label, results) in [
+        ("Only in baseline", &diff.only_in_baseline),
+        ("Only in candidate", &diff.only_in_candidate),
+    ] {
+        if !results.is_empty() {
+            println!(
+                "\n{} {}",
+                format!("{}:", label).yellow(),
+                results.join(", ")
+            );
+        }
+    }
+    println!(
+        "\n{}",
+        format!(
+            "* significant change ({} test, p < {})",
+            significance.test.name(),
+            significance.alpha
+        )
+        .bright_black()
+    );
+}
+
+/// Renders the deltas as a Markdown report
+fn render_markdown(
+    diff: &RunDiff,
+    violations: &[Violation],
+    significance: &SignificanceOptions,
+) -> String {
+    let mut out = String::from("# Benchmark Diff\n");
+    let _ = writeln!(
+        out,
+        "\nP-values from the {} test; changes with p < {} are significant (✓).",
+        significance.test.name(),
+        significance.alpha
+    );
+    for (result, deltas) in &diff.results {
+        let _ = writeln!(out, "\n## {}\n", result);
+        let _ = writeln!(
+            out,
+            "| Metric | Stat | Baseline | Candidate | Change | p-value |"
+        );
+        let _ = writeln!(out, "|---|---|---:|---:|---:|---:|");
+        for delta in deltas {
+            // The p-value compares the samples as a whole, so it is shown once per metric
+            let p_value = format!(
+                "{}{}",
+                format_p_value(delta.p_value(significance.test)),
+                if delta.is_significant(significance) {
+                    " ✓"
+                } else {
+                    ""
+                }
+            );
+            for (i, stat) in Stat::ALL.into_iter().enumerate() {
+                let marker = if is_violation(violations, result, delta.metric.id, stat) {
+                    " ❌"
+                } else {
+                    ""
+                };
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {:.2} {unit} | {:.2} {unit} | {}{} | {} |",
+                    delta.metric.name,
+                    stat.name(),
+                    stat.value(&delta.baseline),
+                    stat.value(&delta.candidate),
+                    format_change(delta.change_pct(stat)),
+                    marker,
+                    if i == 0 { p_value.as_str() } else { "" },
+                    unit = delta.metric.unit
+                );
+            }
+        }
+    }
+    if !violations.is_empty() {
+        let _ = writeln!(out, "\n## Regressions\n");
+        for violation in violations {
+            let _ = writeln!(
+                out,
+                "- {}: {} {} {:+.1}% (threshold +{}%)",
+                violation.result,
+                violation.metric,
+                violation.stat.name(),
+                violation.change_pct,
+                violation.max_increase_pct
+            );
+        }
+    }
+    out
+}
+
+/// Renders the threshold checks as a JUnit XML report, with a test suite per result and a test
+/// case per threshold
+fn render_junit(checks: &[ThresholdCheck]) -> String {
+    let mut suites: BTreeMap<&str, Vec<&ThresholdCheck>> = BTreeMap::new();
+    for check in checks {
+        suites.entry(&check.result).or_default().push(check);
+    }
+    let failures = |checks: &[&ThresholdCheck]| checks.iter().filter(|c| c.is_violation()).count();
+    let skipped =
+        |checks: &[&ThresholdCheck]| checks.iter().filter(|c| c.change_pct.is_none()).count();
+    let all: Vec<&ThresholdCheck> = checks.iter().collect();
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<testsuites name=\"startled diff\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+        all.len(),
+        failures(&all),
+        skipped(&all)
+    );
+    for (result, checks) in &suites {
+        let _ = writeln!(
+            out,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+            escape_xml(result),
+            checks.len(),
+            failures(checks),
+            skipped(checks)
+        );
+        for check in checks {
+            let _ = write!(
+                out,
+                "    <testcase classname=\"{}\" name=\"{}\"",
+                escape_xml(result),
+                escape_xml(&check.name())
+            );
+            if check.is_violation() {
+                let _ = writeln!(
+                    out,
+                    ">\n      <failure type=\"threshold\" message=\"{}\"/>\n    </testcase>",
+                    escape_xml(&check.message())
+                );
+            } else if check.change_pct.is_none() {
+                let _ = writeln!(
+                    out,
+                    ">\n      <skipped message=\"The baseline value is zero\"/>\n    </testcase>"
+                );
+            } else if check.ignored && check.is_exceeded() {
+                let _ = writeln!(
+                    out,
+                    ">\n      <system-out>{}, not statistically significant</system-out>\n    </testcase>",
+                    escape_xml(&check.message())
+                );
+            } else {
+                let _ = writeln!(out, "/>");
+            }
+        }
+        let _ = writeln!(out, "  </testsuite>");
+    }
+    let _ = writeln!(out, "</testsuites>");
+    out
+}
+
+/// Returns GitHub Actions workflow commands annotating the exceeded thresholds: errors for the
+/// regressions, and notices for the increases ignored as not significant
+fn github_annotation_lines(checks: &[ThresholdCheck]) -> Vec<String> {
+    checks
+        .iter()
+        .filter(|check| check.is_exceeded())
+        .map(|check| {
+            let (level, title, suffix) = if check.is_violation() {
+                ("error", "Benchmark regression", "")
+            } else {
+                (
+                    "notice",
+                    "Benchmark change not significant",
+                    ", not statistically significant",
+                )
+            };
+            format!(
+                "::{} title={}::{}",
+                level,
+                title,
+                escape_annotation(&format!("{}: {}{}", check.result, check.message(), suffix))
+            )
+        })
+        .collect()
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// Workflow command messages end at a line break, so they are percent-encoded
+fn escape_annotation(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::REPORT_SCHEMA_VERSION;
+    use crate::types::{BenchmarkConfig, ColdStartMetrics};
+
+    fn report(init_durations: &[f64]) -> BenchmarkReport {
+        BenchmarkReport {
+            schema_version: REPORT_SCHEMA_VERSION,
+            config: BenchmarkConfig {
+                function_name: "func_a".to_string(),
+                memory_size: 128,
+                concurrent_invocations: 1,
+                number: 1,
+                timestamp: "".to_string(),
+                runtime: None,
+                architecture: None,
+                environment: vec![],
+                sustained_load: None,
+                response_streaming: false,
+                variant: None,
+                warm_pacing: None,
+            },
+            cold_starts: init_durations
+                .iter()
+                .map(|&init_duration| ColdStartMetrics {
+                    timestamp: "".to_string(),
+                    init_duration,
+                    duration: 10.0,
+                    extension_overhead: 0.0,
+                    total_cold_start_duration: None,
+                    billed_duration: 10,
+                    max_memory_used: 64,
+                    memory_size: 128,
+                    response_latency_ms: None,
+                    response_duration_ms: None,
+                    runtime_overhead_ms: None,
+                    produced_bytes: None,
+                    runtime_done_metrics_duration_ms: None,
+                    extension_overheads: Default::default(),
+                    trace_id: None,
+                })
+                .collect(),
+            warm_starts: vec![],
+            client_measurements: vec![],
+            errors: vec![],
+            environment_recycles: vec![],
+        }
+    }
+
+    #[test]
+    fn test_compare_runs_with_thresholds() {
+        let baseline = BTreeMap::from([
+            ("g/128mb/func_a".to_string(), report(&[100.0, 100.0])),
+            ("g/128mb/func_b".to_string(), report(&[100.0])),
+        ]);
+        let candidate = BTreeMap::from([
+            ("g/128mb/func_a".to_string(), report(&[120.0, 120.0])),
+            ("g/128mb/func_c".to_string(), report(&[100.0])),
+        ]);
+        let diff = compare_runs(&baseline, &candidate);
+        assert_eq!(diff.only_in_baseline, vec!["g/128mb/func_b"]);
+        assert_eq!(diff.only_in_candidate, vec!["g/128mb/func_c"]);
+        let deltas = &diff.results["g/128mb/func_a"];
+        // Only the cold start metrics with values in both runs are compared
+        assert_eq!(
+            deltas.iter().map(|d| d.metric.id).collect::<Vec<_>>(),
+            vec!["cold-start-init", "cold-start-server"]
+        );
+        assert!((deltas[0].change_pct(Stat::P95).unwrap() - 20.0).abs() < 1e-9);
+        assert_eq!(deltas[1].change_pct(Stat::Mean), Some(0.0));
+
+        let thresholds: Vec<Threshold> = ["cold-start-init:p95:+10%", "*:mean:25"]
+            .iter()
+            .map(|t| t.parse().unwrap())
+            .collect();
+        let violations = find_violations(&diff, &thresholds, &SignificanceOptions::default());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].metric, "cold-start-init");
+        assert_eq!(violations[0].stat, Stat::P95);
+
+        for invalid in [
+            "cold-start-init:p95",
+            "unknown:p95:10",
+            "cold-start-init:p90:10",
+            "cold-start-init:p95:ten",
+        ] {
+            assert!(invalid.parse::<Threshold>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_significance_and_baselines() {
+        let baseline = BTreeMap::from([(
+            "g/128mb/func_a".to_string(),
+            report(&[100.0, 102.0, 98.0, 101.0, 99.0, 100.0]),
+        )]);
+        // A shift well beyond the noise, and a small change within it
+        let shifted = BTreeMap::from([(
+            "g/128mb/func_a".to_string(),
+            report(&[130.0, 131.0, 129.0, 132.0, 128.0, 130.0]),
+        )]);
+        let noisy = BTreeMap::from([(
+            "g/128mb/func_a".to_string(),
+            report(&[101.0, 99.0, 103.0, 98.0, 102.0, 100.0]),
+        )]);
+        let threshold: Vec<Threshold> = vec!["cold-start-init:mean:0.1%".parse().unwrap()];
+
+        for test in [SignificanceTest::Welch, SignificanceTest::MannWhitney] {
+            let options = SignificanceOptions::new(test, 0.05, true).unwrap();
+            let diff = compare_runs(&baseline, &shifted);
+            assert!(diff.results["g/128mb/func_a"][0].is_significant(&options));
+            assert_eq!(find_violations(&diff, &threshold, &options).len(), 1);
+
+            let diff = compare_runs(&baseline, &noisy);
+            assert!(!diff.results["g/128mb/func_a"][0].is_significant(&options));
+            assert!(find_violations(&diff, &threshold, &options).is_empty());
+            // Without requiring significance, the percentage alone exceeds the threshold
+            let options = SignificanceOptions::new(test, 0.05, false).unwrap();
+            assert_eq!(find_violations(&diff, &threshold, &options).len(), 1);
+        }
+        assert!(SignificanceOptions::new(SignificanceTest::Welch, 1.5, false).is_err());
+        assert_eq!(
+            "Mann-Whitney".parse::<SignificanceTest>().unwrap(),
+            SignificanceTest::MannWhitney
+        );
+
+        // A pinned baseline loads back the results of its directory
+        let dir = tempfile::tempdir().unwrap();
+        let results_dir = dir.path().join("results").join("g").join("128mb");
+        fs::create_dir_all(&results_dir).unwrap();
+        fs::write(
+            results_dir.join("func_a.json"),
+            serde_json::to_string(&baseline["g/128mb/func_a"]).unwrap(),
+        )
+        .unwrap();
+        let file = dir.path().join("main.json");
+        save_baseline(&dir.path().join("results"), "main", Some(&file)).unwrap();
+        let loaded = load_run(&file).unwrap();
+        assert_eq!(loaded.keys().collect::<Vec<_>>(), vec!["g/128mb/func_a"]);
+        assert!(save_baseline(&dir.path().join("results"), "../main", None).is_err());
+        assert!(load_run(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_junit_and_annotations() {
+        let baseline = BTreeMap::from([
+            ("g/128mb/a&b".to_string(), report(&[100.0, 100.0])),
+            ("g/128mb/func_b".to_string(), report(&[0.0, 0.0])),
+        ]);
+        let candidate = BTreeMap::from([
+            ("g/128mb/a&b".to_string(), report(&[120.0, 120.0])),
+            ("g/128mb/func_b".to_string(), report(&[10.0, 10.0])),
+        ]);
+        let diff = compare_runs(&baseline, &candidate);
+        let thresholds = vec!["cold-start-init:p95:10%".parse().unwrap()];
+        let checks = check_thresholds(&diff, &thresholds, &SignificanceOptions::default());
+        assert_eq!(checks.len(), 2);
+
+        let junit = render_junit(&checks);
+        assert!(junit.contains(
+            "<testsuites name=\"startled diff\" tests=\"2\" failures=\"1\" skipped=\"1\">"
+        ));
+        assert!(junit.contains("<testsuite name=\"g/128mb/a&amp;b\" tests=\"1\" failures=\"1\""));
+        assert!(junit.contains(
+            "name=\"cold-start-init p95 &lt;= +10%\">\n      <failure type=\"threshold\" \
+             message=\"cold-start-init p95 +20.0% (threshold +10%)\"/>"
+        ));
+        assert!(junit.contains("<skipped message=\"The baseline value is zero\"/>"));
+
+        assert_eq!(
+            github_annotation_lines(&checks),
+            vec![
+                "::error title=Benchmark regression::g/128mb/a&b: cold-start-init p95 +20.0%25 \
+                 (threshold +10%25)"
+            ]
+        );
+    }
+}
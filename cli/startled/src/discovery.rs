@@ -0,0 +1,177 @@
+//! Discovers the Lambda functions to benchmark from CloudFormation stacks and function tags.
+//!
+//! The `stack` command selects the `AWS::Lambda::Function` resources of one or more stacks
+//! (`--stack-name`), and/or the functions whose tags match every `--tag key=value` filter. A
+//! function found by several sources is benchmarked once, and `--select`/`--select-regex` then
+//! narrow down the discovered functions.
+
+use anyhow::{anyhow, Context, Result};
+use aws_sdk_cloudformation::Client as CloudFormationClient;
+use aws_sdk_lambda::Client as LambdaClient;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Number of Lambda functions whose tags are fetched concurrently during tag discovery
+const LIST_TAGS_CONCURRENCY: usize = 8;
+
+/// Returns the names of the functions of the stacks and of the functions matching the tags, in
+/// discovery order and without duplicates
+pub async fn discover_functions(
+    lambda_client: &LambdaClient,
+    cf_client: &CloudFormationClient,
+    stack_names: &[String],
+    tags: &[String],
+) -> Result<Vec<String>> {
+    let mut functions = Vec::new();
+    for stack_name in stack_names {
+        functions.extend(discover_stack_functions(cf_client, stack_name).await?);
+    }
+    if !tags.is_empty() {
+        let tag_filters = parse_tag_filters(tags)?;
+        functions.extend(discover_tagged_functions(lambda_client, &tag_filters).await?);
+    }
+
+    let mut seen = HashSet::new();
+    functions.retain(|function| seen.insert(function.clone()));
+    Ok(functions)
+}
+
+/// Returns the physical IDs of the `AWS::Lambda::Function` resources of a stack
+async fn discover_stack_functions(
+    cf_client: &CloudFormationClient,
+    stack_name: &str,
+) -> Result<Vec<String>> {
+    let mut functions = Vec::new();
+    let mut next_token: Option<String> = None;
+    loop {
+        let resp = cf_client
+            .list_stack_resources()
+            .stack_name(stack_name)
+            .set_next_token(next_token)
+            .send()
+            .await
+            .with_context(|| format!("Failed to list the resources of stack '{}'", stack_name))?;
+
+        functions.extend(
+            resp.stack_resource_summaries()
+                .iter()
+                .filter(|summary| summary.resource_type() == Some("AWS::Lambda::Function"))
+                .filter_map(|summary| summary.physical_resource_id())
+                .map(str::to_string),
+        );
+
+        next_token = resp.next_token().map(|s| s.to_string());
+        if next_token.is_none() {
+            break;
+        }
+    }
+    Ok(functions)
+}
+
+/// Returns the names of the functions whose tags match the filters
+async fn discover_tagged_functions(
+    lambda_client: &LambdaClient,
+    tag_filters: &BTreeMap<String, Vec<String>>,
+) -> Result<Vec<String>> {
+    let mut functions = Vec::new();
+    let mut marker: Option<String> = None;
+    loop {
+        let output = lambda_client
+            .list_functions()
+            .set_marker(marker)
+            .send()
+            .await
+            .context("Failed to list Lambda functions")?;
+
+        functions.extend(
+            output
+                .functions()
+                .iter()
+                .filter_map(|function| function.function_name().zip(function.function_arn()))
+                .map(|(name, arn)| (name.to_string(), arn.to_string())),
+        );
+
+        marker = output.next_marker().map(|s| s.to_string());
+        if marker.is_none() {
+            break;
+        }
+    }
+
+    // Fetch the tags of the functions concurrently, keeping their listing order
+    let matched: Vec<Option<String>> = stream::iter(functions)
+        .map(|(name, arn)| async move {
+            let output = lambda_client
+                .list_tags()
+                .resource(&arn)
+                .send()
+                .await
+                .with_context(|| format!("Failed to list tags of Lambda function '{}'", name))?;
+            let matches = output
+                .tags()
+                .is_some_and(|tags| tags_match(tag_filters, tags));
+            Ok::<_, anyhow::Error>(matches.then_some(name))
+        })
+        .buffered(LIST_TAGS_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    Ok(matched.into_iter().flatten().collect())
+}
+
+/// Parses `key=value` tag filters, grouping the accepted values of each key
+fn parse_tag_filters(tags: &[String]) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut filters: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for tag in tags {
+        let (key, value) = tag
+            .split_once('=')
+            .filter(|(key, _)| !key.is_empty())
+            .ok_or_else(|| anyhow!("Invalid tag '{}', expected key=value", tag))?;
+        filters
+            .entry(key.to_string())
+            .or_default()
+            .push(value.to_string());
+    }
+    Ok(filters)
+}
+
+/// Returns true if the tags have every key of the filters, with one of its accepted values
+fn tags_match(filters: &BTreeMap<String, Vec<String>>, tags: &HashMap<String, String>) -> bool {
+    filters
+        .iter()
+        .all(|(key, values)| tags.get(key).is_some_and(|value| values.contains(value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_filters() {
+        let filters = parse_tag_filters(&[
+            "team=payments".to_string(),
+            "env=dev".to_string(),
+            "env=staging".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(filters["env"], vec!["dev", "staging"]);
+
+        let tags = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        assert!(tags_match(
+            &filters,
+            &tags(&[("team", "payments"), ("env", "staging"), ("owner", "a")])
+        ));
+        assert!(!tags_match(&filters, &tags(&[("team", "payments")])));
+        assert!(!tags_match(
+            &filters,
+            &tags(&[("team", "payments"), ("env", "prod")])
+        ));
+
+        assert!(parse_tag_filters(&["team".to_string()]).is_err());
+        assert!(parse_tag_filters(&["=payments".to_string()]).is_err());
+    }
+}
@@ -1,27 +1,79 @@
 use crate::types::{
-    InvocationMetrics, PlatformReport, PlatformRuntimeDoneReport, ProxyRequest, ProxyResponse,
+    InvocationMetrics, PlatformExtension, PlatformReport, PlatformRuntimeDoneReport, ProxyRequest,
+    ProxyResponse,
 };
 use anyhow::{anyhow, Context, Result};
+use aws_sdk_lambda::config::http::HttpRequest;
+use aws_sdk_lambda::operation::invoke_with_response_stream::InvokeWithResponseStreamOutput;
 use aws_sdk_lambda::primitives::Blob;
+use aws_sdk_lambda::types::InvokeWithResponseStreamResponseEvent;
 use aws_sdk_lambda::{error::ProvideErrorMetadata, error::SdkError, Client as LambdaClient};
 use base64::Engine;
-use opentelemetry::trace::SpanKind;
+use opentelemetry::trace::{SpanKind, TraceContextExt};
 use opentelemetry_http::HeaderInjector;
 use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
-use std::time::Duration;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
 use tracing::Span;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 // Helper types needed for metrics extraction and config
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OriginalConfig {
     pub memory_size: i32,
     pub environment: Vec<(String, String)>,
+    /// Log format ("JSON" or "Text"), if reported by Lambda
+    #[serde(default)]
+    pub log_format: Option<String>,
+    /// Application log level (JSON log format only)
+    #[serde(default)]
+    pub application_log_level: Option<String>,
+    /// System log level (JSON log format only)
+    #[serde(default)]
+    pub system_log_level: Option<String>,
+    /// Layer ARNs attached to the function
+    #[serde(default)]
+    pub layers: Vec<String>,
 }
 
+/// A failed invocation, classified for the error accounting of the benchmark reports
+#[derive(Debug)]
+pub struct InvocationFailure {
+    /// The function error (`Unhandled`, `Handled`), the service error code, or `SdkError`
+    pub error_type: String,
+    pub throttled: bool,
+    /// The measurements of the invocation, when the function ran to the end
+    pub metrics: Option<Box<InvocationMetrics>>,
+    message: String,
+}
+
+impl std::fmt::Display for InvocationFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for InvocationFailure {}
+
+// Error code of the throttled invocations
+const THROTTLED_ERROR_CODE: &str = "TooManyRequestsException";
+
+/// The response of an invocation, returned at once or streamed
+struct InvokeResponse {
+    function_error: Option<String>,
+    payload: Option<Blob>,
+    log_result: Option<String>,
+    /// Time to the first payload chunk, and from it to the end of the stream, in milliseconds
+    stream_timings: Option<(f64, f64)>,
+}
+
+#[allow(clippy::too_many_arguments)]
+// Each invocation is the root of its own trace, linked to the span of the benchmark
 #[tracing::instrument(
+    parent = None,
+    follows_from = [Span::current().id()],
     skip_all,
     fields(
         otel.name = %format!("invoke {}", function_name),
@@ -36,25 +88,40 @@ pub async fn invoke_function(
     _environment: &[(String, String)],
     client_metrics_mode: bool,
     proxy_function: Option<&str>,
+    response_streaming: bool,
 ) -> Result<InvocationMetrics> {
     let span = Span::current();
 
     // Set initial span attributes
     span.set_attribute("function.name", function_name.to_string());
     span.set_attribute("function.memory_size", memory_size as i64);
+    span.set_attribute(
+        "benchmark.measurement",
+        if client_metrics_mode {
+            "client"
+        } else {
+            "server"
+        },
+    );
     if let Some(proxy) = proxy_function {
         span.set_attribute("function.proxy", proxy.to_string());
     }
     if let Some(payload) = payload {
         span.set_attribute("function.payload", payload.to_string());
     }
-    let mut req = client.invoke();
-
-    // Only request logs if not skipping
-    if !client_metrics_mode {
-        req = req.log_type(aws_sdk_lambda::types::LogType::Tail);
+    if response_streaming {
+        span.set_attribute("function.response_streaming", true);
     }
 
+    // The trace of the invocation, stored with its measurements
+    let trace_id = {
+        let cx = span.context();
+        let span_context = cx.span().span_context().clone();
+        span_context
+            .is_valid()
+            .then(|| span_context.trace_id().to_string())
+    };
+
     // Inject trace context into payload
     let mut final_payload = if let Some(p) = payload {
         serde_json::from_str(p)?
@@ -127,20 +194,44 @@ pub async fn invoke_function(
         (function_name, Blob::new(final_payload.to_string()))
     };
 
-    let req_builder = req.function_name(target_function).payload(invoke_payload);
-
-    let result = if let Some(header_value) = xray_header_value {
-        req_builder
+    // Only request logs if not skipping
+    let log_type = (!client_metrics_mode).then_some(aws_sdk_lambda::types::LogType::Tail);
+
+    let result = if response_streaming {
+        let req_builder = client
+            .invoke_with_response_stream()
+            .function_name(target_function)
+            .payload(invoke_payload)
+            .set_log_type(log_type);
+        let stream_start = Instant::now();
+        let output = req_builder
             .customize()
-            .mutate_request(move |http_req| {
-                http_req
-                    .headers_mut()
-                    .insert("X-Amzn-Trace-Id", header_value.clone());
-            })
+            .mutate_request(trace_header_mutator(xray_header_value))
             .send()
-            .await
+            .await;
+        match output {
+            Ok(output) => read_response_stream(output, stream_start, &span).await,
+            Err(err) => Err(sdk_failure(err, &span)),
+        }
     } else {
-        req_builder.send().await
+        let req_builder = client
+            .invoke()
+            .function_name(target_function)
+            .payload(invoke_payload)
+            .set_log_type(log_type);
+        let output = req_builder
+            .customize()
+            .mutate_request(trace_header_mutator(xray_header_value))
+            .send()
+            .await;
+        output
+            .map(|output| InvokeResponse {
+                function_error: output.function_error().map(str::to_string),
+                payload: output.payload().cloned(),
+                log_result: output.log_result().map(str::to_string),
+                stream_timings: None,
+            })
+            .map_err(|err| sdk_failure(err, &span))
     };
 
     match result {
@@ -149,18 +240,30 @@ pub async fn invoke_function(
                 .map(|s| s.elapsed().as_secs_f64() * 1000.0)
                 .unwrap_or(0.0);
             span.set_attribute("function.client.duration_ms", client_duration);
+            let (time_to_first_byte, stream_duration) = output.stream_timings.unzip();
+            if let Some(time_to_first_byte) = time_to_first_byte {
+                span.set_attribute("function.client.time_to_first_byte_ms", time_to_first_byte);
+            }
             if client_metrics_mode {
-                Ok(InvocationMetrics {
+                let function_error = output.function_error.as_deref();
+                let metrics = InvocationMetrics {
                     timestamp: chrono::Utc::now()
                         .format("%Y-%m-%dT%H:%M:%S%.3fZ")
                         .to_string(),
-                    client_duration: if proxy_function.is_some() {
+                    // The response of a failed proxy function holds no measurement
+                    client_duration: if proxy_function.is_some() && function_error.is_none() {
                         let proxy_response: ProxyResponse = serde_json::from_slice(
                             output
-                                .payload()
+                                .payload
+                                .as_ref()
                                 .ok_or_else(|| anyhow!("No response from proxy function"))?
                                 .as_ref(),
                         )?;
+                        // Measured by the proxy function, without the round trip to the client
+                        span.set_attribute(
+                            "function.proxy.duration_ms",
+                            proxy_response.invocation_time_ms,
+                        );
                         proxy_response.invocation_time_ms
                     } else {
                         client_duration
@@ -177,10 +280,28 @@ pub async fn invoke_function(
                     runtime_overhead_ms: None,
                     produced_bytes: None,
                     runtime_done_metrics_duration_ms: None,
-                })
+                    time_to_first_byte,
+                    stream_duration,
+                    extensions: Vec::new(),
+                    report_spans: BTreeMap::new(),
+                    trace_id,
+                };
+                if let Some(func_error) = function_error {
+                    span.set_attribute("error", true);
+                    span.set_attribute("error.type", func_error.to_string());
+                    return Err(InvocationFailure {
+                        error_type: func_error.to_string(),
+                        throttled: false,
+                        metrics: proxy_function.is_none().then(|| Box::new(metrics)),
+                        message: format!("Function invocation failed: {}", func_error),
+                    }
+                    .into());
+                }
+                Ok(metrics)
             } else {
                 let logs = output
-                    .log_result()
+                    .log_result
+                    .as_deref()
                     .ok_or_else(|| anyhow!("No logs returned"))?;
                 let decoded_logs = String::from_utf8(
                     base64::engine::general_purpose::STANDARD
@@ -188,16 +309,37 @@ pub async fn invoke_function(
                         .expect("Failed to decode base64 payload"),
                 )
                 .expect("Failed to decode logs");
-                if let Some(func_error) = output.function_error() {
+                if let Some(func_error) = output.function_error.as_deref() {
                     span.set_attribute("error", true);
                     span.set_attribute("error.type", func_error.to_string());
-                    return Err(anyhow!(
-                        "Function invocation failed: {}.\nLogs:\n{}",
-                        func_error,
-                        decoded_logs
-                    ));
+                    // The function ran to the end, so its report is still in the logs
+                    let metrics = extract_metrics(&decoded_logs).ok().map(|mut metrics| {
+                        metrics.client_duration = client_duration;
+                        metrics.time_to_first_byte = time_to_first_byte;
+                        metrics.stream_duration = stream_duration;
+                        metrics.trace_id = trace_id;
+                        Box::new(metrics)
+                    });
+                    return Err(InvocationFailure {
+                        error_type: func_error.to_string(),
+                        throttled: false,
+                        metrics,
+                        message: format!(
+                            "Function invocation failed: {}.\nLogs:\n{}",
+                            func_error, decoded_logs
+                        ),
+                    }
+                    .into());
                 }
                 let mut metrics = extract_metrics(&decoded_logs)?;
+                span.set_attribute(
+                    "function.start_type",
+                    if metrics.init_duration.is_some() {
+                        "cold"
+                    } else {
+                        "warm"
+                    },
+                );
                 span.set_attribute("function.duration_ms", metrics.duration);
                 span.set_attribute("function.billed_duration_ms", metrics.billed_duration);
                 span.set_attribute("function.extension_overhead_ms", metrics.extension_overhead);
@@ -208,38 +350,132 @@ pub async fn invoke_function(
                     span.set_attribute("function.total_cold_start_duration_ms", total);
                 }
                 metrics.client_duration = client_duration;
+                metrics.time_to_first_byte = time_to_first_byte;
+                metrics.stream_duration = stream_duration;
+                metrics.trace_id = trace_id;
                 Ok(metrics)
             }
         }
-        Err(err) => {
-            span.set_attribute("error", true);
-            let error_details = match err {
-                aws_sdk_lambda::error::SdkError::ServiceError(context) => {
-                    let msg = format!(
-                        "Service error: {} ({})",
-                        context.err().message().unwrap_or_default(),
-                        context.err().code().unwrap_or_default()
-                    );
-                    span.set_attribute("error.type", "service_error");
-                    span.set_attribute("error.message", msg.clone());
-                    msg
-                }
-                other_err => {
-                    let msg = format!("SDK error: {}", other_err);
-                    span.set_attribute("error.type", "sdk_error");
-                    span.set_attribute("error.message", msg.clone());
-                    msg
-                }
-            };
-            Err(anyhow!("Failed to invoke function: {}", error_details))
+        Err(failure) => Err(failure.into()),
+    }
+}
+
+/// Reads a response stream to its end, timing the first payload chunk and the rest of the stream
+async fn read_response_stream(
+    mut output: InvokeWithResponseStreamOutput,
+    start: Instant,
+    span: &Span,
+) -> Result<InvokeResponse, InvocationFailure> {
+    let mut first_byte = None;
+    let mut complete = None;
+    while let Some(event) = output
+        .event_stream
+        .recv()
+        .await
+        .map_err(|err| sdk_failure(err, span))?
+    {
+        match event {
+            InvokeWithResponseStreamResponseEvent::PayloadChunk(_) => {
+                first_byte.get_or_insert_with(|| start.elapsed());
+            }
+            InvokeWithResponseStreamResponseEvent::InvokeComplete(event) => {
+                complete = Some(event);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let end = start.elapsed();
+
+    let Some(complete) = complete else {
+        span.set_attribute("error", true);
+        span.set_attribute("error.type", "incomplete_stream");
+        return Err(InvocationFailure {
+            error_type: "IncompleteStream".to_string(),
+            throttled: false,
+            metrics: None,
+            message: "Response stream ended before the invocation completed".to_string(),
+        });
+    };
+    // An empty response completes without payload chunk
+    let first_byte = first_byte.unwrap_or(end);
+    Ok(InvokeResponse {
+        function_error: complete.error_code().map(str::to_string),
+        payload: None,
+        log_result: complete.log_result().map(str::to_string),
+        stream_timings: Some((
+            first_byte.as_secs_f64() * 1000.0,
+            (end - first_byte).as_secs_f64() * 1000.0,
+        )),
+    })
+}
+
+/// Returns a request mutator adding the X-Ray trace header of the invocation, if any, to the
+/// invoke request
+fn trace_header_mutator(
+    xray_header_value: Option<String>,
+) -> impl Fn(&mut HttpRequest) + Send + Sync + 'static {
+    move |http_req| {
+        if let Some(header_value) = &xray_header_value {
+            http_req
+                .headers_mut()
+                .insert("X-Amzn-Trace-Id", header_value.clone());
         }
     }
 }
 
+/// Classifies a failed request by its service error code, or as an SDK error
+fn sdk_failure<E, R>(err: SdkError<E, R>, span: &Span) -> InvocationFailure
+where
+    E: ProvideErrorMetadata,
+{
+    span.set_attribute("error", true);
+    let (error_type, error_details) = match err {
+        SdkError::ServiceError(context) => {
+            let code = context.err().code().unwrap_or("ServiceError").to_string();
+            let msg = format!(
+                "Service error: {} ({})",
+                context.err().message().unwrap_or_default(),
+                code
+            );
+            span.set_attribute("error.type", "service_error");
+            span.set_attribute("error.message", msg.clone());
+            (code, msg)
+        }
+        other_err => {
+            let msg = format!("SDK error: {}", other_err);
+            span.set_attribute("error.type", "sdk_error");
+            span.set_attribute("error.message", msg.clone());
+            ("SdkError".to_string(), msg)
+        }
+    };
+    InvocationFailure {
+        throttled: error_type == THROTTLED_ERROR_CODE,
+        error_type,
+        metrics: None,
+        message: format!("Failed to invoke function: {}", error_details),
+    }
+}
+
 pub fn extract_metrics(logs: &str) -> Result<InvocationMetrics> {
     let mut platform_report_data: Option<PlatformReport> = None;
     let mut runtime_done_report_data: Option<PlatformRuntimeDoneReport> = None;
 
+    // Extensions registered for the INVOKE events, logged during the initialization
+    let extensions: Vec<String> = logs
+        .lines()
+        .filter_map(|line| serde_json::from_str::<PlatformExtension>(line).ok())
+        .filter(|extension| {
+            extension.event_type == "platform.extension"
+                && extension
+                    .record
+                    .events
+                    .iter()
+                    .any(|event| event == "INVOKE")
+        })
+        .map(|extension| extension.record.name)
+        .collect();
+
     // Iterate lines in reverse to find the last occurrence of each report type
     for line in logs.lines().rev() {
         // Try to parse as PlatformReport
@@ -276,6 +512,13 @@ pub fn extract_metrics(logs: &str) -> Result<InvocationMetrics> {
         .iter()
         .find(|span| span.name == "extensionOverhead")
         .map_or(0.0, |span| span.duration_ms);
+    let report_spans = report
+        .record
+        .spans
+        .iter()
+        .filter(|span| span.name != "extensionOverhead")
+        .map(|span| (span.name.clone(), span.duration_ms))
+        .collect();
     let duration = report.record.metrics.duration_ms;
     let init_duration = report.record.metrics.init_duration_ms;
     let total_cold_start_duration = init_duration.map(|init| init + duration);
@@ -318,6 +561,11 @@ pub fn extract_metrics(logs: &str) -> Result<InvocationMetrics> {
         runtime_overhead_ms, // Assign from simplified local variable
         produced_bytes,
         runtime_done_metrics_duration_ms,
+        time_to_first_byte: None,
+        stream_duration: None,
+        extensions,
+        report_spans,
+        trace_id: None,
     })
 }
 
@@ -346,6 +594,7 @@ pub async fn get_function_config(
             function_name
         )
     })?;
+    let logging = config.logging_config();
     Ok(OriginalConfig {
         memory_size: config.memory_size().unwrap_or(128),
         environment: config
@@ -353,6 +602,20 @@ pub async fn get_function_config(
             .and_then(|e| e.variables())
             .map(|vars| vars.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
             .unwrap_or_default(),
+        log_format: logging
+            .and_then(|l| l.log_format())
+            .map(|f| f.as_str().to_string()),
+        application_log_level: logging
+            .and_then(|l| l.application_log_level())
+            .map(|l| l.as_str().to_string()),
+        system_log_level: logging
+            .and_then(|l| l.system_log_level())
+            .map(|l| l.as_str().to_string()),
+        layers: config
+            .layers()
+            .iter()
+            .filter_map(|layer| layer.arn().map(|arn| arn.to_string()))
+            .collect(),
     })
 }
 
@@ -415,6 +678,7 @@ pub async fn update_function_config(
 
     match update.send().await {
         Ok(_) => {
+            wait_for_function_updated(client, function_name).await?;
             tokio::time::sleep(Duration::from_secs(5)).await;
             Ok(())
         }
@@ -435,24 +699,112 @@ pub async fn update_function_config(
     }
 }
 
+/// Waits until a configuration update of the function is applied, so the next update or
+/// invocation does not race with it (e.g. between the memory sizes of a sweep)
+pub async fn wait_for_function_updated(client: &LambdaClient, function_name: &str) -> Result<()> {
+    use aws_sdk_lambda::types::LastUpdateStatus;
+
+    const MAX_ATTEMPTS: u32 = 60;
+    for _ in 0..MAX_ATTEMPTS {
+        let config = client
+            .get_function_configuration()
+            .function_name(function_name)
+            .send()
+            .await
+            .context("Failed to get function configuration update status")?;
+        match config.last_update_status() {
+            Some(LastUpdateStatus::InProgress) => {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            Some(LastUpdateStatus::Failed) => {
+                return Err(anyhow!(
+                    "Function configuration update failed: {}",
+                    config
+                        .last_update_status_reason()
+                        .unwrap_or("unknown reason")
+                ));
+            }
+            _ => return Ok(()),
+        }
+    }
+    Err(anyhow!(
+        "Timed out waiting for the configuration update of function '{}'",
+        function_name
+    ))
+}
+
+/// Restores the exact configuration captured by [`get_function_config`].
+///
+/// Unlike [`update_function_config`], which merges environment variables and switches the
+/// function to JSON logging, this replaces the environment wholesale (removing variables added
+/// by the benchmark) and restores the original logging configuration and layers.
 pub async fn restore_function_config(
     client: &LambdaClient,
     function_name: &str,
     original_config: &OriginalConfig,
     quiet_mode: bool,
 ) -> Result<()> {
+    use aws_sdk_lambda::types::{
+        ApplicationLogLevel, Environment, LogFormat, LoggingConfig, SystemLogLevel,
+    };
+
     if !quiet_mode {
         println!("\nRestoring function configuration...");
     }
 
-    update_function_config(
-        client,
-        function_name,
-        Some(original_config.memory_size),
-        &original_config.environment, // Pass directly as &Vec<(String, String)> coerces to &[(String, String)]
-    )
-    .await
-    .context("Failed to restore function configuration")?;
+    let current = get_function_config(client, function_name)
+        .await
+        .context("Failed to restore function configuration")?;
+
+    let mut update = client
+        .update_function_configuration()
+        .function_name(function_name)
+        .memory_size(original_config.memory_size)
+        .environment(
+            Environment::builder()
+                .set_variables(Some(original_config.environment.iter().cloned().collect()))
+                .build(),
+        );
+
+    if let Some(log_format) = &original_config.log_format {
+        let mut logging = LoggingConfig::builder().log_format(LogFormat::from(log_format.as_str()));
+        // Log levels can only be set with the JSON log format
+        if log_format.eq_ignore_ascii_case("json") {
+            if let Some(level) = &original_config.application_log_level {
+                logging = logging.application_log_level(ApplicationLogLevel::from(level.as_str()));
+            }
+            if let Some(level) = &original_config.system_log_level {
+                logging = logging.system_log_level(SystemLogLevel::from(level.as_str()));
+            }
+        }
+        update = update.logging_config(logging.build());
+    }
+
+    // Only touch layers when they changed, as container image functions reject any layer update
+    if current.layers != original_config.layers {
+        update = update.set_layers(Some(original_config.layers.clone()));
+    }
+
+    match update.send().await {
+        Ok(_) => {
+            wait_for_function_updated(client, function_name).await?;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+        Err(err) => {
+            let error_details = match err {
+                aws_sdk_lambda::error::SdkError::ServiceError(context) => format!(
+                    "Service error: {} ({})",
+                    context.err().message().unwrap_or_default(),
+                    context.err().code().unwrap_or_default()
+                ),
+                other_err => format!("SDK error: {}", other_err),
+            };
+            return Err(anyhow!(
+                "Failed to restore function configuration: {}",
+                error_details
+            ));
+        }
+    }
 
     if !quiet_mode {
         println!("Function {} configuration restored.", function_name);
@@ -488,3 +840,53 @@ pub async fn check_function_exists(client: &LambdaClient, function_name: &str) -
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_extension_overheads() {
+        let logs = [
+            r#"{"time":"2025-01-01T00:00:00.000Z","type":"platform.extension","record":{"name":"otel","state":"Ready","events":["INVOKE","SHUTDOWN"]}}"#,
+            r#"{"time":"2025-01-01T00:00:00.000Z","type":"platform.extension","record":{"name":"logs","state":"Ready","events":["SHUTDOWN"]}}"#,
+            r#"{"time":"2025-01-01T00:00:01.000Z","type":"platform.report","record":{"metrics":{"durationMs":10.0,"billedDurationMs":11,"memorySizeMB":128,"maxMemoryUsedMB":64,"initDurationMs":100.0},"spans":[{"name":"extensionOverhead","durationMs":5.0}]}}"#,
+        ]
+        .join("\n");
+        let metrics = extract_metrics(&logs).unwrap();
+        // Only the extensions subscribed to the invocations add overhead
+        assert_eq!(metrics.extensions, vec!["otel"]);
+        assert_eq!(
+            metrics.extension_overheads(&metrics.extensions),
+            BTreeMap::from([("otel".to_string(), 5.0)])
+        );
+
+        let extensions = vec!["otel".to_string(), "secrets".to_string()];
+        let mut metrics = metrics;
+        metrics.report_spans.insert("secrets".to_string(), 2.0);
+        assert_eq!(
+            metrics.extension_overheads(&extensions),
+            BTreeMap::from([
+                ("secrets".to_string(), 2.0),
+                ("unattributed".to_string(), 3.0)
+            ])
+        );
+        assert!(metrics.extension_overheads(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_trace_header_mutator() {
+        let mut http_req = HttpRequest::empty();
+        trace_header_mutator(Some("Root=1-5759e988-bd862e3fe1be46a994272793".to_string()))(
+            &mut http_req,
+        );
+        assert_eq!(
+            http_req.headers().get("X-Amzn-Trace-Id"),
+            Some("Root=1-5759e988-bd862e3fe1be46a994272793")
+        );
+
+        let mut http_req = HttpRequest::empty();
+        trace_header_mutator(None)(&mut http_req);
+        assert_eq!(http_req.headers().get("X-Amzn-Trace-Id"), None);
+    }
+}
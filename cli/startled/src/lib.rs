@@ -3,9 +3,17 @@
 //! of AWS Lambda functions. It provides insights into performance, cold starts, and invocation durations.
 
 pub mod benchmark;
+pub mod cloudwatch;
 pub mod console;
+pub mod diff;
+pub mod discovery;
 pub mod lambda;
+pub mod limits;
+pub mod metrics;
+pub mod plan;
 pub mod report;
+pub mod restore;
+pub mod schema;
 pub mod screenshot;
 pub mod stats;
 pub mod telemetry;
@@ -0,0 +1,136 @@
+//! Limits of the invocations of the functions benchmarked by a stack run.
+//!
+//! With `--parallel`, all the selected functions are benchmarked at the same time, which can
+//! exceed the concurrency of the account and get invocations throttled:
+//! - `--max-concurrency <N>` caps the invocations in flight across all the functions;
+//! - `--rate-limit <N>` caps the invocations per second of each function.
+//!
+//! Both limits apply to whole rounds of `--concurrent` invocations (`--concurrent-environments`
+//! when set), so the invocations of a round still start together, and the cold starts of the
+//! first round land on distinct environments.
+//! With `--duration`, the warm starts of the sustained load are limited one invocation at a time.
+
+use anyhow::{bail, Result};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// The invocation limits of a run, shared by the functions benchmarked in parallel
+#[derive(Debug, Clone, Default)]
+pub struct InvocationLimits {
+    max_concurrency: Option<Arc<Semaphore>>,
+    rate_limit: Option<f64>,
+}
+
+impl InvocationLimits {
+    /// Validates the limits against the invocations of a round
+    pub fn new(
+        max_concurrency: Option<u32>,
+        rate_limit: Option<f64>,
+        concurrent: u32,
+    ) -> Result<Self> {
+        if let Some(max_concurrency) = max_concurrency {
+            if max_concurrency < concurrent {
+                bail!(
+                    "--max-concurrency ({}) must be at least --concurrent ({})",
+                    max_concurrency,
+                    concurrent
+                );
+            }
+        }
+        if let Some(rate_limit) = rate_limit {
+            if !(rate_limit > 0.0 && rate_limit.is_finite()) {
+                bail!("--rate-limit must be a positive number of invocations per second");
+            }
+        }
+        Ok(Self {
+            max_concurrency: max_concurrency
+                .map(|permits| Arc::new(Semaphore::new(permits as usize))),
+            rate_limit,
+        })
+    }
+
+    /// Returns the invocations per second of each function, if limited
+    pub fn rate_limit(&self) -> Option<f64> {
+        self.rate_limit
+    }
+
+    /// Returns the rate limiter of the rounds of a function
+    pub fn rate_limiter(&self) -> RateLimiter {
+        RateLimiter {
+            rate_limit: self.rate_limit,
+            next_start: None,
+        }
+    }
+
+    /// Waits until a round of invocations can start, returning the permit held until it ends
+    pub async fn start_round(
+        &self,
+        invocations: u32,
+        rate_limiter: &mut RateLimiter,
+    ) -> Option<OwnedSemaphorePermit> {
+        let start = rate_limiter.schedule(invocations, Instant::now());
+        tokio::time::sleep_until(start).await;
+        match &self.max_concurrency {
+            // The semaphore is never closed
+            Some(semaphore) => Arc::clone(semaphore)
+                .acquire_many_owned(invocations)
+                .await
+                .ok(),
+            None => None,
+        }
+    }
+}
+
+/// Spaces the rounds of invocations of a function to stay under the rate limit
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate_limit: Option<f64>,
+    next_start: Option<Instant>,
+}
+
+impl RateLimiter {
+    /// Returns when a round of invocations can start, and books the time it takes from the rate
+    fn schedule(&mut self, invocations: u32, now: Instant) -> Instant {
+        let Some(rate_limit) = self.rate_limit else {
+            return now;
+        };
+        let start = self
+            .next_start
+            .map_or(now, |next_start| next_start.max(now));
+        self.next_start = Some(start + Duration::from_secs_f64(invocations as f64 / rate_limit));
+        start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invocation_limits() {
+        assert!(InvocationLimits::new(Some(10), Some(5.0), 10).is_ok());
+        assert!(InvocationLimits::new(Some(5), None, 10).is_err());
+        assert!(InvocationLimits::new(None, Some(0.0), 1).is_err());
+
+        // Rounds of 2 invocations at 4 per second start every 500ms
+        let limits = InvocationLimits::new(None, Some(4.0), 2).unwrap();
+        let mut rate_limiter = limits.rate_limiter();
+        let now = Instant::now();
+        assert_eq!(rate_limiter.schedule(2, now), now);
+        assert_eq!(
+            rate_limiter.schedule(2, now + Duration::from_millis(100)),
+            now + Duration::from_millis(500)
+        );
+        // A round started late does not catch up with the rounds missed
+        assert_eq!(
+            rate_limiter.schedule(2, now + Duration::from_secs(3)),
+            now + Duration::from_secs(3)
+        );
+
+        let mut unlimited = InvocationLimits::default().rate_limiter();
+        assert_eq!(unlimited.schedule(100, now), now);
+        assert_eq!(unlimited.schedule(100, now), now);
+    }
+}
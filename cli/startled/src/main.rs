@@ -1,18 +1,32 @@
 use startled::{
-    benchmark::{run_function_benchmark, run_stack_benchmark},
+    benchmark::{
+        is_interrupted, run_function_benchmark, run_stack_benchmark, select_stack_functions,
+    },
+    cloudwatch::{CloudWatchPublisher, DEFAULT_LOG_GROUP},
+    diff::{run_diff, save_baseline, SignificanceOptions, SignificanceTest, Threshold},
+    limits::InvocationLimits,
+    plan::{run_dry_run, ExecutionPlan},
     report::generate_reports,
+    restore::{restore_from_state, DEFAULT_STATE_FILE},
+    schema::{validate_directory, REPORT_SCHEMA},
+    stats::StatsOptions,
     telemetry::{init_telemetry, init_tracing},
-    types::{EnvVar, StackBenchmarkConfig},
-    utils::validate_fs_safe_name,
+    types::{EnvVar, EnvVariant, StackBenchmarkConfig, SustainedLoad, WarmPacing},
+    utils::{parse_duration_secs, validate_fs_safe_name},
 };
 
 use anyhow::{anyhow, Context, Result};
 use aws_sdk_cloudformation::Client as CloudFormationClient;
+use aws_sdk_cloudwatchlogs::Client as CloudWatchLogsClient;
 use aws_sdk_lambda::Client as LambdaClient;
-use clap::{crate_authors, crate_description, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap::{
+    crate_authors, crate_description, ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum,
+};
 use clap_complete::generate;
 use clap_complete::Shell as ClapShell; // Alias to avoid conflict with local Theme if any, or just for clarity
+use colored::*;
 use std::fs;
+use std::path::PathBuf;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
 pub enum Theme {
@@ -31,10 +45,63 @@ EXAMPLES:
     # Benchmark a function with a specific memory size and payload from a file
     startled function my-lambda-function --memory 512 --payload-file ./payload.json
 
+    # Compare the arm64 and x86_64 builds of a function
+    startled function my-function-arm64 --pair my-function-x86_64 --memory 512 -d ./benchmark_results
+
+    # Benchmark the functions of a stack at several memory sizes in a single run
+    startled stack my-app-stack -s \"service-a\" --memory 128,256,512,1024 --output-dir ./benchmark_results
+
     # Generate HTML reports from benchmark results in a directory
     startled report -d ./benchmark_results -o ./reports --screenshot light \\
         --title \"Performance Analysis\" --description \"Comparison of OTEL configurations\"
 
+    # Estimate costs in the reports with a negotiated price per GB-second
+    startled report -d ./benchmark_results -o ./reports --price-per-gb-second 0.0000150000
+
+    # Benchmark the functions of a stack in parallel without exceeding 50 invocations in flight
+    startled stack my-app-stack -s \"service-a\" --memory 512 -c 10 --parallel --max-concurrency 50 --rate-limit 20
+
+    # Sustain 5 requests per second for 10 minutes to observe drift and environment recycling
+    startled function my-lambda-function --memory 512 --duration 10m --rps 5 -d ./benchmark_results
+
+    # Hold 20 environments warm, measuring 5 invocations of a round every 30 seconds
+    startled function my-lambda-function --memory 512 -c 5 -n 20 --concurrent-environments 20 --warm-interval 30s
+
+    # Benchmark a response streaming function, measuring the time to first byte
+    startled function my-streaming-function --memory 512 -n 50 --stream -d ./benchmark_results
+
+    # Compare the function with the exporter disabled and with gzip compression
+    startled function my-lambda-function --memory 512 -n 50 --variant baseline --variant off:OTEL_SDK_DISABLED=true --variant gzip:OTEL_EXPORTER_OTLP_COMPRESSION=gzip -d ./benchmark_results
+
+    # Print the plan and estimated cost of a memory sweep of a stack, without invoking anything
+    startled stack my-app-stack -s \"api\" --memory 128,512,1024 -c 10 -n 100 --dry-run
+
+    # Benchmark the functions of several stacks, and the functions tagged team=payments
+    startled stack --stack-name my-api-stack --stack-name my-auth-stack --tag team=payments \
+        --select-name payments --memory 512 --output-dir ./benchmark_results
+
+    # Publish the P50/P95/P99 of each run as CloudWatch custom metrics for dashboards and alarms
+    startled stack my-app-stack -s \"service-a\" --memory 512 --publish-cloudwatch-namespace Startled
+
+    # Show more percentiles and a mean without the 5% most extreme values on each end
+    startled report -d ./benchmark_results -o ./reports --percentiles 50,90,99,99.9 --trim 5 --stddev
+
+    # Compare a candidate run against a baseline, failing if the P95 cold start grows over 10%
+    startled diff ./results/main ./results/pr-123 --threshold cold-start-total-duration:p95:10%
+
+    # Pin a run as the \"main\" baseline, then fail only on significant regressions against it
+    startled baseline ./results/main --name main
+    startled diff main ./results/pr-123 -t \"*:mean:5%\" --significance-test welch --require-significance
+
+    # Report the thresholds to CI as JUnit test cases and GitHub Actions annotations
+    startled diff main ./results/pr-123 -t \"*:p95:10%\" --junit diff-junit.xml --github-annotations
+
+    # Check the result files against the JSON schema of the results
+    startled validate ./benchmark_results
+
+    # Restore functions left modified by an interrupted benchmark
+    startled restore --from-state .startled-restore.json
+
     # Generate shell completions for bash
     startled generate-completions bash";
 
@@ -52,9 +119,13 @@ enum Commands {
         /// Lambda function ARN or name
         function_name: String,
 
-        /// Memory size in MB
-        #[arg(short, long)]
-        memory: i32,
+        /// Same function packaged for the other architecture, benchmarked with the same settings for an architecture comparison
+        #[arg(long = "pair", value_name = "FUNCTION")]
+        pair: Option<String>,
+
+        /// Memory size in MB, or a comma-separated list of sizes to benchmark in sequence (e.g. 128,256,512)
+        #[arg(short, long, value_delimiter = ',', required = true)]
+        memory: Vec<i32>,
 
         /// Number of concurrent invocations
         #[arg(short = 'c', long, default_value_t = 1)]
@@ -64,6 +135,26 @@ enum Commands {
         #[arg(short = 'n', long, default_value_t = 1)]
         number: u32,
 
+        /// Sustain the warm starts at --rps for this duration (e.g. 10m), instead of --number rounds
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration_secs, conflicts_with = "number", requires = "rps")]
+        duration: Option<u64>,
+
+        /// Target requests per second of the warm starts sustained for --duration
+        #[arg(long, value_name = "PER_SECOND", requires = "duration")]
+        rps: Option<f64>,
+
+        /// Start the warm start rounds at this interval (e.g. 30s), instead of back to back
+        #[arg(long = "warm-interval", value_name = "DURATION", value_parser = parse_duration_secs, conflicts_with = "duration")]
+        warm_interval: Option<u64>,
+
+        /// Execution environments to hold warm: every round invokes all of them, measuring --concurrent
+        #[arg(
+            long = "concurrent-environments",
+            value_name = "N",
+            conflicts_with = "duration"
+        )]
+        concurrent_environments: Option<u32>,
+
         /// Directory to save the benchmark results (optional)
         #[arg(short = 'd', long = "dir")]
         output_dir: Option<String>,
@@ -80,19 +171,62 @@ enum Commands {
         #[arg(short = 'e', long = "env", value_parser = clap::value_parser!(EnvVar))]
         environment: Vec<EnvVar>,
 
+        /// Environment variant to benchmark, as NAME or NAME:KEY=VALUE,KEY=VALUE, on top of the
+        /// --env variables (can be specified multiple times to compare the variants)
+        #[arg(long = "variant", value_name = "NAME[:KEY=VALUE,...]", value_parser = clap::value_parser!(EnvVariant))]
+        variants: Vec<EnvVariant>,
+
         /// Proxy Lambda function to use for client-side measurements
         #[arg(long = "proxy")]
         proxy: Option<String>,
+
+        /// Exclude the failed invocations from the latency samples; they are still counted as errors
+        #[arg(long = "exclude-failed", default_value_t = false)]
+        exclude_failed: bool,
+
+        /// Invoke with response streaming, measuring the time to first byte and the stream duration
+        #[arg(long = "stream", default_value_t = false, conflicts_with = "proxy")]
+        stream: bool,
+
+        /// Print the execution plan and check the functions can be invoked, without invoking them
+        #[arg(long = "dry-run", default_value_t = false)]
+        dry_run: bool,
+
+        /// File recording original function configurations until they are restored
+        #[arg(long = "state-file", env = "STARTLED_STATE_FILE", default_value = DEFAULT_STATE_FILE)]
+        state_file: PathBuf,
+
+        /// CloudWatch namespace to publish the P50, P95 and P99 of the cold and warm start metrics of each run to
+        #[arg(long = "publish-cloudwatch-namespace", value_name = "NAMESPACE")]
+        publish_cloudwatch_namespace: Option<String>,
+
+        /// Log group of the Embedded Metric Format events of the published metrics
+        #[arg(long = "publish-cloudwatch-log-group", value_name = "LOG_GROUP", default_value = DEFAULT_LOG_GROUP, requires = "publish_cloudwatch_namespace")]
+        publish_cloudwatch_log_group: String,
     },
 
-    /// Test all functions in a CloudFormation stack
+    /// Test all functions in CloudFormation stacks, or the functions matching tags
+    #[command(group(
+        ArgGroup::new("discovery")
+            .required(true)
+            .multiple(true)
+            .args(["stack_name", "stack_names", "tags"])
+    ))]
     Stack {
         /// CloudFormation stack name
-        stack_name: String,
+        stack_name: Option<String>,
 
-        /// Pattern for substring matching against function names/ARNs. Used for directory naming if --select-name is not provided.
+        /// More CloudFormation stacks to discover the functions of (can be specified multiple times)
+        #[arg(long = "stack-name", value_name = "STACK")]
+        stack_names: Vec<String>,
+
+        /// Lambda function tag to discover the functions by, as key=value (e.g. team=payments). Can be specified multiple times; functions must match every key.
+        #[arg(long = "tag", value_name = "KEY=VALUE")]
+        tags: Vec<String>,
+
+        /// Optional: Pattern for substring matching against the discovered function names. Used for directory naming if --select-name is not provided.
         #[arg(short = 's', long)]
-        select: String,
+        select: Option<String>,
 
         /// Optional: Regular expression for filtering functions. Overrides --select for filtering if provided.
         #[arg(long = "select-regex")]
@@ -102,9 +236,9 @@ enum Commands {
         #[arg(long = "select-name")]
         select_name: Option<String>,
 
-        /// Memory size in MB
-        #[arg(short = 'm', long)]
-        memory: i32,
+        /// Memory size in MB, or a comma-separated list of sizes to benchmark in sequence (e.g. 128,256,512)
+        #[arg(short = 'm', long, value_delimiter = ',', required = true)]
+        memory: Vec<i32>,
 
         /// Number of concurrent invocations
         #[arg(short = 'c', long, default_value_t = 1)]
@@ -114,6 +248,26 @@ enum Commands {
         #[arg(short = 'n', long, default_value_t = 1)]
         number: u32,
 
+        /// Sustain the warm starts at --rps for this duration (e.g. 10m), instead of --number rounds
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration_secs, conflicts_with = "number", requires = "rps")]
+        duration: Option<u64>,
+
+        /// Target requests per second of the warm starts sustained for --duration
+        #[arg(long, value_name = "PER_SECOND", requires = "duration")]
+        rps: Option<f64>,
+
+        /// Start the warm start rounds at this interval (e.g. 30s), instead of back to back
+        #[arg(long = "warm-interval", value_name = "DURATION", value_parser = parse_duration_secs, conflicts_with = "duration")]
+        warm_interval: Option<u64>,
+
+        /// Execution environments to hold warm: every round invokes all of them, measuring --concurrent
+        #[arg(
+            long = "concurrent-environments",
+            value_name = "N",
+            conflicts_with = "duration"
+        )]
+        concurrent_environments: Option<u32>,
+
         /// Directory to save the benchmark results (optional)
         #[arg(short = 'd', long = "dir")]
         output_dir: Option<String>,
@@ -134,9 +288,41 @@ enum Commands {
         #[arg(long = "proxy")]
         proxy: Option<String>,
 
+        /// Exclude the failed invocations from the latency samples; they are still counted as errors
+        #[arg(long = "exclude-failed", default_value_t = false)]
+        exclude_failed: bool,
+
+        /// Invoke with response streaming, measuring the time to first byte and the stream duration
+        #[arg(long = "stream", default_value_t = false, conflicts_with = "proxy")]
+        stream: bool,
+
+        /// Print the execution plan and check the functions can be invoked, without invoking them
+        #[arg(long = "dry-run", default_value_t = false)]
+        dry_run: bool,
+
         /// Run benchmarks for selected functions in parallel
         #[arg(long, default_value_t = false)]
         parallel: bool,
+
+        /// Maximum number of invocations in flight across all the functions, to avoid account throttling
+        #[arg(long = "max-concurrency", value_name = "N")]
+        max_concurrency: Option<u32>,
+
+        /// Maximum number of invocations per second of each function
+        #[arg(long = "rate-limit", value_name = "PER_SECOND")]
+        rate_limit: Option<f64>,
+
+        /// File recording original function configurations until they are restored
+        #[arg(long = "state-file", env = "STARTLED_STATE_FILE", default_value = DEFAULT_STATE_FILE)]
+        state_file: PathBuf,
+
+        /// CloudWatch namespace to publish the P50, P95 and P99 of the cold and warm start metrics of each run to
+        #[arg(long = "publish-cloudwatch-namespace", value_name = "NAMESPACE")]
+        publish_cloudwatch_namespace: Option<String>,
+
+        /// Log group of the Embedded Metric Format events of the published metrics
+        #[arg(long = "publish-cloudwatch-log-group", value_name = "LOG_GROUP", default_value = DEFAULT_LOG_GROUP, requires = "publish_cloudwatch_namespace")]
+        publish_cloudwatch_log_group: String,
     },
 
     /// Generate visualization reports from benchmark results
@@ -180,7 +366,97 @@ enum Commands {
         /// Append 'index.html' to internal links for local file system browsing
         #[arg(long, default_value_t = false)]
         local_browsing: bool,
+
+        /// Price per GB-second in USD for the cost estimates (default: AWS Lambda on-demand price of the function architecture)
+        #[arg(long = "price-per-gb-second", value_name = "USD")]
+        price_per_gb_second: Option<f64>,
+
+        /// Comma-separated percentiles shown in the charts after the mean (e.g. 50,90,99,99.9)
+        #[arg(long, value_delimiter = ',', default_value = "50,95,99")]
+        percentiles: Vec<f64>,
+
+        /// Percentage of the lowest and of the highest values left out of the mean and standard deviation
+        #[arg(long = "trim", value_name = "PERCENT", default_value_t = 0.0)]
+        trim_percent: f64,
+
+        /// Show the standard deviation in the charts
+        #[arg(long = "stddev", default_value_t = false)]
+        show_std_dev: bool,
+
+        /// URL of the trace of a sample in the tracing backend, opened by clicking it in the time
+        /// series, with {trace_id} (W3C) or {xray_trace_id} in place of its trace ID
+        #[arg(long = "trace-url", value_name = "TEMPLATE")]
+        trace_url: Option<String>,
+    },
+    /// Pin the results of a benchmark run as a named baseline
+    Baseline {
+        /// Directory containing the benchmark results
+        results_dir: PathBuf,
+
+        /// Name of the baseline, used to compare against it with the diff command
+        #[arg(short = 'n', long = "name")]
+        name: String,
+
+        /// Write the baseline to this file instead of .startled-baselines/<NAME>.json
+        #[arg(short = 'o', long = "output", value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Compare two benchmark runs and fail on regressions
+    Diff {
+        /// Baseline results: a results directory, a baseline file or the name of a baseline
+        baseline_dir: PathBuf,
+
+        /// Candidate results: a results directory, a baseline file or the name of a baseline
+        candidate_dir: PathBuf,
+
+        /// Maximum increase of a statistic, e.g. "cold-start-total-duration:p95:10%" (metric can be *)
+        #[arg(short = 't', long = "threshold", value_name = "METRIC:STAT:PERCENT", value_parser = clap::value_parser!(Threshold))]
+        thresholds: Vec<Threshold>,
+
+        /// Test deciding whether a change is statistically significant: welch or mann-whitney
+        #[arg(long = "significance-test", value_name = "TEST", default_value = "mann-whitney", value_parser = clap::value_parser!(SignificanceTest))]
+        significance_test: SignificanceTest,
+
+        /// Significance level: changes with a lower p-value are significant
+        #[arg(long = "alpha", default_value_t = 0.05)]
+        alpha: f64,
+
+        /// Only fail on threshold increases that are statistically significant
+        #[arg(long = "require-significance", default_value_t = false)]
+        require_significance: bool,
+
+        /// Write the comparison as a Markdown report to this file
+        #[arg(short = 'o', long = "output", value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Write the thresholds as a JUnit XML report to this file, with a test case per function and metric threshold
+        #[arg(long = "junit", value_name = "FILE")]
+        junit: Option<PathBuf>,
+
+        /// Print the exceeded thresholds as GitHub Actions annotations
+        #[arg(long = "github-annotations", default_value_t = false)]
+        github_annotations: bool,
+    },
+
+    /// Check the result files of a directory against the JSON schema of the results
+    #[command(group = ArgGroup::new("target").required(true).args(["directory", "schema"]))]
+    Validate {
+        /// Directory of the result files, searched recursively
+        directory: Option<PathBuf>,
+
+        /// Print the JSON schema of the result files instead
+        #[arg(long = "schema", default_value_t = false)]
+        schema: bool,
     },
+
+    /// Restore function configurations left modified by an interrupted benchmark
+    Restore {
+        /// State file written by the function and stack commands
+        #[arg(long = "from-state", default_value = DEFAULT_STATE_FILE)]
+        from_state: PathBuf,
+    },
+
     /// Generate shell completion script
     #[command(name = "generate-completions", hide = true)]
     GenerateCompletions {
@@ -222,10 +498,14 @@ async fn run() -> Result<()> {
     }
 
     // Initialize telemetry/tracing based on command type
-    let tracer_provider = match &args.command {
+    let telemetry = match &args.command {
         Commands::Function { .. } | Commands::Stack { .. } => Some(init_telemetry().await?),
-        Commands::Report { .. } => {
-            init_tracing(); // Initialize basic tracing for report command
+        Commands::Report { .. }
+        | Commands::Baseline { .. }
+        | Commands::Diff { .. }
+        | Commands::Validate { .. }
+        | Commands::Restore { .. } => {
+            init_tracing(); // Initialize basic tracing for report, baseline, diff, validate and restore commands
             None
         }
         Commands::GenerateCompletions { .. } => None,
@@ -234,15 +514,29 @@ async fn run() -> Result<()> {
     match args.command {
         Commands::Function {
             function_name,
+            pair,
             memory,
             concurrent,
             number,
+            duration,
+            rps,
+            warm_interval,
+            concurrent_environments,
             output_dir,
             payload,
             payload_file,
             environment,
+            variants,
             proxy,
+            exclude_failed,
+            stream,
+            dry_run,
+            state_file,
+            publish_cloudwatch_namespace,
+            publish_cloudwatch_log_group,
         } => {
+            let sustained_load = sustained_load(duration, rps)?;
+            let warm_pacing = WarmPacing::new(warm_interval, concurrent_environments, concurrent)?;
             let config = aws_config::load_from_env().await;
             let client = LambdaClient::new(&config);
 
@@ -268,69 +562,147 @@ async fn run() -> Result<()> {
                 path.to_string_lossy().into_owned()
             });
 
-            run_function_benchmark(
-                &client,
-                &function_name,
-                memory,
-                concurrent,
-                number,
-                payload.as_deref(),
-                final_output_dir.as_deref(),
-                &environment
-                    .iter()
-                    .map(|e| (e.key.as_str(), e.value.as_str()))
-                    .collect::<Vec<_>>(),
-                true,
-                proxy.as_deref(),
-                false,
-                None,
+            // The paired function is saved next to the first one, so the report compares them
+            let function_names: Vec<&str> = std::iter::once(function_name.as_str())
+                .chain(pair.as_deref())
+                .collect();
+            let variant_environments = variant_environments(&environment, &variants)?;
+
+            if dry_run {
+                let plan = ExecutionPlan {
+                    functions: function_names.iter().map(|f| f.to_string()).collect(),
+                    memory_sizes: memory,
+                    concurrent,
+                    number,
+                    sustained_load,
+                    warm_pacing,
+                    proxy_function: proxy,
+                    parallel: false,
+                    rate_limit: None,
+                    variants: variants.into_iter().map(|v| v.name).collect(),
+                };
+                return run_dry_run(&client, &plan).await;
+            }
+            let cloudwatch_publisher = cloudwatch_publisher(
+                &config,
+                publish_cloudwatch_namespace,
+                publish_cloudwatch_log_group,
             )
-            .await
+            .await?;
+            for (index, &memory_size) in memory.iter().enumerate() {
+                print_memory_sweep_step(index, &memory, memory_size);
+                for function_name in &function_names {
+                    for (variant, environment) in &variant_environments {
+                        run_function_benchmark(
+                            &client,
+                            function_name,
+                            memory_size,
+                            concurrent,
+                            number,
+                            payload.as_deref(),
+                            final_output_dir.as_deref(),
+                            environment,
+                            true,
+                            proxy.as_deref(),
+                            false,
+                            None,
+                            &state_file,
+                            cloudwatch_publisher.as_ref(),
+                            &InvocationLimits::default(),
+                            exclude_failed,
+                            sustained_load,
+                            warm_pacing,
+                            stream,
+                            *variant,
+                        )
+                        .await?;
+                    }
+                }
+            }
+            Ok(())
         }
 
         Commands::Stack {
             stack_name,
+            stack_names,
+            tags,
             select,
             select_regex,
             select_name,
             memory,
             concurrent,
             number,
+            duration,
+            rps,
+            warm_interval,
+            concurrent_environments,
             output_dir,
             payload,
             payload_file,
             environment,
             proxy,
+            exclude_failed,
+            stream,
+            dry_run,
             parallel,
+            max_concurrency,
+            rate_limit,
+            state_file,
+            publish_cloudwatch_namespace,
+            publish_cloudwatch_log_group,
         } => {
             let directory_group_name = if let Some(name_override) = &select_name {
                 validate_fs_safe_name(name_override)
                     .map_err(|e| anyhow!("Invalid --select-name: {}", e))?;
-                name_override.clone()
-            } else {
-                validate_fs_safe_name(&select)
+                Some(name_override.clone())
+            } else if let Some(select) = &select {
+                validate_fs_safe_name(select)
                         .map_err(|e| anyhow!("Invalid --select pattern for directory name: {}. Use --select-name to specify a different directory name.", e))?;
-                select.clone()
+                Some(select.clone())
+            } else {
+                None
             };
 
             // If output_dir is Some, construct the full path including the directory_group_name.
             // If output_dir is None, then final_output_dir_for_benchmark_group will also be None.
-            let final_output_dir_for_benchmark_group: Option<String> =
-                output_dir.map(|base_path| format!("{}/{}", base_path, directory_group_name));
+            let final_output_dir_for_benchmark_group: Option<String> = output_dir
+                .map(|base_path| {
+                    directory_group_name
+                        .map(|group_name| format!("{}/{}", base_path, group_name))
+                        .ok_or_else(|| {
+                            anyhow!("--select-name is required to name the output directory when --select is not provided")
+                        })
+                })
+                .transpose()?;
+            let warm_pacing = WarmPacing::new(warm_interval, concurrent_environments, concurrent)?;
 
             execute_stack_command(
-                stack_name,
+                stack_name.into_iter().chain(stack_names).collect(),
+                tags,
                 select,       // This is select_arg (pattern)
                 select_regex, // This is select_regex_arg
                 memory,
                 concurrent,
                 number,
+                sustained_load(duration, rps)?,
+                warm_pacing,
                 final_output_dir_for_benchmark_group,
                 payload,
                 payload_file,
                 environment,
                 proxy,
+                exclude_failed,
+                stream,
                 parallel,
+                dry_run,
+                InvocationLimits::new(
+                    max_concurrency,
+                    rate_limit,
+                    WarmPacing::round_invocations(warm_pacing, concurrent),
+                )?,
+                state_file,
+                publish_cloudwatch_namespace,
+                publish_cloudwatch_log_group,
             )
             .await
         }
@@ -346,7 +718,13 @@ async fn run() -> Result<()> {
             readme_file,
             base_url,
             local_browsing,
+            price_per_gb_second,
+            percentiles,
+            trim_percent,
+            show_std_dev,
+            trace_url,
         } => {
+            let stats_options = StatsOptions::new(percentiles, trim_percent, show_std_dev)?;
             let screenshot_theme = screenshot.map(|theme| match theme {
                 Theme::Light => "light",
                 Theme::Dark => "dark",
@@ -362,9 +740,53 @@ async fn run() -> Result<()> {
                 template_dir,
                 readme_file,
                 local_browsing,
+                price_per_gb_second,
+                &stats_options,
+                trace_url.as_deref(),
             )
             .await
         }
+        Commands::Baseline {
+            results_dir,
+            name,
+            output,
+        } => save_baseline(&results_dir, &name, output.as_deref()).map(|_| ()),
+        Commands::Diff {
+            baseline_dir,
+            candidate_dir,
+            thresholds,
+            significance_test,
+            alpha,
+            require_significance,
+            output,
+            junit,
+            github_annotations,
+        } => {
+            let significance =
+                SignificanceOptions::new(significance_test, alpha, require_significance)?;
+            run_diff(
+                &baseline_dir,
+                &candidate_dir,
+                &thresholds,
+                &significance,
+                output.as_deref(),
+                junit.as_deref(),
+                github_annotations,
+            )
+        }
+        // The directory and --schema are exclusive, and one of them is required
+        Commands::Validate { directory, .. } => match directory {
+            Some(directory) => validate_directory(&directory),
+            None => {
+                println!("{}", REPORT_SCHEMA);
+                Ok(())
+            }
+        },
+        Commands::Restore { from_state } => {
+            let config = aws_config::load_from_env().await;
+            let client = LambdaClient::new(&config);
+            restore_from_state(&client, &from_state).await
+        }
         Commands::GenerateCompletions { .. } => {
             unreachable!(
                 "clap should have handled GenerateCompletions and exited before this match arm"
@@ -372,11 +794,9 @@ async fn run() -> Result<()> {
         }
     }?;
 
-    // Ensure all spans are exported before exit (only if telemetry was initialized)
-    if let Some(provider) = tracer_provider {
-        if let Err(e) = provider.force_flush() {
-            tracing::error!("Failed to flush spans: {}", e);
-        }
+    // Ensure all spans and metrics are exported before exit (only if telemetry was initialized)
+    if let Some(telemetry) = telemetry {
+        telemetry.flush();
     }
 
     Ok(())
@@ -384,18 +804,28 @@ async fn run() -> Result<()> {
 
 #[allow(clippy::too_many_arguments)]
 async fn execute_stack_command(
-    stack_name: String,
-    select_pattern_arg: String,       // from --select
-    select_regex_arg: Option<String>, // from --select-regex
-    memory: i32,
+    stack_names: Vec<String>,
+    tags: Vec<String>,
+    select_pattern_arg: Option<String>, // from --select
+    select_regex_arg: Option<String>,   // from --select-regex
+    memory: Vec<i32>,
     concurrent: u32,
     number: u32,
+    sustained_load: Option<SustainedLoad>,
+    warm_pacing: Option<WarmPacing>,
     output_dir: Option<String>, // This is now base_dir/group_name or group_name
     payload: Option<String>,
     payload_file: Option<String>,
     environment: Vec<EnvVar>,
     proxy: Option<String>,
+    exclude_failed: bool,
+    response_streaming: bool,
     parallel: bool,
+    dry_run: bool,
+    limits: InvocationLimits,
+    state_file: PathBuf,
+    publish_cloudwatch_namespace: Option<String>,
+    publish_cloudwatch_log_group: String,
 ) -> Result<()> {
     let config = aws_config::load_from_env().await;
     let lambda_client = LambdaClient::new(&config);
@@ -415,20 +845,149 @@ async fn execute_stack_command(
         serde_json::from_str::<serde_json::Value>(p).context("Invalid JSON payload")?;
     }
 
-    let config = StackBenchmarkConfig {
-        stack_name,
-        select_pattern: select_pattern_arg,
-        select_regex: select_regex_arg,
-        memory_size: memory,
-        concurrent_invocations: concurrent as usize,
-        number: number as usize,
-        output_dir, // Already correctly formed
-        payload,
-        environment,
-        client_metrics_mode: true,
-        proxy_function: proxy,
-        parallel,
+    if dry_run {
+        let functions = select_stack_functions(
+            &lambda_client,
+            &cf_client,
+            &stack_names,
+            &tags,
+            select_pattern_arg.as_deref(),
+            select_regex_arg.as_deref(),
+        )
+        .await?;
+        if functions.is_empty() {
+            return Err(anyhow!("No Lambda functions match the select criteria"));
+        }
+        let plan = ExecutionPlan {
+            functions,
+            memory_sizes: memory,
+            concurrent,
+            number,
+            sustained_load,
+            warm_pacing,
+            proxy_function: proxy,
+            parallel,
+            rate_limit: limits.rate_limit(),
+            variants: Vec::new(),
+        };
+        return run_dry_run(&lambda_client, &plan).await;
+    }
+    let cloudwatch_publisher = cloudwatch_publisher(
+        &config,
+        publish_cloudwatch_namespace,
+        publish_cloudwatch_log_group,
+    )
+    .await?;
+
+    // Each memory size is benchmarked in turn, its results saved under its own <memory>mb directory
+    for (index, &memory_size) in memory.iter().enumerate() {
+        print_memory_sweep_step(index, &memory, memory_size);
+        let config = StackBenchmarkConfig {
+            stack_names: stack_names.clone(),
+            tags: tags.clone(),
+            select_pattern: select_pattern_arg.clone(),
+            select_regex: select_regex_arg.clone(),
+            memory_size,
+            concurrent_invocations: concurrent as usize,
+            number: number as usize,
+            output_dir: output_dir.clone(), // Already correctly formed
+            payload: payload.clone(),
+            environment: environment.clone(),
+            client_metrics_mode: true,
+            proxy_function: proxy.clone(),
+            parallel,
+            state_file: state_file.clone(),
+            cloudwatch_publisher: cloudwatch_publisher.clone(),
+            limits: limits.clone(),
+            exclude_failed,
+            sustained_load,
+            response_streaming,
+            warm_pacing,
+        };
+
+        run_stack_benchmark(&lambda_client, &cf_client, config).await?;
+        if is_interrupted() {
+            return Err(anyhow!("Benchmark interrupted by user"));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the sustained load of the warm starts, if a duration is given (clap requires both)
+fn sustained_load(duration: Option<u64>, rps: Option<f64>) -> Result<Option<SustainedLoad>> {
+    duration
+        .zip(rps)
+        .map(|(duration_secs, rps)| SustainedLoad::new(duration_secs, rps))
+        .transpose()
+}
+
+/// Returns the publisher of the CloudWatch metrics, if a namespace is given
+async fn cloudwatch_publisher(
+    config: &aws_config::SdkConfig,
+    namespace: Option<String>,
+    log_group: String,
+) -> Result<Option<CloudWatchPublisher>> {
+    let Some(namespace) = namespace else {
+        return Ok(None);
     };
+    let client = CloudWatchLogsClient::new(config);
+    Ok(Some(
+        CloudWatchPublisher::new(client, namespace, log_group).await?,
+    ))
+}
 
-    run_stack_benchmark(&lambda_client, &cf_client, config).await
+/// The name of a variant, if any, and the environment variables of its runs
+type VariantEnvironment<'a> = (Option<&'a str>, Vec<(&'a str, &'a str)>);
+
+/// Returns the environment of each variant: the --env variables, overridden by the variables of
+/// the variant. Without variants, the function only runs with the --env variables.
+fn variant_environments<'a>(
+    environment: &'a [EnvVar],
+    variants: &'a [EnvVariant],
+) -> Result<Vec<VariantEnvironment<'a>>> {
+    let base: Vec<(&str, &str)> = environment
+        .iter()
+        .map(|e| (e.key.as_str(), e.value.as_str()))
+        .collect();
+    if variants.is_empty() {
+        return Ok(vec![(None, base)]);
+    }
+    let mut names = std::collections::HashSet::new();
+    variants
+        .iter()
+        .map(|variant| {
+            if !names.insert(variant.name.as_str()) {
+                return Err(anyhow!("Duplicate variant name: {}", variant.name));
+            }
+            let mut environment: Vec<(&str, &str)> = base
+                .iter()
+                .filter(|(key, _)| !variant.environment.iter().any(|e| e.key == *key))
+                .copied()
+                .collect();
+            environment.extend(
+                variant
+                    .environment
+                    .iter()
+                    .map(|e| (e.key.as_str(), e.value.as_str())),
+            );
+            Ok((Some(variant.name.as_str()), environment))
+        })
+        .collect()
+}
+
+/// Prints the memory size about to be benchmarked, when sweeping several sizes
+fn print_memory_sweep_step(index: usize, memory_sizes: &[i32], memory_size: i32) {
+    if memory_sizes.len() > 1 {
+        println!(
+            "\n{}",
+            format!(
+                "=== Memory sweep [{}/{}]: {} MB ===",
+                index + 1,
+                memory_sizes.len(),
+                memory_size
+            )
+            .bright_blue()
+            .bold()
+        );
+    }
 }
@@ -0,0 +1,193 @@
+//! Records the results of each benchmark run as OpenTelemetry metrics.
+//!
+//! The measurements of a run are recorded in histograms when its report is complete, with the
+//! function name, memory size, architecture and runtime as attributes, and exported to the OTLP
+//! endpoint with the spans of the invocations. The results can then be queried and compared in
+//! the same backend as the production telemetry of the functions.
+
+use opentelemetry::{global, KeyValue};
+
+use crate::types::BenchmarkReport;
+
+/// A histogram of a measurement of the benchmark runs
+struct HistogramDefinition {
+    name: &'static str,
+    description: &'static str,
+    unit: &'static str,
+    values: fn(&BenchmarkReport) -> Vec<f64>,
+}
+
+static HISTOGRAMS: [HistogramDefinition; 7] = [
+    HistogramDefinition {
+        name: "startled.cold_start.init_duration",
+        description: "Init duration of the cold starts",
+        unit: "ms",
+        values: |report| report.cold_starts.iter().map(|m| m.init_duration).collect(),
+    },
+    HistogramDefinition {
+        name: "startled.cold_start.duration",
+        description: "Server duration of the cold starts",
+        unit: "ms",
+        values: |report| report.cold_starts.iter().map(|m| m.duration).collect(),
+    },
+    HistogramDefinition {
+        name: "startled.warm_start.duration",
+        description: "Server duration of the warm starts",
+        unit: "ms",
+        values: |report| report.warm_starts.iter().map(|m| m.duration).collect(),
+    },
+    HistogramDefinition {
+        name: "startled.warm_start.extension_overhead",
+        description: "Extension overhead of the warm starts",
+        unit: "ms",
+        values: |report| {
+            report
+                .warm_starts
+                .iter()
+                .map(|m| m.extension_overhead)
+                .collect()
+        },
+    },
+    HistogramDefinition {
+        name: "startled.warm_start.billed_duration",
+        description: "Billed duration of the warm starts",
+        unit: "ms",
+        values: |report| {
+            report
+                .warm_starts
+                .iter()
+                .map(|m| m.billed_duration as f64)
+                .collect()
+        },
+    },
+    HistogramDefinition {
+        name: "startled.warm_start.memory_used",
+        description: "Maximum memory used by the warm starts",
+        unit: "MBy",
+        values: |report| {
+            report
+                .warm_starts
+                .iter()
+                .map(|m| m.max_memory_used as f64)
+                .collect()
+        },
+    },
+    HistogramDefinition {
+        name: "startled.client.duration",
+        description: "Duration of the warm starts measured by the client or the proxy function",
+        unit: "ms",
+        values: |report| {
+            report
+                .client_measurements
+                .iter()
+                .map(|m| m.client_duration)
+                .collect()
+        },
+    },
+];
+
+/// Records the measurements of a benchmark run with the global meter provider
+pub fn record_benchmark_metrics(report: &BenchmarkReport) {
+    let meter = global::meter(env!("CARGO_PKG_NAME"));
+    let attributes = report_attributes(report);
+    for definition in &HISTOGRAMS {
+        let histogram = meter
+            .f64_histogram(definition.name)
+            .with_description(definition.description)
+            .with_unit(definition.unit)
+            .build();
+        for value in (definition.values)(report) {
+            histogram.record(value, &attributes);
+        }
+    }
+}
+
+fn report_attributes(report: &BenchmarkReport) -> Vec<KeyValue> {
+    let config = &report.config;
+    let mut attributes = vec![
+        KeyValue::new("function.name", config.function_name.clone()),
+        KeyValue::new("function.memory_size", config.memory_size as i64),
+    ];
+    if let Some(architecture) = &config.architecture {
+        attributes.push(KeyValue::new("function.architecture", architecture.clone()));
+    }
+    if let Some(runtime) = &config.runtime {
+        attributes.push(KeyValue::new("function.runtime", runtime.clone()));
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::REPORT_SCHEMA_VERSION;
+    use crate::types::{BenchmarkConfig, ClientMetrics, WarmStartMetrics};
+
+    #[test]
+    fn test_histogram_values_and_attributes() {
+        let warm_start = |duration: f64| WarmStartMetrics {
+            timestamp: String::new(),
+            duration,
+            extension_overhead: 1.5,
+            billed_duration: duration.ceil() as i64,
+            max_memory_used: 40,
+            memory_size: 128,
+            response_latency_ms: None,
+            response_duration_ms: None,
+            runtime_overhead_ms: None,
+            produced_bytes: None,
+            runtime_done_metrics_duration_ms: None,
+            extension_overheads: Default::default(),
+            trace_id: None,
+        };
+        let report = BenchmarkReport {
+            schema_version: REPORT_SCHEMA_VERSION,
+            config: BenchmarkConfig {
+                function_name: "fn-a".to_string(),
+                memory_size: 128,
+                concurrent_invocations: 1,
+                number: 2,
+                timestamp: String::new(),
+                runtime: None,
+                architecture: Some("arm64".to_string()),
+                environment: Vec::new(),
+                sustained_load: None,
+                response_streaming: false,
+                variant: None,
+                warm_pacing: None,
+            },
+            cold_starts: Vec::new(),
+            warm_starts: vec![warm_start(10.2), warm_start(12.0)],
+            client_measurements: vec![ClientMetrics {
+                timestamp: String::new(),
+                client_duration: 30.0,
+                memory_size: 128,
+                time_to_first_byte: None,
+                stream_duration: None,
+                trace_id: None,
+            }],
+            errors: Vec::new(),
+            environment_recycles: vec![],
+        };
+
+        let values = |name: &str| {
+            let definition = HISTOGRAMS.iter().find(|d| d.name == name).unwrap();
+            (definition.values)(&report)
+        };
+        assert!(values("startled.cold_start.init_duration").is_empty());
+        assert_eq!(values("startled.warm_start.duration"), vec![10.2, 12.0]);
+        assert_eq!(
+            values("startled.warm_start.billed_duration"),
+            vec![11.0, 12.0]
+        );
+        assert_eq!(values("startled.client.duration"), vec![30.0]);
+        assert_eq!(
+            report_attributes(&report),
+            vec![
+                KeyValue::new("function.name", "fn-a"),
+                KeyValue::new("function.memory_size", 128),
+                KeyValue::new("function.architecture", "arm64"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//! Execution plan of the `function` and `stack` commands, printed by `--dry-run`.
+//!
+//! A dry run checks that every function (and the proxy function) exists and can be invoked,
+//! with the `DryRun` invocation type of Lambda, then prints the invocations of each function and
+//! memory size with an estimate of their duration and cost. Nothing is invoked, and no function
+//! configuration is changed.
+
+use crate::report::{price_per_gb_second, PRICE_PER_MILLION_REQUESTS};
+use crate::types::{SustainedLoad, WarmPacing};
+use anyhow::{bail, Result};
+use aws_sdk_lambda::error::ProvideErrorMetadata;
+use aws_sdk_lambda::types::InvocationType;
+use aws_sdk_lambda::Client as LambdaClient;
+use colored::*;
+
+/// Duration of an invocation assumed by the estimates, before any has been measured
+pub const ASSUMED_INVOCATION_MS: f64 = 100.0;
+
+// Time taken by a configuration update to be applied before the first round
+const CONFIG_UPDATE_SECS: f64 = 5.0;
+
+/// The benchmark runs of a command: each function at each memory size
+#[derive(Debug, Clone)]
+pub struct ExecutionPlan {
+    pub functions: Vec<String>,
+    pub memory_sizes: Vec<i32>,
+    pub concurrent: u32,
+    pub number: u32,
+    pub sustained_load: Option<SustainedLoad>,
+    pub warm_pacing: Option<WarmPacing>,
+    pub proxy_function: Option<String>,
+    /// Whether the functions of a memory size are benchmarked at the same time (`--parallel`)
+    pub parallel: bool,
+    /// Invocations per second of each function (`--rate-limit`)
+    pub rate_limit: Option<f64>,
+    /// Environment variants each function runs with (`--variant`)
+    pub variants: Vec<String>,
+}
+
+impl ExecutionPlan {
+    /// Invocations of a pass: a round of cold starts, then the rounds or the sustained load
+    pub fn invocations_per_pass(&self) -> u64 {
+        let round = WarmPacing::round_invocations(self.warm_pacing, self.concurrent) as u64;
+        let warm_starts = match self.sustained_load {
+            Some(load) => (load.rps * load.duration_secs as f64).ceil() as u64,
+            None => self.number as u64 * round,
+        };
+        round + warm_starts
+    }
+
+    /// Invocations of a function at a memory size, in the server and client passes
+    pub fn invocations_per_run(&self) -> u64 {
+        2 * self.invocations_per_pass()
+    }
+
+    /// Estimated seconds of a function at a memory size
+    pub fn estimated_run_secs(&self) -> f64 {
+        let rounds = 1 + match self.sustained_load {
+            Some(_) => 0,
+            None => self.number as u64,
+        };
+        let mut pass_secs = rounds as f64 * ASSUMED_INVOCATION_MS / 1000.0;
+        if let Some(load) = self.sustained_load {
+            pass_secs += load.duration_secs as f64;
+        }
+        if let Some(interval_secs) = self.warm_pacing.and_then(|pacing| pacing.interval_secs) {
+            pass_secs += (self.number as u64 * interval_secs) as f64;
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            pass_secs = pass_secs.max(self.invocations_per_pass() as f64 / rate_limit);
+        }
+        CONFIG_UPDATE_SECS + 2.0 * pass_secs
+    }
+
+    /// Estimated seconds of the whole command
+    pub fn estimated_total_secs(&self) -> f64 {
+        let functions = if self.parallel {
+            1
+        } else {
+            self.functions.len()
+        };
+        let runs = functions * self.memory_sizes.len() * self.variants.len().max(1);
+        self.estimated_run_secs() * runs as f64
+    }
+
+    /// Estimated cost in USD of a function at a memory size
+    pub fn estimated_run_cost(&self, memory_size: i32, architecture: Option<&str>) -> f64 {
+        let invocations = self.invocations_per_run() as f64;
+        let gb_seconds = (memory_size as f64 / 1024.0) * (ASSUMED_INVOCATION_MS / 1000.0);
+        invocations
+            * (gb_seconds * price_per_gb_second(architecture)
+                + PRICE_PER_MILLION_REQUESTS / 1_000_000.0)
+    }
+}
+
+/// A function checked by the dry run
+struct FunctionCheck {
+    name: String,
+    architecture: Option<String>,
+    problem: Option<String>,
+}
+
+/// Checks the functions and prints the plan, failing if any function cannot be benchmarked
+pub async fn run_dry_run(client: &LambdaClient, plan: &ExecutionPlan) -> Result<()> {
+    println!(
+        "\n{}",
+        "Dry run: nothing will be invoked".bright_blue().bold()
+    );
+
+    let mut checks = Vec::new();
+    for function_name in plan.functions.iter().chain(&plan.proxy_function) {
+        checks.push(check_function(client, function_name).await);
+    }
+
+    println!("\nPlan:");
+    println!("  {:20}: {}", "Functions".dimmed(), plan.functions.len());
+    println!(
+        "  {:20}: {}",
+        "Memory Sizes".dimmed(),
+        plan.memory_sizes
+            .iter()
+            .map(|memory_size| format!("{} MB", memory_size))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!("  {:20}: {}", "Concurrency".dimmed(), plan.concurrent);
+    match plan.sustained_load {
+        Some(load) => println!(
+            "  {:20}: {} rps for {}s",
+            "Sustained Load".dimmed(),
+            load.rps,
+            load.duration_secs
+        ),
+        None => println!("  {:20}: {}", "Rounds".dimmed(), plan.number),
+    }
+    if let Some(interval_secs) = plan.warm_pacing.and_then(|pacing| pacing.interval_secs) {
+        println!("  {:20}: {}s", "Warm Interval".dimmed(), interval_secs);
+    }
+    if let Some(environments) = plan.warm_pacing.and_then(|pacing| pacing.environments) {
+        println!("  {:20}: {}", "Warm Environments".dimmed(), environments);
+    }
+    if let Some(proxy) = &plan.proxy_function {
+        println!("  {:20}: {}", "Using Proxy Function".dimmed(), proxy);
+    }
+    if !plan.variants.is_empty() {
+        println!("  {:20}: {}", "Variants".dimmed(), plan.variants.join(", "));
+    }
+
+    println!(
+        "\n  {:<40} {:>8} {:>12} {:>12}",
+        "Function".dimmed(),
+        "Memory".dimmed(),
+        "Invocations".dimmed(),
+        "Cost (USD)".dimmed()
+    );
+    let mut total_invocations = 0;
+    let mut total_cost = 0.0;
+    // Each variant is a run of its own, shown after the function name
+    let run_names = |check: &FunctionCheck| -> Vec<String> {
+        if plan.variants.is_empty() {
+            vec![check.name.clone()]
+        } else {
+            plan.variants
+                .iter()
+                .map(|variant| format!("{} [{}]", check.name, variant))
+                .collect()
+        }
+    };
+    for check in checks.iter().take(plan.functions.len()) {
+        for name in run_names(check) {
+            for &memory_size in &plan.memory_sizes {
+                let cost = plan.estimated_run_cost(memory_size, check.architecture.as_deref());
+                total_invocations += plan.invocations_per_run();
+                total_cost += cost;
+                println!(
+                    "  {:<40} {:>5} MB {:>12} {:>12.4}",
+                    name,
+                    memory_size,
+                    plan.invocations_per_run(),
+                    cost
+                );
+            }
+        }
+    }
+    println!("\n  {:20}: {}", "Invocations".dimmed(), total_invocations);
+    println!(
+        "  {:20}: ~{}",
+        "Estimated Duration".dimmed(),
+        format_secs(plan.estimated_total_secs())
+    );
+    println!("  {:20}: ~${:.4}", "Estimated Cost".dimmed(), total_cost);
+    println!(
+        "  {}",
+        format!(
+            "Estimates assume {} ms per invocation and the on-demand price of the architectures",
+            ASSUMED_INVOCATION_MS
+        )
+        .dimmed()
+    );
+
+    println!("\nChecks:");
+    let mut problems = 0;
+    for check in &checks {
+        match &check.problem {
+            None => println!("  {} {}", "✓".green(), check.name),
+            Some(problem) => {
+                problems += 1;
+                println!("  {} {}: {}", "✗".red(), check.name, problem);
+            }
+        }
+    }
+    if problems > 0 {
+        bail!(
+            "Dry run found {} function(s) that cannot be benchmarked",
+            problems
+        );
+    }
+    println!("\n{}", "✓ Dry run passed".green());
+    Ok(())
+}
+
+/// Checks that a function exists and can be invoked, without invoking it
+async fn check_function(client: &LambdaClient, function_name: &str) -> FunctionCheck {
+    let mut check = FunctionCheck {
+        name: function_name.to_string(),
+        architecture: None,
+        problem: None,
+    };
+    match client
+        .get_function()
+        .function_name(function_name)
+        .send()
+        .await
+    {
+        Ok(output) => {
+            check.architecture = output
+                .configuration()
+                .and_then(|config| config.architectures().first())
+                .map(|architecture| architecture.as_str().to_string());
+        }
+        Err(err) => {
+            check.problem = Some(format!(
+                "cannot be read ({})",
+                err.code().unwrap_or("unknown error")
+            ));
+            return check;
+        }
+    }
+    if let Err(err) = client
+        .invoke()
+        .function_name(function_name)
+        .invocation_type(InvocationType::DryRun)
+        .send()
+        .await
+    {
+        check.problem = Some(format!(
+            "cannot be invoked ({})",
+            err.code().unwrap_or("unknown error")
+        ));
+    }
+    check
+}
+
+/// Formats a number of seconds as hours, minutes and seconds
+fn format_secs(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {}s", m, s),
+        (h, m, _) => format!("{}h {}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execution_plan_estimates() {
+        let mut plan = ExecutionPlan {
+            functions: vec!["a".to_string(), "b".to_string()],
+            memory_sizes: vec![128, 1024],
+            concurrent: 10,
+            number: 5,
+            sustained_load: None,
+            warm_pacing: None,
+            proxy_function: None,
+            parallel: false,
+            rate_limit: None,
+            variants: Vec::new(),
+        };
+        // A round of 10 cold starts and 5 rounds of 10 warm starts, in both passes
+        assert_eq!(plan.invocations_per_pass(), 60);
+        assert_eq!(plan.invocations_per_run(), 120);
+        assert!((plan.estimated_run_secs() - (CONFIG_UPDATE_SECS + 1.2)).abs() < 1e-9);
+        assert!((plan.estimated_total_secs() - 4.0 * plan.estimated_run_secs()).abs() < 1e-9);
+
+        // 120 invocations of 0.1 GB-second at the arm64 price, plus the requests
+        let cost = plan.estimated_run_cost(1024, Some("arm64"));
+        let expected = 120.0 * (0.1 * 0.0000133334 + 0.2 / 1_000_000.0);
+        assert!((cost - expected).abs() < 1e-12);
+
+        // Rounds of 20 invocations, started every 30 seconds
+        plan.warm_pacing = WarmPacing::new(Some(30), Some(20), 10).unwrap();
+        assert_eq!(plan.invocations_per_pass(), 120);
+        assert!((plan.estimated_run_secs() - (CONFIG_UPDATE_SECS + 301.2)).abs() < 1e-9);
+        assert!(WarmPacing::new(None, Some(5), 10).is_err());
+        assert!(WarmPacing::new(None, None, 10).unwrap().is_none());
+        plan.warm_pacing = None;
+
+        plan.sustained_load = Some(SustainedLoad::new(60, 2.5).unwrap());
+        plan.rate_limit = Some(1.0);
+        plan.parallel = true;
+        assert_eq!(plan.invocations_per_pass(), 160);
+        // The rate limit stretches each pass to 160 seconds
+        assert!((plan.estimated_run_secs() - (CONFIG_UPDATE_SECS + 320.0)).abs() < 1e-9);
+        assert!((plan.estimated_total_secs() - 2.0 * plan.estimated_run_secs()).abs() < 1e-9);
+
+        // Each variant runs every function at every memory size again
+        plan.variants = vec!["baseline".to_string(), "gzip".to_string()];
+        assert!((plan.estimated_total_secs() - 4.0 * plan.estimated_run_secs()).abs() < 1e-9);
+
+        assert_eq!(format_secs(42.0), "42s");
+        assert_eq!(format_secs(125.0), "2m 5s");
+        assert_eq!(format_secs(7260.0), "2h 1m");
+    }
+}
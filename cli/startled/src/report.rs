@@ -1,17 +1,9 @@
 use crate::screenshot::take_chart_screenshot;
-use crate::stats::{
-    calculate_client_stats, calculate_cold_start_extension_overhead_stats,
-    calculate_cold_start_init_stats, calculate_cold_start_produced_bytes_stats,
-    calculate_cold_start_response_duration_stats, calculate_cold_start_response_latency_stats,
-    calculate_cold_start_runtime_done_metrics_duration_stats,
-    calculate_cold_start_runtime_overhead_stats, calculate_cold_start_server_stats,
-    calculate_cold_start_total_duration_stats, calculate_memory_stats,
-    calculate_warm_start_produced_bytes_stats, calculate_warm_start_response_duration_stats,
-    calculate_warm_start_response_latency_stats,
-    calculate_warm_start_runtime_done_metrics_duration_stats,
-    calculate_warm_start_runtime_overhead_stats, calculate_warm_start_stats,
+use crate::stats::StatsOptions;
+use crate::types::{
+    BenchmarkConfig, BenchmarkReport, ClientMetrics, ColdStartMetrics, InvocationPhase,
+    WarmStartMetrics, UNATTRIBUTED_EXTENSION_OVERHEAD,
 };
-use crate::types::{BenchmarkConfig, BenchmarkReport, ColdStartMetrics, WarmStartMetrics};
 use anyhow::{Context, Result};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use pulldown_cmark::{html, Options, Parser};
@@ -20,7 +12,7 @@ use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use serde::Serialize;
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fs,
     path::{Path, PathBuf},
     time::Duration,
@@ -30,6 +22,24 @@ use tera::{Context as TeraContext, Tera};
 /// Define a type alias for the report structure
 type ReportStructure = BTreeMap<String, Vec<String>>;
 
+/// AWS Lambda on-demand prices in USD (us-east-1), used unless --price-per-gb-second is given
+const X86_64_PRICE_PER_GB_SECOND: f64 = 0.0000166667;
+const ARM64_PRICE_PER_GB_SECOND: f64 = 0.0000133334;
+pub const PRICE_PER_MILLION_REQUESTS: f64 = 0.20;
+
+/// Unit of the estimated cost charts
+const COST_UNIT: &str = "USD per 1M";
+
+// Metric pages of the client measurements, rather than of the warm starts
+const CLIENT_PAGE_TYPES: [&str; 3] = [
+    "warm_start_client_duration",
+    "warm_start_time_to_first_byte",
+    "warm_start_stream_duration",
+];
+
+// Maximum number of buckets of the distribution histograms
+const HISTOGRAM_MAX_BUCKETS: usize = 40;
+
 /// Convert snake_case to kebab-case for SEO-friendly URLs
 fn snake_to_kebab(input: &str) -> String {
     input.replace('_', "-")
@@ -38,7 +48,7 @@ fn snake_to_kebab(input: &str) -> String {
 #[derive(Serialize)]
 struct SeriesRenderData {
     name: String,
-    values: Vec<f64>, // e.g., [avg, p99, p95, p50]
+    values: Vec<f64>, // In the order of the categories, e.g., [avg, p50, p95, p99]
 }
 
 #[derive(Serialize)]
@@ -55,6 +65,8 @@ struct BarChartRenderData {
 struct ScatterPoint {
     x: usize, // Original index or offsetted index
     y: f64,   // Duration
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trace_id: Option<String>, // Trace of the sample, linked with --trace-url
 }
 
 #[derive(Serialize)]
@@ -76,6 +88,22 @@ struct LineChartRenderData {
     description: Option<String>, // AWS-documentation-based description of the metric
 }
 
+#[derive(Serialize)]
+struct HistogramSeriesRenderData {
+    name: String,
+    counts: Vec<usize>, // Number of values in each bucket
+}
+
+/// Distribution of the values of a metric, bucketed over the same range for every function
+#[derive(Serialize)]
+struct HistogramChartRenderData {
+    title: String,
+    unit: String,
+    bucket_bounds: Vec<f64>, // Bucket edges, one more than the buckets
+    series: Vec<HistogramSeriesRenderData>,
+    page_type: String,
+}
+
 /// Data structure for memory scaling charts
 #[derive(Debug, Serialize)]
 struct MemoryScalingPoint {
@@ -135,14 +163,45 @@ struct MemoryScalingSummaryData {
     page_type: String,
 }
 
+/// A function in the cost-performance chart
+#[derive(Debug, Serialize)]
+struct CostPerformancePoint {
+    name: String,
+    architecture: String,
+    duration: f64, // Average warm start server duration (ms)
+    cost: f64,     // Estimated cost per million invocations (USD)
+}
+
+/// The same function benchmarked on both architectures
+#[derive(Debug, Serialize)]
+struct ArchitecturePair {
+    name: String, // Function name without the architecture
+    arm64: CostPerformancePoint,
+    x86_64: CostPerformancePoint,
+}
+
+/// Data structure for the cost-performance and architecture comparison page
+#[derive(Debug, Serialize)]
+struct CostPerformanceChartRenderData {
+    title: String,
+    description: String,
+    unit: String,
+    points: Vec<CostPerformancePoint>,
+    pairs: Vec<ArchitecturePair>,
+    page_type: String,
+}
+
 #[derive(Serialize)]
 enum ChartRenderData {
     Combined {
         bar: Box<BarChartRenderData>,
         line: Box<LineChartRenderData>,
+        histogram: Box<HistogramChartRenderData>,
     },
     Summary(SummaryChartRenderData),
     MemoryScalingSummary(MemoryScalingSummaryData),
+    CostPerformance(CostPerformanceChartRenderData),
+    Bar(BarChartRenderData),
 }
 
 /// Generate a chart with the given options
@@ -162,6 +221,7 @@ async fn generate_chart(
     template_dir: Option<&String>,
     base_url: Option<&str>,
     local_browsing: bool,
+    trace_url: Option<&str>,
 ) -> Result<()> {
     // Initialize Tera for HTML templates (chart.html, _sidebar.html)
     let mut tera_html = Tera::default();
@@ -219,7 +279,7 @@ async fn generate_chart(
     let mut ctx = TeraContext::new();
     // Extract title, page_type, and description from the enum variant
     let (title, page_type, description) = match chart_render_data {
-        ChartRenderData::Combined { bar, line: _ } => {
+        ChartRenderData::Combined { bar, .. } => {
             (bar.title.as_str(), bar.page_type.as_str(), &bar.description)
         }
         ChartRenderData::Summary(summary) => (
@@ -232,6 +292,12 @@ async fn generate_chart(
             memory_summary.page_type.as_str(),
             &Some(memory_summary.description.clone()),
         ),
+        ChartRenderData::CostPerformance(cost_performance) => (
+            cost_performance.title.as_str(),
+            cost_performance.page_type.as_str(),
+            &Some(cost_performance.description.clone()),
+        ),
+        ChartRenderData::Bar(bar) => (bar.title.as_str(), bar.page_type.as_str(), &bar.description),
     };
 
     ctx.insert("title", title);
@@ -250,6 +316,9 @@ async fn generate_chart(
     // Use the kebab-case name for URL references
     ctx.insert("kebab_name", &kebab_name);
 
+    // Template of the links from the samples to their traces
+    ctx.insert("trace_url", &trace_url);
+
     // Add link_suffix for local browsing
     ctx.insert(
         "link_suffix",
@@ -522,6 +591,9 @@ pub async fn generate_reports(
     template_dir: Option<String>,
     readme_file: Option<String>,
     local_browsing: bool,
+    price_per_gb_second: Option<f64>,
+    stats_options: &StatsOptions,
+    trace_url: Option<&str>,
 ) -> Result<()> {
     // Create output directory if it doesn't exist
     fs::create_dir_all(output_directory)?;
@@ -641,6 +713,9 @@ pub async fn generate_reports(
                 template_dir.as_ref(),
                 base_url,
                 local_browsing,
+                price_per_gb_second,
+                stats_options,
+                trace_url,
             )
             .await
             .context(format!(
@@ -667,6 +742,7 @@ pub async fn generate_reports(
             template_dir.as_ref(),
             base_url,
             local_browsing,
+            price_per_gb_second,
         )
         .await
         .context(format!(
@@ -723,6 +799,7 @@ async fn generate_group_memory_scaling_summary(
     template_dir: Option<&String>,
     base_url: Option<&str>,
     local_browsing: bool,
+    price_per_gb_second: Option<f64>,
 ) -> Result<()> {
     // Create group/all directory for memory scaling summary
     let all_dir = Path::new(output_directory).join(group_name).join("all");
@@ -820,6 +897,21 @@ async fn generate_group_memory_scaling_summary(
                 calculate_gb_seconds_per_million(&r.warm_starts, r.config.memory_size)
             }),
         ),
+        (
+            "estimated_cost",
+            "Estimated Cost per Million Invocations",
+            COST_UNIT,
+            Box::new(move |r: &BenchmarkReport| {
+                let price = resolve_price_per_gb_second(&r.config, price_per_gb_second);
+                calculate_avg_from_warm_starts(&r.warm_starts, |ws| {
+                    Some(cost_per_million_invocations(
+                        ws.billed_duration,
+                        ws.memory_size,
+                        price,
+                    ))
+                })
+            }),
+        ),
     ];
 
     // Collect all chart data for the single summary page
@@ -866,6 +958,7 @@ async fn generate_group_memory_scaling_summary(
         template_dir,
         base_url,
         local_browsing,
+        None,
     )
     .await?;
 
@@ -910,6 +1003,31 @@ fn calculate_gb_seconds_per_million(
     Some(gb * seconds * 1_000_000.0)
 }
 
+/// Returns the price per GB-second of a function: the override if given, or the AWS Lambda
+/// on-demand price of its architecture
+fn resolve_price_per_gb_second(config: &BenchmarkConfig, price_override: Option<f64>) -> f64 {
+    price_override.unwrap_or_else(|| price_per_gb_second(config.architecture.as_deref()))
+}
+
+/// Returns the AWS Lambda on-demand price per GB-second of an architecture
+pub fn price_per_gb_second(architecture: Option<&str>) -> f64 {
+    match architecture {
+        Some("arm64") => ARM64_PRICE_PER_GB_SECOND,
+        _ => X86_64_PRICE_PER_GB_SECOND,
+    }
+}
+
+/// Estimated cost in USD of a million invocations with the given billed duration and memory,
+/// including the request charge
+fn cost_per_million_invocations(
+    billed_duration_ms: i64,
+    memory_mb: i64,
+    price_per_gb_second: f64,
+) -> f64 {
+    let gb_seconds = (memory_mb as f64 / 1024.0) * (billed_duration_ms as f64 / 1000.0);
+    gb_seconds * price_per_gb_second * 1_000_000.0 + PRICE_PER_MILLION_REQUESTS
+}
+
 fn prepare_memory_scaling_chart_data<F>(
     function_memory_data: &BTreeMap<String, BTreeMap<i32, BenchmarkReport>>,
     title: &str,
@@ -967,6 +1085,7 @@ fn get_memory_scaling_description(page_type: &str) -> &'static str {
         "warm_start_billed_duration" => "The duration AWS bills for warm invocations. This directly impacts cost and helps find the optimal memory configuration for your workload.",
         "warm_start_extension_overhead" => "Performance impact of Lambda Extensions (e.g., observability agents). Shows how extension overhead scales with available resources.",
         "resource_consumption" => "Cost efficiency measured in GB-seconds per million invocations. Lower values mean more cost-efficient execution. Helps balance performance vs. cost when choosing memory allocation.",
+        "estimated_cost" => "Estimated cost in USD of one million warm invocations, from the billed duration, the configured memory and the price per GB-second, plus the request charge. Shows whether a larger memory size pays for itself with shorter durations.",
         _ => "Performance metric across different memory configurations.",
     }
 }
@@ -985,6 +1104,9 @@ pub async fn generate_reports_for_directory(
     template_dir: Option<&String>,
     base_url: Option<&str>,
     local_browsing: bool,
+    price_per_gb_second: Option<f64>,
+    stats_options: &StatsOptions,
+    trace_url: Option<&str>,
 ) -> Result<()> {
     // Create output directory for PNG files if screenshots are enabled
     let png_dir = if screenshot_theme.is_some() {
@@ -1027,137 +1149,13 @@ pub async fn generate_reports_for_directory(
         return Err(anyhow::anyhow!("No benchmark results found in '{}' or its subdirectories. Please check the directory path.", input_directory));
     }
 
-    // Calculate statistics and generate charts
-    let cold_init_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_cold_start_init_stats(&report.cold_starts)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0)) // 5-tuple default
-        })
-        .collect();
-
-    let cold_server_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_cold_start_server_stats(&report.cold_starts)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-
-    let client_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_client_stats(&report.client_measurements).unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-
-    let server_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_warm_start_stats(&report.warm_starts, |m| m.duration)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-
-    let cold_extension_overhead_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_cold_start_extension_overhead_stats(&report.cold_starts)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-
-    let cold_total_duration_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_cold_start_total_duration_stats(&report.cold_starts)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-
-    let memory_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_memory_stats(&report.warm_starts).unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-
-    // --- Calculate New Platform Metrics Stats ---
-    // Cold Start
-    let cold_response_latency_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_cold_start_response_latency_stats(&report.cold_starts)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-    let cold_response_duration_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_cold_start_response_duration_stats(&report.cold_starts)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-    let cold_runtime_overhead_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_cold_start_runtime_overhead_stats(&report.cold_starts)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-    let cold_runtime_done_duration_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_cold_start_runtime_done_metrics_duration_stats(&report.cold_starts)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-
-    // Warm Start (also for produced_bytes, though it could be cold or warm)
-    let warm_response_latency_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_warm_start_response_latency_stats(&report.warm_starts)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-    let warm_response_duration_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_warm_start_response_duration_stats(&report.warm_starts)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-    let warm_runtime_overhead_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_warm_start_runtime_overhead_stats(&report.warm_starts)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-    let warm_runtime_done_duration_stats: Vec<_> = results
-        .iter()
-        .map(|report| {
-            calculate_warm_start_runtime_done_metrics_duration_stats(&report.warm_starts)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-    let produced_bytes_stats: Vec<_> = results // Assuming we take produced_bytes from warm starts, could be cold too.
-        .iter()
-        .map(|report| {
-            calculate_warm_start_produced_bytes_stats(&report.warm_starts)
-                .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-        })
-        .collect();
-    // --- End New Platform Metrics Stats ---
-
     // Generate cold start init duration chart if we have data
     if results.iter().any(|r| !r.cold_starts.is_empty()) {
         // Cold Start Init Duration - Combined Chart
         let cold_init_combined = prepare_combined_chart_render_data(
             &function_names,
-            &cold_init_stats,
             &results,
+            stats_options,
             "Cold Start - Init Duration",
             "ms",
             "cold_init",
@@ -1184,14 +1182,15 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
         // Cold Start Server Duration - Combined Chart
         let cold_server_combined = prepare_combined_chart_render_data(
             &function_names,
-            &cold_server_stats,
             &results,
+            stats_options,
             "Cold Start - Server Duration",
             "ms",
             "cold_server",
@@ -1212,14 +1211,15 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
         // Cold Start Extension Overhead - Combined Chart
         let cold_ext_overhead_combined = prepare_combined_chart_render_data(
             &function_names,
-            &cold_extension_overhead_stats,
             &results,
+            stats_options,
             "Cold Start - Extension Overhead",
             "ms",
             "cold_extension_overhead",
@@ -1246,14 +1246,15 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
         // Cold Start Total Duration - Combined Chart
         let cold_total_duration_combined = prepare_combined_chart_render_data(
             &function_names,
-            &cold_total_duration_stats,
             &results,
+            stats_options,
             "Cold Start - Total Cold Start Duration",
             "ms",
             "cold_total_duration",
@@ -1280,6 +1281,7 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
@@ -1287,8 +1289,8 @@ pub async fn generate_reports_for_directory(
         // Cold Start Response Latency - Combined Chart
         let cold_resp_latency_combined = prepare_combined_chart_render_data(
             &function_names,
-            &cold_response_latency_stats,
             &results,
+            stats_options,
             "Cold Start - Response Latency",
             "ms",
             "cold_start_response_latency",
@@ -1315,14 +1317,15 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
         // Cold Start Response Duration - Combined Chart
         let cold_resp_duration_combined = prepare_combined_chart_render_data(
             &function_names,
-            &cold_response_duration_stats,
             &results,
+            stats_options,
             "Cold Start - Response Duration",
             "ms",
             "cold_start_response_duration",
@@ -1349,14 +1352,15 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
         // Cold Start Runtime Overhead - Combined Chart
         let cold_runtime_overhead_combined = prepare_combined_chart_render_data(
             &function_names,
-            &cold_runtime_overhead_stats,
             &results,
+            stats_options,
             "Cold Start - Runtime Overhead",
             "ms",
             "cold_start_runtime_overhead",
@@ -1383,14 +1387,15 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
         // Cold Start Runtime Done Duration - Combined Chart
         let cold_runtime_done_combined = prepare_combined_chart_render_data(
             &function_names,
-            &cold_runtime_done_duration_stats,
             &results,
+            stats_options,
             "Cold Start - Runtime Done Duration",
             "ms",
             "cold_start_runtime_done_duration",
@@ -1417,33 +1422,17 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
         // --- End New Cold Start Platform Metric Charts ---
 
         // --- Add Missing Cold Start Resource Metric Charts ---
         // Cold Start Memory Usage - Combined Chart
-        let cold_memory_stats: Vec<_> = results
-            .iter()
-            .map(|report| {
-                // Calculate memory stats for cold starts inline
-                if report.cold_starts.is_empty() {
-                    (0.0, 0.0, 0.0, 0.0, 0.0)
-                } else {
-                    let memory: Vec<f64> = report
-                        .cold_starts
-                        .iter()
-                        .map(|cs| cs.max_memory_used as f64)
-                        .collect();
-                    let stats = crate::stats::calculate_stats(&memory);
-                    (stats.mean, stats.p99, stats.p95, stats.p50, stats.std_dev)
-                }
-            })
-            .collect();
         let cold_memory_combined = prepare_combined_chart_render_data(
             &function_names,
-            &cold_memory_stats,
             &results,
+            stats_options,
             "Cold Start - Memory Usage",
             "MB",
             "cold_start_memory",
@@ -1470,21 +1459,15 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
         // Cold Start Produced Bytes - Combined Chart
-        let cold_produced_bytes_stats: Vec<_> = results
-            .iter()
-            .map(|report| {
-                calculate_cold_start_produced_bytes_stats(&report.cold_starts)
-                    .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-            })
-            .collect();
         let cold_produced_bytes_combined = prepare_combined_chart_render_data(
             &function_names,
-            &cold_produced_bytes_stats,
             &results,
+            stats_options,
             "Cold Start - Produced Bytes",
             "bytes",
             "cold_start_produced_bytes",
@@ -1511,6 +1494,7 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
         // --- End Missing Cold Start Resource Metric Charts ---
@@ -1521,8 +1505,8 @@ pub async fn generate_reports_for_directory(
         // Warm Start Client Duration - Combined Chart (RENAMED for consistency)
         let client_duration_combined = prepare_combined_chart_render_data(
             &function_names,
-            &client_stats,
             &results,
+            stats_options,
             "Warm Start - Client Duration",
             "ms",
             "warm_start_client_duration", // CHANGED: was "client"
@@ -1549,8 +1533,64 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
+
+        // Streamed responses (--stream): time to first byte and stream duration
+        if results.iter().any(|r| {
+            r.client_measurements
+                .iter()
+                .any(|m| m.time_to_first_byte.is_some())
+        }) {
+            let streaming_charts = [
+                (
+                    "Warm Start - Time to First Byte",
+                    "warm_start_time_to_first_byte",
+                    (|m: &ClientMetrics| m.time_to_first_byte) as fn(&ClientMetrics) -> _,
+                ),
+                (
+                    "Warm Start - Stream Duration",
+                    "warm_start_stream_duration",
+                    |m| m.stream_duration,
+                ),
+            ];
+            for (title, name, extract) in streaming_charts {
+                let combined = prepare_combined_chart_render_data(
+                    &function_names,
+                    &results,
+                    stats_options,
+                    title,
+                    "ms",
+                    name,
+                    |report| {
+                        report
+                            .client_measurements
+                            .iter()
+                            .filter_map(extract)
+                            .collect()
+                    },
+                );
+                generate_chart(
+                    &PathBuf::from(output_directory),
+                    png_dir.as_deref(),
+                    name,
+                    &combined,
+                    &results[0].config,
+                    suffix,
+                    screenshot_theme,
+                    pb,
+                    report_structure,
+                    current_group,
+                    current_subgroup,
+                    template_dir,
+                    base_url,
+                    local_browsing,
+                    trace_url,
+                )
+                .await?;
+            }
+        }
     }
 
     // Generate server duration chart if we have data
@@ -1558,8 +1598,8 @@ pub async fn generate_reports_for_directory(
         // Warm Start Server Duration - Combined Chart (RENAMED for consistency)
         let server_duration_combined = prepare_combined_chart_render_data(
             &function_names,
-            &server_stats,
             &results,
+            stats_options,
             "Warm Start - Server Duration",
             "ms",
             "warm_start_server_duration", // CHANGED: was "server"
@@ -1580,21 +1620,15 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
         // Warm Start Extension Overhead - Combined Chart (RENAMED for consistency)
-        let warm_extension_overhead_stats: Vec<_> = results
-            .iter()
-            .map(|report| {
-                calculate_warm_start_stats(&report.warm_starts, |m| m.extension_overhead)
-                    .unwrap_or((0.0, 0.0, 0.0, 0.0, 0.0))
-            })
-            .collect();
         let ext_overhead_combined = prepare_combined_chart_render_data(
             &function_names,
-            &warm_extension_overhead_stats,
             &results,
+            stats_options,
             "Warm Start - Extension Overhead",
             "ms",
             "warm_start_extension_overhead", // CHANGED: was "extension_overhead"
@@ -1621,14 +1655,15 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
         // Warm Start Memory Usage - Combined Chart (RENAMED for consistency)
         let memory_combined = prepare_combined_chart_render_data(
             &function_names,
-            &memory_stats,
             &results,
+            stats_options,
             "Warm Start - Memory Usage",
             "MB",
             "warm_start_memory", // CHANGED: was "memory"
@@ -1655,6 +1690,7 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
@@ -1662,8 +1698,8 @@ pub async fn generate_reports_for_directory(
         // Warm Start Response Latency - Combined Chart
         let warm_resp_latency_combined = prepare_combined_chart_render_data(
             &function_names,
-            &warm_response_latency_stats,
             &results,
+            stats_options,
             "Warm Start - Response Latency",
             "ms",
             "warm_start_response_latency",
@@ -1690,14 +1726,15 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
         // Warm Start Response Duration - Combined Chart
         let warm_resp_duration_combined = prepare_combined_chart_render_data(
             &function_names,
-            &warm_response_duration_stats,
             &results,
+            stats_options,
             "Warm Start - Response Duration",
             "ms",
             "warm_start_response_duration",
@@ -1724,14 +1761,15 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
         // Warm Start Runtime Overhead - Combined Chart
         let warm_runtime_overhead_combined = prepare_combined_chart_render_data(
             &function_names,
-            &warm_runtime_overhead_stats,
             &results,
+            stats_options,
             "Warm Start - Runtime Overhead",
             "ms",
             "warm_start_runtime_overhead",
@@ -1758,14 +1796,15 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
         // Warm Start Runtime Done Duration - Combined Chart
         let warm_runtime_done_combined = prepare_combined_chart_render_data(
             &function_names,
-            &warm_runtime_done_duration_stats,
             &results,
+            stats_options,
             "Warm Start - Runtime Done Duration",
             "ms",
             "warm_start_runtime_done_duration",
@@ -1792,14 +1831,15 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
 
         // Warm Start Produced Bytes - Combined Chart (RENAMED for consistency)
         let produced_bytes_combined = prepare_combined_chart_render_data(
             &function_names,
-            &produced_bytes_stats,
             &results,
+            stats_options,
             "Warm Start - Produced Bytes",
             "bytes",
             "warm_start_produced_bytes", // CHANGED: was "produced_bytes"
@@ -1826,16 +1866,115 @@ pub async fn generate_reports_for_directory(
             template_dir,
             base_url,
             local_browsing,
+            trace_url,
         )
         .await?;
         // --- End Complete Set of Warm Start Platform Metric Charts ---
+
+        // Warm Start Estimated Cost - Combined Chart
+        let warm_cost_combined = prepare_combined_chart_render_data(
+            &function_names,
+            &results,
+            stats_options,
+            "Warm Start - Estimated Cost",
+            COST_UNIT,
+            "warm_start_cost",
+            |report| warm_start_costs(report, price_per_gb_second),
+        );
+        generate_chart(
+            &PathBuf::from(output_directory),
+            png_dir.as_deref(),
+            "warm_start_cost",
+            &warm_cost_combined,
+            &results[0].config,
+            suffix,
+            screenshot_theme,
+            pb,
+            report_structure,
+            current_group,
+            current_subgroup,
+            template_dir,
+            base_url,
+            local_browsing,
+            trace_url,
+        )
+        .await?;
+
+        // Cost vs Performance, with the arm64/x86_64 pairs side by side
+        let cost_performance =
+            prepare_cost_performance_render_data(&function_names, &results, price_per_gb_second);
+        generate_chart(
+            &PathBuf::from(output_directory),
+            png_dir.as_deref(),
+            "cost_performance",
+            &cost_performance,
+            &results[0].config,
+            suffix,
+            screenshot_theme,
+            pb,
+            report_structure,
+            current_group,
+            current_subgroup,
+            template_dir,
+            base_url,
+            local_browsing,
+            trace_url,
+        )
+        .await?;
     }
 
+    // Warm start extension overhead of each extension
+    let extension_breakdown = ChartRenderData::Bar(prepare_extension_breakdown_render_data(
+        &function_names,
+        &results,
+    ));
+    generate_chart(
+        &PathBuf::from(output_directory),
+        png_dir.as_deref(),
+        "warm_start_extension_breakdown",
+        &extension_breakdown,
+        &results[0].config,
+        suffix,
+        screenshot_theme,
+        pb,
+        report_structure,
+        current_group,
+        current_subgroup,
+        template_dir,
+        base_url,
+        local_browsing,
+        trace_url,
+    )
+    .await?;
+
+    // Error rates of the invocations, with the throttles
+    let error_rate =
+        ChartRenderData::Bar(prepare_error_rate_render_data(&function_names, &results));
+    generate_chart(
+        &PathBuf::from(output_directory),
+        png_dir.as_deref(),
+        "error_rate",
+        &error_rate,
+        &results[0].config,
+        suffix,
+        screenshot_theme,
+        pb,
+        report_structure,
+        current_group,
+        current_subgroup,
+        template_dir,
+        base_url,
+        local_browsing,
+        trace_url,
+    )
+    .await?;
+
     // Generate Summary Page
     let summary_combined = prepare_summary_chart_render_data(
         &function_names,
         &results,
         custom_title.unwrap_or("Performance Summary"),
+        price_per_gb_second,
     );
     generate_chart(
         &PathBuf::from(output_directory),
@@ -1852,6 +1991,7 @@ pub async fn generate_reports_for_directory(
         template_dir,
         base_url,
         local_browsing,
+        trace_url,
     )
     .await?;
 
@@ -1860,7 +2000,8 @@ pub async fn generate_reports_for_directory(
 
 fn prepare_bar_chart_render_data(
     function_names: &[String],
-    stats: &[(f64, f64, f64, f64, f64)], // Expects (avg, p99, p95, p50, std_dev)
+    stats: &[Vec<f64>], // Per function, in the order of the categories
+    categories: Vec<String>,
     title: &str,
     unit: &str,
     page_type: &str,
@@ -1868,72 +2009,62 @@ fn prepare_bar_chart_render_data(
     let series_render_data = function_names
         .iter()
         .zip(stats.iter())
-        .map(|(name, &(avg, p99, p95, p50, _std_dev))| {
+        .map(|(name, values)| SeriesRenderData {
+            name: name.clone(),
             // Use Decimal for precise rounding to 3 decimal places
-            let rounded_avg = Decimal::from_f64(avg)
-                .unwrap_or_default()
-                .round_dp(3)
-                .to_f64()
-                .unwrap_or(0.0);
-            let rounded_p50 = Decimal::from_f64(p50)
-                .unwrap_or_default()
-                .round_dp(3)
-                .to_f64()
-                .unwrap_or(0.0);
-            let rounded_p95 = Decimal::from_f64(p95)
-                .unwrap_or_default()
-                .round_dp(3)
-                .to_f64()
-                .unwrap_or(0.0);
-            let rounded_p99 = Decimal::from_f64(p99)
-                .unwrap_or_default()
-                .round_dp(3)
-                .to_f64()
-                .unwrap_or(0.0);
-
-            SeriesRenderData {
-                name: name.clone(),
-                values: vec![
-                    rounded_avg, // AVG
-                    rounded_p50, // P50
-                    rounded_p95, // P95
-                    rounded_p99, // P99
-                ],
-            }
+            values: values
+                .iter()
+                .map(|&value| {
+                    Decimal::from_f64(value)
+                        .unwrap_or_default()
+                        .round_dp(3)
+                        .to_f64()
+                        .unwrap_or(0.0)
+                })
+                .collect(),
         })
         .collect();
 
     BarChartRenderData {
         title: title.to_string(),
         unit: unit.to_string(),
-        y_axis_categories: vec![
-            "AVG".to_string(),
-            "P50".to_string(),
-            "P95".to_string(),
-            "P99".to_string(),
-        ],
+        y_axis_categories: categories,
         series: series_render_data,
         page_type: page_type.to_string(),
         description: get_metric_description(page_type).map(|s| s.to_string()),
     }
 }
 
-/// Prepares a combined chart with both bar chart (aggregates) and line chart (time series) data
+/// Prepares a combined chart with bar chart (aggregates), line chart (time series) and
+/// histogram (distribution) data
 fn prepare_combined_chart_render_data(
     function_names: &[String],
-    stats: &[(f64, f64, f64, f64, f64)], // Expects (avg, p99, p95, p50, std_dev)
     results: &[BenchmarkReport],
+    stats_options: &StatsOptions,
     title: &str,
     unit: &str,
     page_type: &str,
     value_extractor: impl Fn(&BenchmarkReport) -> Vec<f64>,
 ) -> ChartRenderData {
+    let values: Vec<Vec<f64>> = results.iter().map(&value_extractor).collect();
+
     // Prepare bar chart data
-    let bar_data = prepare_bar_chart_render_data(function_names, stats, title, unit, page_type);
+    let stats: Vec<Vec<f64>> = values
+        .iter()
+        .map(|values| stats_options.summarize(values))
+        .collect();
+    let bar_data = prepare_bar_chart_render_data(
+        function_names,
+        &stats,
+        stats_options.labels(),
+        title,
+        unit,
+        page_type,
+    );
 
     // Prepare line chart data for the same metric over time
     let line_title = format!("{} - Over Time", title);
-    let line_data = prepare_metric_line_chart_render_data(
+    let mut line_data = prepare_metric_line_chart_render_data(
         results,
         function_names,
         &line_title,
@@ -1942,12 +2073,235 @@ fn prepare_combined_chart_render_data(
         value_extractor,
     );
 
+    // Link the samples to their traces, unless some samples have no value for the metric
+    for (series, report) in line_data.series.iter_mut().zip(results) {
+        let trace_ids = sample_trace_ids(report, page_type);
+        if trace_ids.len() == series.points.len() {
+            for (point, trace_id) in series.points.iter_mut().zip(trace_ids) {
+                point.trace_id = trace_id.map(str::to_string);
+            }
+        }
+    }
+
+    // Prepare the distribution of the same metric, which averages and percentiles hide
+    let histogram_data = prepare_histogram_render_data(
+        function_names,
+        &values,
+        &format!("{} - Distribution", title),
+        unit,
+        page_type,
+    );
+
     ChartRenderData::Combined {
         bar: Box::new(bar_data),
         line: Box::new(line_data),
+        histogram: Box::new(histogram_data),
+    }
+}
+
+/// Buckets the values of each function over the range of all of them, so that their
+/// distributions can be overlaid. The number of buckets follows the square root of the largest
+/// sample, up to HISTOGRAM_MAX_BUCKETS.
+fn prepare_histogram_render_data(
+    function_names: &[String],
+    values: &[Vec<f64>],
+    title: &str,
+    unit: &str,
+    page_type: &str,
+) -> HistogramChartRenderData {
+    let (min, max) = values
+        .iter()
+        .flatten()
+        .filter(|value| value.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+            (min.min(value), max.max(value))
+        });
+    let largest_sample = values.iter().map(Vec::len).max().unwrap_or(0);
+    let buckets = if min > max {
+        0 // No values
+    } else if min == max {
+        1
+    } else {
+        ((largest_sample as f64).sqrt().ceil() as usize).clamp(1, HISTOGRAM_MAX_BUCKETS)
+    };
+    let width = if buckets > 0 {
+        (max - min) / buckets as f64
+    } else {
+        0.0
+    };
+
+    let series = function_names
+        .iter()
+        .zip(values)
+        .map(|(name, values)| {
+            let mut counts = vec![0; buckets];
+            for value in values.iter().filter(|value| value.is_finite()) {
+                let bucket = if width > 0.0 {
+                    ((value - min) / width).floor() as usize
+                } else {
+                    0
+                };
+                // The maximum falls in the last bucket
+                counts[bucket.min(buckets - 1)] += 1;
+            }
+            HistogramSeriesRenderData {
+                name: name.clone(),
+                counts,
+            }
+        })
+        .collect();
+
+    let bucket_bounds = if buckets > 0 {
+        (0..=buckets)
+            .map(|i| {
+                Decimal::from_f64(min + i as f64 * width)
+                    .unwrap_or_default()
+                    .round_dp(3)
+                    .to_f64()
+                    .unwrap_or(0.0)
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+
+    HistogramChartRenderData {
+        title: title.to_string(),
+        unit: unit.to_string(),
+        bucket_bounds,
+        series,
+        page_type: format!("{}_histogram", page_type),
     }
 }
 
+/// Prepares the error rates of the functions, by measurement, and their rate of throttles
+fn prepare_error_rate_render_data(
+    function_names: &[String],
+    results: &[BenchmarkReport],
+) -> BarChartRenderData {
+    let rates: Vec<Vec<f64>> = results.iter().map(error_rates).collect();
+    prepare_bar_chart_render_data(
+        function_names,
+        &rates,
+        ["COLD START", "WARM START", "CLIENT", "THROTTLED"]
+            .map(String::from)
+            .to_vec(),
+        "Error Rate",
+        "%",
+        "error_rate",
+    )
+}
+
+/// Returns the trace IDs of the samples of a metric page, from the cold starts, the warm starts
+/// or the client measurements
+fn sample_trace_ids<'a>(report: &'a BenchmarkReport, page_type: &str) -> Vec<Option<&'a str>> {
+    if page_type.starts_with("cold") {
+        report
+            .cold_starts
+            .iter()
+            .map(|m| m.trace_id.as_deref())
+            .collect()
+    } else if CLIENT_PAGE_TYPES.contains(&page_type) {
+        report
+            .client_measurements
+            .iter()
+            .map(|m| m.trace_id.as_deref())
+            .collect()
+    } else {
+        report
+            .warm_starts
+            .iter()
+            .map(|m| m.trace_id.as_deref())
+            .collect()
+    }
+}
+
+/// Prepares the average warm start extension overhead of each extension, as categories. The
+/// overhead of the functions without extension breakdown is unattributed.
+fn prepare_extension_breakdown_render_data(
+    function_names: &[String],
+    results: &[BenchmarkReport],
+) -> BarChartRenderData {
+    let overheads: Vec<BTreeMap<String, f64>> = results
+        .iter()
+        .map(|report| {
+            let mut totals: BTreeMap<String, f64> = BTreeMap::new();
+            for warm_start in &report.warm_starts {
+                if warm_start.extension_overheads.is_empty() {
+                    *totals
+                        .entry(UNATTRIBUTED_EXTENSION_OVERHEAD.to_string())
+                        .or_default() += warm_start.extension_overhead;
+                }
+                for (extension, overhead) in &warm_start.extension_overheads {
+                    *totals.entry(extension.clone()).or_default() += overhead;
+                }
+            }
+            let samples = report.warm_starts.len().max(1) as f64;
+            totals.values_mut().for_each(|total| *total /= samples);
+            totals
+        })
+        .collect();
+
+    // The extensions by name, then the unattributed overhead
+    let mut categories: Vec<String> = overheads
+        .iter()
+        .flat_map(|totals| totals.keys())
+        .filter(|name| *name != UNATTRIBUTED_EXTENSION_OVERHEAD)
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    categories.push(UNATTRIBUTED_EXTENSION_OVERHEAD.to_string());
+
+    let values: Vec<Vec<f64>> = overheads
+        .iter()
+        .map(|totals| {
+            categories
+                .iter()
+                .map(|category| totals.get(category).copied().unwrap_or(0.0))
+                .collect()
+        })
+        .collect();
+    prepare_bar_chart_render_data(
+        function_names,
+        &values,
+        categories,
+        "Warm Start - Extension Breakdown",
+        "ms",
+        "warm_start_extension_breakdown",
+    )
+}
+
+/// Returns the percentages of failed cold starts, warm starts and client measurements, and the
+/// percentage of throttled invocations
+fn error_rates(report: &BenchmarkReport) -> Vec<f64> {
+    let percentage = |errors: usize, total: usize| {
+        if total == 0 {
+            0.0
+        } else {
+            100.0 * errors as f64 / total as f64
+        }
+    };
+    let mut invocations = 0;
+    let mut rates: Vec<f64> = [
+        (InvocationPhase::ColdStart, report.cold_starts.len()),
+        (InvocationPhase::WarmStart, report.warm_starts.len()),
+        (InvocationPhase::Client, report.client_measurements.len()),
+    ]
+    .into_iter()
+    .map(|(phase, samples)| {
+        let errors: Vec<_> = report.errors.iter().filter(|e| e.phase == phase).collect();
+        // The failed invocations kept in the samples are already counted
+        let total = samples + errors.iter().filter(|e| !e.sampled).count();
+        invocations += total;
+        percentage(errors.len(), total)
+    })
+    .collect();
+    let throttled = report.errors.iter().filter(|e| e.throttled).count();
+    rates.push(percentage(throttled, invocations));
+    rates
+}
+
 /// Prepares line chart data for a specific metric using a value extraction function
 fn prepare_metric_line_chart_render_data(
     results: &[BenchmarkReport],
@@ -1994,6 +2348,7 @@ fn prepare_metric_line_chart_render_data(
                     ScatterPoint {
                         x: x_offset + index,
                         y: duration,
+                        trace_id: None,
                     }
                 })
                 .collect();
@@ -2082,6 +2437,17 @@ fn get_metric_description(page_type: &str) -> Option<&'static str> {
             network latency, Lambda service processing time, and function execution time. Warm starts reuse existing execution \
             environments, skipping the Init phase, resulting in significantly lower latency than cold starts. Measured in milliseconds."
         ),
+        "warm_start_time_to_first_byte" => Some(
+            "The time from sending the request to receiving the first chunk of a streamed response, measured from the client \
+            perspective for functions invoked with response streaming (InvokeWithResponseStream). A streaming function can \
+            send its first bytes long before it completes, so this is the latency perceived by clients that render the \
+            response progressively. Measured in milliseconds."
+        ),
+        "warm_start_stream_duration" => Some(
+            "The time from the first chunk to the end of a streamed response, measured from the client perspective for \
+            functions invoked with response streaming. Together with the time to first byte, it makes up the client duration \
+            of the invocation. Measured in milliseconds."
+        ),
         "warm_start_server_duration" => Some(
             "The time your function code spends processing an event during warm start invocations. Since warm starts reuse \
             existing execution environments, this excludes initialization overhead and focuses purely on your application logic \
@@ -2092,6 +2458,12 @@ fn get_metric_description(page_type: &str) -> Option<&'static str> {
             extensions are already initialized in warm starts, they may still perform post-invocation processing (e.g., sending \
             telemetry, cleanup). This is the AWS CloudWatch 'PostRuntimeExtensionsDuration' metric. Measured in milliseconds."
         ),
+        "warm_start_extension_breakdown" => Some(
+            "The average warm start extension overhead of each extension subscribed to the invocations. The extensions are \
+            registered by the platform.extension events of the cold starts. A platform.report span named after an extension \
+            is its overhead, and a single extension accounts for all of it. The overhead no extension accounts for is \
+            unattributed. Measured in milliseconds."
+        ),
         "warm_start_response_latency" => Some(
             "The time between when the Lambda service receives an invocation request and when the response becomes available \
             during warm start invocations. Since warm starts skip initialization, this latency is typically much lower than \
@@ -2135,6 +2507,18 @@ fn get_metric_description(page_type: &str) -> Option<&'static str> {
             serialization in steady-state operations. Large responses may impact performance and incur additional data transfer costs. Part of the \
             platform.runtimeDone metrics. Measured in bytes."
         ),
+        "warm_start_cost" => Some(
+            "The estimated cost of one million warm invocations, computed from the billed duration and the configured memory \
+            (GB-seconds) at the price per GB-second of the function architecture, plus the $0.20 per million request charge. \
+            The price defaults to the AWS Lambda on-demand price in us-east-1 and can be overridden with --price-per-gb-second. \
+            Free tier, Savings Plans and provisioned concurrency are not taken into account. Measured in USD per million invocations."
+        ),
+        "error_rate" => Some(
+            "The percentage of failed invocations of the cold starts, warm starts and client measurements, and the percentage \
+            of all the invocations throttled by Lambda. Failed invocations are function errors (Unhandled or Handled) and \
+            service or SDK errors. Failed invocations that still reported measurements are part of the latency samples, \
+            unless the benchmark ran with --exclude-failed."
+        ),
 
         _ => None,
     }
@@ -2145,6 +2529,7 @@ fn prepare_summary_chart_render_data(
     function_names: &[String],
     results: &[BenchmarkReport],
     title: &str,
+    price_per_gb_second: Option<f64>,
 ) -> ChartRenderData {
     let metrics = vec![
         // Key Cold Start Metrics
@@ -2229,6 +2614,12 @@ fn prepare_summary_chart_render_data(
                     .collect()
             }),
         ),
+        (
+            "warm-start-cost",
+            "Warm Start Estimated Cost",
+            COST_UNIT,
+            collect_avg_values(results, |r| warm_start_costs(r, price_per_gb_second)),
+        ),
     ];
 
     let summary_metrics: Vec<SummaryMetricData> = metrics
@@ -2263,6 +2654,107 @@ fn prepare_summary_chart_render_data(
     ChartRenderData::Summary(summary_data)
 }
 
+/// Estimated cost per million invocations of each warm start of a report
+fn warm_start_costs(report: &BenchmarkReport, price_per_gb_second: Option<f64>) -> Vec<f64> {
+    let price = resolve_price_per_gb_second(&report.config, price_per_gb_second);
+    report
+        .warm_starts
+        .iter()
+        .map(|ws| cost_per_million_invocations(ws.billed_duration, ws.memory_size, price))
+        .collect()
+}
+
+/// Returns the name of a function without its architecture, e.g. "api-rust" for
+/// "api-rust-arm64" and "api-rust-x86_64", to pair the builds of the same function
+fn architecture_pair_key(function_name: &str) -> String {
+    const ARCHITECTURE_TOKENS: [&str; 7] =
+        ["arm64", "arm", "graviton", "x86", "x64", "amd64", "intel"];
+    let lowercase = function_name
+        .to_lowercase()
+        .replace("x86_64", "x86")
+        .replace("x86-64", "x86");
+    lowercase
+        .split(['-', '_'])
+        .filter(|token| !token.is_empty() && !ARCHITECTURE_TOKENS.contains(token))
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Prepares the cost-performance chart of the functions, and the comparison of the functions
+/// benchmarked on both architectures
+fn prepare_cost_performance_render_data(
+    function_names: &[String],
+    results: &[BenchmarkReport],
+    price_per_gb_second: Option<f64>,
+) -> ChartRenderData {
+    let round = |value: f64| {
+        Decimal::from_f64(value)
+            .unwrap_or_default()
+            .round_dp(3)
+            .to_f64()
+            .unwrap_or(0.0)
+    };
+    let point = |name: &String, report: &BenchmarkReport| -> Option<CostPerformancePoint> {
+        let duration = calculate_avg_from_warm_starts(&report.warm_starts, |ws| Some(ws.duration))?;
+        let costs = warm_start_costs(report, price_per_gb_second);
+        Some(CostPerformancePoint {
+            name: name.clone(),
+            architecture: report
+                .config
+                .architecture
+                .clone()
+                .unwrap_or_else(|| "x86_64".to_string()),
+            duration: round(duration),
+            cost: round(costs.iter().sum::<f64>() / costs.len() as f64),
+        })
+    };
+
+    // Functions by name without architecture, to find the pairs
+    let mut candidates: BTreeMap<String, Vec<(&String, &BenchmarkReport)>> = BTreeMap::new();
+    for (name, report) in function_names.iter().zip(results) {
+        candidates
+            .entry(architecture_pair_key(name))
+            .or_default()
+            .push((name, report));
+    }
+    let pairs = candidates
+        .into_iter()
+        .filter_map(|(key, functions)| {
+            let [first, second] = functions[..] else {
+                return None;
+            };
+            let (first, second) = (point(first.0, first.1)?, point(second.0, second.1)?);
+            let (arm64, x86_64) = match (first.architecture.as_str(), second.architecture.as_str())
+            {
+                ("arm64", "x86_64") => (first, second),
+                ("x86_64", "arm64") => (second, first),
+                _ => return None,
+            };
+            Some(ArchitecturePair {
+                name: key,
+                arm64,
+                x86_64,
+            })
+        })
+        .collect();
+
+    ChartRenderData::CostPerformance(CostPerformanceChartRenderData {
+        title: "Cost vs Performance".to_string(),
+        description: "Average warm start server duration and estimated cost per million invocations of each function. \
+            Functions closer to the bottom left are both faster and cheaper. Functions benchmarked on both arm64 and x86_64, \
+            paired by their name without the architecture (e.g. api-arm64 and api-x86_64), are compared side by side."
+            .to_string(),
+        unit: COST_UNIT.to_string(),
+        points: function_names
+            .iter()
+            .zip(results)
+            .filter_map(|(name, report)| point(name, report))
+            .collect(),
+        pairs,
+        page_type: "cost_performance".to_string(),
+    })
+}
+
 /// Helper function to collect average values for a metric across all results
 fn collect_avg_values(
     results: &[BenchmarkReport],
@@ -2291,7 +2783,8 @@ fn collect_avg_values(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{BenchmarkConfig, BenchmarkReport, ClientMetrics}; // Removed unused ColdStartMetrics, EnvVar, WarmStartMetrics
+    use crate::schema::REPORT_SCHEMA_VERSION;
+    use crate::types::{BenchmarkConfig, BenchmarkReport, ClientMetrics, InvocationError}; // Removed unused ColdStartMetrics, EnvVar, WarmStartMetrics
     use std::path::PathBuf;
 
     #[test]
@@ -2376,15 +2869,21 @@ mod tests {
     fn test_prepare_bar_chart_render_data() {
         let function_names = vec!["func_a".to_string(), "func_b".to_string()];
         let stats = vec![
-            (10.5126, 15.1001, 14.2999, 12.3456, 1.0), // avg, p99, p95, p50, std_dev for func_a
-            (20.0004, 25.5555, 24.0011, 22.5678, 1.5), // avg, p99, p95, p50, std_dev for func_b
+            vec![10.5126, 12.3456, 14.2999, 15.1001], // avg, p50, p95, p99 for func_a
+            vec![20.0004, 22.5678, 24.0011, 25.5555], // avg, p50, p95, p99 for func_b
         ];
         let title = "Test Bar Chart";
         let unit = "ms";
         let page_type = "test_bar";
 
-        let render_data =
-            prepare_bar_chart_render_data(&function_names, &stats, title, unit, page_type);
+        let render_data = prepare_bar_chart_render_data(
+            &function_names,
+            &stats,
+            StatsOptions::default().labels(),
+            title,
+            unit,
+            page_type,
+        );
 
         assert_eq!(render_data.title, title);
         assert_eq!(render_data.unit, unit);
@@ -2417,21 +2916,31 @@ mod tests {
                 timestamp: "t1".to_string(),
                 client_duration: 10.12,
                 memory_size: 128,
+                time_to_first_byte: None,
+                stream_duration: None,
+                trace_id: None,
             },
             ClientMetrics {
                 timestamp: "t2".to_string(),
                 client_duration: 12.34,
                 memory_size: 128,
+                time_to_first_byte: None,
+                stream_duration: None,
+                trace_id: None,
             },
         ];
         let func_b_metrics = vec![ClientMetrics {
             timestamp: "t3".to_string(),
             client_duration: 20.56,
             memory_size: 128,
+            time_to_first_byte: None,
+            stream_duration: None,
+            trace_id: None,
         }];
 
         let results = vec![
             BenchmarkReport {
+                schema_version: REPORT_SCHEMA_VERSION,
                 config: BenchmarkConfig {
                     function_name: "func_a".to_string(),
                     memory_size: 128,
@@ -2441,12 +2950,19 @@ mod tests {
                     runtime: None,
                     architecture: None,
                     environment: vec![],
+                    sustained_load: None,
+                    response_streaming: false,
+                    variant: None,
+                    warm_pacing: None,
                 },
                 cold_starts: vec![],
                 warm_starts: vec![],
                 client_measurements: func_a_metrics,
+                errors: vec![],
+                environment_recycles: vec![],
             },
             BenchmarkReport {
+                schema_version: REPORT_SCHEMA_VERSION,
                 config: BenchmarkConfig {
                     function_name: "func_b".to_string(),
                     memory_size: 128,
@@ -2456,10 +2972,16 @@ mod tests {
                     runtime: None,
                     architecture: None,
                     environment: vec![],
+                    sustained_load: None,
+                    response_streaming: false,
+                    variant: None,
+                    warm_pacing: None,
                 },
                 cold_starts: vec![],
                 warm_starts: vec![],
                 client_measurements: func_b_metrics,
+                errors: vec![],
+                environment_recycles: vec![],
             },
         ];
         let function_names = vec!["func_a".to_string(), "func_b".to_string()];
@@ -2519,6 +3041,7 @@ mod tests {
     #[test]
     fn test_prepare_line_chart_render_data_empty_measurements() {
         let results = vec![BenchmarkReport {
+            schema_version: REPORT_SCHEMA_VERSION,
             config: BenchmarkConfig {
                 function_name: "func_a".to_string(),
                 memory_size: 128,
@@ -2528,10 +3051,16 @@ mod tests {
                 runtime: None,
                 architecture: None,
                 environment: vec![],
+                sustained_load: None,
+                response_streaming: false,
+                variant: None,
+                warm_pacing: None,
             },
             cold_starts: vec![],
             warm_starts: vec![],
             client_measurements: vec![], // Empty
+            errors: vec![],
+            environment_recycles: vec![],
         }];
         let function_names = vec!["func_a".to_string()];
         let render_data = prepare_metric_line_chart_render_data(
@@ -2570,17 +3099,21 @@ mod tests {
         assert!(get_metric_description("warm_start_extension_overhead").is_some());
         assert!(get_metric_description("warm_start_memory").is_some());
         assert!(get_metric_description("warm_start_produced_bytes").is_some());
+        assert!(get_metric_description("warm_start_cost").is_some());
+        assert!(get_metric_description("warm_start_time_to_first_byte").is_some());
+        assert!(get_metric_description("warm_start_stream_duration").is_some());
 
         // Test unknown metric type returns None
         assert!(get_metric_description("unknown_metric").is_none());
 
         // Test that bar chart includes description for known metric types
         let function_names = vec!["test_func".to_string()];
-        let stats = vec![(10.0, 15.0, 14.0, 12.0, 1.0)];
+        let stats = vec![vec![10.0, 12.0, 14.0, 15.0]];
 
         let bar_data = prepare_bar_chart_render_data(
             &function_names,
             &stats,
+            StatsOptions::default().labels(),
             "Cold Start - Init Duration",
             "ms",
             "cold_init",
@@ -2592,4 +3125,327 @@ mod tests {
             .unwrap()
             .contains("AWS Lambda spends initializing"));
     }
+
+    #[test]
+    fn test_architecture_pair_key() {
+        assert_eq!(architecture_pair_key("api-rust-arm64"), "api-rust");
+        assert_eq!(architecture_pair_key("api-rust-x86_64"), "api-rust");
+        assert_eq!(architecture_pair_key("Api_Rust_X86-64"), "api-rust");
+        assert_eq!(architecture_pair_key("arm64-api-rust"), "api-rust");
+        assert_eq!(architecture_pair_key("api-rust"), "api-rust");
+        assert_ne!(
+            architecture_pair_key("api-rust-arm64"),
+            architecture_pair_key("api-node-arm64")
+        );
+    }
+
+    #[test]
+    fn test_estimated_cost() {
+        let mut config = BenchmarkConfig {
+            function_name: "func_a".to_string(),
+            memory_size: 1024,
+            concurrent_invocations: 1,
+            number: 1,
+            timestamp: "".to_string(),
+            runtime: None,
+            architecture: Some("x86_64".to_string()),
+            environment: vec![],
+            sustained_load: None,
+            response_streaming: false,
+            variant: None,
+            warm_pacing: None,
+        };
+        assert_eq!(
+            resolve_price_per_gb_second(&config, None),
+            X86_64_PRICE_PER_GB_SECOND
+        );
+        assert_eq!(resolve_price_per_gb_second(&config, Some(0.00002)), 0.00002);
+        config.architecture = Some("arm64".to_string());
+        assert_eq!(
+            resolve_price_per_gb_second(&config, None),
+            ARM64_PRICE_PER_GB_SECOND
+        );
+
+        // 1M invocations of 1 second at 1 GB: 1M GB-seconds plus the request charge
+        let cost = cost_per_million_invocations(1000, 1024, 0.00001);
+        assert!((cost - 10.20).abs() < 1e-9);
+        let cost = cost_per_million_invocations(0, 128, 0.00001);
+        assert!((cost - PRICE_PER_MILLION_REQUESTS).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_prepare_histogram_render_data() {
+        let names = vec!["func_a".to_string(), "func_b".to_string()];
+        // A bimodal sample and a narrow one, bucketed over their shared range
+        let values = vec![
+            vec![10.0, 11.0, 10.5, 10.2, 30.0, 29.5, 30.0, 29.0, 10.8],
+            vec![20.0, 20.0, 20.0],
+        ];
+        let histogram = prepare_histogram_render_data(&names, &values, "Test", "ms", "cold_init");
+        // sqrt(9) = 3 buckets of 20/3 ms from 10 to 30
+        assert_eq!(histogram.bucket_bounds, vec![10.0, 16.667, 23.333, 30.0]);
+        assert_eq!(histogram.series[0].counts, vec![5, 0, 4]);
+        assert_eq!(histogram.series[1].counts, vec![0, 3, 0]);
+        assert_eq!(histogram.page_type, "cold_init_histogram");
+
+        let single = prepare_histogram_render_data(&names[..1], &[vec![5.0, 5.0]], "T", "ms", "x");
+        assert_eq!(single.bucket_bounds, vec![5.0, 5.0]);
+        assert_eq!(single.series[0].counts, vec![2]);
+
+        let empty = prepare_histogram_render_data(&names[..1], &[vec![]], "T", "ms", "x");
+        assert!(empty.bucket_bounds.is_empty());
+        assert!(empty.series[0].counts.is_empty());
+    }
+
+    #[test]
+    fn test_error_rates() {
+        let client_metrics = |duration: f64| ClientMetrics {
+            timestamp: "".to_string(),
+            client_duration: duration,
+            memory_size: 128,
+            time_to_first_byte: None,
+            stream_duration: None,
+            trace_id: None,
+        };
+        let error = |error_type: &str, throttled: bool, sampled: bool| InvocationError {
+            timestamp: "".to_string(),
+            phase: InvocationPhase::Client,
+            error_type: error_type.to_string(),
+            throttled,
+            sampled,
+        };
+        let report = BenchmarkReport {
+            schema_version: REPORT_SCHEMA_VERSION,
+            config: BenchmarkConfig {
+                function_name: "func_a".to_string(),
+                memory_size: 128,
+                concurrent_invocations: 1,
+                number: 4,
+                timestamp: "".to_string(),
+                runtime: None,
+                architecture: None,
+                environment: vec![],
+                sustained_load: None,
+                response_streaming: false,
+                variant: None,
+                warm_pacing: None,
+            },
+            cold_starts: vec![],
+            warm_starts: vec![],
+            // The failed invocation kept in the samples is the last one
+            client_measurements: vec![
+                client_metrics(10.0),
+                client_metrics(12.0),
+                client_metrics(90.0),
+            ],
+            errors: vec![
+                error("Unhandled", false, true),
+                error("TooManyRequestsException", true, false),
+            ],
+            environment_recycles: vec![],
+        };
+
+        // 2 of the 4 client invocations failed, and 1 of them was throttled
+        assert_eq!(error_rates(&report), vec![0.0, 0.0, 50.0, 25.0]);
+
+        let render_data = prepare_error_rate_render_data(&["func_a".to_string()], &[report]);
+        assert_eq!(render_data.unit, "%");
+        assert_eq!(render_data.y_axis_categories.len(), 4);
+        assert!(render_data.description.is_some());
+    }
+
+    #[test]
+    fn test_sample_trace_ids() {
+        let client_metrics = |trace_id: Option<&str>| ClientMetrics {
+            timestamp: "".to_string(),
+            client_duration: 10.0,
+            memory_size: 128,
+            time_to_first_byte: None,
+            stream_duration: None,
+            trace_id: trace_id.map(str::to_string),
+        };
+        let report = BenchmarkReport {
+            schema_version: REPORT_SCHEMA_VERSION,
+            config: BenchmarkConfig {
+                function_name: "func_a".to_string(),
+                memory_size: 128,
+                concurrent_invocations: 1,
+                number: 2,
+                timestamp: "".to_string(),
+                runtime: None,
+                architecture: None,
+                environment: vec![],
+                sustained_load: None,
+                response_streaming: false,
+                variant: None,
+                warm_pacing: None,
+            },
+            cold_starts: vec![],
+            warm_starts: vec![],
+            client_measurements: vec![
+                client_metrics(Some("4bf92f3577b34da6a3ce929d0e0e4736")),
+                client_metrics(None),
+            ],
+            errors: vec![],
+            environment_recycles: vec![],
+        };
+
+        assert_eq!(
+            sample_trace_ids(&report, "warm_start_client_duration"),
+            vec![Some("4bf92f3577b34da6a3ce929d0e0e4736"), None]
+        );
+        assert!(sample_trace_ids(&report, "warm_start_server_duration").is_empty());
+
+        let ChartRenderData::Combined { line, .. } = prepare_combined_chart_render_data(
+            &["func_a".to_string()],
+            std::slice::from_ref(&report),
+            &StatsOptions::default(),
+            "Warm Start - Client Duration",
+            "ms",
+            "warm_start_client_duration",
+            |r| {
+                r.client_measurements
+                    .iter()
+                    .map(|m| m.client_duration)
+                    .collect()
+            },
+        ) else {
+            panic!("Expected a combined chart");
+        };
+        assert_eq!(
+            line.series[0].points[0].trace_id.as_deref(),
+            Some("4bf92f3577b34da6a3ce929d0e0e4736")
+        );
+        assert_eq!(line.series[0].points[1].trace_id, None);
+    }
+
+    #[test]
+    fn test_prepare_extension_breakdown_render_data() {
+        let warm_start = |overhead: f64, overheads: &[(&str, f64)]| WarmStartMetrics {
+            timestamp: "".to_string(),
+            duration: 10.0,
+            extension_overhead: overhead,
+            billed_duration: 10,
+            max_memory_used: 64,
+            memory_size: 128,
+            response_latency_ms: None,
+            response_duration_ms: None,
+            runtime_overhead_ms: None,
+            produced_bytes: None,
+            runtime_done_metrics_duration_ms: None,
+            extension_overheads: overheads
+                .iter()
+                .map(|(name, value)| (name.to_string(), *value))
+                .collect(),
+            trace_id: None,
+        };
+        let report = |warm_starts: Vec<WarmStartMetrics>| BenchmarkReport {
+            schema_version: REPORT_SCHEMA_VERSION,
+            config: BenchmarkConfig {
+                function_name: "".to_string(),
+                memory_size: 128,
+                concurrent_invocations: 1,
+                number: 2,
+                timestamp: "".to_string(),
+                runtime: None,
+                architecture: None,
+                environment: vec![],
+                sustained_load: None,
+                response_streaming: false,
+                variant: None,
+                warm_pacing: None,
+            },
+            cold_starts: vec![],
+            warm_starts,
+            client_measurements: vec![],
+            errors: vec![],
+            environment_recycles: vec![],
+        };
+        let results = vec![
+            report(vec![
+                warm_start(6.0, &[("otel", 4.0), ("unattributed", 2.0)]),
+                warm_start(2.0, &[("otel", 2.0)]),
+            ]),
+            report(vec![warm_start(3.0, &[]), warm_start(5.0, &[])]),
+        ];
+
+        let render_data = prepare_extension_breakdown_render_data(
+            &["func_a".to_string(), "func_b".to_string()],
+            &results,
+        );
+        assert_eq!(render_data.y_axis_categories, vec!["otel", "unattributed"]);
+        assert_eq!(render_data.series[0].values, vec![3.0, 1.0]);
+        // Without breakdown, all of the overhead is unattributed
+        assert_eq!(render_data.series[1].values, vec![0.0, 4.0]);
+        assert!(render_data.description.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_generate_reports_for_directory_renders_streaming_charts() {
+        let report = |time_to_first_byte: Option<f64>| BenchmarkReport {
+            schema_version: REPORT_SCHEMA_VERSION,
+            config: BenchmarkConfig {
+                function_name: "func_a".to_string(),
+                memory_size: 128,
+                concurrent_invocations: 1,
+                number: 1,
+                timestamp: "".to_string(),
+                runtime: None,
+                architecture: None,
+                environment: vec![],
+                sustained_load: None,
+                response_streaming: time_to_first_byte.is_some(),
+                variant: None,
+                warm_pacing: None,
+            },
+            cold_starts: vec![],
+            warm_starts: vec![],
+            client_measurements: vec![ClientMetrics {
+                timestamp: "t1".to_string(),
+                client_duration: 10.0,
+                memory_size: 128,
+                time_to_first_byte,
+                stream_duration: time_to_first_byte.map(|ttfb| 10.0 - ttfb),
+                trace_id: None,
+            }],
+            errors: vec![],
+            environment_recycles: vec![],
+        };
+
+        for (time_to_first_byte, streamed) in [(Some(4.0), true), (None, false)] {
+            let input_dir = tempfile::tempdir().unwrap();
+            let output_dir = tempfile::tempdir().unwrap();
+            fs::write(
+                input_dir.path().join("func_a.json"),
+                serde_json::to_string(&report(time_to_first_byte)).unwrap(),
+            )
+            .unwrap();
+
+            generate_reports_for_directory(
+                input_dir.path().to_str().unwrap(),
+                output_dir.path().to_str().unwrap(),
+                None,
+                "html",
+                None,
+                &ProgressBar::hidden(),
+                &ReportStructure::new(),
+                "group",
+                "subgroup",
+                None,
+                None,
+                false,
+                None,
+                &StatsOptions::default(),
+                None,
+            )
+            .await
+            .unwrap();
+
+            let chart_exists =
+                |name: &str| output_dir.path().join(name).join("index.html").exists();
+            assert!(chart_exists("warm-start-client-duration"));
+            assert_eq!(chart_exists("warm-start-time-to-first-byte"), streamed);
+            assert_eq!(chart_exists("warm-start-stream-duration"), streamed);
+        }
+    }
 }
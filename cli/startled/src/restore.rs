@@ -0,0 +1,277 @@
+//! Tracking and restoration of function configuration mutated by a benchmark.
+//!
+//! Before startled changes a function's configuration (memory size, environment, logging), the
+//! original settings are written to a state file and held by a [`ConfigRestoreGuard`]. The guard
+//! restores them when the benchmark finishes, fails or is interrupted with Ctrl-C, and also when
+//! it is dropped without having restored (for example while unwinding from a panic).
+//!
+//! Entries are removed from the state file once restored, and the file is deleted when it no
+//! longer holds any entry. If startled is killed before it can restore, the file remains and
+//! `startled restore --from-state <FILE>` puts every recorded function back.
+
+use crate::lambda::{self, OriginalConfig};
+use anyhow::{anyhow, Context, Result};
+use aws_sdk_lambda::Client as LambdaClient;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Default location of the restore state file
+pub const DEFAULT_STATE_FILE: &str = ".startled-restore.json";
+
+// Serializes read-modify-write cycles on the state file across parallel benchmarks
+static STATE_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Original configurations of the functions currently mutated, keyed by function name
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RestoreState {
+    pub functions: BTreeMap<String, OriginalConfig>,
+}
+
+impl RestoreState {
+    /// Loads the state file, returning an empty state if it does not exist
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read restore state file {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid restore state file {}", path.display()))
+    }
+
+    /// Writes the state file, or removes it when no function is left to restore
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if self.functions.is_empty() {
+            if path.exists() {
+                fs::remove_file(path).with_context(|| {
+                    format!("Failed to remove restore state file {}", path.display())
+                })?;
+            }
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // The state holds environment variables, so keep it private to the user
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options
+            .open(path)
+            .with_context(|| format!("Failed to write restore state file {}", path.display()))?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Records the original configuration of a function in the state file.
+///
+/// If the function is already recorded (a previous run did not restore it), the recorded
+/// configuration is kept, since the current one is the mutated one. Returns the configuration
+/// to restore.
+pub fn record_original(
+    path: &Path,
+    function_name: &str,
+    current: OriginalConfig,
+) -> Result<OriginalConfig> {
+    let _lock = STATE_FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut state = RestoreState::load(path)?;
+    let original = state
+        .functions
+        .entry(function_name.to_string())
+        .or_insert(current)
+        .clone();
+    state.save(path)?;
+    Ok(original)
+}
+
+/// Removes a restored function from the state file
+pub fn clear_original(path: &Path, function_name: &str) -> Result<()> {
+    let _lock = STATE_FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut state = RestoreState::load(path)?;
+    if state.functions.remove(function_name).is_some() {
+        state.save(path)?;
+    }
+    Ok(())
+}
+
+fn recovery_hint(state_file: &Path) -> String {
+    format!(
+        "Run `startled restore --from-state {}` to restore it.",
+        state_file.display()
+    )
+}
+
+/// Guarantees that a function's original configuration is restored
+pub struct ConfigRestoreGuard {
+    client: LambdaClient,
+    function_name: String,
+    original: OriginalConfig,
+    state_file: PathBuf,
+    quiet_mode: bool,
+    restored: bool,
+}
+
+impl ConfigRestoreGuard {
+    /// Captures the current configuration of the function and records it in the state file.
+    /// Must be called before the configuration is mutated.
+    pub async fn capture(
+        client: &LambdaClient,
+        function_name: &str,
+        state_file: &Path,
+        quiet_mode: bool,
+    ) -> Result<Self> {
+        let current = lambda::get_function_config(client, function_name).await?;
+        let original = record_original(state_file, function_name, current)?;
+        Ok(Self {
+            client: client.clone(),
+            function_name: function_name.to_string(),
+            original,
+            state_file: state_file.to_path_buf(),
+            quiet_mode,
+            restored: false,
+        })
+    }
+
+    /// Restores the original configuration and removes it from the state file
+    pub async fn restore(mut self) -> Result<()> {
+        // Whatever the outcome, the drop handler must not try again
+        self.restored = true;
+        lambda::restore_function_config(
+            &self.client,
+            &self.function_name,
+            &self.original,
+            self.quiet_mode,
+        )
+        .await
+        .with_context(|| recovery_hint(&self.state_file))?;
+        clear_original(&self.state_file, &self.function_name)
+    }
+}
+
+impl Drop for ConfigRestoreGuard {
+    fn drop(&mut self) {
+        if self.restored {
+            return;
+        }
+        eprintln!(
+            "\nRestoring configuration of {} before exiting...",
+            self.function_name
+        );
+
+        // Drop cannot be async: block on the restore when the runtime allows it
+        let restored = match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| {
+                    handle.block_on(lambda::restore_function_config(
+                        &self.client,
+                        &self.function_name,
+                        &self.original,
+                        true,
+                    ))
+                })
+                .and_then(|_| clear_original(&self.state_file, &self.function_name))
+            }
+            _ => Err(anyhow!("no multi-threaded runtime available")),
+        };
+
+        match restored {
+            Ok(()) => eprintln!("Function {} configuration restored.", self.function_name),
+            Err(e) => eprintln!(
+                "Warning: Failed to restore function configuration for {}: {}. {}",
+                self.function_name,
+                e,
+                recovery_hint(&self.state_file)
+            ),
+        }
+    }
+}
+
+/// Restores every function recorded in the state file (`startled restore --from-state`)
+pub async fn restore_from_state(client: &LambdaClient, state_file: &Path) -> Result<()> {
+    let state = RestoreState::load(state_file)?;
+    if state.functions.is_empty() {
+        println!("No pending restorations found in {}.", state_file.display());
+        return Ok(());
+    }
+
+    let mut failures = 0;
+    for (function_name, original) in &state.functions {
+        match lambda::restore_function_config(client, function_name, original, false).await {
+            Ok(()) => clear_original(state_file, function_name)?,
+            Err(e) => {
+                eprintln!("  ❌ {}: {}", function_name, e);
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        return Err(anyhow!(
+            "{} function(s) could not be restored; they remain in {}",
+            failures,
+            state_file.display()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn config(memory_size: i32) -> OriginalConfig {
+        OriginalConfig {
+            memory_size,
+            environment: vec![("KEY".to_string(), "value".to_string())],
+            log_format: Some("Text".to_string()),
+            application_log_level: None,
+            system_log_level: None,
+            layers: vec![],
+        }
+    }
+
+    #[test]
+    fn test_record_and_clear_original() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state").join(DEFAULT_STATE_FILE);
+
+        let original = record_original(&path, "fn-a", config(128)).unwrap();
+        assert_eq!(original.memory_size, 128);
+        record_original(&path, "fn-b", config(256)).unwrap();
+
+        let state = RestoreState::load(&path).unwrap();
+        assert_eq!(state.functions.len(), 2);
+        assert_eq!(state.functions["fn-a"], config(128));
+
+        clear_original(&path, "fn-a").unwrap();
+        assert!(path.exists());
+        clear_original(&path, "fn-b").unwrap();
+        assert!(!path.exists(), "state file should be removed once empty");
+    }
+
+    #[test]
+    fn test_record_original_keeps_unrestored_entry() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(DEFAULT_STATE_FILE);
+
+        record_original(&path, "fn-a", config(128)).unwrap();
+        // A later run sees the mutated configuration, but must restore the first one
+        let original = record_original(&path, "fn-a", config(1024)).unwrap();
+        assert_eq!(original.memory_size, 128);
+    }
+
+    #[test]
+    fn test_load_missing_state_is_empty() {
+        let dir = tempdir().unwrap();
+        let state = RestoreState::load(&dir.path().join("missing.json")).unwrap();
+        assert!(state.functions.is_empty());
+    }
+}
@@ -0,0 +1,336 @@
+//! JSON schema of the benchmark results (`startled validate`).
+//!
+//! Each result file written by the `function` and `stack` commands is a [`BenchmarkReport`]
+//! carrying the version of the schema it follows in `schema_version`. The schema is embedded in
+//! the binary, so tools consuming the results (dashboards, diff tooling) can rely on a stable
+//! contract, and `startled validate <DIR>` checks the result files of a directory against it.
+//!
+//! Fields may be added within a version; removing or changing a field bumps the version. Only
+//! the keywords the embedded schema uses are supported by the validator: `$ref` to a local
+//! definition, `type`, `required`, `properties`, `additionalProperties`, `items`, `enum`,
+//! `const`, `minimum` and `pattern`.
+
+use crate::types::BenchmarkReport;
+use anyhow::{bail, Context, Result};
+use colored::*;
+use regex::Regex;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Version of the schema of the result files written by this version of startled
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// JSON schema of the result files, in the JSON Schema 2020-12 format
+pub const REPORT_SCHEMA: &str = include_str!("schema/benchmark-report.v1.json");
+
+/// Returns the version of the schema written to new result files
+pub fn report_schema_version() -> u32 {
+    REPORT_SCHEMA_VERSION
+}
+
+/// Validates the content of a result file, returning the problems found
+pub fn validate_report(content: &str) -> Vec<String> {
+    let value: Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(err) => {
+            return vec![format!(
+                "not valid JSON (line {}, column {})",
+                err.line(),
+                err.column()
+            )]
+        }
+    };
+    // Results written before the schema was versioned follow the first version
+    if let Some(version) = value.get("schema_version").and_then(Value::as_u64) {
+        if version > REPORT_SCHEMA_VERSION as u64 {
+            return vec![format!(
+                "schema version {} is newer than the version {} supported by this startled",
+                version, REPORT_SCHEMA_VERSION
+            )];
+        }
+    }
+
+    let schema: Value = serde_json::from_str(REPORT_SCHEMA).expect("embedded schema is valid");
+    let mut problems = Vec::new();
+    validate_value(&schema, &schema, &value, "", &mut problems);
+    if problems.is_empty() {
+        if let Err(err) = serde_json::from_str::<BenchmarkReport>(content) {
+            problems.push(format!(
+                "cannot be read as a benchmark report (line {}, column {})",
+                err.line(),
+                err.column()
+            ));
+        }
+    }
+    problems
+}
+
+/// Validates the result files of a directory and its subdirectories against the schema,
+/// failing if any does not match it
+pub fn validate_directory(directory: &Path) -> Result<()> {
+    let mut files = Vec::new();
+    collect_json_files(directory, &mut files)?;
+    files.sort();
+    if files.is_empty() {
+        bail!("No result files found in {}", directory.display());
+    }
+
+    println!(
+        "\n{} {} result file(s) against schema version {}",
+        "Validating".bright_blue().bold(),
+        files.len(),
+        REPORT_SCHEMA_VERSION
+    );
+    let mut invalid = 0;
+    for path in &files {
+        let name = path.strip_prefix(directory).unwrap_or(path).display();
+        let content =
+            fs::read_to_string(path).with_context(|| format!("Failed to read {}", name))?;
+        let problems = validate_report(&content);
+        if problems.is_empty() {
+            println!("  {} {}", "✓".green(), name);
+        } else {
+            invalid += 1;
+            println!("  {} {}", "✗".red(), name);
+            for problem in problems {
+                println!("      {}", problem);
+            }
+        }
+    }
+    if invalid > 0 {
+        bail!(
+            "{} of {} result file(s) do not match the schema",
+            invalid,
+            files.len()
+        );
+    }
+    println!("\n{}", "✓ All result files match the schema".green());
+    Ok(())
+}
+
+fn collect_json_files(directory: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(directory)
+        .with_context(|| format!("Failed to read directory {}", directory.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_json_files(&path, files)?;
+        } else if path.extension().and_then(|s| s.to_str()) == Some("json") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Checks a value against a schema, adding the problems found at the JSON pointer `path`
+fn validate_value(
+    root: &Value,
+    schema: &Value,
+    value: &Value,
+    path: &str,
+    problems: &mut Vec<String>,
+) {
+    let location = if path.is_empty() { "/" } else { path };
+
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+        {
+            Some(definition) => validate_value(root, definition, value, path, problems),
+            None => problems.push(format!("{}: unresolved schema reference", location)),
+        }
+        return;
+    }
+
+    if let Some(types) = schema.get("type") {
+        let matches = match types {
+            Value::String(name) => type_matches(name, value),
+            Value::Array(names) => names
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|name| type_matches(name, value)),
+            _ => true,
+        };
+        if !matches {
+            problems.push(format!("{}: expected {}", location, types));
+            return;
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            problems.push(format!("{}: must be {}", location, expected));
+        }
+    }
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            problems.push(format!(
+                "{}: must be one of {}",
+                location,
+                Value::Array(allowed.clone())
+            ));
+        }
+    }
+    if let (Some(minimum), Some(number)) = (
+        schema.get("minimum").and_then(Value::as_f64),
+        value.as_f64(),
+    ) {
+        if number < minimum {
+            problems.push(format!("{}: must be at least {}", location, minimum));
+        }
+    }
+    if let (Some(pattern), Some(text)) = (
+        schema.get("pattern").and_then(Value::as_str),
+        value.as_str(),
+    ) {
+        if !Regex::new(pattern).is_ok_and(|regex| regex.is_match(text)) {
+            problems.push(format!("{}: does not match {}", location, pattern));
+        }
+    }
+
+    if let Value::Object(object) = value {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for property in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(property) {
+                    problems.push(format!("{}: missing property {}", location, property));
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (key, item) in object {
+            let item_path = format!("{}/{}", path, key);
+            match properties.and_then(|properties| properties.get(key)) {
+                Some(item_schema) => validate_value(root, item_schema, item, &item_path, problems),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        problems.push(format!("{}: unexpected property", item_path))
+                    }
+                    Some(item_schema @ Value::Object(_)) => {
+                        validate_value(root, item_schema, item, &item_path, problems)
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (index, item) in items.iter().enumerate() {
+            let item_path = format!("{}/{}", path, index);
+            validate_value(root, item_schema, item, &item_path, problems);
+        }
+    }
+}
+
+fn type_matches(name: &str, value: &Value) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        BenchmarkConfig, ClientMetrics, InvocationError, InvocationPhase, WarmPacing,
+        WarmStartMetrics,
+    };
+
+    fn report() -> BenchmarkReport {
+        BenchmarkReport {
+            schema_version: REPORT_SCHEMA_VERSION,
+            config: BenchmarkConfig {
+                function_name: "fn-a".to_string(),
+                memory_size: 512,
+                concurrent_invocations: 2,
+                number: 1,
+                timestamp: "2025-01-01T00:00:00Z".to_string(),
+                runtime: Some("provided.al2023".to_string()),
+                architecture: Some("arm64".to_string()),
+                environment: vec![],
+                sustained_load: None,
+                response_streaming: true,
+                variant: Some("gzip".to_string()),
+                warm_pacing: Some(WarmPacing {
+                    interval_secs: Some(30),
+                    environments: None,
+                }),
+            },
+            cold_starts: vec![],
+            warm_starts: vec![WarmStartMetrics {
+                timestamp: "2025-01-01T00:00:01Z".to_string(),
+                duration: 12.5,
+                extension_overhead: 1.0,
+                billed_duration: 13,
+                max_memory_used: 64,
+                memory_size: 512,
+                response_latency_ms: None,
+                response_duration_ms: Some(0.2),
+                runtime_overhead_ms: None,
+                produced_bytes: Some(42),
+                runtime_done_metrics_duration_ms: None,
+                extension_overheads: [("collector".to_string(), 1.0)].into(),
+                trace_id: Some("0af7651916cd43dd8448eb211c80319c".to_string()),
+            }],
+            client_measurements: vec![ClientMetrics {
+                timestamp: "2025-01-01T00:00:02Z".to_string(),
+                client_duration: 20.0,
+                memory_size: 512,
+                time_to_first_byte: Some(5.0),
+                stream_duration: Some(15.0),
+                trace_id: None,
+            }],
+            errors: vec![InvocationError {
+                timestamp: "2025-01-01T00:00:03Z".to_string(),
+                phase: InvocationPhase::Client,
+                error_type: "Unhandled".to_string(),
+                throttled: false,
+                sampled: true,
+            }],
+            environment_recycles: vec![],
+        }
+    }
+
+    #[test]
+    fn test_validate_report() {
+        let content = serde_json::to_string(&report()).unwrap();
+        assert_eq!(validate_report(&content), Vec::<String>::new());
+
+        // Results written before the schema was versioned are still valid
+        let mut value: Value = serde_json::from_str(&content).unwrap();
+        value.as_object_mut().unwrap().remove("schema_version");
+        assert!(validate_report(&value.to_string()).is_empty());
+
+        value["warm_starts"][0]["duration"] = Value::from("fast");
+        value["warm_starts"][0]["trace_id"] = Value::from("not-a-trace");
+        value["errors"][0]["phase"] = Value::from("init");
+        value["config"]
+            .as_object_mut()
+            .unwrap()
+            .remove("memory_size");
+        let problems = validate_report(&value.to_string());
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+        assert!(problems.contains(&"/config: missing property memory_size".to_string()));
+        assert!(problems.contains(&"/warm_starts/0/duration: expected \"number\"".to_string()));
+        assert!(problems
+            .iter()
+            .any(|p| p.starts_with("/warm_starts/0/trace_id: does not match")));
+        assert!(problems
+            .iter()
+            .any(|p| p.starts_with("/errors/0/phase: must be one of")));
+
+        value = serde_json::from_str(&content).unwrap();
+        value["schema_version"] = Value::from(2);
+        assert!(validate_report(&value.to_string())[0].contains("newer than"));
+        assert!(validate_report("{")[0].starts_with("not valid JSON"));
+    }
+}
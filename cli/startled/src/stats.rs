@@ -1,3 +1,5 @@
+use anyhow::{bail, Result};
+use statrs::distribution::{ContinuousCDF, Normal, StudentsT};
 use statrs::statistics::{Data, Distribution, OrderStatistics};
 
 pub struct MetricsStats {
@@ -45,6 +47,93 @@ pub fn calculate_stats(values: &[f64]) -> MetricsStats {
     }
 }
 
+/// Statistics of the report charts, from `--percentiles`, `--trim` and `--stddev`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsOptions {
+    /// Percentiles shown after the mean, between 0 and 100 (e.g. 99.9)
+    pub percentiles: Vec<f64>,
+    /// Percentage of the lowest and of the highest values left out of the mean and standard deviation
+    pub trim_percent: f64,
+    /// Shows the standard deviation after the percentiles
+    pub show_std_dev: bool,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            percentiles: vec![50.0, 95.0, 99.0],
+            trim_percent: 0.0,
+            show_std_dev: false,
+        }
+    }
+}
+
+impl StatsOptions {
+    pub fn new(percentiles: Vec<f64>, trim_percent: f64, show_std_dev: bool) -> Result<Self> {
+        if percentiles.is_empty() {
+            bail!("At least one percentile is required");
+        }
+        if let Some(p) = percentiles.iter().find(|p| !(**p > 0.0 && **p <= 100.0)) {
+            bail!(
+                "Invalid percentile {}: must be greater than 0 and at most 100",
+                p
+            );
+        }
+        if !(0.0..50.0).contains(&trim_percent) {
+            bail!(
+                "Invalid trim percentage {}: must be at least 0 and less than 50",
+                trim_percent
+            );
+        }
+        Ok(Self {
+            percentiles,
+            trim_percent,
+            show_std_dev,
+        })
+    }
+
+    /// Returns the names of the statistics, e.g. ["AVG", "P50", "P99.9"]
+    pub fn labels(&self) -> Vec<String> {
+        let trimmed = |label: &str| {
+            if self.trim_percent > 0.0 {
+                format!("{} (TRIM {}%)", label, self.trim_percent)
+            } else {
+                label.to_string()
+            }
+        };
+        std::iter::once(trimmed("AVG"))
+            .chain(self.percentiles.iter().map(|p| format!("P{}", p)))
+            .chain(self.show_std_dev.then(|| trimmed("STDDEV")))
+            .collect()
+    }
+
+    /// Returns the statistics of the values, in the order of the labels
+    pub fn summarize(&self, values: &[f64]) -> Vec<f64> {
+        if values.is_empty() {
+            return vec![0.0; self.labels().len()];
+        }
+        let mut data = Data::new(values.to_vec());
+        let percentiles = self.percentiles.iter().map(|p| data.quantile(p / 100.0));
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        // Less than half of the values are trimmed from each end, so at least one is left
+        let trimmed_count = (sorted.len() as f64 * self.trim_percent / 100.0).floor() as usize;
+        let trimmed = Data::new(sorted[trimmed_count..sorted.len() - trimmed_count].to_vec());
+        let mean = trimmed.mean().unwrap_or(f64::NAN);
+        let std_dev = if trimmed.len() < 2 {
+            0.0
+        } else {
+            trimmed.std_dev().unwrap_or(f64::NAN)
+        };
+
+        std::iter::once(mean)
+            .chain(percentiles)
+            .chain(self.show_std_dev.then_some(std_dev))
+            .collect()
+    }
+}
+
 /// Calculate statistics for cold start init duration
 pub fn calculate_cold_start_init_stats(
     cold_starts: &[crate::types::ColdStartMetrics],
@@ -289,6 +378,76 @@ pub fn calculate_warm_start_runtime_done_metrics_duration_stats(
     Some((stats.mean, stats.p99, stats.p95, stats.p50, stats.std_dev))
 }
 
+/// Two-sided p-value of Welch's t-test for a difference between the means of two samples.
+///
+/// Returns None when a sample has fewer than two values. Samples without variance are
+/// identical (p = 1) or certainly different (p = 0).
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let (a, b) = (Data::new(a.to_vec()), Data::new(b.to_vec()));
+    let (mean_a, mean_b) = (a.mean()?, b.mean()?);
+    let (se_a, se_b) = (a.variance()? / na, b.variance()? / nb);
+    let se = se_a + se_b;
+    if se == 0.0 {
+        return Some(if mean_a == mean_b { 1.0 } else { 0.0 });
+    }
+
+    let t = (mean_b - mean_a) / se.sqrt();
+    // Welch–Satterthwaite degrees of freedom
+    let freedom = se.powi(2) / (se_a.powi(2) / (na - 1.0) + se_b.powi(2) / (nb - 1.0));
+    let dist = StudentsT::new(0.0, 1.0, freedom).ok()?;
+    Some((2.0 * (1.0 - dist.cdf(t.abs()))).clamp(0.0, 1.0))
+}
+
+/// Two-sided p-value of the Mann-Whitney U test for a shift between the distributions of two
+/// samples.
+///
+/// Uses the normal approximation with tie and continuity corrections, so it does not assume
+/// normally distributed values. Returns None when a sample is empty.
+pub fn mann_whitney_u_test(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let n = na + nb;
+
+    // Rank the pooled values, giving tied values the average of their ranks
+    let mut pooled: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&v| (v, true))
+        .chain(b.iter().map(|&v| (v, false)))
+        .collect();
+    pooled.sort_by(|x, y| x.0.total_cmp(&y.0));
+    let mut rank_sum_a = 0.0;
+    let mut tie_term = 0.0;
+    let mut i = 0;
+    while i < pooled.len() {
+        let j = i + pooled[i..]
+            .iter()
+            .take_while(|v| v.0 == pooled[i].0)
+            .count();
+        let rank = (i + 1 + j) as f64 / 2.0;
+        rank_sum_a += rank * pooled[i..j].iter().filter(|v| v.1).count() as f64;
+        let ties = (j - i) as f64;
+        tie_term += ties.powi(3) - ties;
+        i = j;
+    }
+
+    let u = rank_sum_a - na * (na + 1.0) / 2.0;
+    let mean_u = na * nb / 2.0;
+    let variance = na * nb / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        // Every value is tied
+        return Some(1.0);
+    }
+    let z = ((u - mean_u).abs() - 0.5).max(0.0) / variance.sqrt();
+    let normal = Normal::new(0.0, 1.0).ok()?;
+    Some((2.0 * (1.0 - normal.cdf(z))).clamp(0.0, 1.0))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -396,6 +555,39 @@ mod tests {
         assert_metrics_stats_eq(&stats, &expected, "multiple_values");
     }
 
+    #[test]
+    fn test_stats_options() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 100.0]; // N=11
+
+        // The default statistics match calculate_stats
+        let default_options = StatsOptions::default();
+        let stats = calculate_stats(&values);
+        assert_eq!(default_options.labels(), ["AVG", "P50", "P95", "P99"]);
+        assert_eq!(
+            default_options.summarize(&values),
+            vec![stats.mean, stats.p50, stats.p95, stats.p99]
+        );
+
+        // Trimming 10% of 11 values leaves out the lowest and the highest one
+        let options = StatsOptions::new(vec![50.0, 99.9], 10.0, true).unwrap();
+        assert_eq!(
+            options.labels(),
+            ["AVG (TRIM 10%)", "P50", "P99.9", "STDDEV (TRIM 10%)"]
+        );
+        let summary = options.summarize(&values);
+        let trimmed = statrs::statistics::Data::new(values[1..10].to_vec());
+        assert_f64_eq(summary[0], 6.0, "trimmed mean");
+        assert_f64_eq(summary[1], stats.p50, "p50");
+        assert_f64_eq(summary[2], 100.0, "p99.9");
+        assert_f64_eq(summary[3], trimmed.std_dev().unwrap(), "trimmed std_dev");
+        assert_eq!(options.summarize(&[]), vec![0.0; 4]);
+
+        assert!(StatsOptions::new(vec![], 0.0, false).is_err());
+        assert!(StatsOptions::new(vec![0.0], 0.0, false).is_err());
+        assert!(StatsOptions::new(vec![100.5], 0.0, false).is_err());
+        assert!(StatsOptions::new(vec![50.0], 50.0, false).is_err());
+    }
+
     #[test]
     fn test_calculate_cold_start_init_stats_empty() {
         let cold_starts: [ColdStartMetrics; 0] = [];
@@ -420,6 +612,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
             ColdStartMetrics {
                 timestamp: "ts2".to_string(),
@@ -435,6 +629,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
         ];
         let result = calculate_cold_start_init_stats(&cold_starts);
@@ -468,6 +664,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
             ColdStartMetrics {
                 timestamp: "ts2".to_string(),
@@ -483,6 +681,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
         ];
         let result = calculate_cold_start_server_stats(&cold_starts);
@@ -518,6 +718,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
             WarmStartMetrics {
                 timestamp: "ts2".to_string(),
@@ -531,6 +733,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
         ];
         let result = calculate_warm_start_stats(&warm_starts, get_warm_duration);
@@ -554,11 +758,17 @@ mod tests {
                 timestamp: "ts1".to_string(),
                 client_duration: 30.0,
                 memory_size: 256,
+                time_to_first_byte: None,
+                stream_duration: None,
+                trace_id: None,
             },
             ClientMetrics {
                 timestamp: "ts2".to_string(),
                 client_duration: 35.0,
                 memory_size: 256,
+                time_to_first_byte: None,
+                stream_duration: None,
+                trace_id: None,
             },
         ];
         let result = calculate_client_stats(&client_metrics);
@@ -590,6 +800,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
             WarmStartMetrics {
                 timestamp: "ts2".to_string(),
@@ -603,6 +815,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
         ];
         let result = calculate_memory_stats(&warm_starts);
@@ -636,6 +850,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
             ColdStartMetrics {
                 timestamp: "ts2".to_string(),
@@ -651,6 +867,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
         ];
         let result = calculate_cold_start_extension_overhead_stats(&cold_starts);
@@ -684,6 +902,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
             ColdStartMetrics {
                 timestamp: "ts2".to_string(),
@@ -699,6 +919,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
         ];
         let result = calculate_cold_start_total_duration_stats(&cold_starts);
@@ -722,6 +944,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
             ColdStartMetrics {
                 timestamp: "ts2".to_string(),
@@ -737,6 +961,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             }, // One None
             ColdStartMetrics {
                 timestamp: "ts3".to_string(),
@@ -752,6 +978,8 @@ mod tests {
                 runtime_overhead_ms: None,
                 produced_bytes: None,
                 runtime_done_metrics_duration_ms: None,
+                extension_overheads: Default::default(),
+                trace_id: None,
             },
         ];
         let result = calculate_cold_start_total_duration_stats(&cold_starts);
@@ -760,4 +988,24 @@ mod tests {
         let expected = Some((stats.mean, stats.p99, stats.p95, stats.p50, stats.std_dev));
         assert_option_tuple_eq(result, expected, "cs_total_dur_happy");
     }
+
+    #[test]
+    fn test_significance_tests() {
+        let a = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let b = [6.0, 7.0, 8.0, 9.0, 10.0];
+
+        // t = 5 with 8 degrees of freedom
+        let p = welch_t_test(&a, &b).unwrap();
+        assert!((p - 0.001053).abs() < 1e-5, "welch p = {}", p);
+        assert!(welch_t_test(&a, &a).unwrap() > 0.99);
+        assert_eq!(welch_t_test(&[1.0, 1.0], &[2.0, 2.0]), Some(0.0));
+        assert_eq!(welch_t_test(&[1.0], &b), None);
+
+        // U = 0, z = 12 / sqrt(275 / 12)
+        let p = mann_whitney_u_test(&a, &b).unwrap();
+        assert!((p - 0.012186).abs() < 1e-5, "mann-whitney p = {}", p);
+        assert_eq!(mann_whitney_u_test(&a, &a), Some(1.0));
+        assert_eq!(mann_whitney_u_test(&[3.0, 3.0], &[3.0]), Some(1.0));
+        assert_eq!(mann_whitney_u_test(&[], &b), None);
+    }
 }
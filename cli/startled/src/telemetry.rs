@@ -5,9 +5,10 @@ use opentelemetry::propagation::TextMapCompositePropagator;
 use opentelemetry::trace::TracerProvider;
 use opentelemetry_aws::trace::{XrayIdGenerator, XrayPropagator};
 use opentelemetry_otlp::{Protocol, WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::metrics::SdkMeterProvider;
 use opentelemetry_sdk::propagation::TraceContextPropagator;
 use opentelemetry_sdk::trace::SdkTracerProvider;
-use otlp_sigv4_client::SigV4ClientBuilder;
+use otlp_sigv4_client::{SigV4Client, SigV4ClientBuilder};
 use tracing_opentelemetry::OpenTelemetryLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
@@ -32,15 +33,40 @@ pub fn init_tracing() {
     }
 }
 
+/// The OpenTelemetry providers of the benchmark commands
+pub struct Telemetry {
+    tracer_provider: SdkTracerProvider,
+    meter_provider: Option<SdkMeterProvider>,
+}
+
+impl Telemetry {
+    /// Exports the pending spans and metrics
+    pub fn flush(&self) {
+        if let Err(e) = self.tracer_provider.force_flush() {
+            tracing::error!("Failed to flush spans: {}", e);
+        }
+        if let Some(meter_provider) = &self.meter_provider {
+            if let Err(e) = meter_provider.force_flush() {
+                tracing::error!("Failed to flush metrics: {}", e);
+            }
+        }
+    }
+}
+
 /// Initialize OpenTelemetry with configuration from environment variables
 ///
+/// Each invocation is exported as a span, and the results of each benchmark run as metrics
+/// (see `metrics.rs`).
+///
 /// Environment variables:
 /// - OTEL_EXPORTER_OTLP_ENDPOINT: The OTLP endpoint URL (default: AWS App Signals endpoint)
+/// - OTEL_EXPORTER_OTLP_TRACES_ENDPOINT, OTEL_EXPORTER_OTLP_METRICS_ENDPOINT: Per-signal endpoint URLs
 /// - OTEL_SERVICE_NAME: Service name for telemetry (default: "lambda-benchmark")
 /// - OTEL_EXPORTER_OTLP_PROTOCOL: Protocol to use (http/protobuf or http/json)
+/// - OTEL_METRICS_EXPORTER: Set to "none" to disable the export of the metrics
 /// - AWS_REGION: AWS region for signing requests
 /// - RUST_LOG: Log level (e.g. "info" to see telemetry data)
-pub async fn init_telemetry() -> Result<SdkTracerProvider> {
+pub async fn init_telemetry() -> Result<Telemetry> {
     let config = aws_config::load_from_env().await;
     let region = config.region().expect("AWS region is required").to_string();
 
@@ -50,29 +76,22 @@ pub async fn init_telemetry() -> Result<SdkTracerProvider> {
         .provide_credentials()
         .await?;
 
+    // This is a blocking call, so we need to spawn a thread to run it, and is required since otel 0.28.0
+    let reqwest_client = std::thread::spawn(move || {
+        reqwest::blocking::Client::builder()
+            .build()
+            .expect("Failed to build HTTP client")
+    })
+    .join()
+    .expect("Failed to join HTTP client thread");
+
     // Build HTTP client with AWS SigV4 signing
-    let http_client = SigV4ClientBuilder::new()
-        .with_client(
-            // This is a blocking call, so we need to spawn a thread to run it, and is required since otel 0.28.0
-            std::thread::spawn(move || {
-                reqwest::blocking::Client::builder()
-                    .build()
-                    .expect("Failed to build HTTP client")
-            })
-            .join()
-            .expect("Failed to join HTTP client thread"),
-        )
-        .with_credentials(credentials)
-        .with_region(region)
-        .with_service("xray") // For AWS App Signals
-        .with_signing_predicate(Box::new(|request| {
-            // Only sign requests to AWS endpoints
-            request
-                .uri()
-                .host()
-                .is_some_and(|host| host.ends_with(".amazonaws.com"))
-        }))
-        .build()?;
+    let http_client = signing_client(
+        reqwest_client.clone(),
+        credentials.clone(),
+        &region,
+        "xray", // For AWS App Signals
+    )?;
 
     let exporter = opentelemetry_otlp::SpanExporter::builder()
         .with_http()
@@ -81,6 +100,29 @@ pub async fn init_telemetry() -> Result<SdkTracerProvider> {
         .with_timeout(std::time::Duration::from_secs(3))
         .build()?;
 
+    let metrics_disabled = std::env::var("OTEL_METRICS_EXPORTER")
+        .is_ok_and(|value| value.eq_ignore_ascii_case("none"));
+    let meter_provider = if metrics_disabled {
+        None
+    } else {
+        let metric_exporter = opentelemetry_otlp::MetricExporter::builder()
+            .with_http()
+            .with_http_client(signing_client(
+                reqwest_client,
+                credentials,
+                &region,
+                "monitoring", // For the CloudWatch OTLP endpoint
+            )?)
+            .with_protocol(Protocol::HttpBinary)
+            .with_timeout(std::time::Duration::from_secs(3))
+            .build()?;
+        let meter_provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(metric_exporter)
+            .build();
+        global::set_meter_provider(meter_provider.clone());
+        Some(meter_provider)
+    };
+
     let tracer_provider = SdkTracerProvider::builder()
         .with_id_generator(XrayIdGenerator::default())
         .with_batch_exporter(exporter)
@@ -120,5 +162,29 @@ pub async fn init_telemetry() -> Result<SdkTracerProvider> {
     global::set_text_map_propagator(composite_propagator);
 
     // Initialize the OpenTelemetry subscriber
-    Ok(tracer_provider)
+    Ok(Telemetry {
+        tracer_provider,
+        meter_provider,
+    })
+}
+
+fn signing_client(
+    client: reqwest::blocking::Client,
+    credentials: aws_credential_types::Credentials,
+    region: &str,
+    service: &str,
+) -> Result<SigV4Client<reqwest::blocking::Client>> {
+    Ok(SigV4ClientBuilder::new()
+        .with_client(client)
+        .with_credentials(credentials)
+        .with_region(region)
+        .with_service(service)
+        .with_signing_predicate(Box::new(|request| {
+            // Only sign requests to AWS endpoints
+            request
+                .uri()
+                .host()
+                .is_some_and(|host| host.ends_with(".amazonaws.com"))
+        }))
+        .build()?)
 }
@@ -7,7 +7,9 @@
  * 3. Scatter chart generation for time-series data like client duration over time
  * 4. Memory scaling charts for performance across different memory configurations
  * 5. Summary charts for overview pages with multiple metrics
- * 6. Series highlighting and interactive tooltip functionality
+ * 6. Cost vs performance charts with the architecture comparison
+ * 7. Histograms of the distribution of each metric
+ * 8. Series highlighting and interactive tooltip functionality
  * 
  * Users can customize this file to change the appearance and behavior of the reports.
  * When providing a custom template directory with --template-dir, place a modified
@@ -31,6 +33,12 @@ window.DEFAULT_COLOR_PALETTE = [
     "#96e3a7"
 ];
 
+/**
+ * Unit of the estimated cost charts, matching the report generator
+ * @type {string}
+ */
+const COST_UNIT = "USD per 1M";
+
 /**
  * Chart styling constants
  * @type {Object}
@@ -141,6 +149,7 @@ const ChartManager = {
  */
 let barChart;
 let lineChart;
+let histogramChart;
 
 /**
  * Document root element reference
@@ -361,11 +370,11 @@ const BarCharts = {
             }],
             xAxis: [{
                 type: "value",
-                name: `${data.unit === "MB" ? "Memory" : "Duration"} (${data.unit})`,
+                name: `${valueAxisName(data.unit)} (${data.unit})`,
                 nameLocation: "middle",
                 nameGap: 30,
                 axisLabel: { formatter: `{value} ${data.unit}` },
-                minInterval: 1
+                minInterval: data.unit === COST_UNIT || data.unit === "%" ? 0 : 1
             }],
             yAxis: [{
                 type: "category",
@@ -448,7 +457,8 @@ const ScatterCharts = {
         // Transform series data for ECharts
         const echartsSeries = data.series.map(s => {
             const seriesPoints = s.points.map(p => ({
-                value: [p.x, p.y] // ECharts scatter data format [x, y]
+                value: [p.x, p.y], // ECharts scatter data format [x, y]
+                traceId: p.trace_id
             }));
 
             const markLineData = [];
@@ -726,6 +736,8 @@ const MemoryScalingCharts = {
                             return value.toLocaleString();
                         } else if (data.unit === "%") {
                             return value.toFixed(1) + '%';
+                        } else if (data.unit === COST_UNIT) {
+                            return value.toFixed(2);
                         } else {
                             return value.toFixed(0);
                         }
@@ -851,6 +863,214 @@ const MemoryScalingCharts = {
     }
 };
 
+// ======================================
+// Cost-Performance Chart Generator Module
+// ======================================
+
+/**
+ * Module for generating the cost vs performance scatter chart and the
+ * side-by-side comparison of the functions benchmarked on both architectures
+ * @namespace CostPerformanceCharts
+ */
+const CostPerformanceCharts = {
+    /**
+     * Symbols of the points by architecture
+     * @type {Object}
+     */
+    SYMBOLS: { arm64: 'circle', x86_64: 'diamond' },
+
+    /**
+     * Generates ECharts options for the cost vs performance scatter chart
+     * @param {Object} data - Cost-performance chart data
+     * @param {string} theme - The current theme ('light' or 'dark')
+     * @returns {Object} ECharts options object
+     */
+    generateScatterOptions: function(data, theme = 'light') {
+        const echartsSeries = data.points.map(p => ({
+            name: p.name,
+            type: 'scatter',
+            symbol: this.SYMBOLS[p.architecture] || 'circle',
+            symbolSize: 16,
+            label: {
+                show: true,
+                position: 'top',
+                formatter: p.architecture
+            },
+            emphasis: { focus: 'series' },
+            blur: { itemStyle: { opacity: CHART_CONSTANTS.BLUR_OPACITY } },
+            data: [[p.duration, p.cost]]
+        }));
+
+        return {
+            backgroundColor: getThemeBackgroundColor(theme),
+            title: {
+                text: data.title.toUpperCase(),
+                top: "5",
+                left: "center",
+                textStyle: { fontWeight: "light", color: "#666" }
+            },
+            tooltip: {
+                trigger: "item",
+                formatter: function(param) {
+                    const [duration, cost] = param.value;
+                    return `${param.marker} <strong>${param.seriesName}</strong><br/>` +
+                        `Duration: <strong>${duration} ms</strong><br/>` +
+                        `Cost: <strong>${cost.toFixed(3)} ${data.unit}</strong>`;
+                }
+            },
+            legend: { orient: "horizontal", bottom: 5 },
+            grid: { left: "30", top: "50", right: "50", bottom: "85", containLabel: true },
+            xAxis: {
+                type: "value",
+                name: "Warm Start Server Duration (ms)",
+                nameLocation: "middle",
+                nameGap: 30,
+                axisLabel: { formatter: `{value} ms` }
+            },
+            yAxis: {
+                type: "value",
+                name: `Cost (${data.unit})`,
+                nameLocation: "middle",
+                nameGap: 50,
+                axisLabel: { formatter: value => value.toFixed(2) }
+            },
+            series: echartsSeries,
+            toolbox: { feature: { saveAsImage: {} }, right: "20px" }
+        };
+    },
+
+    /**
+     * Generates ECharts options for the side-by-side architecture comparison
+     * @param {Object} data - Cost-performance chart data
+     * @param {string} theme - The current theme ('light' or 'dark')
+     * @returns {Object} ECharts options object
+     */
+    generateComparisonOptions: function(data, theme = 'light') {
+        const names = data.pairs.map(pair => pair.name);
+        const series = ['arm64', 'x86_64'].map(architecture => ({
+            name: architecture,
+            type: 'bar',
+            label: { show: true, position: 'right', formatter: '{c} ms' },
+            data: data.pairs.map(pair => ({
+                value: pair[architecture].duration,
+                cost: pair[architecture].cost,
+                functionName: pair[architecture].name
+            }))
+        }));
+
+        return {
+            backgroundColor: getThemeBackgroundColor(theme),
+            title: {
+                text: "ARCHITECTURE COMPARISON",
+                top: "5",
+                left: "center",
+                textStyle: { fontWeight: "light", color: "#666" }
+            },
+            tooltip: {
+                trigger: "axis",
+                axisPointer: { type: "shadow" },
+                formatter: function(params) {
+                    if (!params || params.length === 0) return '';
+                    let tooltip = `<strong>${params[0].name}</strong><br/>`;
+                    params.forEach(param => {
+                        tooltip += `<div>${param.marker} ${param.data.functionName} (${param.seriesName}): ` +
+                            `<strong>${param.value} ms</strong>, ` +
+                            `<strong>${param.data.cost.toFixed(3)} ${data.unit}</strong></div>`;
+                    });
+                    return tooltip;
+                }
+            },
+            legend: { orient: "horizontal", bottom: 5 },
+            grid: { left: "30", top: "50", right: "50", bottom: "85", containLabel: true },
+            xAxis: {
+                type: "value",
+                name: "Warm Start Server Duration (ms)",
+                nameLocation: "middle",
+                nameGap: 30,
+                axisLabel: { formatter: `{value} ms` }
+            },
+            yAxis: { type: "category", inverse: true, data: names },
+            series: series,
+            toolbox: { feature: { saveAsImage: {} }, right: "20px" }
+        };
+    }
+};
+
+// ================================
+// Histogram Chart Generator Module
+// ================================
+
+/**
+ * Module for generating distribution histograms
+ * Used to reveal multimodal distributions hidden by averages and percentiles
+ * @namespace HistogramCharts
+ */
+const HistogramCharts = {
+    /**
+     * Generates ECharts options for histograms, overlaying the functions
+     * @param {Object} data - Histogram chart data, with the bucket bounds shared by all functions
+     * @param {string} theme - The current theme ('light' or 'dark')
+     * @returns {Object} ECharts options object
+     */
+    generateOptions: function(data, theme = 'light') {
+        const bounds = data.bucket_bounds;
+        const buckets = bounds.slice(0, -1).map((start, i) => `${start}–${bounds[i + 1]}`);
+        const series = data.series.map(s => ({
+            name: s.name,
+            type: 'bar',
+            // Overlap the functions instead of grouping them, so their shapes can be compared
+            barGap: '-100%',
+            barCategoryGap: '0%',
+            itemStyle: { opacity: 0.6 },
+            emphasis: { focus: 'series' },
+            blur: { itemStyle: { opacity: CHART_CONSTANTS.BLUR_OPACITY } },
+            data: s.counts
+        }));
+
+        return {
+            backgroundColor: getThemeBackgroundColor(theme),
+            title: {
+                text: data.title.toUpperCase(),
+                top: "5",
+                left: "center",
+                textStyle: { fontWeight: "light", color: "#666" }
+            },
+            tooltip: {
+                order: 'valueDesc',
+                trigger: "axis",
+                axisPointer: { type: "shadow" },
+                formatter: function(params) {
+                    if (!params || params.length === 0) return '';
+                    let tooltip = `<strong>${params[0].name} ${data.unit}</strong><br/>`;
+                    params.forEach(param => {
+                        tooltip += `<div>${param.marker} ${param.seriesName}: <strong>${param.value}</strong></div>`;
+                    });
+                    return tooltip;
+                }
+            },
+            legend: { orient: "horizontal", bottom: 5 },
+            grid: { top: "30", bottom: "85", left: "50", right: "70", containLabel: true },
+            xAxis: {
+                type: "category",
+                name: `${valueAxisName(data.unit)} (${data.unit})`,
+                nameLocation: "middle",
+                nameGap: 30,
+                data: buckets,
+                axisLabel: { formatter: (value) => value.split('–')[0] }
+            },
+            yAxis: {
+                type: "value",
+                name: "Invocations",
+                nameLocation: "middle",
+                nameGap: 50,
+                minInterval: 1
+            },
+            series: series,
+            toolbox: { feature: { saveAsImage: {} }, right: "20px" }
+        };
+    }
+};
+
 // =============================
 // Summary Chart Generator Module
 // =============================
@@ -1008,11 +1228,11 @@ const SummaryCharts = {
             },
             xAxis: {
                 type: "value",
-                name: `${metric.unit === "MB" ? "Memory" : "Duration"} (${metric.unit})`,
+                name: `${valueAxisName(metric.unit)} (${metric.unit})`,
                 nameLocation: "middle",
                 nameGap: 30,
                 axisLabel: { formatter: `{value} ${metric.unit}` },
-                minInterval: 1
+                minInterval: metric.unit === COST_UNIT ? 0 : 1
             },
             yAxis: {
                 type: "category",
@@ -1049,6 +1269,30 @@ const SummaryCharts = {
 // Common Chart Utilities
 // ===============================
 
+/**
+ * Gets the name of a value axis from the unit of its metric
+ * @param {string} unit - The unit of the metric
+ * @returns {string} The axis name
+ */
+function valueAxisName(unit) {
+    if (unit === "MB") return "Memory";
+    if (unit === COST_UNIT) return "Cost";
+    if (unit === "%") return "Rate";
+    return "Duration";
+}
+
+/**
+ * Gets the URL of a trace from the --trace-url template of the report
+ * @param {string} traceId - The W3C trace ID of a sample
+ * @returns {string} The URL of the trace, with its X-Ray trace ID if the template asks for it
+ */
+function traceUrlOf(traceId) {
+    const xrayTraceId = `1-${traceId.slice(0, 8)}-${traceId.slice(8)}`;
+    return window.traceUrl
+        .replaceAll('{trace_id}', encodeURIComponent(traceId))
+        .replaceAll('{xray_trace_id}', encodeURIComponent(xrayTraceId));
+}
+
 /**
  * Gets the appropriate background color based on theme
  * @param {string} theme - The current theme ('light' or 'dark')
@@ -1148,6 +1392,7 @@ function getChartDOMElements() {
     return {
         barChart: document.getElementById('chart_bar'),
         lineChart: document.getElementById('chart_time'),
+        histogramChart: document.getElementById('chart_histogram'),
         summaryGrid: document.getElementById('summary-charts-grid')
     };
 }
@@ -1162,13 +1407,17 @@ function getChartDOMElements() {
  */
 const ChartHandlers = {
     /**
-     * Handles combined charts (bar + line)
+     * Handles combined charts (bar + line + histogram)
      * @param {string} theme - The theme to use ('light' or 'dark')
      * @param {Object} domElements - DOM elements for chart containers
-     * @param {Object} data - Combined chart data containing bar and line data
+     * @param {Object} data - Combined chart data containing bar, line and histogram data
      */
     Combined: function(theme, domElements, data) {
-        const { barChart: barChartDom, lineChart: lineChartDom } = domElements;
+        const {
+            barChart: barChartDom,
+            lineChart: lineChartDom,
+            histogramChart: histogramChartDom
+        } = domElements;
         
         if (!barChartDom) {
             console.error("Bar chart DOM element not found for combined chart.");
@@ -1187,6 +1436,24 @@ const ChartHandlers = {
             ChartManager.register('lineChart', lineChart);
             const lineOptions = ScatterCharts.generateOptions({ Line: data.line }, theme);
             setupChart(lineChart, lineOptions);
+
+            // Clicking a sample opens its trace in the tracing backend
+            if (window.traceUrl) {
+                lineChart.on('click', 'series', function(params) {
+                    const traceId = params.data && params.data.traceId;
+                    if (traceId) {
+                        window.open(traceUrlOf(traceId), '_blank', 'noopener');
+                    }
+                });
+            }
+        }
+
+        // The histogram container is hidden until there is a distribution to show
+        if (histogramChartDom && data.histogram && data.histogram.bucket_bounds.length > 0) {
+            histogramChartDom.style.display = '';
+            histogramChart = echarts.init(histogramChartDom, theme);
+            ChartManager.register('histogramChart', histogramChart);
+            setupChart(histogramChart, HistogramCharts.generateOptions(data.histogram, theme));
         }
     },
 
@@ -1197,12 +1464,15 @@ const ChartHandlers = {
      * @param {Object} data - Bar chart data
      */
     Bar: function(theme, domElements, data) {
-        const { barChart: barChartDom } = domElements;
+        const { barChart: barChartDom, lineChart: lineChartDom } = domElements;
         
         if (!barChartDom) {
             console.error("Bar chart DOM element not found.");
             return;
         }
+        if (lineChartDom) {
+            lineChartDom.style.display = 'none';
+        }
 
         barChart = echarts.init(barChartDom, theme);
         ChartManager.register('barChart', barChart);
@@ -1269,6 +1539,36 @@ const ChartHandlers = {
      */
     Summary: function(theme, domElements, data) {
         SummaryCharts.initialize(data, theme);
+    },
+
+    /**
+     * Handles the cost vs performance chart and the architecture comparison
+     * @param {string} theme - The theme to use ('light' or 'dark')
+     * @param {Object} domElements - DOM elements for chart containers
+     * @param {Object} data - Cost-performance chart data
+     */
+    CostPerformance: function(theme, domElements, data) {
+        const { barChart: barChartDom, lineChart: lineChartDom } = domElements;
+
+        if (!barChartDom) {
+            console.error("Bar chart DOM element not found for cost-performance chart.");
+            return;
+        }
+
+        barChart = echarts.init(barChartDom, theme);
+        ChartManager.register('barChart', barChart);
+        setupChart(barChart, CostPerformanceCharts.generateScatterOptions(data, theme));
+
+        // The comparison is only shown for functions benchmarked on both architectures
+        if (lineChartDom) {
+            if (data.pairs.length === 0) {
+                lineChartDom.style.display = 'none';
+                return;
+            }
+            lineChart = echarts.init(lineChartDom, theme);
+            ChartManager.register('lineChart', lineChart);
+            setupChart(lineChart, CostPerformanceCharts.generateComparisonOptions(data, theme));
+        }
     }
 };
 
@@ -1,6 +1,9 @@
+use crate::cloudwatch::CloudWatchPublisher;
+use crate::limits::InvocationLimits;
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
 /// Environment variable key-value pair
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +36,33 @@ impl std::str::FromStr for EnvVar {
     }
 }
 
+/// A named set of environment variables the function is benchmarked with, from
+/// `--variant NAME[:KEY=VALUE,...]`
+#[derive(Debug, Clone)]
+pub struct EnvVariant {
+    pub name: String,
+    pub environment: Vec<EnvVar>,
+}
+
+impl std::str::FromStr for EnvVariant {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, variables) = s.split_once(':').unwrap_or((s, ""));
+        crate::utils::validate_fs_safe_name(name)
+            .map_err(|e| anyhow::anyhow!("Invalid variant name: {}", e))?;
+        let environment = variables
+            .split(',')
+            .filter(|variable| !variable.is_empty())
+            .map(str::parse)
+            .collect::<anyhow::Result<Vec<EnvVar>>>()?;
+        Ok(EnvVariant {
+            name: name.to_string(),
+            environment,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BenchmarkConfig {
     pub function_name: String,
@@ -44,8 +74,89 @@ pub struct BenchmarkConfig {
     pub architecture: Option<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub environment: Vec<EnvVar>,
+    /// The sustained load of the warm starts, instead of `number` rounds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sustained_load: Option<SustainedLoad>,
+    /// The function was invoked with response streaming
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub response_streaming: bool,
+    /// The environment variant of the run, from `--variant`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    /// The pacing of the warm start rounds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warm_pacing: Option<WarmPacing>,
+}
+
+/// A target request rate sustained for a period, from `--duration` and `--rps`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SustainedLoad {
+    pub duration_secs: u64,
+    pub rps: f64,
 }
 
+impl SustainedLoad {
+    pub fn new(duration_secs: u64, rps: f64) -> anyhow::Result<Self> {
+        if duration_secs == 0 {
+            anyhow::bail!("--duration must be at least 1 second");
+        }
+        if !(rps > 0.0 && rps.is_finite()) {
+            anyhow::bail!("--rps must be a positive number of requests per second");
+        }
+        Ok(Self { duration_secs, rps })
+    }
+}
+
+/// The pacing of the warm start rounds, from `--warm-interval` and `--concurrent-environments`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WarmPacing {
+    /// Seconds between the starts of the warm start rounds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval_secs: Option<u64>,
+    /// Execution environments held warm: the cold starts initialize them, and every warm start
+    /// round invokes all of them, measuring `--concurrent` of the invocations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environments: Option<u32>,
+}
+
+impl WarmPacing {
+    /// Returns the pacing of the warm starts, if either knob is set
+    pub fn new(
+        interval_secs: Option<u64>,
+        environments: Option<u32>,
+        concurrent: u32,
+    ) -> anyhow::Result<Option<Self>> {
+        if interval_secs == Some(0) {
+            anyhow::bail!("--warm-interval must be at least 1 second");
+        }
+        if let Some(environments) = environments {
+            if environments < concurrent {
+                anyhow::bail!(
+                    "--concurrent-environments ({}) must be at least --concurrent ({})",
+                    environments,
+                    concurrent
+                );
+            }
+        }
+        Ok(
+            (interval_secs.is_some() || environments.is_some()).then_some(Self {
+                interval_secs,
+                environments,
+            }),
+        )
+    }
+
+    /// Returns the invocations of each round: one per environment held warm
+    pub fn round_invocations(pacing: Option<Self>, concurrent: u32) -> u32 {
+        pacing
+            .and_then(|pacing| pacing.environments)
+            .unwrap_or(concurrent)
+    }
+}
+
+/// Name of the extension overhead that no extension accounts for
+pub const UNATTRIBUTED_EXTENSION_OVERHEAD: &str = "unattributed";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColdStartMetrics {
     pub timestamp: String,
@@ -61,6 +172,12 @@ pub struct ColdStartMetrics {
     pub runtime_overhead_ms: Option<f64>,
     pub produced_bytes: Option<i64>,
     pub runtime_done_metrics_duration_ms: Option<f64>,
+    /// Extension overhead attributed to each extension subscribed to the invocations
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extension_overheads: BTreeMap<String, f64>,
+    /// Trace of the invocation, as a W3C trace ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -76,6 +193,12 @@ pub struct WarmStartMetrics {
     pub runtime_overhead_ms: Option<f64>,
     pub produced_bytes: Option<i64>,
     pub runtime_done_metrics_duration_ms: Option<f64>,
+    /// Extension overhead attributed to each extension subscribed to the invocations
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extension_overheads: BTreeMap<String, f64>,
+    /// Trace of the invocation, as a W3C trace ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -83,6 +206,15 @@ pub struct ClientMetrics {
     pub timestamp: String,
     pub client_duration: f64,
     pub memory_size: i64,
+    /// Time to the first byte of a streamed response (`--stream`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_to_first_byte: Option<f64>,
+    /// Time from the first byte to the end of a streamed response (`--stream`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_duration: Option<f64>,
+    /// Trace of the invocation, as a W3C trace ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -103,10 +235,20 @@ pub struct InvocationMetrics {
     pub runtime_overhead_ms: Option<f64>,
     pub produced_bytes: Option<i64>,
     pub runtime_done_metrics_duration_ms: Option<f64>,
+
+    // Client-side timings of streamed responses
+    pub time_to_first_byte: Option<f64>,
+    pub stream_duration: Option<f64>,
+
+    // Extensions subscribed to the INVOKE events, from the platform.extension events of a cold
+    // start, and the durations of the other spans of the platform.report
+    pub extensions: Vec<String>,
+    pub report_spans: BTreeMap<String, f64>,
+    pub trace_id: Option<String>,
 }
 
 impl InvocationMetrics {
-    pub fn to_cold_start(&self) -> Option<ColdStartMetrics> {
+    pub fn to_cold_start(&self, extensions: &[String]) -> Option<ColdStartMetrics> {
         self.init_duration.map(|init| ColdStartMetrics {
             timestamp: self.timestamp.clone(),
             init_duration: init,
@@ -121,10 +263,12 @@ impl InvocationMetrics {
             runtime_overhead_ms: self.runtime_overhead_ms,
             produced_bytes: self.produced_bytes,
             runtime_done_metrics_duration_ms: self.runtime_done_metrics_duration_ms,
+            extension_overheads: self.extension_overheads(extensions),
+            trace_id: self.trace_id.clone(),
         })
     }
 
-    pub fn to_warm_start(&self) -> WarmStartMetrics {
+    pub fn to_warm_start(&self, extensions: &[String]) -> WarmStartMetrics {
         WarmStartMetrics {
             timestamp: self.timestamp.clone(),
             duration: self.duration,
@@ -137,7 +281,35 @@ impl InvocationMetrics {
             runtime_overhead_ms: self.runtime_overhead_ms,
             produced_bytes: self.produced_bytes,
             runtime_done_metrics_duration_ms: self.runtime_done_metrics_duration_ms,
+            extension_overheads: self.extension_overheads(extensions),
+            trace_id: self.trace_id.clone(),
+        }
+    }
+
+    /// Attributes the extension overhead to the extensions: a span of the platform.report named
+    /// after an extension is its overhead, and a single extension accounts for all of it. The
+    /// overhead no span accounts for is `unattributed`.
+    pub fn extension_overheads(&self, extensions: &[String]) -> BTreeMap<String, f64> {
+        let mut overheads = BTreeMap::new();
+        if extensions.is_empty() {
+            return overheads;
         }
+        if let [extension] = extensions {
+            if !self.report_spans.contains_key(extension) {
+                overheads.insert(extension.clone(), self.extension_overhead);
+                return overheads;
+            }
+        }
+        for extension in extensions {
+            if let Some(&duration) = self.report_spans.get(extension) {
+                overheads.insert(extension.clone(), duration);
+            }
+        }
+        let remainder = self.extension_overhead - overheads.values().sum::<f64>();
+        if remainder > 0.0 {
+            overheads.insert(UNATTRIBUTED_EXTENSION_OVERHEAD.to_string(), remainder);
+        }
+        overheads
     }
 
     pub fn to_client_metrics(&self) -> ClientMetrics {
@@ -145,16 +317,58 @@ impl InvocationMetrics {
             timestamp: self.timestamp.clone(),
             client_duration: self.client_duration,
             memory_size: self.memory_size,
+            time_to_first_byte: self.time_to_first_byte,
+            stream_duration: self.stream_duration,
+            trace_id: self.trace_id.clone(),
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BenchmarkReport {
+    /// Version of the schema of the result file, see [`crate::schema`]
+    #[serde(default = "crate::schema::report_schema_version")]
+    pub schema_version: u32,
     pub config: BenchmarkConfig,
     pub cold_starts: Vec<ColdStartMetrics>,
     pub warm_starts: Vec<WarmStartMetrics>,
     pub client_measurements: Vec<ClientMetrics>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<InvocationError>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub environment_recycles: Vec<EnvironmentRecycle>,
+}
+
+/// A new execution environment initialized during the warm starts of a sustained load, after
+/// Lambda recycled or scaled out the environments
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvironmentRecycle {
+    pub timestamp: String,
+    /// Seconds since the start of the sustained load
+    pub elapsed_secs: f64,
+    pub init_duration: f64,
+}
+
+/// A failed invocation of a benchmark run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvocationError {
+    pub timestamp: String,
+    pub phase: InvocationPhase,
+    /// The function error (`Unhandled`, `Handled`), the service error code, or `SdkError`
+    pub error_type: String,
+    pub throttled: bool,
+    /// Whether the measurements of the invocation are kept in the samples of the run
+    #[serde(default)]
+    pub sampled: bool,
+}
+
+/// The measurements a failed invocation was part of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvocationPhase {
+    ColdStart,
+    WarmStart,
+    Client,
 }
 
 #[derive(Debug, Deserialize)]
@@ -186,6 +400,21 @@ pub struct ReportMetrics {
     pub init_duration_ms: Option<f64>,
 }
 
+/// A platform.extension event, logged when an extension registers during the initialization
+#[derive(Debug, Deserialize)]
+pub struct PlatformExtension {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub record: ExtensionRecord,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExtensionRecord {
+    pub name: String,
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Span {
     pub name: String,
@@ -196,8 +425,9 @@ pub struct Span {
 /// Configuration for stack benchmarking
 #[derive(Debug, Clone)]
 pub struct StackBenchmarkConfig {
-    pub stack_name: String,
-    pub select_pattern: String,       // Value from --select (required)
+    pub stack_names: Vec<String>, // Stacks to discover the functions of
+    pub tags: Vec<String>,        // Value from --tag, as key=value
+    pub select_pattern: Option<String>, // Value from --select (optional)
     pub select_regex: Option<String>, // Value from --select-regex (optional)
     pub memory_size: i32,
     pub concurrent_invocations: usize,
@@ -207,7 +437,14 @@ pub struct StackBenchmarkConfig {
     pub environment: Vec<EnvVar>,
     pub client_metrics_mode: bool,
     pub proxy_function: Option<String>,
-    pub parallel: bool, // Added for parallel execution
+    pub parallel: bool,      // Added for parallel execution
+    pub state_file: PathBuf, // Restore state file for mutated function configurations
+    pub cloudwatch_publisher: Option<CloudWatchPublisher>, // From --publish-cloudwatch-namespace
+    pub limits: InvocationLimits, // From --max-concurrency and --rate-limit
+    pub exclude_failed: bool, // From --exclude-failed
+    pub sustained_load: Option<SustainedLoad>, // From --duration and --rps
+    pub response_streaming: bool, // From --stream
+    pub warm_pacing: Option<WarmPacing>, // From --warm-interval and --concurrent-environments
 }
 
 /// Original function configuration to restore after testing
@@ -269,3 +506,39 @@ pub struct RuntimeDoneRecordMetrics {
     #[serde(rename = "producedBytes")]
     pub produced_bytes: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_env_variant() {
+        let variant: EnvVariant = "gzip:OTEL_EXPORTER_OTLP_COMPRESSION=gzip,LEVEL=9"
+            .parse()
+            .unwrap();
+        assert_eq!(variant.name, "gzip");
+        assert_eq!(variant.environment.len(), 2);
+        assert_eq!(variant.environment[1].key, "LEVEL");
+        assert_eq!(variant.environment[1].value, "9");
+
+        let baseline: EnvVariant = "baseline".parse().unwrap();
+        assert!(baseline.environment.is_empty());
+
+        assert!("../up:A=1".parse::<EnvVariant>().is_err());
+        assert!("bad:NOVALUE".parse::<EnvVariant>().is_err());
+    }
+
+    #[test]
+    fn test_warm_pacing_validation() {
+        let error = WarmPacing::new(None, Some(5), 10).unwrap_err();
+        assert!(error.to_string().contains("--concurrent-environments (5)"));
+        let error = WarmPacing::new(Some(0), None, 10).unwrap_err();
+        assert!(error.to_string().contains("--warm-interval"));
+
+        assert_eq!(WarmPacing::new(None, None, 10).unwrap(), None);
+        let pacing = WarmPacing::new(Some(30), Some(10), 10).unwrap().unwrap();
+        assert_eq!(pacing.interval_secs, Some(30));
+        assert_eq!(WarmPacing::round_invocations(Some(pacing), 10), 10);
+        assert_eq!(WarmPacing::round_invocations(None, 4), 4);
+    }
+}
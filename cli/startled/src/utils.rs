@@ -30,6 +30,27 @@ pub fn validate_fs_safe_name(name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Parses a duration with a unit suffix (`s`, `m` or `h`, e.g. `90s`, `10m`, `1h`) into seconds.
+pub fn parse_duration_secs(s: &str) -> Result<u64, String> {
+    let s = s.trim().to_lowercase();
+    let (value, multiplier) = if let Some(value) = s.strip_suffix('s') {
+        (value, 1)
+    } else if let Some(value) = s.strip_suffix('m') {
+        (value, 60)
+    } else if let Some(value) = s.strip_suffix('h') {
+        (value, 60 * 60)
+    } else {
+        return Err(format!(
+            "Invalid duration '{}'. Must end with 's', 'm', or 'h' (e.g. '10m').",
+            s
+        ));
+    };
+    value
+        .parse::<u64>()
+        .map(|value| value * multiplier)
+        .map_err(|e| format!("Invalid duration value '{}': {}", value, e))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,4 +81,14 @@ mod tests {
         assert!(validate_fs_safe_name("invalid name").is_err()); // space
         assert!(validate_fs_safe_name("name!").is_err()); // exclamation
     }
+
+    #[test]
+    fn test_parse_duration_secs() {
+        assert_eq!(parse_duration_secs("90s"), Ok(90));
+        assert_eq!(parse_duration_secs("10m"), Ok(600));
+        assert_eq!(parse_duration_secs("1H"), Ok(3600));
+        assert!(parse_duration_secs("10").is_err());
+        assert!(parse_duration_secs("1.5m").is_err());
+        assert!(parse_duration_secs("m").is_err());
+    }
 }
@@ -4,8 +4,7 @@ mod parser;
 use anyhow::Result;
 use aws_lambda_events::event::cloudwatch_logs::LogsEvent;
 use lambda_otel_lite::{
-    init_telemetry, LambdaSpanProcessor, OtelTracingLayer, SpanAttributes, SpanAttributesExtractor,
-    TelemetryConfig,
+    init_telemetry, LambdaSpanProcessor, OtelTracingLayer, SpanAttributes, TelemetryConfig,
 };
 use lambda_runtime::{tower::ServiceBuilder, Error as LambdaError, LambdaEvent, Runtime};
 use opentelemetry::Value as OtelValue;
@@ -13,78 +12,78 @@ use opentelemetry_otlp::{Protocol, WithExportConfig};
 use reqwest13::Client as ReqwestClient;
 use reqwest_middleware::ClientBuilder;
 use reqwest_tracing::TracingMiddleware;
-use serde::{Deserialize, Serialize};
 use serverless_otlp_forwarder_core::{
-    processor::process_event_batch, span_compactor::SpanCompactionConfig, InstrumentedHttpClient,
+    process_event_batch_with_config, ForwarderConfig, InstrumentedHttpClient,
 };
 use std::collections::HashMap;
 use std::sync::Arc;
 
 use parser::AwsAppSignalSpanParser;
 
-// Wrapper for LogsEvent for this specific processor to implement SpanAttributesExtractor
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct AwsSpanProcessorEventWrapper(LogsEvent);
-
-impl SpanAttributesExtractor for AwsSpanProcessorEventWrapper {
-    fn extract_span_attributes(&self) -> SpanAttributes {
-        let mut attributes: HashMap<String, OtelValue> = HashMap::new();
-        let log_data = &self.0.aws_logs.data;
-
-        attributes.insert(
-            "faas.trigger.type".to_string(),
-            OtelValue::String("cloudwatch_logs".into()),
-        );
-        attributes.insert(
-            "aws.cloudwatch.log_group".to_string(),
-            OtelValue::String(log_data.log_group.clone().into()),
-        );
-        attributes.insert(
-            "aws.cloudwatch.log_stream".to_string(),
-            OtelValue::String(log_data.log_stream.clone().into()),
-        );
-        attributes.insert(
-            "aws.cloudwatch.owner".to_string(),
-            OtelValue::String(log_data.owner.clone().into()),
-        );
-        attributes.insert(
-            "aws.cloudwatch.events.count".to_string(),
-            OtelValue::I64(log_data.log_events.len() as i64),
-        );
-        attributes.insert(
-            "processor.type".to_string(),
-            OtelValue::String("aws_appsignal_span_processor".into()),
-        );
-
-        SpanAttributes::builder()
-            .span_name(format!("aws_span_processor_{}", log_data.log_group.clone()))
-            .kind("consumer".to_string())
-            .attributes(attributes)
-            .build()
-    }
+/// Extracts the span attributes of the invocation from the CloudWatch Logs event
+fn extract_span_attributes(event: &LogsEvent) -> SpanAttributes {
+    let mut attributes: HashMap<String, OtelValue> = HashMap::new();
+    let log_data = &event.aws_logs.data;
+
+    attributes.insert(
+        "faas.trigger.type".to_string(),
+        OtelValue::String("cloudwatch_logs".into()),
+    );
+    attributes.insert(
+        "aws.cloudwatch.log_group".to_string(),
+        OtelValue::String(log_data.log_group.clone().into()),
+    );
+    attributes.insert(
+        "aws.cloudwatch.log_stream".to_string(),
+        OtelValue::String(log_data.log_stream.clone().into()),
+    );
+    attributes.insert(
+        "aws.cloudwatch.owner".to_string(),
+        OtelValue::String(log_data.owner.clone().into()),
+    );
+    attributes.insert(
+        "aws.cloudwatch.events.count".to_string(),
+        OtelValue::I64(log_data.log_events.len() as i64),
+    );
+    attributes.insert(
+        "processor.type".to_string(),
+        OtelValue::String("aws_appsignal_span_processor".into()),
+    );
+
+    SpanAttributes::builder()
+        .span_name(format!("aws_span_processor_{}", log_data.log_group.clone()))
+        .kind("consumer".to_string())
+        .attributes(attributes)
+        .build()
 }
 
 async fn function_handler(
-    event: LambdaEvent<AwsSpanProcessorEventWrapper>,
+    event: LambdaEvent<LogsEvent>,
     http_client: Arc<InstrumentedHttpClient>,
+    config: Arc<ForwarderConfig>,
 ) -> Result<(), LambdaError> {
     tracing::info!("aws-span-processor: function_handler started.");
 
-    let log_group = event.payload.0.aws_logs.data.log_group.clone();
+    let log_group = event.payload.aws_logs.data.log_group.clone();
     let parser = AwsAppSignalSpanParser;
-    let compaction_config = SpanCompactionConfig::default();
 
-    match process_event_batch(
-        event.payload.0,
+    match process_event_batch_with_config(
+        event.payload,
         &parser,
         &log_group,
         http_client.as_ref(),
-        &compaction_config,
+        &config,
     )
     .await
     {
-        Ok(_) => {
-            tracing::info!("aws-span-processor: Batch processed successfully.");
+        Ok(outcome) => {
+            tracing::info!(
+                records_parsed = outcome.records_parsed as i64,
+                records_skipped = outcome.records_skipped as i64,
+                records_failed = outcome.records_failed as i64,
+                spans_forwarded = outcome.spans_forwarded as i64,
+                "aws-span-processor: Batch processed successfully."
+            );
             Ok(())
         }
         Err(e) => {
@@ -113,6 +112,9 @@ async fn main() -> Result<(), LambdaError> {
     .await?;
     tracing::info!("lambda-otel-lite initialized with OTLP HTTP exporter for aws-span-processor.");
 
+    // Resolved once and shared across invocations (OTLP_FORWARDER_CONFIG_FILE and environment)
+    let forwarder_config = Arc::new(ForwarderConfig::from_env()?);
+
     let base_reqwest_client = ReqwestClient::new();
     let client_with_middleware = ClientBuilder::new(base_reqwest_client)
         .with(TracingMiddleware::default())
@@ -123,10 +125,14 @@ async fn main() -> Result<(), LambdaError> {
     tracing::info!("Instrumented HTTP client for data forwarding initialized.");
 
     let service = ServiceBuilder::new()
-        .layer(OtelTracingLayer::new(completion_handler))
-        .service_fn(move |event: LambdaEvent<AwsSpanProcessorEventWrapper>| {
+        .layer(OtelTracingLayer::with_extractor(
+            completion_handler,
+            extract_span_attributes,
+        ))
+        .service_fn(move |event: LambdaEvent<LogsEvent>| {
             let client_for_handler = Arc::clone(&http_client_for_forwarding);
-            async move { function_handler(event, client_for_handler).await }
+            let config = Arc::clone(&forwarder_config);
+            async move { function_handler(event, client_for_handler, config).await }
         });
 
     tracing::info!("aws-span-processor starting Lambda runtime.");
@@ -2,7 +2,7 @@ use anyhow::Result;
 use aws_lambda_events::event::cloudwatch_logs::LogsEvent;
 use serde_json::Value as JsonValue; // For parsing the raw span message
 use serverless_otlp_forwarder_core::core_parser::EventParser;
-use serverless_otlp_forwarder_core::telemetry::TelemetryData;
+use serverless_otlp_forwarder_core::telemetry::{Signal, TelemetryData};
 use tracing;
 // Assuming otlp.rs is in the same crate/module directory (e.g., src/otlp.rs)
 // It will be declared in this crate's main.rs or lib.rs as `mod otlp;`
@@ -47,6 +47,9 @@ impl EventParser for AwsAppSignalSpanParser {
                         payload: protobuf_bytes,
                         content_type: "application/x-protobuf".to_string(),
                         content_encoding: None, // convert_span_to_otlp_protobuf produces uncompressed protobuf
+                        batch_id: None,
+                        signal: Signal::Traces,
+                        span_count: None,
                     });
                 }
                 Err(e) => {
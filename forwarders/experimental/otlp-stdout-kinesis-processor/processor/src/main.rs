@@ -1,8 +1,7 @@
 use anyhow::Result;
 use aws_lambda_events::event::kinesis::KinesisEvent;
 use lambda_otel_lite::{
-    init_telemetry, LambdaSpanProcessor, OtelTracingLayer, SpanAttributes, SpanAttributesExtractor,
-    TelemetryConfig,
+    init_telemetry, LambdaSpanProcessor, OtelTracingLayer, SpanAttributes, TelemetryConfig,
 };
 use lambda_runtime::{tower::ServiceBuilder, Error as LambdaError, LambdaEvent, Runtime};
 use opentelemetry::Value as OtelValue;
@@ -10,104 +9,99 @@ use opentelemetry_otlp::{Protocol, WithExportConfig};
 use reqwest13::Client as ReqwestClient;
 use reqwest_middleware::ClientBuilder;
 use reqwest_tracing::TracingMiddleware;
-use serde::{Deserialize, Serialize};
 use serverless_otlp_forwarder_core::{
-    processor::process_event_batch, span_compactor::SpanCompactionConfig, InstrumentedHttpClient,
+    kinesis_batch_items, process_batch_items, BatchItemFailures, ForwarderConfig,
+    InstrumentedHttpClient, ParserRegistry,
 };
 use std::collections::HashMap;
 use std::sync::Arc;
 
-// The specific parser for this Lambda
-mod parser;
-use parser::KinesisOtlpStdoutParser;
+/// Extracts the span attributes of the invocation from the Kinesis event
+fn extract_span_attributes(event: &KinesisEvent) -> SpanAttributes {
+    let mut attributes: HashMap<String, OtelValue> = HashMap::new();
+    let records = &event.records;
 
-// Wrapper for KinesisEvent to implement SpanAttributesExtractor
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct KinesisEventProcessorWrapper(KinesisEvent);
-
-impl SpanAttributesExtractor for KinesisEventProcessorWrapper {
-    fn extract_span_attributes(&self) -> SpanAttributes {
-        let mut attributes: HashMap<String, OtelValue> = HashMap::new();
-        let records = &self.0.records;
-
-        attributes.insert(
-            "faas.trigger.type".to_string(),
-            OtelValue::String("aws_kinesis".into()),
-        );
-        attributes.insert(
-            "aws.kinesis.records.count".to_string(),
-            OtelValue::I64(records.len() as i64),
-        );
+    attributes.insert(
+        "faas.trigger.type".to_string(),
+        OtelValue::String("aws_kinesis".into()),
+    );
+    attributes.insert(
+        "aws.kinesis.records.count".to_string(),
+        OtelValue::I64(records.len() as i64),
+    );
 
-        if let Some(first_record) = records.first() {
-            if let Some(event_source_arn) = &first_record.event_source_arn {
-                // Attempt to extract stream name from ARN: arn:aws:kinesis:region:account-id:stream/stream-name
-                if let Some(stream_name) = event_source_arn
-                    .split(':')
-                    .nth(5)
-                    .and_then(|s| s.split('/').nth(1))
-                {
-                    attributes.insert(
-                        "aws.kinesis.stream_name".to_string(),
-                        OtelValue::String(stream_name.to_string().into()),
-                    );
-                }
-                attributes.insert(
-                    "aws.kinesis.event_source_arn".to_string(),
-                    OtelValue::String(event_source_arn.clone().into()),
-                );
-            }
-            if let Some(event_id) = &first_record.event_id {
+    if let Some(first_record) = records.first() {
+        if let Some(event_source_arn) = &first_record.event_source_arn {
+            // Attempt to extract stream name from ARN: arn:aws:kinesis:region:account-id:stream/stream-name
+            if let Some(stream_name) = event_source_arn
+                .split(':')
+                .nth(5)
+                .and_then(|s| s.split('/').nth(1))
+            {
                 attributes.insert(
-                    "aws.kinesis.event_id_prefix".to_string(),
-                    OtelValue::String(event_id.split(':').next().unwrap_or("").to_string().into()),
+                    "aws.kinesis.stream_name".to_string(),
+                    OtelValue::String(stream_name.to_string().into()),
                 );
             }
+            attributes.insert(
+                "aws.kinesis.event_source_arn".to_string(),
+                OtelValue::String(event_source_arn.clone().into()),
+            );
+        }
+        if let Some(event_id) = &first_record.event_id {
+            attributes.insert(
+                "aws.kinesis.event_id_prefix".to_string(),
+                OtelValue::String(event_id.split(':').next().unwrap_or("").to_string().into()),
+            );
         }
-
-        SpanAttributes::builder()
-            .span_name("kinesis_event_processor".to_string())
-            .kind("consumer".to_string())
-            .attributes(attributes)
-            .build()
     }
+
+    SpanAttributes::builder()
+        .span_name("kinesis_event_processor".to_string())
+        .kind("consumer".to_string())
+        .attributes(attributes)
+        .build()
 }
 
 async fn function_handler(
-    event: LambdaEvent<KinesisEventProcessorWrapper>,
+    event: LambdaEvent<KinesisEvent>,
     http_client: Arc<InstrumentedHttpClient>,
-) -> Result<(), LambdaError> {
+    config: Arc<ForwarderConfig>,
+) -> Result<BatchItemFailures, LambdaError> {
     tracing::info!("otlp-stdout-kinesis-processor: function_handler started.");
 
     let source_identifier = event
         .payload
-        .0
         .records
         .first()
         .and_then(|r| r.event_source_arn.as_ref())
         .map_or_else(|| "kinesis_stream_unknown".to_string(), |arn| arn.clone());
 
-    let parser = KinesisOtlpStdoutParser;
-    let compaction_config = SpanCompactionConfig::default();
-
-    match process_event_batch(
-        event.payload.0,
-        &parser,
-        &source_identifier,
+    // Records are either otlp-stdout lines put on the stream directly, or CloudWatch Logs
+    // subscription data (base64 + gzip envelopes); only the records that failed are retried
+    let report = process_batch_items(
+        kinesis_batch_items(event.payload, &source_identifier),
+        &ParserRegistry::with_defaults(),
         http_client.as_ref(), // Pass &InstrumentedOtlpClient
-        &compaction_config,
+        &config,
     )
-    .await
-    {
-        Ok(_) => {
-            tracing::info!("otlp-stdout-kinesis-processor: Batch processed successfully.");
-            Ok(())
-        }
-        Err(e) => {
-            tracing::error!(error = %e, "otlp-stdout-kinesis-processor: Error processing event batch.");
-            Err(LambdaError::from(e.to_string()))
-        }
+    .await;
+
+    if report.all_succeeded() {
+        tracing::info!(
+            records_parsed = report.outcome.records_parsed as i64,
+            records_skipped = report.outcome.records_skipped as i64,
+            records_failed = report.outcome.records_failed as i64,
+            spans_forwarded = report.outcome.spans_forwarded as i64,
+            "otlp-stdout-kinesis-processor: Batch processed successfully."
+        );
+    } else {
+        tracing::warn!(
+            failed_records = report.failed_count() as i64,
+            "otlp-stdout-kinesis-processor: Some records failed and will be retried."
+        );
     }
+    Ok(report.batch_item_failures())
 }
 
 #[tokio::main]
@@ -131,6 +125,9 @@ async fn main() -> Result<(), LambdaError> {
         "lambda-otel-lite initialized with OTLP HTTP exporter for otlp-stdout-kinesis-processor."
     );
 
+    // Resolved once and shared across invocations (OTLP_FORWARDER_CONFIG_FILE and environment)
+    let forwarder_config = Arc::new(ForwarderConfig::from_env()?);
+
     let base_reqwest_client = ReqwestClient::new();
     // Wrap it with tracing middleware
     let client_with_middleware = ClientBuilder::new(base_reqwest_client)
@@ -143,10 +140,14 @@ async fn main() -> Result<(), LambdaError> {
     tracing::info!("Instrumented HTTP client for data forwarding initialized.");
 
     let service = ServiceBuilder::new()
-        .layer(OtelTracingLayer::new(completion_handler))
-        .service_fn(move |event: LambdaEvent<KinesisEventProcessorWrapper>| {
+        .layer(OtelTracingLayer::with_extractor(
+            completion_handler,
+            extract_span_attributes,
+        ))
+        .service_fn(move |event: LambdaEvent<KinesisEvent>| {
             let client_for_handler = Arc::clone(&http_client_for_forwarding);
-            async move { function_handler(event, client_for_handler).await }
+            let config = Arc::clone(&forwarder_config);
+            async move { function_handler(event, client_for_handler, config).await }
         });
 
     tracing::info!("otlp-stdout-kinesis-processor starting Lambda runtime.");
@@ -156,5 +157,5 @@ async fn main() -> Result<(), LambdaError> {
 #[cfg(test)]
 mod tests {
     // Kinesis processor specific tests (if any) would go here.
-    // For now, main logic is tested in core and parser.rs has its own tests.
+    // For now, main logic is tested in core.
 }
@@ -9,27 +9,26 @@
 //!
 //! The function supports:
 //! - Configurable OTLP collector endpoint via environment variables
+//! - OTLP over HTTP or gRPC (`OTEL_EXPORTER_OTLP_PROTOCOL=grpc`)
 //! - Custom headers and authentication via environment variables
+//! - Mutual TLS and private certificate authorities (`OTEL_EXPORTER_OTLP_CLIENT_CERTIFICATE`)
 //! - Base64 encoded payloads
 //! - Gzip compressed data
 //! - Span compaction and batching for efficiency
+//! - Optional source metadata (log group, log stream, account) as resource attributes
 //! - Self-instrumentation with OpenTelemetry tracing
 
 use anyhow::Result;
 use aws_lambda_events::event::cloudwatch_logs::LogsEvent;
 use lambda_otel_lite::{
-    init_telemetry, LambdaSpanProcessor, OtelTracingLayer, SpanAttributes, SpanAttributesExtractor,
-    TelemetryConfig,
+    init_telemetry, LambdaSpanProcessor, OtelTracingLayer, SpanAttributes, TelemetryConfig,
 };
 use lambda_runtime::{tower::ServiceBuilder, Error as LambdaError, LambdaEvent, Runtime};
 use opentelemetry::Value as OtelValue;
 use opentelemetry_otlp::{Protocol, WithExportConfig};
-use reqwest13::Client as ReqwestClient;
-use reqwest_middleware::ClientBuilder;
-use reqwest_tracing::TracingMiddleware;
-use serde::{Deserialize, Serialize};
 use serverless_otlp_forwarder_core::{
-    processor::process_event_batch, span_compactor::SpanCompactionConfig, InstrumentedHttpClient,
+    client_builder, process_event_batch_with_config, warm_up, CircuitBreaker, ForwarderConfig,
+    GrpcClient, HttpClient, OtlpProtocol, SourceMetadata, TlsConfig,
 };
 
 use std::{collections::HashMap, sync::Arc};
@@ -38,67 +37,75 @@ use std::{collections::HashMap, sync::Arc};
 mod parser;
 use parser::CloudWatchLogsOtlpStdoutParser;
 
-// Define a wrapper for LogsEvent to implement SpanAttributesExtractor
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct LogsEventProcessorWrapper(LogsEvent);
-
-impl SpanAttributesExtractor for LogsEventProcessorWrapper {
-    fn extract_span_attributes(&self) -> SpanAttributes {
-        let mut attributes: HashMap<String, OtelValue> = HashMap::new();
-        let log_data = &self.0.aws_logs.data;
-
-        attributes.insert(
-            "faas.trigger.type".to_string(),
-            OtelValue::String("cloudwatch_logs".into()),
-        );
-        attributes.insert(
-            "aws.cloudwatch.log_group".to_string(),
-            OtelValue::String(log_data.log_group.clone().into()),
-        );
-        attributes.insert(
-            "aws.cloudwatch.log_stream".to_string(),
-            OtelValue::String(log_data.log_stream.clone().into()),
-        );
-        attributes.insert(
-            "aws.cloudwatch.owner".to_string(),
-            OtelValue::String(log_data.owner.clone().into()),
-        );
-        attributes.insert(
-            "aws.cloudwatch.events.count".to_string(),
-            OtelValue::I64(log_data.log_events.len() as i64),
-        );
-
-        SpanAttributes::builder()
-            .span_name(format!("log {}", log_data.log_group.clone()))
-            .kind("consumer".to_string()) // As per OpenTelemetry semantic conventions for messaging
-            .attributes(attributes)
-            .build()
-    }
+/// Extracts the span attributes of the invocation from the CloudWatch Logs event
+fn extract_span_attributes(event: &LogsEvent) -> SpanAttributes {
+    let mut attributes: HashMap<String, OtelValue> = HashMap::new();
+    let log_data = &event.aws_logs.data;
+
+    attributes.insert(
+        "faas.trigger.type".to_string(),
+        OtelValue::String("cloudwatch_logs".into()),
+    );
+    attributes.insert(
+        "aws.cloudwatch.log_group".to_string(),
+        OtelValue::String(log_data.log_group.clone().into()),
+    );
+    attributes.insert(
+        "aws.cloudwatch.log_stream".to_string(),
+        OtelValue::String(log_data.log_stream.clone().into()),
+    );
+    attributes.insert(
+        "aws.cloudwatch.owner".to_string(),
+        OtelValue::String(log_data.owner.clone().into()),
+    );
+    attributes.insert(
+        "aws.cloudwatch.events.count".to_string(),
+        OtelValue::I64(log_data.log_events.len() as i64),
+    );
+
+    SpanAttributes::builder()
+        .span_name(format!("log {}", log_data.log_group.clone()))
+        .kind("consumer".to_string()) // As per OpenTelemetry semantic conventions for messaging
+        .attributes(attributes)
+        .build()
 }
 
 // Main Lambda function handler - simplified to use the core library
 async fn function_handler(
-    event: LambdaEvent<LogsEventProcessorWrapper>,
-    http_client: Arc<InstrumentedHttpClient>,
+    event: LambdaEvent<LogsEvent>,
+    http_client: Arc<CircuitBreaker<HttpClient>>,
+    config: Arc<ForwarderConfig>,
 ) -> Result<(), LambdaError> {
     tracing::info!("otlp-stdout-logs-processor: function_handler started.");
 
-    let log_group = event.payload.0.aws_logs.data.log_group.clone();
+    let log_data = &event.payload.aws_logs.data;
+    let log_group = log_data.log_group.clone();
+    // Adds the log group, stream and account as resource attributes when enabled
+    let config = config.with_source_metadata(&SourceMetadata::new(
+        &log_data.log_group,
+        &log_data.log_stream,
+        &log_data.owner,
+    ));
 
     let parser = CloudWatchLogsOtlpStdoutParser;
-    let compaction_config = SpanCompactionConfig::default();
 
-    match process_event_batch(
-        event.payload.0,
+    match process_event_batch_with_config(
+        event.payload,
         &parser,
         &log_group,
         http_client.as_ref(),
-        &compaction_config,
+        &config,
     )
     .await
     {
-        Ok(_) => {
-            tracing::info!("otlp-stdout-logs-processor: Batch processed successfully.");
+        Ok(outcome) => {
+            tracing::info!(
+                records_parsed = outcome.records_parsed as i64,
+                records_skipped = outcome.records_skipped as i64,
+                records_failed = outcome.records_failed as i64,
+                spans_forwarded = outcome.spans_forwarded as i64,
+                "otlp-stdout-logs-processor: Batch processed successfully."
+            );
             Ok(())
         }
         Err(e) => {
@@ -131,23 +138,47 @@ async fn main() -> Result<(), LambdaError> {
         "lambda-otel-lite initialized with OTLP HTTP exporter for otlp-stdout-logs-processor."
     );
 
-    // Create a base reqwest client
-    let base_reqwest_client = ReqwestClient::new();
-    // Wrap it with tracing middleware
-    let client_with_middleware = ClientBuilder::new(base_reqwest_client)
-        .with(TracingMiddleware::default())
-        .build();
-    // Wrap the ClientWithMiddleware in our newtype
-    let instrumented_client = InstrumentedHttpClient::new(client_with_middleware);
-    let http_client_for_forwarding = Arc::new(instrumented_client); // Now Arc<InstrumentedHttpClient>
+    // Resolved once and shared across invocations (OTLP_FORWARDER_CONFIG_FILE and environment)
+    let forwarder_config = Arc::new(ForwarderConfig::from_env()?);
+
+    // Client certificate and private CA from OTEL_EXPORTER_OTLP_*CERTIFICATE/*CLIENT_KEY files
+    let tls_config = TlsConfig::from_env()?;
+    let forwarding_client: HttpClient = match forwarder_config.protocol {
+        // The instrumented client wraps reqwest with tracing middleware; connections are pooled
+        // across invocations (pool_*, http2 and tcp_keepalive_ms settings)
+        OtlpProtocol::HttpProtobuf => Arc::new(client_builder::instrumented_with_options(
+            &forwarder_config.connection,
+            &tls_config,
+        )?),
+        OtlpProtocol::Grpc => Arc::new(GrpcClient::with_tls(tls_config)),
+    };
+    // Open the connection during init so the first invocation skips the TCP and TLS handshakes
+    if warm_up(&forwarding_client, &forwarder_config.destination)
+        .await
+        .is_err()
+    {
+        tracing::warn!("Failed to open connection to OTLP endpoint during init.");
+    }
+    // Stop hammering a dead collector across warm invocations (circuit_breaker_* settings)
+    let http_client_for_forwarding = Arc::new(CircuitBreaker::with_config(
+        forwarding_client,
+        forwarder_config.circuit_breaker.clone(),
+    ));
 
-    tracing::info!("Instrumented HTTP client for data forwarding initialized.");
+    tracing::info!(
+        protocol = ?forwarder_config.protocol,
+        "OTLP client for data forwarding initialized."
+    );
 
     let service = ServiceBuilder::new()
-        .layer(OtelTracingLayer::new(completion_handler))
-        .service_fn(move |event: LambdaEvent<LogsEventProcessorWrapper>| {
+        .layer(OtelTracingLayer::with_extractor(
+            completion_handler,
+            extract_span_attributes,
+        ))
+        .service_fn(move |event: LambdaEvent<LogsEvent>| {
             let client_for_handler = Arc::clone(&http_client_for_forwarding);
-            async move { function_handler(event, client_for_handler).await }
+            let config = Arc::clone(&forwarder_config);
+            async move { function_handler(event, client_for_handler, config).await }
         });
 
     tracing::info!("otlp-stdout-logs-processor starting Lambda runtime.");
@@ -1,11 +1,11 @@
 use anyhow::Result;
 use aws_lambda_events::event::cloudwatch_logs::LogsEvent;
-use otlp_stdout_span_exporter::ExporterOutput;
-use serverless_otlp_forwarder_core::core_parser::EventParser;
+use serverless_otlp_forwarder_core::core_parser::{EventParser, ParserRegistry};
 use serverless_otlp_forwarder_core::telemetry::TelemetryData;
-use tracing;
 
 // Define a local struct for parsing CloudWatch Logs events containing OTLP stdout format.
+// Each log event is dispatched through the core parser registry, so log groups may also
+// carry raw OTLP/JSON export requests alongside otlp-stdout records.
 pub struct CloudWatchLogsOtlpStdoutParser;
 
 impl EventParser for CloudWatchLogsOtlpStdoutParser {
@@ -14,48 +14,25 @@ impl EventParser for CloudWatchLogsOtlpStdoutParser {
     fn parse(
         &self,
         event_payload: Self::EventInput,
-        _log_group: &str,
+        log_group: &str,
     ) -> Result<Vec<TelemetryData>> {
-        let log_events = event_payload.aws_logs.data.log_events;
-        let mut telemetry_items = Vec::with_capacity(log_events.len());
-
-        for log_event in log_events {
-            let log_record_str = &log_event.message;
-            tracing::debug!(
-                "Received log record string for OTLP stdout processing: {}",
-                log_record_str
-            );
-
-            let record: ExporterOutput = match serde_json::from_str(log_record_str) {
-                Ok(output) => output,
-                Err(err) => {
-                    tracing::warn!(
-                        "Failed to parse log record as ExporterOutput JSON: {}. Error details: {}. Skipping record.",
-                        log_record_str,
-                        err
-                    );
-                    continue; // Skip this log event if it doesn't parse to ExporterOutput
-                }
-            };
-
-            tracing::debug!(
-                "Successfully parsed log record as ExporterOutput with version: {}",
-                record.version
-            );
+        self.parse_counting_failures(event_payload, log_group)
+            .map(|(telemetry_items, _)| telemetry_items)
+    }
 
-            // TelemetryData::from_log_record is now part of the core library
-            // and handles the conversion from ExporterOutput to the core TelemetryData format.
-            match TelemetryData::from_log_record(record) {
-                Ok(telemetry_data) => telemetry_items.push(telemetry_data),
-                Err(e) => {
-                    tracing::warn!(
-                        "Failed to convert ExporterOutput to TelemetryData: {}. Skipping record.",
-                        e
-                    );
-                }
-            }
-        }
-        Ok(telemetry_items)
+    fn parse_counting_failures(
+        &self,
+        event_payload: Self::EventInput,
+        log_group: &str,
+    ) -> Result<(Vec<TelemetryData>, usize)> {
+        let messages = event_payload
+            .aws_logs
+            .data
+            .log_events
+            .into_iter()
+            .map(|log_event| log_event.message)
+            .collect();
+        ParserRegistry::with_defaults().parse_counting_failures(messages, log_group)
     }
 }
 
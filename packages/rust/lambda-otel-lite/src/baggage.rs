@@ -0,0 +1,238 @@
+//! W3C Baggage support.
+//!
+//! Baggage entries of incoming requests are extracted with the trace context by the traced
+//! handler and the Tower layer, from the same carrier headers, whether or not the `baggage`
+//! propagator is configured. Handlers read them with [`current`] or [`get`], from the
+//! context of the current span.
+//!
+//! Request-scoped metadata such as a tenant ID can also be copied onto spans and events as
+//! attributes: the [`BaggageSpanProcessor`] sets the allowed baggage entries of the parent
+//! context on every span it starts, and events recorded with [`record_event`](crate::record_event)
+//! get the allowed entries of the current span. Both are enabled by
+//! [`TelemetryConfig::baggage_attributes`](crate::TelemetryConfig), or the
+//! `LAMBDA_OTEL_LITE_BAGGAGE_ATTRIBUTES` environment variable:
+//!
+//! ```no_run
+//! use lambda_otel_lite::{baggage, init_telemetry, TelemetryConfig};
+//! use lambda_runtime::Error;
+//!
+//! # async fn example() -> Result<(), Error> {
+//! let config = TelemetryConfig::builder()
+//!     .baggage_attributes(vec!["tenant-id".to_string()])
+//!     .build();
+//! let (_, completion_handler) = init_telemetry(config).await?;
+//!
+//! // In the handler
+//! let tenant_id = baggage::get("tenant-id");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Baggage is set by the caller, so only allow keys whose values are safe to record.
+
+use opentelemetry::baggage::{Baggage, BaggageExt, KeyValueMetadata};
+use opentelemetry::trace::Span as _;
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+use std::sync::RwLock;
+use std::time::Duration;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Allowed key copying all the baggage entries.
+pub const ALL_KEYS: &str = "*";
+
+// Baggage keys copied onto events, set by `init_telemetry`
+static EVENT_BAGGAGE_KEYS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Returns the baggage of the current span.
+pub fn current() -> Baggage {
+    tracing::Span::current()
+        .context()
+        .baggage()
+        .iter()
+        .map(|(key, (value, metadata))| {
+            KeyValueMetadata::new(key.clone(), value.clone(), metadata.clone())
+        })
+        .collect()
+}
+
+/// Returns the value of a baggage entry of the current span.
+pub fn get(key: &str) -> Option<String> {
+    current().get(key).map(|value| value.to_string())
+}
+
+/// Span processor setting the allowed baggage entries of the parent context as attributes
+/// of the spans it starts.
+///
+/// The attribute keys are the baggage keys. Use [`ALL_KEYS`] to copy all entries.
+///
+/// # Examples
+///
+/// ```
+/// use lambda_otel_lite::baggage::BaggageSpanProcessor;
+/// use lambda_otel_lite::TelemetryConfig;
+///
+/// let config = TelemetryConfig::builder()
+///     .with_span_processor(BaggageSpanProcessor::new(["tenant-id", "user-tier"]))
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct BaggageSpanProcessor {
+    keys: Vec<String>,
+}
+
+impl BaggageSpanProcessor {
+    /// Creates a processor copying the baggage entries with the given keys.
+    pub fn new<I>(keys: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            keys: keys.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl SpanProcessor for BaggageSpanProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        for attribute in baggage_attributes(&self.keys, cx.baggage()) {
+            span.set_attribute(attribute);
+        }
+    }
+
+    fn on_end(&self, _span: SpanData) {}
+
+    fn force_flush(&self) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn shutdown_with_timeout(&self, _timeout: Duration) -> OTelSdkResult {
+        Ok(())
+    }
+}
+
+/// Parses a comma-separated list of baggage keys.
+pub(crate) fn parse_keys(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Sets the baggage keys copied onto events.
+pub(crate) fn set_event_keys(keys: Vec<String>) {
+    if let Ok(mut event_keys) = EVENT_BAGGAGE_KEYS.write() {
+        *event_keys = keys;
+    }
+}
+
+/// Returns the allowed baggage entries of a span, as event attributes.
+pub(crate) fn event_attributes(span: &tracing::Span) -> Vec<KeyValue> {
+    match EVENT_BAGGAGE_KEYS.read() {
+        Ok(keys) if !keys.is_empty() => baggage_attributes(&keys, span.context().baggage()),
+        _ => Vec::new(),
+    }
+}
+
+fn baggage_attributes(keys: &[String], baggage: &Baggage) -> Vec<KeyValue> {
+    let all = keys.iter().any(|key| key == ALL_KEYS);
+    baggage
+        .iter()
+        .filter(|(key, _)| all || keys.iter().any(|allowed| allowed == key.as_str()))
+        .map(|(key, (value, _))| KeyValue::new(key.clone(), value.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::trace::{SdkTracerProvider, SpanExporter};
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    // Test exporter that captures spans
+    #[derive(Debug, Default, Clone)]
+    struct TestExporter {
+        spans: Arc<Mutex<Vec<SpanData>>>,
+    }
+
+    impl SpanExporter for TestExporter {
+        async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+            self.spans.lock().unwrap().extend(batch);
+            Ok(())
+        }
+    }
+
+    fn find_attribute(attributes: &[KeyValue], key: &str) -> Option<String> {
+        attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| kv.value.to_string())
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!(
+            parse_keys(" tenant-id, ,user-tier ,"),
+            vec!["tenant-id", "user-tier"]
+        );
+        assert!(parse_keys("").is_empty());
+    }
+
+    #[test]
+    fn test_baggage_span_processor() {
+        let exporter = TestExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_span_processor(BaggageSpanProcessor::new(["tenant-id"]))
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        let parent_context = Context::new().with_baggage(vec![
+            KeyValue::new("tenant-id", "acme"),
+            KeyValue::new("session", "secret"),
+        ]);
+        let (tenant_id, session) = tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("handler");
+            let _ = span.set_parent(parent_context);
+            let _guard = span.enter();
+            let _child = tracing::info_span!("child").entered();
+            (get("tenant-id"), get("session"))
+        });
+
+        // Handlers get all the entries
+        assert_eq!(tenant_id.as_deref(), Some("acme"));
+        assert_eq!(session.as_deref(), Some("secret"));
+
+        // Spans only get the allowed entries
+        let spans = exporter.spans.lock().unwrap();
+        assert_eq!(spans.len(), 2);
+        for span in spans.iter() {
+            assert_eq!(
+                find_attribute(&span.attributes, "tenant-id").as_deref(),
+                Some("acme"),
+                "span: {}",
+                span.name
+            );
+            assert_eq!(find_attribute(&span.attributes, "session"), None);
+        }
+    }
+
+    #[test]
+    fn test_baggage_attributes_all_keys() {
+        let baggage = Baggage::from_iter([
+            KeyValue::new("tenant-id", "acme"),
+            KeyValue::new("user-tier", "gold"),
+        ]);
+
+        let attributes = baggage_attributes(&[ALL_KEYS.to_string()], &baggage);
+        assert_eq!(attributes.len(), 2);
+        assert!(baggage_attributes(&[], &baggage).is_empty());
+    }
+}
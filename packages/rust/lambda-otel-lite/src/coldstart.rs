@@ -0,0 +1,157 @@
+//! Init span of cold starts.
+//!
+//! On the first invocation of an on-demand execution environment, the traced handler and the
+//! Tower layer record an `init` span, a child of the invocation span, starting when the
+//! process started and ending when the first invocation started. The time spent loading the
+//! binary, running `main` and initializing telemetry and clients is then visible in the trace
+//! of the cold start, next to the `faas.coldstart` attribute of the invocation span.
+//!
+//! The init span is only recorded when `AWS_LAMBDA_INITIALIZATION_TYPE` is `on-demand`: with
+//! provisioned concurrency the environment is initialized long before its first invocation,
+//! and with SnapStart the process is restored from a snapshot, so the process start time
+//! does not measure the cold start of the invocation.
+//!
+//! The process start time is read from `/proc`, so the span is only recorded on Linux.
+
+use opentelemetry::trace::{Span as _, SpanKind, Tracer as _};
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::trace::Tracer;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Name of the init span
+pub const INIT_SPAN_NAME: &str = "init";
+
+/// Initialization type of the execution environment, set by Lambda
+const INITIALIZATION_TYPE_ENV_VAR: &str = "AWS_LAMBDA_INITIALIZATION_TYPE";
+
+// Clock ticks per second of the `/proc` times (`USER_HZ`), fixed by the Linux ABI
+const CLOCK_TICKS_PER_SEC: f64 = 100.0;
+
+static INIT_SPAN_RECORDED: AtomicBool = AtomicBool::new(false);
+
+/// Returns the time the current process started, read from `/proc`.
+///
+/// The time has the 10 ms resolution of the kernel clock ticks. Returns `None` when `/proc` is
+/// not available, for example outside of Linux.
+pub fn process_start_time() -> Option<SystemTime> {
+    let stat = fs::read_to_string("/proc/self/stat").ok()?;
+    // The command name may contain spaces, the fields after it don't: `starttime` is the 22nd
+    // field, counting the PID and the command name
+    let start_ticks: u64 = stat
+        .rsplit_once(')')?
+        .1
+        .split_whitespace()
+        .nth(19)?
+        .parse()
+        .ok()?;
+    let uptime: f64 = fs::read_to_string("/proc/uptime")
+        .ok()?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    let age = (uptime - start_ticks as f64 / CLOCK_TICKS_PER_SEC).max(0.0);
+    SystemTime::now().checked_sub(Duration::from_secs_f64(age))
+}
+
+/// Records the init span as a child of the invocation span, once per process.
+///
+/// Does nothing after the first call, outside of on-demand environments, or when the process
+/// start time is not available.
+pub(crate) fn record_init_span(invocation_span: &Span, tracer: &Tracer) {
+    if INIT_SPAN_RECORDED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    if std::env::var(INITIALIZATION_TYPE_ENV_VAR).as_deref() != Ok("on-demand") {
+        return;
+    }
+    let Some(start_time) = process_start_time() else {
+        return;
+    };
+
+    let mut span = tracer
+        .span_builder(INIT_SPAN_NAME)
+        .with_kind(SpanKind::Internal)
+        .with_start_time(start_time)
+        .with_attributes(vec![KeyValue::new("faas.coldstart", true)])
+        .start_with_context(tracer, &invocation_span.context());
+    span.end_with_timestamp(SystemTime::now());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+    use opentelemetry_sdk::error::OTelSdkResult;
+    use opentelemetry_sdk::trace::{SdkTracerProvider, SpanData, SpanExporter};
+    use sealed_test::prelude::*;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    // Test exporter that captures spans
+    #[derive(Debug, Default, Clone)]
+    struct TestExporter {
+        spans: Arc<Mutex<Vec<SpanData>>>,
+    }
+
+    impl SpanExporter for TestExporter {
+        async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+            self.spans.lock().unwrap().extend(batch);
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[sealed_test]
+    fn test_record_init_span() {
+        std::env::set_var(INITIALIZATION_TYPE_ENV_VAR, "on-demand");
+        let exporter = TestExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let tracer = provider.tracer("test");
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(tracer.clone()));
+
+        let invocation_span_id = tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("handler");
+            record_init_span(&span, &tracer);
+            // Only the first invocation records the init span
+            record_init_span(&span, &tracer);
+            span.context().span().span_context().span_id()
+        });
+
+        let spans = exporter.spans.lock().unwrap();
+        let init_spans: Vec<_> = spans
+            .iter()
+            .filter(|span| span.name == INIT_SPAN_NAME)
+            .collect();
+        // The process start time is only available on Linux
+        if !cfg!(target_os = "linux") {
+            assert!(init_spans.is_empty());
+            return;
+        }
+        assert_eq!(init_spans.len(), 1);
+        assert_eq!(init_spans[0].parent_span_id, invocation_span_id);
+        assert!(init_spans[0].start_time <= init_spans[0].end_time);
+        assert!(init_spans[0]
+            .attributes
+            .contains(&KeyValue::new("faas.coldstart", true)));
+    }
+
+    #[test]
+    fn test_process_start_time() {
+        // `/proc` is only available on Linux
+        if !cfg!(target_os = "linux") {
+            assert!(process_start_time().is_none());
+            return;
+        }
+        let start_time = process_start_time().unwrap();
+        let age = SystemTime::now().duration_since(start_time).unwrap();
+        assert!(age < Duration::from_secs(24 * 3600));
+    }
+}
@@ -11,6 +11,13 @@ pub mod env_vars {
     /// Maximum queue size for LambdaSpanProcessor.
     pub const QUEUE_SIZE: &str = "LAMBDA_SPAN_PROCESSOR_QUEUE_SIZE";
 
+    /// What LambdaSpanProcessor does with a span when its queue is full.
+    /// Valid values: drop_new, drop_oldest, block
+    pub const OVERFLOW_STRATEGY: &str = "LAMBDA_SPAN_PROCESSOR_OVERFLOW_STRATEGY";
+
+    /// Maximum time in milliseconds to wait for queue space with the `block` overflow strategy.
+    pub const BLOCK_TIMEOUT_MS: &str = "LAMBDA_SPAN_PROCESSOR_BLOCK_TIMEOUT_MS";
+
     /// Compression level for OTLP stdout span exporter.
     pub const COMPRESSION_LEVEL: &str = "OTLP_STDOUT_SPAN_EXPORTER_COMPRESSION_LEVEL";
 
@@ -24,12 +31,28 @@ pub mod env_vars {
     pub const AWS_LAMBDA_FUNCTION_NAME: &str = "AWS_LAMBDA_FUNCTION_NAME";
 
     /// Comma-separated list of context propagators to use.
-    /// Valid values: tracecontext, xray, xray-lambda, none
+    /// Valid values: tracecontext, baggage, xray, xray-lambda, b3, b3multi, none
     pub const PROPAGATORS: &str = "OTEL_PROPAGATORS";
 
     /// Controls whether to enable the fmt layer for logging regardless of code settings.
     /// Set to "true" to force enable logging output.
     pub const ENABLE_FMT_LAYER: &str = "LAMBDA_TRACING_ENABLE_FMT_LAYER";
+
+    /// Controls whether to initialize a meter provider regardless of code settings.
+    /// Set to "true" to export metrics through the OTLP stdout metric exporter.
+    pub const ENABLE_METRICS: &str = "LAMBDA_OTEL_LITE_ENABLE_METRICS";
+
+    /// Controls whether to bridge `tracing` events to OTLP log records regardless of code settings.
+    /// Set to "true" to export log records through the OTLP stdout log exporter.
+    pub const ENABLE_LOGS: &str = "LAMBDA_OTEL_LITE_ENABLE_LOGS";
+
+    /// ID generator of the trace and span IDs, regardless of code settings.
+    /// Valid values: random, xray
+    pub const ID_GENERATOR: &str = "LAMBDA_OTEL_LITE_ID_GENERATOR";
+
+    /// Comma-separated list of baggage keys copied onto spans and events as attributes,
+    /// regardless of code settings. Use `*` to copy all the entries.
+    pub const BAGGAGE_ATTRIBUTES: &str = "LAMBDA_OTEL_LITE_BAGGAGE_ATTRIBUTES";
 }
 
 /// Default values for configuration parameters.
@@ -37,6 +60,9 @@ pub mod defaults {
     /// Default maximum queue size for LambdaSpanProcessor.
     pub const QUEUE_SIZE: usize = 2048;
 
+    /// Default maximum time in milliseconds to wait for queue space with the `block` overflow strategy.
+    pub const BLOCK_TIMEOUT_MS: u64 = 100;
+
     /// Default compression level for OTLP stdout span exporter.
     pub const COMPRESSION_LEVEL: u8 = 6;
 
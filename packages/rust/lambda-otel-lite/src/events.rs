@@ -265,6 +265,13 @@ fn record_event_impl(
         event_attributes.push(KeyValue::new("event.body", message.to_string()));
     }
 
+    // Add the allowed baggage entries, unless set by a custom attribute
+    for attribute in crate::baggage::event_attributes(&span) {
+        if !attributes.iter().any(|kv| kv.key == attribute.key) {
+            event_attributes.push(attribute);
+        }
+    }
+
     // Add custom attributes
     event_attributes.extend(attributes);
 
@@ -28,6 +28,9 @@
 //! 4. **Graceful Shutdown**: The extension implements proper shutdown handling to ensure
 //!    no telemetry data is lost when the Lambda environment is terminated.
 //!
+//! When metrics or logs are enabled, the meter and logger providers are flushed together with
+//! the tracer provider.
+//!
 //! # Error Handling
 //!
 //! The extension implements robust error handling:
@@ -36,9 +39,12 @@
 //! - Handles channel communication failures
 
 use crate::logger::Logger;
+use crate::telemetry::flush_providers;
 use crate::ProcessorMode;
 use lambda_extension::{service_fn, Error, Extension, NextEvent};
-use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::{
+    logs::SdkLoggerProvider, metrics::SdkMeterProvider, trace::SdkTracerProvider,
+};
 use std::sync::Arc;
 use tokio::{
     signal::unix::{signal, SignalKind},
@@ -85,6 +91,10 @@ pub struct OtelInternalExtension {
     request_done_receiver: Mutex<UnboundedReceiver<()>>,
     /// Reference to the tracer provider for flushing spans
     tracer_provider: Arc<SdkTracerProvider>,
+    /// Meter provider flushed with the spans, when metrics are enabled
+    meter_provider: Option<SdkMeterProvider>,
+    /// Logger provider flushed with the spans, when logs are enabled
+    logger_provider: Option<SdkLoggerProvider>,
 }
 
 impl OtelInternalExtension {
@@ -101,9 +111,23 @@ impl OtelInternalExtension {
         Self {
             request_done_receiver: Mutex::new(request_done_receiver),
             tracer_provider,
+            meter_provider: None,
+            logger_provider: None,
         }
     }
 
+    /// Sets the meter provider flushed after each invocation, along with the spans.
+    pub fn with_meter_provider(mut self, meter_provider: Option<SdkMeterProvider>) -> Self {
+        self.meter_provider = meter_provider;
+        self
+    }
+
+    /// Sets the logger provider flushed after each invocation, along with the spans.
+    pub fn with_logger_provider(mut self, logger_provider: Option<SdkLoggerProvider>) -> Self {
+        self.logger_provider = logger_provider;
+        self
+    }
+
     /// Handles extension events and flushes telemetry after each invocation.
     ///
     /// This method implements the core event handling logic for the extension.
@@ -166,11 +190,12 @@ impl OtelInternalExtension {
                 .await
                 .ok_or_else(|| Error::from("channel closed"))?;
             // Force flush all spans and handle any errors
-            if let Err(err) = self.tracer_provider.force_flush() {
-                LOGGER.error(format!(
-                    "OtelInternalExtension.invoke.Error: Error flushing tracer provider: {err:?}"
-                ));
-            }
+            flush_providers(
+                &self.tracer_provider,
+                self.meter_provider.as_ref(),
+                self.logger_provider.as_ref(),
+                "OtelInternalExtension.invoke",
+            );
         }
 
         Ok(())
@@ -195,6 +220,8 @@ impl OtelInternalExtension {
 /// # Arguments
 ///
 /// * `tracer_provider` - The TracerProvider to use for span management
+/// * `meter_provider` - The MeterProvider flushed with the spans, when metrics are enabled
+/// * `logger_provider` - The LoggerProvider flushed with the spans, when logs are enabled
 /// * `processor_mode` - The mode determining how spans are processed
 ///
 /// # Returns
@@ -202,15 +229,18 @@ impl OtelInternalExtension {
 /// Returns a channel sender for signaling completion, or an Error if registration fails.
 pub(crate) async fn register_extension(
     tracer_provider: Arc<SdkTracerProvider>,
+    meter_provider: Option<SdkMeterProvider>,
+    logger_provider: Option<SdkLoggerProvider>,
     processor_mode: ProcessorMode,
 ) -> Result<UnboundedSender<()>, Error> {
     LOGGER.debug("OtelInternalExtension.register_extension: starting registration");
     let (request_done_sender, request_done_receiver) = unbounded_channel::<()>();
 
-    let extension = Arc::new(OtelInternalExtension::new(
-        request_done_receiver,
-        tracer_provider.clone(),
-    ));
+    let extension = Arc::new(
+        OtelInternalExtension::new(request_done_receiver, tracer_provider.clone())
+            .with_meter_provider(meter_provider.clone())
+            .with_logger_provider(logger_provider.clone()),
+    );
 
     // Register and start the extension
     let mut ext = Extension::new();
@@ -247,11 +277,12 @@ pub(crate) async fn register_extension(
         if sigterm.recv().await.is_some() {
             LOGGER.debug("OtelInternalExtension.SIGTERM: SIGTERM received, flushing spans");
             // Direct synchronous flush
-            if let Err(err) = tracer_provider.force_flush() {
-                LOGGER.error(format!(
-                    "OtelInternalExtension.SIGTERM.Error: Error during shutdown: {err:?}"
-                ));
-            }
+            flush_providers(
+                &tracer_provider,
+                meter_provider.as_ref(),
+                logger_provider.as_ref(),
+                "OtelInternalExtension.SIGTERM",
+            );
             LOGGER.debug("OtelInternalExtension.SIGTERM: Shutdown complete");
             std::process::exit(0);
         }
@@ -264,6 +295,7 @@ pub(crate) async fn register_extension(
 mod tests {
     use super::*;
     use lambda_extension::{InvokeEvent, LambdaEvent, ShutdownEvent};
+    use opentelemetry::metrics::MeterProvider as _;
     use opentelemetry::trace::{Tracer, TracerProvider as _};
     use opentelemetry::Context;
     use opentelemetry_sdk::{
@@ -271,6 +303,9 @@ mod tests {
         trace::{SdkTracerProvider, Span, SpanData, SpanExporter, SpanProcessor},
         Resource,
     };
+    use otlp_stdout_span_exporter::{
+        BufferOutput, OtlpStdoutMetricExporter, OtlpStdoutSpanExporter,
+    };
     use std::sync::{
         atomic::{AtomicUsize, Ordering},
         Mutex,
@@ -490,4 +525,50 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_extension_invoke_flushes_meter_provider() -> Result<(), Error> {
+        let (provider, _) = setup_test_provider();
+        let output = BufferOutput::new();
+        let meter_provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(OtlpStdoutMetricExporter::new(
+                OtlpStdoutSpanExporter::builder()
+                    .output(Arc::new(output.clone()))
+                    .build(),
+            ))
+            .build();
+        let counter = meter_provider
+            .meter("extension-test")
+            .u64_counter("orders")
+            .build();
+        counter.add(1, &[]);
+        let (sender, receiver) = unbounded_channel();
+
+        let extension = Arc::new(
+            OtelInternalExtension::new(receiver, provider)
+                .with_meter_provider(Some(meter_provider)),
+        );
+
+        let event = LambdaEvent {
+            next: NextEvent::Invoke(InvokeEvent {
+                deadline_ms: 1000,
+                request_id: "test-id".to_string(),
+                invoked_function_arn: "test-arn".to_string(),
+                tracing: Default::default(),
+            }),
+        };
+
+        // The extension is kept alive, so the meter provider is not shut down by the task
+        let handle = tokio::spawn({
+            let extension = extension.clone();
+            async move { extension.invoke(event).await }
+        });
+        sender.send(()).unwrap();
+
+        let result = handle.await.unwrap();
+        assert!(result.is_ok());
+        assert_eq!(output.take_lines().unwrap().len(), 1);
+
+        Ok(())
+    }
 }
@@ -2,7 +2,8 @@
 //!
 //! This module provides functionality for extracting OpenTelemetry span attributes from AWS Lambda
 //! events. It includes:
-//! - Built-in support for common AWS event types (API Gateway, ALB)
+//! - Built-in support for common AWS event types (API Gateway, ALB, SQS, SNS, Kinesis,
+//!   DynamoDB Streams)
 //! - Extensible trait system for custom event types
 //! - Automatic W3C Trace Context and AWS X-Ray propagation
 //! - Support for span links and custom attributes
@@ -55,6 +56,9 @@
 //! The following AWS event types are supported out of the box:
 //! - API Gateway v1/v2 (HTTP API and REST API)
 //! - Application Load Balancer
+//! - `http::Request`, as received from `lambda_http` by axum and other Tower HTTP services
+//! - SQS, SNS and Kinesis, as messaging consumers with span links to the message producers
+//! - DynamoDB Streams, as a datasource trigger
 //!
 //! Each implementation follows OpenTelemetry semantic conventions for HTTP spans:
 //! - `http.request.method`: The HTTP method (e.g., "GET", "POST")
@@ -75,13 +79,22 @@
 //!
 use aws_lambda_events::event::alb::AlbTargetGroupRequest;
 use aws_lambda_events::event::apigw::{ApiGatewayProxyRequest, ApiGatewayV2httpRequest};
+use aws_lambda_events::event::dynamodb::Event as DynamoDbEvent;
+use aws_lambda_events::event::kinesis::KinesisEvent;
+use aws_lambda_events::event::sns::SnsEvent;
+use aws_lambda_events::event::sqs::SqsEvent;
+use aws_lambda_events::http::{Request, Version};
 use bon::Builder;
 use lambda_runtime::Context;
-use opentelemetry::trace::{Link, Status};
-use opentelemetry::Value;
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::{Link, Status, TraceContextExt};
+use opentelemetry::{KeyValue, Value};
+use opentelemetry_aws::trace::XrayPropagator;
+use opentelemetry_sdk::propagation::{BaggagePropagator, TraceContextPropagator};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
 use std::fmt::{self, Display};
+use std::sync::Arc;
 use tracing::Span;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 use urlencoding;
@@ -249,16 +262,21 @@ pub fn get_status_code(response: &JsonValue) -> Option<i64> {
 /// including status code and error status for HTTP responses.
 pub fn set_response_attributes(span: &Span, response: &JsonValue) {
     if let Some(status_code) = get_status_code(response) {
-        span.set_attribute("http.status_code", status_code.to_string());
+        set_status_code_attributes(span, status_code);
+    }
+}
 
-        // Set span status based on status code
-        if status_code >= 500 {
-            span.set_status(Status::error(format!("HTTP {status_code} response")));
-        } else {
-            span.set_status(Status::Ok);
-        }
-        span.set_attribute("http.response.status_code", status_code.to_string());
+/// Set the status code attributes and the span status of an HTTP response.
+pub(crate) fn set_status_code_attributes(span: &Span, status_code: i64) {
+    span.set_attribute("http.status_code", status_code.to_string());
+
+    // Set span status based on status code
+    if status_code >= 500 {
+        span.set_status(Status::error(format!("HTTP {status_code} response")));
+    } else {
+        span.set_status(Status::Ok);
     }
+    span.set_attribute("http.response.status_code", status_code.to_string());
 }
 
 /// Set common attributes on the span based on the Lambda context.
@@ -287,6 +305,63 @@ pub fn set_common_attributes(span: &Span, context: &Context, is_cold_start: bool
     }
 }
 
+/// Apply extracted span attributes to the invocation span.
+///
+/// Records the span name and kind, sets the custom attributes, links and trigger type,
+/// and sets the parent context extracted from the carrier headers, if any.
+pub(crate) fn apply_span_attributes(span: &Span, attrs: SpanAttributes) {
+    if let Some(span_name) = attrs.span_name {
+        span.record("otel.name", span_name);
+    }
+
+    if let Some(kind) = &attrs.kind {
+        span.record("otel.kind", kind.to_string());
+    }
+
+    for (key, value) in &attrs.attributes {
+        span.set_attribute(key.to_string(), value.to_string());
+    }
+
+    for link in attrs.links {
+        span.add_link_with_attributes(link.span_context, link.attributes);
+    }
+
+    // Propagate context from headers
+    if let Some(carrier) = attrs.carrier {
+        // Header names are case-insensitive, but propagators look up lowercase names
+        let carrier: HashMap<String, String> = carrier
+            .into_iter()
+            .map(|(key, value)| (key.to_lowercase(), value))
+            .collect();
+        let parent_context = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&carrier)
+        });
+        // Baggage is extracted even when the baggage propagator is not configured
+        let parent_context =
+            BaggagePropagator::new().extract_with_context(&parent_context, &carrier);
+        let _ = span.set_parent(parent_context);
+    }
+
+    span.set_attribute("faas.trigger", attrs.trigger);
+}
+
+/// Closure-based span attribute extractor for event types that don't implement
+/// [`SpanAttributesExtractor`].
+///
+/// This is what [`create_traced_handler_with_extractor`](crate::create_traced_handler_with_extractor)
+/// and [`OtelTracingLayer::with_extractor`](crate::OtelTracingLayer::with_extractor) store.
+/// It removes the need for a newtype wrapper around event types from other crates, such as
+/// the ones in `aws-lambda-events`.
+pub type SpanAttributesExtractorFn<T> = Arc<dyn Fn(&T) -> SpanAttributes + Send + Sync>;
+
+/// Extractor that only keeps the default span attributes.
+///
+/// Use it with [`create_traced_handler_with_extractor`](crate::create_traced_handler_with_extractor)
+/// when the common Lambda attributes are enough for an event type.
+pub fn no_span_attributes<T>(_event: &T) -> SpanAttributes {
+    SpanAttributes::default()
+}
+
 /// Trait for types that can provide span attributes.
 ///
 /// This trait enables automatic extraction of OpenTelemetry span attributes from event types.
@@ -723,12 +798,397 @@ impl SpanAttributesExtractor for AlbTargetGroupRequest {
     }
 }
 
+/// Implementation for HTTP requests, as passed by `lambda_http` to axum routers and other
+/// Tower HTTP services.
+///
+/// Extracts standard HTTP attributes following OpenTelemetry semantic conventions:
+/// - `http.request.method`: The HTTP method
+/// - `url.path`: The request path
+/// - `url.query`: The query string if present
+/// - `url.scheme`: The URI scheme, or the `x-forwarded-proto` header
+/// - `network.protocol.version`: The HTTP protocol version
+/// - `client.address`: The first address of the `x-forwarded-for` header
+/// - `user_agent.original`: The user agent header
+/// - `server.address`: The URI host, or the `host` header
+///
+/// The route is only known once the request is routed, so `http.route` is not set and the
+/// span is named after the path. Also extracts W3C Trace Context headers and AWS X-Ray
+/// headers for distributed tracing.
+impl<B> SpanAttributesExtractor for Request<B> {
+    fn extract_span_attributes(&self) -> SpanAttributes {
+        let mut attributes = HashMap::new();
+        let method = self.method().to_string();
+        let path = self.uri().path();
+        let header = |name: &str| self.headers().get(name).and_then(|v| v.to_str().ok());
+
+        attributes.insert(
+            "http.request.method".to_string(),
+            Value::String(method.clone().into()),
+        );
+        attributes.insert(
+            "url.path".to_string(),
+            Value::String(path.to_string().into()),
+        );
+
+        if let Some(query) = self.uri().query() {
+            if !query.is_empty() {
+                attributes.insert(
+                    "url.query".to_string(),
+                    Value::String(query.to_string().into()),
+                );
+            }
+        }
+
+        if let Some(scheme) = self
+            .uri()
+            .scheme_str()
+            .or_else(|| header("x-forwarded-proto"))
+        {
+            attributes.insert(
+                "url.scheme".to_string(),
+                Value::String(scheme.to_lowercase().into()),
+            );
+        }
+
+        let protocol_version = match self.version() {
+            Version::HTTP_09 => Some("0.9"),
+            Version::HTTP_10 => Some("1.0"),
+            Version::HTTP_11 => Some("1.1"),
+            Version::HTTP_2 => Some("2"),
+            Version::HTTP_3 => Some("3"),
+            _ => None,
+        };
+        if let Some(version) = protocol_version {
+            attributes.insert(
+                "network.protocol.version".to_string(),
+                Value::String(version.to_string().into()),
+            );
+        }
+
+        // The client is the first address of the forwarding chain
+        if let Some(client_ip) = header("x-forwarded-for")
+            .and_then(|v| v.split(',').next())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+        {
+            attributes.insert(
+                "client.address".to_string(),
+                Value::String(client_ip.to_string().into()),
+            );
+        }
+
+        if let Some(user_agent) = header("user-agent") {
+            attributes.insert(
+                "user_agent.original".to_string(),
+                Value::String(user_agent.to_string().into()),
+            );
+        }
+
+        if let Some(host) = self.uri().host().or_else(|| header("host")) {
+            attributes.insert(
+                "server.address".to_string(),
+                Value::String(host.to_string().into()),
+            );
+        }
+
+        // Extract headers for context propagation
+        let carrier = self
+            .headers()
+            .iter()
+            .filter_map(|(k, v)| v.to_str().ok().map(|v| (k.to_string(), v.to_string())))
+            .collect();
+
+        SpanAttributes::builder()
+            .attributes(attributes)
+            .carrier(carrier)
+            .span_name(format!("{method} {path}"))
+            .trigger(TriggerType::Http.to_string())
+            .build()
+    }
+}
+
+/// Implementation for SQS events.
+///
+/// Follows the messaging semantic conventions for a consumer processing a batch:
+/// - `messaging.system`: "aws_sqs"
+/// - `messaging.operation.type`: "process"
+/// - `messaging.destination.name`: The queue name, from the event source ARN
+/// - `messaging.message.id`: The message ID, for a single message
+/// - `messaging.batch.message_count`: The number of messages, for a batch
+///
+/// Each message carrying a trace context, in the `AWSTraceHeader` system attribute or in
+/// `traceparent`/`tracestate` message attributes, adds a span link to its producer.
+impl SpanAttributesExtractor for SqsEvent {
+    fn extract_span_attributes(&self) -> SpanAttributes {
+        let queue = self
+            .records
+            .first()
+            .and_then(|record| record.event_source_arn.as_deref())
+            .map(|arn| arn_resource(arn).to_string());
+        let message_ids: Vec<&str> = self
+            .records
+            .iter()
+            .filter_map(|record| record.message_id.as_deref())
+            .collect();
+
+        let links = self
+            .records
+            .iter()
+            .filter_map(|record| {
+                let mut carrier: HashMap<String, String> = record
+                    .message_attributes
+                    .iter()
+                    .filter_map(|(key, attribute)| {
+                        attribute
+                            .string_value
+                            .as_ref()
+                            .map(|value| (key.to_lowercase(), value.clone()))
+                    })
+                    .collect();
+                if let Some(header) = record.attributes.get("AWSTraceHeader") {
+                    carrier.insert("x-amzn-trace-id".to_string(), header.clone());
+                }
+                producer_link(&carrier, record.message_id.as_deref())
+            })
+            .collect();
+
+        consumer_span_attributes(
+            messaging_attributes("aws_sqs", queue.as_deref(), &message_ids),
+            queue,
+            links,
+            TriggerType::PubSub,
+        )
+    }
+}
+
+/// Implementation for SNS events.
+///
+/// Sets the same messaging attributes as [`SqsEvent`], with `messaging.system` set to "aws.sns"
+/// and the topic name, from the topic ARN, as destination. Messages carrying a trace context in
+/// `traceparent`/`tracestate` or `X-Amzn-Trace-Id` message attributes add span links to their
+/// producers.
+impl SpanAttributesExtractor for SnsEvent {
+    fn extract_span_attributes(&self) -> SpanAttributes {
+        let topic = self
+            .records
+            .first()
+            .map(|record| arn_resource(&record.sns.topic_arn).to_string());
+        let message_ids: Vec<&str> = self
+            .records
+            .iter()
+            .map(|record| record.sns.message_id.as_str())
+            .collect();
+
+        let links = self
+            .records
+            .iter()
+            .filter_map(|record| {
+                let carrier: HashMap<String, String> = record
+                    .sns
+                    .message_attributes
+                    .iter()
+                    .map(|(key, attribute)| (key.to_lowercase(), attribute.value.clone()))
+                    .collect();
+                producer_link(&carrier, Some(&record.sns.message_id))
+            })
+            .collect();
+
+        consumer_span_attributes(
+            messaging_attributes("aws.sns", topic.as_deref(), &message_ids),
+            topic,
+            links,
+            TriggerType::PubSub,
+        )
+    }
+}
+
+/// Implementation for Kinesis events.
+///
+/// Sets the same messaging attributes as [`SqsEvent`], with `messaging.system` set to
+/// "aws.kinesis", the stream name as destination and the sequence numbers as message IDs.
+/// Kinesis records have no attributes to carry a trace context, so no span links are added.
+impl SpanAttributesExtractor for KinesisEvent {
+    fn extract_span_attributes(&self) -> SpanAttributes {
+        let stream = self
+            .records
+            .first()
+            .and_then(|record| record.event_source_arn.as_deref())
+            .map(|arn| {
+                let resource = arn_resource(arn);
+                resource
+                    .strip_prefix("stream/")
+                    .unwrap_or(resource)
+                    .to_string()
+            });
+        let message_ids: Vec<&str> = self
+            .records
+            .iter()
+            .map(|record| record.kinesis.sequence_number.as_str())
+            .collect();
+
+        consumer_span_attributes(
+            messaging_attributes("aws.kinesis", stream.as_deref(), &message_ids),
+            stream,
+            Vec::new(),
+            TriggerType::PubSub,
+        )
+    }
+}
+
+/// Implementation for DynamoDB Streams events.
+///
+/// Follows the semantic conventions of datasource triggers:
+/// - `faas.trigger`: "datasource"
+/// - `faas.document.collection`: The table name, from the event source ARN
+/// - `faas.document.operation`: "insert", "edit" or "delete", when all the records share it
+/// - `faas.document.time`: The creation time of the change, for a single record
+///
+/// The span is named `process {table}` and has the CONSUMER kind, like the messaging events.
+impl SpanAttributesExtractor for DynamoDbEvent {
+    fn extract_span_attributes(&self) -> SpanAttributes {
+        let mut attributes = HashMap::new();
+        let table = self.records.first().and_then(|record| {
+            record.table_name.clone().or_else(|| {
+                let resource = arn_resource(record.event_source_arn.as_deref()?);
+                let table = resource.strip_prefix("table/")?;
+                Some(table.split('/').next().unwrap_or(table).to_string())
+            })
+        });
+        if let Some(table) = &table {
+            attributes.insert(
+                "faas.document.collection".to_string(),
+                Value::String(table.clone().into()),
+            );
+        }
+
+        let mut operations = self
+            .records
+            .iter()
+            .map(|record| match record.event_name.as_str() {
+                "INSERT" => "insert",
+                "MODIFY" => "edit",
+                "REMOVE" => "delete",
+                _ => "",
+            });
+        if let Some(operation) = operations.next().filter(|op| !op.is_empty()) {
+            if operations.all(|other| other == operation) {
+                attributes.insert(
+                    "faas.document.operation".to_string(),
+                    Value::String(operation.into()),
+                );
+            }
+        }
+        if let [record] = self.records.as_slice() {
+            attributes.insert(
+                "faas.document.time".to_string(),
+                Value::String(
+                    record
+                        .change
+                        .approximate_creation_date_time
+                        .to_rfc3339()
+                        .into(),
+                ),
+            );
+        }
+
+        consumer_span_attributes(attributes, table, Vec::new(), TriggerType::Datasource)
+    }
+}
+
+// Returns the resource of an ARN: the queue, topic, `stream/{name}` or `table/{name}/...`
+fn arn_resource(arn: &str) -> &str {
+    arn.splitn(6, ':').nth(5).unwrap_or(arn)
+}
+
+// Builds the messaging attributes of the messages consumed from a destination
+fn messaging_attributes(
+    system: &str,
+    destination: Option<&str>,
+    message_ids: &[&str],
+) -> HashMap<String, Value> {
+    let mut attributes = HashMap::new();
+    attributes.insert(
+        "messaging.system".to_string(),
+        Value::String(system.to_string().into()),
+    );
+    attributes.insert(
+        "messaging.operation.type".to_string(),
+        Value::String("process".into()),
+    );
+    if let Some(destination) = destination {
+        attributes.insert(
+            "messaging.destination.name".to_string(),
+            Value::String(destination.to_string().into()),
+        );
+    }
+    match message_ids {
+        [message_id] => {
+            attributes.insert(
+                "messaging.message.id".to_string(),
+                Value::String(message_id.to_string().into()),
+            );
+        }
+        [] => {}
+        _ => {
+            attributes.insert(
+                "messaging.batch.message_count".to_string(),
+                Value::I64(message_ids.len() as i64),
+            );
+        }
+    }
+    attributes
+}
+
+// Builds the span attributes of a consumer processing the records of a source
+fn consumer_span_attributes(
+    attributes: HashMap<String, Value>,
+    source: Option<String>,
+    links: Vec<Link>,
+    trigger: TriggerType,
+) -> SpanAttributes {
+    let span_name = match source {
+        Some(source) => format!("process {source}"),
+        None => "process".to_string(),
+    };
+
+    SpanAttributes::builder()
+        .kind("CONSUMER".to_string())
+        .span_name(span_name)
+        .attributes(attributes)
+        .links(links)
+        .trigger(trigger.to_string())
+        .build()
+}
+
+// Links to the producer of a message whose trace headers carry a valid span context.
+// W3C Trace Context takes precedence over X-Ray. Unlike the Lambda X-Ray propagator, the
+// `_X_AMZN_TRACE_ID` environment variable is never used, as it is the context of the invocation.
+fn producer_link(carrier: &HashMap<String, String>, message_id: Option<&str>) -> Option<Link> {
+    if carrier.is_empty() {
+        return None;
+    }
+    let root = opentelemetry::Context::new();
+    let span_context = [
+        TraceContextPropagator::new().extract_with_context(&root, carrier),
+        XrayPropagator::new().extract_with_context(&root, carrier),
+    ]
+    .iter()
+    .map(|cx| cx.span().span_context().clone())
+    .find(|span_context| span_context.is_valid())?;
+
+    let attributes = message_id
+        .map(|id| vec![KeyValue::new("messaging.message.id", id.to_string())])
+        .unwrap_or_default();
+    Some(Link::new(span_context, attributes, 0))
+}
+
 /// Default implementation for serde_json::Value.
 ///
 /// This implementation provides a fallback for when the event type is not known
 /// or when working with raw JSON data. It returns default attributes with
 /// the trigger type set to "other".
-/// If there's a headers field, it will be used to populate the carrier.
+/// If there's a headers field, it will be used to populate the carrier, with the header
+/// names lowercased like the ones of the typed HTTP events.
 impl SpanAttributesExtractor for serde_json::Value {
     fn extract_span_attributes(&self) -> SpanAttributes {
         let carrier = self
@@ -736,7 +1196,7 @@ impl SpanAttributesExtractor for serde_json::Value {
             .and_then(|headers| headers.as_object())
             .map(|obj| {
                 obj.iter()
-                    .filter_map(|(k, v)| v.as_str().map(|v| (k.to_string(), v.to_string())))
+                    .filter_map(|(k, v)| v.as_str().map(|v| (k.to_lowercase(), v.to_string())))
                     .collect()
             })
             .unwrap_or_default();
@@ -751,7 +1211,12 @@ impl SpanAttributesExtractor for serde_json::Value {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::propagation::LambdaXrayPropagator;
     use aws_lambda_events::http::Method;
+    use opentelemetry::trace::{TraceContextExt, TraceId, TracerProvider as _};
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use sealed_test::prelude::*;
+    use tracing_subscriber::layer::SubscriberExt;
 
     #[test]
     fn test_trigger_types() {
@@ -809,6 +1274,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_http_request_extraction() {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("https://api.example.com/orders?id=1")
+            .header("user-agent", "test-agent")
+            .header("x-forwarded-for", "203.0.113.1, 10.0.0.1")
+            .header(
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            )
+            .body(())
+            .unwrap();
+
+        let attrs = request.extract_span_attributes();
+
+        assert_eq!(attrs.span_name.as_deref(), Some("POST /orders"));
+        assert_eq!(attrs.trigger, TriggerType::Http.to_string());
+        for (key, value) in [
+            ("http.request.method", "POST"),
+            ("url.path", "/orders"),
+            ("url.query", "id=1"),
+            ("url.scheme", "https"),
+            ("network.protocol.version", "1.1"),
+            ("client.address", "203.0.113.1"),
+            ("user_agent.original", "test-agent"),
+            ("server.address", "api.example.com"),
+        ] {
+            assert_eq!(
+                attrs.attributes.get(key),
+                Some(&Value::String(value.to_string().into())),
+                "attribute: {key}"
+            );
+        }
+        assert!(attrs.carrier.unwrap().contains_key("traceparent"));
+
+        // Relative URIs fall back to the forwarding headers
+        let request = Request::builder()
+            .uri("/health")
+            .header("host", "internal.example.com")
+            .header("x-forwarded-proto", "HTTP")
+            .body(())
+            .unwrap();
+
+        let attrs = request.extract_span_attributes();
+
+        assert_eq!(attrs.span_name.as_deref(), Some("GET /health"));
+        assert_eq!(
+            attrs.attributes.get("server.address"),
+            Some(&Value::String("internal.example.com".to_string().into()))
+        );
+        assert_eq!(
+            attrs.attributes.get("url.scheme"),
+            Some(&Value::String("http".to_string().into()))
+        );
+        assert!(!attrs.attributes.contains_key("url.query"));
+        assert!(!attrs.attributes.contains_key("client.address"));
+    }
+
     #[test]
     fn test_apigw_v1_extraction() {
         let mut request = ApiGatewayProxyRequest::default();
@@ -942,8 +1466,8 @@ mod tests {
         // Create a JSON value with headers including X-Ray
         let json_value = serde_json::json!({
             "headers": {
-                "x-amzn-trace-id": "Root=1-58406520-a006649127e371903a2de979;Parent=4c721bf33e3caf8f;Sampled=1",
-                "traceparent": "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+                "X-Amzn-Trace-Id": "Root=1-58406520-a006649127e371903a2de979;Parent=4c721bf33e3caf8f;Sampled=1",
+                "Traceparent": "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
                 "content-type": "application/json"
             },
             "body": "{\"message\":\"Hello World\"}",
@@ -959,7 +1483,7 @@ mod tests {
         assert!(attrs.carrier.is_some());
         let carrier = attrs.carrier.unwrap();
 
-        // Both trace headers should be present
+        // Both trace headers should be present, with lowercased names
         assert!(carrier.contains_key("x-amzn-trace-id"));
         assert_eq!(
             carrier.get("x-amzn-trace-id").unwrap(),
@@ -972,4 +1496,224 @@ mod tests {
             "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
         );
     }
+
+    #[test]
+    fn test_sqs_extraction() {
+        let event: SqsEvent = serde_json::from_value(serde_json::json!({
+            "Records": [
+                {
+                    "messageId": "msg-1",
+                    "attributes": {
+                        "AWSTraceHeader": "Root=1-58406520-a006649127e371903a2de979;Parent=4c721bf33e3caf8f;Sampled=1"
+                    },
+                    "messageAttributes": {},
+                    "eventSourceARN": "arn:aws:sqs:us-east-1:123456789012:orders"
+                },
+                {
+                    "messageId": "msg-2",
+                    "attributes": {},
+                    "messageAttributes": {
+                        "traceparent": {
+                            "stringValue": "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+                            "stringListValues": [],
+                            "binaryListValues": [],
+                            "dataType": "String"
+                        }
+                    },
+                    "eventSourceARN": "arn:aws:sqs:us-east-1:123456789012:orders"
+                },
+                {
+                    "messageId": "msg-3",
+                    "attributes": {},
+                    "messageAttributes": {},
+                    "eventSourceARN": "arn:aws:sqs:us-east-1:123456789012:orders"
+                }
+            ]
+        }))
+        .unwrap();
+
+        let attrs = event.extract_span_attributes();
+        assert_eq!(attrs.kind.as_deref(), Some("CONSUMER"));
+        assert_eq!(attrs.span_name.as_deref(), Some("process orders"));
+        assert_eq!(attrs.trigger, TriggerType::PubSub.to_string());
+        assert_eq!(
+            attrs.attributes.get("messaging.system"),
+            Some(&Value::String("aws_sqs".into()))
+        );
+        assert_eq!(
+            attrs.attributes.get("messaging.destination.name"),
+            Some(&Value::String("orders".into()))
+        );
+        assert_eq!(
+            attrs.attributes.get("messaging.batch.message_count"),
+            Some(&Value::I64(3))
+        );
+        assert!(!attrs.attributes.contains_key("messaging.message.id"));
+
+        // The messages carrying a trace context link to their producers
+        assert_eq!(attrs.links.len(), 2);
+        assert_eq!(
+            attrs.links[0].span_context.trace_id(),
+            TraceId::from_hex("58406520a006649127e371903a2de979").unwrap()
+        );
+        assert_eq!(
+            attrs.links[1].span_context.trace_id(),
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap()
+        );
+        assert_eq!(
+            attrs.links[1].attributes,
+            vec![KeyValue::new("messaging.message.id", "msg-2")]
+        );
+    }
+
+    #[test]
+    fn test_sns_extraction() {
+        let event: SnsEvent = serde_json::from_value(serde_json::json!({
+            "Records": [{
+                "EventSource": "aws:sns",
+                "EventVersion": "1.0",
+                "EventSubscriptionArn": "arn:aws:sns:us-east-1:123456789012:alerts:sub",
+                "Sns": {
+                    "Type": "Notification",
+                    "MessageId": "msg-1",
+                    "TopicArn": "arn:aws:sns:us-east-1:123456789012:alerts",
+                    "Timestamp": "2025-01-01T00:00:00.000Z",
+                    "SignatureVersion": "1",
+                    "Signature": "",
+                    "SigningCertUrl": "",
+                    "UnsubscribeUrl": "",
+                    "Message": "hello",
+                    "MessageAttributes": {
+                        "Traceparent": {
+                            "Type": "String",
+                            "Value": "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+                        }
+                    }
+                }
+            }]
+        }))
+        .unwrap();
+
+        let attrs = event.extract_span_attributes();
+        assert_eq!(attrs.span_name.as_deref(), Some("process alerts"));
+        assert_eq!(
+            attrs.attributes.get("messaging.system"),
+            Some(&Value::String("aws.sns".into()))
+        );
+        assert_eq!(
+            attrs.attributes.get("messaging.message.id"),
+            Some(&Value::String("msg-1".into()))
+        );
+        assert_eq!(attrs.links.len(), 1);
+        assert!(attrs.carrier.is_none());
+    }
+
+    #[test]
+    fn test_kinesis_and_dynamodb_extraction() {
+        let event: KinesisEvent = serde_json::from_value(serde_json::json!({
+            "Records": [{
+                "eventSourceARN": "arn:aws:kinesis:us-east-1:123456789012:stream/clicks",
+                "kinesis": {
+                    "approximateArrivalTimestamp": 1735689600.0,
+                    "data": "aGVsbG8=",
+                    "partitionKey": "user-1",
+                    "sequenceNumber": "4959"
+                }
+            }]
+        }))
+        .unwrap();
+        let attrs = event.extract_span_attributes();
+        assert_eq!(attrs.span_name.as_deref(), Some("process clicks"));
+        assert_eq!(
+            attrs.attributes.get("messaging.message.id"),
+            Some(&Value::String("4959".into()))
+        );
+        assert!(attrs.links.is_empty());
+
+        let record = serde_json::json!({
+            "awsRegion": "us-east-1",
+            "eventID": "1",
+            "eventName": "INSERT",
+            "eventSourceARN": "arn:aws:dynamodb:us-east-1:123456789012:table/users/stream/2025-01-01T00:00:00.000",
+            "dynamodb": {
+                "ApproximateCreationDateTime": 1735689600,
+                "Keys": {"id": {"S": "1"}},
+                "SizeBytes": 26,
+                "StreamViewType": "KEYS_ONLY"
+            }
+        });
+        let mut modified = record.clone();
+        modified["eventName"] = "MODIFY".into();
+
+        let event: DynamoDbEvent =
+            serde_json::from_value(serde_json::json!({ "Records": [record.clone()] })).unwrap();
+        let attrs = event.extract_span_attributes();
+        assert_eq!(attrs.span_name.as_deref(), Some("process users"));
+        assert_eq!(attrs.trigger, TriggerType::Datasource.to_string());
+        assert_eq!(
+            attrs.attributes.get("faas.document.collection"),
+            Some(&Value::String("users".into()))
+        );
+        assert_eq!(
+            attrs.attributes.get("faas.document.operation"),
+            Some(&Value::String("insert".into()))
+        );
+        assert!(attrs.attributes.contains_key("faas.document.time"));
+
+        // A batch of different operations has no single operation
+        let event: DynamoDbEvent =
+            serde_json::from_value(serde_json::json!({ "Records": [record, modified] })).unwrap();
+        let attrs = event.extract_span_attributes();
+        assert!(!attrs.attributes.contains_key("faas.document.operation"));
+        assert!(!attrs.attributes.contains_key("faas.document.time"));
+    }
+
+    #[test]
+    #[sealed_test]
+    fn test_apply_span_attributes_mixed_case_xray_header() {
+        opentelemetry::global::set_text_map_propagator(LambdaXrayPropagator::new());
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        // Carriers built by hand may keep the `X-Amzn-Trace-Id` spelling of the header
+        let carrier = HashMap::from([(
+            "X-Amzn-Trace-Id".to_string(),
+            "Root=1-58406520-a006649127e371903a2de979;Parent=4c721bf33e3caf8f;Sampled=1"
+                .to_string(),
+        )]);
+        let trace_id = tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("handler");
+            apply_span_attributes(&span, SpanAttributes::builder().carrier(carrier).build());
+            span.context().span().span_context().trace_id()
+        });
+
+        assert_eq!(
+            trace_id,
+            TraceId::from_hex("58406520a006649127e371903a2de979").unwrap()
+        );
+    }
+
+    #[test]
+    #[sealed_test]
+    fn test_apply_span_attributes_extracts_baggage() {
+        // The baggage propagator is not configured
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        let carrier = HashMap::from([(
+            "baggage".to_string(),
+            "tenant-id=acme,user-tier=gold".to_string(),
+        )]);
+        let tenant_id = tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("handler");
+            apply_span_attributes(&span, SpanAttributes::builder().carrier(carrier).build());
+            let _guard = span.enter();
+            crate::baggage::get("tenant-id")
+        });
+
+        assert_eq!(tenant_id.as_deref(), Some("acme"));
+    }
 }
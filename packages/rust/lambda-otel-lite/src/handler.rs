@@ -21,6 +21,7 @@
 //! - Automatic context propagation from HTTP headers
 //! - Response status code tracking
 //! - Custom attribute extraction
+//! - Exception events for handler errors and panics
 //!
 //! # Architecture
 //!
@@ -31,6 +32,21 @@
 //! 4. Capturing response attributes (e.g., status code)
 //! 5. Signaling completion for span export
 //!
+//! # Errors and Panics
+//!
+//! When the handler returns an `Err` or panics, the span gets an `exception` event with the
+//! `exception.type` and `exception.message` attributes, and an error status. For panics, the
+//! event also has an `exception.stacktrace`, captured by a panic hook installed on the first
+//! invocation, which calls the previously installed hook. Telemetry is flushed before the
+//! error is returned or the panic resumed, so crashes show up as failed spans.
+//!
+//! # Response Streaming
+//!
+//! For handlers returning a `StreamResponse`, [`create_traced_streaming_handler`] hands the
+//! invocation span over to the response stream, which ends it and signals completion when the
+//! stream completes, rather than when the handler returns. The time to first byte is recorded
+//! as a `first_byte` span event.
+//!
 //! # Performance Considerations
 //!
 //! The wrapper is designed to minimize overhead:
@@ -58,135 +74,275 @@
 //! - You want standardized instrumentation
 //!
 //! # Examples
-///
-/// ```rust,no_run
-/// use std::result::Result;
-/// use lambda_runtime::{Error, LambdaEvent};
-/// use serde_json::Value;
-/// use lambda_otel_lite::{init_telemetry, create_traced_handler, TelemetryConfig};
-///
-/// async fn my_handler(event: LambdaEvent<Value>) -> Result<Value, Error> {
-///     let prefix = event.payload.get("prefix").and_then(|p| p.as_str()).unwrap_or("default");
-///     Ok::<Value, Error>(serde_json::json!({ "prefix": prefix }))
-/// }
-///
-/// # #[tokio::main]
-/// # async fn main() -> Result<(), Error> {
-///     let (_, completion_handler) = init_telemetry(TelemetryConfig::default()).await?;
-///     let handler = create_traced_handler(
-///         "my-handler",
-///         completion_handler,
-///         my_handler
-///     );
-///     // ... use handler with Runtime ...
-/// #   Ok(())
-/// # }
-/// ```
-use crate::extractors::{set_common_attributes, set_response_attributes, SpanAttributesExtractor};
+//!
+//! ```rust,no_run
+//! use std::result::Result;
+//! use lambda_runtime::{Error, LambdaEvent};
+//! use serde_json::Value;
+//! use lambda_otel_lite::{init_telemetry, create_traced_handler, TelemetryConfig};
+//!
+//! async fn my_handler(event: LambdaEvent<Value>) -> Result<Value, Error> {
+//!     let prefix = event.payload.get("prefix").and_then(|p| p.as_str()).unwrap_or("default");
+//!     Ok::<Value, Error>(serde_json::json!({ "prefix": prefix }))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Error> {
+//!     let (_, completion_handler) = init_telemetry(TelemetryConfig::default()).await?;
+//!     let handler = create_traced_handler(
+//!         "my-handler",
+//!         completion_handler,
+//!         my_handler
+//!     );
+//!     // ... use handler with Runtime ...
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::coldstart::record_init_span;
+use crate::extractors::{
+    apply_span_attributes, set_common_attributes, set_response_attributes,
+    set_status_code_attributes, SpanAttributes, SpanAttributesExtractor,
+};
 use crate::TelemetryCompletionHandler;
 use futures_util::future::BoxFuture;
-use lambda_runtime::{Error, LambdaEvent};
+use futures_util::{FutureExt, Stream};
+use lambda_runtime::{Error, LambdaEvent, StreamResponse};
+use opentelemetry::trace::Status;
+use opentelemetry::KeyValue;
 use serde::{de::DeserializeOwned, Serialize};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::fmt::Debug;
 use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use std::task::{Context, Poll};
+use std::time::Instant;
 use tracing::field::Empty;
-use tracing::Instrument;
+use tracing::{Instrument, Span};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 static IS_COLD_START: AtomicBool = AtomicBool::new(true);
 
+static PANIC_HOOK: Once = Once::new();
+
+thread_local! {
+    // Whether this thread is polling a traced handler, whose panics are caught and recorded
+    static CAPTURE_PANIC_BACKTRACE: Cell<bool> = const { Cell::new(false) };
+    // Backtrace of the last panic on this thread, captured by the panic hook
+    static PANIC_BACKTRACE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Installs a panic hook capturing the backtrace of the panics of traced handlers, then
+/// calling the previous hook.
+///
+/// Once a panic is caught the stack is unwound, so the backtrace has to be captured when
+/// the panic happens. Panics elsewhere in the process are left to the previous hook.
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if CAPTURE_PANIC_BACKTRACE.get() {
+                PANIC_BACKTRACE.with(|backtrace| {
+                    *backtrace.borrow_mut() = Some(Backtrace::force_capture().to_string());
+                });
+            }
+            previous(info);
+        }));
+    });
+}
+
+/// Marks the current thread as polling a traced handler, until dropped.
+struct CapturePanicBacktrace(bool);
+
+impl CapturePanicBacktrace {
+    fn enter() -> Self {
+        Self(CAPTURE_PANIC_BACKTRACE.replace(true))
+    }
+}
+
+impl Drop for CapturePanicBacktrace {
+    fn drop(&mut self) {
+        CAPTURE_PANIC_BACKTRACE.set(self.0);
+    }
+}
+
+/// Records an `exception` event and sets the error status on the span.
+fn record_exception(
+    span: &Span,
+    exception_type: String,
+    message: String,
+    stacktrace: Option<String>,
+) {
+    let mut attributes = vec![
+        KeyValue::new("exception.type", exception_type),
+        KeyValue::new("exception.message", message.clone()),
+    ];
+    if let Some(stacktrace) = stacktrace {
+        attributes.push(KeyValue::new("exception.stacktrace", stacktrace));
+    }
+    span.add_event("exception", attributes);
+    span.set_status(Status::error(message));
+}
+
+/// Returns the type name of an error, from the start of its `Debug` representation.
+///
+/// The concrete type of a boxed error is not known, but `Debug` is derived for most error
+/// types, and starts with the type name. Falls back to `Error`, for example for errors
+/// created from strings.
+fn error_type(error: &(impl Debug + ?Sized)) -> String {
+    let debug = format!("{error:?}");
+    let name: String = debug
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == ':')
+        .collect();
+    if name.starts_with(char::is_uppercase) {
+        name
+    } else {
+        "Error".to_string()
+    }
+}
+
+/// Records the `exception` event of a panic, with the backtrace captured by the panic hook.
+fn record_panic(span: &Span, payload: &(dyn Any + Send)) {
+    let stacktrace = PANIC_BACKTRACE.with(|backtrace| backtrace.borrow_mut().take());
+    record_exception(
+        span,
+        "panic".to_string(),
+        panic_message(payload),
+        stacktrace,
+    );
+}
+
+/// Returns the message of a panic payload.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
 /// Type representing a traced Lambda handler function.
 /// Takes a `LambdaEvent<T>` and returns a `Future` that resolves to `Result<R, Error>`.
 pub type TracedHandler<T, R> =
     Box<dyn Fn(LambdaEvent<T>) -> BoxFuture<'static, Result<R, Error>> + Send + Sync>;
 
+/// Type representing a traced response streaming Lambda handler function.
+/// Takes a `LambdaEvent<T>` and returns a `Future` that resolves to a `StreamResponse`
+/// of a [`TracedStream`].
+pub type TracedStreamingHandler<T, S> = Box<
+    dyn Fn(LambdaEvent<T>) -> BoxFuture<'static, Result<StreamResponse<TracedStream<S>>, Error>>
+        + Send
+        + Sync,
+>;
+
+/// Creates the invocation span, with the common Lambda attributes and the attributes
+/// extracted from the event, and records the init span of a cold start.
+fn start_invocation_span<T, X>(
+    name: &'static str,
+    event: &LambdaEvent<T>,
+    completion_handler: &TelemetryCompletionHandler,
+    extractor: X,
+) -> Span
+where
+    X: FnOnce(&T) -> SpanAttributes,
+{
+    // Create the base span
+    let span = tracing::info_span!(
+        parent: None,
+        "handler",
+        otel.name=Empty,
+        otel.kind=Empty,
+        otel.status_code=Empty,
+        otel.status_message=Empty,
+        requestId=%event.context.request_id,
+    );
+
+    // Set the span name and default kind
+    span.record("otel.name", name.to_string());
+    span.record("otel.kind", "SERVER");
+
+    // Set common Lambda attributes with cold start tracking
+    let is_cold = IS_COLD_START.swap(false, Ordering::Relaxed);
+    set_common_attributes(&span, &event.context, is_cold);
+
+    // Extract and apply attributes from the event
+    apply_span_attributes(&span, extractor(&event.payload));
+
+    // Record the init span of a cold start, once the trace of the invocation is known
+    record_init_span(&span, completion_handler.get_tracer());
+
+    span
+}
+
+/// Runs the handler future within the span, catching its panics.
+async fn catch_handler_panic<Fut: Future>(
+    future: Fut,
+    span: &Span,
+) -> Result<Fut::Output, Box<dyn Any + Send>> {
+    install_panic_hook();
+    let mut future =
+        std::pin::pin!(AssertUnwindSafe(future.instrument(span.clone())).catch_unwind());
+    // The future may move between threads, so the flag is set around each poll
+    std::future::poll_fn(|cx| {
+        let _capture = CapturePanicBacktrace::enter();
+        future.as_mut().poll(cx)
+    })
+    .await
+}
+
 /// Internal implementation that wraps a Lambda handler function with OpenTelemetry tracing.
 ///
-/// This is an implementation detail. Users should use `create_traced_handler` instead.
-pub(crate) async fn traced_handler<T, R, F, Fut>(
+/// This is an implementation detail. Users should use `create_traced_handler` or
+/// `create_traced_handler_with_extractor` instead.
+pub(crate) async fn traced_handler<T, R, F, Fut, X>(
     name: &'static str,
     event: LambdaEvent<T>,
     completion_handler: TelemetryCompletionHandler,
+    extractor: X,
     handler_fn: F,
 ) -> Result<R, Error>
 where
-    T: SpanAttributesExtractor + DeserializeOwned + Serialize + Send + 'static,
+    T: DeserializeOwned + Serialize + Send + 'static,
+    X: FnOnce(&T) -> SpanAttributes,
     R: Serialize + Send + 'static,
     F: FnOnce(LambdaEvent<T>) -> Fut,
     Fut: Future<Output = Result<R, Error>> + Send,
 {
     let result = {
-        // Create the base span
-        let span = tracing::info_span!(
-            parent: None,
-            "handler",
-            otel.name=Empty,
-            otel.kind=Empty,
-            otel.status_code=Empty,
-            otel.status_message=Empty,
-            requestId=%event.context.request_id,
-        );
-
-        // Set the span name and default kind
-        span.record("otel.name", name.to_string());
-        span.record("otel.kind", "SERVER");
-
-        // Set common Lambda attributes with cold start tracking
-        let is_cold = IS_COLD_START.swap(false, Ordering::Relaxed);
-        set_common_attributes(&span, &event.context, is_cold);
-
-        // Extract attributes directly using the trait
-        let attrs = event.payload.extract_span_attributes();
-
-        // Apply extracted attributes
-        if let Some(span_name) = attrs.span_name {
-            span.record("otel.name", span_name);
-        }
-
-        if let Some(kind) = &attrs.kind {
-            span.record("otel.kind", kind.to_string());
-        }
-
-        // Set custom attributes
-        for (key, value) in &attrs.attributes {
-            span.set_attribute(key.to_string(), value.to_string());
-        }
-
-        // Add span links
-        for link in attrs.links {
-            span.add_link_with_attributes(link.span_context, link.attributes);
-        }
-
-        // Propagate context from headers
-        if let Some(carrier) = attrs.carrier {
-            let parent_context = opentelemetry::global::get_text_map_propagator(|propagator| {
-                propagator.extract(&carrier)
-            });
-            let _ = span.set_parent(parent_context);
-        }
-
-        // Set trigger type
-        span.set_attribute("faas.trigger", attrs.trigger.to_string());
+        let span = start_invocation_span(name, &event, &completion_handler, extractor);
 
-        // Run the handler with the span
-        let result = handler_fn(event).instrument(span.clone()).await;
+        // Run the handler with the span, catching panics
+        let result = catch_handler_panic(async move { handler_fn(event).await }, &span).await;
 
-        // Set response attributes if successful
-        if let Ok(response) = &result {
-            if let Ok(value) = serde_json::to_value(response) {
-                set_response_attributes(&span, &value);
+        match &result {
+            // Set response attributes if successful
+            Ok(Ok(response)) => {
+                if let Ok(value) = serde_json::to_value(response) {
+                    set_response_attributes(&span, &value);
+                }
             }
-        } else if let Err(error) = &result {
-            // Set error status according to OpenTelemetry spec
-            span.set_status(opentelemetry::trace::Status::error(error.to_string()));
+            // Record the error and set the error status according to OpenTelemetry spec
+            Ok(Err(error)) => record_exception(&span, error_type(error), error.to_string(), None),
+            Err(payload) => record_panic(&span, payload.as_ref()),
         }
 
         result
     };
 
-    // Signal completion
+    // Signal completion, then resume a panic of the handler
     completion_handler.complete();
-    result
+    match result {
+        Ok(result) => result,
+        Err(payload) => panic::resume_unwind(payload),
+    }
 }
 
 /// Creates a traced handler function that can be used directly with `service_fn`.
@@ -267,11 +423,323 @@ where
     R: Serialize + Send + 'static,
     F: Fn(LambdaEvent<T>) -> Fut + Send + Sync + Clone + 'static,
     Fut: Future<Output = Result<R, Error>> + Send + 'static,
+{
+    create_traced_handler_with_extractor(
+        name,
+        completion_handler,
+        T::extract_span_attributes,
+        handler_fn,
+    )
+}
+
+/// Creates a traced handler function for any serde event type, using a closure to extract
+/// span attributes.
+///
+/// This works like [`create_traced_handler`], but the event type doesn't need to implement
+/// [`SpanAttributesExtractor`]. Events from other crates (e.g. `aws-lambda-events`) can be
+/// used directly, without a newtype wrapper to satisfy the orphan rule. Pass
+/// [`no_span_attributes`](crate::extractors::no_span_attributes) to only record the common
+/// Lambda attributes.
+///
+/// # Arguments
+///
+/// * `name` - Name of the handler/span
+/// * `completion_handler` - Handler for managing span export
+/// * `extractor` - Function extracting span attributes from the event payload
+/// * `handler_fn` - The actual Lambda handler function to wrap
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use aws_lambda_events::event::kinesis::KinesisEvent;
+/// use lambda_runtime::{service_fn, Error, LambdaEvent, Runtime};
+/// use lambda_otel_lite::{
+///     create_traced_handler_with_extractor, init_telemetry, SpanAttributes, TelemetryConfig,
+/// };
+/// use std::collections::HashMap;
+///
+/// async fn my_handler(event: LambdaEvent<KinesisEvent>) -> Result<(), Error> {
+///     Ok(())
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+///     let (_, completion_handler) = init_telemetry(TelemetryConfig::default()).await?;
+///     let handler = create_traced_handler_with_extractor(
+///         "kinesis-handler",
+///         completion_handler,
+///         |event: &KinesisEvent| {
+///             SpanAttributes::builder()
+///                 .kind("consumer".to_string())
+///                 .attributes(HashMap::from([(
+///                     "messaging.batch.message_count".to_string(),
+///                     (event.records.len() as i64).into(),
+///                 )]))
+///                 .build()
+///         },
+///         my_handler,
+///     );
+///     Runtime::new(service_fn(handler)).run().await
+/// # }
+/// ```
+pub fn create_traced_handler_with_extractor<T, R, F, Fut, X>(
+    name: &'static str,
+    completion_handler: TelemetryCompletionHandler,
+    extractor: X,
+    handler_fn: F,
+) -> TracedHandler<T, R>
+where
+    T: DeserializeOwned + Serialize + Send + 'static,
+    R: Serialize + Send + 'static,
+    F: Fn(LambdaEvent<T>) -> Fut + Send + Sync + Clone + 'static,
+    Fut: Future<Output = Result<R, Error>> + Send + 'static,
+    X: Fn(&T) -> SpanAttributes + Send + Sync + Clone + 'static,
 {
     Box::new(move |event: LambdaEvent<T>| {
         let completion_handler = completion_handler.clone();
+        let extractor = extractor.clone();
         let handler_fn = handler_fn.clone();
-        Box::pin(traced_handler(name, event, completion_handler, handler_fn))
+        Box::pin(traced_handler(
+            name,
+            event,
+            completion_handler,
+            extractor,
+            handler_fn,
+        ))
+    })
+}
+
+/// Response stream of a traced streaming handler.
+///
+/// Polls the inner stream within the invocation span, and ends the span when the stream
+/// completes or is dropped, rather than when the handler returns:
+/// - The first chunk records a `first_byte` event, with the `time_to_first_byte_ms`
+///   elapsed since the invocation started
+/// - An error chunk records an `exception` event and sets the error status
+/// - The end of the stream sets the span status from the status code of the response,
+///   then signals completion for span export
+pub struct TracedStream<S> {
+    stream: S,
+    span: Option<Span>,
+    completion_handler: Option<TelemetryCompletionHandler>,
+    started: Instant,
+    status_code: u16,
+    first_byte_recorded: bool,
+    failed: bool,
+}
+
+impl<S> TracedStream<S> {
+    fn finish(&mut self) {
+        if let Some(span) = self.span.take() {
+            if self.failed {
+                // Keep the error status set by the stream error
+                span.set_attribute("http.status_code", self.status_code.to_string());
+                span.set_attribute("http.response.status_code", self.status_code.to_string());
+            } else {
+                set_status_code_attributes(&span, self.status_code.into());
+            }
+        }
+        // Now that the span is closed, complete telemetry
+        if let Some(handler) = self.completion_handler.take() {
+            handler.complete();
+        }
+    }
+}
+
+impl<S, D, E> Stream for TracedStream<S>
+where
+    S: Stream<Item = Result<D, E>> + Unpin,
+    E: Debug,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let poll = {
+            let _guard = this.span.as_ref().map(Span::enter);
+            Pin::new(&mut this.stream).poll_next(cx)
+        };
+
+        match &poll {
+            Poll::Ready(Some(Ok(_))) if !this.first_byte_recorded => {
+                this.first_byte_recorded = true;
+                if let Some(span) = &this.span {
+                    let elapsed = this.started.elapsed().as_millis() as i64;
+                    span.add_event(
+                        "first_byte",
+                        vec![KeyValue::new("time_to_first_byte_ms", elapsed)],
+                    );
+                }
+            }
+            Poll::Ready(Some(Err(error))) => {
+                this.failed = true;
+                if let Some(span) = &this.span {
+                    record_exception(span, error_type(error), format!("{error:?}"), None);
+                }
+            }
+            Poll::Ready(None) => this.finish(),
+            _ => {}
+        }
+
+        poll
+    }
+}
+
+impl<S> Drop for TracedStream<S> {
+    fn drop(&mut self) {
+        // The stream may be dropped before it completes, for example when the client disconnects
+        self.finish();
+    }
+}
+
+/// Internal implementation of a traced response streaming handler.
+///
+/// Creates the invocation span like [`traced_handler`], but hands it over to the
+/// [`TracedStream`] of a successful response, which ends it when the stream completes.
+pub(crate) async fn traced_streaming_handler<T, S, F, Fut, X>(
+    name: &'static str,
+    event: LambdaEvent<T>,
+    completion_handler: TelemetryCompletionHandler,
+    extractor: X,
+    handler_fn: F,
+) -> Result<StreamResponse<TracedStream<S>>, Error>
+where
+    T: DeserializeOwned + Serialize + Send + 'static,
+    X: FnOnce(&T) -> SpanAttributes,
+    F: FnOnce(LambdaEvent<T>) -> Fut,
+    Fut: Future<Output = Result<StreamResponse<S>, Error>> + Send,
+{
+    let started = Instant::now();
+    let span = start_invocation_span(name, &event, &completion_handler, extractor);
+
+    // Run the handler with the span, catching panics
+    let result = catch_handler_panic(async move { handler_fn(event).await }, &span).await;
+
+    let result = match result {
+        Ok(Ok(response)) => {
+            let status_code = response.metadata_prelude.status_code.as_u16();
+            return Ok(StreamResponse {
+                metadata_prelude: response.metadata_prelude,
+                stream: TracedStream {
+                    stream: response.stream,
+                    span: Some(span),
+                    completion_handler: Some(completion_handler),
+                    started,
+                    status_code,
+                    first_byte_recorded: false,
+                    failed: false,
+                },
+            });
+        }
+        Ok(Err(error)) => {
+            record_exception(&span, error_type(&error), error.to_string(), None);
+            Ok(Err(error))
+        }
+        Err(payload) => {
+            record_panic(&span, payload.as_ref());
+            Err(payload)
+        }
+    };
+
+    // The handler failed before streaming: signal completion, then resume a panic
+    drop(span);
+    completion_handler.complete();
+    match result {
+        Ok(result) => result,
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
+
+/// Creates a traced response streaming handler using the event's [`SpanAttributesExtractor`]
+/// implementation.
+///
+/// Like [`create_traced_handler`], but for handlers returning a [`StreamResponse`]: the
+/// invocation span ends when the response stream completes rather than when the handler
+/// returns, so it covers the whole response, and records the time to first byte as a
+/// `first_byte` event. See [`TracedStream`].
+///
+/// # Arguments
+///
+/// * `name` - Name of the handler/span
+/// * `completion_handler` - Handler for managing span export
+/// * `handler_fn` - The streaming handler function to wrap
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use futures_util::stream::{self, Iter};
+/// use lambda_otel_lite::{create_traced_streaming_handler, init_telemetry, TelemetryConfig};
+/// use lambda_runtime::{service_fn, Error, LambdaEvent, StreamResponse};
+/// use serde_json::Value;
+/// use std::vec::IntoIter;
+///
+/// type Chunks = Iter<IntoIter<Result<&'static str, Error>>>;
+///
+/// async fn handler(_event: LambdaEvent<Value>) -> Result<StreamResponse<Chunks>, Error> {
+///     Ok(StreamResponse::from(stream::iter(vec![Ok("Hello, "), Ok("world!")])))
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// let (_, completion_handler) = init_telemetry(TelemetryConfig::default()).await?;
+/// let handler = create_traced_streaming_handler("streaming-handler", completion_handler, handler);
+/// lambda_runtime::run(service_fn(handler)).await
+/// # }
+/// ```
+pub fn create_traced_streaming_handler<T, S, F, Fut>(
+    name: &'static str,
+    completion_handler: TelemetryCompletionHandler,
+    handler_fn: F,
+) -> TracedStreamingHandler<T, S>
+where
+    T: SpanAttributesExtractor + DeserializeOwned + Serialize + Send + 'static,
+    S: Send + 'static,
+    F: Fn(LambdaEvent<T>) -> Fut + Send + Sync + Clone + 'static,
+    Fut: Future<Output = Result<StreamResponse<S>, Error>> + Send + 'static,
+{
+    create_traced_streaming_handler_with_extractor(
+        name,
+        completion_handler,
+        T::extract_span_attributes,
+        handler_fn,
+    )
+}
+
+/// Creates a traced response streaming handler that extracts span attributes with a closure.
+///
+/// Like [`create_traced_handler_with_extractor`], for handlers returning a
+/// [`StreamResponse`]. See [`create_traced_streaming_handler`].
+///
+/// # Arguments
+///
+/// * `name` - Name of the handler/span
+/// * `completion_handler` - Handler for managing span export
+/// * `extractor` - Function extracting span attributes from the event payload
+/// * `handler_fn` - The streaming handler function to wrap
+pub fn create_traced_streaming_handler_with_extractor<T, S, F, Fut, X>(
+    name: &'static str,
+    completion_handler: TelemetryCompletionHandler,
+    extractor: X,
+    handler_fn: F,
+) -> TracedStreamingHandler<T, S>
+where
+    T: DeserializeOwned + Serialize + Send + 'static,
+    S: Send + 'static,
+    F: Fn(LambdaEvent<T>) -> Fut + Send + Sync + Clone + 'static,
+    Fut: Future<Output = Result<StreamResponse<S>, Error>> + Send + 'static,
+    X: Fn(&T) -> SpanAttributes + Send + Sync + Clone + 'static,
+{
+    Box::new(move |event: LambdaEvent<T>| {
+        let completion_handler = completion_handler.clone();
+        let extractor = extractor.clone();
+        let handler_fn = handler_fn.clone();
+        Box::pin(traced_streaming_handler(
+            name,
+            event,
+            completion_handler,
+            extractor,
+            handler_fn,
+        ))
     })
 }
 
@@ -490,4 +958,233 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_handler_with_extractor() -> Result<(), Error> {
+        let (provider, exporter, _guard) = setup_test_provider();
+        let completion_handler =
+            TelemetryCompletionHandler::new(provider, None, ProcessorMode::Sync);
+
+        // A plain serde type that doesn't implement SpanAttributesExtractor
+        #[derive(serde::Deserialize, serde::Serialize)]
+        struct OrderEvent {
+            order_id: String,
+        }
+
+        async fn handler(event: LambdaEvent<OrderEvent>) -> Result<Value, Error> {
+            Ok(serde_json::json!({ "orderId": event.payload.order_id }))
+        }
+
+        let traced_handler = create_traced_handler_with_extractor(
+            "test-handler",
+            completion_handler,
+            |event: &OrderEvent| {
+                SpanAttributes::builder()
+                    .span_name(format!("process {}", event.order_id))
+                    .kind("consumer".to_string())
+                    .attributes(std::collections::HashMap::from([(
+                        "order.id".to_string(),
+                        event.order_id.clone().into(),
+                    )]))
+                    .build()
+            },
+            handler,
+        );
+        let event = LambdaEvent::new(
+            OrderEvent {
+                order_id: "42".to_string(),
+            },
+            Context::default(),
+        );
+
+        let result = traced_handler(event).await?;
+
+        wait_for_spans(Duration::from_millis(100)).await;
+
+        let spans = exporter.get_spans();
+        assert_eq!(spans.len(), 1, "Expected exactly 1 span");
+        assert_eq!(spans[0].name, "process 42");
+        assert_eq!(
+            TestExporter::find_attribute(&spans[0], "order.id"),
+            Some("42".to_string())
+        );
+        assert_eq!(result["orderId"], "42");
+
+        Ok(())
+    }
+
+    fn find_exception_event(span: &SpanData) -> Option<&opentelemetry::trace::Event> {
+        span.events.iter().find(|event| event.name == "exception")
+    }
+
+    fn event_attribute(event: &opentelemetry::trace::Event, key: &str) -> Option<String> {
+        event
+            .attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| kv.value.to_string())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_handler_error_records_exception() -> Result<(), Error> {
+        let (provider, exporter, _guard) = setup_test_provider();
+        let completion_handler =
+            TelemetryCompletionHandler::new(provider, None, ProcessorMode::Sync);
+
+        async fn handler(_: LambdaEvent<Value>) -> Result<Value, Error> {
+            let number: i32 = "not a number".parse()?;
+            Ok(serde_json::json!({ "number": number }))
+        }
+
+        let traced_handler = create_traced_handler("test-handler", completion_handler, handler);
+        let event = LambdaEvent::new(serde_json::json!({}), Context::default());
+
+        let error = traced_handler(event).await.unwrap_err();
+
+        let spans = exporter.get_spans();
+        assert_eq!(spans.len(), 1, "Expected exactly 1 span");
+        assert_eq!(spans[0].status, Status::error(error.to_string()));
+
+        let event = find_exception_event(&spans[0]).expect("No exception event");
+        assert_eq!(
+            event_attribute(event, "exception.type"),
+            Some("ParseIntError".to_string())
+        );
+        assert_eq!(
+            event_attribute(event, "exception.message"),
+            Some(error.to_string())
+        );
+        assert_eq!(event_attribute(event, "exception.stacktrace"), None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_handler_panic_records_exception() {
+        let (provider, exporter, _guard) = setup_test_provider();
+        let completion_handler =
+            TelemetryCompletionHandler::new(provider, None, ProcessorMode::Sync);
+
+        async fn handler(_: LambdaEvent<Value>) -> Result<Value, Error> {
+            panic!("handler crashed");
+        }
+
+        let traced_handler = create_traced_handler("test-handler", completion_handler, handler);
+        let event = LambdaEvent::new(serde_json::json!({}), Context::default());
+
+        // The panic is resumed after the span is exported
+        let payload = AssertUnwindSafe(traced_handler(event))
+            .catch_unwind()
+            .await
+            .unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "handler crashed");
+
+        let spans = exporter.get_spans();
+        assert_eq!(spans.len(), 1, "Expected exactly 1 span");
+        assert!(matches!(spans[0].status, Status::Error { .. }));
+
+        let event = find_exception_event(&spans[0]).expect("No exception event");
+        assert_eq!(
+            event_attribute(event, "exception.type"),
+            Some("panic".to_string())
+        );
+        assert_eq!(
+            event_attribute(event, "exception.message"),
+            Some("handler crashed".to_string())
+        );
+        assert!(event_attribute(event, "exception.stacktrace").is_some());
+    }
+
+    #[test]
+    fn test_panic_outside_handler_skips_backtrace() {
+        install_panic_hook();
+        PANIC_BACKTRACE.with(|backtrace| backtrace.borrow_mut().take());
+
+        let result = panic::catch_unwind(|| panic!("not traced"));
+        assert!(result.is_err());
+        assert!(PANIC_BACKTRACE.with(|backtrace| backtrace.borrow().is_none()));
+        assert!(!CAPTURE_PANIC_BACKTRACE.get());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_streaming_handler_ends_span_with_stream() -> Result<(), Error> {
+        use futures_util::StreamExt;
+
+        let (provider, exporter, _guard) = setup_test_provider();
+        let completion_handler =
+            TelemetryCompletionHandler::new(provider, None, ProcessorMode::Sync);
+
+        let handler = |_: LambdaEvent<Value>| async {
+            let chunks: Vec<Result<&'static str, Error>> =
+                vec![Ok("first"), Ok("second"), Err("broken pipe".into())];
+            Ok::<_, Error>(StreamResponse::from(futures_util::stream::iter(chunks)))
+        };
+
+        let traced_handler =
+            create_traced_streaming_handler("test-handler", completion_handler, handler);
+        let event = LambdaEvent::new(serde_json::json!({}), Context::default());
+
+        let mut response = traced_handler(event).await?;
+
+        // The span is still open while the response streams
+        assert_eq!(response.stream.next().await.unwrap()?, "first");
+        assert!(exporter.get_spans().is_empty());
+
+        let remaining: Vec<_> = response.stream.by_ref().collect().await;
+        assert_eq!(remaining.len(), 2);
+
+        let spans = exporter.get_spans();
+        assert_eq!(spans.len(), 1, "Expected exactly 1 span");
+        let span = &spans[0];
+        assert_eq!(span.name, "test-handler");
+        assert_eq!(
+            TestExporter::find_attribute(span, "http.status_code"),
+            Some("200".to_string())
+        );
+        assert!(matches!(span.status, Status::Error { .. }));
+
+        let first_byte = span
+            .events
+            .iter()
+            .find(|event| event.name == "first_byte")
+            .expect("No first_byte event");
+        assert!(event_attribute(first_byte, "time_to_first_byte_ms").is_some());
+        let exception = find_exception_event(span).expect("No exception event");
+        assert_eq!(
+            event_attribute(exception, "exception.message"),
+            Some("\"broken pipe\"".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_streaming_handler_dropped_stream() -> Result<(), Error> {
+        let (provider, exporter, _guard) = setup_test_provider();
+        let completion_handler =
+            TelemetryCompletionHandler::new(provider, None, ProcessorMode::Sync);
+
+        let handler = |_: LambdaEvent<Value>| async {
+            let chunks: Vec<Result<&'static str, Error>> = vec![Ok("first")];
+            Ok::<_, Error>(StreamResponse::from(futures_util::stream::iter(chunks)))
+        };
+
+        let traced_handler =
+            create_traced_streaming_handler("test-handler", completion_handler, handler);
+        let event = LambdaEvent::new(serde_json::json!({}), Context::default());
+
+        // Dropping the stream before it completes still ends and exports the span
+        drop(traced_handler(event).await?);
+
+        let spans = exporter.get_spans();
+        assert_eq!(spans.len(), 1, "Expected exactly 1 span");
+        assert_eq!(spans[0].status, Status::Ok);
+
+        Ok(())
+    }
 }
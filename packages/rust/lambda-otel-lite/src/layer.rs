@@ -32,6 +32,7 @@
 //! - Built-in support for common AWS event types:
 //!   - API Gateway v1/v2 (HTTP method, path, route, protocol)
 //!   - Application Load Balancer (HTTP method, path, target group ARN)
+//! - HTTP services of `http::Request`, such as axum routers run with `lambda_http`
 //! - Extensible attribute extraction through the `SpanAttributesExtractor` trait
 //! - Custom attribute extraction through closure-based extractors
 //! - Automatic context propagation from HTTP headers
@@ -62,6 +63,35 @@
 //! }
 //! ```
 //!
+//! # HTTP Services
+//!
+//! The layer also wraps Tower services of `http::Request` and `http::Response`, such as an
+//! axum `Router` run with `lambda_http`. HTTP semantic convention attributes are extracted
+//! from the request, the Lambda context is read from the request extensions, where
+//! `lambda_http` stores it, and the response status code is recorded on the span:
+//!
+//! ```no_run
+//! use http::{Request, Response};
+//! use lambda_otel_lite::{init_telemetry, OtelTracingLayer, TelemetryConfig};
+//! use lambda_runtime::Error;
+//! use tower::ServiceBuilder;
+//!
+//! async fn handler(request: Request<String>) -> Result<Response<String>, Error> {
+//!     Ok(Response::new(format!("Hello from {}", request.uri().path())))
+//! }
+//!
+//! # async fn example() -> Result<(), Error> {
+//! let (_, completion_handler) = init_telemetry(TelemetryConfig::default()).await?;
+//!
+//! let service = ServiceBuilder::new()
+//!     .layer(OtelTracingLayer::<Request<String>>::new(completion_handler).with_name("http-handler"))
+//!     .service_fn(handler);
+//!
+//! // With lambda_http, run it with `lambda_http::run(service).await`
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! # Custom Attribute Extraction
 //!
 //! You can implement the `SpanAttributesExtractor` trait for your own event types:
@@ -95,19 +125,26 @@
 //! # Response Tracking
 //!
 //! For HTTP responses, the layer automatically:
-//! - Sets `http.status_code` from the response statusCode
+//! - Sets `http.status_code` from the response statusCode, or the status of an
+//!   `http::Response`
 //! - Sets span status to ERROR for 5xx responses
 //! - Sets span status to OK for all other responses
 
-use crate::extractors::{set_common_attributes, set_response_attributes, SpanAttributesExtractor};
+use crate::coldstart::record_init_span;
+use crate::extractors::{
+    apply_span_attributes, set_common_attributes, set_response_attributes,
+    set_status_code_attributes, SpanAttributes, SpanAttributesExtractor, SpanAttributesExtractorFn,
+};
 use crate::TelemetryCompletionHandler;
+use aws_lambda_events::http::{Request, Response};
 use futures_util::ready;
-use lambda_runtime::{Error, LambdaEvent};
+use lambda_runtime::{Context, Error, LambdaEvent};
 use opentelemetry::trace::Status;
 use pin_project::pin_project;
 use serde::{de::DeserializeOwned, Serialize};
-use std::marker::PhantomData;
+use std::sync::Arc;
 use std::{
+    fmt::Display,
     future::Future,
     pin::Pin,
     task::{self, Poll},
@@ -179,6 +216,58 @@ where
     }
 }
 
+/// Future that records the status of an HTTP response and calls complete() on the
+/// completion handler when the inner future completes.
+///
+/// This is the [`CompletionFuture`] of HTTP services: the span status is set from the
+/// status code of the `http::Response`, or from the error of the inner service.
+///
+/// This type is created automatically by `OtelTracingService` - you shouldn't need to
+/// construct it directly.
+#[pin_project]
+pub struct HttpCompletionFuture<Fut> {
+    #[pin]
+    future: Option<Fut>,
+    completion_handler: Option<TelemetryCompletionHandler>,
+    span: Option<tracing::Span>,
+}
+
+impl<Fut, B, E> Future for HttpCompletionFuture<Fut>
+where
+    Fut: Future<Output = Result<Response<B>, E>>,
+    E: Display,
+{
+    type Output = Result<Response<B>, E>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let ready = ready!(self
+            .as_mut()
+            .project()
+            .future
+            .as_pin_mut()
+            .expect("future polled after completion")
+            .poll(cx));
+
+        if let Some(span) = self.span.as_ref() {
+            match &ready {
+                Ok(response) => set_status_code_attributes(span, response.status().as_u16().into()),
+                Err(error) => span.set_status(Status::error(error.to_string())),
+            }
+        }
+
+        // Drop the future and span before calling complete
+        Pin::set(&mut self.as_mut().project().future, None);
+        let this = self.project();
+        this.span.take();
+
+        if let Some(handler) = this.completion_handler.take() {
+            handler.complete();
+        }
+
+        Poll::Ready(ready)
+    }
+}
+
 /// Tower middleware to create an OpenTelemetry tracing span for Lambda invocations.
 ///
 /// This layer wraps a Lambda service to automatically create and configure OpenTelemetry
@@ -215,14 +304,27 @@ where
 /// Runtime::new(service).run().await
 /// # }
 /// ```
-#[derive(Clone)]
-pub struct OtelTracingLayer<T: SpanAttributesExtractor> {
+///
+/// For HTTP services, such as an axum `Router` run with `lambda_http`, use the request type
+/// as the event type: `OtelTracingLayer::<lambda_http::Request>::new(completion_handler)`.
+pub struct OtelTracingLayer<T> {
     completion_handler: TelemetryCompletionHandler,
     name: String,
-    _phantom: PhantomData<T>,
+    extractor: SpanAttributesExtractorFn<T>,
 }
 
-impl<T: SpanAttributesExtractor> OtelTracingLayer<T> {
+// Implemented manually so that the event type doesn't need to be `Clone`
+impl<T> Clone for OtelTracingLayer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            completion_handler: self.completion_handler.clone(),
+            name: self.name.clone(),
+            extractor: self.extractor.clone(),
+        }
+    }
+}
+
+impl<T: SpanAttributesExtractor + 'static> OtelTracingLayer<T> {
     /// Create a new OpenTelemetry tracing layer with the required completion handler.
     ///
     /// The completion handler is used to signal when spans should be exported. It's typically
@@ -232,10 +334,29 @@ impl<T: SpanAttributesExtractor> OtelTracingLayer<T> {
     ///
     /// * `completion_handler` - Handler for managing span export timing
     pub fn new(completion_handler: TelemetryCompletionHandler) -> Self {
+        Self::with_extractor(completion_handler, T::extract_span_attributes)
+    }
+}
+
+impl<T> OtelTracingLayer<T> {
+    /// Create a new OpenTelemetry tracing layer that extracts span attributes with a closure.
+    ///
+    /// Unlike [`new`](Self::new), the event type doesn't need to implement
+    /// [`SpanAttributesExtractor`], so events from other crates can be used without a
+    /// newtype wrapper.
+    ///
+    /// # Arguments
+    ///
+    /// * `completion_handler` - Handler for managing span export timing
+    /// * `extractor` - Function extracting span attributes from the event payload
+    pub fn with_extractor<X>(completion_handler: TelemetryCompletionHandler, extractor: X) -> Self
+    where
+        X: Fn(&T) -> SpanAttributes + Send + Sync + 'static,
+    {
         Self {
             completion_handler,
             name: "lambda-invocation".to_string(),
-            _phantom: PhantomData,
+            extractor: Arc::new(extractor),
         }
     }
 
@@ -253,10 +374,7 @@ impl<T: SpanAttributesExtractor> OtelTracingLayer<T> {
     }
 }
 
-impl<S, T> Layer<S> for OtelTracingLayer<T>
-where
-    T: SpanAttributesExtractor + Clone,
-{
+impl<S, T> Layer<S> for OtelTracingLayer<T> {
     type Service = OtelTracingService<S, T>;
 
     fn layer(&self, inner: S) -> Self::Service {
@@ -265,7 +383,7 @@ where
             completion_handler: self.completion_handler.clone(),
             name: self.name.clone(),
             is_cold_start: true,
-            _phantom: PhantomData,
+            extractor: self.extractor.clone(),
         }
     }
 }
@@ -281,20 +399,31 @@ where
 ///
 /// The service is created automatically by the layer - you shouldn't need to
 /// construct it directly.
-#[derive(Clone)]
-pub struct OtelTracingService<S, T: SpanAttributesExtractor> {
+pub struct OtelTracingService<S, T> {
     inner: S,
     completion_handler: TelemetryCompletionHandler,
     name: String,
     is_cold_start: bool,
-    _phantom: PhantomData<T>,
+    extractor: SpanAttributesExtractorFn<T>,
+}
+
+impl<S: Clone, T> Clone for OtelTracingService<S, T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            completion_handler: self.completion_handler.clone(),
+            name: self.name.clone(),
+            is_cold_start: self.is_cold_start,
+            extractor: self.extractor.clone(),
+        }
+    }
 }
 
 impl<S, F, T, R> Service<LambdaEvent<T>> for OtelTracingService<S, T>
 where
     S: Service<LambdaEvent<T>, Response = R, Error = Error, Future = F> + Send,
     F: Future<Output = Result<R, Error>> + Send + 'static,
-    T: SpanAttributesExtractor + DeserializeOwned + Serialize + Send + 'static,
+    T: DeserializeOwned + Serialize + Send + 'static,
     R: Serialize + Send + 'static,
 {
     type Response = R;
@@ -326,43 +455,76 @@ where
             self.is_cold_start = false;
         }
 
-        // Extract attributes directly using the trait
-        let attrs = event.payload.extract_span_attributes();
+        // Extract and apply attributes from the event
+        apply_span_attributes(&span, (self.extractor)(&event.payload));
 
-        // Apply extracted attributes
-        if let Some(span_name) = attrs.span_name {
-            span.record("otel.name", span_name);
-        }
+        // Record the init span of a cold start, once the trace of the invocation is known
+        record_init_span(&span, self.completion_handler.get_tracer());
 
-        if let Some(kind) = &attrs.kind {
-            span.record("otel.kind", kind.to_string());
-        }
+        let future = {
+            let _guard = span.enter();
+            self.inner.call(event)
+        };
 
-        for (key, value) in &attrs.attributes {
-            span.set_attribute(key.to_string(), value.to_string());
+        CompletionFuture {
+            future: Some(future.instrument(span.clone())),
+            completion_handler: Some(self.completion_handler.clone()),
+            span: Some(span),
         }
+    }
+}
 
-        for link in attrs.links {
-            span.add_link_with_attributes(link.span_context, link.attributes);
-        }
+impl<S, B, RB> Service<Request<B>> for OtelTracingService<S, Request<B>>
+where
+    S: Service<Request<B>, Response = Response<RB>> + Send,
+    S::Error: Display,
+{
+    type Response = Response<RB>;
+    type Error = S::Error;
+    type Future = HttpCompletionFuture<Instrumented<S::Future>>;
+
+    fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let span = tracing::info_span!(
+            parent: None,
+            "handler",
+            otel.name=Empty,
+            otel.kind=Empty,
+            otel.status_code=Empty,
+            otel.status_message=Empty,
+            requestId=Empty,
+        );
 
-        // Propagate context from headers
-        if let Some(carrier) = attrs.carrier {
-            let parent_context = opentelemetry::global::get_text_map_propagator(|propagator| {
-                propagator.extract(&carrier)
-            });
-            let _ = span.set_parent(parent_context);
+        // Set the span name and default kind
+        span.record("otel.name", self.name.clone());
+        span.record("otel.kind", "SERVER");
+
+        // Set common Lambda attributes from the context stored by lambda_http, if any
+        match request.extensions().get::<Context>() {
+            Some(context) => {
+                span.record("requestId", context.request_id.as_str());
+                set_common_attributes(&span, context, self.is_cold_start);
+            }
+            None if self.is_cold_start => span.set_attribute("faas.coldstart", true),
+            None => {}
         }
+        self.is_cold_start = false;
+
+        // Extract and apply attributes from the request
+        apply_span_attributes(&span, (self.extractor)(&request));
 
-        // Set trigger type
-        span.set_attribute("faas.trigger", attrs.trigger.to_string());
+        // Record the init span of a cold start, once the trace of the invocation is known
+        record_init_span(&span, self.completion_handler.get_tracer());
 
         let future = {
             let _guard = span.enter();
-            self.inner.call(event)
+            self.inner.call(request)
         };
 
-        CompletionFuture {
+        HttpCompletionFuture {
             future: Some(future.instrument(span.clone())),
             completion_handler: Some(self.completion_handler.clone()),
             span: Some(span),
@@ -461,4 +623,96 @@ mod tests {
 
         Ok(())
     }
+
+    // Mock exporter that captures exported spans
+    #[derive(Debug, Clone, Default)]
+    struct CapturingExporter {
+        spans: Arc<std::sync::Mutex<Vec<SpanData>>>,
+    }
+
+    impl SpanExporter for CapturingExporter {
+        async fn export(&self, batch: Vec<SpanData>) -> opentelemetry_sdk::error::OTelSdkResult {
+            self.spans.lock().unwrap().extend(batch);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_http_layer() -> Result<(), Error> {
+        let exporter = CapturingExporter::default();
+        let provider = Arc::new(
+            SdkTracerProvider::builder()
+                .with_simple_exporter(exporter.clone())
+                .with_resource(Resource::builder().build())
+                .build(),
+        );
+        let _subscriber = tracing_subscriber::registry::Registry::default()
+            .with(tracing_opentelemetry::OpenTelemetryLayer::new(
+                provider.tracer("test"),
+            ))
+            .set_default();
+
+        let completion_handler =
+            TelemetryCompletionHandler::new(provider.clone(), None, ProcessorMode::Sync);
+
+        let handler = |request: Request<String>| async move {
+            let status = if request.uri().path() == "/fail" {
+                503
+            } else {
+                200
+            };
+            Ok::<_, Error>(
+                Response::builder()
+                    .status(status)
+                    .body(String::new())
+                    .unwrap(),
+            )
+        };
+
+        let mut svc = tower::ServiceBuilder::new()
+            .layer(OtelTracingLayer::<Request<String>>::new(completion_handler))
+            .service_fn(handler);
+
+        let mut context = Context::default();
+        context.request_id = "test-request-id".to_string();
+        let mut request = Request::builder()
+            .method("GET")
+            .uri("/users?id=1")
+            .body(String::new())
+            .unwrap();
+        request.extensions_mut().insert(context);
+        let response = svc.ready().await?.call(request).await?;
+        assert_eq!(response.status(), 200);
+
+        let request = Request::builder().uri("/fail").body(String::new()).unwrap();
+        let response = svc.ready().await?.call(request).await?;
+        assert_eq!(response.status(), 503);
+
+        let spans = exporter.spans.lock().unwrap();
+        assert_eq!(spans.len(), 2);
+
+        let attribute = |span: &SpanData, key: &str| {
+            span.attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == key)
+                .map(|kv| kv.value.to_string())
+        };
+        assert_eq!(spans[0].name, "GET /users");
+        assert_eq!(spans[0].span_kind, opentelemetry::trace::SpanKind::Server);
+        assert_eq!(attribute(&spans[0], "url.query").as_deref(), Some("id=1"));
+        assert_eq!(
+            attribute(&spans[0], "faas.invocation_id").as_deref(),
+            Some("test-request-id")
+        );
+        assert_eq!(
+            attribute(&spans[0], "http.response.status_code").as_deref(),
+            Some("200")
+        );
+        assert_eq!(spans[0].status, Status::Ok);
+        assert_eq!(spans[1].name, "GET /fail");
+        assert!(matches!(spans[1].status, Status::Error { .. }));
+
+        Ok(())
+    }
 }
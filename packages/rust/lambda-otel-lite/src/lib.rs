@@ -39,10 +39,29 @@
 //!   - Custom attribute support
 //!
 //! - [`extractors`]: Event processing
-//!   - Built-in support for API Gateway and ALB events
+//!   - Built-in support for API Gateway, ALB, SQS, SNS, Kinesis and DynamoDB Streams events
 //!   - Extensible trait system for custom events
 //!   - W3C Trace Context propagation
 //!
+//! - [`baggage`]: W3C Baggage support
+//!   - Baggage of incoming requests available to handlers
+//!   - Allowed entries copied onto spans and events as attributes
+//!
+//! - [`coldstart`]: Cold start init span
+//!   - Records the init phase as a child of the first invocation span
+//!   - Starts at the process start time, read from `/proc`
+//!   - Only in on-demand execution environments
+//!
+//! - [`diagnostics`]: Configuration diagnostics
+//!   - Snapshot of the resolved configuration via `telemetry_config_snapshot`
+//!   - Logged once at DEBUG level during initialization
+//!   - Sensitive values redacted
+//!
+//! - [`logs`]: Logs bridge
+//!   - Converts `tracing` events into OTLP log records
+//!   - Attaches the trace context of the current span
+//!   - Exported to stdout with the spans
+//!
 //! - [`events`]: Structured event recording
 //!   - Record queryable events within spans
 //!   - Dual API: function-based and builder-based
@@ -55,11 +74,13 @@
 //!   - Best for complex services with middleware chains
 //!   - Integrates with Tower's service ecosystem
 //!   - Standardized instrumentation across services
+//!   - Also wraps `http::Request` services, such as axum routers run with `lambda_http`
 //!
 //! - [`handler`]: Direct function wrapper
 //!   - Best for simple Lambda functions
 //!   - Lower overhead for basic use cases
 //!   - Quick integration with existing handlers
+//!   - Response streaming handlers, with the span ending when the stream completes
 //!
 //! # Processing Modes
 //!
@@ -96,6 +117,10 @@
 //!   - Defaults to 2048 spans
 //!   - Should be tuned based on span volume
 //!
+//! - `LAMBDA_SPAN_PROCESSOR_OVERFLOW_STRATEGY`: What to do when the buffer is full
+//!   - "drop_new" (default), "drop_oldest" or "block"
+//!   - `LAMBDA_SPAN_PROCESSOR_BLOCK_TIMEOUT_MS` bounds the wait of "block" (default: 100)
+//!
 //! - `OTEL_SERVICE_NAME`: Service name for spans
 //!   - Falls back to AWS_LAMBDA_FUNCTION_NAME
 //!   - Required for proper service identification
@@ -160,26 +185,38 @@
 
 pub use otlp_stdout_span_exporter::OtlpStdoutSpanExporter;
 
+pub mod baggage;
+pub mod coldstart;
 pub mod constants;
+pub mod diagnostics;
 pub mod events;
 pub mod extension;
 pub mod extractors;
 pub mod handler;
 pub mod layer;
 pub mod logger;
+pub mod logs;
 pub mod mode;
 pub mod processor;
 pub mod propagation;
 pub mod resource;
 pub mod telemetry;
 
+pub use diagnostics::{telemetry_config_snapshot, TelemetryConfigSnapshot};
 pub use events::{event, record_event, EventLevel};
 pub use extension::OtelInternalExtension;
-pub use extractors::{SpanAttributes, SpanAttributesExtractor, TriggerType};
-pub use handler::create_traced_handler;
+pub use extractors::{
+    no_span_attributes, SpanAttributes, SpanAttributesExtractor, SpanAttributesExtractorFn,
+    TriggerType,
+};
+pub use handler::{
+    create_traced_handler, create_traced_handler_with_extractor, create_traced_streaming_handler,
+    create_traced_streaming_handler_with_extractor,
+};
 pub use layer::OtelTracingLayer;
+pub use logs::OtelLogLayer;
 pub use mode::ProcessorMode;
-pub use processor::LambdaSpanProcessor;
+pub use processor::{LambdaSpanProcessor, OverflowStrategy};
 pub use propagation::LambdaXrayPropagator;
 pub use resource::get_lambda_resource;
 pub use telemetry::{
@@ -0,0 +1,218 @@
+//! Bridge from `tracing` events to OpenTelemetry log records.
+//!
+//! [`OtelLogLayer`] is a `tracing-subscriber` layer that converts each `tracing` event into an
+//! OpenTelemetry log record, emitted through a logger provider exporting to stdout with
+//! [`OtlpStdoutLogExporter`](otlp_stdout_span_exporter::OtlpStdoutLogExporter). Structured logs
+//! and spans then travel through the same forwarder, and correlate in the backend.
+//!
+//! The layer is installed by [`init_telemetry`](crate::init_telemetry) when
+//! [`enable_logs`](crate::TelemetryConfig::enable_logs) is set, or the
+//! `LAMBDA_OTEL_LITE_ENABLE_LOGS` environment variable is `true`. Events are filtered by the
+//! same log level configuration (`RUST_LOG` or `AWS_LAMBDA_LOG_LEVEL`) as spans.
+//!
+//! # Record Mapping
+//!
+//! - The `message` field becomes the body of the record
+//! - The other fields become attributes, keeping their integer, float and boolean types
+//! - The level becomes the severity (`TRACE` to `ERROR`)
+//! - The target and name of the event become the target and event name of the record
+//! - The trace and span IDs of the current span are attached to the record
+//!
+//! Events of the OpenTelemetry crates themselves are not bridged, so that exporting a record
+//! cannot emit further records.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use lambda_otel_lite::{init_telemetry, TelemetryConfig};
+//! use lambda_runtime::Error;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Error> {
+//!     let config = TelemetryConfig::builder().enable_logs(true).build();
+//!     let (_, completion_handler) = init_telemetry(config).await?;
+//!
+//!     // Exported as a log record carrying the trace context of the current span
+//!     tracing::info!(order_id = 42, "order processed");
+//!     Ok(())
+//! }
+//! ```
+
+use opentelemetry::logs::{AnyValue, LogRecord, Logger, Severity};
+use opentelemetry::Key;
+use opentelemetry_sdk::logs::SdkLogger;
+use std::time::SystemTime;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Prefix of the targets of the OpenTelemetry crates, whose events are not bridged
+const OTEL_TARGET_PREFIX: &str = "opentelemetry";
+
+/// Layer converting `tracing` events into OpenTelemetry log records.
+///
+/// The records are emitted through the logger given to [`OtelLogLayer::new`]. The trace
+/// context of the record is taken from the active OpenTelemetry context, which
+/// `tracing-opentelemetry` sets when a span is entered, so the layer must be installed in the
+/// same subscriber as the `OpenTelemetryLayer`.
+#[derive(Debug)]
+pub struct OtelLogLayer {
+    logger: SdkLogger,
+}
+
+impl OtelLogLayer {
+    /// Create a layer emitting records through a logger.
+    pub fn new(logger: SdkLogger) -> Self {
+        Self { logger }
+    }
+}
+
+impl<S> Layer<S> for OtelLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if metadata.target().starts_with(OTEL_TARGET_PREFIX) {
+            return;
+        }
+
+        let mut record = self.logger.create_log_record();
+        record.set_timestamp(SystemTime::now());
+        record.set_target(metadata.target());
+        record.set_event_name(metadata.name());
+        let (severity, severity_text) = severity_of(metadata.level());
+        record.set_severity_number(severity);
+        record.set_severity_text(severity_text);
+        event.record(&mut RecordVisitor {
+            record: &mut record,
+        });
+
+        // The trace context is attached by the logger from the active context
+        self.logger.emit(record);
+    }
+}
+
+/// Map a `tracing` level to the severity of a log record
+fn severity_of(level: &Level) -> (Severity, &'static str) {
+    match *level {
+        Level::TRACE => (Severity::Trace, "TRACE"),
+        Level::DEBUG => (Severity::Debug, "DEBUG"),
+        Level::INFO => (Severity::Info, "INFO"),
+        Level::WARN => (Severity::Warn, "WARN"),
+        Level::ERROR => (Severity::Error, "ERROR"),
+    }
+}
+
+/// Records the fields of an event as the body and attributes of a log record
+struct RecordVisitor<'a, R: LogRecord> {
+    record: &'a mut R,
+}
+
+impl<R: LogRecord> RecordVisitor<'_, R> {
+    fn record_value(&mut self, field: &Field, value: AnyValue) {
+        if field.name() == "message" {
+            self.record.set_body(value);
+        } else {
+            self.record
+                .add_attribute(Key::from_static_str(field.name()), value);
+        }
+    }
+}
+
+impl<R: LogRecord> Visit for RecordVisitor<'_, R> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.record_value(field, AnyValue::Double(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record_value(field, AnyValue::Int(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match i64::try_from(value) {
+            Ok(value) => self.record_value(field, AnyValue::Int(value)),
+            Err(_) => self.record_value(field, AnyValue::from(value.to_string())),
+        }
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record_value(field, AnyValue::Boolean(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_value(field, AnyValue::from(value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record_value(field, AnyValue::from(format!("{value:?}")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::logs::LoggerProvider as _;
+    use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+    use opentelemetry_sdk::error::OTelSdkResult;
+    use opentelemetry_sdk::logs::{LogBatch, LogExporter, SdkLogRecord, SdkLoggerProvider};
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use std::sync::{Arc, Mutex};
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    // Test exporter that captures log records
+    #[derive(Debug, Default, Clone)]
+    struct TestExporter {
+        records: Arc<Mutex<Vec<SdkLogRecord>>>,
+    }
+
+    impl LogExporter for TestExporter {
+        fn export(
+            &self,
+            batch: LogBatch<'_>,
+        ) -> impl std::future::Future<Output = OTelSdkResult> + Send {
+            let mut records = self.records.lock().unwrap();
+            records.extend(batch.iter().map(|(record, _)| record.clone()));
+            futures_util::future::ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn test_log_layer_converts_events() {
+        let exporter = TestExporter::default();
+        let logger_provider = SdkLoggerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let tracer_provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(tracer_provider.tracer("test")))
+            .with(OtelLogLayer::new(logger_provider.logger("test")));
+
+        let trace_id = tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("handler");
+            let _guard = span.enter();
+            tracing::warn!(order_id = 42, paid = true, "order delayed");
+            tracing::info!(target: "opentelemetry_sdk", "not bridged");
+            span.context().span().span_context().trace_id()
+        });
+
+        let records = exporter.records.lock().unwrap();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.severity_number(), Some(Severity::Warn));
+        assert_eq!(
+            record.body(),
+            Some(&AnyValue::from("order delayed".to_string()))
+        );
+        assert!(record
+            .attributes_iter()
+            .any(|(key, value)| key.as_str() == "order_id" && *value == AnyValue::Int(42)));
+        assert!(record
+            .attributes_iter()
+            .any(|(key, value)| key.as_str() == "paid" && *value == AnyValue::Boolean(true)));
+        assert_eq!(record.trace_context().unwrap().trace_id, trace_id);
+    }
+}
@@ -13,7 +13,7 @@
 //!    - O(1) push operations with no memory reallocation
 //!    - FIFO ordering ensures spans are processed in order
 //!    - Efficient batch removal for export
-//!    - When full, new spans are dropped (with warning logs)
+//!    - When full, spans are dropped or wait for space, as set by the overflow strategy
 //!
 //! 2. **Thread Safety**:
 //!    - All operations are thread-safe
@@ -29,6 +29,14 @@
 //!   - Defaults to 2048 spans
 //!   - Should be tuned based on span volume
 //!
+//! - `LAMBDA_SPAN_PROCESSOR_OVERFLOW_STRATEGY`: What to do with a span when the buffer is full
+//!   - `drop_new` (default): drop the new span
+//!   - `drop_oldest`: drop the oldest buffered span to make room for the new one
+//!   - `block`: wait for a flush to free space, then drop the new span on timeout
+//!
+//! - `LAMBDA_SPAN_PROCESSOR_BLOCK_TIMEOUT_MS`: Maximum wait of the `block` strategy
+//!   - Defaults to 100 milliseconds
+//!
 //! # Usage Examples
 //!
 //! Basic setup with default configuration:
@@ -78,13 +86,16 @@
 //!
 //! 3. **Reliability**:
 //!    - Spans may be dropped if buffer fills
-//!    - Warning logs indicate dropped spans
+//!    - Warning logs indicate dropped spans, and each flush reports the spans dropped since
+//!      the previous one
 //!    - Consider increasing buffer size if spans are dropped
+//!    - `block` only helps when spans are flushed concurrently, as in async mode: in sync
+//!      mode the handler thread waits for the whole timeout before dropping the span
 //!
 //! # Best Practices
 //!
 //! 1. **Buffer Sizing**:
-//!    - Monitor dropped_spans metric
+//!    - Monitor `dropped_spans`
 //!    - Size based on max spans per invocation
 //!    - Consider function memory when sizing
 //!
@@ -113,10 +124,79 @@ use opentelemetry_sdk::{
     Resource,
 };
 use std::env;
+use std::fmt;
 use std::sync::{
     atomic::{AtomicBool, AtomicUsize, Ordering},
-    Arc, Mutex,
+    Arc, Condvar, Mutex,
 };
+use std::time::Duration;
+
+/// What [`LambdaSpanProcessor`] does with an ended span when its queue is full.
+///
+/// The strategy can be set with the `overflow_strategy` builder method, or with the
+/// `LAMBDA_SPAN_PROCESSOR_OVERFLOW_STRATEGY` environment variable (`drop_new`, `drop_oldest`
+/// or `block`), which takes precedence. With `block`, the timeout is read from
+/// `LAMBDA_SPAN_PROCESSOR_BLOCK_TIMEOUT_MS` when set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowStrategy {
+    /// Drop the new span, keeping the buffered ones
+    #[default]
+    DropNew,
+    /// Drop the oldest buffered span to make room for the new one
+    DropOldest,
+    /// Wait up to the given duration for a flush to free space, then drop the new span
+    Block(Duration),
+}
+
+impl fmt::Display for OverflowStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverflowStrategy::DropNew => write!(f, "drop_new"),
+            OverflowStrategy::DropOldest => write!(f, "drop_oldest"),
+            OverflowStrategy::Block(timeout) => write!(f, "block({}ms)", timeout.as_millis()),
+        }
+    }
+}
+
+impl OverflowStrategy {
+    /// Resolves the strategy from the environment, falling back to `config_strategy`,
+    /// then to [`OverflowStrategy::DropNew`].
+    pub fn resolve(config_strategy: Option<OverflowStrategy>) -> Self {
+        let strategy = match env::var(env_vars::OVERFLOW_STRATEGY)
+            .map(|s| s.to_lowercase())
+            .as_deref()
+        {
+            Ok("drop_new") => OverflowStrategy::DropNew,
+            Ok("drop_oldest") => OverflowStrategy::DropOldest,
+            Ok("block") => match config_strategy {
+                Some(OverflowStrategy::Block(timeout)) => OverflowStrategy::Block(timeout),
+                _ => OverflowStrategy::Block(Duration::from_millis(defaults::BLOCK_TIMEOUT_MS)),
+            },
+            Ok(value) => {
+                LOGGER.warn(format!(
+                    "OverflowStrategy.resolve: invalid overflow strategy in env: {value}, using config or default"
+                ));
+                config_strategy.unwrap_or_default()
+            }
+            Err(_) => config_strategy.unwrap_or_default(),
+        };
+
+        match (strategy, env::var(env_vars::BLOCK_TIMEOUT_MS)) {
+            (OverflowStrategy::Block(timeout), Ok(value)) => match value.parse::<u64>() {
+                Ok(millis) => OverflowStrategy::Block(Duration::from_millis(millis)),
+                Err(_) => {
+                    LOGGER.warn(format!(
+                        "Failed to parse {}: {}, using fallback",
+                        env_vars::BLOCK_TIMEOUT_MS,
+                        value
+                    ));
+                    OverflowStrategy::Block(timeout)
+                }
+            },
+            (strategy, _) => strategy,
+        }
+    }
+}
 
 /// A fixed-size ring buffer for storing spans efficiently.
 ///
@@ -138,8 +218,8 @@ use std::sync::{
 /// - `size`: Current number of elements
 /// - `capacity`: Maximum number of elements
 ///
-/// When the buffer is full, new spans are rejected rather than overwriting old ones.
-/// This ensures no data loss occurs silently.
+/// When the buffer is full, `push` rejects the new span and `push_evicting` overwrites the
+/// oldest one, returning the span that was dropped so the caller can account for it.
 #[derive(Debug)]
 struct SpanRingBuffer {
     buffer: Vec<Option<SpanData>>,
@@ -179,6 +259,25 @@ impl SpanRingBuffer {
         true
     }
 
+    fn push_evicting(&mut self, span: SpanData) -> Option<SpanData> {
+        if self.capacity == 0 {
+            return Some(span);
+        }
+        if self.size < self.capacity {
+            self.push(span);
+            return None;
+        }
+
+        let evicted = self.buffer[self.tail].replace(span);
+        self.tail = (self.tail + 1) % self.capacity;
+        self.head = self.tail;
+        evicted
+    }
+
+    fn is_full(&self) -> bool {
+        self.size == self.capacity
+    }
+
     fn take_batch(&mut self, max_batch_size: usize) -> Vec<SpanData> {
         let batch_size = self.size.min(max_batch_size);
         let mut result = Vec::with_capacity(batch_size);
@@ -207,6 +306,8 @@ impl SpanRingBuffer {
 /// - Supports synchronous and asynchronous export modes
 /// - Handles graceful shutdown for Lambda termination
 /// - Exports *all* buffered spans in a single batch when `force_flush` is called.
+/// - Drops or waits for space when the buffer is full, as set by the [`OverflowStrategy`],
+///   and counts the dropped spans.
 ///
 /// # Examples
 ///
@@ -230,6 +331,19 @@ impl SpanRingBuffer {
 ///     .max_queue_size(1000)
 ///     .build();
 /// ```
+///
+/// Dropping the oldest spans when the buffer is full:
+///
+/// ```
+/// use lambda_otel_lite::{LambdaSpanProcessor, OverflowStrategy};
+/// use otlp_stdout_span_exporter::OtlpStdoutSpanExporter;
+///
+/// let processor = LambdaSpanProcessor::builder()
+///     .exporter(OtlpStdoutSpanExporter::default())
+///     .overflow_strategy(OverflowStrategy::DropOldest)
+///     .build();
+/// assert_eq!(processor.dropped_spans(), 0);
+/// ```
 #[derive(Debug)]
 pub struct LambdaSpanProcessor<E>
 where
@@ -241,11 +355,20 @@ where
     /// Internal buffer for storing spans
     spans: Mutex<SpanRingBuffer>,
 
+    /// Notified when a flush frees space in the buffer
+    space_available: Condvar,
+
+    /// What to do with a span when the buffer is full
+    overflow_strategy: OverflowStrategy,
+
     /// Flag indicating whether the processor is shut down
     is_shutdown: Arc<AtomicBool>,
 
     /// Counter for dropped spans
     dropped_count: AtomicUsize,
+
+    /// Dropped spans already reported by a flush
+    reported_dropped_count: AtomicUsize,
 }
 
 #[bon]
@@ -264,8 +387,14 @@ where
     ///
     /// The relevant environment variables are:
     /// - `LAMBDA_SPAN_PROCESSOR_QUEUE_SIZE`: Controls the maximum queue size
+    /// - `LAMBDA_SPAN_PROCESSOR_OVERFLOW_STRATEGY`: Controls what happens when the queue is full
+    /// - `LAMBDA_SPAN_PROCESSOR_BLOCK_TIMEOUT_MS`: Controls the wait of the `block` strategy
     #[builder]
-    pub fn new(exporter: E, max_queue_size: Option<usize>) -> Self {
+    pub fn new(
+        exporter: E,
+        max_queue_size: Option<usize>,
+        overflow_strategy: Option<OverflowStrategy>,
+    ) -> Self {
         // Get queue size with proper precedence (env var > param > default)
         let max_queue_size = match env::var(env_vars::QUEUE_SIZE) {
             Ok(value) => match value.parse::<usize>() {
@@ -285,8 +414,33 @@ where
         Self {
             exporter: Mutex::new(exporter),
             spans: Mutex::new(SpanRingBuffer::new(max_queue_size)),
+            space_available: Condvar::new(),
+            overflow_strategy: OverflowStrategy::resolve(overflow_strategy),
             is_shutdown: Arc::new(AtomicBool::new(false)),
             dropped_count: AtomicUsize::new(0),
+            reported_dropped_count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the number of spans dropped since the processor was created, because the
+    /// buffer was full or the processor was shut down.
+    pub fn dropped_spans(&self) -> usize {
+        self.dropped_count.load(Ordering::Relaxed)
+    }
+
+    /// Returns the overflow strategy in use, after applying the environment variables.
+    pub fn overflow_strategy(&self) -> OverflowStrategy {
+        self.overflow_strategy
+    }
+
+    fn record_overflow(&self) {
+        let prev = self.dropped_count.fetch_add(1, Ordering::Relaxed);
+        if prev == 0 || prev % 100 == 0 {
+            LOGGER.warn(format!(
+                "LambdaSpanProcessor.on_end: Dropping span because buffer is full (strategy={}, dropped_spans={})",
+                self.overflow_strategy,
+                prev + 1
+            ));
         }
     }
 }
@@ -312,18 +466,43 @@ where
         }
 
         // Try to add span to the buffer
-        if let Ok(mut spans) = self.spans.lock() {
-            if !spans.push(span) {
-                let prev = self.dropped_count.fetch_add(1, Ordering::Relaxed);
-                if prev == 0 || prev % 100 == 0 {
-                    LOGGER.warn(format!(
-                        "LambdaSpanProcessor.on_end: Dropping span because buffer is full (dropped_spans={})",
-                        prev + 1
-                    ));
+        let Ok(mut spans) = self.spans.lock() else {
+            LOGGER.warn("LambdaSpanProcessor.on_end: Failed to acquire spans lock in on_end");
+            return;
+        };
+
+        match self.overflow_strategy {
+            OverflowStrategy::DropNew => {
+                if !spans.push(span) {
+                    self.record_overflow();
+                }
+            }
+            OverflowStrategy::DropOldest => {
+                if spans.push_evicting(span).is_some() {
+                    self.record_overflow();
+                }
+            }
+            OverflowStrategy::Block(timeout) => {
+                if spans.is_full() && spans.capacity > 0 {
+                    // Wait for a flush to drain the buffer, releasing the lock meanwhile
+                    spans = match self
+                        .space_available
+                        .wait_timeout_while(spans, timeout, |spans| {
+                            spans.is_full() && !self.is_shutdown.load(Ordering::Relaxed)
+                        }) {
+                        Ok((spans, _)) => spans,
+                        Err(_) => {
+                            LOGGER.warn(
+                                "LambdaSpanProcessor.on_end: Failed to acquire spans lock in on_end",
+                            );
+                            return;
+                        }
+                    };
+                }
+                if !spans.push(span) {
+                    self.record_overflow();
                 }
             }
-        } else {
-            LOGGER.warn("LambdaSpanProcessor.on_end: Failed to acquire spans lock in on_end");
         }
     }
 
@@ -347,6 +526,18 @@ where
             }
         };
         // Mutex guard for spans is dropped here, releasing the lock
+        self.space_available.notify_all();
+
+        // Report the spans dropped since the previous flush
+        let dropped = self.dropped_count.load(Ordering::Relaxed);
+        let reported = self.reported_dropped_count.swap(dropped, Ordering::Relaxed);
+        if dropped > reported {
+            LOGGER.warn(format!(
+                "LambdaSpanProcessor.force_flush: {} spans dropped since last flush (dropped_spans={})",
+                dropped - reported,
+                dropped
+            ));
+        }
 
         // Acquire lock on the exporter
         let exporter_result = self.exporter.lock();
@@ -491,6 +682,8 @@ mod tests {
 
     fn cleanup_env() {
         env::remove_var(env_vars::QUEUE_SIZE);
+        env::remove_var(env_vars::OVERFLOW_STRATEGY);
+        env::remove_var(env_vars::BLOCK_TIMEOUT_MS);
         env::remove_var(env_vars::PROCESSOR_MODE);
         env::remove_var(env_vars::COMPRESSION_LEVEL);
         env::remove_var(env_vars::SERVICE_NAME);
@@ -536,6 +729,29 @@ mod tests {
         assert!(spans.iter().any(|s| s.name == "span2"));
     }
 
+    #[test]
+    #[serial]
+    fn test_ring_buffer_push_evicting() {
+        let mut buffer = SpanRingBuffer::new(2);
+
+        assert!(buffer.push_evicting(create_test_span("span1")).is_none());
+        assert!(buffer.push_evicting(create_test_span("span2")).is_none());
+
+        // The oldest span makes room for the new one
+        let evicted = buffer.push_evicting(create_test_span("span3")).unwrap();
+        assert_eq!(evicted.name, "span1");
+        let evicted = buffer.push_evicting(create_test_span("span4")).unwrap();
+        assert_eq!(evicted.name, "span2");
+
+        let names: Vec<_> = buffer.take_batch(2).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["span3", "span4"]);
+
+        // A zero capacity buffer drops the new span
+        let mut buffer = SpanRingBuffer::new(0);
+        let evicted = buffer.push_evicting(create_test_span("span1")).unwrap();
+        assert_eq!(evicted.name, "span1");
+    }
+
     #[test]
     #[serial]
     fn test_ring_buffer_batch_operations() {
@@ -643,6 +859,132 @@ mod tests {
         assert_eq!(processor.dropped_count.load(Ordering::Relaxed), 0);
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_overflow_strategies() {
+        cleanup_env();
+
+        for (strategy, expected) in [
+            (OverflowStrategy::DropNew, vec!["span1", "span2"]),
+            (OverflowStrategy::DropOldest, vec!["span2", "span3"]),
+            (
+                OverflowStrategy::Block(Duration::from_millis(10)),
+                vec!["span1", "span2"],
+            ),
+        ] {
+            let mock_exporter = MockExporter::new();
+            let spans_exported = mock_exporter.spans.clone();
+            let processor = LambdaSpanProcessor::builder()
+                .exporter(mock_exporter)
+                .max_queue_size(2)
+                .overflow_strategy(strategy)
+                .build();
+
+            for name in ["span1", "span2", "span3"] {
+                processor.on_end(create_test_span(name));
+            }
+            assert_eq!(processor.dropped_spans(), 1, "strategy: {strategy}");
+
+            processor.force_flush().unwrap();
+            let exported = spans_exported.lock().await;
+            let names: Vec<_> = exported.iter().map(|s| s.name.as_ref()).collect();
+            assert_eq!(names, expected, "strategy: {strategy}");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_block_strategy_waits_for_flush() {
+        cleanup_env();
+
+        let mock_exporter = MockExporter::new();
+        let spans_exported = mock_exporter.spans.clone();
+        let processor = Arc::new(
+            LambdaSpanProcessor::builder()
+                .exporter(mock_exporter)
+                .max_queue_size(1)
+                .overflow_strategy(OverflowStrategy::Block(Duration::from_secs(5)))
+                .build(),
+        );
+
+        processor.on_end(create_test_span("span1"));
+        let blocked = {
+            let processor = processor.clone();
+            std::thread::spawn(move || processor.on_end(create_test_span("span2")))
+        };
+        // Give the span time to wait for space, then free it
+        std::thread::sleep(Duration::from_millis(50));
+        processor.force_flush().unwrap();
+        blocked.join().unwrap();
+        processor.force_flush().unwrap();
+
+        assert_eq!(processor.dropped_spans(), 0);
+        assert_eq!(spans_exported.lock().await.len(), 2);
+    }
+
+    #[test]
+    #[serial]
+    fn test_overflow_strategy_resolve() {
+        cleanup_env();
+        let block = OverflowStrategy::Block(Duration::from_millis(5));
+
+        let cases = [
+            (None, None, None, OverflowStrategy::DropNew),
+            (None, None, Some(block), block),
+            (
+                Some("drop_oldest"),
+                None,
+                Some(block),
+                OverflowStrategy::DropOldest,
+            ),
+            (Some("DROP_NEW"), None, None, OverflowStrategy::DropNew),
+            (
+                Some("block"),
+                None,
+                None,
+                OverflowStrategy::Block(Duration::from_millis(defaults::BLOCK_TIMEOUT_MS)),
+            ),
+            (Some("block"), None, Some(block), block),
+            (
+                Some("block"),
+                Some("250"),
+                Some(block),
+                OverflowStrategy::Block(Duration::from_millis(250)),
+            ),
+            (
+                None,
+                Some("250"),
+                Some(block),
+                OverflowStrategy::Block(Duration::from_millis(250)),
+            ),
+            (None, Some("invalid"), Some(block), block),
+            (None, Some("250"), None, OverflowStrategy::DropNew),
+            (
+                Some("invalid"),
+                None,
+                Some(OverflowStrategy::DropOldest),
+                OverflowStrategy::DropOldest,
+            ),
+        ];
+
+        for (env_strategy, env_timeout, config_strategy, expected) in cases {
+            cleanup_env();
+            if let Some(value) = env_strategy {
+                env::set_var(env_vars::OVERFLOW_STRATEGY, value);
+            }
+            if let Some(value) = env_timeout {
+                env::set_var(env_vars::BLOCK_TIMEOUT_MS, value);
+            }
+            assert_eq!(
+                OverflowStrategy::resolve(config_strategy),
+                expected,
+                "env: {env_strategy:?}/{env_timeout:?}, config: {config_strategy:?}"
+            );
+        }
+
+        cleanup_env();
+    }
+
     #[test]
     #[serial]
     fn test_builder_default_values() {
@@ -656,6 +998,7 @@ mod tests {
 
         // Check default values
         assert_eq!(processor.spans.lock().unwrap().capacity, 2048); // Default queue size
+        assert_eq!(processor.overflow_strategy(), OverflowStrategy::DropNew);
     }
 
     #[test]
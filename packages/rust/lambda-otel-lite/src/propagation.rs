@@ -1,16 +1,32 @@
 //! Context propagation extensions for AWS Lambda.
 //!
 //! This module provides specialized context propagators for AWS Lambda environments,
-//! including enhanced X-Ray propagation that integrates with Lambda's built-in tracing.
+//! including enhanced X-Ray propagation that integrates with Lambda's built-in tracing,
+//! and a B3 propagator for services instrumented with Zipkin.
+//!
+//! # Named Propagators
+//!
+//! Propagators can be selected by name, with the `OTEL_PROPAGATORS` environment variable or
+//! [`with_propagators`](crate::TelemetryConfigBuilder::with_propagators). Several names are
+//! combined into a composite propagator:
+//!
+//! - `tracecontext`: W3C Trace Context
+//! - `baggage`: W3C Baggage
+//! - `xray`: AWS X-Ray
+//! - `xray-lambda`: AWS X-Ray, falling back to the `_X_AMZN_TRACE_ID` environment variable
+//! - `b3`: B3 single header (`b3`)
+//! - `b3multi`: B3 multiple headers (`x-b3-traceid`, `x-b3-spanid`, `x-b3-sampled`)
+//! - `none`: No propagation
 
 use crate::logger::Logger;
-use opentelemetry::trace::TraceContextExt;
+use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
 use opentelemetry::{
     propagation::{text_map_propagator::FieldIter, Extractor, Injector, TextMapPropagator},
     Context,
 };
 use opentelemetry_aws::trace::XrayPropagator;
-use std::{collections::HashMap, env};
+use opentelemetry_sdk::propagation::{BaggagePropagator, TraceContextPropagator};
+use std::{any::Any, collections::HashMap, env};
 
 // Add module-specific logger
 static LOGGER: Logger = Logger::const_new("propagation");
@@ -119,6 +135,223 @@ fn has_active_span(cx: &Context) -> bool {
     cx.span().span_context().is_valid()
 }
 
+const B3_SINGLE_HEADER: &str = "b3";
+const B3_TRACE_ID_HEADER: &str = "x-b3-traceid";
+const B3_SPAN_ID_HEADER: &str = "x-b3-spanid";
+const B3_SAMPLED_HEADER: &str = "x-b3-sampled";
+const B3_FLAGS_HEADER: &str = "x-b3-flags";
+
+/// Header encoding used by the [`B3Propagator`] to inject the context.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum B3Encoding {
+    /// A single `b3` header: `{trace_id}-{span_id}-{sampled}`
+    #[default]
+    SingleHeader,
+    /// The `x-b3-traceid`, `x-b3-spanid` and `x-b3-sampled` headers
+    MultipleHeaders,
+}
+
+/// Propagator of the [B3](https://github.com/openzipkin/b3-propagation) format used by Zipkin.
+///
+/// The context is injected with the configured [`B3Encoding`], and extracted from either the
+/// single header or the multiple headers, the single header taking precedence. 64-bit trace IDs
+/// are left-padded to 128 bits, and the debug flag is treated as sampled.
+///
+/// # Example
+///
+/// ```no_run
+/// use lambda_otel_lite::TelemetryConfig;
+/// use lambda_otel_lite::propagation::{B3Encoding, B3Propagator};
+///
+/// let config = TelemetryConfig::builder()
+///     .with_propagator(B3Propagator::with_encoding(B3Encoding::MultipleHeaders))
+///     .build();
+/// ```
+#[derive(Debug, Default)]
+pub struct B3Propagator {
+    encoding: B3Encoding,
+    fields: Vec<String>,
+}
+
+impl B3Propagator {
+    /// Create a propagator injecting the single `b3` header.
+    pub fn new() -> Self {
+        Self::with_encoding(B3Encoding::SingleHeader)
+    }
+
+    /// Create a propagator injecting the context with the given encoding.
+    pub fn with_encoding(encoding: B3Encoding) -> Self {
+        let fields = match encoding {
+            B3Encoding::SingleHeader => vec![B3_SINGLE_HEADER.to_string()],
+            B3Encoding::MultipleHeaders => vec![
+                B3_TRACE_ID_HEADER.to_string(),
+                B3_SPAN_ID_HEADER.to_string(),
+                B3_SAMPLED_HEADER.to_string(),
+            ],
+        };
+        Self { encoding, fields }
+    }
+
+    fn extract_single_header(&self, value: &str) -> Option<SpanContext> {
+        let mut parts = value.trim().split('-');
+        let trace_id = parse_b3_trace_id(parts.next()?)?;
+        let span_id = SpanId::from_hex(parts.next()?).ok()?;
+        let sampled = match parts.next() {
+            Some(state) => parse_b3_sampling_state(state)?,
+            None => true,
+        };
+        Some(remote_span_context(trace_id, span_id, sampled))
+    }
+
+    fn extract_multiple_headers(&self, extractor: &dyn Extractor) -> Option<SpanContext> {
+        let trace_id = parse_b3_trace_id(extractor.get(B3_TRACE_ID_HEADER)?.trim())?;
+        let span_id = SpanId::from_hex(extractor.get(B3_SPAN_ID_HEADER)?.trim()).ok()?;
+        let sampled = if extractor.get(B3_FLAGS_HEADER).map(str::trim) == Some("1") {
+            true
+        } else {
+            match extractor.get(B3_SAMPLED_HEADER) {
+                Some(state) => match state.trim() {
+                    "true" => true,
+                    "false" => false,
+                    state => parse_b3_sampling_state(state)?,
+                },
+                None => true,
+            }
+        };
+        Some(remote_span_context(trace_id, span_id, sampled))
+    }
+}
+
+impl TextMapPropagator for B3Propagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+        let span = cx.span();
+        let span_context = span.span_context();
+        if !span_context.is_valid() {
+            return;
+        }
+        let sampled = if span_context.is_sampled() { "1" } else { "0" };
+        match self.encoding {
+            B3Encoding::SingleHeader => injector.set(
+                B3_SINGLE_HEADER,
+                format!(
+                    "{}-{}-{}",
+                    span_context.trace_id(),
+                    span_context.span_id(),
+                    sampled
+                ),
+            ),
+            B3Encoding::MultipleHeaders => {
+                injector.set(B3_TRACE_ID_HEADER, span_context.trace_id().to_string());
+                injector.set(B3_SPAN_ID_HEADER, span_context.span_id().to_string());
+                injector.set(B3_SAMPLED_HEADER, sampled.to_string());
+            }
+        }
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        let span_context = match extractor.get(B3_SINGLE_HEADER) {
+            Some(value) => self.extract_single_header(value),
+            None => self.extract_multiple_headers(extractor),
+        };
+        match span_context {
+            Some(span_context) => cx.with_remote_span_context(span_context),
+            None => cx.clone(),
+        }
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(&self.fields)
+    }
+}
+
+// Parses a 64 or 128-bit B3 trace ID, left-padding 64-bit IDs
+fn parse_b3_trace_id(value: &str) -> Option<TraceId> {
+    let trace_id = match value.len() {
+        16 => TraceId::from_hex(&format!("{:0>32}", value)),
+        32 => TraceId::from_hex(value),
+        _ => return None,
+    }
+    .ok()?;
+    (trace_id != TraceId::INVALID).then_some(trace_id)
+}
+
+// Parses the sampling state of a B3 header, the debug flag (`d`) meaning sampled
+fn parse_b3_sampling_state(value: &str) -> Option<bool> {
+    match value {
+        "1" | "d" => Some(true),
+        "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn remote_span_context(trace_id: TraceId, span_id: SpanId, sampled: bool) -> SpanContext {
+    let flags = if sampled {
+        TraceFlags::SAMPLED
+    } else {
+        TraceFlags::default()
+    };
+    SpanContext::new(trace_id, span_id, flags, true, TraceState::default())
+}
+
+/// A propagator that neither injects nor extracts any context, selected by `none`.
+#[derive(Debug, Default)]
+pub(crate) struct NoopPropagator;
+
+impl TextMapPropagator for NoopPropagator {
+    fn inject_context(&self, _cx: &Context, _injector: &mut dyn Injector) {}
+
+    fn extract_with_context(&self, cx: &Context, _extractor: &dyn Extractor) -> Context {
+        cx.clone()
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(&[])
+    }
+}
+
+/// Create the propagator of a name, returning `None` for an unknown name.
+///
+/// Names are matched case-insensitively, see the [module documentation](self) for the list.
+pub(crate) fn named_propagator(name: &str) -> Option<Box<dyn TextMapPropagator + Send + Sync>> {
+    let propagator: Box<dyn TextMapPropagator + Send + Sync> =
+        match name.trim().to_lowercase().as_str() {
+            "tracecontext" => Box::new(TraceContextPropagator::new()),
+            "baggage" => Box::new(BaggagePropagator::new()),
+            "xray" => Box::new(XrayPropagator::new()),
+            "xray-lambda" => Box::new(LambdaXrayPropagator::new()),
+            "b3" => Box::new(B3Propagator::new()),
+            "b3multi" => Box::new(B3Propagator::with_encoding(B3Encoding::MultipleHeaders)),
+            "none" => Box::new(NoopPropagator),
+            _ => return None,
+        };
+    Some(propagator)
+}
+
+/// Name of a propagator in configuration snapshots: its `OTEL_PROPAGATORS` name, or the type
+/// name of a propagator that has none.
+pub(crate) fn propagator_name<T: TextMapPropagator + 'static>(propagator: &T) -> String {
+    let propagator: &dyn Any = propagator;
+    let name = if propagator.is::<TraceContextPropagator>() {
+        "tracecontext"
+    } else if propagator.is::<BaggagePropagator>() {
+        "baggage"
+    } else if propagator.is::<XrayPropagator>() {
+        "xray"
+    } else if propagator.is::<LambdaXrayPropagator>() {
+        "xray-lambda"
+    } else if propagator.is::<NoopPropagator>() {
+        "none"
+    } else if let Some(b3) = propagator.downcast_ref::<B3Propagator>() {
+        match b3.encoding {
+            B3Encoding::SingleHeader => "b3",
+            B3Encoding::MultipleHeaders => "b3multi",
+        }
+    } else {
+        return crate::diagnostics::short_type_name::<T>();
+    };
+    name.to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -257,4 +490,73 @@ mod tests {
             env::remove_var("_X_AMZN_TRACE_ID");
         }
     }
+
+    #[test]
+    fn test_b3_extract_and_inject() {
+        let propagator = B3Propagator::new();
+
+        // A 64-bit trace ID in the single header is left-padded
+        let carrier = HashMap::from([(
+            B3_SINGLE_HEADER.to_string(),
+            "a3ce929d0e0e4736-00f067aa0ba902b7-d".to_string(),
+        )]);
+        let context = propagator.extract(&carrier);
+        let span = context.span();
+        let span_context = span.span_context();
+        assert_eq!(
+            span_context.trace_id(),
+            TraceId::from_hex("0000000000000000a3ce929d0e0e4736").unwrap()
+        );
+        assert!(span_context.is_sampled());
+        assert!(span_context.is_remote());
+
+        // The multiple headers are used when the single header is missing
+        let carrier = HashMap::from([
+            (
+                B3_TRACE_ID_HEADER.to_string(),
+                "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+            ),
+            (
+                B3_SPAN_ID_HEADER.to_string(),
+                "00f067aa0ba902b7".to_string(),
+            ),
+            (B3_SAMPLED_HEADER.to_string(), "0".to_string()),
+        ]);
+        let context = B3Propagator::with_encoding(B3Encoding::MultipleHeaders).extract(&carrier);
+        assert!(context.span().span_context().is_valid());
+        assert!(!context.span().span_context().is_sampled());
+
+        let mut injected = HashMap::new();
+        propagator.inject_context(&context, &mut injected);
+        assert_eq!(
+            injected.get(B3_SINGLE_HEADER).map(String::as_str),
+            Some("4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-0")
+        );
+        let mut injected = HashMap::new();
+        B3Propagator::with_encoding(B3Encoding::MultipleHeaders)
+            .inject_context(&context, &mut injected);
+        assert_eq!(injected, carrier);
+
+        // Invalid headers leave the context unchanged
+        let carrier = HashMap::from([(B3_SINGLE_HEADER.to_string(), "0-0-1".to_string())]);
+        assert!(!propagator.extract(&carrier).has_active_span());
+    }
+
+    #[test]
+    fn test_named_propagator() {
+        for name in [
+            "tracecontext",
+            "baggage",
+            "xray",
+            "xray-lambda",
+            "b3",
+            " B3Multi ",
+            "none",
+        ] {
+            assert!(named_propagator(name).is_some(), "{name}");
+        }
+        assert!(named_propagator("jaeger").is_none());
+        assert_eq!(named_propagator("b3multi").unwrap().fields().count(), 3);
+        assert_eq!(named_propagator("none").unwrap().fields().count(), 0);
+    }
 }
@@ -233,6 +233,26 @@ pub fn get_lambda_resource() -> Resource {
     Resource::builder().with_attributes(attributes).build()
 }
 
+/// Merge the attributes found by resource detectors with a resource.
+///
+/// The attributes of the resource take precedence over the detected ones.
+pub(crate) fn merge_detected_attributes(resource: Resource, detected: Vec<KeyValue>) -> Resource {
+    if detected.is_empty() {
+        return resource;
+    }
+
+    let attributes = resource
+        .iter()
+        .map(|(key, value)| KeyValue::new(key.clone(), value.clone()))
+        .collect::<Vec<_>>();
+    let builder = Resource::builder_empty().with_attributes(detected);
+    match resource.schema_url() {
+        Some(schema_url) => builder.with_schema_url(attributes, schema_url.to_string()),
+        None => builder.with_attributes(attributes),
+    }
+    .build()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -490,4 +510,29 @@ mod tests {
 
         cleanup_env();
     }
+
+    #[test]
+    fn test_merge_detected_attributes() {
+        let resource = Resource::builder_empty()
+            .with_attributes(vec![KeyValue::new("service.name", "orders")])
+            .build();
+        let detected = vec![
+            KeyValue::new("service.name", "detected"),
+            KeyValue::new("host.name", "first"),
+            KeyValue::new("host.name", "second"),
+        ];
+
+        let merged = merge_detected_attributes(resource, detected);
+        let attrs: Vec<_> = merged.iter().map(|(k, v)| (k.as_str(), v)).collect();
+
+        // The resource takes precedence, and later detectors over earlier ones
+        assert_eq!(
+            find_attr(&attrs, "service.name"),
+            Some(&opentelemetry::Value::String("orders".into()))
+        );
+        assert_eq!(
+            find_attr(&attrs, "host.name"),
+            Some(&opentelemetry::Value::String("second".into()))
+        );
+    }
 }
@@ -3,7 +3,7 @@
 //! This module provides the initialization and configuration components for OpenTelemetry in Lambda:
 //! - `init_telemetry`: Main entry point for telemetry setup
 //! - `TelemetryConfig`: Configuration builder with environment-based defaults
-//! - `TelemetryCompletionHandler`: Controls span export timing based on processing mode
+//! - `TelemetryCompletionHandler`: Controls span, metric and log export timing based on processing mode
 //!
 //! # Architecture
 //!
@@ -12,7 +12,8 @@
 //! 2. Span processor is created based on processing mode
 //! 3. Resource attributes are detected from Lambda environment
 //! 4. Tracer provider is initialized with the configuration
-//! 5. Completion handler is returned for managing span export
+//! 5. Meter and logger providers are initialized when metrics or logs are enabled
+//! 6. Completion handler is returned for managing span, metric and log export
 //!
 //! # Environment Configuration
 //!
@@ -80,33 +81,77 @@
 //! }
 //! ```
 //!
+//! Metrics recorded through the global meter provider, exported with the spans:
+//! ```no_run
+//! use lambda_otel_lite::{init_telemetry, TelemetryConfig};
+//! use opentelemetry::global;
+//! use lambda_runtime::Error;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Error> {
+//!     let config = TelemetryConfig::builder().enable_metrics(true).build();
+//!     let (_, completion_handler) = init_telemetry(config).await?;
+//!
+//!     let orders = global::meter("orders").u64_counter("orders.processed").build();
+//!     orders.add(1, &[]);
+//!     Ok(())
+//! }
+//! ```
+//!
 //! # Environment Variables
 //!
 //! The following environment variables affect the configuration:
 //! - `OTEL_SERVICE_NAME`: Service name for spans
 //! - `OTEL_RESOURCE_ATTRIBUTES`: Additional resource attributes
 //! - `LAMBDA_SPAN_PROCESSOR_QUEUE_SIZE`: Span buffer size (default: 2048)
+//! - `LAMBDA_SPAN_PROCESSOR_OVERFLOW_STRATEGY`: Full buffer behavior (drop_new/drop_oldest/block)
+//! - `LAMBDA_SPAN_PROCESSOR_BLOCK_TIMEOUT_MS`: Maximum wait of `block` (default: 100)
 //! - `OTLP_STDOUT_SPAN_EXPORTER_COMPRESSION_LEVEL`: Export compression (default: 6)
 //! - `LAMBDA_TRACING_ENABLE_FMT_LAYER`: Enable formatting layer (default: false)
+//! - `LAMBDA_OTEL_LITE_ENABLE_METRICS`: Enable the meter provider (default: false)
+//! - `LAMBDA_OTEL_LITE_ENABLE_LOGS`: Bridge `tracing` events to OTLP log records (default: false)
 //! - `LAMBDA_EXTENSION_SPAN_PROCESSOR_MODE`: Processing mode (sync/async/finalize)
 //! - `RUST_LOG` or `AWS_LAMBDA_LOG_LEVEL`: Log level configuration
+//!
+//! # Diagnostics
+//!
+//! The resolved configuration is logged once at DEBUG level and can be inspected at runtime
+//! with [`telemetry_config_snapshot`](crate::diagnostics::telemetry_config_snapshot).
 
 use crate::{
-    constants, extension::register_extension, logger::Logger, mode::ProcessorMode,
-    processor::LambdaSpanProcessor, propagation::LambdaXrayPropagator,
-    resource::get_lambda_resource,
+    baggage::{self, BaggageSpanProcessor},
+    constants,
+    diagnostics::{self, TelemetryConfigSnapshot},
+    extension::register_extension,
+    logger::Logger,
+    logs::OtelLogLayer,
+    mode::ProcessorMode,
+    processor::LambdaSpanProcessor,
+    propagation::{named_propagator, propagator_name, LambdaXrayPropagator},
+    resource::{get_lambda_resource, merge_detected_attributes},
 };
 use bon::Builder;
 use lambda_runtime::Error;
 use opentelemetry::propagation::{TextMapCompositePropagator, TextMapPropagator};
-use opentelemetry::{global, global::set_tracer_provider, trace::TracerProvider as _, KeyValue};
-use opentelemetry_aws::trace::XrayPropagator;
+use opentelemetry::{
+    global, global::set_tracer_provider, logs::LoggerProvider as _, trace::TracerProvider as _,
+    KeyValue,
+};
+use opentelemetry_aws::trace::XrayIdGenerator;
 use opentelemetry_sdk::{
+    logs::{BatchLogProcessor, SdkLoggerProvider},
+    metrics::SdkMeterProvider,
     propagation::TraceContextPropagator,
-    trace::{IdGenerator, SdkTracerProvider, ShouldSample, SpanProcessor, TracerProviderBuilder},
+    resource::ResourceDetector,
+    trace::{
+        IdGenerator, RandomIdGenerator, SdkTracerProvider, ShouldSample, SpanProcessor,
+        TracerProviderBuilder,
+    },
     Resource,
 };
-use otlp_stdout_span_exporter::OtlpStdoutSpanExporter;
+use otlp_stdout_span_exporter::{
+    OtlpStdoutLogExporter, OtlpStdoutMetricExporter, OtlpStdoutSpanExporter,
+};
 use std::{borrow::Cow, env, sync::Arc};
 use tokio::sync::mpsc::UnboundedSender;
 use tracing_subscriber::layer::SubscriberExt;
@@ -122,6 +167,9 @@ static LOGGER: Logger = Logger::const_new("telemetry");
 /// - Async: Signals the extension to export
 /// - Finalize: Defers to span processor
 ///
+/// When metrics or logs are enabled, the meter and logger providers are flushed at the same
+/// points as the spans.
+///
 /// # Thread Safety
 ///
 /// This type is `Clone` and can be safely shared between threads.
@@ -131,6 +179,8 @@ pub struct TelemetryCompletionHandler {
     sender: Option<UnboundedSender<()>>,
     mode: ProcessorMode,
     tracer: opentelemetry_sdk::trace::Tracer,
+    meter_provider: Option<SdkMeterProvider>,
+    logger_provider: Option<SdkLoggerProvider>,
 }
 
 impl TelemetryCompletionHandler {
@@ -158,9 +208,35 @@ impl TelemetryCompletionHandler {
             sender,
             mode,
             tracer,
+            meter_provider: None,
+            logger_provider: None,
         }
     }
 
+    /// Set the meter provider flushed on completion, along with the spans.
+    pub fn with_meter_provider(mut self, meter_provider: Option<SdkMeterProvider>) -> Self {
+        self.meter_provider = meter_provider;
+        self
+    }
+
+    /// Get the meter provider, when metrics are enabled.
+    ///
+    /// Meters created from it record metrics exported with the same lifecycle as spans.
+    pub fn get_meter_provider(&self) -> Option<&SdkMeterProvider> {
+        self.meter_provider.as_ref()
+    }
+
+    /// Set the logger provider flushed on completion, along with the spans.
+    pub fn with_logger_provider(mut self, logger_provider: Option<SdkLoggerProvider>) -> Self {
+        self.logger_provider = logger_provider;
+        self
+    }
+
+    /// Get the logger provider, when logs are enabled.
+    pub fn get_logger_provider(&self) -> Option<&SdkLoggerProvider> {
+        self.logger_provider.as_ref()
+    }
+
     /// Get the tracer instance for creating spans.
     ///
     /// Returns the cached tracer instance configured with this package's instrumentation scope.
@@ -172,15 +248,18 @@ impl TelemetryCompletionHandler {
 
     /// Complete telemetry processing for the current invocation
     ///
-    /// In Sync mode, this will force flush the provider and log any errors that occur.
+    /// In Sync mode, this will force flush the providers and log any errors that occur.
     /// In Async mode, this will send a completion signal to the extension.
     /// In Finalize mode, this will do nothing (handled by drop).
     pub fn complete(&self) {
         match self.mode {
             ProcessorMode::Sync => {
-                if let Err(e) = self.provider.force_flush() {
-                    LOGGER.warn(format!("Error flushing telemetry: {e:?}"));
-                }
+                flush_providers(
+                    &self.provider,
+                    self.meter_provider.as_ref(),
+                    self.logger_provider.as_ref(),
+                    "TelemetryCompletionHandler.complete",
+                );
             }
             ProcessorMode::Async => {
                 if let Some(sender) = &self.sender {
@@ -198,6 +277,35 @@ impl TelemetryCompletionHandler {
     }
 }
 
+/// Force flush the tracer provider, and the meter and logger providers when enabled, logging
+/// any errors under `context`.
+pub(crate) fn flush_providers(
+    tracer_provider: &SdkTracerProvider,
+    meter_provider: Option<&SdkMeterProvider>,
+    logger_provider: Option<&SdkLoggerProvider>,
+    context: &str,
+) {
+    if let Err(err) = tracer_provider.force_flush() {
+        LOGGER.error(format!(
+            "{context}.Error: Error flushing tracer provider: {err:?}"
+        ));
+    }
+    if let Some(meter_provider) = meter_provider {
+        if let Err(err) = meter_provider.force_flush() {
+            LOGGER.error(format!(
+                "{context}.Error: Error flushing meter provider: {err:?}"
+            ));
+        }
+    }
+    if let Some(logger_provider) = logger_provider {
+        if let Err(err) = logger_provider.force_flush() {
+            LOGGER.error(format!(
+                "{context}.Error: Error flushing logger provider: {err:?}"
+            ));
+        }
+    }
+}
+
 /// Configuration for OpenTelemetry initialization.
 ///
 /// Provides configuration options for telemetry setup. Use `TelemetryConfig::default()`
@@ -206,6 +314,9 @@ impl TelemetryCompletionHandler {
 /// # Fields
 ///
 /// * `enable_fmt_layer` - Enable console output for debugging (default: false)
+/// * `enable_metrics` - Initialize a meter provider exporting to stdout (default: false)
+/// * `enable_logs` - Bridge `tracing` events to log records exported to stdout (default: false)
+/// * `baggage_attributes` - Baggage keys copied onto spans and events as attributes (default: none)
 /// * `set_global_provider` - Set as global tracer provider (default: true)
 /// * `resource` - Custom resource attributes (default: auto-detected from Lambda)
 /// * `env_var_name` - Environment variable name for log level configuration
@@ -258,6 +369,26 @@ pub struct TelemetryConfig {
     #[builder(field)]
     propagators: Vec<Box<dyn TextMapPropagator + Send + Sync>>,
 
+    // Names of the configured components, reported by the configuration snapshot
+    #[builder(field)]
+    span_processor_names: Vec<String>,
+
+    #[builder(field)]
+    propagator_names: Vec<String>,
+
+    #[builder(field)]
+    sampler_name: Option<String>,
+
+    #[builder(field)]
+    id_generator_name: Option<String>,
+
+    // Attributes found by the resource detectors, in registration order
+    #[builder(field)]
+    detected_resource_attributes: Vec<KeyValue>,
+
+    #[builder(field)]
+    resource_detector_names: Vec<String>,
+
     /// Enable console output for debugging.
     ///
     /// When enabled, spans and events will be printed to the console in addition
@@ -276,10 +407,55 @@ pub struct TelemetryConfig {
     #[builder(default = false)]
     pub enable_fmt_layer: bool,
 
+    /// Initialize a meter provider exporting metrics to stdout.
+    ///
+    /// When enabled, an [`SdkMeterProvider`] is built with the same resource as the tracer
+    /// provider, exporting through an [`OtlpStdoutMetricExporter`]. It is flushed by the
+    /// completion handler (or the extension in async mode), so metrics recorded during an
+    /// invocation are exported with its spans. When `set_global_provider` is enabled, it is
+    /// also registered as the global meter provider.
+    ///
+    /// This can also be controlled via the `LAMBDA_OTEL_LITE_ENABLE_METRICS` environment
+    /// variable, which takes precedence over this setting when present, like
+    /// `LAMBDA_TRACING_ENABLE_FMT_LAYER`.
+    ///
+    /// Default: `false`
+    #[builder(default = false)]
+    pub enable_metrics: bool,
+
+    /// Bridge `tracing` events to OTLP log records exported to stdout.
+    ///
+    /// When enabled, an [`OtelLogLayer`] is added to the tracing subscriber. It converts each
+    /// event passing the log level filter into a log record carrying the trace context of the
+    /// current span, emitted through an [`SdkLoggerProvider`] with the same resource as the
+    /// tracer provider and exporting through an [`OtlpStdoutLogExporter`]. The logger provider
+    /// is flushed with the spans by the completion handler (or the extension in async mode).
+    ///
+    /// This can also be controlled via the `LAMBDA_OTEL_LITE_ENABLE_LOGS` environment
+    /// variable, which takes precedence over this setting when present.
+    ///
+    /// Default: `false`
+    #[builder(default = false)]
+    pub enable_logs: bool,
+
+    /// Keys of the baggage entries copied onto spans and events as attributes.
+    ///
+    /// When not empty, a [`BaggageSpanProcessor`] sets the allowed entries of the parent
+    /// context on every span, and [`record_event`](crate::record_event) adds the allowed
+    /// entries of the current span to the event attributes. Use `"*"` to copy all the entries.
+    /// Baggage is set by the caller, so only allow keys whose values are safe to record.
+    ///
+    /// This can also be controlled via the `LAMBDA_OTEL_LITE_BAGGAGE_ATTRIBUTES` environment
+    /// variable (comma-separated keys), which takes precedence over this setting when present.
+    ///
+    /// Default: no keys
+    #[builder(default)]
+    pub baggage_attributes: Vec<String>,
+
     /// Set this provider as the global OpenTelemetry provider.
     ///
-    /// When enabled, the provider will be registered as the global provider
-    /// for the OpenTelemetry API. This allows using the global tracer API
+    /// When enabled, the provider (and the meter provider, when metrics are enabled) will be
+    /// registered as the global provider for the OpenTelemetry API. This allows using the global tracer API
     /// without explicitly passing around the provider.
     ///
     /// Default: `true`
@@ -290,7 +466,8 @@ pub struct TelemetryConfig {
     ///
     /// If not provided, resource attributes will be automatically detected
     /// from the Lambda environment. Custom resources will override any
-    /// automatically detected attributes with the same keys.
+    /// automatically detected attributes with the same keys, including those of the
+    /// detectors added with `with_resource_detector`.
     ///
     /// Default: `None` (auto-detected from Lambda environment)
     pub resource: Option<Resource>,
@@ -350,13 +527,66 @@ impl<S: telemetry_config_builder::State> TelemetryConfigBuilder<S> {
     {
         self.provider_builder = self.provider_builder.with_span_processor(processor);
         self.has_processor = true;
+        self.span_processor_names
+            .push(diagnostics::short_type_name::<T>());
+        self
+    }
+
+    /// Add a resource detector to the resource detector chain.
+    ///
+    /// The detector runs when it is added, and the attributes it finds are merged with the
+    /// resource (the one set with [`resource`](TelemetryConfigBuilder::resource), or the
+    /// detected Lambda resource). The resource takes precedence over the detected attributes
+    /// with the same keys, and later detectors take precedence over earlier ones.
+    ///
+    /// Any [`ResourceDetector`] can be used, such as the process and host detectors of the
+    /// `opentelemetry-resource-detectors` crate, or a custom one.
+    ///
+    /// # Arguments
+    ///
+    /// * `detector` - A resource detector implementing the [`ResourceDetector`] trait
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use lambda_otel_lite::TelemetryConfig;
+    /// use opentelemetry::KeyValue;
+    /// use opentelemetry_sdk::resource::ResourceDetector;
+    /// use opentelemetry_sdk::Resource;
+    ///
+    /// struct DeploymentDetector;
+    ///
+    /// impl ResourceDetector for DeploymentDetector {
+    ///     fn detect(&self) -> Resource {
+    ///         Resource::builder_empty()
+    ///             .with_attribute(KeyValue::new("deployment.environment.name", "prod"))
+    ///             .build()
+    ///     }
+    /// }
+    ///
+    /// let config = TelemetryConfig::builder()
+    ///     .with_resource_detector(DeploymentDetector)
+    ///     .build();
+    /// ```
+    pub fn with_resource_detector<T>(mut self, detector: T) -> Self
+    where
+        T: ResourceDetector + 'static,
+    {
+        let detected = detector.detect();
+        self.detected_resource_attributes.extend(
+            detected
+                .iter()
+                .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+        );
+        self.resource_detector_names
+            .push(diagnostics::short_type_name::<T>());
         self
     }
 
     /// Add a propagator to the list of propagators.
     ///
     /// Multiple propagators can be added and will be combined into a composite propagator.
-    /// The default propagator is TraceContextPropagator.
+    /// When none is added, the Lambda X-Ray and W3C Trace Context propagators are used.
     ///
     /// # Arguments
     ///
@@ -376,23 +606,61 @@ impl<S: telemetry_config_builder::State> TelemetryConfigBuilder<S> {
     where
         T: TextMapPropagator + Send + Sync + 'static,
     {
+        self.propagator_names.push(propagator_name(&propagator));
         self.propagators.push(Box::new(propagator));
         self
     }
 
-    pub fn with_named_propagator(self, name: &str) -> Self {
-        match name {
-            "tracecontext" => self.with_propagator(TraceContextPropagator::new()),
-            "xray" => self.with_propagator(XrayPropagator::new()),
-            "xray-lambda" => self.with_propagator(LambdaXrayPropagator::new()),
-            "none" => self.with_propagator(NoopPropagator::new()),
-            _ => {
-                LOGGER.warn(format!(
-                    "Unknown propagator: {name}, using default propagators"
-                ));
-                self
+    /// Add a propagator by name.
+    ///
+    /// Supported names are `tracecontext`, `baggage`, `xray`, `xray-lambda`, `b3`, `b3multi`
+    /// and `none`, the values of the `OTEL_PROPAGATORS` environment variable. Unknown names
+    /// log a warning and are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use lambda_otel_lite::TelemetryConfig;
+    ///
+    /// let config = TelemetryConfig::builder()
+    ///     .with_named_propagator("b3")
+    ///     .build();
+    /// ```
+    pub fn with_named_propagator(mut self, name: &str) -> Self {
+        let name = name.trim().to_lowercase();
+        match named_propagator(&name) {
+            Some(propagator) => {
+                self.propagators.push(propagator);
+                self.propagator_names.push(name);
             }
+            None => LOGGER.warn(format!("Unknown propagator: {name}, ignoring it")),
         }
+        self
+    }
+
+    /// Add several propagators by name, combined into a composite propagator.
+    ///
+    /// This is the code equivalent of the `OTEL_PROPAGATORS` environment variable, which
+    /// replaces the propagators configured in code when set. See
+    /// [`with_named_propagator`](Self::with_named_propagator) for the supported names.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use lambda_otel_lite::TelemetryConfig;
+    ///
+    /// let config = TelemetryConfig::builder()
+    ///     .with_propagators(["tracecontext", "baggage", "xray-lambda"])
+    ///     .build();
+    /// ```
+    pub fn with_propagators<I>(self, names: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        names.into_iter().fold(self, |builder, name| {
+            builder.with_named_propagator(name.as_ref())
+        })
     }
 
     /// Add a custom ID generator to the tracer provider.
@@ -421,6 +689,33 @@ impl<S: telemetry_config_builder::State> TelemetryConfigBuilder<S> {
         T: IdGenerator + 'static,
     {
         self.provider_builder = self.provider_builder.with_id_generator(id_generator);
+        self.id_generator_name = Some(diagnostics::short_type_name::<T>());
+        self
+    }
+
+    /// Set the ID generator of the tracer provider by name.
+    ///
+    /// Supported names are `random`, the W3C-compatible default, and `xray`, generating trace
+    /// IDs that embed their start time like the ones of AWS X-Ray, so that traces interleave
+    /// with services instrumented with the X-Ray SDKs. Unknown names log a warning and are
+    /// ignored.
+    ///
+    /// The `LAMBDA_OTEL_LITE_ID_GENERATOR` environment variable takes precedence when set.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use lambda_otel_lite::TelemetryConfig;
+    ///
+    /// let config = TelemetryConfig::builder()
+    ///     .with_named_id_generator("xray")
+    ///     .build();
+    /// ```
+    pub fn with_named_id_generator(mut self, name: &str) -> Self {
+        match set_named_id_generator(&mut self.provider_builder, name) {
+            Some(type_name) => self.id_generator_name = Some(type_name),
+            None => LOGGER.warn(format!("Unknown ID generator: {name}, ignoring it")),
+        }
         self
     }
 
@@ -453,6 +748,7 @@ impl<S: telemetry_config_builder::State> TelemetryConfigBuilder<S> {
     where
         T: ShouldSample + 'static,
     {
+        self.sampler_name = Some(format!("{sampler:?}"));
         self.provider_builder = self.provider_builder.with_sampler(sampler);
         self
     }
@@ -573,71 +869,138 @@ pub async fn init_telemetry(
     mut config: TelemetryConfig,
 ) -> Result<(opentelemetry_sdk::trace::Tracer, TelemetryCompletionHandler), Error> {
     // Get mode from config or environment with environment taking precedence
+    let mode_source = diagnostics::processor_mode_source(config.processor_mode.as_ref());
     let mode = ProcessorMode::resolve(config.processor_mode);
 
     if let Ok(env_propagators) = env::var(constants::env_vars::PROPAGATORS) {
-        let propagators: Vec<&str> = env_propagators.split(',').map(|s| s.trim()).collect();
-
-        for propagator in propagators {
-            match propagator {
-                "tracecontext" => config
-                    .propagators
-                    .push(Box::new(TraceContextPropagator::new())),
-                "xray" => config.propagators.push(Box::new(XrayPropagator::new())),
-                "xray-lambda" => config
-                    .propagators
-                    .push(Box::new(LambdaXrayPropagator::new())),
-                "none" => config.propagators.push(Box::new(NoopPropagator::new())),
-                _ => LOGGER.warn(format!(
-                    "Unknown propagator: {propagator}, using default propagators"
-                )),
+        // The environment variable takes precedence over the propagators configured in code
+        config.propagators.clear();
+        config.propagator_names.clear();
+        for name in env_propagators.split(',').map(|s| s.trim().to_lowercase()) {
+            if name.is_empty() || config.propagator_names.contains(&name) {
+                continue;
+            }
+            match named_propagator(&name) {
+                Some(propagator) => {
+                    config.propagators.push(propagator);
+                    config.propagator_names.push(name);
+                }
+                None => LOGGER.warn(format!("Unknown propagator: {name}, ignoring it")),
             }
         }
-    } else {
-        // if no propagators are set, use the default propagators
-        if config.propagators.is_empty() {
-            // IMPORTANT:
-            // LambdaXrayPropagator is added *before* TraceContextPropagator
-            // because in OpenTelemetry Rust, the *last* propagator that extracts
-            // a valid context wins during extraction.
-            // This ensures that if both an AWS X-Ray header (or _X_AMZN_TRACE_ID)
-            // and a W3C traceparent header are present, the W3C traceparent takes precedence.
-            config
-                .propagators
-                .push(Box::new(LambdaXrayPropagator::new()));
-            config
-                .propagators
-                .push(Box::new(TraceContextPropagator::new()));
-        }
+    }
+
+    // if no propagators are set, use the default propagators
+    if config.propagators.is_empty() {
+        // IMPORTANT:
+        // LambdaXrayPropagator is added *before* TraceContextPropagator
+        // because in OpenTelemetry Rust, the *last* propagator that extracts
+        // a valid context wins during extraction.
+        // This ensures that if both an AWS X-Ray header (or _X_AMZN_TRACE_ID)
+        // and a W3C traceparent header are present, the W3C traceparent takes precedence.
+        config
+            .propagators
+            .push(Box::new(LambdaXrayPropagator::new()));
+        config
+            .propagators
+            .push(Box::new(TraceContextPropagator::new()));
+        config.propagator_names = vec!["xray-lambda".to_string(), "tracecontext".to_string()];
     }
 
     let composite_propagator = TextMapCompositePropagator::new(config.propagators);
     global::set_text_map_propagator(composite_propagator);
 
+    // The environment variable takes precedence over the ID generator configured in code
+    if let Ok(name) = env::var(constants::env_vars::ID_GENERATOR) {
+        match set_named_id_generator(&mut config.provider_builder, &name) {
+            Some(type_name) => config.id_generator_name = Some(type_name),
+            None => LOGGER.warn(format!("Unknown ID generator: {name}, ignoring it")),
+        }
+    }
+
+    // Copy the allowed baggage entries onto spans and events
+    let baggage_attributes = env::var(constants::env_vars::BAGGAGE_ATTRIBUTES)
+        .map(|value| baggage::parse_keys(&value))
+        .unwrap_or(config.baggage_attributes);
+    baggage::set_event_keys(baggage_attributes.clone());
+
     // Add default span processor if none was added
+    let exporter_name = if config.has_processor {
+        "custom".to_string()
+    } else {
+        config
+            .span_processor_names
+            .push(diagnostics::short_type_name::<
+                LambdaSpanProcessor<OtlpStdoutSpanExporter>,
+            >());
+        diagnostics::short_type_name::<OtlpStdoutSpanExporter>()
+    };
     if !config.has_processor {
         let processor = LambdaSpanProcessor::builder()
             .exporter(OtlpStdoutSpanExporter::default())
             .build();
         config.provider_builder = config.provider_builder.with_span_processor(processor);
     }
+    if !baggage_attributes.is_empty() {
+        config
+            .span_processor_names
+            .push(diagnostics::short_type_name::<BaggageSpanProcessor>());
+        config.provider_builder = config
+            .provider_builder
+            .with_span_processor(BaggageSpanProcessor::new(baggage_attributes));
+    }
 
     // Apply defaults and build the provider
-    let resource = config.resource.unwrap_or_else(get_lambda_resource);
+    let resource = merge_detected_attributes(
+        config.resource.unwrap_or_else(get_lambda_resource),
+        config.detected_resource_attributes,
+    );
+    let service_name = resource
+        .get(&opentelemetry::Key::from_static_str("service.name"))
+        .map(|value| value.to_string());
+
+    // Build the meter provider with the same resource when metrics are enabled
+    let enable_metrics = env_flag(constants::env_vars::ENABLE_METRICS, config.enable_metrics);
+    let meter_provider = enable_metrics.then(|| {
+        SdkMeterProvider::builder()
+            .with_resource(resource.clone())
+            .with_periodic_exporter(OtlpStdoutMetricExporter::default())
+            .build()
+    });
+
+    // Build the logger provider of the logs bridge when logs are enabled
+    let enable_logs = env_flag(constants::env_vars::ENABLE_LOGS, config.enable_logs);
+    let logger_provider = enable_logs.then(|| {
+        SdkLoggerProvider::builder()
+            .with_resource(resource.clone())
+            .with_log_processor(
+                BatchLogProcessor::builder(OtlpStdoutLogExporter::default()).build(),
+            )
+            .build()
+    });
 
     let provider = Arc::new(config.provider_builder.with_resource(resource).build());
 
     // Register the extension if in async or finalize mode
     let sender = match mode {
-        ProcessorMode::Async | ProcessorMode::Finalize => {
-            Some(register_extension(provider.clone(), mode.clone()).await?)
-        }
+        ProcessorMode::Async | ProcessorMode::Finalize => Some(
+            register_extension(
+                provider.clone(),
+                meter_provider.clone(),
+                logger_provider.clone(),
+                mode.clone(),
+            )
+            .await?,
+        ),
         _ => None,
     };
 
     if config.set_global_provider {
-        // Set the provider as global
+        // Set the providers as global
         set_tracer_provider(provider.as_ref().clone());
+        if let Some(meter_provider) = &meter_provider {
+            global::set_meter_provider(meter_provider.clone());
+        }
     }
 
     // Initialize tracing subscriber with smart env var selection
@@ -653,33 +1016,47 @@ pub async fn init_telemetry(
         .with_env_var(env_var_name)
         .from_env_lossy();
 
-    let completion_handler = TelemetryCompletionHandler::new(provider.clone(), sender, mode);
+    let log_layer = logger_provider
+        .as_ref()
+        .map(|logger_provider| OtelLogLayer::new(logger_provider.logger(env!("CARGO_PKG_NAME"))));
+
+    let completion_handler = TelemetryCompletionHandler::new(provider.clone(), sender, mode)
+        .with_meter_provider(meter_provider)
+        .with_logger_provider(logger_provider);
     let tracer = completion_handler.get_tracer().clone();
 
     let subscriber = tracing_subscriber::registry::Registry::default()
         .with(tracing_opentelemetry::OpenTelemetryLayer::new(
             tracer.clone(),
         ))
+        .with(log_layer)
         .with(env_filter);
 
     // Determine if fmt layer should be enabled - environment variable takes precedence when set
-    let enable_fmt = if let Ok(env_value) = env::var(constants::env_vars::ENABLE_FMT_LAYER) {
-        match env_value.to_lowercase().as_str() {
-            "true" => true,
-            "false" => false,
-            other => {
-                LOGGER.warn(format!(
-                    "Invalid value '{}' for {}, expected 'true' or 'false'. Using code configuration.",
-                    other,
-                    constants::env_vars::ENABLE_FMT_LAYER
-                ));
-                config.enable_fmt_layer
-            }
-        }
-    } else {
-        // If env var not set, use the configured value
-        config.enable_fmt_layer
-    };
+    let enable_fmt = env_flag(
+        constants::env_vars::ENABLE_FMT_LAYER,
+        config.enable_fmt_layer,
+    );
+
+    diagnostics::record_snapshot(TelemetryConfigSnapshot {
+        processor_mode: completion_handler.mode.to_string(),
+        processor_mode_source: mode_source,
+        span_processors: config.span_processor_names,
+        exporter: exporter_name,
+        sampler: config.sampler_name.unwrap_or_else(default_sampler_name),
+        id_generator: config
+            .id_generator_name
+            .unwrap_or_else(|| "RandomIdGenerator".to_string()),
+        propagators: config.propagator_names,
+        resource_detectors: config.resource_detector_names,
+        service_name,
+        fmt_layer_enabled: enable_fmt,
+        metrics_enabled: enable_metrics,
+        logs_enabled: enable_logs,
+        log_level_env_var: env_var_name.to_string(),
+        set_global_provider: config.set_global_provider,
+        env_vars: TelemetryConfigSnapshot::collect_env_vars(),
+    });
 
     // Enable fmt layer based on the determined value
     if enable_fmt {
@@ -715,20 +1092,87 @@ pub async fn init_telemetry(
     Ok((tracer, completion_handler))
 }
 
+/// Resolve a boolean setting, the environment variable taking precedence when set.
+///
+/// Invalid values log a warning and fall back to the configured value.
+fn env_flag(name: &str, configured: bool) -> bool {
+    match env::var(name) {
+        Ok(env_value) => match env_value.to_lowercase().as_str() {
+            "true" => true,
+            "false" => false,
+            other => {
+                LOGGER.warn(format!(
+                    "Invalid value '{other}' for {name}, expected 'true' or 'false'. Using code configuration."
+                ));
+                configured
+            }
+        },
+        // If env var not set, use the configured value
+        Err(_) => configured,
+    }
+}
+
+/// Name of the sampler the SDK uses when none is configured in code.
+///
+/// The SDK honours `OTEL_TRACES_SAMPLER`, falling back to `parentbased_always_on`.
+fn default_sampler_name() -> String {
+    match env::var("OTEL_TRACES_SAMPLER") {
+        Ok(sampler) => match env::var("OTEL_TRACES_SAMPLER_ARG") {
+            Ok(arg) => format!("{sampler} ({arg})"),
+            Err(_) => sampler,
+        },
+        Err(_) => "parentbased_always_on".to_string(),
+    }
+}
+
+// Sets the ID generator of a name on a tracer provider builder, returning the type name of
+// the generator, or `None` if the name is unknown
+fn set_named_id_generator(
+    provider_builder: &mut TracerProviderBuilder,
+    name: &str,
+) -> Option<String> {
+    let builder = std::mem::take(provider_builder);
+    let (builder, type_name) = match name.trim().to_lowercase().as_str() {
+        "xray" => (
+            builder.with_id_generator(XrayIdGenerator::default()),
+            diagnostics::short_type_name::<XrayIdGenerator>(),
+        ),
+        "random" => (
+            builder.with_id_generator(RandomIdGenerator::default()),
+            diagnostics::short_type_name::<RandomIdGenerator>(),
+        ),
+        _ => {
+            *provider_builder = builder;
+            return None;
+        }
+    };
+    *provider_builder = builder;
+    Some(type_name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::propagation::{B3Encoding, B3Propagator};
+    use opentelemetry::baggage::BaggageExt;
+    use opentelemetry::metrics::MeterProvider as _;
     use opentelemetry::trace::{Span, Tracer};
     use opentelemetry_aws::trace::XrayIdGenerator;
     use opentelemetry_sdk::trace::{Sampler, SimpleSpanProcessor};
+    use otlp_stdout_span_exporter::BufferOutput;
     use sealed_test::prelude::*;
     use std::sync::Arc;
     use tokio::sync::mpsc;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
 
     // Helper to clean up environment variables between tests
     fn cleanup_env() {
         env::remove_var(constants::env_vars::ENABLE_FMT_LAYER);
+        env::remove_var(constants::env_vars::ENABLE_METRICS);
+        env::remove_var(constants::env_vars::ENABLE_LOGS);
         env::remove_var(constants::env_vars::PROPAGATORS);
+        env::remove_var(constants::env_vars::ID_GENERATOR);
+        env::remove_var(constants::env_vars::BAGGAGE_ATTRIBUTES);
         env::remove_var(constants::env_vars::PROCESSOR_MODE);
         env::remove_var("_X_AMZN_TRACE_ID");
         env::remove_var("AWS_LAMBDA_RUNTIME_API");
@@ -780,6 +1224,26 @@ mod tests {
             .with_named_propagator("none")
             .build();
         assert_eq!(config.propagators.len(), 1);
+
+        // Test with a list of names, skipping unknown ones
+        let config = TelemetryConfig::builder()
+            .with_propagators(["tracecontext", "baggage", "b3multi", "jaeger"])
+            .build();
+        assert_eq!(
+            config.propagator_names,
+            vec!["tracecontext", "baggage", "b3multi"]
+        );
+
+        // Propagators added as values are named like the named ones
+        let config = TelemetryConfig::builder()
+            .with_propagator(TraceContextPropagator::new())
+            .with_propagator(B3Propagator::with_encoding(B3Encoding::MultipleHeaders))
+            .with_propagator(LambdaXrayPropagator::new())
+            .build();
+        assert_eq!(
+            config.propagator_names,
+            vec!["tracecontext", "b3multi", "xray-lambda"]
+        );
     }
 
     #[tokio::test]
@@ -815,11 +1279,21 @@ mod tests {
     async fn test_telemetry_config_env_propagators_combined() {
         cleanup_env();
 
-        // Test with OTEL_PROPAGATORS=tracecontext,xray-lambda
-        env::set_var(constants::env_vars::PROPAGATORS, "tracecontext,xray-lambda");
-        let (_, handler) = init_telemetry(TelemetryConfig::default()).await.unwrap();
+        // OTEL_PROPAGATORS replaces the propagators configured in code,
+        // ignoring unknown and duplicate names
+        env::set_var(
+            constants::env_vars::PROPAGATORS,
+            "tracecontext, B3,jaeger,tracecontext",
+        );
+        let config = TelemetryConfig::builder()
+            .with_named_propagator("xray")
+            .build();
+        let (_, handler) = init_telemetry(config).await.unwrap();
         assert!(handler.sender.is_none());
 
+        let snapshot = diagnostics::telemetry_config_snapshot().unwrap();
+        assert_eq!(snapshot.propagators, vec!["tracecontext", "b3"]);
+
         cleanup_env();
     }
 
@@ -841,6 +1315,83 @@ mod tests {
     async fn test_init_telemetry_defaults() {
         let (_, completion_handler) = init_telemetry(TelemetryConfig::default()).await.unwrap();
         assert!(completion_handler.sender.is_none()); // Default mode is Sync
+        assert!(completion_handler.get_meter_provider().is_none()); // Metrics are opt-in
+    }
+
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_init_telemetry_metrics() {
+        cleanup_env();
+
+        // The env var enables metrics even if the code setting is false
+        env::set_var(constants::env_vars::ENABLE_METRICS, "true");
+        let config = TelemetryConfig::default();
+        assert!(!config.enable_metrics);
+
+        let (_, completion_handler) = init_telemetry(config).await.unwrap();
+        assert!(completion_handler.get_meter_provider().is_some());
+        completion_handler.complete();
+
+        cleanup_env();
+    }
+
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_init_telemetry_logs() {
+        cleanup_env();
+
+        let config = TelemetryConfig::builder().enable_logs(true).build();
+        let (_, completion_handler) = init_telemetry(config).await.unwrap();
+        assert!(completion_handler.get_logger_provider().is_some());
+        assert!(completion_handler.get_meter_provider().is_none());
+        assert!(
+            diagnostics::telemetry_config_snapshot()
+                .unwrap()
+                .logs_enabled
+        );
+
+        // Events are bridged to log records, flushed on completion
+        tracing::info!(order_id = 42, "order processed");
+        completion_handler.complete();
+
+        cleanup_env();
+    }
+
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_init_telemetry_baggage_attributes() {
+        cleanup_env();
+        env::set_var(
+            constants::env_vars::BAGGAGE_ATTRIBUTES,
+            "tenant-id, user-tier",
+        );
+
+        let config = TelemetryConfig::builder()
+            .baggage_attributes(vec!["ignored".to_string()])
+            .build();
+        let (tracer, _) = init_telemetry(config).await.unwrap();
+
+        // The environment variable takes precedence over the code setting
+        let snapshot = diagnostics::telemetry_config_snapshot().unwrap();
+        assert!(snapshot
+            .span_processors
+            .contains(&"BaggageSpanProcessor".to_string()));
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+        let keys: Vec<String> = tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("handler");
+            let _ = span.set_parent(opentelemetry::Context::new().with_baggage(vec![
+                KeyValue::new("tenant-id", "acme"),
+                KeyValue::new("ignored", "value"),
+            ]));
+            baggage::event_attributes(&span)
+                .into_iter()
+                .map(|kv| kv.key.to_string())
+                .collect()
+        });
+        assert_eq!(keys, vec!["tenant-id"]);
+
+        cleanup_env();
     }
 
     #[tokio::test]
@@ -929,6 +1480,84 @@ mod tests {
         cleanup_env();
     }
 
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_init_telemetry_resource_detectors() {
+        cleanup_env();
+
+        struct HostDetector;
+
+        impl opentelemetry_sdk::resource::ResourceDetector for HostDetector {
+            fn detect(&self) -> Resource {
+                Resource::builder_empty()
+                    .with_attributes(vec![
+                        KeyValue::new("service.name", "detected"),
+                        KeyValue::new("host.name", "sandbox"),
+                    ])
+                    .build()
+            }
+        }
+
+        let config = TelemetryConfig::builder()
+            .resource(
+                Resource::builder_empty()
+                    .with_attributes(vec![KeyValue::new("service.name", "orders")])
+                    .build(),
+            )
+            .with_resource_detector(HostDetector)
+            .set_global_provider(false)
+            .build();
+        assert_eq!(config.detected_resource_attributes.len(), 2);
+        init_telemetry(config).await.unwrap();
+
+        // The configured resource takes precedence over the detected attributes
+        let snapshot = diagnostics::telemetry_config_snapshot().unwrap();
+        assert_eq!(snapshot.resource_detectors, vec!["HostDetector"]);
+        assert_eq!(snapshot.service_name.as_deref(), Some("orders"));
+
+        cleanup_env();
+    }
+
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_init_telemetry_records_config_snapshot() {
+        cleanup_env();
+        env::set_var("OTEL_EXPORTER_OTLP_HEADERS", "x-api-key=secret");
+
+        let config = TelemetryConfig::builder()
+            .resource(
+                Resource::builder()
+                    .with_attributes(vec![KeyValue::new("service.name", "orders")])
+                    .build(),
+            )
+            .with_named_propagator("tracecontext")
+            .with_sampler(Sampler::AlwaysOn)
+            .set_global_provider(false)
+            .build();
+        init_telemetry(config).await.unwrap();
+
+        let snapshot = diagnostics::telemetry_config_snapshot().unwrap();
+        assert_eq!(snapshot.processor_mode, "sync");
+        assert_eq!(
+            snapshot.processor_mode_source,
+            diagnostics::ConfigSource::Default
+        );
+        assert_eq!(snapshot.span_processors, vec!["LambdaSpanProcessor"]);
+        assert_eq!(snapshot.exporter, "OtlpStdoutSpanExporter");
+        assert_eq!(snapshot.sampler, "AlwaysOn");
+        assert_eq!(snapshot.id_generator, "RandomIdGenerator");
+        assert_eq!(snapshot.propagators, vec!["tracecontext"]);
+        assert_eq!(snapshot.service_name.as_deref(), Some("orders"));
+        assert!(!snapshot.set_global_provider);
+        assert_eq!(
+            snapshot.env_vars["OTEL_EXPORTER_OTLP_HEADERS"],
+            diagnostics::REDACTED
+        );
+
+        env::remove_var("OTEL_EXPORTER_OTLP_HEADERS");
+        cleanup_env();
+    }
+
     #[test]
     fn test_completion_handler_sync_mode() {
         let provider = Arc::new(
@@ -945,6 +1574,35 @@ mod tests {
         // doesn't expose this information, but we can verify it doesn't panic
     }
 
+    #[test]
+    fn test_completion_handler_sync_mode_flushes_metrics() {
+        let provider = Arc::new(SdkTracerProvider::builder().build());
+        let output = BufferOutput::new();
+        let meter_provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(OtlpStdoutMetricExporter::new(
+                OtlpStdoutSpanExporter::builder()
+                    .output(Arc::new(output.clone()))
+                    .build(),
+            ))
+            .build();
+
+        let handler = TelemetryCompletionHandler::new(provider, None, ProcessorMode::Sync)
+            .with_meter_provider(Some(meter_provider));
+        let counter = handler
+            .get_meter_provider()
+            .unwrap()
+            .meter("test")
+            .u64_counter("orders")
+            .build();
+        counter.add(2, &[]);
+
+        // In sync mode, complete() should also flush the recorded metrics
+        handler.complete();
+        let lines = output.take_lines().unwrap();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("/v1/metrics"));
+    }
+
     #[tokio::test]
     async fn test_completion_handler_async_mode() {
         let provider = Arc::new(
@@ -1093,6 +1751,42 @@ mod tests {
         assert_ne!(random_part, "000000000000000000000000");
     }
 
+    #[test]
+    #[sealed_test]
+    fn test_telemetry_config_with_named_id_generator() {
+        cleanup_env();
+
+        let config = TelemetryConfig::builder()
+            .with_named_id_generator(" XRay ")
+            .build();
+        assert_eq!(config.id_generator_name.as_deref(), Some("XrayIdGenerator"));
+
+        // Unknown names keep the default generator
+        let config = TelemetryConfig::builder()
+            .with_named_id_generator("snowflake")
+            .build();
+        assert!(config.id_generator_name.is_none());
+    }
+
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_init_telemetry_env_id_generator() {
+        cleanup_env();
+
+        // The environment variable takes precedence over the code configuration
+        env::set_var(constants::env_vars::ID_GENERATOR, "xray");
+        let config = TelemetryConfig::builder()
+            .with_named_id_generator("random")
+            .set_global_provider(false)
+            .build();
+        init_telemetry(config).await.unwrap();
+
+        let snapshot = diagnostics::telemetry_config_snapshot().unwrap();
+        assert_eq!(snapshot.id_generator, "XrayIdGenerator");
+
+        cleanup_env();
+    }
+
     #[test]
     #[sealed_test]
     fn test_telemetry_config_with_sampler() {
@@ -1116,6 +1810,10 @@ mod tests {
         let config = TelemetryConfig::builder()
             .with_sampler(Sampler::ParentBased(Box::new(Sampler::AlwaysOn)))
             .build();
+        assert_eq!(
+            config.sampler_name.as_deref(),
+            Some("ParentBased(AlwaysOn)")
+        );
 
         // Verify configurations don't panic
         let _provider = config.provider_builder.build();
@@ -1170,34 +1868,3 @@ mod tests {
         cleanup_env();
     }
 }
-
-// A simple no-op propagator
-#[derive(Debug)]
-struct NoopPropagator;
-
-impl NoopPropagator {
-    fn new() -> Self {
-        NoopPropagator
-    }
-}
-
-impl TextMapPropagator for NoopPropagator {
-    fn inject_context(
-        &self,
-        _cx: &opentelemetry::Context,
-        _injector: &mut dyn opentelemetry::propagation::Injector,
-    ) {
-    }
-
-    fn extract_with_context(
-        &self,
-        cx: &opentelemetry::Context,
-        _extractor: &dyn opentelemetry::propagation::Extractor,
-    ) -> opentelemetry::Context {
-        cx.clone()
-    }
-
-    fn fields(&self) -> opentelemetry::propagation::text_map_propagator::FieldIter<'_> {
-        opentelemetry::propagation::text_map_propagator::FieldIter::new(&[])
-    }
-}
@@ -0,0 +1,118 @@
+//! Micro-benchmark for the gzip compression step of the exporter.
+//!
+//! Compresses a synthetic OTLP payload at every compression level with the backend selected
+//! through this crate's features, and reports time per batch, throughput and compression ratio.
+//! Compare backends by running it once per feature set:
+//!
+//! ```text
+//! cargo bench -p otlp-stdout-span-exporter --bench compression
+//! cargo bench -p otlp-stdout-span-exporter --bench compression --features zlib-rs
+//! cargo bench -p otlp-stdout-span-exporter --bench compression --features zlib-ng-compat
+//! ```
+//!
+//! `BENCH_SPANS` (default: 100) and `BENCH_ITERATIONS` (default: 200) control the batch size
+//! and the number of timed iterations per level.
+
+use flate2::{write::GzEncoder, Compression};
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+use otlp_stdout_span_exporter::COMPRESSION_BACKEND;
+use prost::Message;
+use std::hint::black_box;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+fn env_or(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+fn attribute(key: &str, value: String) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue {
+            value: Some(any_value::Value::StringValue(value)),
+        }),
+    }
+}
+
+/// Builds a payload shaped like a typical Lambda invocation batch
+fn synthetic_payload(span_count: usize) -> Vec<u8> {
+    let spans = (0..span_count)
+        .map(|i| Span {
+            trace_id: (i as u128 / 10).to_be_bytes().to_vec(),
+            span_id: (i as u64).to_be_bytes().to_vec(),
+            name: format!("GET /orders/{}", i % 7),
+            start_time_unix_nano: 1_700_000_000_000_000_000 + i as u64 * 1_000_000,
+            end_time_unix_nano: 1_700_000_000_000_000_000 + i as u64 * 1_000_000 + 250_000,
+            attributes: vec![
+                attribute("http.request.method", "GET".to_string()),
+                attribute("url.path", format!("/orders/{}", i % 7)),
+                attribute(
+                    "faas.invocation_id",
+                    format!("{i:08x}-5e2c-4d4b-9a8e-c0ffee"),
+                ),
+                attribute(
+                    "user_agent.original",
+                    "Mozilla/5.0 (compatible)".to_string(),
+                ),
+            ],
+            ..Default::default()
+        })
+        .collect();
+
+    ExportTraceServiceRequest {
+        resource_spans: vec![ResourceSpans {
+            scope_spans: vec![ScopeSpans {
+                spans,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    }
+    .encode_to_vec()
+}
+
+fn compress(payload: &[u8], level: u32) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level));
+    encoder.write_all(payload).expect("compression failed");
+    encoder.finish().expect("compression failed")
+}
+
+fn main() {
+    let span_count = env_or("BENCH_SPANS", 100);
+    let iterations = env_or("BENCH_ITERATIONS", 200).max(1);
+    let payload = synthetic_payload(span_count);
+
+    println!(
+        "backend: {COMPRESSION_BACKEND}, spans: {span_count}, payload: {} bytes, iterations: {iterations}",
+        payload.len()
+    );
+    println!(
+        "{:>5} {:>12} {:>12} {:>12} {:>8}",
+        "level", "time/batch", "MiB/s", "compressed", "ratio"
+    );
+
+    for level in 0..=9 {
+        // Warm up once so allocations and lazy tables are not measured
+        let compressed_len = compress(&payload, level).len();
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            black_box(compress(black_box(&payload), level));
+        }
+        let per_batch = start.elapsed() / iterations as u32;
+        let throughput = payload.len() as f64
+            / per_batch.max(Duration::from_nanos(1)).as_secs_f64()
+            / (1024.0 * 1024.0);
+
+        println!(
+            "{level:>5} {:>12} {throughput:>12.1} {compressed_len:>12} {:>8.2}",
+            format!("{per_batch:.1?}"),
+            payload.len() as f64 / compressed_len as f64
+        );
+    }
+}
@@ -25,6 +25,15 @@ pub mod env_vars {
 
     /// Output type ("pipe" or "stdout", defaults to "stdout")
     pub const OUTPUT_TYPE: &str = "OTLP_STDOUT_SPAN_EXPORTER_OUTPUT_TYPE";
+
+    /// Whether to include a `payload_sha256` checksum in the output ("true" or "false")
+    pub const PAYLOAD_CHECKSUM: &str = "OTLP_STDOUT_SPAN_EXPORTER_PAYLOAD_CHECKSUM";
+
+    /// Whether to include a content-addressable `batch_id` in the output ("true" or "false")
+    pub const BATCH_ID: &str = "OTLP_STDOUT_SPAN_EXPORTER_BATCH_ID";
+
+    /// Whether to write an EMF metric line to stdout when an export fails ("true" or "false")
+    pub const EMF_ERROR_METRIC: &str = "OTLP_STDOUT_SPAN_EXPORTER_EMF_ERROR_METRIC";
 }
 
 /// Default values for configuration parameters.
@@ -38,11 +47,26 @@ pub mod defaults {
     /// Default endpoint for OTLP export.
     pub const ENDPOINT: &str = "http://localhost:4318/v1/traces";
 
+    /// Endpoint of the envelopes written by the metric exporter.
+    pub const METRICS_ENDPOINT: &str = "http://localhost:4318/v1/metrics";
+
+    /// Endpoint of the envelopes written by the log exporter.
+    pub const LOGS_ENDPOINT: &str = "http://localhost:4318/v1/logs";
+
     /// Default output type
     pub const OUTPUT_TYPE: &str = "stdout";
 
     /// Fixed path for named pipe
     pub const PIPE_PATH: &str = "/tmp/otlp-stdout-span-exporter.pipe";
+
+    /// Service name placeholder used by the OpenTelemetry SDK when no service name is configured.
+    pub const SDK_UNKNOWN_SERVICE_NAME: &str = "unknown_service";
+
+    /// CloudWatch namespace of the EMF error metric.
+    pub const EMF_NAMESPACE: &str = "OtlpStdoutSpanExporter";
+
+    /// Name of the EMF error metric.
+    pub const EMF_ERROR_METRIC_NAME: &str = "ExportFailures";
 }
 
 /// Resource attribute keys used in the Lambda resource.
@@ -50,4 +74,7 @@ pub mod resource_attributes {
     /// Resource attribute key for compression level.
     pub const COMPRESSION_LEVEL: &str =
         "lambda_otel_lite.otlp_stdout_span_exporter.compression_level";
+
+    /// Resource attribute key for the service name.
+    pub const SERVICE_NAME: &str = "service.name";
 }
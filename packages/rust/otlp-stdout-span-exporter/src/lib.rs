@@ -7,11 +7,13 @@
 //! # Features
 //!
 //! - Uses OTLP Protobuf serialization for efficient encoding
-//! - Applies GZIP compression with configurable levels
-//! - Detects service name from environment variables
+//! - Applies GZIP compression with configurable levels and a selectable backend
+//! - Detects service name from environment variables, the builder or the resource
 //! - Supports custom headers via environment variables
 //! - Supports writing to stdout or named pipe
 //! - Consistent JSON output format
+//! - Exports metrics in the same envelope with [`OtlpStdoutMetricExporter`]
+//! - Exports log records in the same envelope with [`OtlpStdoutLogExporter`]
 //!
 //! # Example
 //!
@@ -64,11 +66,15 @@
 //! The exporter respects the following environment variables:
 //!
 //! - `OTEL_SERVICE_NAME`: Service name to use in output
-//! - `AWS_LAMBDA_FUNCTION_NAME`: Fallback service name (if `OTEL_SERVICE_NAME` not set)
+//! - `AWS_LAMBDA_FUNCTION_NAME`: Fallback service name (if neither `OTEL_SERVICE_NAME`, the
+//!   `service_name` builder option nor the resource `service.name` is set)
 //! - `OTEL_EXPORTER_OTLP_HEADERS`: Global headers for OTLP export
 //! - `OTEL_EXPORTER_OTLP_TRACES_HEADERS`: Trace-specific headers (takes precedence if conflicting with `OTEL_EXPORTER_OTLP_HEADERS`)
 //! - `OTLP_STDOUT_SPAN_EXPORTER_COMPRESSION_LEVEL`: GZIP compression level (0-9, default: 6)
 //! - `OTLP_STDOUT_SPAN_EXPORTER_OUTPUT_TYPE`: Output type ("pipe" or "stdout", default: "stdout")
+//! - `OTLP_STDOUT_SPAN_EXPORTER_PAYLOAD_CHECKSUM`: Include a `payload_sha256` field ("true" or "false", default: "false")
+//! - `OTLP_STDOUT_SPAN_EXPORTER_BATCH_ID`: Include a content-addressable `batch_id` field ("true" or "false", default: "false")
+//! - `OTLP_STDOUT_SPAN_EXPORTER_EMF_ERROR_METRIC`: Write an EMF metric line to stdout when an export fails ("true" or "false", default: "false")
 //!
 //! # Configuration Precedence
 //!
@@ -113,10 +119,29 @@
 //!   "base64": true
 //! }
 //! ```
+//!
+//! When the payload checksum is enabled, the envelope also carries a `payload_sha256` field
+//! with the hex-encoded SHA-256 digest of the base64-decoded payload bytes, which lets
+//! consumers detect payloads corrupted in transit before attempting to decode them.
+//!
+//! When the batch id is enabled, the envelope carries a `batch_id` field derived from a hash
+//! of the uncompressed protobuf payload. Identical batches always get the same id, which lets
+//! the forwarder drop batches redelivered by the log transport.
+//!
+//! # Export Failures
+//!
+//! A failed export loses its spans. To make such failures observable, the exporter counts
+//! exported batches and failures; the counters are available through
+//! [`OtlpStdoutSpanExporter::stats_handle`], which keeps working after the exporter has been
+//! moved into a span processor. When the EMF error metric is enabled, every failure also writes
+//! a CloudWatch Embedded Metric Format line with an `ExportFailures` count to stdout, which
+//! CloudWatch Logs turns into a metric without any forwarder involved. This is most useful
+//! with pipe output, where stdout usually remains writable when the pipe does not.
 
 use base64::{engine::general_purpose::STANDARD as base64_engine, Engine};
 use bon::bon;
 use flate2::{write::GzEncoder, Compression};
+use opentelemetry::Key;
 use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
 use opentelemetry_proto::transform::common::tonic::ResourceAttributesWithSchema;
 use opentelemetry_proto::transform::trace::tonic::group_spans_by_resource_and_scope;
@@ -128,6 +153,7 @@ use opentelemetry_sdk::{
 };
 use prost::Message;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
     collections::HashMap,
     env,
@@ -137,11 +163,20 @@ use std::{
     path::PathBuf,
     result::Result,
     str::FromStr,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 mod constants;
-use constants::{defaults, env_vars};
+use constants::{defaults, env_vars, resource_attributes};
+
+mod logs;
+mod metrics;
+pub use logs::OtlpStdoutLogExporter;
+pub use metrics::OtlpStdoutMetricExporter;
 
 // Make the constants module and its sub-modules publicly available
 pub mod consts {
@@ -157,6 +192,26 @@ pub mod consts {
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Name of the default flate2 compression backend, omitted from the envelopes
+const DEFAULT_COMPRESSION_BACKEND: &str = "miniz_oxide";
+
+/// Name of the flate2 compression backend selected through this crate's cargo features
+///
+/// - `zlib-rs`: enabled by the `zlib-rs` feature
+/// - `zlib-ng-compat`: enabled by the `zlib-ng-compat` feature
+/// - `miniz_oxide`: the default pure-Rust backend
+///
+/// A non-default value is reported in the `compression_backend` field of the envelopes. It only reflects
+/// this crate's features; a backend enabled on flate2 directly elsewhere in the dependency
+/// graph is not detected.
+pub const COMPRESSION_BACKEND: &str = if cfg!(feature = "zlib-rs") {
+    "zlib-rs"
+} else if cfg!(feature = "zlib-ng-compat") {
+    "zlib-ng-compat"
+} else {
+    DEFAULT_COMPRESSION_BACKEND
+};
+
 /// Log level for the exported spans
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum LogLevel {
@@ -302,6 +357,44 @@ impl Output for NamedPipeOutput {
     }
 }
 
+/// Snapshot of the exporter's counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExportStats {
+    /// Number of batches written successfully
+    pub exported_batches: u64,
+    /// Number of exports that failed, losing their spans
+    pub failed_exports: u64,
+    /// Number of EMF error metric lines written to stdout
+    pub emf_metrics_written: u64,
+}
+
+#[derive(Debug, Default)]
+struct ExportCounters {
+    exported_batches: AtomicU64,
+    failed_exports: AtomicU64,
+    emf_metrics_written: AtomicU64,
+}
+
+/// Handle to the counters of an [`OtlpStdoutSpanExporter`]
+///
+/// The handle shares the counters with the exporter, so it can be kept after the exporter has
+/// been moved into a span processor.
+#[derive(Debug, Clone)]
+pub struct ExportStatsHandle {
+    counters: Arc<ExportCounters>,
+}
+
+impl ExportStatsHandle {
+    /// Returns the current value of the counters
+    pub fn snapshot(&self) -> ExportStats {
+        ExportStats {
+            exported_batches: self.counters.exported_batches.load(Ordering::Relaxed),
+            failed_exports: self.counters.failed_exports.load(Ordering::Relaxed),
+            emf_metrics_written: self.counters.emf_metrics_written.load(Ordering::Relaxed),
+        }
+    }
+}
+
 /// An Output implementation that writes lines to an internal buffer.
 #[derive(Clone, Default)]
 pub struct BufferOutput {
@@ -368,6 +461,9 @@ fn create_output(use_pipe: bool) -> Arc<dyn Output> {
 ///
 /// This struct defines the JSON structure that will be written to stdout
 /// for each batch of spans.
+///
+/// Code constructing it with a struct literal must set every field; field additions are listed
+/// as breaking changes in the changelog.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExporterOutput {
     /// Version identifier for the output format
@@ -375,7 +471,7 @@ pub struct ExporterOutput {
     pub version: String,
     /// Service name that generated the spans
     pub source: String,
-    /// OTLP endpoint (always http://localhost:4318/v1/traces)
+    /// OTLP endpoint (http://localhost:4318/v1/traces, or /v1/metrics for metrics)
     pub endpoint: String,
     /// HTTP method (always POST)
     pub method: String,
@@ -395,6 +491,15 @@ pub struct ExporterOutput {
     /// Log level for filtering (optional)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub level: Option<String>,
+    /// Hex-encoded SHA-256 digest of the base64-decoded payload (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_sha256: Option<String>,
+    /// Content-addressable identifier of the batch, derived from the protobuf payload (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    /// Compression backend that produced the payload, for diagnostics (omitted for the default)
+    #[serde(skip_serializing_if = "ExporterOutput::is_default_compression_backend")]
+    pub compression_backend: Option<String>,
 }
 
 impl ExporterOutput {
@@ -402,6 +507,56 @@ impl ExporterOutput {
     fn is_headers_empty(headers: &Option<HashMap<String, String>>) -> bool {
         headers.as_ref().is_none_or(|h| h.is_empty())
     }
+
+    /// Helper function for serde to skip serializing the default compression backend
+    fn is_default_compression_backend(backend: &Option<String>) -> bool {
+        backend
+            .as_deref()
+            .is_none_or(|backend| backend == DEFAULT_COMPRESSION_BACKEND)
+    }
+
+    /// Computes the checksum carried in the `payload_sha256` field.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The payload bytes after base64 decoding
+    ///
+    /// # Returns
+    ///
+    /// The lowercase hex-encoded SHA-256 digest of the payload
+    pub fn compute_payload_sha256(payload: &[u8]) -> String {
+        hex::encode(Sha256::digest(payload))
+    }
+
+    /// Verifies the payload against the `payload_sha256` field.
+    ///
+    /// Records produced without a checksum are always considered valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `payload` - The payload bytes after base64 decoding
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if no checksum is present or if it matches the payload
+    pub fn verify_payload_sha256(&self, payload: &[u8]) -> bool {
+        self.payload_sha256.as_deref().is_none_or(|expected| {
+            expected.eq_ignore_ascii_case(&Self::compute_payload_sha256(payload))
+        })
+    }
+
+    /// Computes the content-addressable id carried in the `batch_id` field.
+    ///
+    /// # Arguments
+    ///
+    /// * `protobuf_payload` - The uncompressed protobuf-serialized span data
+    ///
+    /// # Returns
+    ///
+    /// The first 16 bytes of the SHA-256 digest of the payload, hex encoded
+    pub fn compute_batch_id(protobuf_payload: &[u8]) -> String {
+        hex::encode(&Sha256::digest(protobuf_payload)[..16])
+    }
 }
 
 /// A span exporter that writes spans to stdout in OTLP format
@@ -439,6 +594,18 @@ pub struct OtlpStdoutSpanExporter {
     output: Arc<dyn Output>,
     /// Optional log level for the exported spans
     level: Option<LogLevel>,
+    /// Whether to include a payload checksum in the output
+    payload_checksum: bool,
+    /// Whether to include a content-addressable batch id in the output
+    batch_id: bool,
+    /// Optional service name set programmatically
+    service_name: Option<String>,
+    /// Whether to write an EMF metric line when an export fails
+    emf_error_metric: bool,
+    /// Output used for the EMF error metric (stdout)
+    emf_output: Arc<dyn Output>,
+    /// Export counters, shared with [`ExportStatsHandle`]s
+    stats: Arc<ExportCounters>,
 }
 
 impl Default for OtlpStdoutSpanExporter {
@@ -475,6 +642,10 @@ impl OtlpStdoutSpanExporter {
         output: Option<Arc<dyn Output>>,
         level: Option<LogLevel>,
         pipe: Option<bool>,
+        payload_checksum: Option<bool>,
+        batch_id: Option<bool>,
+        #[builder(into)] service_name: Option<String>,
+        emf_error_metric: Option<bool>,
     ) -> Self {
         // Set gzip_level with proper precedence (env var > constructor param > default)
         let compression_level = match env::var(env_vars::COMPRESSION_LEVEL) {
@@ -547,26 +718,206 @@ impl OtlpStdoutSpanExporter {
         // Create output implementation
         let output = output.unwrap_or_else(|| create_output(use_pipe));
 
+        // Determine optional envelope fields with proper precedence (env var > constructor > default)
+        let payload_checksum = Self::parse_bool_env(
+            env_vars::PAYLOAD_CHECKSUM,
+            payload_checksum.unwrap_or(false),
+        );
+        let batch_id = Self::parse_bool_env(env_vars::BATCH_ID, batch_id.unwrap_or(false));
+        let emf_error_metric = Self::parse_bool_env(
+            env_vars::EMF_ERROR_METRIC,
+            emf_error_metric.unwrap_or(false),
+        );
+
         Self {
             compression_level,
             resource,
             headers,
             output,
             level,
+            payload_checksum,
+            batch_id,
+            service_name,
+            emf_error_metric,
+            emf_output: Arc::new(StdOutput),
+            stats: Arc::default(),
+        }
+    }
+
+    /// Returns the current value of the export counters
+    pub fn stats(&self) -> ExportStats {
+        self.stats_handle().snapshot()
+    }
+
+    /// Returns a handle to the export counters that outlives moving the exporter
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use otlp_stdout_span_exporter::OtlpStdoutSpanExporter;
+    /// use opentelemetry_sdk::trace::SdkTracerProvider;
+    ///
+    /// let exporter = OtlpStdoutSpanExporter::default();
+    /// let stats = exporter.stats_handle();
+    /// let provider = SdkTracerProvider::builder()
+    ///     .with_simple_exporter(exporter)
+    ///     .build();
+    ///
+    /// assert_eq!(stats.snapshot().failed_exports, 0);
+    /// ```
+    pub fn stats_handle(&self) -> ExportStatsHandle {
+        ExportStatsHandle {
+            counters: Arc::clone(&self.stats),
+        }
+    }
+
+    /// Records a failed export and, if enabled, writes the EMF error metric
+    fn record_export_failure(&self) {
+        self.stats.failed_exports.fetch_add(1, Ordering::Relaxed);
+        if !self.emf_error_metric {
+            return;
+        }
+        // If stdout is not usable either, the failure remains visible through the counters
+        match self.emf_output.write_line(&self.emf_error_metric_line()) {
+            Ok(()) => {
+                self.stats
+                    .emf_metrics_written
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+            Err(_) => log::warn!("Failed to write EMF error metric"),
+        }
+    }
+
+    /// Builds an EMF line reporting a single export failure for this service
+    fn emf_error_metric_line(&self) -> String {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        serde_json::json!({
+            "_aws": {
+                "Timestamp": timestamp,
+                "CloudWatchMetrics": [{
+                    "Namespace": defaults::EMF_NAMESPACE,
+                    "Dimensions": [["ServiceName"]],
+                    "Metrics": [{ "Name": defaults::EMF_ERROR_METRIC_NAME, "Unit": "Count" }],
+                }],
+            },
+            "ServiceName": self.get_service_name(),
+            defaults::EMF_ERROR_METRIC_NAME: 1,
+        })
+        .to_string()
+    }
+
+    /// Compresses a protobuf-serialized OTLP request and writes it in an envelope
+    ///
+    /// This function:
+    /// 1. Compresses the request with GZIP
+    /// 2. Base64 encodes the result
+    /// 3. Writes a JSON object to the output, addressed to `endpoint`
+    ///
+    /// Successful and failed writes are recorded in the export counters.
+    pub(crate) fn write_envelope(&self, proto_bytes: &[u8], endpoint: &str) -> OTelSdkResult {
+        let result = (|| {
+            let batch_id = self
+                .batch_id
+                .then(|| ExporterOutput::compute_batch_id(proto_bytes));
+
+            // Compress with GZIP
+            let mut encoder =
+                GzEncoder::new(Vec::new(), Compression::new(self.compression_level as u32));
+            encoder
+                .write_all(proto_bytes)
+                .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
+            let compressed_bytes = encoder
+                .finish()
+                .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
+
+            // Compute the optional checksum over the bytes that will be base64 encoded
+            let payload_sha256 = self
+                .payload_checksum
+                .then(|| ExporterOutput::compute_payload_sha256(&compressed_bytes));
+
+            // Base64 encode
+            let payload = base64_engine.encode(compressed_bytes);
+
+            // Prepare the output
+            let output_data = ExporterOutput {
+                version: VERSION.to_string(),
+                source: self.get_service_name(),
+                endpoint: endpoint.to_string(),
+                method: "POST".to_string(),
+                content_type: "application/x-protobuf".to_string(),
+                content_encoding: "gzip".to_string(),
+                headers: self.headers.clone(),
+                payload,
+                base64: true,
+                level: self.level.map(|l| l.to_string()),
+                payload_sha256,
+                batch_id,
+                compression_backend: Some(COMPRESSION_BACKEND.to_string()),
+            };
+
+            // Write using the output implementation
+            self.output.write_line(
+                &serde_json::to_string(&output_data)
+                    .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?,
+            )?;
+
+            Ok(())
+        })();
+
+        match &result {
+            Ok(()) => {
+                self.stats.exported_batches.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(_) => self.record_export_failure(),
+        }
+
+        result
+    }
+
+    /// Parse a boolean flag from an environment variable
+    ///
+    /// Accepts "true"/"1" and "false"/"0" (case-insensitive). Invalid values are
+    /// logged and, like unset variables, resolve to the fallback.
+    fn parse_bool_env(var_name: &str, fallback: bool) -> bool {
+        match env::var(var_name) {
+            Ok(value) => match value.to_lowercase().as_str() {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => {
+                    log::warn!("Invalid value in {}: {}, using fallback", var_name, value);
+                    fallback
+                }
+            },
+            Err(_) => fallback,
         }
     }
 
-    /// Get the service name from environment variables.
+    /// Get the service name used as the `source` of the output.
     ///
     /// The service name is determined in the following order:
     ///
     /// 1. OTEL_SERVICE_NAME
-    /// 2. AWS_LAMBDA_FUNCTION_NAME
-    /// 3. "unknown-service" (fallback)
-    fn get_service_name() -> String {
+    /// 2. The `service_name` builder option
+    /// 3. The `service.name` attribute of the resource, unless it is the SDK's
+    ///    `unknown_service` placeholder
+    /// 4. AWS_LAMBDA_FUNCTION_NAME
+    /// 5. "unknown-service" (fallback)
+    fn get_service_name(&self) -> String {
         env::var(env_vars::SERVICE_NAME)
-            .or_else(|_| env::var(env_vars::AWS_LAMBDA_FUNCTION_NAME))
-            .unwrap_or_else(|_| defaults::SERVICE_NAME.to_string())
+            .ok()
+            .or_else(|| self.service_name.clone())
+            .or_else(|| {
+                self.resource
+                    .as_ref()?
+                    .get(&Key::from_static_str(resource_attributes::SERVICE_NAME))
+                    .map(|value| value.to_string())
+                    .filter(|name| !name.starts_with(defaults::SDK_UNKNOWN_SERVICE_NAME))
+            })
+            .or_else(|| env::var(env_vars::AWS_LAMBDA_FUNCTION_NAME).ok())
+            .unwrap_or_else(|| defaults::SERVICE_NAME.to_string())
     }
 
     #[cfg(test)]
@@ -670,55 +1021,17 @@ impl SpanExporter for OtlpStdoutSpanExporter {
             return Box::pin(std::future::ready(touch_result));
         }
 
-        // Original export logic for non-empty batches or stdout output
-        let result = (|| {
-            // Convert spans to OTLP format
-            let resource = self
-                .resource
-                .clone()
-                .unwrap_or_else(|| opentelemetry_sdk::Resource::builder_empty().build());
-            let resource_attrs = ResourceAttributesWithSchema::from(&resource);
-            let resource_spans = group_spans_by_resource_and_scope(batch, &resource_attrs);
-            let request = ExportTraceServiceRequest { resource_spans };
-
-            // Serialize to protobuf
-            let proto_bytes = request.encode_to_vec();
-
-            // Compress with GZIP
-            let mut encoder =
-                GzEncoder::new(Vec::new(), Compression::new(self.compression_level as u32));
-            encoder
-                .write_all(&proto_bytes)
-                .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
-            let compressed_bytes = encoder
-                .finish()
-                .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
-
-            // Base64 encode
-            let payload = base64_engine.encode(compressed_bytes);
-
-            // Prepare the output
-            let output_data = ExporterOutput {
-                version: VERSION.to_string(),
-                source: Self::get_service_name(),
-                endpoint: defaults::ENDPOINT.to_string(),
-                method: "POST".to_string(),
-                content_type: "application/x-protobuf".to_string(),
-                content_encoding: "gzip".to_string(),
-                headers: self.headers.clone(),
-                payload,
-                base64: true,
-                level: self.level.map(|l| l.to_string()),
-            };
-
-            // Write using the output implementation
-            self.output.write_line(
-                &serde_json::to_string(&output_data)
-                    .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?,
-            )?;
+        // Convert spans to OTLP format
+        let resource = self
+            .resource
+            .clone()
+            .unwrap_or_else(|| opentelemetry_sdk::Resource::builder_empty().build());
+        let resource_attrs = ResourceAttributesWithSchema::from(&resource);
+        let resource_spans = group_spans_by_resource_and_scope(batch, &resource_attrs);
+        let request = ExportTraceServiceRequest { resource_spans };
 
-            Ok(())
-        })();
+        // Serialize to protobuf and write the envelope
+        let result = self.write_envelope(&request.encode_to_vec(), defaults::ENDPOINT);
 
         // Return a resolved future with the result
         Box::pin(std::future::ready(result))
@@ -935,24 +1248,58 @@ mod tests {
 
     #[test]
     fn test_service_name_resolution() {
+        let exporter = OtlpStdoutSpanExporter::default();
+
         // Test OTEL_SERVICE_NAME priority
         std::env::set_var(env_vars::SERVICE_NAME, "otel-service");
         std::env::set_var(env_vars::AWS_LAMBDA_FUNCTION_NAME, "lambda-function");
-        assert_eq!(OtlpStdoutSpanExporter::get_service_name(), "otel-service");
+        assert_eq!(exporter.get_service_name(), "otel-service");
 
         // Test AWS_LAMBDA_FUNCTION_NAME fallback
         std::env::remove_var(env_vars::SERVICE_NAME);
-        assert_eq!(
-            OtlpStdoutSpanExporter::get_service_name(),
-            "lambda-function"
-        );
+        assert_eq!(exporter.get_service_name(), "lambda-function");
 
         // Test default fallback
         std::env::remove_var(env_vars::AWS_LAMBDA_FUNCTION_NAME);
-        assert_eq!(
-            OtlpStdoutSpanExporter::get_service_name(),
-            defaults::SERVICE_NAME
+        assert_eq!(exporter.get_service_name(), defaults::SERVICE_NAME);
+    }
+
+    #[test]
+    #[serial]
+    fn test_service_name_builder_and_resource_precedence() {
+        std::env::remove_var(env_vars::SERVICE_NAME);
+        std::env::set_var(env_vars::AWS_LAMBDA_FUNCTION_NAME, "lambda-function");
+
+        // Builder option wins over the resource and the function name
+        let mut exporter = OtlpStdoutSpanExporter::builder()
+            .service_name("checkout-api")
+            .build();
+        exporter.set_resource(
+            &Resource::builder_empty()
+                .with_service_name("resource-service")
+                .build(),
+        );
+        assert_eq!(exporter.get_service_name(), "checkout-api");
+
+        // OTEL_SERVICE_NAME still takes precedence over the builder option
+        std::env::set_var(env_vars::SERVICE_NAME, "otel-service");
+        assert_eq!(exporter.get_service_name(), "otel-service");
+        std::env::remove_var(env_vars::SERVICE_NAME);
+
+        // Resource service.name is used when no builder option is set
+        let mut exporter = OtlpStdoutSpanExporter::default();
+        exporter.set_resource(
+            &Resource::builder_empty()
+                .with_service_name("resource-service")
+                .build(),
         );
+        assert_eq!(exporter.get_service_name(), "resource-service");
+
+        // The SDK's unknown_service placeholder is ignored
+        exporter.set_resource(&Resource::builder().build());
+        assert_eq!(exporter.get_service_name(), "lambda-function");
+
+        std::env::remove_var(env_vars::AWS_LAMBDA_FUNCTION_NAME);
     }
 
     #[test]
@@ -1024,6 +1371,12 @@ mod tests {
             output: no_compression_output.clone() as Arc<dyn Output>,
             headers: None,
             level: None,
+            payload_checksum: false,
+            batch_id: false,
+            service_name: None,
+            emf_error_metric: false,
+            emf_output: Arc::new(StdOutput),
+            stats: Arc::default(),
         };
         let _ = no_compression_exporter.export(spans.clone()).await;
         let no_compression_size = extract_payload_size(&no_compression_output.get_output()[0]);
@@ -1036,6 +1389,12 @@ mod tests {
             output: max_compression_output.clone() as Arc<dyn Output>,
             headers: None,
             level: None,
+            payload_checksum: false,
+            batch_id: false,
+            service_name: None,
+            emf_error_metric: false,
+            emf_output: Arc::new(StdOutput),
+            stats: Arc::default(),
         };
         let _ = max_compression_exporter.export(spans.clone()).await;
         let max_compression_size = extract_payload_size(&max_compression_output.get_output()[0]);
@@ -1101,6 +1460,11 @@ mod tests {
         assert_eq!(json["content-type"], "application/x-protobuf");
         assert_eq!(json["content-encoding"], "gzip");
         assert_eq!(json["base64"], true);
+        if COMPRESSION_BACKEND == DEFAULT_COMPRESSION_BACKEND {
+            assert!(json.get("compression_backend").is_none());
+        } else {
+            assert_eq!(json["compression_backend"], COMPRESSION_BACKEND);
+        }
 
         // Verify payload is valid base64 and can be decoded
         let payload = json["payload"].as_str().unwrap();
@@ -1182,6 +1546,58 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_export_failures_are_counted_and_emitted_as_emf() {
+        std::env::remove_var(env_vars::EMF_ERROR_METRIC);
+        let exporter = OtlpStdoutSpanExporter::builder()
+            .output(Arc::new(FailingOutput))
+            .service_name("checkout")
+            .emf_error_metric(true)
+            .build();
+        let emf_output = Arc::new(TestOutput::new());
+        let exporter = OtlpStdoutSpanExporter {
+            emf_output: emf_output.clone(),
+            ..exporter
+        };
+        let stats = exporter.stats_handle();
+
+        assert!(exporter.export(vec![create_test_span()]).await.is_err());
+        assert!(exporter.export(vec![create_test_span()]).await.is_err());
+
+        assert_eq!(
+            stats.snapshot(),
+            ExportStats {
+                exported_batches: 0,
+                failed_exports: 2,
+                emf_metrics_written: 2,
+            }
+        );
+        let lines = emf_output.get_output();
+        assert_eq!(lines.len(), 2);
+        let emf: Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(emf["ServiceName"], "checkout");
+        assert_eq!(emf["ExportFailures"], 1);
+        assert_eq!(
+            emf["_aws"]["CloudWatchMetrics"][0]["Namespace"],
+            defaults::EMF_NAMESPACE
+        );
+        assert!(emf["_aws"]["Timestamp"].as_u64().unwrap() > 0);
+
+        // Without EMF, failures are only counted; successes are counted as well
+        let (exporter, output) = OtlpStdoutSpanExporter::with_test_output();
+        exporter.export(vec![create_test_span()]).await.unwrap();
+        assert_eq!(exporter.stats().exported_batches, 1);
+        let exporter = OtlpStdoutSpanExporter {
+            output: Arc::new(FailingOutput),
+            ..exporter
+        };
+        assert!(exporter.export(vec![create_test_span()]).await.is_err());
+        assert_eq!(exporter.stats().failed_exports, 1);
+        assert_eq!(exporter.stats().emf_metrics_written, 0);
+        assert_eq!(output.get_output().len(), 1);
+    }
+
     #[test]
     #[serial]
     fn test_gzip_level_configuration() {
@@ -1586,6 +2002,12 @@ mod tests {
             headers: None,
             output: Arc::new(NamedPipeOutput { path: path.clone() }),
             level: None,
+            payload_checksum: false,
+            batch_id: false,
+            service_name: None,
+            emf_error_metric: false,
+            emf_output: Arc::new(StdOutput),
+            stats: Arc::default(),
         };
 
         exporter.export(vec![]).await.unwrap();
@@ -1595,6 +2017,120 @@ mod tests {
         std::fs::remove_file(path).unwrap();
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_payload_checksum_in_output() {
+        std::env::remove_var(env_vars::PAYLOAD_CHECKSUM);
+        let output = Arc::new(TestOutput::new());
+        let exporter = OtlpStdoutSpanExporter::builder()
+            .output(output.clone())
+            .payload_checksum(true)
+            .build();
+
+        exporter.export(vec![create_test_span()]).await.unwrap();
+
+        let lines = output.get_output();
+        let record: ExporterOutput = serde_json::from_str(&lines[0]).unwrap();
+        let decoded = base64_engine.decode(&record.payload).unwrap();
+        let checksum = record.payload_sha256.as_deref().unwrap();
+        assert_eq!(checksum.len(), 64);
+        assert_eq!(checksum, ExporterOutput::compute_payload_sha256(&decoded));
+        assert!(record.verify_payload_sha256(&decoded));
+        assert!(!record.verify_payload_sha256(&decoded[1..]));
+
+        // Checksum is omitted by default
+        let (exporter, output) = OtlpStdoutSpanExporter::with_test_output();
+        exporter.export(vec![create_test_span()]).await.unwrap();
+        let json: Value = serde_json::from_str(&output.get_output()[0]).unwrap();
+        assert!(!json.as_object().unwrap().contains_key("payload_sha256"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_payload_checksum_env_precedence() {
+        std::env::set_var(env_vars::PAYLOAD_CHECKSUM, "true");
+        let exporter = OtlpStdoutSpanExporter::builder()
+            .payload_checksum(false)
+            .build();
+        assert!(exporter.payload_checksum);
+
+        std::env::set_var(env_vars::PAYLOAD_CHECKSUM, "invalid");
+        let exporter = OtlpStdoutSpanExporter::builder()
+            .payload_checksum(true)
+            .build();
+        assert!(exporter.payload_checksum);
+
+        std::env::remove_var(env_vars::PAYLOAD_CHECKSUM);
+        let exporter = OtlpStdoutSpanExporter::default();
+        assert!(!exporter.payload_checksum);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_batch_id_is_content_addressable() {
+        std::env::remove_var(env_vars::BATCH_ID);
+        let output = Arc::new(TestOutput::new());
+        let exporter = OtlpStdoutSpanExporter::builder()
+            .output(output.clone())
+            .batch_id(true)
+            .build();
+
+        exporter.export(vec![create_test_span()]).await.unwrap();
+        exporter.export(vec![create_test_span()]).await.unwrap();
+        let mut other_span = create_test_span();
+        other_span.name = "other-span".into();
+        exporter.export(vec![other_span]).await.unwrap();
+
+        let ids: Vec<String> = output
+            .get_output()
+            .iter()
+            .map(|line| {
+                let record: ExporterOutput = serde_json::from_str(line).unwrap();
+                record.batch_id.unwrap()
+            })
+            .collect();
+        assert_eq!(ids[0].len(), 32);
+        assert_eq!(ids[0], ids[1]);
+        assert_ne!(ids[0], ids[2]);
+
+        // Batch id is omitted by default
+        let (exporter, output) = OtlpStdoutSpanExporter::with_test_output();
+        exporter.export(vec![create_test_span()]).await.unwrap();
+        let json: Value = serde_json::from_str(&output.get_output()[0]).unwrap();
+        assert!(!json.as_object().unwrap().contains_key("batch_id"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_batch_id_env_precedence() {
+        std::env::set_var(env_vars::BATCH_ID, "1");
+        let exporter = OtlpStdoutSpanExporter::builder().batch_id(false).build();
+        assert!(exporter.batch_id);
+
+        std::env::remove_var(env_vars::BATCH_ID);
+        let exporter = OtlpStdoutSpanExporter::builder().batch_id(true).build();
+        assert!(exporter.batch_id);
+    }
+
+    #[test]
+    fn test_verify_payload_sha256_without_checksum() {
+        let record: ExporterOutput = serde_json::from_str(
+            r#"{
+                "__otel_otlp_stdout": "0.17.1",
+                "source": "test-service",
+                "endpoint": "http://localhost:4318/v1/traces",
+                "method": "POST",
+                "content-type": "application/x-protobuf",
+                "content-encoding": "gzip",
+                "payload": "SGVsbG8gd29ybGQ=",
+                "base64": true
+            }"#,
+        )
+        .unwrap();
+        assert!(record.payload_sha256.is_none());
+        assert!(record.verify_payload_sha256(b"anything"));
+    }
+
     #[test]
     fn test_stdout_output() {
         let output = create_output(false);
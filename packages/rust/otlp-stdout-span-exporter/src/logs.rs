@@ -0,0 +1,142 @@
+//! A log exporter that writes OpenTelemetry log records to stdout in OTLP format.
+//!
+//! Log records are written in the same envelope as spans, addressed to the `/v1/logs`
+//! endpoint, so the forwarder can deliver logs and spans from the same log stream.
+
+use crate::{constants::defaults, ExportStatsHandle, OtlpStdoutSpanExporter};
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+use opentelemetry_proto::transform::common::tonic::ResourceAttributesWithSchema;
+use opentelemetry_proto::transform::logs::tonic::group_logs_by_resource_and_scope;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter};
+use opentelemetry_sdk::trace::SpanExporter;
+use opentelemetry_sdk::Resource;
+use prost::Message;
+
+/// A log exporter that writes log records to stdout in OTLP format
+///
+/// This exporter implements the OpenTelemetry [`LogExporter`] trait. It shares the
+/// configuration of an [`OtlpStdoutSpanExporter`]: the compression level, output (stdout or
+/// named pipe), headers, log level and optional envelope fields all apply to the log
+/// envelopes, whose `endpoint` is `http://localhost:4318/v1/logs`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use opentelemetry_sdk::logs::SdkLoggerProvider;
+/// use otlp_stdout_span_exporter::OtlpStdoutLogExporter;
+///
+/// let provider = SdkLoggerProvider::builder()
+///     .with_simple_exporter(OtlpStdoutLogExporter::default())
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct OtlpStdoutLogExporter {
+    /// Exporter writing the envelopes
+    exporter: OtlpStdoutSpanExporter,
+    /// Resource of the exported log records, set by the logger provider
+    resource: ResourceAttributesWithSchema,
+}
+
+impl Default for OtlpStdoutLogExporter {
+    fn default() -> Self {
+        Self::new(OtlpStdoutSpanExporter::default())
+    }
+}
+
+impl OtlpStdoutLogExporter {
+    /// Create a log exporter writing envelopes with the configuration of a span exporter.
+    pub fn new(exporter: OtlpStdoutSpanExporter) -> Self {
+        Self {
+            exporter,
+            resource: ResourceAttributesWithSchema::default(),
+        }
+    }
+
+    /// Returns a handle to the export counters that outlives moving the exporter
+    pub fn stats_handle(&self) -> ExportStatsHandle {
+        self.exporter.stats_handle()
+    }
+}
+
+impl LogExporter for OtlpStdoutLogExporter {
+    /// Export log records to stdout in OTLP format
+    ///
+    /// The records are grouped by resource and scope into an OTLP export request, serialized
+    /// to protobuf and written in an envelope, like the spans of [`OtlpStdoutSpanExporter`].
+    fn export(
+        &self,
+        batch: LogBatch<'_>,
+    ) -> impl std::future::Future<Output = OTelSdkResult> + Send {
+        let request = ExportLogsServiceRequest {
+            resource_logs: group_logs_by_resource_and_scope(batch, &self.resource),
+        };
+        let result = self
+            .exporter
+            .write_envelope(&request.encode_to_vec(), defaults::LOGS_ENDPOINT);
+        std::future::ready(result)
+    }
+
+    /// Sets the resource of the log records
+    ///
+    /// The resource is also used to resolve the `source` of the envelopes, like for spans.
+    fn set_resource(&mut self, resource: &Resource) {
+        self.resource = resource.into();
+        SpanExporter::set_resource(&mut self.exporter, resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BufferOutput, ExporterOutput};
+    use base64::{engine::general_purpose::STANDARD as base64_engine, Engine};
+    use flate2::read::GzDecoder;
+    use opentelemetry::logs::{AnyValue, LogRecord, Logger, LoggerProvider, Severity};
+    use opentelemetry::KeyValue;
+    use opentelemetry_sdk::logs::SdkLoggerProvider;
+    use serial_test::serial;
+    use std::io::Read;
+    use std::sync::Arc;
+
+    #[test]
+    #[serial]
+    fn test_log_export_envelope() {
+        std::env::remove_var("OTEL_SERVICE_NAME");
+        let output = BufferOutput::new();
+        let exporter = OtlpStdoutLogExporter::new(
+            OtlpStdoutSpanExporter::builder()
+                .output(Arc::new(output.clone()))
+                .build(),
+        );
+        let provider = SdkLoggerProvider::builder()
+            .with_resource(
+                Resource::builder_empty()
+                    .with_attribute(KeyValue::new("service.name", "orders"))
+                    .build(),
+            )
+            .with_simple_exporter(exporter)
+            .build();
+
+        let logger = provider.logger("test");
+        let mut record = logger.create_log_record();
+        record.set_severity_number(Severity::Warn);
+        record.set_body(AnyValue::from("order delayed"));
+        logger.emit(record);
+
+        let lines = output.take_lines().unwrap();
+        assert_eq!(lines.len(), 1);
+        let envelope: ExporterOutput = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(envelope.endpoint, "http://localhost:4318/v1/logs");
+        assert_eq!(envelope.source, "orders");
+
+        let mut proto_bytes = Vec::new();
+        GzDecoder::new(&base64_engine.decode(&envelope.payload).unwrap()[..])
+            .read_to_end(&mut proto_bytes)
+            .unwrap();
+        let request = ExportLogsServiceRequest::decode(&proto_bytes[..]).unwrap();
+        let log_record = &request.resource_logs[0].scope_logs[0].log_records[0];
+        assert_eq!(log_record.severity_number, Severity::Warn as i32);
+        assert!(request.resource_logs[0].resource.is_some());
+    }
+}
@@ -0,0 +1,154 @@
+//! A metric exporter that writes OpenTelemetry metrics to stdout in OTLP format.
+//!
+//! Metrics are written in the same envelope as spans, addressed to the `/v1/metrics`
+//! endpoint, so the forwarder can deliver both signals from the same log stream.
+
+use crate::{constants::defaults, ExportStatsHandle, OtlpStdoutSpanExporter};
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::metrics::{
+    data::ResourceMetrics, exporter::PushMetricExporter, Temporality,
+};
+use prost::Message;
+use std::time::Duration;
+
+/// A metric exporter that writes metrics to stdout in OTLP format
+///
+/// This exporter implements the OpenTelemetry [`PushMetricExporter`] trait. It shares the
+/// configuration of an [`OtlpStdoutSpanExporter`]: the compression level, output (stdout or
+/// named pipe), headers, log level and optional envelope fields all apply to the metric
+/// envelopes, whose `endpoint` is `http://localhost:4318/v1/metrics`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use opentelemetry_sdk::metrics::SdkMeterProvider;
+/// use otlp_stdout_span_exporter::OtlpStdoutMetricExporter;
+///
+/// let provider = SdkMeterProvider::builder()
+///     .with_periodic_exporter(OtlpStdoutMetricExporter::default())
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct OtlpStdoutMetricExporter {
+    /// Exporter writing the envelopes
+    exporter: OtlpStdoutSpanExporter,
+    /// Temporality of the exported metrics
+    temporality: Temporality,
+}
+
+impl Default for OtlpStdoutMetricExporter {
+    fn default() -> Self {
+        Self::new(OtlpStdoutSpanExporter::default())
+    }
+}
+
+impl OtlpStdoutMetricExporter {
+    /// Create a metric exporter writing envelopes with the configuration of a span exporter.
+    ///
+    /// The metrics are exported with cumulative temporality, unless set otherwise with
+    /// [`with_temporality`](Self::with_temporality).
+    pub fn new(exporter: OtlpStdoutSpanExporter) -> Self {
+        Self {
+            exporter,
+            temporality: Temporality::default(),
+        }
+    }
+
+    /// Set the temporality of the exported metrics
+    pub fn with_temporality(mut self, temporality: Temporality) -> Self {
+        self.temporality = temporality;
+        self
+    }
+
+    /// Returns a handle to the export counters that outlives moving the exporter
+    pub fn stats_handle(&self) -> ExportStatsHandle {
+        self.exporter.stats_handle()
+    }
+}
+
+impl PushMetricExporter for OtlpStdoutMetricExporter {
+    /// Export metrics to stdout in OTLP format
+    ///
+    /// The metrics are converted to an OTLP export request, serialized to protobuf and
+    /// written in an envelope, like the spans of [`OtlpStdoutSpanExporter`].
+    fn export(
+        &self,
+        metrics: &ResourceMetrics,
+    ) -> impl std::future::Future<Output = OTelSdkResult> + Send {
+        let request = ExportMetricsServiceRequest::from(metrics);
+        let result = self
+            .exporter
+            .write_envelope(&request.encode_to_vec(), defaults::METRICS_ENDPOINT);
+        std::future::ready(result)
+    }
+
+    /// Force flushes any pending metrics
+    ///
+    /// This is a no-op as metrics are written as soon as they are exported.
+    fn force_flush(&self) -> OTelSdkResult {
+        Ok(())
+    }
+
+    /// Shuts down the exporter
+    ///
+    /// This is a no-op for stdout export as no cleanup is needed.
+    fn shutdown_with_timeout(&self, _timeout: Duration) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn temporality(&self) -> Temporality {
+        self.temporality
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BufferOutput, ExporterOutput};
+    use base64::{engine::general_purpose::STANDARD as base64_engine, Engine};
+    use flate2::read::GzDecoder;
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry::KeyValue;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use std::io::Read;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_metric_export_envelope() {
+        let output = BufferOutput::new();
+        let exporter = OtlpStdoutMetricExporter::new(
+            OtlpStdoutSpanExporter::builder()
+                .output(Arc::new(output.clone()))
+                .build(),
+        )
+        .with_temporality(Temporality::Delta);
+        let stats = exporter.stats_handle();
+        let provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(exporter)
+            .build();
+
+        let counter = provider.meter("test").u64_counter("orders").build();
+        counter.add(3, &[KeyValue::new("region", "eu")]);
+        provider.force_flush().unwrap();
+
+        let lines = output.take_lines().unwrap();
+        assert_eq!(lines.len(), 1);
+        let envelope: ExporterOutput = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(envelope.endpoint, "http://localhost:4318/v1/metrics");
+        assert_eq!(envelope.content_encoding, "gzip");
+
+        let mut proto_bytes = Vec::new();
+        GzDecoder::new(&base64_engine.decode(&envelope.payload).unwrap()[..])
+            .read_to_end(&mut proto_bytes)
+            .unwrap();
+        let request = ExportMetricsServiceRequest::decode(&proto_bytes[..]).unwrap();
+        let metric = &request.resource_metrics[0].scope_metrics[0].metrics[0];
+        assert_eq!(metric.name, "orders");
+        assert_eq!(stats.snapshot().exported_batches, 1);
+
+        // Nothing was recorded since the last collection, so nothing is written
+        provider.force_flush().unwrap();
+        assert!(output.take_lines().unwrap().is_empty());
+    }
+}
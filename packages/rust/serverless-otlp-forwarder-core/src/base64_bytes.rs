@@ -0,0 +1,15 @@
+//! Serde helpers storing payload bytes as base64 strings, for use with `#[serde(with = ...)]`.
+
+use base64::{engine::general_purpose, Engine};
+use serde::{Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&general_purpose::STANDARD.encode(bytes))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let encoded = String::deserialize(deserializer)?;
+    general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(serde::de::Error::custom)
+}
@@ -0,0 +1,171 @@
+//! Deduplication of redelivered telemetry batches.
+//!
+//! CloudWatch Logs subscriptions deliver at least once, so the same exporter record can reach
+//! the forwarder more than once. Records carrying a content-addressable `batch_id` are tracked
+//! here for a configurable window, and repeated ids are dropped by `process_event_batch` before
+//! they are sent, which keeps duplicate spans out of the backend.
+//!
+//! The cache is process-wide, so it survives across invocations handled by the same Lambda
+//! execution environment. It is bounded both in time (the window) and in size.
+
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Environment variable controlling the deduplication window in seconds (`0` disables it)
+pub const DEDUP_WINDOW_ENV_VAR: &str = "OTLP_FORWARDER_DEDUP_WINDOW_SECS";
+/// Default deduplication window in seconds
+pub const DEFAULT_DEDUP_WINDOW_SECS: u64 = 300;
+/// Maximum number of batch ids remembered at any time
+pub const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Default)]
+struct SeenBatches {
+    /// Batch id to the instant it was recorded
+    entries: HashMap<String, Instant>,
+    /// Insertion order, used for expiry and for evicting the oldest ids when full
+    order: VecDeque<(String, Instant)>,
+}
+
+/// Time-windowed set of batch ids that have already been forwarded
+#[derive(Debug)]
+pub struct BatchDeduplicator {
+    window: Duration,
+    max_entries: usize,
+    seen: Mutex<SeenBatches>,
+}
+
+impl BatchDeduplicator {
+    /// Creates a deduplicator remembering up to `max_entries` ids for `window`
+    pub fn new(window: Duration, max_entries: usize) -> Self {
+        Self {
+            window,
+            max_entries,
+            seen: Mutex::new(SeenBatches::default()),
+        }
+    }
+
+    /// Creates a deduplicator from `OTLP_FORWARDER_DEDUP_WINDOW_SECS`
+    ///
+    /// Returns `None` when the window is set to `0`. Invalid values fall back to the default window.
+    pub fn from_env() -> Option<Self> {
+        let window_secs = match env::var(DEDUP_WINDOW_ENV_VAR) {
+            Ok(value) => value.trim().parse::<u64>().unwrap_or_else(|_| {
+                tracing::warn!(
+                    env_var = DEDUP_WINDOW_ENV_VAR,
+                    default_window_secs = DEFAULT_DEDUP_WINDOW_SECS,
+                    "Failed to parse deduplication window; using default"
+                );
+                DEFAULT_DEDUP_WINDOW_SECS
+            }),
+            Err(_) => DEFAULT_DEDUP_WINDOW_SECS,
+        };
+
+        (window_secs > 0).then(|| Self::new(Duration::from_secs(window_secs), DEFAULT_MAX_ENTRIES))
+    }
+
+    /// Returns the process-wide deduplicator, initialised from the environment on first use
+    pub fn global() -> Option<&'static Self> {
+        static GLOBAL: OnceLock<Option<BatchDeduplicator>> = OnceLock::new();
+        GLOBAL.get_or_init(Self::from_env).as_ref()
+    }
+
+    /// Returns true if the batch id was recorded within the window
+    pub fn contains(&self, batch_id: &str) -> bool {
+        self.contains_at(batch_id, Instant::now())
+    }
+
+    /// Records batch ids that have been forwarded successfully
+    pub fn record<'a>(&self, batch_ids: impl IntoIterator<Item = &'a str>) {
+        self.record_at(batch_ids, Instant::now());
+    }
+
+    fn contains_at(&self, batch_id: &str, now: Instant) -> bool {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        self.expire(&mut seen, now);
+        seen.entries.contains_key(batch_id)
+    }
+
+    fn record_at<'a>(&self, batch_ids: impl IntoIterator<Item = &'a str>, now: Instant) {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        self.expire(&mut seen, now);
+        for batch_id in batch_ids {
+            if seen.entries.insert(batch_id.to_string(), now).is_none() {
+                seen.order.push_back((batch_id.to_string(), now));
+            }
+        }
+        while seen.entries.len() > self.max_entries {
+            match seen.order.pop_front() {
+                Some((oldest, _)) => {
+                    seen.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn expire(&self, seen: &mut SeenBatches, now: Instant) {
+        while let Some((_, recorded_at)) = seen.order.front() {
+            if now.saturating_duration_since(*recorded_at) < self.window {
+                break;
+            }
+            if let Some((expired, _)) = seen.order.pop_front() {
+                seen.entries.remove(&expired);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    fn test_records_and_expires_batch_ids() {
+        let dedup = BatchDeduplicator::new(Duration::from_secs(60), 100);
+        let start = Instant::now();
+
+        assert!(!dedup.contains_at("a", start));
+        dedup.record_at(["a", "b"], start);
+        assert!(dedup.contains_at("a", start + Duration::from_secs(30)));
+        assert!(dedup.contains_at("b", start + Duration::from_secs(59)));
+        assert!(!dedup.contains_at("a", start + Duration::from_secs(60)));
+        assert!(!dedup.contains_at("b", start + Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn test_evicts_oldest_when_full() {
+        let dedup = BatchDeduplicator::new(Duration::from_secs(60), 2);
+        let start = Instant::now();
+
+        dedup.record_at(["a"], start);
+        dedup.record_at(["b"], start + Duration::from_secs(1));
+        dedup.record_at(["c"], start + Duration::from_secs(2));
+
+        let now = start + Duration::from_secs(3);
+        assert!(!dedup.contains_at("a", now));
+        assert!(dedup.contains_at("b", now));
+        assert!(dedup.contains_at("c", now));
+    }
+
+    #[test]
+    #[serial]
+    fn test_from_env() {
+        env::set_var(DEDUP_WINDOW_ENV_VAR, "0");
+        assert!(BatchDeduplicator::from_env().is_none());
+
+        env::set_var(DEDUP_WINDOW_ENV_VAR, "30");
+        let dedup = BatchDeduplicator::from_env().unwrap();
+        assert_eq!(dedup.window, Duration::from_secs(30));
+
+        env::set_var(DEDUP_WINDOW_ENV_VAR, "not-a-number");
+        let dedup = BatchDeduplicator::from_env().unwrap();
+        assert_eq!(dedup.window, Duration::from_secs(DEFAULT_DEDUP_WINDOW_SECS));
+
+        env::remove_var(DEDUP_WINDOW_ENV_VAR);
+        let dedup = BatchDeduplicator::from_env().unwrap();
+        assert_eq!(dedup.window, Duration::from_secs(DEFAULT_DEDUP_WINDOW_SECS));
+    }
+}
@@ -0,0 +1,493 @@
+//! Payload capture and replay for validating forwarder changes.
+//!
+//! This module is only available with the `capture` feature. It provides:
+//!
+//! - [`PayloadSink`]: a pluggable destination for outgoing payloads.
+//! - [`FileCaptureSink`]: a sink writing one JSON file per payload to a local directory,
+//!   bounded by file count and total size (oldest captures are evicted first).
+//! - [`CapturingHttpClient`]: a wrapper around any [`HttpOtlpForwarderClient`] that tees every
+//!   outgoing payload to a sink before performing the real send.
+//! - [`load_captures`] and [`replay_captures`]: a replay harness that reads captured payloads
+//!   back and sends them through [`send_telemetry_batch`], so a new forwarder build can be
+//!   exercised against production traffic shapes before it is deployed.
+//!
+//! Only the payload, its content type, content encoding and signal are captured. Target URLs and
+//! request headers are never persisted, as they may carry credentials.
+
+use crate::http_sender::{send_telemetry_batch, HttpForwarderResponse, HttpOtlpForwarderClient};
+use crate::telemetry::{Signal, TelemetryData};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, CONTENT_ENCODING, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+use url::Url;
+
+/// Environment variable enabling file capture when set to a directory path
+pub const CAPTURE_DIR_ENV_VAR: &str = "OTLP_FORWARDER_CAPTURE_DIR";
+/// Environment variable bounding the number of capture files kept on disk
+pub const CAPTURE_MAX_FILES_ENV_VAR: &str = "OTLP_FORWARDER_CAPTURE_MAX_FILES";
+/// Environment variable bounding the total size of capture files kept on disk, in bytes
+pub const CAPTURE_MAX_BYTES_ENV_VAR: &str = "OTLP_FORWARDER_CAPTURE_MAX_BYTES";
+/// Default maximum number of capture files
+pub const DEFAULT_CAPTURE_MAX_FILES: usize = 100;
+/// Default maximum total size of capture files (50 MiB)
+pub const DEFAULT_CAPTURE_MAX_BYTES: u64 = 50 * 1024 * 1024;
+
+const CAPTURE_FILE_PREFIX: &str = "capture-";
+const CAPTURE_FILE_EXTENSION: &str = "json";
+
+/// A single outgoing payload, as persisted by a [`PayloadSink`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CapturedPayload {
+    /// Capture time in milliseconds since the Unix epoch
+    pub captured_at_ms: u64,
+    /// Content type of the payload
+    pub content_type: String,
+    /// Optional content encoding of the payload (e.g., gzip)
+    pub content_encoding: Option<String>,
+    /// The payload bytes, exactly as sent
+    #[serde(with = "crate::base64_bytes")]
+    pub payload: Vec<u8>,
+    /// Signal of the payload, inferred from the target URL path; traces for older captures
+    #[serde(default)]
+    pub signal: Signal,
+}
+
+impl CapturedPayload {
+    /// Builds a capture from the headers and body of an outgoing request
+    pub fn from_request(headers: &HeaderMap, payload: &[u8]) -> Self {
+        let header_value = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            captured_at_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            content_type: header_value(CONTENT_TYPE)
+                .unwrap_or_else(|| "application/x-protobuf".to_string()),
+            content_encoding: header_value(CONTENT_ENCODING),
+            payload: payload.to_vec(),
+            signal: Signal::default(),
+        }
+    }
+
+    /// Converts the capture back into [`TelemetryData`] ready to be sent
+    pub fn into_telemetry_data(self) -> TelemetryData {
+        TelemetryData {
+            source: "capture".to_string(),
+            payload: self.payload,
+            content_type: self.content_type,
+            content_encoding: self.content_encoding,
+            signal: self.signal,
+            ..Default::default()
+        }
+    }
+}
+
+/// A destination for captured payloads
+pub trait PayloadSink: Send + Sync {
+    /// Persists a single payload
+    fn persist(&self, payload: &CapturedPayload) -> Result<()>;
+}
+
+#[derive(Debug, Default)]
+struct CaptureFiles {
+    /// Capture files on disk with their sizes, oldest first
+    files: VecDeque<(PathBuf, u64)>,
+    total_bytes: u64,
+}
+
+/// A [`PayloadSink`] writing one JSON file per payload into a directory
+///
+/// The directory is bounded by `max_files` and `max_total_bytes`; when either limit is
+/// exceeded, the oldest captures are deleted.
+#[derive(Debug)]
+pub struct FileCaptureSink {
+    dir: PathBuf,
+    max_files: usize,
+    max_total_bytes: u64,
+    sequence: AtomicU64,
+    state: Mutex<CaptureFiles>,
+}
+
+impl FileCaptureSink {
+    /// Creates a sink writing into `dir`, creating the directory if needed
+    ///
+    /// Existing capture files in the directory count towards the limits.
+    pub fn new(dir: impl Into<PathBuf>, max_files: usize, max_total_bytes: u64) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).context("Failed to create capture directory")?;
+
+        let mut state = CaptureFiles::default();
+        let mut next_sequence = 0;
+        for path in capture_files(&dir)? {
+            if let Some(sequence) = capture_sequence(&path) {
+                next_sequence = next_sequence.max(sequence + 1);
+            }
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or_default();
+            state.total_bytes += size;
+            state.files.push_back((path, size));
+        }
+
+        let sink = Self {
+            sequence: AtomicU64::new(next_sequence),
+            dir,
+            max_files,
+            max_total_bytes,
+            state: Mutex::new(state),
+        };
+        sink.enforce_limits(&mut sink.state.lock().unwrap_or_else(|e| e.into_inner()));
+        Ok(sink)
+    }
+
+    /// Creates a sink from `OTLP_FORWARDER_CAPTURE_DIR`
+    ///
+    /// Returns `Ok(None)` when the directory variable is not set. The limits are read from
+    /// `OTLP_FORWARDER_CAPTURE_MAX_FILES` and `OTLP_FORWARDER_CAPTURE_MAX_BYTES`, falling back
+    /// to the defaults when unset or invalid.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(dir) = env::var(CAPTURE_DIR_ENV_VAR) else {
+            return Ok(None);
+        };
+        let max_files = parse_env_or(CAPTURE_MAX_FILES_ENV_VAR, DEFAULT_CAPTURE_MAX_FILES);
+        let max_total_bytes = parse_env_or(CAPTURE_MAX_BYTES_ENV_VAR, DEFAULT_CAPTURE_MAX_BYTES);
+        Self::new(dir, max_files, max_total_bytes).map(Some)
+    }
+
+    /// Returns the directory captures are written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn enforce_limits(&self, state: &mut CaptureFiles) {
+        while state.files.len() > self.max_files || state.total_bytes > self.max_total_bytes {
+            let Some((path, size)) = state.files.pop_front() else {
+                break;
+            };
+            state.total_bytes = state.total_bytes.saturating_sub(size);
+            if fs::remove_file(&path).is_err() {
+                warn!("Failed to evict capture file");
+            }
+        }
+    }
+}
+
+impl PayloadSink for FileCaptureSink {
+    fn persist(&self, payload: &CapturedPayload) -> Result<()> {
+        let contents = serde_json::to_vec(payload).context("Failed to serialize capture")?;
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+        let path = self.dir.join(format!(
+            "{CAPTURE_FILE_PREFIX}{sequence:012}.{CAPTURE_FILE_EXTENSION}"
+        ));
+        fs::write(&path, &contents).context("Failed to write capture file")?;
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.total_bytes += contents.len() as u64;
+        state.files.push_back((path, contents.len() as u64));
+        self.enforce_limits(&mut state);
+        Ok(())
+    }
+}
+
+/// An [`HttpOtlpForwarderClient`] that tees every outgoing payload to a [`PayloadSink`]
+///
+/// Capture failures are logged and never affect the real send.
+pub struct CapturingHttpClient<C, S = FileCaptureSink> {
+    inner: C,
+    sink: S,
+}
+
+impl<C, S> CapturingHttpClient<C, S> {
+    /// Wraps `inner`, capturing outgoing payloads into `sink`
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use serverless_otlp_forwarder_core::capture::{CapturingHttpClient, FileCaptureSink};
+    /// use serverless_otlp_forwarder_core::client_builder;
+    ///
+    /// let sink = FileCaptureSink::new("/tmp/otlp-captures", 100, 50 * 1024 * 1024)?;
+    /// let client = CapturingHttpClient::new(client_builder::simple(), sink);
+    /// ```
+    pub fn new(inner: C, sink: S) -> Self {
+        Self { inner, sink }
+    }
+
+    /// Returns the wrapped client
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Returns the capture sink
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+}
+
+#[async_trait]
+impl<C: HttpOtlpForwarderClient, S: PayloadSink> HttpOtlpForwarderClient
+    for CapturingHttpClient<C, S>
+{
+    async fn post_telemetry(
+        &self,
+        target_url: Url,
+        headers: HeaderMap,
+        payload: Bytes,
+        timeout: Duration,
+    ) -> Result<HttpForwarderResponse> {
+        let captured = CapturedPayload {
+            signal: Signal::from_endpoint(target_url.path()),
+            ..CapturedPayload::from_request(&headers, &payload)
+        };
+        match self.sink.persist(&captured) {
+            Ok(()) => debug!(
+                payload_size_bytes = payload.len() as u64,
+                "Captured outgoing payload"
+            ),
+            Err(_) => warn!(
+                payload_size_bytes = payload.len() as u64,
+                "Failed to capture outgoing payload"
+            ),
+        }
+        self.inner
+            .post_telemetry(target_url, headers, payload, timeout)
+            .await
+    }
+}
+
+/// Loads all captures from a directory, oldest first
+pub fn load_captures(dir: impl AsRef<Path>) -> Result<Vec<CapturedPayload>> {
+    capture_files(dir.as_ref())?
+        .into_iter()
+        .map(|path| {
+            let contents = fs::read(&path).context("Failed to read capture file")?;
+            serde_json::from_slice(&contents).context("Failed to parse capture file")
+        })
+        .collect()
+}
+
+/// Result of replaying captured payloads
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReplaySummary {
+    /// Number of payloads replayed
+    pub total: usize,
+    /// Number of payloads accepted by the endpoint
+    pub succeeded: usize,
+    /// Number of payloads that failed to send
+    pub failed: usize,
+}
+
+/// Replays captured payloads through [`send_telemetry_batch`]
+///
+/// The target endpoint, headers and timeout are resolved from the standard
+/// `OTEL_EXPORTER_OTLP_*` environment variables, exactly as for live traffic.
+/// Payloads are sent sequentially in capture order; failures are counted and do not stop
+/// the replay.
+pub async fn replay_captures(
+    client: &impl HttpOtlpForwarderClient,
+    captures: Vec<CapturedPayload>,
+) -> ReplaySummary {
+    let mut summary = ReplaySummary::default();
+    for capture in captures {
+        summary.total += 1;
+        match send_telemetry_batch(client, capture.into_telemetry_data()).await {
+            Ok(_) => summary.succeeded += 1,
+            Err(_) => summary.failed += 1,
+        }
+    }
+    debug!(
+        replayed_count = summary.total as u64,
+        failed_count = summary.failed as u64,
+        "Replayed captured payloads"
+    );
+    summary
+}
+
+/// Lists capture files in a directory, sorted oldest first
+fn capture_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .context("Failed to read capture directory")?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == CAPTURE_FILE_EXTENSION)
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(CAPTURE_FILE_PREFIX))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Extracts the sequence number from a capture file name
+fn capture_sequence(path: &Path) -> Option<u64> {
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix(CAPTURE_FILE_PREFIX)?
+        .parse()
+        .ok()
+}
+
+fn parse_env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match env::var(name) {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            warn!(
+                env_var = name,
+                "Failed to parse capture limit; using default"
+            );
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use reqwest::Client as ReqwestClient;
+    use serial_test::serial;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn capture(payload: &[u8]) -> CapturedPayload {
+        CapturedPayload {
+            captured_at_ms: 1,
+            content_type: "application/x-protobuf".to_string(),
+            content_encoding: Some("gzip".to_string()),
+            payload: payload.to_vec(),
+            signal: Signal::Traces,
+        }
+    }
+
+    #[test]
+    fn test_file_sink_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = FileCaptureSink::new(dir.path(), 10, 1024 * 1024).unwrap();
+
+        sink.persist(&capture(b"first")).unwrap();
+        sink.persist(&capture(b"second")).unwrap();
+
+        let loaded = load_captures(dir.path()).unwrap();
+        assert_eq!(loaded, vec![capture(b"first"), capture(b"second")]);
+    }
+
+    #[test]
+    fn test_file_sink_evicts_oldest() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = FileCaptureSink::new(dir.path(), 2, 1024 * 1024).unwrap();
+
+        for payload in [b"one", b"two", b"six"] {
+            sink.persist(&capture(payload)).unwrap();
+        }
+
+        let payloads: Vec<Vec<u8>> = load_captures(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|c| c.payload)
+            .collect();
+        assert_eq!(payloads, vec![b"two".to_vec(), b"six".to_vec()]);
+
+        // Size limit: a sink that only fits a single capture keeps the latest one
+        let sink = FileCaptureSink::new(dir.path(), 10, 150).unwrap();
+        sink.persist(&capture(b"ten")).unwrap();
+        let loaded = load_captures(dir.path()).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].payload, b"ten");
+    }
+
+    #[test]
+    #[serial]
+    fn test_file_sink_from_env() {
+        env::remove_var(CAPTURE_DIR_ENV_VAR);
+        assert!(FileCaptureSink::from_env().unwrap().is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        env::set_var(CAPTURE_DIR_ENV_VAR, dir.path());
+        env::set_var(CAPTURE_MAX_FILES_ENV_VAR, "5");
+        env::set_var(CAPTURE_MAX_BYTES_ENV_VAR, "invalid");
+        let sink = FileCaptureSink::from_env().unwrap().unwrap();
+        assert_eq!(sink.dir(), dir.path());
+        assert_eq!(sink.max_files, 5);
+        assert_eq!(sink.max_total_bytes, DEFAULT_CAPTURE_MAX_BYTES);
+
+        env::remove_var(CAPTURE_DIR_ENV_VAR);
+        env::remove_var(CAPTURE_MAX_FILES_ENV_VAR);
+        env::remove_var(CAPTURE_MAX_BYTES_ENV_VAR);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_capturing_client_tees_and_replays() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .and(header("content-encoding", "gzip"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let client = CapturingHttpClient::new(
+            ReqwestClient::new(),
+            FileCaptureSink::new(dir.path(), 10, 1024 * 1024).unwrap(),
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-protobuf"),
+        );
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        headers.insert("authorization", HeaderValue::from_static("Bearer secret"));
+        let url = Url::parse(&format!("{}/v1/traces", server.uri())).unwrap();
+        let response = client
+            .post_telemetry(
+                url,
+                headers,
+                Bytes::from_static(b"payload"),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+
+        let captures = load_captures(dir.path()).unwrap();
+        assert_eq!(captures.len(), 1);
+        assert_eq!(captures[0].payload, b"payload");
+        assert_eq!(captures[0].content_encoding.as_deref(), Some("gzip"));
+        let raw = fs::read_to_string(capture_files(dir.path()).unwrap().remove(0)).unwrap();
+        assert!(!raw.contains("secret"));
+        assert!(!raw.contains(&server.uri()));
+
+        env::set_var(
+            "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+            format!("{}/v1/traces", server.uri()),
+        );
+        let summary = replay_captures(client.inner(), captures).await;
+        env::remove_var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT");
+        assert_eq!(
+            summary,
+            ReplaySummary {
+                total: 1,
+                succeeded: 1,
+                failed: 0
+            }
+        );
+    }
+}
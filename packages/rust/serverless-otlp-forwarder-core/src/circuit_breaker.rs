@@ -0,0 +1,429 @@
+//! Circuit breaker for OTLP collector endpoints.
+//!
+//! A forwarder Lambda keeps its HTTP client across warm invocations. When the collector is down,
+//! every invocation would otherwise wait for the export timeout (and its retries) before giving
+//! up. [`CircuitBreaker`] wraps any [`HttpOtlpForwarderClient`] and tracks consecutive failures per
+//! endpoint: once the failure threshold is reached the circuit opens and sends to that endpoint
+//! fail immediately with [`CircuitOpenError`] for the cooldown period. After the cooldown a single
+//! probe request is let through; its success closes the circuit, its failure opens it again.
+//!
+//! Transport errors, `5xx` and `429` responses count as failures. Other responses, including
+//! `4xx` errors, show the collector is reachable and close the circuit.
+
+use crate::config::ForwarderSettings;
+use crate::http_sender::{HttpForwarderResponse, HttpOtlpForwarderClient};
+use crate::retry::is_retryable_status;
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+use url::Url;
+
+/// Environment variable for the number of consecutive failures that opens the circuit (`0` disables the breaker)
+pub const FAILURE_THRESHOLD_ENV_VAR: &str = "OTLP_FORWARDER_CIRCUIT_BREAKER_FAILURE_THRESHOLD";
+/// Environment variable for how long an open circuit short-circuits sends, in milliseconds
+pub const COOLDOWN_ENV_VAR: &str = "OTLP_FORWARDER_CIRCUIT_BREAKER_COOLDOWN_MS";
+
+/// Default number of consecutive failures that opens the circuit
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+/// Default cooldown of an open circuit
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Configuration of a [`CircuitBreaker`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures after which the circuit opens; `0` never opens it
+    pub failure_threshold: u32,
+    /// Time during which an open circuit rejects sends before letting a probe through
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            cooldown: DEFAULT_COOLDOWN,
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// Resolves the configuration from the `OTLP_FORWARDER_CIRCUIT_BREAKER_*` environment variables
+    ///
+    /// Unset variables use the defaults; invalid values are logged and fall back to the defaults.
+    pub fn from_env() -> Self {
+        ForwarderSettings::circuit_breaker_from_env().circuit_breaker_config()
+    }
+}
+
+/// Error returned without contacting the endpoint while its circuit is open
+///
+/// [`send_telemetry_batch`](crate::send_telemetry_batch) does not retry this error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitOpenError {
+    /// Time left before a probe request is allowed
+    pub retry_in: Duration,
+}
+
+impl fmt::Display for CircuitOpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Circuit breaker open for OTLP endpoint; retry in {} ms",
+            self.retry_in.as_millis()
+        )
+    }
+}
+
+impl std::error::Error for CircuitOpenError {}
+
+/// State of the circuit for a single endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Sends go through; holds the number of consecutive failures so far
+    Closed { consecutive_failures: u32 },
+    /// Sends are rejected until the cooldown ends
+    Open { until: Instant },
+    /// A probe request is in flight; other sends are rejected
+    HalfOpen { probe_started: Instant },
+}
+
+impl Default for CircuitState {
+    fn default() -> Self {
+        Self::Closed {
+            consecutive_failures: 0,
+        }
+    }
+}
+
+/// An [`HttpOtlpForwarderClient`] that stops sending to endpoints that keep failing
+pub struct CircuitBreaker<C> {
+    inner: C,
+    config: CircuitBreakerConfig,
+    circuits: Mutex<HashMap<String, CircuitState>>,
+}
+
+impl<C> CircuitBreaker<C> {
+    /// Wraps `inner` with the configuration from [`CircuitBreakerConfig::from_env`]
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use serverless_otlp_forwarder_core::{client_builder, CircuitBreaker};
+    /// use std::sync::Arc;
+    ///
+    /// let http_client = Arc::new(CircuitBreaker::new(client_builder::simple()));
+    /// ```
+    pub fn new(inner: C) -> Self {
+        Self::with_config(inner, CircuitBreakerConfig::from_env())
+    }
+
+    /// Wraps `inner` with an explicit configuration
+    pub fn with_config(inner: C, config: CircuitBreakerConfig) -> Self {
+        Self {
+            inner,
+            config,
+            circuits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the wrapped client
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Returns the breaker configuration
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
+    /// Returns the current state of the circuit for `target_url`
+    pub fn state(&self, target_url: &Url) -> CircuitState {
+        self.circuits
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&endpoint_key(target_url))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Decides whether a send to `endpoint` may proceed, moving an expired open circuit to half-open
+    fn admit(&self, endpoint: &str, now: Instant) -> std::result::Result<(), CircuitOpenError> {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(state) = circuits.get_mut(endpoint) else {
+            return Ok(());
+        };
+        match *state {
+            CircuitState::Closed { .. } => Ok(()),
+            CircuitState::Open { until } if now < until => Err(CircuitOpenError {
+                retry_in: until - now,
+            }),
+            // A probe that never reported back (e.g. its future was dropped) must not keep
+            // the circuit half-open forever
+            CircuitState::HalfOpen { probe_started }
+                if now.duration_since(probe_started) < self.config.cooldown =>
+            {
+                Err(CircuitOpenError {
+                    retry_in: self.config.cooldown - now.duration_since(probe_started),
+                })
+            }
+            CircuitState::Open { .. } | CircuitState::HalfOpen { .. } => {
+                debug!("Circuit breaker half-open; sending probe request");
+                *state = CircuitState::HalfOpen { probe_started: now };
+                Ok(())
+            }
+        }
+    }
+
+    fn record_success(&self, endpoint: &str) {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(previous) = circuits.remove(endpoint) {
+            if !matches!(previous, CircuitState::Closed { .. }) {
+                info!("Circuit breaker closed; OTLP endpoint recovered");
+            }
+        }
+    }
+
+    fn record_failure(&self, endpoint: &str, now: Instant) {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+        let state = circuits.entry(endpoint.to_string()).or_default();
+        let consecutive_failures = match *state {
+            CircuitState::Closed {
+                consecutive_failures,
+            } => consecutive_failures + 1,
+            // A failed probe opens the circuit again right away
+            CircuitState::Open { .. } | CircuitState::HalfOpen { .. } => {
+                self.config.failure_threshold
+            }
+        };
+
+        if consecutive_failures >= self.config.failure_threshold {
+            warn!(
+                consecutive_failures,
+                cooldown_ms = self.config.cooldown.as_millis() as u64,
+                "Circuit breaker opened; short-circuiting sends to OTLP endpoint"
+            );
+            *state = CircuitState::Open {
+                until: now + self.config.cooldown,
+            };
+        } else {
+            *state = CircuitState::Closed {
+                consecutive_failures,
+            };
+        }
+    }
+}
+
+/// Identifies an endpoint by scheme, authority and path; query and fragment are ignored
+pub(crate) fn endpoint_key(url: &Url) -> String {
+    let mut key = url.clone();
+    key.set_query(None);
+    key.set_fragment(None);
+    key.into()
+}
+
+#[async_trait]
+impl<C: HttpOtlpForwarderClient> HttpOtlpForwarderClient for CircuitBreaker<C> {
+    async fn post_telemetry(
+        &self,
+        target_url: Url,
+        headers: HeaderMap,
+        payload: Bytes,
+        timeout: Duration,
+    ) -> Result<HttpForwarderResponse> {
+        if self.config.failure_threshold == 0 {
+            return self
+                .inner
+                .post_telemetry(target_url, headers, payload, timeout)
+                .await;
+        }
+
+        let endpoint = endpoint_key(&target_url);
+        if let Err(open) = self.admit(&endpoint, Instant::now()) {
+            debug!(
+                retry_in_ms = open.retry_in.as_millis() as u64,
+                "Circuit breaker open; skipping OTLP export"
+            );
+            return Err(open.into());
+        }
+
+        let result = self
+            .inner
+            .post_telemetry(target_url, headers, payload, timeout)
+            .await;
+        match &result {
+            Ok(response) if !is_retryable_status(response.status()) => {
+                self.record_success(&endpoint)
+            }
+            _ => self.record_failure(&endpoint, Instant::now()),
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use http::StatusCode;
+    use serial_test::serial;
+    use std::env;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Returns the configured status, or a transport error when `None`
+    struct FixedClient {
+        status: Mutex<Option<StatusCode>>,
+        calls: AtomicUsize,
+    }
+
+    impl FixedClient {
+        fn new(status: Option<StatusCode>) -> Self {
+            Self {
+                status: Mutex::new(status),
+                calls: AtomicUsize::new(0),
+            }
+        }
+
+        fn set_status(&self, status: Option<StatusCode>) {
+            *self.status.lock().unwrap() = status;
+        }
+    }
+
+    #[async_trait]
+    impl HttpOtlpForwarderClient for FixedClient {
+        async fn post_telemetry(
+            &self,
+            _target_url: Url,
+            _headers: HeaderMap,
+            _payload: Bytes,
+            _timeout: Duration,
+        ) -> Result<HttpForwarderResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match *self.status.lock().unwrap() {
+                Some(status) => Ok(HttpForwarderResponse::new(status, String::new())),
+                None => Err(anyhow!("connection refused")),
+            }
+        }
+    }
+
+    fn breaker(client: FixedClient, cooldown: Duration) -> CircuitBreaker<FixedClient> {
+        CircuitBreaker::with_config(
+            client,
+            CircuitBreakerConfig {
+                failure_threshold: 2,
+                cooldown,
+            },
+        )
+    }
+
+    async fn send(breaker: &CircuitBreaker<FixedClient>, url: &Url) -> Result<StatusCode> {
+        breaker
+            .post_telemetry(
+                url.clone(),
+                HeaderMap::new(),
+                Bytes::new(),
+                Duration::from_secs(1),
+            )
+            .await
+            .map(|r| r.status())
+    }
+
+    #[tokio::test]
+    async fn test_opens_after_consecutive_failures() {
+        let url = Url::parse("http://collector:4318/v1/traces").unwrap();
+        let breaker = breaker(FixedClient::new(None), Duration::from_secs(60));
+
+        assert!(send(&breaker, &url).await.is_err());
+        assert!(matches!(
+            breaker.state(&url),
+            CircuitState::Closed {
+                consecutive_failures: 1
+            }
+        ));
+        assert!(send(&breaker, &url).await.is_err());
+        assert!(matches!(breaker.state(&url), CircuitState::Open { .. }));
+
+        // Open: the inner client is not called anymore
+        let err = send(&breaker, &url).await.unwrap_err();
+        assert!(err.is::<CircuitOpenError>());
+        assert_eq!(breaker.inner().calls.load(Ordering::SeqCst), 2);
+
+        // Circuits are tracked per endpoint; the query string does not matter
+        let other = Url::parse("http://other:4318/v1/traces").unwrap();
+        breaker.inner().set_status(Some(StatusCode::OK));
+        assert_eq!(send(&breaker, &other).await.unwrap(), StatusCode::OK);
+        let same = Url::parse("http://collector:4318/v1/traces?tenant=a").unwrap();
+        assert!(send(&breaker, &same)
+            .await
+            .unwrap_err()
+            .is::<CircuitOpenError>());
+    }
+
+    #[tokio::test]
+    async fn test_half_open_probe_closes_or_reopens() {
+        let url = Url::parse("http://collector:4318/v1/traces").unwrap();
+        let breaker = breaker(
+            FixedClient::new(Some(StatusCode::SERVICE_UNAVAILABLE)),
+            Duration::from_millis(20),
+        );
+
+        for _ in 0..2 {
+            assert_eq!(
+                send(&breaker, &url).await.unwrap(),
+                StatusCode::SERVICE_UNAVAILABLE
+            );
+        }
+        assert!(matches!(breaker.state(&url), CircuitState::Open { .. }));
+
+        // A failed probe opens the circuit again
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(send(&breaker, &url).await.is_ok());
+        assert!(matches!(breaker.state(&url), CircuitState::Open { .. }));
+
+        // A successful probe closes it; client errors mean the collector is reachable
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        breaker.inner().set_status(Some(StatusCode::BAD_REQUEST));
+        assert_eq!(send(&breaker, &url).await.unwrap(), StatusCode::BAD_REQUEST);
+        assert_eq!(breaker.state(&url), CircuitState::default());
+        assert_eq!(breaker.inner().calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_half_open_admits_a_single_probe() {
+        let breaker = breaker(FixedClient::new(None), Duration::from_secs(60));
+        let now = Instant::now();
+        breaker
+            .circuits
+            .lock()
+            .unwrap()
+            .insert("endpoint".to_string(), CircuitState::Open { until: now });
+
+        assert!(breaker.admit("endpoint", now).is_ok());
+        assert!(breaker.admit("endpoint", now).is_err());
+        // A probe that never completed is replaced after the cooldown
+        assert!(breaker
+            .admit("endpoint", now + Duration::from_secs(60))
+            .is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn test_config_from_env() {
+        env::set_var(FAILURE_THRESHOLD_ENV_VAR, "3");
+        env::set_var(COOLDOWN_ENV_VAR, "invalid");
+        let config = CircuitBreakerConfig::from_env();
+        assert_eq!(config.failure_threshold, 3);
+        assert_eq!(config.cooldown, DEFAULT_COOLDOWN);
+
+        env::remove_var(FAILURE_THRESHOLD_ENV_VAR);
+        env::remove_var(COOLDOWN_ENV_VAR);
+        assert_eq!(
+            CircuitBreakerConfig::from_env(),
+            CircuitBreakerConfig::default()
+        );
+    }
+}
@@ -0,0 +1,1280 @@
+//! Unified forwarder configuration.
+//!
+//! [`ForwarderConfig`] gathers everything needed to deliver telemetry: the OTLP [`Destination`]
+//! and transport protocol, span compaction, retries and the circuit breaker. It is resolved from layers of
+//! [`ForwarderSettings`], each one overriding the values set by the previous ones:
+//!
+//! 1. Built-in defaults
+//! 2. An optional JSON or YAML file, named by `OTLP_FORWARDER_CONFIG_FILE`
+//! 3. Settings passed to [`ForwarderConfig::load`]
+//! 4. Environment variables (`OTEL_EXPORTER_OTLP_*` and `OTLP_FORWARDER_*`)
+//!
+//! Environment variables take precedence over everything else, so a deployed function can always
+//! be reconfigured without a new build. Invalid environment values are logged and ignored, which
+//! lets the lower layers apply.
+//!
+//! A configuration file uses the field names of [`ForwarderSettings`]; every field is optional:
+//!
+//! ```yaml
+//! endpoint: https://collector.example.com/v1/traces
+//! protocol: http/protobuf
+//! headers:
+//!   x-api-key: my-api-key
+//! timeout_ms: 5000
+//! compression: gzip
+//! retry_max_attempts: 5
+//! send_concurrency: 8
+//! tcp_keepalive_ms: 30000
+//! circuit_breaker_failure_threshold: 10
+//! attribute_transform:
+//!   resource:
+//!     set:
+//!       deployment.environment: production
+//! span_filter:
+//!   - name: GET /health
+//! sampling_percentage: 20
+//! span_dedup_capacity: 100000
+//! normalize_semconv: true
+//! enrich_source_metadata: true
+//! ```
+
+use crate::circuit_breaker::{CircuitBreakerConfig, COOLDOWN_ENV_VAR, FAILURE_THRESHOLD_ENV_VAR};
+use crate::connection::{
+    ConnectionConfig, HTTP2_ENV_VAR, POOL_IDLE_TIMEOUT_ENV_VAR, POOL_MAX_IDLE_PER_HOST_ENV_VAR,
+    TCP_KEEPALIVE_ENV_VAR,
+};
+use crate::dead_letter::DeadLetterSink;
+use crate::destination::Destination;
+use crate::http_sender::{
+    parse_otlp_headers, DEFAULT_OTLP_ENDPOINT, DEFAULT_OTLP_EXPORT_TIMEOUT,
+    DEFAULT_SEND_CONCURRENCY, OTLP_TRACES_PATH, SEND_CONCURRENCY_ENV_VAR,
+};
+use crate::retry::{
+    RetryConfig, BASE_DELAY_ENV_VAR, BUDGET_ENV_VAR, JITTER_ENV_VAR, MAX_ATTEMPTS_ENV_VAR,
+};
+use crate::semconv::{SemconvNormalizer, NORMALIZE_SEMCONV_ENV_VAR};
+use crate::source_metadata::{SourceMetadata, ENRICH_SOURCE_METADATA_ENV_VAR};
+use crate::span_compactor::{
+    CompressionPreference, SpanCompactionConfig, DEFAULT_MAX_PAYLOAD_SIZE, MAX_PAYLOAD_SIZE_ENV_VAR,
+};
+use crate::span_dedup::{SpanDeduplicator, SPAN_DEDUP_CAPACITY_ENV_VAR};
+use crate::span_filter::{SpanFilter, SPAN_FILTER_ENV_VAR};
+use crate::trace_sampler::{TraceSampler, SAMPLING_PERCENTAGE_ENV_VAR};
+use crate::transform::{AttributeTransform, ATTRIBUTE_TRANSFORM_ENV_VAR};
+use anyhow::{anyhow, Context, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+use url::Url;
+
+/// Environment variable naming an optional JSON or YAML configuration file
+pub const CONFIG_FILE_ENV_VAR: &str = "OTLP_FORWARDER_CONFIG_FILE";
+
+/// Default GZIP compression level
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 9;
+
+/// Default OTLP/gRPC endpoint, used when the protocol is `grpc` and no endpoint is set
+pub(crate) const DEFAULT_OTLP_GRPC_ENDPOINT: &str = "http://localhost:4317";
+
+/// OTLP transport protocol, as named by `OTEL_EXPORTER_OTLP_PROTOCOL`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OtlpProtocol {
+    /// Protobuf-encoded OTLP over HTTP
+    #[default]
+    #[serde(rename = "http/protobuf")]
+    HttpProtobuf,
+    /// OTLP over gRPC; requires the `grpc` feature to send
+    #[serde(rename = "grpc")]
+    Grpc,
+}
+
+/// One layer of forwarder configuration, where unset fields defer to the lower layers
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ForwarderSettings {
+    /// Full OTLP traces endpoint URL
+    pub endpoint: Option<String>,
+    /// Full OTLP logs endpoint URL; derived from the traces endpoint if unset
+    pub logs_endpoint: Option<String>,
+    /// Full OTLP metrics endpoint URL; derived from the traces endpoint if unset
+    pub metrics_endpoint: Option<String>,
+    /// Transport protocol
+    pub protocol: Option<OtlpProtocol>,
+    /// Headers sent with every export, such as authentication; replaces the headers of lower layers
+    pub headers: Option<BTreeMap<String, String>>,
+    /// Export timeout of each attempt, in milliseconds
+    pub timeout_ms: Option<u64>,
+    /// Compression of the compacted payload
+    pub compression: Option<CompressionPreference>,
+    /// GZIP compression level (0-9)
+    pub compression_level: Option<u32>,
+    /// Maximum uncompressed size of a compacted payload, in bytes; `0` disables splitting
+    pub max_payload_size_bytes: Option<usize>,
+    /// Maximum number of attempts, including the first one
+    pub retry_max_attempts: Option<u32>,
+    /// Base backoff delay, in milliseconds
+    pub retry_base_delay_ms: Option<u64>,
+    /// Whether to randomize the backoff delay
+    pub retry_jitter: Option<bool>,
+    /// Retry budget, in milliseconds
+    pub retry_budget_ms: Option<u64>,
+    /// Consecutive failures after which the circuit opens; `0` disables the circuit breaker
+    pub circuit_breaker_failure_threshold: Option<u32>,
+    /// Time during which an open circuit rejects sends, in milliseconds
+    pub circuit_breaker_cooldown_ms: Option<u64>,
+    /// Maximum number of payloads of a batch sent concurrently
+    pub send_concurrency: Option<usize>,
+    /// How long idle connections are kept for reuse, in milliseconds
+    pub pool_idle_timeout_ms: Option<u64>,
+    /// Maximum number of idle connections kept per host; `0` disables connection reuse
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Whether HTTP/2 is negotiated with `https` endpoints
+    pub http2: Option<bool>,
+    /// Interval of TCP keepalive probes, in milliseconds; `0` disables them
+    pub tcp_keepalive_ms: Option<u64>,
+    /// Attribute changes applied before compaction; replaces the transform of lower layers
+    pub attribute_transform: Option<AttributeTransform>,
+    /// Rules dropping spans before compaction; replaces the filter of lower layers
+    pub span_filter: Option<SpanFilter>,
+    /// Percentage of non-error traces kept by tail-based sampling (0-100); error traces are
+    /// always kept
+    pub sampling_percentage: Option<u32>,
+    /// Number of spans remembered to drop redelivered ones; `0` disables span deduplication
+    pub span_dedup_capacity: Option<usize>,
+    /// Whether deprecated semantic-convention attributes are renamed to their current names
+    pub normalize_semconv: Option<bool>,
+    /// Whether the log group, log stream and account of the source are added as resource
+    /// attributes
+    pub enrich_source_metadata: Option<bool>,
+}
+
+impl ForwarderSettings {
+    /// Reads the settings from a JSON or YAML file, chosen by the `.yaml`/`.yml` extension
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).with_context(|| {
+            format!(
+                "Failed to read forwarder configuration file {}",
+                path.display()
+            )
+        })?;
+
+        let is_yaml = matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("yaml" | "yml")
+        );
+        // Parse errors may quote the offending value, so only their location is reported
+        let settings: Self = if is_yaml {
+            serde_yaml::from_str(&content).map_err(|e| {
+                let line = e.location().map_or(0, |location| location.line());
+                anyhow!(
+                    "Invalid forwarder configuration file {} at line {}",
+                    path.display(),
+                    line
+                )
+            })?
+        } else {
+            serde_json::from_str(&content).map_err(|e| {
+                anyhow!(
+                    "Invalid forwarder configuration file {} at line {}",
+                    path.display(),
+                    e.line()
+                )
+            })?
+        };
+        for endpoint in [
+            &settings.endpoint,
+            &settings.logs_endpoint,
+            &settings.metrics_endpoint,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            Url::parse(endpoint).with_context(|| {
+                format!(
+                    "Invalid URL in forwarder configuration file {}",
+                    path.display()
+                )
+            })?;
+        }
+        Ok(settings)
+    }
+
+    /// Reads the settings from the `OTEL_EXPORTER_OTLP_*` and `OTLP_FORWARDER_*` environment variables
+    ///
+    /// Fails if an endpoint variable holds an invalid URL.
+    pub fn from_env() -> Result<Self> {
+        Ok(Self::endpoint_from_env()?
+            .merge(Self::protocol_from_env())
+            .merge(Self::headers_from_env())
+            .merge(Self::timeout_from_env())
+            .merge(Self::compression_from_env())
+            .merge(Self::payload_size_from_env())
+            .merge(Self::retry_from_env())
+            .merge(Self::circuit_breaker_from_env())
+            .merge(Self::send_concurrency_from_env())
+            .merge(Self::connection_from_env())
+            .merge(Self::attribute_transform_from_env())
+            .merge(Self::span_filter_from_env())
+            .merge(Self::sampling_from_env())
+            .merge(Self::span_dedup_from_env())
+            .merge(Self::semconv_from_env())
+            .merge(Self::source_metadata_from_env()))
+    }
+
+    /// Returns these settings overridden by the fields set in `other`
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            endpoint: other.endpoint.or(self.endpoint),
+            logs_endpoint: other.logs_endpoint.or(self.logs_endpoint),
+            metrics_endpoint: other.metrics_endpoint.or(self.metrics_endpoint),
+            protocol: other.protocol.or(self.protocol),
+            headers: other.headers.or(self.headers),
+            timeout_ms: other.timeout_ms.or(self.timeout_ms),
+            compression: other.compression.or(self.compression),
+            compression_level: other.compression_level.or(self.compression_level),
+            max_payload_size_bytes: other.max_payload_size_bytes.or(self.max_payload_size_bytes),
+            retry_max_attempts: other.retry_max_attempts.or(self.retry_max_attempts),
+            retry_base_delay_ms: other.retry_base_delay_ms.or(self.retry_base_delay_ms),
+            retry_jitter: other.retry_jitter.or(self.retry_jitter),
+            retry_budget_ms: other.retry_budget_ms.or(self.retry_budget_ms),
+            circuit_breaker_failure_threshold: other
+                .circuit_breaker_failure_threshold
+                .or(self.circuit_breaker_failure_threshold),
+            circuit_breaker_cooldown_ms: other
+                .circuit_breaker_cooldown_ms
+                .or(self.circuit_breaker_cooldown_ms),
+            send_concurrency: other.send_concurrency.or(self.send_concurrency),
+            pool_idle_timeout_ms: other.pool_idle_timeout_ms.or(self.pool_idle_timeout_ms),
+            pool_max_idle_per_host: other.pool_max_idle_per_host.or(self.pool_max_idle_per_host),
+            http2: other.http2.or(self.http2),
+            tcp_keepalive_ms: other.tcp_keepalive_ms.or(self.tcp_keepalive_ms),
+            attribute_transform: other.attribute_transform.or(self.attribute_transform),
+            span_filter: other.span_filter.or(self.span_filter),
+            sampling_percentage: other.sampling_percentage.or(self.sampling_percentage),
+            span_dedup_capacity: other.span_dedup_capacity.or(self.span_dedup_capacity),
+            normalize_semconv: other.normalize_semconv.or(self.normalize_semconv),
+            enrich_source_metadata: other.enrich_source_metadata.or(self.enrich_source_metadata),
+        }
+    }
+
+    /// Reads the endpoint from `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` (used as is), or from
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT` (base URL, `/v1/traces` is appended if missing), and the
+    /// logs and metrics endpoints from `OTEL_EXPORTER_OTLP_LOGS_ENDPOINT` and
+    /// `OTEL_EXPORTER_OTLP_METRICS_ENDPOINT` (used as is)
+    pub(crate) fn endpoint_from_env() -> Result<Self> {
+        let logs_endpoint = url_from_env("OTEL_EXPORTER_OTLP_LOGS_ENDPOINT")?;
+        let metrics_endpoint = url_from_env("OTEL_EXPORTER_OTLP_METRICS_ENDPOINT")?;
+        let endpoint = if let Some(endpoint) = non_empty_env("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT") {
+            debug!(
+                endpoint_source = "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+                "Using configured OTLP endpoint"
+            );
+            // The URL may carry credentials, so only its source is reported
+            Url::parse(&endpoint).context("Invalid URL in OTEL_EXPORTER_OTLP_TRACES_ENDPOINT")?
+        } else if let Some(base) = non_empty_env("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            debug!(
+                endpoint_source = "OTEL_EXPORTER_OTLP_ENDPOINT",
+                "Using configured OTLP endpoint"
+            );
+            let url = Url::parse(&base).context("Invalid URL in OTEL_EXPORTER_OTLP_ENDPOINT")?;
+            traces_endpoint_from_base(url)
+        } else {
+            return Ok(Self {
+                logs_endpoint,
+                metrics_endpoint,
+                ..Self::default()
+            });
+        };
+        Ok(Self {
+            endpoint: Some(endpoint.to_string()),
+            logs_endpoint,
+            metrics_endpoint,
+            ..Self::default()
+        })
+    }
+
+    /// Reads the protocol from `OTEL_EXPORTER_OTLP_TRACES_PROTOCOL`, or from
+    /// `OTEL_EXPORTER_OTLP_PROTOCOL`
+    pub(crate) fn protocol_from_env() -> Self {
+        let Some((protocol_source, value)) = [
+            "OTEL_EXPORTER_OTLP_TRACES_PROTOCOL",
+            "OTEL_EXPORTER_OTLP_PROTOCOL",
+        ]
+        .into_iter()
+        .find_map(|var| non_empty_env(var).map(|value| (var, value))) else {
+            return Self::default();
+        };
+        let protocol = match value.trim().to_lowercase().as_str() {
+            "http/protobuf" => OtlpProtocol::HttpProtobuf,
+            "grpc" => OtlpProtocol::Grpc,
+            _ => {
+                warn!(
+                    protocol_source,
+                    "Unsupported OTLP protocol; using http/protobuf"
+                );
+                return Self::default();
+            }
+        };
+        debug!(protocol_source, protocol = ?protocol, "Using configured OTLP protocol");
+        Self {
+            protocol: Some(protocol),
+            ..Self::default()
+        }
+    }
+
+    /// Reads the headers from `OTEL_EXPORTER_OTLP_TRACES_HEADERS`, or from `OTEL_EXPORTER_OTLP_HEADERS`
+    pub(crate) fn headers_from_env() -> Self {
+        let Some((source, headers_str)) = [
+            "OTEL_EXPORTER_OTLP_TRACES_HEADERS",
+            "OTEL_EXPORTER_OTLP_HEADERS",
+        ]
+        .into_iter()
+        .find_map(|var| non_empty_env(var).map(|value| (var, value))) else {
+            return Self::default();
+        };
+        debug!(
+            header_source = source,
+            configured_header_parts_count = headers_str
+                .split(',')
+                .filter(|part| !part.trim().is_empty())
+                .count() as u64,
+            "Using configured OTLP headers"
+        );
+        // Invalid headers are skipped, and logged, by the parser
+        let headers = parse_otlp_headers(&headers_str, source)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+            })
+            .collect();
+        Self {
+            headers: Some(headers),
+            ..Self::default()
+        }
+    }
+
+    /// Reads the export timeout, in milliseconds, from `OTEL_EXPORTER_OTLP_TRACES_TIMEOUT`, or
+    /// from `OTEL_EXPORTER_OTLP_TIMEOUT`
+    pub(crate) fn timeout_from_env() -> Self {
+        let Some((timeout_source, value)) = [
+            "OTEL_EXPORTER_OTLP_TRACES_TIMEOUT",
+            "OTEL_EXPORTER_OTLP_TIMEOUT",
+        ]
+        .into_iter()
+        .find_map(|var| non_empty_env(var).map(|value| (var, value))) else {
+            return Self::default();
+        };
+        match value.parse::<u64>() {
+            Ok(timeout_ms) => {
+                debug!(
+                    timeout_source,
+                    timeout_ms, "Using configured OTLP export timeout"
+                );
+                Self {
+                    timeout_ms: Some(timeout_ms),
+                    ..Self::default()
+                }
+            }
+            Err(_) => {
+                warn!(
+                    timeout_source,
+                    timeout_ms = DEFAULT_OTLP_EXPORT_TIMEOUT.as_millis() as u64,
+                    "Failed to parse OTLP export timeout; using default"
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Reads the compression from `OTEL_EXPORTER_OTLP_TRACES_COMPRESSION`, or from
+    /// `OTEL_EXPORTER_OTLP_COMPRESSION`, and the level from `OTEL_EXPORTER_OTLP_COMPRESSION_LEVEL`
+    pub(crate) fn compression_from_env() -> Self {
+        let compression = [
+            "OTEL_EXPORTER_OTLP_TRACES_COMPRESSION",
+            "OTEL_EXPORTER_OTLP_COMPRESSION",
+        ]
+        .into_iter()
+        .find_map(|var| env::var(var).ok().map(|value| (var, value)))
+        .and_then(|(env_var, value)| match value.to_lowercase().as_str() {
+            "gzip" => Some(CompressionPreference::Gzip),
+            "none" => Some(CompressionPreference::None),
+            _ => {
+                warn!(
+                    env_var,
+                    "Invalid OTLP compression setting; defaulting to no compression"
+                );
+                None
+            }
+        });
+
+        let compression_level = env::var("OTEL_EXPORTER_OTLP_COMPRESSION_LEVEL")
+            .ok()
+            .and_then(|value| match value.parse::<u32>() {
+                Ok(level) if (0..=9).contains(&level) => Some(level),
+                Ok(_) => {
+                    warn!(
+                        env_var = "OTEL_EXPORTER_OTLP_COMPRESSION_LEVEL",
+                        default_compression_level = DEFAULT_COMPRESSION_LEVEL,
+                        "Invalid OTLP compression level; defaulting to configured fallback"
+                    );
+                    None
+                }
+                Err(_) => {
+                    warn!(
+                        env_var = "OTEL_EXPORTER_OTLP_COMPRESSION_LEVEL",
+                        default_compression_level = DEFAULT_COMPRESSION_LEVEL,
+                        "Failed to parse OTLP compression level; defaulting to configured fallback"
+                    );
+                    None
+                }
+            });
+
+        Self {
+            compression,
+            compression_level,
+            ..Self::default()
+        }
+    }
+
+    /// Reads the payload size limit from `OTLP_FORWARDER_MAX_PAYLOAD_SIZE`
+    pub(crate) fn payload_size_from_env() -> Self {
+        Self {
+            max_payload_size_bytes: parse_env(MAX_PAYLOAD_SIZE_ENV_VAR, |v| v.parse().ok()),
+            ..Self::default()
+        }
+    }
+
+    /// Reads the retry policy from the `OTLP_FORWARDER_RETRY_*` environment variables
+    pub(crate) fn retry_from_env() -> Self {
+        Self {
+            retry_max_attempts: parse_env(MAX_ATTEMPTS_ENV_VAR, |v| v.parse().ok()),
+            retry_base_delay_ms: parse_env(BASE_DELAY_ENV_VAR, |v| v.parse().ok()),
+            retry_jitter: parse_env(JITTER_ENV_VAR, |v| v.to_lowercase().parse().ok()),
+            retry_budget_ms: parse_env(BUDGET_ENV_VAR, |v| v.parse().ok()),
+            ..Self::default()
+        }
+    }
+
+    /// Reads the circuit breaker settings from the `OTLP_FORWARDER_CIRCUIT_BREAKER_*` environment variables
+    pub(crate) fn circuit_breaker_from_env() -> Self {
+        Self {
+            circuit_breaker_failure_threshold: parse_env(FAILURE_THRESHOLD_ENV_VAR, |v| {
+                v.parse().ok()
+            }),
+            circuit_breaker_cooldown_ms: parse_env(COOLDOWN_ENV_VAR, |v| v.parse().ok()),
+            ..Self::default()
+        }
+    }
+
+    /// Reads the send concurrency from `OTLP_FORWARDER_SEND_CONCURRENCY`
+    pub(crate) fn send_concurrency_from_env() -> Self {
+        Self {
+            send_concurrency: parse_env(SEND_CONCURRENCY_ENV_VAR, |v| {
+                v.parse().ok().filter(|concurrency| *concurrency > 0)
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Reads the connection settings from the `OTLP_FORWARDER_POOL_*`, `OTLP_FORWARDER_HTTP2` and
+    /// `OTLP_FORWARDER_TCP_KEEPALIVE_MS` environment variables
+    pub(crate) fn connection_from_env() -> Self {
+        Self {
+            pool_idle_timeout_ms: parse_env(POOL_IDLE_TIMEOUT_ENV_VAR, |v| v.parse().ok()),
+            pool_max_idle_per_host: parse_env(POOL_MAX_IDLE_PER_HOST_ENV_VAR, |v| v.parse().ok()),
+            http2: parse_env(HTTP2_ENV_VAR, |v| v.to_lowercase().parse().ok()),
+            tcp_keepalive_ms: parse_env(TCP_KEEPALIVE_ENV_VAR, |v| v.parse().ok()),
+            ..Self::default()
+        }
+    }
+
+    /// Reads the attribute transform document, as JSON or YAML, from `OTLP_FORWARDER_ATTRIBUTE_TRANSFORM`
+    pub(crate) fn attribute_transform_from_env() -> Self {
+        Self {
+            attribute_transform: parse_env(ATTRIBUTE_TRANSFORM_ENV_VAR, |v| {
+                AttributeTransform::parse(v).ok()
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Reads the span filter document, as JSON or YAML, from `OTLP_FORWARDER_SPAN_FILTER`
+    pub(crate) fn span_filter_from_env() -> Self {
+        Self {
+            span_filter: parse_env(SPAN_FILTER_ENV_VAR, |v| SpanFilter::parse(v).ok()),
+            ..Self::default()
+        }
+    }
+
+    /// Reads the span deduplication capacity from `OTLP_FORWARDER_SPAN_DEDUP_CAPACITY`
+    pub(crate) fn span_dedup_from_env() -> Self {
+        Self {
+            span_dedup_capacity: parse_env(SPAN_DEDUP_CAPACITY_ENV_VAR, |v| v.parse().ok()),
+            ..Self::default()
+        }
+    }
+
+    /// Reads whether semantic conventions are normalized from `OTLP_FORWARDER_NORMALIZE_SEMCONV`
+    pub(crate) fn semconv_from_env() -> Self {
+        Self {
+            normalize_semconv: parse_env(NORMALIZE_SEMCONV_ENV_VAR, |v| {
+                v.to_lowercase().parse().ok()
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Reads whether source metadata is added from `OTLP_FORWARDER_ENRICH_SOURCE_METADATA`
+    pub(crate) fn source_metadata_from_env() -> Self {
+        Self {
+            enrich_source_metadata: parse_env(ENRICH_SOURCE_METADATA_ENV_VAR, |v| {
+                v.to_lowercase().parse().ok()
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Reads the tail-based sampling percentage from `OTLP_FORWARDER_SAMPLING_PERCENTAGE`
+    pub(crate) fn sampling_from_env() -> Self {
+        Self {
+            sampling_percentage: parse_env(SAMPLING_PERCENTAGE_ENV_VAR, |v| {
+                v.parse().ok().filter(|percentage| *percentage <= 100)
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Resolves the endpoint URL, defaulting to `http://localhost:4318/v1/traces`, or to
+    /// `http://localhost:4317` for gRPC
+    pub(crate) fn otlp_endpoint(&self) -> Result<Url> {
+        match &self.endpoint {
+            // The URL may carry credentials, so it is not reported
+            Some(endpoint) => Url::parse(endpoint).context("Invalid URL in forwarder settings"),
+            None => {
+                debug!(endpoint_source = "default", "Using default OTLP endpoint");
+                let default_endpoint = match self.otlp_protocol() {
+                    OtlpProtocol::HttpProtobuf => DEFAULT_OTLP_ENDPOINT,
+                    OtlpProtocol::Grpc => DEFAULT_OTLP_GRPC_ENDPOINT,
+                };
+                Url::parse(default_endpoint).context("Failed to parse default OTLP endpoint URL")
+            }
+        }
+    }
+
+    /// Resolves the logs endpoint URL, if one is set
+    pub(crate) fn otlp_logs_endpoint(&self) -> Result<Option<Url>> {
+        self.logs_endpoint
+            .as_deref()
+            .map(|endpoint| {
+                Url::parse(endpoint).context("Invalid logs endpoint URL in forwarder settings")
+            })
+            .transpose()
+    }
+
+    /// Resolves the metrics endpoint URL, if one is set
+    pub(crate) fn otlp_metrics_endpoint(&self) -> Result<Option<Url>> {
+        self.metrics_endpoint
+            .as_deref()
+            .map(|endpoint| {
+                Url::parse(endpoint).context("Invalid metrics endpoint URL in forwarder settings")
+            })
+            .transpose()
+    }
+
+    /// Resolves the transport protocol, defaulting to `http/protobuf`
+    pub(crate) fn otlp_protocol(&self) -> OtlpProtocol {
+        self.protocol.unwrap_or_default()
+    }
+
+    /// Resolves the headers, skipping (and logging) the invalid ones
+    pub(crate) fn otlp_headers(&self) -> HeaderMap {
+        let header_source = "forwarder_settings";
+        let mut headers = HeaderMap::new();
+        for (name, value) in self.headers.iter().flatten() {
+            let Ok(name) = HeaderName::from_str(name.trim()) else {
+                warn!(
+                    header_source,
+                    invalid_header_reason = "invalid_name",
+                    "Skipping invalid OTLP header"
+                );
+                continue;
+            };
+            let Ok(value) = HeaderValue::from_str(value.trim()) else {
+                warn!(
+                    header_source,
+                    invalid_header_reason = "invalid_value",
+                    "Skipping invalid OTLP header"
+                );
+                continue;
+            };
+            headers.append(name, value);
+        }
+        headers
+    }
+
+    /// Resolves the export timeout, defaulting to 10 seconds
+    pub(crate) fn otlp_timeout(&self) -> Duration {
+        match self.timeout_ms {
+            Some(timeout_ms) => Duration::from_millis(timeout_ms),
+            None => {
+                debug!(
+                    timeout_source = "default",
+                    timeout_ms = DEFAULT_OTLP_EXPORT_TIMEOUT.as_millis() as u64,
+                    "Using default OTLP export timeout"
+                );
+                DEFAULT_OTLP_EXPORT_TIMEOUT
+            }
+        }
+    }
+
+    /// Resolves the span compaction configuration
+    pub(crate) fn compaction_config(&self) -> SpanCompactionConfig {
+        let gzip_compression_level = match self.compression_level {
+            Some(level) if level <= 9 => level,
+            Some(_) => {
+                warn!(
+                    setting = "compression_level",
+                    default_compression_level = DEFAULT_COMPRESSION_LEVEL,
+                    "Invalid OTLP compression level; defaulting to configured fallback"
+                );
+                DEFAULT_COMPRESSION_LEVEL
+            }
+            None => DEFAULT_COMPRESSION_LEVEL,
+        };
+        let trace_sampler = match self.sampling_percentage {
+            Some(percentage) if percentage <= 100 => TraceSampler::new(percentage),
+            Some(_) => {
+                warn!(
+                    setting = "sampling_percentage",
+                    "Invalid sampling percentage; keeping every trace"
+                );
+                TraceSampler::default()
+            }
+            None => TraceSampler::default(),
+        };
+        SpanCompactionConfig {
+            compression: self
+                .compression
+                .clone()
+                .unwrap_or(CompressionPreference::None),
+            gzip_compression_level,
+            max_payload_size: self
+                .max_payload_size_bytes
+                .unwrap_or(DEFAULT_MAX_PAYLOAD_SIZE),
+            attribute_transform: self.attribute_transform.clone().unwrap_or_default(),
+            span_filter: self.span_filter.clone().unwrap_or_default(),
+            trace_sampler,
+            span_deduplicator: self
+                .span_dedup_capacity
+                .filter(|capacity| *capacity > 0)
+                .map(SpanDeduplicator::global),
+            semconv_normalizer: SemconvNormalizer::new(self.normalize_semconv.unwrap_or(false)),
+        }
+    }
+
+    /// Resolves the number of payloads sent concurrently, defaulting to 4
+    pub(crate) fn send_concurrency(&self) -> usize {
+        match self.send_concurrency {
+            Some(concurrency) if concurrency > 0 => concurrency,
+            Some(_) => {
+                warn!(
+                    setting = "send_concurrency",
+                    default_send_concurrency = DEFAULT_SEND_CONCURRENCY,
+                    "Invalid send concurrency; defaulting to configured fallback"
+                );
+                DEFAULT_SEND_CONCURRENCY
+            }
+            None => DEFAULT_SEND_CONCURRENCY,
+        }
+    }
+
+    /// Resolves the retry policy
+    pub(crate) fn retry_config(&self) -> RetryConfig {
+        let defaults = RetryConfig::default();
+        RetryConfig {
+            max_attempts: self
+                .retry_max_attempts
+                .unwrap_or(defaults.max_attempts)
+                .max(1),
+            base_delay: self
+                .retry_base_delay_ms
+                .map_or(defaults.base_delay, Duration::from_millis),
+            jitter: self.retry_jitter.unwrap_or(defaults.jitter),
+            retry_budget: self
+                .retry_budget_ms
+                .map_or(defaults.retry_budget, Duration::from_millis),
+        }
+    }
+
+    /// Resolves the circuit breaker configuration
+    pub(crate) fn circuit_breaker_config(&self) -> CircuitBreakerConfig {
+        let defaults = CircuitBreakerConfig::default();
+        CircuitBreakerConfig {
+            failure_threshold: self
+                .circuit_breaker_failure_threshold
+                .unwrap_or(defaults.failure_threshold),
+            cooldown: self
+                .circuit_breaker_cooldown_ms
+                .map_or(defaults.cooldown, Duration::from_millis),
+        }
+    }
+
+    /// Resolves the connection pooling and keep-alive configuration
+    pub(crate) fn connection_config(&self) -> ConnectionConfig {
+        let defaults = ConnectionConfig::default();
+        ConnectionConfig {
+            pool_idle_timeout: self
+                .pool_idle_timeout_ms
+                .map_or(defaults.pool_idle_timeout, Duration::from_millis),
+            pool_max_idle_per_host: self
+                .pool_max_idle_per_host
+                .unwrap_or(defaults.pool_max_idle_per_host),
+            http2: self.http2.unwrap_or(defaults.http2),
+            tcp_keepalive: match self.tcp_keepalive_ms {
+                Some(0) => None,
+                Some(interval_ms) => Some(Duration::from_millis(interval_ms)),
+                None => defaults.tcp_keepalive,
+            },
+        }
+    }
+}
+
+// The endpoint and header values may carry credentials, so they are left out
+impl fmt::Debug for ForwarderSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ForwarderSettings")
+            .field("endpoint_set", &self.endpoint.is_some())
+            .field("logs_endpoint_set", &self.logs_endpoint.is_some())
+            .field("metrics_endpoint_set", &self.metrics_endpoint.is_some())
+            .field("protocol", &self.protocol)
+            .field("headers_count", &self.headers.as_ref().map(BTreeMap::len))
+            .field("timeout_ms", &self.timeout_ms)
+            .field("compression", &self.compression)
+            .field("compression_level", &self.compression_level)
+            .field("max_payload_size_bytes", &self.max_payload_size_bytes)
+            .field("retry_max_attempts", &self.retry_max_attempts)
+            .field("retry_base_delay_ms", &self.retry_base_delay_ms)
+            .field("retry_jitter", &self.retry_jitter)
+            .field("retry_budget_ms", &self.retry_budget_ms)
+            .field(
+                "circuit_breaker_failure_threshold",
+                &self.circuit_breaker_failure_threshold,
+            )
+            .field(
+                "circuit_breaker_cooldown_ms",
+                &self.circuit_breaker_cooldown_ms,
+            )
+            .field("send_concurrency", &self.send_concurrency)
+            .field("pool_idle_timeout_ms", &self.pool_idle_timeout_ms)
+            .field("pool_max_idle_per_host", &self.pool_max_idle_per_host)
+            .field("http2", &self.http2)
+            .field("tcp_keepalive_ms", &self.tcp_keepalive_ms)
+            .field(
+                "attribute_transform_set",
+                &self.attribute_transform.is_some(),
+            )
+            .field("span_filter_set", &self.span_filter.is_some())
+            .field("sampling_percentage", &self.sampling_percentage)
+            .field("span_dedup_capacity", &self.span_dedup_capacity)
+            .field("normalize_semconv", &self.normalize_semconv)
+            .field("enrich_source_metadata", &self.enrich_source_metadata)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Resolved configuration shared by the forwarder components
+#[derive(Debug, Clone)]
+pub struct ForwarderConfig {
+    /// Where telemetry is sent
+    pub destination: Destination,
+    /// How telemetry is sent; see [`client_builder::for_protocol`](crate::client_builder::for_protocol)
+    pub protocol: OtlpProtocol,
+    /// How batches are compacted and compressed
+    pub compaction: SpanCompactionConfig,
+    /// How failed exports are retried
+    pub retry: RetryConfig,
+    /// When to stop sending to a failing endpoint
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Maximum number of payloads of a batch sent concurrently
+    pub send_concurrency: usize,
+    /// How the HTTP clients pool and keep connections alive; see [`client_builder::for_config`](crate::client_builder::for_config)
+    pub connection: ConnectionConfig,
+    /// Where batches that could not be delivered are persisted; see [`crate::dead_letter`]
+    pub dead_letter_sink: Option<Arc<dyn DeadLetterSink>>,
+    /// Whether [`Self::with_source_metadata`] adds the source as resource attributes
+    pub enrich_source_metadata: bool,
+}
+
+impl ForwarderConfig {
+    /// Loads the configuration from the optional configuration file and the environment
+    pub fn from_env() -> Result<Self> {
+        Self::load(ForwarderSettings::default())
+    }
+
+    /// Loads the configuration from the optional configuration file, the given settings and the
+    /// environment, in increasing order of precedence
+    pub fn load(settings: ForwarderSettings) -> Result<Self> {
+        let file = match non_empty_env(CONFIG_FILE_ENV_VAR) {
+            Some(path) => ForwarderSettings::from_file(path.trim())?,
+            None => ForwarderSettings::default(),
+        };
+        Self::resolve(&file.merge(settings).merge(ForwarderSettings::from_env()?))
+    }
+
+    /// Resolves the configuration from a single layer of settings, using defaults for unset fields
+    pub fn resolve(settings: &ForwarderSettings) -> Result<Self> {
+        Ok(Self {
+            destination: Destination::from_settings(settings)?,
+            protocol: settings.otlp_protocol(),
+            compaction: settings.compaction_config(),
+            retry: settings.retry_config(),
+            circuit_breaker: settings.circuit_breaker_config(),
+            send_concurrency: settings.send_concurrency(),
+            connection: settings.connection_config(),
+            dead_letter_sink: None,
+            enrich_source_metadata: settings.enrich_source_metadata.unwrap_or(false),
+        })
+    }
+
+    /// Sends traces to `endpoint` instead of the configured endpoint
+    pub fn with_endpoint(mut self, endpoint: Url) -> Self {
+        self.destination = self.destination.with_endpoint(endpoint);
+        self
+    }
+
+    /// Adds the given headers to the configured ones, replacing headers with the same name
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.destination = self.destination.with_headers(headers);
+        self
+    }
+
+    /// Sets the export timeout of each attempt
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.destination = self.destination.with_timeout(timeout);
+        self
+    }
+
+    /// Sets the compression of compacted payloads
+    pub fn with_compression(mut self, compression: CompressionPreference) -> Self {
+        self.compaction.compression = compression;
+        self
+    }
+
+    /// Returns the configuration to forward the records of an event read from `metadata`
+    ///
+    /// When source metadata enrichment is enabled, the returned configuration adds the log group,
+    /// log stream and account of the source as resource attributes of every signal (see
+    /// [`crate::source_metadata`]); otherwise this configuration is returned unchanged.
+    pub fn with_source_metadata(&self, metadata: &SourceMetadata) -> Cow<'_, Self> {
+        if !self.enrich_source_metadata || metadata.is_empty() {
+            return Cow::Borrowed(self);
+        }
+        let mut config = self.clone();
+        config.compaction = metadata.apply_to_compaction(&self.compaction);
+        Cow::Owned(config)
+    }
+
+    /// Persists batches that could not be delivered to `sink` instead of dropping them
+    pub fn with_dead_letter_sink(mut self, sink: impl DeadLetterSink + 'static) -> Self {
+        self.dead_letter_sink = Some(Arc::new(sink));
+        self
+    }
+}
+
+fn non_empty_env(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Reads a full endpoint URL; the URL may carry credentials, so errors only name the variable
+fn url_from_env(name: &str) -> Result<Option<String>> {
+    non_empty_env(name)
+        .map(|endpoint| {
+            Url::parse(&endpoint)
+                .map(String::from)
+                .with_context(|| format!("Invalid URL in {name}"))
+        })
+        .transpose()
+}
+
+/// Appends `/v1/traces` to a base endpoint URL, unless it is already there
+fn traces_endpoint_from_base(mut url: Url) -> Url {
+    let current_path = url.path();
+    if !current_path.ends_with(OTLP_TRACES_PATH) {
+        let new_path = if current_path == "/" || current_path.is_empty() {
+            OTLP_TRACES_PATH.to_string()
+        } else {
+            format!("{}{}", current_path.trim_end_matches('/'), OTLP_TRACES_PATH)
+        };
+        url.set_path(&new_path);
+    }
+    url
+}
+
+fn parse_env<T>(env_var: &'static str, parse: impl Fn(&str) -> Option<T>) -> Option<T> {
+    let value = env::var(env_var).ok()?;
+    let parsed = parse(value.trim());
+    if parsed.is_none() {
+        warn!(env_var, "Failed to parse forwarder setting; using default");
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sealed_test::prelude::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_merge_prefers_set_fields_of_the_higher_layer() {
+        let file = ForwarderSettings {
+            endpoint: Some("https://file.example.com/v1/traces".to_string()),
+            timeout_ms: Some(1000),
+            retry_max_attempts: Some(5),
+            ..ForwarderSettings::default()
+        };
+        let settings = ForwarderSettings {
+            timeout_ms: Some(2000),
+            compression: Some(CompressionPreference::Gzip),
+            ..ForwarderSettings::default()
+        };
+
+        let merged = file.merge(settings);
+        assert_eq!(
+            merged.endpoint.as_deref(),
+            Some("https://file.example.com/v1/traces")
+        );
+        assert_eq!(merged.timeout_ms, Some(2000));
+        assert_eq!(merged.retry_max_attempts, Some(5));
+        assert_eq!(merged.compression, Some(CompressionPreference::Gzip));
+    }
+
+    #[test]
+    fn test_from_file_reads_yaml_and_json() {
+        let dir = tempdir().unwrap();
+        let yaml_path = dir.path().join("forwarder.yaml");
+        fs::write(
+            &yaml_path,
+            "endpoint: https://collector.example.com/v1/traces\nheaders:\n  x-api-key: secret\ncompression: gzip\nretry_jitter: false\n",
+        )
+        .unwrap();
+        let json_path = dir.path().join("forwarder.json");
+        fs::write(
+            &json_path,
+            r#"{"timeout_ms": 2500, "compression_level": 4, "protocol": "grpc"}"#,
+        )
+        .unwrap();
+
+        let yaml = ForwarderSettings::from_file(&yaml_path).unwrap();
+        assert_eq!(yaml.compression, Some(CompressionPreference::Gzip));
+        assert_eq!(yaml.retry_jitter, Some(false));
+        assert_eq!(yaml.otlp_headers()["x-api-key"], "secret");
+
+        let json = ForwarderSettings::from_file(&json_path).unwrap();
+        assert_eq!(json.timeout_ms, Some(2500));
+        assert_eq!(json.compaction_config().gzip_compression_level, 4);
+        assert_eq!(json.protocol, Some(OtlpProtocol::Grpc));
+    }
+
+    #[test]
+    fn test_from_file_error_does_not_leak_values() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("forwarder.json");
+        fs::write(&path, r#"{"timeout_ms": "top-secret"}"#).unwrap();
+
+        let err = format!("{:#}", ForwarderSettings::from_file(&path).unwrap_err());
+        assert!(err.contains("Invalid forwarder configuration file"));
+        assert!(!err.contains("top-secret"));
+
+        fs::write(&path, r#"{"unknown_setting": 1}"#).unwrap();
+        assert!(ForwarderSettings::from_file(&path).is_err());
+
+        fs::write(&path, r#"{"endpoint": "not a url with a secret"}"#).unwrap();
+        let err = format!("{:#}", ForwarderSettings::from_file(&path).unwrap_err());
+        assert!(!err.contains("secret"));
+    }
+
+    #[test]
+    #[sealed_test]
+    fn test_load_layers_file_settings_and_env() {
+        env::set_var("OTEL_EXPORTER_OTLP_ENDPOINT", "https://env.example.com");
+        env::set_var(MAX_ATTEMPTS_ENV_VAR, "not-a-number");
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("forwarder.yaml");
+        fs::write(
+            &path,
+            "endpoint: https://file.example.com/v1/traces\ntimeout_ms: 1000\nretry_max_attempts: 7\ncircuit_breaker_failure_threshold: 2\n",
+        )
+        .unwrap();
+        env::set_var(CONFIG_FILE_ENV_VAR, &path);
+
+        let config = ForwarderConfig::load(ForwarderSettings {
+            timeout_ms: Some(3000),
+            ..ForwarderSettings::default()
+        })
+        .unwrap();
+
+        // The environment wins over the file and the settings
+        assert_eq!(
+            config.destination.endpoint().as_str(),
+            "https://env.example.com/v1/traces"
+        );
+        // The settings win over the file
+        assert_eq!(config.destination.timeout(), Duration::from_millis(3000));
+        // An invalid environment value is ignored
+        assert_eq!(config.retry.max_attempts, 7);
+        assert_eq!(config.circuit_breaker.failure_threshold, 2);
+        assert_eq!(config.compaction.compression, CompressionPreference::None);
+        assert!(config.compaction.attribute_transform.is_empty());
+    }
+
+    #[test]
+    #[sealed_test]
+    fn test_attribute_transform_from_file_and_env() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("forwarder.yaml");
+        fs::write(
+            &path,
+            "attribute_transform:\n  resource:\n    delete: [aws.log.group.names]\n",
+        )
+        .unwrap();
+        env::set_var(CONFIG_FILE_ENV_VAR, &path);
+
+        let config = ForwarderConfig::from_env().unwrap();
+        assert_eq!(
+            config.compaction.attribute_transform.resource.delete,
+            vec!["aws.log.group.names".to_string()]
+        );
+
+        // The environment replaces the whole transform; an invalid document is ignored
+        env::set_var(
+            ATTRIBUTE_TRANSFORM_ENV_VAR,
+            r#"{"span": {"delete": ["http.user_agent"]}}"#,
+        );
+        let config = ForwarderConfig::from_env().unwrap();
+        assert!(config.compaction.attribute_transform.resource.is_empty());
+        assert_eq!(
+            config.compaction.attribute_transform.span.delete,
+            vec!["http.user_agent".to_string()]
+        );
+
+        env::set_var(ATTRIBUTE_TRANSFORM_ENV_VAR, "{not: [valid");
+        let config = ForwarderConfig::from_env().unwrap();
+        assert!(!config.compaction.attribute_transform.resource.is_empty());
+
+        env::remove_var(ATTRIBUTE_TRANSFORM_ENV_VAR);
+        env::remove_var(CONFIG_FILE_ENV_VAR);
+    }
+
+    #[test]
+    #[sealed_test]
+    fn test_span_filter_from_file_and_env() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("forwarder.yaml");
+        fs::write(&path, "span_filter:\n  - name: GET /health\n").unwrap();
+        env::set_var(CONFIG_FILE_ENV_VAR, &path);
+
+        let config = ForwarderConfig::from_env().unwrap();
+        assert_eq!(
+            config.compaction.span_filter.rules[0].name.as_deref(),
+            Some("GET /health")
+        );
+
+        // The environment replaces the whole filter; an invalid document is ignored
+        env::set_var(
+            SPAN_FILTER_ENV_VAR,
+            r#"[{"status": "error"}, {"name": "ping"}]"#,
+        );
+        let config = ForwarderConfig::from_env().unwrap();
+        assert_eq!(config.compaction.span_filter.rules.len(), 2);
+
+        env::set_var(SPAN_FILTER_ENV_VAR, r#"[{"name_regex": "(unclosed"}]"#);
+        let config = ForwarderConfig::from_env().unwrap();
+        assert_eq!(config.compaction.span_filter.rules.len(), 1);
+
+        env::remove_var(SPAN_FILTER_ENV_VAR);
+        env::remove_var(CONFIG_FILE_ENV_VAR);
+    }
+
+    #[test]
+    #[sealed_test]
+    fn test_sampling_percentage_from_settings_and_env() {
+        let config = ForwarderConfig::resolve(&ForwarderSettings {
+            sampling_percentage: Some(150),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!config.compaction.trace_sampler.is_enabled());
+
+        env::set_var(SAMPLING_PERCENTAGE_ENV_VAR, "25");
+        let settings = ForwarderSettings {
+            sampling_percentage: Some(50),
+            ..Default::default()
+        }
+        .merge(ForwarderSettings::from_env().unwrap());
+        let config = ForwarderConfig::resolve(&settings).unwrap();
+        assert_eq!(config.compaction.trace_sampler.percentage(), 25);
+
+        // An invalid value is ignored, so the lower layers apply
+        env::set_var(SAMPLING_PERCENTAGE_ENV_VAR, "101");
+        let settings = ForwarderSettings {
+            sampling_percentage: Some(50),
+            ..Default::default()
+        }
+        .merge(ForwarderSettings::from_env().unwrap());
+        let config = ForwarderConfig::resolve(&settings).unwrap();
+        assert_eq!(config.compaction.trace_sampler.percentage(), 50);
+        assert!(config.compaction.span_deduplicator.is_none());
+
+        env::remove_var(SAMPLING_PERCENTAGE_ENV_VAR);
+    }
+
+    #[test]
+    #[sealed_test]
+    fn test_normalize_semconv_from_settings_and_env() {
+        let config = ForwarderConfig::resolve(&ForwarderSettings::default()).unwrap();
+        assert!(!config.compaction.semconv_normalizer.is_enabled());
+
+        let settings = ForwarderSettings {
+            normalize_semconv: Some(true),
+            ..Default::default()
+        };
+        env::set_var(NORMALIZE_SEMCONV_ENV_VAR, "FALSE");
+        let config = ForwarderConfig::resolve(
+            &settings
+                .clone()
+                .merge(ForwarderSettings::from_env().unwrap()),
+        )
+        .unwrap();
+        assert!(!config.compaction.semconv_normalizer.is_enabled());
+
+        // An invalid value is ignored
+        env::set_var(NORMALIZE_SEMCONV_ENV_VAR, "yes");
+        let config =
+            ForwarderConfig::resolve(&settings.merge(ForwarderSettings::from_env().unwrap()))
+                .unwrap();
+        assert!(config.compaction.semconv_normalizer.is_enabled());
+
+        env::remove_var(NORMALIZE_SEMCONV_ENV_VAR);
+    }
+
+    #[test]
+    #[sealed_test]
+    fn test_with_source_metadata() {
+        let metadata = SourceMetadata::new("/aws/lambda/checkout", "stream", "123456789012");
+        let config = ForwarderConfig::resolve(&ForwarderSettings::default()).unwrap();
+        assert!(matches!(
+            config.with_source_metadata(&metadata),
+            Cow::Borrowed(_)
+        ));
+
+        env::set_var(ENRICH_SOURCE_METADATA_ENV_VAR, "true");
+        let config = ForwarderConfig::resolve(&ForwarderSettings::from_env().unwrap()).unwrap();
+        let enriched = config.with_source_metadata(&metadata);
+        assert_eq!(
+            enriched.compaction.attribute_transform.resource.set.len(),
+            3
+        );
+        assert!(config.compaction.attribute_transform.is_empty());
+
+        env::remove_var(ENRICH_SOURCE_METADATA_ENV_VAR);
+    }
+
+    #[test]
+    #[sealed_test]
+    fn test_span_dedup_capacity_from_env() {
+        env::set_var(SPAN_DEDUP_CAPACITY_ENV_VAR, "0");
+        let config = ForwarderConfig::resolve(&ForwarderSettings::from_env().unwrap()).unwrap();
+        assert!(config.compaction.span_deduplicator.is_none());
+
+        env::set_var(SPAN_DEDUP_CAPACITY_ENV_VAR, "1000");
+        let config = ForwarderConfig::resolve(&ForwarderSettings::from_env().unwrap()).unwrap();
+        // The cache is process-wide and shared by every configuration
+        let deduplicator = config.compaction.span_deduplicator.unwrap();
+        let config = ForwarderConfig::resolve(&ForwarderSettings::from_env().unwrap()).unwrap();
+        assert!(Arc::ptr_eq(
+            &deduplicator,
+            config.compaction.span_deduplicator.as_ref().unwrap()
+        ));
+
+        env::remove_var(SPAN_DEDUP_CAPACITY_ENV_VAR);
+    }
+
+    #[test]
+    #[sealed_test]
+    fn test_connection_settings_from_file_and_env() {
+        let settings: ForwarderSettings = serde_yaml::from_str(
+            "pool_idle_timeout_ms: 5000\npool_max_idle_per_host: 2\nhttp2: true\n",
+        )
+        .unwrap();
+        env::set_var(POOL_MAX_IDLE_PER_HOST_ENV_VAR, "1");
+        env::set_var(TCP_KEEPALIVE_ENV_VAR, "30000");
+        env::set_var(HTTP2_ENV_VAR, "yes");
+
+        // Invalid values are ignored, leaving the value of the file
+        let config =
+            ForwarderConfig::resolve(&settings.merge(ForwarderSettings::from_env().unwrap()))
+                .unwrap();
+        assert_eq!(
+            config.connection,
+            ConnectionConfig {
+                pool_idle_timeout: Duration::from_secs(5),
+                pool_max_idle_per_host: 1,
+                http2: true,
+                tcp_keepalive: Some(Duration::from_secs(30)),
+            }
+        );
+
+        env::set_var(TCP_KEEPALIVE_ENV_VAR, "0");
+        assert_eq!(ConnectionConfig::from_env().tcp_keepalive, None);
+
+        env::remove_var(POOL_MAX_IDLE_PER_HOST_ENV_VAR);
+        env::remove_var(TCP_KEEPALIVE_ENV_VAR);
+        env::remove_var(HTTP2_ENV_VAR);
+    }
+
+    #[test]
+    #[sealed_test]
+    fn test_grpc_protocol_from_env_uses_grpc_default_endpoint() {
+        env::set_var("OTEL_EXPORTER_OTLP_PROTOCOL", "grpc");
+
+        let config = ForwarderConfig::from_env().unwrap();
+        assert_eq!(config.protocol, OtlpProtocol::Grpc);
+        assert_eq!(
+            config.destination.endpoint().as_str(),
+            "http://localhost:4317/"
+        );
+
+        // The signal-specific variable wins; unsupported protocols fall back to http/protobuf
+        env::set_var("OTEL_EXPORTER_OTLP_TRACES_PROTOCOL", "http/json");
+        let config = ForwarderConfig::from_env().unwrap();
+        assert_eq!(config.protocol, OtlpProtocol::HttpProtobuf);
+        assert_eq!(
+            config.destination.endpoint().as_str(),
+            "http://localhost:4318/v1/traces"
+        );
+    }
+
+    #[test]
+    fn test_resolve_reports_endpoint_source_only() {
+        let settings = ForwarderSettings {
+            endpoint: Some("not a url with a secret token".to_string()),
+            ..ForwarderSettings::default()
+        };
+        let err = ForwarderConfig::resolve(&settings).unwrap_err().to_string();
+        assert!(err.contains("forwarder settings"));
+        assert!(!err.contains("secret"));
+        assert!(!format!("{settings:?}").contains("secret"));
+    }
+}
@@ -0,0 +1,172 @@
+//! Connection reuse of the HTTP clients.
+//!
+//! A forwarder Lambda keeps its HTTP client across warm invocations, so the connections it opened
+//! to the collector can be reused instead of paying a TCP and TLS handshake on every invocation.
+//! [`ConnectionConfig`] tunes how idle connections are pooled, whether HTTP/2 is negotiated and
+//! whether TCP keepalive probes are sent, and is applied by the clients of
+//! [`client_builder`](crate::client_builder). [`warm_up`] opens the connection ahead of the first
+//! invocation, typically from the init phase of the function.
+
+use crate::config::ForwarderSettings;
+use crate::destination::Destination;
+use crate::http_sender::HttpOtlpForwarderClient;
+use crate::telemetry::Signal;
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use std::time::Duration;
+use tracing::debug;
+
+/// Environment variable for how long idle connections are kept for reuse, in milliseconds
+pub const POOL_IDLE_TIMEOUT_ENV_VAR: &str = "OTLP_FORWARDER_POOL_IDLE_TIMEOUT_MS";
+/// Environment variable for the number of idle connections kept per host (`0` disables reuse)
+pub const POOL_MAX_IDLE_PER_HOST_ENV_VAR: &str = "OTLP_FORWARDER_POOL_MAX_IDLE_PER_HOST";
+/// Environment variable enabling HTTP/2 (`true` or `false`)
+pub const HTTP2_ENV_VAR: &str = "OTLP_FORWARDER_HTTP2";
+/// Environment variable for the interval of TCP keepalive probes, in milliseconds (`0` disables them)
+pub const TCP_KEEPALIVE_ENV_VAR: &str = "OTLP_FORWARDER_TCP_KEEPALIVE_MS";
+
+/// Default time idle connections are kept for reuse
+pub const DEFAULT_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Connection pooling and keep-alive settings of the HTTP clients
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionConfig {
+    /// How long an idle connection is kept for reuse
+    pub pool_idle_timeout: Duration,
+    /// Maximum number of idle connections kept per host; `0` disables connection reuse
+    pub pool_max_idle_per_host: usize,
+    /// Whether HTTP/2 is negotiated (through ALPN) with `https` endpoints; otherwise only
+    /// HTTP/1.1 is used
+    pub http2: bool,
+    /// Interval of TCP keepalive probes on open connections; `None` disables them
+    pub tcp_keepalive: Option<Duration>,
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            pool_idle_timeout: DEFAULT_POOL_IDLE_TIMEOUT,
+            pool_max_idle_per_host: usize::MAX,
+            http2: false,
+            tcp_keepalive: None,
+        }
+    }
+}
+
+impl ConnectionConfig {
+    /// Resolves the configuration from the `OTLP_FORWARDER_POOL_*`, `OTLP_FORWARDER_HTTP2` and
+    /// `OTLP_FORWARDER_TCP_KEEPALIVE_MS` environment variables
+    ///
+    /// Unset variables use the defaults; invalid values are logged and fall back to the defaults.
+    pub fn from_env() -> Self {
+        ForwarderSettings::connection_from_env().connection_config()
+    }
+
+    /// Applies the settings to a reqwest client builder
+    pub(crate) fn apply(&self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        let builder = builder
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .tcp_keepalive(self.tcp_keepalive);
+        if self.http2 {
+            builder
+        } else {
+            builder.http1_only()
+        }
+    }
+
+    /// Applies the settings to a reqwest 0.13 client builder, as used by the instrumented client
+    #[cfg(feature = "instrumented-client")]
+    pub(crate) fn apply_to_instrumented(
+        &self,
+        builder: reqwest13::ClientBuilder,
+    ) -> reqwest13::ClientBuilder {
+        let builder = builder
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .tcp_keepalive(self.tcp_keepalive);
+        if self.http2 {
+            builder
+        } else {
+            builder.http1_only()
+        }
+    }
+}
+
+/// Opens the connection of `client` to the traces endpoint of `destination`, so that the first
+/// batch does not pay for the TCP and TLS handshakes
+///
+/// Sends an empty trace export request, which collectors accept without effect, with the headers
+/// and timeout of the destination. Any response, whatever its status, leaves an open connection
+/// in the pool of the client; only transport errors are returned.
+pub async fn warm_up(
+    client: &impl HttpOtlpForwarderClient,
+    destination: &Destination,
+) -> Result<()> {
+    let target_url = destination.endpoint_for(Signal::Traces)?;
+    let mut headers = destination.headers().clone();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/x-protobuf"),
+    );
+    let response = client
+        .post_telemetry(target_url, headers, Bytes::new(), destination.timeout())
+        .await
+        .context("Failed to open connection to OTLP endpoint")?;
+    debug!(
+        status = response.status().as_u16(),
+        "Opened connection to OTLP endpoint"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_builder;
+    use url::Url;
+    use wiremock::matchers::{body_bytes, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_warm_up_sends_empty_export() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .and(header("authorization", "Bearer token"))
+            .and(body_bytes(Vec::new()))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let destination = Destination::new(
+            "warm-up",
+            Url::parse(&format!("{}/v1/traces", server.uri())).unwrap(),
+        )
+        .with_bearer_token("token")
+        .unwrap();
+        let client = client_builder::with_options(
+            &ConnectionConfig {
+                pool_max_idle_per_host: 1,
+                tcp_keepalive: Some(Duration::from_secs(30)),
+                ..ConnectionConfig::default()
+            },
+            &Default::default(),
+        )
+        .unwrap();
+
+        // The status does not matter, the connection is open
+        warm_up(&client, &destination).await.unwrap();
+
+        let unreachable = Destination::new(
+            "unreachable",
+            Url::parse("http://127.0.0.1:1/v1/traces").unwrap(),
+        );
+        let err = warm_up(&client, &unreachable).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to open connection to OTLP endpoint"
+        );
+    }
+}
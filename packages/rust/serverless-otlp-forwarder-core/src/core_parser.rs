@@ -1,5 +1,10 @@
-use crate::telemetry::TelemetryData;
-use anyhow::Result;
+use crate::telemetry::{Signal, TelemetryData};
+use anyhow::{anyhow, Context, Result};
+use otlp_stdout_span_exporter::ExporterOutput;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
 
 pub trait EventParser {
     // The specific AWS event type (e.g., LogsEvent, KinesisEvent)
@@ -11,4 +16,390 @@ pub trait EventParser {
         event_payload: Self::EventInput,
         source_identifier: &str,
     ) -> Result<Vec<TelemetryData>>;
+
+    /// Parses an event like [`parse`](Self::parse), also returning the number of records the
+    /// parser rejected and skipped
+    ///
+    /// The processors report this count as
+    /// [`BatchOutcome::records_failed`](crate::processor::BatchOutcome::records_failed). The
+    /// default implementation reports no rejected records; parsers that skip malformed records
+    /// override it.
+    fn parse_counting_failures(
+        &self,
+        event_payload: Self::EventInput,
+        source_identifier: &str,
+    ) -> Result<(Vec<TelemetryData>, usize)> {
+        self.parse(event_payload, source_identifier)
+            .map(|telemetry_items| (telemetry_items, 0))
+    }
+}
+
+/// A parser of a single log record
+pub type RecordParser = dyn EventParser<EventInput = String> + Send + Sync;
+
+/// Format of a single log record, as detected by [`RecordFormat::sniff`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordFormat {
+    /// An `otlp-stdout-span-exporter` envelope, marked by its `__otel_otlp_stdout` field
+    OtlpStdout,
+    /// A raw OTLP/JSON export request (`resourceSpans`, `resourceLogs` or `resourceMetrics`)
+    OtlpJson,
+    /// An AWS X-Ray segment document (`trace_id`, `id` and `start_time`)
+    XRaySegment,
+}
+
+/// Top-level fields used to recognize a record, without parsing their values
+#[derive(Deserialize)]
+struct RecordKeys {
+    #[serde(rename = "__otel_otlp_stdout")]
+    otlp_stdout: Option<IgnoredAny>,
+    #[serde(rename = "resourceSpans")]
+    resource_spans: Option<IgnoredAny>,
+    #[serde(rename = "resourceLogs")]
+    resource_logs: Option<IgnoredAny>,
+    #[serde(rename = "resourceMetrics")]
+    resource_metrics: Option<IgnoredAny>,
+    trace_id: Option<IgnoredAny>,
+    id: Option<IgnoredAny>,
+    start_time: Option<IgnoredAny>,
+}
+
+impl RecordKeys {
+    fn otlp_json_signal(&self) -> Option<Signal> {
+        if self.resource_spans.is_some() {
+            Some(Signal::Traces)
+        } else if self.resource_logs.is_some() {
+            Some(Signal::Logs)
+        } else if self.resource_metrics.is_some() {
+            Some(Signal::Metrics)
+        } else {
+            None
+        }
+    }
+}
+
+impl RecordFormat {
+    /// Detects the format of a record from its top-level JSON fields
+    ///
+    /// Returns `None` for records that are not JSON objects or match no known format.
+    pub fn sniff(record: &str) -> Option<Self> {
+        let keys: RecordKeys = serde_json::from_str(record).ok()?;
+        if keys.otlp_stdout.is_some() {
+            Some(Self::OtlpStdout)
+        } else if keys.otlp_json_signal().is_some() {
+            Some(Self::OtlpJson)
+        } else if keys.trace_id.is_some() && keys.id.is_some() && keys.start_time.is_some() {
+            Some(Self::XRaySegment)
+        } else {
+            None
+        }
+    }
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::OtlpStdout => "otlp_stdout",
+            Self::OtlpJson => "otlp_json",
+            Self::XRaySegment => "xray_segment",
+        }
+    }
+}
+
+impl fmt::Display for RecordFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses `otlp-stdout-span-exporter` envelopes with [`TelemetryData::from_log_record`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OtlpStdoutRecordParser;
+
+impl EventParser for OtlpStdoutRecordParser {
+    type EventInput = String;
+
+    fn parse(&self, record: String, _source_identifier: &str) -> Result<Vec<TelemetryData>> {
+        let output: ExporterOutput =
+            serde_json::from_str(&record).context("Failed to parse otlp-stdout record")?;
+        Ok(vec![TelemetryData::from_log_record(output)?])
+    }
+}
+
+/// Parses raw OTLP/JSON export requests of any signal into protobuf
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OtlpJsonRecordParser;
+
+impl EventParser for OtlpJsonRecordParser {
+    type EventInput = String;
+
+    fn parse(&self, record: String, source_identifier: &str) -> Result<Vec<TelemetryData>> {
+        let signal = serde_json::from_str::<RecordKeys>(&record)
+            .ok()
+            .and_then(|keys| keys.otlp_json_signal())
+            .ok_or_else(|| anyhow!("Record is not an OTLP/JSON export request"))?;
+        let payload = TelemetryData::convert_to_protobuf(
+            record.into_bytes(),
+            "application/json",
+            None,
+            signal,
+        )?;
+        Ok(vec![TelemetryData {
+            source: source_identifier.to_string(),
+            // Default endpoint to localhost for the collector extension model
+            endpoint: format!("http://localhost:4318{}", signal.path()),
+            payload,
+            signal,
+            ..Default::default()
+        }])
+    }
+}
+
+/// Dispatches each log record to the [`EventParser`] registered for its [`RecordFormat`]
+///
+/// This lets a single processor consume log groups holding different record formats. Records
+/// of an unknown or unregistered format, and records their parser rejects, are skipped.
+///
+/// # Example
+/// ```rust,ignore
+/// use serverless_otlp_forwarder_core::core_parser::{ParserRegistry, RecordFormat};
+///
+/// let registry = ParserRegistry::with_defaults()
+///     .with_parser(RecordFormat::XRaySegment, XRaySegmentParser);
+/// let messages = event.aws_logs.data.log_events.into_iter().map(|e| e.message).collect();
+/// process_event_batch_with_config(messages, &registry, &log_group, &http_client, &config).await?;
+/// ```
+#[derive(Default)]
+pub struct ParserRegistry {
+    parsers: HashMap<RecordFormat, Box<RecordParser>>,
+}
+
+impl ParserRegistry {
+    /// Creates a registry without any parser
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry with the parsers of the core crate: [`OtlpStdoutRecordParser`] and
+    /// [`OtlpJsonRecordParser`]
+    pub fn with_defaults() -> Self {
+        Self::new()
+            .with_parser(RecordFormat::OtlpStdout, OtlpStdoutRecordParser)
+            .with_parser(RecordFormat::OtlpJson, OtlpJsonRecordParser)
+    }
+
+    /// Registers the parser of a record format, replacing any previous one
+    pub fn with_parser(
+        mut self,
+        format: RecordFormat,
+        parser: impl EventParser<EventInput = String> + Send + Sync + 'static,
+    ) -> Self {
+        self.parsers.insert(format, Box::new(parser));
+        self
+    }
+
+    /// Returns true if a parser is registered for `format`
+    pub fn handles(&self, format: RecordFormat) -> bool {
+        self.parsers.contains_key(&format)
+    }
+
+    /// Parses records like [`EventParser::parse`], but fails when the parser of a registered
+    /// format rejects a record instead of skipping it
+    ///
+    /// Records of an unknown or unregistered format are still skipped. This lets
+    /// [`process_batch_items`](crate::partial_batch::process_batch_items) report the event
+    /// source record holding a malformed record as failed.
+    pub fn try_parse(
+        &self,
+        records: Vec<String>,
+        source_identifier: &str,
+    ) -> Result<Vec<TelemetryData>> {
+        self.parse_records(records, source_identifier, true)
+            .map(|(telemetry_items, _)| telemetry_items)
+    }
+
+    fn parse_records(
+        &self,
+        records: Vec<String>,
+        source_identifier: &str,
+        strict: bool,
+    ) -> Result<(Vec<TelemetryData>, usize)> {
+        let record_count = records.len();
+        let mut skipped_count = 0usize;
+        let mut failed_count = 0usize;
+        let mut telemetry_items = Vec::with_capacity(record_count);
+        for record in records {
+            let Some(format) = RecordFormat::sniff(&record) else {
+                skipped_count += 1;
+                continue;
+            };
+            let Some(parser) = self.parsers.get(&format) else {
+                tracing::debug!(%format, "No parser registered for record format; skipping record");
+                skipped_count += 1;
+                continue;
+            };
+            match parser.parse(record, source_identifier) {
+                Ok(items) => telemetry_items.extend(items),
+                Err(e) if strict => {
+                    tracing::warn!(%format, "Failed to parse record");
+                    return Err(e.context(format!("Failed to parse {format} record")));
+                }
+                Err(_) => {
+                    tracing::warn!(%format, "Failed to parse record; skipping record");
+                    skipped_count += 1;
+                    failed_count += 1;
+                }
+            }
+        }
+        if skipped_count > 0 {
+            tracing::warn!(
+                records_count = record_count as u64,
+                skipped_count = skipped_count as u64,
+                "Skipped records of unknown format or that failed to parse"
+            );
+        }
+        Ok((telemetry_items, failed_count))
+    }
+}
+
+impl fmt::Debug for ParserRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParserRegistry")
+            .field("formats", &self.parsers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl EventParser for ParserRegistry {
+    type EventInput = Vec<String>;
+
+    fn parse(&self, records: Vec<String>, source_identifier: &str) -> Result<Vec<TelemetryData>> {
+        self.parse_counting_failures(records, source_identifier)
+            .map(|(telemetry_items, _)| telemetry_items)
+    }
+
+    /// Counts the records their parser rejected, but not the records of an unknown or
+    /// unregistered format
+    fn parse_counting_failures(
+        &self,
+        records: Vec<String>,
+        source_identifier: &str,
+    ) -> Result<(Vec<TelemetryData>, usize)> {
+        self.parse_records(records, source_identifier, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::otlp_stdout_record;
+    use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+    use prost::Message;
+    use serde_json::json;
+
+    fn otlp_json_logs_record() -> String {
+        json!({
+            "resourceLogs": [{
+                "scopeLogs": [{
+                    "logRecords": [{"timeUnixNano": "1700000000000000000", "body": {"stringValue": "hello"}}]
+                }]
+            }]
+        })
+        .to_string()
+    }
+
+    fn xray_segment_record() -> String {
+        json!({
+            "name": "checkout",
+            "id": "70de5b6f19ff9a0a",
+            "trace_id": "1-581cf771-a006649127e371903a2de979",
+            "start_time": 1478293361.271,
+            "end_time": 1478293361.449
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_sniff_record_formats() {
+        assert_eq!(
+            RecordFormat::sniff(&otlp_stdout_record("service-a")),
+            Some(RecordFormat::OtlpStdout)
+        );
+        assert_eq!(
+            RecordFormat::sniff(&otlp_json_logs_record()),
+            Some(RecordFormat::OtlpJson)
+        );
+        assert_eq!(
+            RecordFormat::sniff(&xray_segment_record()),
+            Some(RecordFormat::XRaySegment)
+        );
+        assert_eq!(RecordFormat::sniff(r#"{"message": "plain log"}"#), None);
+        assert_eq!(RecordFormat::sniff("START RequestId: 1234"), None);
+    }
+
+    #[test]
+    fn test_registry_dispatches_by_format() {
+        struct SegmentParser;
+
+        impl EventParser for SegmentParser {
+            type EventInput = String;
+
+            fn parse(&self, _record: String, source: &str) -> Result<Vec<TelemetryData>> {
+                Ok(vec![TelemetryData {
+                    source: source.to_string(),
+                    ..Default::default()
+                }])
+            }
+        }
+
+        let records = vec![
+            otlp_stdout_record("service-a"),
+            "not json".to_string(),
+            otlp_json_logs_record(),
+            xray_segment_record(),
+            json!({"__otel_otlp_stdout": "broken"}).to_string(),
+        ];
+
+        let defaults = ParserRegistry::with_defaults();
+        assert!(!defaults.handles(RecordFormat::XRaySegment));
+        let items = defaults
+            .parse(records.clone(), "/aws/lambda/mixed")
+            .unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].source, "service-a");
+        assert_eq!(items[1].signal, Signal::Logs);
+        assert_eq!(items[1].source, "/aws/lambda/mixed");
+        let logs = ExportLogsServiceRequest::decode(items[1].payload.as_slice()).unwrap();
+        assert_eq!(logs.resource_logs[0].scope_logs[0].log_records.len(), 1);
+
+        let registry = defaults.with_parser(RecordFormat::XRaySegment, SegmentParser);
+        let items = registry
+            .parse(records.clone(), "/aws/lambda/mixed")
+            .unwrap();
+        assert_eq!(items.len(), 3);
+
+        // Only the record rejected by its parser counts as failed, not the unknown format
+        let (items, failed_count) = registry
+            .parse_counting_failures(records, "/aws/lambda/mixed")
+            .unwrap();
+        assert_eq!((items.len(), failed_count), (3, 1));
+    }
+
+    #[test]
+    fn test_registry_try_parse_fails_on_rejected_record() {
+        let registry = ParserRegistry::with_defaults();
+        let items = registry
+            .try_parse(
+                vec![otlp_stdout_record("service-a"), "not json".to_string()],
+                "/aws/lambda/a",
+            )
+            .unwrap();
+        assert_eq!(items.len(), 1);
+
+        let err = registry
+            .try_parse(
+                vec![json!({"__otel_otlp_stdout": "broken"}).to_string()],
+                "/aws/lambda/a",
+            )
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Failed to parse otlp_stdout record");
+    }
 }
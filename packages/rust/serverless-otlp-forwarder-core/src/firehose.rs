@@ -0,0 +1,287 @@
+//! Amazon Data Firehose transformation event source.
+//!
+//! A Firehose delivery stream can invoke a Lambda function to transform its records before
+//! delivery. [`process_firehose_event`] lets the forwarder run as that function: it extracts the
+//! log records of each Firehose record (newline-delimited lines, or a gzip-compressed CloudWatch
+//! Logs envelope when the stream is fed by a subscription filter), parses them through a
+//! [`ParserRegistry`], compacts and forwards the telemetry of each signal, and answers with the
+//! per-record transformation response Firehose requires.
+
+use crate::config::ForwarderConfig;
+use crate::core_parser::{EventParser, ParserRegistry};
+use crate::http_sender::HttpOtlpForwarderClient;
+use crate::kinesis::{decode_cloudwatch_logs_data, is_gzip, CONTROL_MESSAGE_TYPE};
+use crate::processor::{compact_and_deliver_signal, BatchOutcome};
+use crate::rate_limiter::{AdaptiveRateLimiter, RateLimitedClient};
+use crate::telemetry::{Signal, TelemetryData};
+use aws_lambda_events::event::firehose::{
+    KinesisFirehoseEvent, KinesisFirehoseResponse, KinesisFirehoseResponseRecord,
+};
+use std::fmt;
+use tracing::{instrument, warn};
+
+/// Transformation result of a Firehose record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirehoseRecordResult {
+    /// The record holds no telemetry and is delivered by Firehose unchanged
+    Ok,
+    /// The telemetry of the record was forwarded, so Firehose does not deliver it
+    Dropped,
+    /// The record could not be decoded or its telemetry was not delivered; Firehose writes it
+    /// to the error output of the delivery stream
+    ProcessingFailed,
+}
+
+impl FirehoseRecordResult {
+    /// Returns the value Firehose expects in the `result` field of a response record
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "Ok",
+            Self::Dropped => "Dropped",
+            Self::ProcessingFailed => "ProcessingFailed",
+        }
+    }
+}
+
+impl fmt::Display for FirehoseRecordResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Extracts the log records of a Firehose record along with their source
+///
+/// Returns `None` if the data cannot be decoded. A CloudWatch Logs control message yields no
+/// log record.
+fn record_messages(data: &[u8], default_source: &str) -> Option<(String, Vec<String>)> {
+    if is_gzip(data) {
+        let log_data = decode_cloudwatch_logs_data(data).ok()?;
+        if log_data.message_type == CONTROL_MESSAGE_TYPE {
+            return Some((log_data.log_group, Vec::new()));
+        }
+        let messages = log_data
+            .log_events
+            .into_iter()
+            .map(|log_event| log_event.message)
+            .collect();
+        return Some((log_data.log_group, messages));
+    }
+    let text = std::str::from_utf8(data).ok()?;
+    let messages = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    Some((default_source.to_string(), messages))
+}
+
+/// Processes a Firehose transformation event, forwarding the telemetry it carries.
+///
+/// The telemetry of all records is compacted per signal and sent to the configured destination
+/// with the configured retries, like [`process_event_batch_with_config`]. The response holds one
+/// record per input record:
+///
+/// - records whose telemetry was forwarded are `Dropped`, as the forwarder already delivered them;
+/// - records without telemetry are `Ok` and passed through unchanged to the Firehose destination;
+/// - records that cannot be decoded, or whose telemetry was not delivered (nor persisted to the
+///   dead-letter sink), are `ProcessingFailed`, so that Firehose keeps them in its error output.
+///
+/// As the telemetry of a signal is sent as one compacted batch, a failed send fails every record
+/// that contributed to it.
+///
+/// [`process_event_batch_with_config`]: crate::processor::process_event_batch_with_config
+#[instrument(name = "processor/process_firehose_event", skip_all, fields(firehose.records.count = event.records.len()))]
+pub async fn process_firehose_event<C: HttpOtlpForwarderClient>(
+    event: KinesisFirehoseEvent,
+    registry: &ParserRegistry,
+    http_client: &C,
+    config: &ForwarderConfig,
+) -> KinesisFirehoseResponse {
+    let default_source = event
+        .delivery_stream_arn
+        .clone()
+        .unwrap_or_else(|| "firehose_delivery_stream_unknown".to_string());
+
+    // 1. Parse the log records of each Firehose record, grouping telemetry by signal
+    let mut results = Vec::with_capacity(event.records.len());
+    let mut items_by_signal: Vec<(Signal, Vec<TelemetryData>, Vec<usize>)> = Vec::new();
+    for (index, record) in event.records.iter().enumerate() {
+        let Some((source, messages)) = record_messages(&record.data.0, &default_source) else {
+            warn!("Failed to decode Firehose record data; marking record as failed.");
+            results.push(FirehoseRecordResult::ProcessingFailed);
+            continue;
+        };
+        let telemetry_items = match registry.parse(messages, &source) {
+            Ok(items) => items,
+            Err(_) => {
+                warn!("Failed to parse Firehose record; marking record as failed.");
+                results.push(FirehoseRecordResult::ProcessingFailed);
+                continue;
+            }
+        };
+        if telemetry_items.is_empty() {
+            results.push(FirehoseRecordResult::Ok);
+            continue;
+        }
+        for item in telemetry_items {
+            match items_by_signal
+                .iter_mut()
+                .find(|(signal, _, _)| *signal == item.signal)
+            {
+                Some((_, items, indices)) => {
+                    items.push(item);
+                    if indices.last() != Some(&index) {
+                        indices.push(index);
+                    }
+                }
+                None => items_by_signal.push((item.signal, vec![item], vec![index])),
+            }
+        }
+        results.push(FirehoseRecordResult::Dropped);
+    }
+
+    // 2. Compact and send the telemetry of each signal, paced by the shared rate limiter
+    let http_client = &RateLimitedClient::new(http_client, AdaptiveRateLimiter::global());
+    for (signal, items, indices) in items_by_signal {
+        if !compact_and_deliver_signal(
+            signal,
+            items,
+            http_client,
+            config,
+            &mut BatchOutcome::default(),
+        )
+        .await
+        {
+            for index in indices {
+                results[index] = FirehoseRecordResult::ProcessingFailed;
+            }
+        }
+    }
+
+    let failed_count = results
+        .iter()
+        .filter(|result| **result == FirehoseRecordResult::ProcessingFailed)
+        .count();
+    if failed_count > 0 {
+        warn!(
+            failed_records_count = failed_count as i64,
+            "Some Firehose records were not processed."
+        );
+    }
+
+    // 3. Answer with one response record per input record
+    let mut response = KinesisFirehoseResponse::default();
+    response.records = event
+        .records
+        .into_iter()
+        .zip(results)
+        .map(|(record, result)| {
+            let mut response_record = KinesisFirehoseResponseRecord::default();
+            response_record.record_id = record.record_id;
+            response_record.result = Some(result.as_str().to_string());
+            // Dropped records carry no data
+            if result != FirehoseRecordResult::Dropped {
+                response_record.data = record.data;
+            }
+            response_record
+        })
+        .collect();
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::otlp_stdout_record;
+    use crate::ForwarderSettings;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use reqwest::Client as ReqwestClient;
+    use serde_json::{json, Value};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn firehose_event(records: &[Vec<u8>]) -> KinesisFirehoseEvent {
+        let records: Vec<Value> = records
+            .iter()
+            .enumerate()
+            .map(|(i, data)| {
+                json!({
+                    "recordId": format!("record-{i}"),
+                    "approximateArrivalTimestamp": 1700000000000i64,
+                    "data": STANDARD.encode(data)
+                })
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "invocationId": "invocation-1",
+            "deliveryStreamArn": "arn:aws:firehose:us-east-1:123456789012:deliverystream/otlp",
+            "region": "us-east-1",
+            "records": records
+        }))
+        .unwrap()
+    }
+
+    async fn process(status: u16) -> Vec<(String, String, usize)> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .respond_with(ResponseTemplate::new(status))
+            .mount(&server)
+            .await;
+        let config = ForwarderConfig::resolve(&ForwarderSettings {
+            endpoint: Some(format!("{}/v1/traces", server.uri())),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let event = firehose_event(&[
+            format!(
+                "{}\n{}\n",
+                otlp_stdout_record("service-a"),
+                otlp_stdout_record("service-b")
+            )
+            .into_bytes(),
+            b"plain application log\n".to_vec(),
+            vec![0x1f, 0x8b, 0x00],
+        ]);
+        let response = process_firehose_event(
+            event,
+            &ParserRegistry::with_defaults(),
+            &ReqwestClient::new(),
+            &config,
+        )
+        .await;
+        response
+            .records
+            .into_iter()
+            .map(|record| {
+                (
+                    record.record_id.unwrap(),
+                    record.result.unwrap(),
+                    record.data.0.len(),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_process_firehose_event_results() {
+        let results = process(200).await;
+        assert_eq!(results[0].0, "record-0");
+        assert_eq!(results[0].1, "Dropped");
+        assert_eq!(results[0].2, 0);
+        assert_eq!(results[1].1, "Ok");
+        assert_eq!(results[1].2, "plain application log\n".len());
+        assert_eq!(results[2].1, "ProcessingFailed");
+    }
+
+    #[tokio::test]
+    async fn test_process_firehose_event_fails_undelivered_records() {
+        let results = process(400).await;
+        assert_eq!(results[0].1, "ProcessingFailed");
+        assert!(results[0].2 > 0);
+        assert_eq!(results[1].1, "Ok");
+        assert_eq!(results[2].1, "ProcessingFailed");
+    }
+}
@@ -0,0 +1,437 @@
+//! OTLP/gRPC transport.
+//!
+//! [`GrpcClient`] implements [`HttpOtlpForwarderClient`] on top of the OTLP `TraceService`,
+//! `LogsService` and `MetricsService` gRPC APIs, so it plugs into the same send path, retries
+//! and circuit breaker as the HTTP clients. It is selected with `OTEL_EXPORTER_OTLP_PROTOCOL=grpc` through
+//! [`client_builder::for_protocol`](crate::client_builder::for_protocol).
+//!
+//! - Only the scheme, host and port of the endpoint are used; gRPC sets its own request path.
+//!   Endpoints ending in `/v1/logs` or `/v1/metrics` select the logs or metrics service, any
+//!   other path the trace service.
+//! - `https` endpoints use TLS with the Mozilla root certificates bundled by `webpki-roots`.
+//! - Destination headers are sent as gRPC metadata.
+//! - GZIP-compressed batches are sent with gRPC GZIP compression.
+//! - gRPC status codes are mapped to the equivalent HTTP statuses, so that `UNAVAILABLE` and
+//!   `RESOURCE_EXHAUSTED` are retried like `503` and `429`.
+//!
+//! Requires the `grpc` feature.
+
+use crate::http_sender::{HttpForwarderResponse, HttpOtlpForwarderClient};
+use crate::telemetry::Signal;
+use crate::tls::TlsConfig;
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+use http::StatusCode;
+use opentelemetry_proto::tonic::collector::logs::v1::logs_service_client::LogsServiceClient;
+use opentelemetry_proto::tonic::collector::metrics::v1::metrics_service_client::MetricsServiceClient;
+use opentelemetry_proto::tonic::collector::trace::v1::trace_service_client::TraceServiceClient;
+use prost::Message;
+use reqwest::header::{HeaderMap, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::Mutex;
+use std::time::Duration;
+use tonic::codec::CompressionEncoding;
+use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue, MetadataMap};
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tonic::Code;
+use tracing::{debug, warn};
+use url::Url;
+
+/// An [`HttpOtlpForwarderClient`] that sends OTLP batches over gRPC
+///
+/// Channels are opened lazily and kept per endpoint, so the client should be kept across
+/// invocations like the HTTP clients.
+#[derive(Debug, Default)]
+pub struct GrpcClient {
+    channels: Mutex<HashMap<String, Channel>>,
+    tls_config: TlsConfig,
+}
+
+impl GrpcClient {
+    /// Creates a client without any open channel
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a client presenting the client identity and trusting the certificate authorities
+    /// of `tls_config` on `https` endpoints
+    pub fn with_tls(tls_config: TlsConfig) -> Self {
+        Self {
+            tls_config,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the channel for the origin of `target_url`, creating it on first use
+    fn channel(&self, target_url: &Url) -> Result<Channel> {
+        let origin = target_url.origin().ascii_serialization();
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(channel) = channels.get(&origin) {
+            return Ok(channel.clone());
+        }
+
+        // The URL may carry credentials, so it is not reported
+        let mut endpoint =
+            Endpoint::from_shared(origin.clone()).context("Invalid OTLP/gRPC endpoint")?;
+        if target_url.scheme() == "https" {
+            endpoint = endpoint
+                .tls_config(
+                    self.tls_config
+                        .apply_to_grpc(ClientTlsConfig::new().with_webpki_roots()),
+                )
+                .context("Failed to configure TLS for OTLP/gRPC endpoint")?;
+        }
+        debug!(
+            tls = target_url.scheme() == "https",
+            "Opening OTLP/gRPC channel"
+        );
+        let channel = endpoint.connect_lazy();
+        channels.insert(origin, channel.clone());
+        Ok(channel)
+    }
+}
+
+#[async_trait]
+impl HttpOtlpForwarderClient for GrpcClient {
+    async fn post_telemetry(
+        &self,
+        target_url: Url,
+        headers: HeaderMap,
+        payload: Bytes,
+        timeout: Duration,
+    ) -> Result<HttpForwarderResponse> {
+        let content_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        if content_type.is_some_and(|value| value != "application/x-protobuf") {
+            return Err(anyhow!("OTLP/gRPC only supports protobuf payloads"));
+        }
+
+        let gzip = match headers
+            .get(CONTENT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+        {
+            None => false,
+            Some(encoding) if encoding.eq_ignore_ascii_case("gzip") => true,
+            Some(_) => return Err(anyhow!("Unsupported Content-Encoding for OTLP/gRPC")),
+        };
+        let message = decompress(&payload, gzip)?;
+        let channel = self.channel(&target_url)?;
+        let metadata = metadata_from_headers(&headers);
+        let compression = gzip.then_some(CompressionEncoding::Gzip);
+
+        let signal = Signal::from_endpoint(target_url.path());
+        let exported = match signal {
+            Signal::Traces => {
+                let request = grpc_request(decode(&message)?, timeout, metadata);
+                let mut client =
+                    TraceServiceClient::new(channel).accept_compressed(CompressionEncoding::Gzip);
+                if let Some(encoding) = compression {
+                    client = client.send_compressed(encoding);
+                }
+                client.export(request).await.map(|response| {
+                    response
+                        .into_inner()
+                        .partial_success
+                        .map_or(0, |partial_success| partial_success.rejected_spans)
+                })
+            }
+            Signal::Logs => {
+                let request = grpc_request(decode(&message)?, timeout, metadata);
+                let mut client =
+                    LogsServiceClient::new(channel).accept_compressed(CompressionEncoding::Gzip);
+                if let Some(encoding) = compression {
+                    client = client.send_compressed(encoding);
+                }
+                client.export(request).await.map(|response| {
+                    response
+                        .into_inner()
+                        .partial_success
+                        .map_or(0, |partial_success| partial_success.rejected_log_records)
+                })
+            }
+            Signal::Metrics => {
+                let request = grpc_request(decode(&message)?, timeout, metadata);
+                let mut client =
+                    MetricsServiceClient::new(channel).accept_compressed(CompressionEncoding::Gzip);
+                if let Some(encoding) = compression {
+                    client = client.send_compressed(encoding);
+                }
+                client.export(request).await.map(|response| {
+                    response
+                        .into_inner()
+                        .partial_success
+                        .map_or(0, |partial_success| partial_success.rejected_data_points)
+                })
+            }
+        };
+
+        match exported {
+            Ok(rejected) => Ok(HttpForwarderResponse::new(StatusCode::OK, String::new())
+                .with_rejected_items(rejected.max(0) as u64)),
+            // The status message comes from the server, so only the code is kept
+            Err(status) => Ok(HttpForwarderResponse::new(
+                http_status_for(status.code()),
+                status.code().description().to_string(),
+            )),
+        }
+    }
+}
+
+/// Returns the protobuf batch, decompressing it first when `gzip` is set
+fn decompress(payload: &[u8], gzip: bool) -> Result<Cow<'_, [u8]>> {
+    if !gzip {
+        return Ok(Cow::Borrowed(payload));
+    }
+    let mut decompressed = Vec::new();
+    GzDecoder::new(payload)
+        .read_to_end(&mut decompressed)
+        .context("Failed to decompress OTLP payload for gRPC export")?;
+    Ok(Cow::Owned(decompressed))
+}
+
+/// Decodes the protobuf batch into the export request of the target service
+fn decode<M: Message + Default>(message: &[u8]) -> Result<M> {
+    M::decode(message).map_err(|_| anyhow!("Failed to decode OTLP payload for gRPC export"))
+}
+
+fn grpc_request<M>(message: M, timeout: Duration, metadata: MetadataMap) -> tonic::Request<M> {
+    let mut request = tonic::Request::new(message);
+    request.set_timeout(timeout);
+    *request.metadata_mut() = metadata;
+    request
+}
+
+/// Converts the destination headers to gRPC metadata, skipping the HTTP framing headers
+fn metadata_from_headers(headers: &HeaderMap) -> MetadataMap {
+    let mut metadata = MetadataMap::new();
+    for (name, value) in headers {
+        if [CONTENT_TYPE, CONTENT_ENCODING, CONTENT_LENGTH].contains(name) {
+            continue;
+        }
+        let (Ok(key), Ok(mut value)) = (
+            AsciiMetadataKey::from_bytes(name.as_str().as_bytes()),
+            AsciiMetadataValue::try_from(value.as_bytes()),
+        ) else {
+            warn!(
+                header_source = "destination",
+                "Skipping OTLP header that is not valid gRPC metadata"
+            );
+            continue;
+        };
+        value.set_sensitive(true);
+        metadata.append(key, value);
+    }
+    metadata
+}
+
+/// Maps a gRPC status code to the HTTP status with the same retry semantics
+fn http_status_for(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+            StatusCode::BAD_REQUEST
+        }
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists => StatusCode::CONFLICT,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        // Retryable per the OTLP specification
+        Code::Cancelled | Code::Aborted | Code::Unavailable | Code::DataLoss => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        Code::Unknown | Code::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use hyper::server::conn::http2;
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use hyper_util::service::TowerToHyperService;
+    use opentelemetry_proto::tonic::collector::trace::v1::trace_service_server::{
+        TraceService, TraceServiceServer,
+    };
+    use opentelemetry_proto::tonic::collector::trace::v1::{
+        ExportTraceServiceRequest, ExportTraceServiceResponse,
+    };
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+    use reqwest::header::HeaderValue;
+    use std::io::Write;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use tonic::{Response, Status};
+
+    /// Records the received requests, or fails them with the configured status
+    #[derive(Default)]
+    struct RecordingService {
+        received: Mutex<Vec<(ExportTraceServiceRequest, MetadataMap)>>,
+        failure: Option<Code>,
+    }
+
+    #[async_trait]
+    impl TraceService for RecordingService {
+        async fn export(
+            &self,
+            request: tonic::Request<ExportTraceServiceRequest>,
+        ) -> std::result::Result<Response<ExportTraceServiceResponse>, Status> {
+            if let Some(code) = self.failure {
+                return Err(Status::new(code, "collector-secret-detail"));
+            }
+            let metadata = request.metadata().clone();
+            self.received
+                .lock()
+                .unwrap()
+                .push((request.into_inner(), metadata));
+            Ok(Response::new(ExportTraceServiceResponse::default()))
+        }
+    }
+
+    /// Serves the trace service over plaintext HTTP/2 on a local port
+    async fn start_server(service: Arc<RecordingService>) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server =
+            TraceServiceServer::from_arc(service).accept_compressed(CompressionEncoding::Gzip);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let service = TowerToHyperService::new(server.clone());
+                tokio::spawn(
+                    http2::Builder::new(TokioExecutor::new())
+                        .serve_connection(TokioIo::new(stream), service),
+                );
+            }
+        });
+        addr
+    }
+
+    fn test_request() -> ExportTraceServiceRequest {
+        ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![Span {
+                        name: "grpc-span".to_string(),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_exports_gzip_batch_with_metadata() {
+        let service = Arc::new(RecordingService::default());
+        let addr = start_server(service.clone()).await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-protobuf"),
+        );
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        headers.insert("x-api-key", HeaderValue::from_static("test-key"));
+
+        let client = GrpcClient::new();
+        let response = client
+            .post_telemetry(
+                Url::parse(&format!("http://{addr}/v1/traces")).unwrap(),
+                headers,
+                Bytes::from(gzip(&test_request().encode_to_vec())),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let received = service.received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (request, metadata) = &received[0];
+        assert_eq!(request, &test_request());
+        assert_eq!(metadata.get("x-api-key").unwrap(), "test-key");
+        assert!(metadata.get("content-encoding").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_maps_grpc_status_without_server_message() {
+        let service = Arc::new(RecordingService {
+            failure: Some(Code::Unavailable),
+            ..RecordingService::default()
+        });
+        let addr = start_server(service).await;
+
+        let response = GrpcClient::new()
+            .post_telemetry(
+                Url::parse(&format!("http://{addr}")).unwrap(),
+                HeaderMap::new(),
+                Bytes::from(test_request().encode_to_vec()),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!response.body().contains("collector-secret-detail"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_undecodable_payload() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+
+        let err = GrpcClient::new()
+            .post_telemetry(
+                Url::parse("http://localhost:4317").unwrap(),
+                headers,
+                Bytes::from_static(b"not gzip"),
+                Duration::from_secs(1),
+            )
+            .await
+            .map(|r| r.status())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to decompress OTLP payload for gRPC export"
+        );
+    }
+
+    #[test]
+    fn test_http_status_for_retryable_codes() {
+        assert_eq!(
+            http_status_for(Code::Unavailable),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            http_status_for(Code::ResourceExhausted),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(
+            http_status_for(Code::DeadlineExceeded),
+            StatusCode::GATEWAY_TIMEOUT
+        );
+        assert_eq!(
+            http_status_for(Code::InvalidArgument),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            http_status_for(Code::Unauthenticated),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
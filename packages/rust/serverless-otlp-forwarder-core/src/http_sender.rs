@@ -1,21 +1,36 @@
-use crate::telemetry::TelemetryData;
+use crate::circuit_breaker::CircuitOpenError;
+use crate::config::{ForwarderConfig, ForwarderSettings, OtlpProtocol};
+use crate::destination::Destination;
+use crate::retry::{is_retryable_status, RetryConfig};
+use crate::telemetry::{Signal, TelemetryData};
+use crate::tls::TlsConfig;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use bytes::Bytes;
+use futures::stream::{self, StreamExt};
 use http::StatusCode;
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_ENCODING, CONTENT_TYPE};
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceResponse;
+use prost::Message;
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, CONTENT_ENCODING, CONTENT_TYPE, RETRY_AFTER,
+};
 use reqwest::Client as ReqwestClient;
-use std::env;
+use std::fmt;
 use std::future::Future;
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 use tracing::{debug, instrument, warn, Span};
 use url::Url;
 
-const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4318/v1/traces";
-const OTLP_TRACES_PATH: &str = "/v1/traces";
-const DEFAULT_OTLP_EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+pub(crate) const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4318/v1/traces";
+pub(crate) const OTLP_TRACES_PATH: &str = "/v1/traces";
+pub(crate) const DEFAULT_OTLP_EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Environment variable bounding the number of payloads sent concurrently by [`send_all`]
+pub const SEND_CONCURRENCY_ENV_VAR: &str = "OTLP_FORWARDER_SEND_CONCURRENCY";
+/// Default number of payloads sent concurrently
+pub const DEFAULT_SEND_CONCURRENCY: usize = 4;
 
 /// Public response carrier returned by [`HttpOtlpForwarderClient`] implementations.
 ///
@@ -24,12 +39,43 @@ const DEFAULT_OTLP_EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
 pub struct HttpForwarderResponse {
     status: StatusCode,
     body: String,
+    retry_after: Option<Duration>,
+    rejected_items: u64,
 }
 
 impl HttpForwarderResponse {
     /// Creates a new forwarder response with the HTTP status and response body.
     pub fn new(status: StatusCode, body: String) -> Self {
-        Self { status, body }
+        Self {
+            status,
+            body,
+            retry_after: None,
+            rejected_items: 0,
+        }
+    }
+
+    /// Sets the delay requested by the server through the `Retry-After` header.
+    pub fn with_retry_after(mut self, retry_after: Option<Duration>) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
+    /// Returns the delay requested by the server through the `Retry-After` header, if any.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
+
+    /// Sets the number of spans, log records or data points the server rejected in a
+    /// partially successful export.
+    pub fn with_rejected_items(mut self, rejected_items: u64) -> Self {
+        self.rejected_items = rejected_items;
+        self
+    }
+
+    /// Returns the number of items the server reported as rejected in a partially successful
+    /// export.
+    pub fn rejected_items(&self) -> u64 {
+        self.rejected_items
     }
 
     /// Returns the HTTP status code from the export attempt.
@@ -68,20 +114,88 @@ where
     }
 }
 
-async fn drain_success_body<F, E>(status: StatusCode, drain_body: F)
+/// Parses a `Retry-After` header expressed in seconds or as an HTTP-date.
+/// Dates in the past yield a zero delay.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Reads a success response body and returns the number of rejected items it reports
+async fn read_rejected_items<F, E>(status: StatusCode, json: bool, read_body: F) -> u64
 where
-    F: Future<Output = std::result::Result<(), E>>,
+    F: Future<Output = std::result::Result<Bytes, E>>,
 {
-    if drain_body.await.is_err() {
+    let body = match read_body.await {
+        Ok(body) => body,
+        Err(_) => {
+            warn!(
+                status = status.as_u16(),
+                "Failed to drain OTLP success response body"
+            );
+            return 0;
+        }
+    };
+    parse_rejected_items(&body, json).unwrap_or_else(|| {
         warn!(
             status = status.as_u16(),
-            "Failed to drain OTLP success response body"
+            response_body_size_bytes = body.len() as u64,
+            "Failed to parse OTLP export response body"
         );
+        0
+    })
+}
+
+/// Parses the `partial_success` of an OTLP export response
+///
+/// The export responses of all signals share the same layout: a `partial_success` message whose
+/// first field is the number of rejected spans, log records or data points. The error message
+/// set by the server is ignored, as it may echo request data.
+fn parse_rejected_items(body: &[u8], json: bool) -> Option<u64> {
+    if body.is_empty() {
+        return Some(0);
+    }
+    if !json {
+        let response = ExportTraceServiceResponse::decode(body).ok()?;
+        return Some(response.partial_success.map_or(0, |partial_success| {
+            partial_success.rejected_spans.max(0) as u64
+        }));
+    }
+    let response: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let Some(partial_success) = response.get("partialSuccess") else {
+        return Some(0);
+    };
+    let rejected = ["rejectedSpans", "rejectedLogRecords", "rejectedDataPoints"]
+        .iter()
+        .find_map(|field| partial_success.get(field));
+    match rejected {
+        None => Some(0),
+        // Protobuf JSON encodes 64-bit integers as strings
+        Some(serde_json::Value::String(value)) => value.parse().ok(),
+        Some(value) => value.as_u64(),
     }
 }
 
+/// Returns true if the response declares a JSON body
+fn is_json_response(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"))
+}
+
 /// Parses OTLP headers from a comma-separated key=value string.
-fn parse_otlp_headers(headers_str: &str, header_source: &'static str) -> Result<HeaderMap> {
+pub(crate) fn parse_otlp_headers(
+    headers_str: &str,
+    header_source: &'static str,
+) -> Result<HeaderMap> {
     let mut headers = HeaderMap::new();
     if headers_str.is_empty() {
         return Ok(headers);
@@ -137,137 +251,10 @@ fn parse_otlp_headers(headers_str: &str, header_source: &'static str) -> Result<
     Ok(headers)
 }
 
-/// Resolves OTLP headers from environment variables.
-/// Priority: OTEL_EXPORTER_OTLP_TRACES_HEADERS, then OTEL_EXPORTER_OTLP_HEADERS.
-fn resolve_otlp_headers() -> Result<HeaderMap> {
-    let traces_headers_var = env::var("OTEL_EXPORTER_OTLP_TRACES_HEADERS");
-    let generic_headers_var = env::var("OTEL_EXPORTER_OTLP_HEADERS");
-
-    match traces_headers_var {
-        Ok(headers_str) if !headers_str.is_empty() => {
-            debug!(
-                header_source = "OTEL_EXPORTER_OTLP_TRACES_HEADERS",
-                configured_header_parts_count = headers_str
-                    .split(',')
-                    .filter(|part| !part.trim().is_empty())
-                    .count() as u64,
-                "Using configured OTLP headers"
-            );
-            return parse_otlp_headers(&headers_str, "OTEL_EXPORTER_OTLP_TRACES_HEADERS");
-        }
-        _ => { // Fall through if TRACES_HEADERS is not set or empty
-        }
-    }
-
-    match generic_headers_var {
-        Ok(headers_str) if !headers_str.is_empty() => {
-            debug!(
-                header_source = "OTEL_EXPORTER_OTLP_HEADERS",
-                configured_header_parts_count = headers_str
-                    .split(',')
-                    .filter(|part| !part.trim().is_empty())
-                    .count() as u64,
-                "Using configured OTLP headers"
-            );
-            return parse_otlp_headers(&headers_str, "OTEL_EXPORTER_OTLP_HEADERS");
-        }
-        _ => { // Fall through if HEADERS is not set or empty
-        }
-    }
-
-    Ok(HeaderMap::new()) // No headers from env vars
-}
-
-/// Resolves the OTLP endpoint URL based on OpenTelemetry environment variables.
-/// Priorities:
-/// 1. OTEL_EXPORTER_OTLP_TRACES_ENDPOINT (used as is)
-/// 2. OTEL_EXPORTER_OTLP_ENDPOINT (base URL, /v1/traces might be appended)
-/// 3. Default: http://localhost:4318/v1/traces
-fn resolve_otlp_endpoint() -> Result<Url> {
-    if let Ok(traces_endpoint) = env::var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT") {
-        if !traces_endpoint.is_empty() {
-            debug!(
-                endpoint_source = "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
-                "Using configured OTLP endpoint"
-            );
-            return Url::parse(&traces_endpoint)
-                .context("Invalid URL in OTEL_EXPORTER_OTLP_TRACES_ENDPOINT");
-        }
-    }
-
-    if let Ok(generic_endpoint) = env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
-        if !generic_endpoint.is_empty() {
-            debug!(
-                endpoint_source = "OTEL_EXPORTER_OTLP_ENDPOINT",
-                "Using configured OTLP endpoint"
-            );
-            let mut url = Url::parse(&generic_endpoint)
-                .context("Invalid URL in OTEL_EXPORTER_OTLP_ENDPOINT")?;
-
-            let current_path = url.path();
-            if !current_path.ends_with(OTLP_TRACES_PATH) {
-                let new_path = if current_path == "/" || current_path.is_empty() {
-                    OTLP_TRACES_PATH.to_string()
-                } else {
-                    format!("{}{}", current_path.trim_end_matches('/'), OTLP_TRACES_PATH)
-                };
-                url.set_path(&new_path);
-            }
-            return Ok(url);
-        }
-    }
-
-    debug!(endpoint_source = "default", "Using default OTLP endpoint");
-    Url::parse(DEFAULT_OTLP_ENDPOINT).context("Failed to parse default OTLP endpoint URL")
-}
-
-/// Parses an OTLP timeout string (expected to be milliseconds) into a Duration.
-fn parse_otlp_timeout_millis(duration_ms_str: &str) -> Result<Duration> {
-    let millis = duration_ms_str
-        .parse::<u64>()
-        .context("Invalid OTLP timeout value")?;
-    Ok(Duration::from_millis(millis))
-}
-
 /// Resolves the OTLP export timeout from environment variables.
 /// Value is expected to be in milliseconds.
-fn resolve_otlp_timeout() -> Duration {
-    let traces_timeout_var = env::var("OTEL_EXPORTER_OTLP_TRACES_TIMEOUT");
-    let generic_timeout_var = env::var("OTEL_EXPORTER_OTLP_TIMEOUT");
-
-    let timeout_str_to_parse = match traces_timeout_var {
-        Ok(val) if !val.is_empty() => Some(("OTEL_EXPORTER_OTLP_TRACES_TIMEOUT", val)),
-        _ => match generic_timeout_var {
-            Ok(val) if !val.is_empty() => Some(("OTEL_EXPORTER_OTLP_TIMEOUT", val)),
-            _ => None,
-        },
-    };
-
-    if let Some((timeout_source, timeout_value)) = timeout_str_to_parse {
-        match parse_otlp_timeout_millis(&timeout_value) {
-            Ok(duration) => {
-                debug!(
-                    timeout_source,
-                    timeout_ms = duration.as_millis() as u64,
-                    "Using configured OTLP export timeout"
-                );
-                return duration;
-            }
-            Err(_) => {
-                warn!(
-                    timeout_source,
-                    timeout_ms = DEFAULT_OTLP_EXPORT_TIMEOUT.as_millis() as u64,
-                    "Failed to parse OTLP export timeout; using default"
-                );
-            }
-        }
-    }
-    debug!(
-        timeout_source = "default",
-        timeout_ms = DEFAULT_OTLP_EXPORT_TIMEOUT.as_millis() as u64,
-        "Using default OTLP export timeout"
-    );
-    DEFAULT_OTLP_EXPORT_TIMEOUT
+pub(crate) fn resolve_otlp_timeout() -> Duration {
+    ForwarderSettings::timeout_from_env().otlp_timeout()
 }
 
 /// Trait for an HTTP client capable of sending OTLP telemetry batches for the forwarder.
@@ -301,21 +288,231 @@ impl HttpOtlpForwarderClient for ReqwestClient {
             .context("HTTP request failed during OTLP export")?;
 
         let status = response.status();
-        let body = if status.is_success() {
-            drain_success_body(status, async move { response.bytes().await.map(|_| ()) }).await;
-            String::new()
+        let retry_after = parse_retry_after(response.headers());
+        if status.is_success() {
+            let json = is_json_response(response.headers());
+            let rejected_items = read_rejected_items(status, json, response.bytes()).await;
+            return Ok(HttpForwarderResponse::new(status, String::new())
+                .with_rejected_items(rejected_items));
+        }
+        let body = read_error_body_if_needed(status, response.text()).await;
+        Ok(HttpForwarderResponse::new(status, body).with_retry_after(retry_after))
+    }
+}
+
+#[async_trait]
+impl<T: HttpOtlpForwarderClient + ?Sized> HttpOtlpForwarderClient for Arc<T> {
+    async fn post_telemetry(
+        &self,
+        target_url: Url,
+        headers: HeaderMap,
+        payload: Bytes,
+        timeout: Duration,
+    ) -> Result<HttpForwarderResponse> {
+        (**self)
+            .post_telemetry(target_url, headers, payload, timeout)
+            .await
+    }
+}
+
+/// Outcome of an export that reached the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportOutcome {
+    /// The server accepted the whole batch
+    Accepted,
+    /// The server accepted the request but rejected some of its spans, log records or data points
+    PartiallyRejected { rejected_items: u64 },
+    /// The server asked to slow down with `429 Too Many Requests` or `503 Service Unavailable`
+    Throttled { retry_after: Option<Duration> },
+}
+
+impl ExportOutcome {
+    /// Classifies a response, returning `None` for failures that are not throttling
+    pub fn from_response(response: &HttpForwarderResponse) -> Option<Self> {
+        let status = response.status();
+        if status.is_success() {
+            Some(match response.rejected_items() {
+                0 => Self::Accepted,
+                rejected_items => Self::PartiallyRejected { rejected_items },
+            })
+        } else if is_throttling_status(status) {
+            Some(Self::Throttled {
+                retry_after: response.retry_after(),
+            })
         } else {
-            read_error_body_if_needed(status, response.text()).await
-        };
-        Ok(HttpForwarderResponse::new(status, body))
+            None
+        }
+    }
+
+    /// Returns the number of items rejected by the server
+    pub fn rejected_items(&self) -> u64 {
+        match self {
+            Self::PartiallyRejected { rejected_items } => *rejected_items,
+            _ => 0,
+        }
+    }
+
+    /// Combines the outcomes of several batches sent to the same destination
+    pub(crate) fn merge(self, other: Self) -> Self {
+        match self.rejected_items() + other.rejected_items() {
+            0 => Self::Accepted,
+            rejected_items => Self::PartiallyRejected { rejected_items },
+        }
+    }
+}
+
+/// Error returned when a batch is still throttled after the last attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThrottledError {
+    /// HTTP status of the last response
+    pub status: u16,
+    /// Delay requested by the server through `Retry-After`, if any
+    pub retry_after: Option<Duration>,
+}
+
+impl fmt::Display for ThrottledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "OTLP export failed with status {}", self.status)
     }
 }
 
+impl std::error::Error for ThrottledError {}
+
+/// Returns true for the HTTP statuses servers use to ask clients to slow down
+fn is_throttling_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE
+}
+
 /// A convenience type alias for Arc\<dyn HttpOtlpForwarderClient\>
 pub type HttpClient = Arc<dyn HttpOtlpForwarderClient + Send + Sync>;
 
 /// Sends a batch of OTLP telemetry data.
 /// The TelemetryData payload is assumed to be a compacted, possibly compressed, OTLP protobuf batch.
+///
+/// Transient failures are retried according to [`RetryConfig::from_env`].
+pub async fn send_telemetry_batch(
+    client: &impl HttpOtlpForwarderClient,
+    telemetry_data: TelemetryData,
+) -> Result<ExportOutcome> {
+    send_telemetry_batch_with_retry(client, telemetry_data, &RetryConfig::from_env()).await
+}
+
+/// Sends a batch of OTLP telemetry data, retrying transient failures with the given policy.
+///
+/// Transport errors, `5xx` responses and `429 Too Many Requests` are retried with exponential
+/// backoff (honoring `Retry-After` on `429` and `503`) until the attempts or the retry budget
+/// run out. Other statuses, and [`CircuitOpenError`]s from a [`CircuitBreaker`](crate::CircuitBreaker)
+/// client, fail immediately. A batch still throttled after the last attempt fails with a
+/// [`ThrottledError`].
+///
+/// Returns [`ExportOutcome::PartiallyRejected`] when the server accepted the request but reported
+/// rejected items in its `partial_success` response.
+pub async fn send_telemetry_batch_with_retry(
+    client: &impl HttpOtlpForwarderClient,
+    telemetry_data: TelemetryData,
+    retry_config: &RetryConfig,
+) -> Result<ExportOutcome> {
+    let destination = Destination::from_env()?;
+    send_telemetry_batch_to(client, telemetry_data, &destination, retry_config).await
+}
+
+/// Sends a batch of OTLP telemetry data to an explicit [`Destination`], retrying transient
+/// failures with the given policy.
+pub async fn send_telemetry_batch_to(
+    client: &impl HttpOtlpForwarderClient,
+    telemetry_data: TelemetryData,
+    destination: &Destination,
+    retry_config: &RetryConfig,
+) -> Result<ExportOutcome> {
+    send_payload(
+        client,
+        Bytes::from(telemetry_data.payload),
+        &telemetry_data.content_type,
+        telemetry_data.content_encoding.as_deref(),
+        telemetry_data.signal,
+        destination,
+        retry_config,
+    )
+    .await
+}
+
+/// Sends a compacted batch to the destination of `config`, retried according to its retry policy
+///
+/// Unlike [`send_telemetry_batch`], the configuration is resolved once, typically at startup,
+/// instead of reading the environment on every send.
+pub async fn send_telemetry_batch_with_config(
+    client: &impl HttpOtlpForwarderClient,
+    telemetry_data: TelemetryData,
+    config: &ForwarderConfig,
+) -> Result<ExportOutcome> {
+    send_telemetry_batch_to(client, telemetry_data, &config.destination, &config.retry).await
+}
+
+/// Results of the payloads sent by [`send_all`], in the order of the payloads
+#[derive(Debug, Default)]
+pub struct SendAllReport {
+    pub results: Vec<Result<ExportOutcome>>,
+}
+
+impl SendAllReport {
+    /// Returns the number of payloads accepted by the destination
+    pub fn succeeded_count(&self) -> usize {
+        self.results.iter().filter(|result| result.is_ok()).count()
+    }
+
+    /// Returns the number of payloads that could not be sent
+    pub fn failed_count(&self) -> usize {
+        self.results.len() - self.succeeded_count()
+    }
+
+    /// Returns true if every payload was accepted
+    pub fn all_succeeded(&self) -> bool {
+        self.results.iter().all(Result::is_ok)
+    }
+
+    /// Returns the combined outcome of all payloads, or the first error if any payload failed
+    pub fn into_result(self) -> Result<ExportOutcome> {
+        let total = self.results.len();
+        let failed = self.failed_count();
+        let mut combined = ExportOutcome::Accepted;
+        for result in self.results {
+            match result {
+                Ok(outcome) => combined = combined.merge(outcome),
+                Err(e) => {
+                    return Err(e.context(format!(
+                        "{failed} of {total} telemetry payloads failed to send"
+                    )))
+                }
+            }
+        }
+        Ok(combined)
+    }
+}
+
+/// Sends several payloads to a destination, up to `concurrency` at a time, each with its own
+/// retries
+///
+/// A failing payload does not stop the others; check the returned [`SendAllReport`] for the
+/// result of each payload. A `concurrency` of `0` is treated as `1`.
+pub async fn send_all(
+    client: &impl HttpOtlpForwarderClient,
+    payloads: Vec<TelemetryData>,
+    destination: &Destination,
+    retry_config: &RetryConfig,
+    concurrency: usize,
+) -> SendAllReport {
+    let results = stream::iter(payloads)
+        .map(|telemetry_data| {
+            send_telemetry_batch_to(client, telemetry_data, destination, retry_config)
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+    SendAllReport { results }
+}
+
+/// Sends an encoded payload to a destination. The payload is taken as [`Bytes`] so that
+/// fan-out to several destinations shares a single buffer.
 #[instrument(
     name = "http_sender/send_telemetry_batch",
     skip_all,
@@ -326,29 +523,37 @@ pub type HttpClient = Arc<dyn HttpOtlpForwarderClient + Send + Sync>;
         otel.status_code,
         error,
         error.kind,
+        otlp.destination = %destination.name(),
+        otlp.signal = %signal,
+        otlp.attempts,
         otlp.headers.count,
         otlp.payload.size_bytes,
         otlp.timeout_ms,
-        otlp.request_content_type = %telemetry_data.content_type,
-        otlp.request_content_encoding = %telemetry_data.content_encoding.as_deref().unwrap_or("none"),
+        otlp.request_content_type = %content_type,
+        otlp.request_content_encoding = %content_encoding.unwrap_or("none"),
+        otlp.rejected_items,
         otlp.response_error_body_present,
         otlp.response_error_body_size_bytes
     )
 )]
-pub async fn send_telemetry_batch(
+pub(crate) async fn send_payload(
     client: &impl HttpOtlpForwarderClient,
-    telemetry_data: TelemetryData,
-) -> Result<()> {
-    let resolved_target_url = resolve_otlp_endpoint()?;
-    let timeout = resolve_otlp_timeout();
-
-    let mut headers = resolve_otlp_headers()?;
+    payload_bytes: Bytes,
+    content_type: &str,
+    content_encoding: Option<&str>,
+    signal: Signal,
+    destination: &Destination,
+    retry_config: &RetryConfig,
+) -> Result<ExportOutcome> {
+    let resolved_target_url = destination.endpoint_for(signal)?;
+    let timeout = destination.timeout();
+
+    let mut headers = destination.headers().clone();
     headers.insert(
         CONTENT_TYPE,
-        HeaderValue::from_str(&telemetry_data.content_type)
-            .context("Invalid Content-Type in TelemetryData")?,
+        HeaderValue::from_str(content_type).context("Invalid Content-Type in TelemetryData")?,
     );
-    if let Some(encoding) = &telemetry_data.content_encoding {
+    if let Some(encoding) = content_encoding {
         headers.insert(
             CONTENT_ENCODING,
             HeaderValue::from_str(encoding).context("Invalid Content-Encoding in TelemetryData")?,
@@ -357,7 +562,6 @@ pub async fn send_telemetry_batch(
         headers.remove(CONTENT_ENCODING);
     }
 
-    let payload_bytes = Bytes::from(telemetry_data.payload); // Convert Vec<u8> to Bytes
     Span::current().record("otlp.timeout_ms", timeout.as_millis() as u64);
     Span::current().record("otlp.headers.count", headers.len() as u64);
     Span::current().record("otlp.payload.size_bytes", payload_bytes.len() as u64);
@@ -366,65 +570,306 @@ pub async fn send_telemetry_batch(
         timeout_ms = timeout.as_millis() as u64,
         header_count = headers.len() as u64,
         payload_size_bytes = payload_bytes.len() as u64,
+        max_attempts = retry_config.max_attempts,
         "Sending telemetry batch"
     );
 
-    let response = match client
-        .post_telemetry(resolved_target_url.clone(), headers, payload_bytes, timeout)
-        .await
-    {
-        Ok(resp) => resp,
-        Err(_) => {
-            Span::current().record("otel.status_code", "ERROR");
-            Span::current().record("error", true);
-            Span::current().record("error.kind", "transport");
-            warn!("OTLP HTTP post_telemetry failed");
-            return Err(anyhow::anyhow!("OTLP export request failed"));
-        }
-    };
+    let started_at = Instant::now();
+    let mut attempt: u32 = 0;
+    loop {
+        attempt += 1;
+        Span::current().record("otlp.attempts", attempt);
+
+        let (error_kind, retry_after, failure) = match client
+            .post_telemetry(
+                resolved_target_url.clone(),
+                headers.clone(),
+                payload_bytes.clone(),
+                timeout,
+            )
+            .await
+        {
+            Ok(response) if response.status().is_success() => {
+                let status = response.status();
+                Span::current().record("http.status_code", status.as_u16());
+                Span::current().record("otel.status_code", "OK");
+                Span::current().record("error", false);
+                let rejected_items = response.rejected_items();
+                Span::current().record("otlp.rejected_items", rejected_items);
+                if rejected_items > 0 {
+                    warn!(
+                        status = status.as_u16(),
+                        attempt, rejected_items, "OTLP export partially rejected"
+                    );
+                    return Ok(ExportOutcome::PartiallyRejected { rejected_items });
+                }
+                debug!(
+                    status = status.as_u16(),
+                    attempt, "Telemetry batch sent successfully"
+                );
+                return Ok(ExportOutcome::Accepted);
+            }
+            Ok(response) => {
+                let status = response.status();
+                let retry_after = response.retry_after();
+                Span::current().record("http.status_code", status.as_u16());
+                let error_body = response.into_body();
+                Span::current().record("otlp.response_error_body_present", !error_body.is_empty());
+                Span::current().record(
+                    "otlp.response_error_body_size_bytes",
+                    error_body.len() as u64,
+                );
+                warn!(
+                    status = status.as_u16(),
+                    attempt,
+                    response_error_body_present = !error_body.is_empty(),
+                    response_error_body_size_bytes = error_body.len() as u64,
+                    "OTLP export failed with non-success status"
+                );
+                if is_throttling_status(status) {
+                    let throttled = ThrottledError {
+                        status: status.as_u16(),
+                        retry_after,
+                    };
+                    ("throttled", retry_after, anyhow::Error::new(throttled))
+                } else {
+                    let failure =
+                        anyhow::anyhow!("OTLP export failed with status {}", status.as_u16());
+                    if !is_retryable_status(status) {
+                        record_export_failure("non_success_status");
+                        return Err(failure);
+                    }
+                    ("non_success_status", None, failure)
+                }
+            }
+            Err(e) if e.is::<CircuitOpenError>() => {
+                // The endpoint is known to be failing; retrying would only add latency
+                debug!(attempt, "OTLP export skipped by open circuit breaker");
+                record_export_failure("circuit_open");
+                return Err(e);
+            }
+            Err(_) => {
+                warn!(attempt, "OTLP HTTP post_telemetry failed");
+                (
+                    "transport",
+                    None,
+                    anyhow::anyhow!("OTLP export request failed"),
+                )
+            }
+        };
 
-    let status = response.status();
-    Span::current().record("http.status_code", status.as_u16());
-
-    if !status.is_success() {
-        Span::current().record("otel.status_code", "ERROR");
-        Span::current().record("error", true);
-        Span::current().record("error.kind", "non_success_status");
-        let error_body = response.into_body();
-        Span::current().record("otlp.response_error_body_present", !error_body.is_empty());
-        Span::current().record(
-            "otlp.response_error_body_size_bytes",
-            error_body.len() as u64,
-        );
-        warn!(
-            status = status.as_u16(),
-            response_error_body_present = !error_body.is_empty(),
-            response_error_body_size_bytes = error_body.len() as u64,
-            "OTLP export failed with non-success status"
-        );
-        return Err(anyhow::anyhow!(
-            "OTLP export failed with status {}",
-            status.as_u16()
-        ));
+        match retry_config.next_delay(attempt, retry_after, started_at.elapsed()) {
+            Some(delay) => {
+                debug!(
+                    attempt,
+                    max_attempts = retry_config.max_attempts,
+                    delay_ms = delay.as_millis() as u64,
+                    error.kind = error_kind,
+                    "Retrying OTLP export after transient failure"
+                );
+                tokio::time::sleep(delay).await;
+            }
+            None => {
+                record_export_failure(error_kind);
+                return Err(failure);
+            }
+        }
     }
+}
 
-    Span::current().record("otel.status_code", "OK");
-    Span::current().record("error", false);
-    debug!(
-        status = status.as_u16(),
-        "Telemetry batch sent successfully"
-    );
-    Ok(())
+fn record_export_failure(error_kind: &'static str) {
+    Span::current().record("otel.status_code", "ERROR");
+    Span::current().record("error", true);
+    Span::current().record("error.kind", error_kind);
 }
 
 #[cfg(feature = "instrumented-client")]
 pub mod instrumented {
+    //! Each request of an [`InstrumentedHttpClient`] runs in an `http_sender/instrumented_post`
+    //! span, parent of the span of the tracing middleware, recording:
+    //!
+    //! - `http.request.body.size` and `http.response.body.size` (from `Content-Length`)
+    //! - `otlp.payload.uncompressed_size_bytes` and `otlp.compression_ratio` (compressed over
+    //!   uncompressed size), for gzip payloads
+    //! - `network.dns.duration_ms` and `network.connect.duration_ms`, when the request opened a
+    //!   new connection. The connect duration includes the DNS resolution and TLS handshake.
+    //!
+    //! Network timings are only measured by the clients of the
+    //! [`client_builder`](super::client_builder) functions. Clients sending concurrently over
+    //! several new connections may attribute the timings of one connection to another request.
     use super::*;
+    use futures::future::BoxFuture;
     use reqwest_middleware::ClientWithMiddleware;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::task::{Context as TaskContext, Poll};
+    use tracing::{field, Instrument};
+
+    /// Hook adding custom attributes to the span of each request, such as through
+    /// `tracing_opentelemetry::OpenTelemetrySpanExt::set_attribute`
+    pub type AttributeHook = Arc<dyn Fn(&Span, &RequestMetrics) + Send + Sync>;
+
+    /// Sizes and timings of a request sent by an [`InstrumentedHttpClient`]
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct RequestMetrics {
+        /// Size of the request body, as sent
+        pub request_body_size: u64,
+        /// Size of the request body before compression, for gzip payloads
+        pub uncompressed_body_size: Option<u64>,
+        /// Size of the response body, from its `Content-Length`
+        pub response_body_size: Option<u64>,
+        /// Status of the response, unless the request failed
+        pub status: Option<StatusCode>,
+        /// Duration of the DNS resolution of the new connection opened for the request
+        pub dns_duration: Option<Duration>,
+        /// Duration of the new connection opened for the request
+        pub connect_duration: Option<Duration>,
+        /// Duration of the request, until the response headers were received
+        pub duration: Duration,
+    }
+
+    impl RequestMetrics {
+        /// Returns the size of the request body over its uncompressed size, for gzip payloads
+        pub fn compression_ratio(&self) -> Option<f64> {
+            self.uncompressed_body_size
+                .filter(|size| *size > 0)
+                .map(|size| self.request_body_size as f64 / size as f64)
+        }
+    }
+
+    /// Returns the uncompressed size recorded in the trailer of a gzip payload
+    ///
+    /// The trailer holds the size modulo 2^32, which is exact for any payload a forwarder sends.
+    fn gzip_uncompressed_size(payload: &[u8]) -> Option<u64> {
+        let trailer: [u8; 4] = payload
+            .get(payload.len().checked_sub(4)?..)?
+            .try_into()
+            .ok()?;
+        Some(u64::from(u32::from_le_bytes(trailer)))
+    }
+
+    /// Durations of the last DNS resolution and connection of a client
+    #[derive(Debug, Default)]
+    pub(crate) struct NetworkTimings {
+        connections: AtomicU64,
+        dns_micros: AtomicU64,
+        connect_micros: AtomicU64,
+    }
+
+    impl NetworkTimings {
+        fn connections(&self) -> u64 {
+            self.connections.load(Ordering::Acquire)
+        }
+
+        fn record_dns(&self, duration: Duration) {
+            self.dns_micros
+                .store(duration.as_micros() as u64, Ordering::Release);
+        }
+
+        fn record_connect(&self, duration: Duration) {
+            self.connect_micros
+                .store(duration.as_micros() as u64, Ordering::Release);
+            self.connections.fetch_add(1, Ordering::AcqRel);
+        }
+
+        /// Returns the DNS and connect durations of the connections opened since `connections`
+        fn since(&self, connections: u64) -> (Option<Duration>, Option<Duration>) {
+            if self.connections() == connections {
+                return (None, None);
+            }
+            let dns = self.dns_micros.swap(0, Ordering::AcqRel);
+            let connect = self.connect_micros.load(Ordering::Acquire);
+            (
+                (dns > 0).then(|| Duration::from_micros(dns)),
+                Some(Duration::from_micros(connect)),
+            )
+        }
+
+        /// Installs the timing resolver and connector layer on a reqwest 0.13 client builder
+        pub(crate) fn apply(
+            self: &Arc<Self>,
+            builder: reqwest13::ClientBuilder,
+        ) -> reqwest13::ClientBuilder {
+            builder
+                .dns_resolver(Arc::new(TimingResolver(self.clone())))
+                .connector_layer(ConnectTimingLayer(self.clone()))
+        }
+    }
+
+    /// Resolves names with the system resolver, recording the duration of each resolution
+    struct TimingResolver(Arc<NetworkTimings>);
+
+    impl reqwest13::dns::Resolve for TimingResolver {
+        fn resolve(&self, name: reqwest13::dns::Name) -> reqwest13::dns::Resolving {
+            let timings = self.0.clone();
+            Box::pin(async move {
+                let started_at = Instant::now();
+                let addrs: Vec<SocketAddr> =
+                    tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+                timings.record_dns(started_at.elapsed());
+                let addrs: Box<dyn Iterator<Item = SocketAddr> + Send> =
+                    Box::new(addrs.into_iter());
+                Ok(addrs)
+            })
+        }
+    }
+
+    /// Connector layer recording the duration of each new connection
+    #[derive(Clone)]
+    struct ConnectTimingLayer(Arc<NetworkTimings>);
+
+    impl<S> tower::Layer<S> for ConnectTimingLayer {
+        type Service = ConnectTiming<S>;
+
+        fn layer(&self, inner: S) -> Self::Service {
+            ConnectTiming {
+                inner,
+                timings: self.0.clone(),
+            }
+        }
+    }
+
+    #[derive(Clone)]
+    struct ConnectTiming<S> {
+        inner: S,
+        timings: Arc<NetworkTimings>,
+    }
+
+    impl<S, R> tower::Service<R> for ConnectTiming<S>
+    where
+        S: tower::Service<R>,
+        S::Future: Send + 'static,
+    {
+        type Response = S::Response;
+        type Error = S::Error;
+        type Future = BoxFuture<'static, std::result::Result<S::Response, S::Error>>;
+
+        fn poll_ready(
+            &mut self,
+            cx: &mut TaskContext<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            self.inner.poll_ready(cx)
+        }
+
+        fn call(&mut self, request: R) -> Self::Future {
+            let started_at = Instant::now();
+            let connecting = self.inner.call(request);
+            let timings = self.timings.clone();
+            Box::pin(async move {
+                let connection = connecting.await;
+                if connection.is_ok() {
+                    timings.record_connect(started_at.elapsed());
+                }
+                connection
+            })
+        }
+    }
 
     /// A pre-configured HTTP client that wraps ClientWithMiddleware and implements HttpOtlpForwarderClient
     pub struct InstrumentedHttpClient {
         inner: ClientWithMiddleware,
+        timings: Option<Arc<NetworkTimings>>,
+        attribute_hook: Option<AttributeHook>,
     }
 
     impl InstrumentedHttpClient {
@@ -445,7 +890,52 @@ pub mod instrumented {
         /// let instrumented_client = InstrumentedHttpClient::new(middleware_client);
         /// ```
         pub fn new(client: ClientWithMiddleware) -> Self {
-            Self { inner: client }
+            Self {
+                inner: client,
+                timings: None,
+                attribute_hook: None,
+            }
+        }
+
+        /// Sets a hook called with the span and metrics of each request, once its response
+        /// headers are received or it failed
+        pub fn with_attribute_hook(
+            mut self,
+            hook: impl Fn(&Span, &RequestMetrics) + Send + Sync + 'static,
+        ) -> Self {
+            self.attribute_hook = Some(Arc::new(hook));
+            self
+        }
+
+        /// Records the network timings measured by the resolver and connector of `timings`
+        pub(crate) fn with_network_timings(mut self, timings: Arc<NetworkTimings>) -> Self {
+            self.timings = Some(timings);
+            self
+        }
+
+        fn record(&self, span: &Span, metrics: &RequestMetrics) {
+            span.record("http.request.body.size", metrics.request_body_size);
+            if let Some(size) = metrics.uncompressed_body_size {
+                span.record("otlp.payload.uncompressed_size_bytes", size);
+            }
+            if let Some(ratio) = metrics.compression_ratio() {
+                span.record("otlp.compression_ratio", ratio);
+            }
+            if let Some(size) = metrics.response_body_size {
+                span.record("http.response.body.size", size);
+            }
+            if let Some(duration) = metrics.dns_duration {
+                span.record("network.dns.duration_ms", duration.as_secs_f64() * 1000.0);
+            }
+            if let Some(duration) = metrics.connect_duration {
+                span.record(
+                    "network.connect.duration_ms",
+                    duration.as_secs_f64() * 1000.0,
+                );
+            }
+            if let Some(hook) = &self.attribute_hook {
+                hook(span, metrics);
+            }
         }
     }
 
@@ -458,24 +948,56 @@ pub mod instrumented {
             payload: Bytes,
             timeout: Duration,
         ) -> Result<HttpForwarderResponse> {
-            let response = self
+            let span = tracing::info_span!(
+                "http_sender/instrumented_post",
+                otel.kind = "internal",
+                http.request.body.size = field::Empty,
+                http.response.body.size = field::Empty,
+                otlp.payload.uncompressed_size_bytes = field::Empty,
+                otlp.compression_ratio = field::Empty,
+                network.dns.duration_ms = field::Empty,
+                network.connect.duration_ms = field::Empty,
+            );
+            let gzip = headers
+                .get(CONTENT_ENCODING)
+                .is_some_and(|value| value.as_bytes().eq_ignore_ascii_case(b"gzip"));
+            let mut metrics = RequestMetrics {
+                request_body_size: payload.len() as u64,
+                uncompressed_body_size: gzip.then(|| gzip_uncompressed_size(&payload)).flatten(),
+                ..Default::default()
+            };
+            let connections = self.timings.as_ref().map(|timings| timings.connections());
+            let started_at = Instant::now();
+            let sent = self
                 .inner
                 .post(target_url)
                 .headers(headers)
                 .body(payload)
                 .timeout(timeout)
                 .send()
-                .await
-                .context("HTTP request failed during instrumented OTLP export")?;
+                .instrument(span.clone())
+                .await;
+            metrics.duration = started_at.elapsed();
+            if let (Some(timings), Some(connections)) = (&self.timings, connections) {
+                (metrics.dns_duration, metrics.connect_duration) = timings.since(connections);
+            }
+            if let Ok(response) = &sent {
+                metrics.status = Some(response.status());
+                metrics.response_body_size = response.content_length();
+            }
+            self.record(&span, &metrics);
+            let response = sent.context("HTTP request failed during instrumented OTLP export")?;
 
             let status = response.status();
-            let body = if status.is_success() {
-                drain_success_body(status, async move { response.bytes().await.map(|_| ()) }).await;
-                String::new()
-            } else {
-                read_error_body_if_needed(status, response.text()).await
-            };
-            Ok(HttpForwarderResponse::new(status, body))
+            let retry_after = parse_retry_after(response.headers());
+            if status.is_success() {
+                let json = is_json_response(response.headers());
+                let rejected_items = read_rejected_items(status, json, response.bytes()).await;
+                return Ok(HttpForwarderResponse::new(status, String::new())
+                    .with_rejected_items(rejected_items));
+            }
+            let body = read_error_body_if_needed(status, response.text()).await;
+            Ok(HttpForwarderResponse::new(status, body).with_retry_after(retry_after))
         }
     }
 }
@@ -483,50 +1005,153 @@ pub mod instrumented {
 /// Utility functions for creating HTTP clients with common configurations
 pub mod client_builder {
     use super::*;
+    use crate::connection::ConnectionConfig;
 
     /// Creates a simple ReqwestClient that implements HttpOtlpForwarderClient
     pub fn simple() -> ReqwestClient {
-        ReqwestClient::new()
+        ConnectionConfig::default()
+            .apply(ReqwestClient::builder())
+            .build()
+            .expect("Failed to build HTTP client")
     }
 
     /// Creates a ReqwestClient with custom timeout
     pub fn with_timeout(timeout: Duration) -> ReqwestClient {
-        ReqwestClient::builder()
+        ConnectionConfig::default()
+            .apply(ReqwestClient::builder())
             .timeout(timeout)
             .build()
             .expect("Failed to build HTTP client")
     }
 
+    /// Creates a ReqwestClient presenting a client identity and trusting additional certificate
+    /// authorities, for collectors requiring mutual TLS or using a private certificate authority
+    ///
+    /// See [`TlsConfig::new`] for the expected PEM contents.
+    pub fn with_tls(identity_pem: Option<&[u8]>, ca_pem: Option<&[u8]>) -> Result<ReqwestClient> {
+        with_tls_config(&TlsConfig::new(identity_pem, ca_pem))
+    }
+
+    /// Creates a ReqwestClient with the given TLS settings, such as [`TlsConfig::from_env`]
+    pub fn with_tls_config(tls_config: &TlsConfig) -> Result<ReqwestClient> {
+        with_options(&ConnectionConfig::default(), tls_config)
+    }
+
+    /// Creates a ReqwestClient with the given connection pooling, keep-alive and TLS settings
+    pub fn with_options(
+        connection: &ConnectionConfig,
+        tls_config: &TlsConfig,
+    ) -> Result<ReqwestClient> {
+        tls_config
+            .apply(connection.apply(ReqwestClient::builder()))?
+            .build()
+            .context("Failed to build HTTP client")
+    }
+
+    /// Creates a client for the protocol and connection settings of `config`
+    ///
+    /// The client applies the TLS settings of the environment (see [`TlsConfig::from_env`]).
+    /// `grpc` requires the `grpc` feature.
+    pub fn for_config(config: &ForwarderConfig) -> Result<HttpClient> {
+        build(config.protocol, &config.connection)
+    }
+
+    /// Creates a client for the given transport protocol, such as [`ForwarderConfig::protocol`]
+    ///
+    /// The client applies the connection and TLS settings of the environment (see
+    /// [`ConnectionConfig::from_env`] and [`TlsConfig::from_env`]). `grpc` requires the `grpc`
+    /// feature.
+    pub fn for_protocol(protocol: OtlpProtocol) -> Result<HttpClient> {
+        build(protocol, &ConnectionConfig::from_env())
+    }
+
+    fn build(protocol: OtlpProtocol, connection: &ConnectionConfig) -> Result<HttpClient> {
+        let tls_config = TlsConfig::from_env()?;
+        match protocol {
+            OtlpProtocol::HttpProtobuf => Ok(Arc::new(with_options(connection, &tls_config)?)),
+            #[cfg(feature = "grpc")]
+            OtlpProtocol::Grpc => Ok(Arc::new(crate::GrpcClient::with_tls(tls_config))),
+            #[cfg(not(feature = "grpc"))]
+            OtlpProtocol::Grpc => Err(anyhow::anyhow!(
+                "OTLP/gRPC requires the `grpc` feature of serverless-otlp-forwarder-core"
+            )),
+        }
+    }
+
     #[cfg(feature = "instrumented-client")]
     /// Creates an instrumented client with tracing middleware
     pub fn instrumented() -> crate::InstrumentedHttpClient {
+        use super::instrumented::NetworkTimings;
         use reqwest_middleware::ClientBuilder;
         use reqwest_tracing::TracingMiddleware;
 
-        let base_client = reqwest13::Client::new();
+        let timings = Arc::new(NetworkTimings::default());
+        let base_client = timings
+            .apply(reqwest13::Client::builder())
+            .build()
+            .expect("Failed to build HTTP client");
+        let middleware_client = ClientBuilder::new(base_client)
+            .with(TracingMiddleware::default())
+            .build();
+        crate::InstrumentedHttpClient::new(middleware_client).with_network_timings(timings)
+    }
+
+    #[cfg(feature = "instrumented-client")]
+    /// Creates an instrumented client with tracing middleware and the given TLS settings
+    pub fn instrumented_with_tls(tls_config: &TlsConfig) -> Result<crate::InstrumentedHttpClient> {
+        instrumented_with_options(&ConnectionConfig::default(), tls_config)
+    }
+
+    #[cfg(feature = "instrumented-client")]
+    /// Creates an instrumented client with tracing middleware and the given connection pooling,
+    /// keep-alive and TLS settings
+    pub fn instrumented_with_options(
+        connection: &ConnectionConfig,
+        tls_config: &TlsConfig,
+    ) -> Result<crate::InstrumentedHttpClient> {
+        use super::instrumented::NetworkTimings;
+        use reqwest_middleware::ClientBuilder;
+        use reqwest_tracing::TracingMiddleware;
+
+        let timings = Arc::new(NetworkTimings::default());
+        let base_client = tls_config
+            .apply_to_instrumented(
+                connection.apply_to_instrumented(timings.apply(reqwest13::Client::builder())),
+            )?
+            .build()
+            .context("Failed to build HTTP client")?;
         let middleware_client = ClientBuilder::new(base_client)
             .with(TracingMiddleware::default())
             .build();
-        crate::InstrumentedHttpClient::new(middleware_client)
+        Ok(crate::InstrumentedHttpClient::new(middleware_client).with_network_timings(timings))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::span_compactor::CompressionPreference;
     use crate::telemetry::TelemetryData;
     use crate::tracing_capture::EventCaptureLayer;
     use anyhow::anyhow;
+    use opentelemetry_proto::tonic::collector::trace::v1::ExportTracePartialSuccess;
     use reqwest::Client as ReqwestClient;
     use sealed_test::prelude::*;
     use serial_test::serial;
-    use std::sync::atomic::{AtomicBool, Ordering};
-    use std::sync::Arc;
+    use std::env;
     use std::time::Duration as StdDuration;
     use tracing_subscriber::{prelude::*, registry::Registry};
     use wiremock::matchers::{body_bytes, header, method, path};
     use wiremock::{Match, Mock, MockServer, Request, ResponseTemplate};
 
+    fn resolve_otlp_headers() -> Result<HeaderMap> {
+        Ok(ForwarderSettings::headers_from_env().otlp_headers())
+    }
+
+    fn resolve_otlp_endpoint() -> Result<Url> {
+        ForwarderSettings::endpoint_from_env()?.otlp_endpoint()
+    }
+
     // Helper struct to ensure env vars are cleaned up.
     struct EnvVarGuard {
         name: String,
@@ -618,25 +1243,46 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_drain_success_body_executes_future() {
-        let drained = Arc::new(AtomicBool::new(false));
-        let drained_clone = Arc::clone(&drained);
+    async fn test_read_rejected_items_parses_partial_success() {
+        let response = ExportTraceServiceResponse {
+            partial_success: Some(ExportTracePartialSuccess {
+                rejected_spans: 3,
+                error_message: "collector-secret-detail".to_string(),
+            }),
+        };
+        let body = Bytes::from(response.encode_to_vec());
 
-        drain_success_body(StatusCode::OK, async move {
-            drained_clone.store(true, Ordering::SeqCst);
-            Ok::<(), anyhow::Error>(())
+        let rejected = read_rejected_items(StatusCode::OK, false, async move {
+            Ok::<_, anyhow::Error>(body)
         })
         .await;
 
-        assert!(drained.load(Ordering::SeqCst));
+        assert_eq!(rejected, 3);
     }
 
     #[tokio::test]
-    async fn test_drain_success_body_ignores_read_errors() {
-        drain_success_body(StatusCode::OK, async {
-            Err::<(), anyhow::Error>(anyhow!("connection closed"))
+    async fn test_read_rejected_items_ignores_read_errors() {
+        let rejected = read_rejected_items(StatusCode::OK, false, async {
+            Err::<Bytes, anyhow::Error>(anyhow!("connection closed"))
         })
         .await;
+
+        assert_eq!(rejected, 0);
+    }
+
+    #[test]
+    fn test_parse_rejected_items() {
+        assert_eq!(parse_rejected_items(b"", false), Some(0));
+        assert_eq!(parse_rejected_items(b"{}", true), Some(0));
+        assert_eq!(
+            parse_rejected_items(br#"{"partialSuccess":{"rejectedLogRecords":"7"}}"#, true),
+            Some(7)
+        );
+        assert_eq!(
+            parse_rejected_items(br#"{"partialSuccess":{"rejectedDataPoints":2}}"#, true),
+            Some(2)
+        );
+        assert_eq!(parse_rejected_items(b"not json", true), None);
     }
 
     #[tokio::test]
@@ -887,6 +1533,7 @@ mod tests {
             &format!("{}{}", server.uri(), OTLP_TRACES_PATH),
         );
         let _g2 = EnvVarGuard::set("OTEL_EXPORTER_OTLP_TRACES_TIMEOUT", "100");
+        let _g3 = EnvVarGuard::set(crate::retry::MAX_ATTEMPTS_ENV_VAR, "1");
         let result = send_telemetry_batch(&client, telemetry).await;
         assert!(
             result.is_err(),
@@ -996,16 +1643,18 @@ mod tests {
         let server = MockServer::start().await;
         let client = test_client();
         let telemetry = TelemetryData::default();
+        // Server errors are retried up to the default number of attempts
         Mock::given(method("POST"))
             .and(path(OTLP_TRACES_PATH))
             .respond_with(ResponseTemplate::new(500).set_body_string("Internal Error"))
-            .expect(1)
+            .expect(3)
             .mount(&server)
             .await;
         let _g1 = EnvVarGuard::set(
             "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
             &format!("{}{}", server.uri(), OTLP_TRACES_PATH),
         );
+        let _g2 = EnvVarGuard::set(crate::retry::BASE_DELAY_ENV_VAR, "1");
         let result = send_telemetry_batch(&client, telemetry).await;
         assert!(result.is_err());
         let err_msg = result.unwrap_err().to_string();
@@ -1036,6 +1685,7 @@ mod tests {
             "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
             &format!("{}{}", server.uri(), OTLP_TRACES_PATH),
         );
+        let _g2 = EnvVarGuard::set(crate::retry::MAX_ATTEMPTS_ENV_VAR, "1");
 
         let result = send_telemetry_batch(&client, telemetry).await;
         assert!(result.is_err());
@@ -1071,4 +1721,355 @@ mod tests {
             .values()
             .all(|value| !value.contains(&server.uri())));
     }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("3"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(3)));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+
+        let in_a_minute = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(60));
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&in_a_minute).unwrap());
+        let retry_after = parse_retry_after(&headers).unwrap();
+        assert!(retry_after > Duration::from_secs(55) && retry_after <= Duration::from_secs(60));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_send_telemetry_batch_retries_transient_failures() {
+        let server = MockServer::start().await;
+        let client = test_client();
+        Mock::given(method("POST"))
+            .and(path(OTLP_TRACES_PATH))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(OTLP_TRACES_PATH))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let _g1 = EnvVarGuard::set(
+            "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+            &format!("{}{}", server.uri(), OTLP_TRACES_PATH),
+        );
+
+        let retry_config = RetryConfig {
+            base_delay: Duration::from_millis(1),
+            ..RetryConfig::default()
+        };
+        let result =
+            send_telemetry_batch_with_retry(&client, TelemetryData::default(), &retry_config).await;
+        assert!(
+            result.is_ok(),
+            "expected retry to succeed: {:?}",
+            result.err()
+        );
+    }
+
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_send_telemetry_batch_honors_retry_after() {
+        let server = MockServer::start().await;
+        let client = test_client();
+        Mock::given(method("POST"))
+            .and(path(OTLP_TRACES_PATH))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(OTLP_TRACES_PATH))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let _g1 = EnvVarGuard::set(
+            "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+            &format!("{}{}", server.uri(), OTLP_TRACES_PATH),
+        );
+
+        let started_at = Instant::now();
+        let result = send_telemetry_batch_with_retry(
+            &client,
+            TelemetryData::default(),
+            &RetryConfig::default(),
+        )
+        .await;
+        assert!(result.is_ok());
+        assert!(started_at.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_send_telemetry_batch_reports_throttling() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(OTLP_TRACES_PATH))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "0"))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let destination = Destination::new(
+            "throttling",
+            Url::parse(&format!("{}{}", server.uri(), OTLP_TRACES_PATH)).unwrap(),
+        );
+        let retry_config = RetryConfig {
+            max_attempts: 2,
+            base_delay: Duration::from_secs(60),
+            ..RetryConfig::default()
+        };
+
+        // The zero Retry-After of the 503 replaces the backoff delay
+        let err = send_telemetry_batch_to(
+            &test_client(),
+            TelemetryData::default(),
+            &destination,
+            &retry_config,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ThrottledError>(),
+            Some(&ThrottledError {
+                status: 503,
+                retry_after: Some(Duration::ZERO),
+            })
+        );
+        assert_eq!(err.to_string(), "OTLP export failed with status 503");
+    }
+
+    #[tokio::test]
+    async fn test_send_telemetry_batch_reports_partial_success() {
+        let server = MockServer::start().await;
+        let response = ExportTraceServiceResponse {
+            partial_success: Some(ExportTracePartialSuccess {
+                rejected_spans: 2,
+                error_message: "span too large".to_string(),
+            }),
+        };
+        Mock::given(method("POST"))
+            .and(path(OTLP_TRACES_PATH))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw(response.encode_to_vec(), "application/x-protobuf"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let destination = Destination::new(
+            "partial",
+            Url::parse(&format!("{}{}", server.uri(), OTLP_TRACES_PATH)).unwrap(),
+        );
+
+        let outcome = send_telemetry_batch_to(
+            &test_client(),
+            TelemetryData::default(),
+            &destination,
+            &RetryConfig::disabled(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            outcome,
+            ExportOutcome::PartiallyRejected { rejected_items: 2 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_telemetry_batch_with_config_overrides() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(OTLP_TRACES_PATH))
+            .and(header("x-api-key", "secret"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", HeaderValue::from_static("secret"));
+        let config = ForwarderConfig::resolve(&ForwarderSettings::default())
+            .unwrap()
+            .with_endpoint(Url::parse(&format!("{}{}", server.uri(), OTLP_TRACES_PATH)).unwrap())
+            .with_headers(headers)
+            .with_timeout(Duration::from_secs(2))
+            .with_compression(CompressionPreference::Gzip);
+        assert_eq!(config.destination.timeout(), Duration::from_secs(2));
+        assert_eq!(config.compaction.compression, CompressionPreference::Gzip);
+
+        let outcome =
+            send_telemetry_batch_with_config(&test_client(), TelemetryData::default(), &config)
+                .await
+                .unwrap();
+        assert_eq!(outcome, ExportOutcome::Accepted);
+    }
+
+    #[tokio::test]
+    async fn test_send_all_aggregates_partial_failures() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(OTLP_TRACES_PATH))
+            .and(body_bytes(b"rejected".to_vec()))
+            .respond_with(ResponseTemplate::new(400))
+            .with_priority(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(OTLP_TRACES_PATH))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(3)
+            .mount(&server)
+            .await;
+        let destination = Destination::new(
+            "send-all",
+            Url::parse(&format!("{}{}", server.uri(), OTLP_TRACES_PATH)).unwrap(),
+        );
+        let payloads = ["first", "rejected", "third", "fourth"]
+            .into_iter()
+            .map(|payload| TelemetryData {
+                payload: payload.as_bytes().to_vec(),
+                ..TelemetryData::default()
+            })
+            .collect();
+
+        let report = send_all(
+            &test_client(),
+            payloads,
+            &destination,
+            &RetryConfig::disabled(),
+            2,
+        )
+        .await;
+        assert_eq!(report.succeeded_count(), 3);
+        assert_eq!(report.failed_count(), 1);
+        assert!(!report.all_succeeded());
+        // Results keep the order of the payloads
+        assert!(report.results[1].is_err());
+
+        let err = report.into_result().unwrap_err();
+        assert_eq!(err.to_string(), "1 of 4 telemetry payloads failed to send");
+    }
+
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_send_telemetry_batch_does_not_retry_client_errors() {
+        let server = MockServer::start().await;
+        let client = test_client();
+        Mock::given(method("POST"))
+            .and(path(OTLP_TRACES_PATH))
+            .respond_with(ResponseTemplate::new(400))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let _g1 = EnvVarGuard::set(
+            "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+            &format!("{}{}", server.uri(), OTLP_TRACES_PATH),
+        );
+
+        let result = send_telemetry_batch_with_retry(
+            &client,
+            TelemetryData::default(),
+            &RetryConfig::default(),
+        )
+        .await;
+        assert!(result.unwrap_err().to_string().contains("status 400"));
+    }
+
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_send_telemetry_batch_does_not_retry_open_circuit() {
+        use crate::{CircuitBreaker, CircuitBreakerConfig};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(OTLP_TRACES_PATH))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let _g1 = EnvVarGuard::set(
+            "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+            &format!("{}{}", server.uri(), OTLP_TRACES_PATH),
+        );
+
+        let client = CircuitBreaker::with_config(
+            test_client(),
+            CircuitBreakerConfig {
+                failure_threshold: 1,
+                cooldown: Duration::from_secs(60),
+            },
+        );
+        let retry_config = RetryConfig {
+            base_delay: Duration::from_millis(1),
+            ..RetryConfig::default()
+        };
+        // The first attempt opens the circuit; the retry is short-circuited and not retried
+        let result =
+            send_telemetry_batch_with_retry(&client, TelemetryData::default(), &retry_config).await;
+        assert!(result.unwrap_err().is::<CircuitOpenError>());
+    }
+
+    #[cfg(feature = "instrumented-client")]
+    #[tokio::test]
+    async fn test_instrumented_client_records_request_metrics() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+        use std::sync::Mutex;
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0; 7]))
+            .mount(&server)
+            .await;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[b'a'; 4096]).unwrap();
+        let payload = encoder.finish().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let hook_recorded = recorded.clone();
+        let client = client_builder::instrumented().with_attribute_hook(move |_, metrics| {
+            hook_recorded.lock().unwrap().push(metrics.clone())
+        });
+        let url = Url::parse(&format!("{}{}", server.uri(), OTLP_TRACES_PATH)).unwrap();
+        for _ in 0..2 {
+            client
+                .post_telemetry(
+                    url.clone(),
+                    headers.clone(),
+                    Bytes::from(payload.clone()),
+                    Duration::from_secs(5),
+                )
+                .await
+                .unwrap();
+        }
+
+        let recorded = recorded.lock().unwrap();
+        let first = &recorded[0];
+        assert_eq!(first.request_body_size, payload.len() as u64);
+        assert_eq!(first.uncompressed_body_size, Some(4096));
+        assert!(first.compression_ratio().unwrap() < 0.1);
+        assert_eq!(first.response_body_size, Some(7));
+        assert_eq!(first.status, Some(StatusCode::OK));
+        // The server is addressed by IP, so only the first request connects, without DNS
+        assert!(first.connect_duration.is_some());
+        assert_eq!(first.dns_duration, None);
+        assert_eq!(recorded[1].connect_duration, None);
+    }
 }
@@ -0,0 +1,339 @@
+//! Kinesis Data Streams event source.
+//!
+//! CloudWatch Logs subscription filters can deliver to a Kinesis data stream instead of invoking
+//! a Lambda function directly, which scales better for high-volume accounts. Each Kinesis record
+//! then carries a base64-encoded, gzip-compressed CloudWatch Logs envelope holding a batch of log
+//! events. Records written to the stream directly by a producer are treated as a single log record.
+//!
+//! [`KinesisCloudWatchLogsParser`] unwraps both kinds of records and dispatches the log records
+//! through a [`ParserRegistry`]. [`kinesis_batch_items`] unwraps them into [`BatchItem`]s instead,
+//! for [`process_batch_items`](crate::partial_batch::process_batch_items) to report the records
+//! that failed.
+
+use crate::core_parser::{EventParser, ParserRegistry};
+use crate::partial_batch::BatchItem;
+use crate::telemetry::TelemetryData;
+use anyhow::{Context, Result};
+use aws_lambda_events::event::cloudwatch_logs::LogData;
+use aws_lambda_events::event::kinesis::KinesisEvent;
+use flate2::read::GzDecoder;
+use std::io::Read;
+
+/// Message type of the envelope CloudWatch Logs writes to check that the destination is writable
+pub const CONTROL_MESSAGE_TYPE: &str = "CONTROL_MESSAGE";
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Returns true if `data` starts with the gzip magic bytes
+pub fn is_gzip(data: &[u8]) -> bool {
+    data.starts_with(&GZIP_MAGIC)
+}
+
+/// Decodes the gzip-compressed CloudWatch Logs envelope carried by a Kinesis record
+///
+/// The record data must already be base64-decoded, as `aws_lambda_events` does when deserializing
+/// a [`KinesisEvent`].
+pub fn decode_cloudwatch_logs_data(data: &[u8]) -> Result<LogData> {
+    let mut json = Vec::new();
+    GzDecoder::new(data)
+        .read_to_end(&mut json)
+        .context("Failed to decompress CloudWatch Logs data")?;
+    serde_json::from_slice(&json).context("Failed to parse CloudWatch Logs data")
+}
+
+/// Unwraps each record of a Kinesis event into a [`BatchItem`] identified by its sequence number
+///
+/// The log records of a CloudWatch Logs envelope have its log group as source, and a record
+/// written directly is a single log record with `stream_name` as source. Control messages yield
+/// an item without log records; records that cannot be decoded yield an undecodable item.
+pub fn kinesis_batch_items(event_payload: KinesisEvent, stream_name: &str) -> Vec<BatchItem> {
+    event_payload
+        .records
+        .into_iter()
+        .map(|record| {
+            let sequence_number = record.kinesis.sequence_number;
+            let data = record.kinesis.data.0;
+            if !is_gzip(&data) {
+                return match String::from_utf8(data) {
+                    Ok(message) => BatchItem::new(sequence_number, stream_name, vec![message]),
+                    Err(_) => BatchItem::undecodable(sequence_number),
+                };
+            }
+            match decode_cloudwatch_logs_data(&data) {
+                Ok(log_data) if log_data.message_type == CONTROL_MESSAGE_TYPE => {
+                    BatchItem::new(sequence_number, log_data.log_group, Vec::new())
+                }
+                Ok(log_data) => BatchItem::new(
+                    sequence_number,
+                    log_data.log_group,
+                    log_data
+                        .log_events
+                        .into_iter()
+                        .map(|log_event| log_event.message)
+                        .collect(),
+                ),
+                Err(_) => BatchItem::undecodable(sequence_number),
+            }
+        })
+        .collect()
+}
+
+/// Parses Kinesis events carrying CloudWatch Logs subscription data or raw log records
+///
+/// Log records are grouped by source, the log group of their CloudWatch Logs envelope or the
+/// stream name for records written directly, and parsed by the registry. Control messages and
+/// records that cannot be decoded are skipped.
+#[derive(Debug)]
+pub struct KinesisCloudWatchLogsParser {
+    registry: ParserRegistry,
+}
+
+impl KinesisCloudWatchLogsParser {
+    /// Creates a parser using [`ParserRegistry::with_defaults`]
+    pub fn new() -> Self {
+        Self::with_registry(ParserRegistry::with_defaults())
+    }
+
+    /// Creates a parser dispatching log records through `registry`
+    pub fn with_registry(registry: ParserRegistry) -> Self {
+        Self { registry }
+    }
+
+    /// Appends `messages` to the group of `source`, keeping sources in arrival order
+    fn group(groups: &mut Vec<(String, Vec<String>)>, source: &str, messages: Vec<String>) {
+        match groups.iter_mut().find(|(name, _)| name == source) {
+            Some((_, group)) => group.extend(messages),
+            None => groups.push((source.to_string(), messages)),
+        }
+    }
+}
+
+impl Default for KinesisCloudWatchLogsParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventParser for KinesisCloudWatchLogsParser {
+    type EventInput = KinesisEvent;
+
+    fn parse(&self, event_payload: KinesisEvent, stream_name: &str) -> Result<Vec<TelemetryData>> {
+        self.parse_counting_failures(event_payload, stream_name)
+            .map(|(telemetry_items, _)| telemetry_items)
+    }
+
+    /// Counts the Kinesis records that could not be decoded, and the log records rejected by
+    /// the registry
+    fn parse_counting_failures(
+        &self,
+        event_payload: KinesisEvent,
+        stream_name: &str,
+    ) -> Result<(Vec<TelemetryData>, usize)> {
+        let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+        let mut failed_count = 0;
+
+        for record in event_payload.records {
+            let data = record.kinesis.data.0;
+            if !is_gzip(&data) {
+                match String::from_utf8(data) {
+                    Ok(message) => Self::group(&mut groups, stream_name, vec![message]),
+                    Err(_) => {
+                        tracing::warn!(
+                            "Failed to decode Kinesis record data as UTF-8; skipping record"
+                        );
+                        failed_count += 1;
+                    }
+                }
+                continue;
+            }
+
+            let log_data = match decode_cloudwatch_logs_data(&data) {
+                Ok(log_data) => log_data,
+                Err(_) => {
+                    tracing::warn!(
+                        payload_size_bytes = data.len() as u64,
+                        "Failed to decode CloudWatch Logs data from Kinesis record; skipping record"
+                    );
+                    failed_count += 1;
+                    continue;
+                }
+            };
+            if log_data.message_type == CONTROL_MESSAGE_TYPE {
+                tracing::debug!("Skipping CloudWatch Logs control message");
+                continue;
+            }
+            let messages = log_data
+                .log_events
+                .into_iter()
+                .map(|log_event| log_event.message)
+                .collect();
+            Self::group(&mut groups, &log_data.log_group, messages);
+        }
+
+        let mut telemetry_items = Vec::new();
+        for (source, messages) in groups {
+            let (items, rejected_count) =
+                self.registry.parse_counting_failures(messages, &source)?;
+            telemetry_items.extend(items);
+            failed_count += rejected_count;
+        }
+        Ok((telemetry_items, failed_count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::otlp_stdout_record;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use flate2::{write::GzEncoder, Compression};
+    use serde_json::{json, Value};
+    use std::io::Write;
+
+    fn cloudwatch_logs_data(message_type: &str, log_group: &str, messages: &[String]) -> Vec<u8> {
+        let log_events: Vec<Value> = messages
+            .iter()
+            .enumerate()
+            .map(|(i, message)| json!({"id": i.to_string(), "timestamp": 1000, "message": message}))
+            .collect();
+        let envelope = json!({
+            "messageType": message_type,
+            "owner": "123456789012",
+            "logGroup": log_group,
+            "logStream": "stream1",
+            "subscriptionFilters": ["filter1"],
+            "logEvents": log_events
+        });
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(envelope.to_string().as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn kinesis_event(records: &[Vec<u8>]) -> KinesisEvent {
+        let records: Vec<Value> = records
+            .iter()
+            .enumerate()
+            .map(|(i, data)| {
+                json!({
+                    "eventSource": "aws:kinesis",
+                    "kinesis": {
+                        "approximateArrivalTimestamp": 1700000000.0,
+                        "data": STANDARD.encode(data),
+                        "partitionKey": "key",
+                        "sequenceNumber": format!("{}", i + 1)
+                    }
+                })
+            })
+            .collect();
+        serde_json::from_value(json!({ "Records": records })).unwrap()
+    }
+
+    #[test]
+    fn test_decode_cloudwatch_logs_data() {
+        let data = cloudwatch_logs_data("DATA_MESSAGE", "/aws/lambda/a", &["hello".to_string()]);
+        assert!(is_gzip(&data));
+
+        let log_data = decode_cloudwatch_logs_data(&data).unwrap();
+        assert_eq!(log_data.log_group, "/aws/lambda/a");
+        assert_eq!(log_data.log_events[0].message, "hello");
+        assert!(decode_cloudwatch_logs_data(b"not gzip").is_err());
+    }
+
+    #[test]
+    fn test_kinesis_parser_unwraps_records() {
+        let event = kinesis_event(&[
+            cloudwatch_logs_data(
+                "DATA_MESSAGE",
+                "/aws/lambda/a",
+                &[
+                    otlp_stdout_record("service-a"),
+                    "START RequestId".to_string(),
+                ],
+            ),
+            cloudwatch_logs_data(CONTROL_MESSAGE_TYPE, "", &[otlp_stdout_record("control")]),
+            otlp_stdout_record("service-b").into_bytes(),
+            vec![0x1f, 0x8b, 0x00],
+        ]);
+
+        let (items, failed_count) = KinesisCloudWatchLogsParser::new()
+            .parse_counting_failures(event, "test-stream")
+            .unwrap();
+        let sources: Vec<&str> = items.iter().map(|item| item.source.as_str()).collect();
+        assert_eq!(sources, vec!["service-a", "service-b"]);
+        assert_eq!(failed_count, 1);
+    }
+
+    #[test]
+    fn test_kinesis_batch_items() {
+        let event = kinesis_event(&[
+            cloudwatch_logs_data(
+                "DATA_MESSAGE",
+                "/aws/lambda/a",
+                &[otlp_stdout_record("service-a")],
+            ),
+            cloudwatch_logs_data(CONTROL_MESSAGE_TYPE, "", &[]),
+            b"raw record".to_vec(),
+            vec![0x1f, 0x8b, 0x00],
+        ]);
+
+        let items = kinesis_batch_items(event, "test-stream");
+        assert_eq!(
+            items,
+            vec![
+                BatchItem::new("1", "/aws/lambda/a", vec![otlp_stdout_record("service-a")]),
+                BatchItem::new("2", "", Vec::new()),
+                BatchItem::new("3", "test-stream", vec!["raw record".to_string()]),
+                BatchItem::undecodable("4"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_kinesis_batch_items_invalid_utf8() {
+        let event = kinesis_event(&[vec![0x80], otlp_stdout_record("service-ok").into_bytes()]);
+
+        let items = kinesis_batch_items(event, "test-stream");
+        assert_eq!(
+            items,
+            vec![
+                BatchItem::undecodable("1"),
+                BatchItem::new("2", "test-stream", vec![otlp_stdout_record("service-ok")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_kinesis_batch_items_malformed_json() {
+        let malformed = "{\"invalid_json".to_string();
+        let event = kinesis_event(&[
+            malformed.clone().into_bytes(),
+            otlp_stdout_record("service-fine").into_bytes(),
+        ]);
+
+        let items = kinesis_batch_items(event, "test-stream");
+        assert_eq!(
+            items,
+            vec![
+                BatchItem::new("1", "test-stream", vec![malformed.clone()]),
+                BatchItem::new("2", "test-stream", vec![otlp_stdout_record("service-fine")]),
+            ]
+        );
+
+        // A record that is not JSON has no known format, so its item is skipped, not failed
+        let registry = ParserRegistry::with_defaults();
+        assert!(registry
+            .try_parse(vec![malformed], "test-stream")
+            .unwrap()
+            .is_empty());
+        let telemetry = registry
+            .try_parse(vec![otlp_stdout_record("service-fine")], "test-stream")
+            .unwrap();
+        assert_eq!(telemetry.len(), 1);
+        assert_eq!(telemetry[0].source, "service-fine");
+    }
+
+    #[test]
+    fn test_kinesis_batch_items_empty_records() {
+        assert!(kinesis_batch_items(kinesis_event(&[]), "test-stream").is_empty());
+    }
+}
@@ -1,23 +1,122 @@
 #![doc = include_str!("../README.md")]
 // In: packages/rust/serverless-otlp-forwarder-core/src/lib.rs
 
+mod base64_bytes;
+
 pub mod telemetry;
-pub use telemetry::TelemetryData;
+pub use telemetry::{Signal, TelemetryData};
 
+#[cfg(test)]
+pub(crate) mod test_fixtures;
 #[cfg(test)]
 pub(crate) mod tracing_capture;
 
+pub mod config;
+pub use config::{ForwarderConfig, ForwarderSettings, OtlpProtocol};
+
 pub mod span_compactor;
 pub use span_compactor::{compact_telemetry_payloads, SpanCompactionConfig};
 
+pub mod transform;
+pub use transform::AttributeTransform;
+
+pub mod semconv;
+pub use semconv::SemconvNormalizer;
+
+pub mod source_metadata;
+pub use source_metadata::SourceMetadata;
+
+pub mod span_filter;
+pub use span_filter::SpanFilter;
+
+pub mod trace_sampler;
+pub use trace_sampler::TraceSampler;
+
+pub mod span_dedup;
+pub use span_dedup::SpanDeduplicator;
+
 pub mod http_sender;
-pub use http_sender::{client_builder, send_telemetry_batch, HttpClient};
+pub use http_sender::{
+    client_builder, send_all, send_telemetry_batch, send_telemetry_batch_to,
+    send_telemetry_batch_with_config, send_telemetry_batch_with_retry, ExportOutcome, HttpClient,
+    SendAllReport, ThrottledError,
+};
+
+pub mod tls;
+pub use tls::TlsConfig;
+
+pub mod connection;
+pub use connection::{warm_up, ConnectionConfig};
+
+pub mod destination;
+pub use destination::{send_telemetry_batch_to_all, Destination, FanOutReport};
+
+pub mod telemetry_destination;
+pub use telemetry_destination::{HttpDestination, TelemetryDestination};
+#[cfg(feature = "aws-destinations")]
+pub use telemetry_destination::{KinesisDestination, S3Destination};
+
+pub mod retry;
+pub use retry::RetryConfig;
+
+pub mod rate_limiter;
+pub use rate_limiter::AdaptiveRateLimiter;
+
+pub mod circuit_breaker;
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitOpenError};
 
 #[cfg(feature = "instrumented-client")]
-pub use http_sender::instrumented::InstrumentedHttpClient;
+pub use http_sender::instrumented::{InstrumentedHttpClient, RequestMetrics};
+
+#[cfg(feature = "grpc")]
+pub mod grpc_sender;
+#[cfg(feature = "grpc")]
+pub use grpc_sender::GrpcClient;
+
+#[cfg(feature = "sigv4")]
+pub mod sigv4;
+#[cfg(feature = "sigv4")]
+pub use sigv4::SigV4SigningClient;
 
 pub mod core_parser;
-pub use core_parser::EventParser;
+pub use core_parser::{EventParser, ParserRegistry, RecordFormat};
+
+#[cfg(feature = "kinesis")]
+pub mod kinesis;
+#[cfg(feature = "kinesis")]
+pub use kinesis::{kinesis_batch_items, KinesisCloudWatchLogsParser};
+
+#[cfg(feature = "sqs")]
+pub mod sqs;
+#[cfg(feature = "sqs")]
+pub use sqs::sqs_batch_items;
+
+#[cfg(feature = "firehose")]
+pub mod firehose;
+#[cfg(feature = "firehose")]
+pub use firehose::{process_firehose_event, FirehoseRecordResult};
+
+#[cfg(feature = "s3-ingest")]
+pub mod s3_ingest;
+#[cfg(feature = "s3-ingest")]
+pub use s3_ingest::S3Ingestor;
+
+pub mod batch_dedup;
+pub use batch_dedup::BatchDeduplicator;
+
+pub mod dead_letter;
+#[cfg(feature = "dlq")]
+pub use dead_letter::{dead_letter_sink_from_env, S3DeadLetterSink, SqsDeadLetterSink};
+pub use dead_letter::{replay_dead_letter, DeadLetter, DeadLetterSink};
+
+#[cfg(feature = "capture")]
+pub mod capture;
+
+pub mod partial_batch;
+pub use partial_batch::{process_batch_items, BatchItem, BatchItemFailures, PartialBatchReport};
 
 pub mod processor;
-pub use processor::process_event_batch;
+pub use processor::{
+    process_event_batch, process_event_batch_to_destinations,
+    process_event_batch_to_telemetry_destination, process_event_batch_with_config, BatchOutcome,
+};
@@ -0,0 +1,396 @@
+//! Partial batch failures for Kinesis and SQS event sources.
+//!
+//! [`process_event_batch`](crate::process_event_batch) forwards an event as a whole: a record
+//! that cannot be parsed is skipped, and a batch that cannot be sent fails the invocation, so the
+//! event source redelivers every record of the event. When the event source mapping enables
+//! `ReportBatchItemFailures`, [`process_batch_items`] instead reports which records failed, in
+//! the [`BatchItemFailures`] response Lambda expects, so that only those are retried.
+//!
+//! Each [`BatchItem`] is one record of the event, identified by its Kinesis sequence number or
+//! SQS message id, with the log records it carries. With the `kinesis` feature,
+//! [`kinesis_batch_items`](crate::kinesis::kinesis_batch_items) builds them from a Kinesis event,
+//! and with the `sqs` feature, [`sqs_batch_items`](crate::sqs::sqs_batch_items) from an SQS event.
+
+use crate::batch_dedup::BatchDeduplicator;
+use crate::config::ForwarderConfig;
+use crate::core_parser::ParserRegistry;
+use crate::http_sender::HttpOtlpForwarderClient;
+use crate::processor::{compact_and_deliver_signal, BatchOutcome};
+use crate::rate_limiter::{AdaptiveRateLimiter, RateLimitedClient};
+use crate::telemetry::{Signal, TelemetryData};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tracing::{field, info, instrument, warn};
+
+/// A record of an event source batch, with the log records it carries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchItem {
+    item_identifier: String,
+    source: String,
+    messages: Option<Vec<String>>,
+}
+
+impl BatchItem {
+    /// Creates an item identified by `item_identifier` (the Kinesis sequence number or SQS
+    /// message id) holding `messages`, parsed with `source` as their source
+    pub fn new(
+        item_identifier: impl Into<String>,
+        source: impl Into<String>,
+        messages: Vec<String>,
+    ) -> Self {
+        Self {
+            item_identifier: item_identifier.into(),
+            source: source.into(),
+            messages: Some(messages),
+        }
+    }
+
+    /// Creates an item for a record whose data could not be decoded, which is reported as failed
+    pub fn undecodable(item_identifier: impl Into<String>) -> Self {
+        Self {
+            item_identifier: item_identifier.into(),
+            source: String::new(),
+            messages: None,
+        }
+    }
+
+    /// Returns the identifier of the record
+    pub fn item_identifier(&self) -> &str {
+        &self.item_identifier
+    }
+}
+
+/// Result of a [`BatchItem`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchItemStatus {
+    /// The telemetry of the item was delivered, or persisted to the dead-letter sink
+    Delivered,
+    /// The item holds no telemetry, or only batches already forwarded
+    Skipped,
+    /// The item could not be decoded or parsed, or its telemetry was not delivered
+    Failed,
+}
+
+/// Result of a [`BatchItem`], with its identifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchItemResult {
+    /// Identifier of the item
+    pub item_identifier: String,
+    /// What happened to the item
+    pub status: BatchItemStatus,
+}
+
+/// Per-item results of [`process_batch_items`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PartialBatchReport {
+    /// Statistics of the batch; see [`BatchOutcome`]
+    pub outcome: BatchOutcome,
+    /// Result of each item, in the order of the items
+    pub results: Vec<BatchItemResult>,
+}
+
+impl PartialBatchReport {
+    /// Returns the number of failed items
+    pub fn failed_count(&self) -> usize {
+        self.failures().count()
+    }
+
+    /// Returns true if no item failed
+    pub fn all_succeeded(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Returns the results of the failed items
+    pub fn failures(&self) -> impl Iterator<Item = &BatchItemResult> {
+        self.results
+            .iter()
+            .filter(|result| result.status == BatchItemStatus::Failed)
+    }
+
+    /// Returns the response reporting the failed items to Lambda
+    pub fn batch_item_failures(&self) -> BatchItemFailures {
+        BatchItemFailures {
+            batch_item_failures: self
+                .failures()
+                .map(|result| BatchItemFailure {
+                    item_identifier: result.item_identifier.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Response of a function reporting batch item failures to a Kinesis or SQS event source
+///
+/// It serializes to `{"batchItemFailures": [{"itemIdentifier": "..."}]}`. An empty list tells
+/// Lambda that the whole batch succeeded.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItemFailures {
+    /// The failed items
+    pub batch_item_failures: Vec<BatchItemFailure>,
+}
+
+/// A failed item of a [`BatchItemFailures`] response
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItemFailure {
+    /// Identifier of the item: the Kinesis sequence number or SQS message id
+    pub item_identifier: String,
+}
+
+/// Processes the records of an event separately, reporting the records that failed.
+///
+/// The log records of each item are parsed with [`ParserRegistry::try_parse`], so an item holding
+/// a malformed record fails instead of being partially forwarded. The telemetry of all the other
+/// items is compacted per signal and sent with the destination, retries and dead-letter sink of
+/// `config`, like [`process_event_batch_with_config`]. As the telemetry of a signal is sent as
+/// one compacted batch, a failed send fails every item that contributed to it.
+///
+/// Items carrying batch ids already forwarded within the deduplication window are skipped, so
+/// a retried item is not forwarded twice once its telemetry got through.
+///
+/// A Kinesis event source retries from the first failed record of a shard, and a record that
+/// keeps failing is retried until the retry limits of the event source mapping are reached.
+///
+/// [`process_event_batch_with_config`]: crate::processor::process_event_batch_with_config
+#[instrument(
+    name = "processor/process_batch_items",
+    skip_all,
+    fields(
+        forwarder.items.count = items.len(),
+        forwarder.items.failed = field::Empty,
+        forwarder.records.parsed = field::Empty,
+        forwarder.records.skipped = field::Empty,
+        forwarder.records.failed = field::Empty,
+        forwarder.spans.forwarded = field::Empty,
+        forwarder.bytes.sent = field::Empty,
+        forwarder.send.attempts = field::Empty
+    )
+)]
+pub async fn process_batch_items<C: HttpOtlpForwarderClient>(
+    items: Vec<BatchItem>,
+    registry: &ParserRegistry,
+    http_client: &C,
+    config: &ForwarderConfig,
+) -> PartialBatchReport {
+    process_batch_items_with_dedup(
+        items,
+        registry,
+        http_client,
+        config,
+        BatchDeduplicator::global(),
+    )
+    .await
+}
+
+async fn process_batch_items_with_dedup<C: HttpOtlpForwarderClient>(
+    items: Vec<BatchItem>,
+    registry: &ParserRegistry,
+    http_client: &C,
+    config: &ForwarderConfig,
+    deduplicator: Option<&BatchDeduplicator>,
+) -> PartialBatchReport {
+    let mut outcome = BatchOutcome::default();
+    let mut results = Vec::with_capacity(items.len());
+
+    // 1. Parse the log records of each item, grouping telemetry by signal
+    let mut items_by_signal: Vec<(Signal, Vec<TelemetryData>, Vec<usize>)> = Vec::new();
+    let mut pending = HashSet::new();
+    for (index, item) in items.into_iter().enumerate() {
+        let mut result = BatchItemResult {
+            item_identifier: item.item_identifier,
+            status: BatchItemStatus::Failed,
+        };
+        let Some(messages) = item.messages else {
+            warn!("Failed to decode batch item; reporting it as failed.");
+            outcome.records_failed += 1;
+            results.push(result);
+            continue;
+        };
+        let mut telemetry_items = match registry.try_parse(messages, &item.source) {
+            Ok(telemetry_items) => telemetry_items,
+            Err(_) => {
+                warn!("Failed to parse batch item; reporting it as failed.");
+                outcome.records_failed += 1;
+                results.push(result);
+                continue;
+            }
+        };
+
+        // Drop batches already forwarded within the window, or repeated within this event
+        let parsed_count = telemetry_items.len();
+        if let Some(deduplicator) = deduplicator {
+            telemetry_items.retain(|telemetry| match telemetry.batch_id.as_deref() {
+                Some(batch_id) => {
+                    !deduplicator.contains(batch_id) && pending.insert(batch_id.to_string())
+                }
+                None => true,
+            });
+        }
+        outcome.records_parsed += parsed_count;
+        outcome.records_skipped += parsed_count - telemetry_items.len();
+
+        if telemetry_items.is_empty() {
+            result.status = BatchItemStatus::Skipped;
+            results.push(result);
+            continue;
+        }
+        for telemetry in telemetry_items {
+            match items_by_signal
+                .iter_mut()
+                .find(|(signal, _, _)| *signal == telemetry.signal)
+            {
+                Some((_, telemetry_items, indices)) => {
+                    telemetry_items.push(telemetry);
+                    if indices.last() != Some(&index) {
+                        indices.push(index);
+                    }
+                }
+                None => items_by_signal.push((telemetry.signal, vec![telemetry], vec![index])),
+            }
+        }
+        result.status = BatchItemStatus::Delivered;
+        results.push(result);
+    }
+
+    // 2. Compact and send the telemetry of each signal, paced by the shared rate limiter
+    let http_client = &RateLimitedClient::new(http_client, AdaptiveRateLimiter::global());
+    for (signal, telemetry_items, indices) in items_by_signal {
+        let batch_ids: Vec<String> = telemetry_items
+            .iter()
+            .filter_map(|telemetry| telemetry.batch_id.clone())
+            .collect();
+        if compact_and_deliver_signal(signal, telemetry_items, http_client, config, &mut outcome)
+            .await
+        {
+            // Only remember batches once delivered, so failed items can be retried
+            if let Some(deduplicator) = deduplicator {
+                deduplicator.record(batch_ids.iter().map(String::as_str));
+            }
+        } else {
+            for index in indices {
+                results[index].status = BatchItemStatus::Failed;
+            }
+        }
+    }
+    outcome.record_on_current_span();
+
+    let report = PartialBatchReport { outcome, results };
+    let failed_count = report.failed_count();
+    tracing::Span::current().record("forwarder.items.failed", failed_count as i64);
+    if failed_count > 0 {
+        warn!(
+            failed_items_count = failed_count as i64,
+            "Some batch items were not processed."
+        );
+    } else {
+        info!("Processed all batch items.");
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::otlp_stdout_batch_record;
+    use crate::ForwarderSettings;
+    use reqwest::Client as ReqwestClient;
+    use serde_json::json;
+    use std::time::Duration;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_process_batch_items_reports_failed_items() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/logs"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&server)
+            .await;
+        let config = ForwarderConfig::resolve(&ForwarderSettings {
+            endpoint: Some(format!("{}/v1/traces", server.uri())),
+            ..Default::default()
+        })
+        .unwrap();
+        let deduplicator = BatchDeduplicator::new(Duration::from_secs(60), 100);
+        let source = "/aws/lambda/checkout";
+
+        let items = vec![
+            BatchItem::new(
+                "1",
+                source,
+                vec![otlp_stdout_batch_record(
+                    "http://collector/v1/traces",
+                    "batch-1",
+                )],
+            ),
+            BatchItem::new("2", source, vec!["plain application log".to_string()]),
+            BatchItem::new(
+                "3",
+                source,
+                vec![json!({"__otel_otlp_stdout": "broken"}).to_string()],
+            ),
+            BatchItem::undecodable("4"),
+            BatchItem::new(
+                "5",
+                source,
+                vec![otlp_stdout_batch_record(
+                    "http://collector/v1/logs",
+                    "batch-5",
+                )],
+            ),
+        ];
+        let report = process_batch_items_with_dedup(
+            items.clone(),
+            &ParserRegistry::with_defaults(),
+            &ReqwestClient::new(),
+            &config,
+            Some(&deduplicator),
+        )
+        .await;
+
+        let statuses: Vec<BatchItemStatus> = report.results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                BatchItemStatus::Delivered,
+                BatchItemStatus::Skipped,
+                BatchItemStatus::Failed,
+                BatchItemStatus::Failed,
+                BatchItemStatus::Failed,
+            ]
+        );
+        assert_eq!(report.outcome.records_parsed, 2);
+        assert_eq!(report.outcome.records_failed, 2);
+        assert_eq!(
+            serde_json::to_value(report.batch_item_failures()).unwrap(),
+            json!({"batchItemFailures": [
+                {"itemIdentifier": "3"},
+                {"itemIdentifier": "4"},
+                {"itemIdentifier": "5"}
+            ]})
+        );
+        assert!(deduplicator.contains("batch-1"));
+        assert!(!deduplicator.contains("batch-5"));
+
+        // The delivered item is not forwarded again when the event is retried
+        let report = process_batch_items_with_dedup(
+            items[..1].to_vec(),
+            &ParserRegistry::with_defaults(),
+            &ReqwestClient::new(),
+            &config,
+            Some(&deduplicator),
+        )
+        .await;
+        assert_eq!(report.results[0].status, BatchItemStatus::Skipped);
+        assert_eq!(report.outcome.records_skipped, 1);
+        assert!(report.all_succeeded());
+    }
+}
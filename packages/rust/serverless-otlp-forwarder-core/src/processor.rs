@@ -1,12 +1,99 @@
+use crate::batch_dedup::BatchDeduplicator;
+use crate::config::{ForwarderConfig, ForwarderSettings};
 use crate::core_parser::EventParser;
-use crate::http_sender::{send_telemetry_batch, HttpOtlpForwarderClient};
+use crate::dead_letter::{DeadLetter, DeadLetterSink};
+use crate::destination::{send_telemetry_batch_to_all, Destination, FanOutReport};
+use crate::http_sender::{send_all, HttpForwarderResponse, HttpOtlpForwarderClient};
+use crate::rate_limiter::{AdaptiveRateLimiter, RateLimitedClient};
+use crate::retry::RetryConfig;
 use crate::span_compactor::{compact_telemetry_payloads, SpanCompactionConfig};
-use anyhow::Result;
-use tracing::{debug, error, info, instrument};
+use crate::span_dedup::SpanKey;
+use crate::telemetry::{Signal, TelemetryData};
+use crate::telemetry_destination::TelemetryDestination;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tracing::{debug, error, field, info, instrument, warn};
+use url::Url;
+
+/// Statistics of an event batch processed by [`process_event_batch`]
+///
+/// They are also recorded as attributes of the `processor/process_event_batch` span
+/// (`forwarder.records.parsed`, `forwarder.records.skipped`, `forwarder.records.failed`,
+/// `forwarder.spans.forwarded`, `forwarder.bytes.sent` and `forwarder.send.attempts`), so
+/// operators can alarm on parse failures and dropped payloads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchOutcome {
+    /// Telemetry items produced by the parser
+    pub records_parsed: usize,
+    /// Parsed telemetry items dropped before compaction, as duplicates of forwarded batches
+    pub records_skipped: usize,
+    /// Records rejected by the parser, or event source items that could not be decoded
+    pub records_failed: usize,
+    /// Spans accepted by the destination, excluding the spans it rejected
+    pub spans_forwarded: usize,
+    /// Payload bytes of the batches accepted by the destination, as sent (after compression)
+    pub bytes_sent: usize,
+    /// HTTP requests made to send the batches, including retries
+    pub send_attempts: usize,
+}
+
+impl BatchOutcome {
+    pub(crate) fn record_on_current_span(&self) {
+        let span = tracing::Span::current();
+        span.record("forwarder.records.parsed", self.records_parsed as i64);
+        span.record("forwarder.records.skipped", self.records_skipped as i64);
+        span.record("forwarder.records.failed", self.records_failed as i64);
+        span.record("forwarder.spans.forwarded", self.spans_forwarded as i64);
+        span.record("forwarder.bytes.sent", self.bytes_sent as i64);
+        span.record("forwarder.send.attempts", self.send_attempts as i64);
+    }
+}
+
+/// Counts the requests made through a client
+struct AttemptCountingClient<'a, C> {
+    inner: &'a C,
+    attempts: AtomicUsize,
+}
+
+impl<'a, C> AttemptCountingClient<'a, C> {
+    fn new(inner: &'a C) -> Self {
+        Self {
+            inner,
+            attempts: AtomicUsize::new(0),
+        }
+    }
+
+    fn attempts(&self) -> usize {
+        self.attempts.load(Ordering::Relaxed)
+    }
+}
+
+#[async_trait]
+impl<C: HttpOtlpForwarderClient> HttpOtlpForwarderClient for AttemptCountingClient<'_, C> {
+    async fn post_telemetry(
+        &self,
+        target_url: Url,
+        headers: HeaderMap,
+        payload: Bytes,
+        timeout: Duration,
+    ) -> Result<HttpForwarderResponse> {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+        self.inner
+            .post_telemetry(target_url, headers, payload, timeout)
+            .await
+    }
+}
 
 /// Processes a batch of events from a specific AWS Lambda event source.
 ///
 /// This function orchestrates the parsing, compaction, and sending of telemetry data.
+/// Items carrying a `batch_id` that was already forwarded within the deduplication window
+/// (see [`BatchDeduplicator`]) are dropped before compaction.
 ///
 /// # Type Parameters
 ///
@@ -22,7 +109,19 @@ use tracing::{debug, error, info, instrument};
 /// * `http_client`: A reference to the HTTP client for making HTTP requests.
 /// * `compaction_config`: Configuration for span compaction.
 ///
-#[instrument(name = "processor/process_event_batch", skip_all)]
+/// Returns the [`BatchOutcome`] of the batch.
+#[instrument(
+    name = "processor/process_event_batch",
+    skip_all,
+    fields(
+        forwarder.records.parsed = field::Empty,
+        forwarder.records.skipped = field::Empty,
+        forwarder.records.failed = field::Empty,
+        forwarder.spans.forwarded = field::Empty,
+        forwarder.bytes.sent = field::Empty,
+        forwarder.send.attempts = field::Empty
+    )
+)]
 pub async fn process_event_batch<
     E,
     P: EventParser<EventInput = E> + Sync + Send,
@@ -33,56 +132,683 @@ pub async fn process_event_batch<
     source_identifier: &str,
     http_client: &C,
     compaction_config: &SpanCompactionConfig,
+) -> Result<BatchOutcome> {
+    process_event_batch_with_dedup(
+        event_payload,
+        parser,
+        source_identifier,
+        http_client,
+        compaction_config,
+        None,
+        BatchDeduplicator::global(),
+    )
+    .await
+}
+
+/// Processes a batch of events like [`process_event_batch`], using a [`ForwarderConfig`] for
+/// compaction, the destination and retries instead of reading the environment on every call.
+///
+/// Load the configuration once, when the function starts, with [`ForwarderConfig::from_env`] or
+/// [`ForwarderConfig::load`] and share it across invocations.
+///
+/// When the configuration has a [`DeadLetterSink`], a batch that still fails after all retries
+/// is persisted there and counts as handled, so the event source does not redeliver it. If it
+/// cannot be persisted either, the send error is returned as usual.
+#[instrument(
+    name = "processor/process_event_batch",
+    skip_all,
+    fields(
+        forwarder.records.parsed = field::Empty,
+        forwarder.records.skipped = field::Empty,
+        forwarder.records.failed = field::Empty,
+        forwarder.spans.forwarded = field::Empty,
+        forwarder.bytes.sent = field::Empty,
+        forwarder.send.attempts = field::Empty
+    )
+)]
+pub async fn process_event_batch_with_config<
+    E,
+    P: EventParser<EventInput = E> + Sync + Send,
+    C: HttpOtlpForwarderClient,
+>(
+    event_payload: E,
+    parser: &P,
+    source_identifier: &str,
+    http_client: &C,
+    config: &ForwarderConfig,
+) -> Result<BatchOutcome> {
+    process_event_batch_with_dedup(
+        event_payload,
+        parser,
+        source_identifier,
+        http_client,
+        &config.compaction,
+        Some(config),
+        BatchDeduplicator::global(),
+    )
+    .await
+}
+
+async fn process_event_batch_with_dedup<
+    E,
+    P: EventParser<EventInput = E> + Sync + Send,
+    C: HttpOtlpForwarderClient,
+>(
+    event_payload: E,
+    parser: &P,
+    source_identifier: &str,
+    http_client: &C,
+    compaction_config: &SpanCompactionConfig,
+    config: Option<&ForwarderConfig>,
+    deduplicator: Option<&BatchDeduplicator>,
+) -> Result<BatchOutcome> {
+    let mut batch_outcome = BatchOutcome::default();
+    let batches = prepare_batches(
+        event_payload,
+        parser,
+        source_identifier,
+        compaction_config,
+        deduplicator,
+        &mut batch_outcome,
+    )?;
+
+    if batches.is_empty() {
+        batch_outcome.record_on_current_span();
+        return Ok(batch_outcome);
+    }
+
+    // 3. Send the compacted batches of each signal, paced by the shared rate limiter
+    let (destination, retry_config, concurrency) = match config {
+        Some(config) => (
+            config.destination.clone(),
+            config.retry.clone(),
+            config.send_concurrency,
+        ),
+        None => (
+            Destination::from_env().context("Sending telemetry batch failed")?,
+            RetryConfig::from_env(),
+            ForwarderSettings::send_concurrency_from_env().send_concurrency(),
+        ),
+    };
+    let delivery = Delivery {
+        destination: &destination,
+        retry: &retry_config,
+        concurrency,
+        dead_letter_sink: config.and_then(|config| config.dead_letter_sink.as_deref()),
+    };
+    let http_client = &RateLimitedClient::new(http_client, AdaptiveRateLimiter::global());
+    let mut first_error = None;
+    for batches in batches {
+        match deliver_signal(
+            batches.compacted,
+            http_client,
+            &delivery,
+            &mut batch_outcome,
+        )
+        .await
+        {
+            // Only remember batches once fully delivered, so failed invocations can be retried
+            Ok(()) => {
+                record_delivered(
+                    batches.batch_ids,
+                    batches.span_keys,
+                    deduplicator,
+                    compaction_config,
+                );
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    batch_outcome.record_on_current_span();
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(batch_outcome),
+    }
+}
+
+/// Where and how [`deliver_signal`] sends compacted batches
+struct Delivery<'a> {
+    destination: &'a Destination,
+    retry: &'a RetryConfig,
+    concurrency: usize,
+    dead_letter_sink: Option<&'a dyn DeadLetterSink>,
+}
+
+impl<'a> From<&'a ForwarderConfig> for Delivery<'a> {
+    fn from(config: &'a ForwarderConfig) -> Self {
+        Self {
+            destination: &config.destination,
+            retry: &config.retry,
+            concurrency: config.send_concurrency,
+            dead_letter_sink: config.dead_letter_sink.as_deref(),
+        }
+    }
+}
+
+/// Sends the compacted batches of one signal, persisting the batches that still fail after all
+/// retries to the dead-letter sink, if any
+///
+/// The spans and bytes of the accepted batches, and the requests made, are added to
+/// `batch_outcome`. Returns the send error of the first batch that was neither delivered nor
+/// persisted.
+async fn deliver_signal<C: HttpOtlpForwarderClient>(
+    compacted_batches: Vec<TelemetryData>,
+    http_client: &C,
+    delivery: &Delivery<'_>,
+    batch_outcome: &mut BatchOutcome,
 ) -> Result<()> {
+    let http_client = &AttemptCountingClient::new(http_client);
+    let stats: Vec<(Signal, usize, usize)> = compacted_batches
+        .iter()
+        .map(|batch| {
+            (
+                batch.signal,
+                batch.span_count.unwrap_or(0),
+                batch.payload.len(),
+            )
+        })
+        .collect();
+    // Keep a copy for the dead-letter sink, as sending consumes the batches
+    let dead_letters = delivery.dead_letter_sink.map(|_| compacted_batches.clone());
+    let report = send_all(
+        http_client,
+        compacted_batches,
+        delivery.destination,
+        delivery.retry,
+        delivery.concurrency,
+    )
+    .await;
+
+    let mut first_error = None;
+    for (index, (sent, (signal, span_count, payload_size))) in
+        report.results.into_iter().zip(stats).enumerate()
+    {
+        match sent {
+            Ok(outcome) => {
+                info!(
+                    %signal,
+                    rejected_items = outcome.rejected_items(),
+                    "Successfully sent telemetry batch."
+                );
+                batch_outcome.spans_forwarded += span_count
+                    .saturating_sub(usize::try_from(outcome.rejected_items()).unwrap_or(0));
+                batch_outcome.bytes_sent += payload_size;
+            }
+            Err(e) => {
+                error!(%signal, "Failed to send telemetry batch.");
+                let persisted = match (delivery.dead_letter_sink, &dead_letters) {
+                    (Some(sink), Some(dead_letters)) => {
+                        persist_dead_letter(sink, dead_letters[index].clone(), delivery.destination)
+                            .await
+                    }
+                    _ => false,
+                };
+                // A persisted batch is handled: the event source must not redeliver it
+                if !persisted {
+                    first_error.get_or_insert(e.context("Sending telemetry batch failed"));
+                }
+            }
+        }
+    }
+    batch_outcome.send_attempts += http_client.attempts();
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Compacts the telemetry items of one signal and delivers them with the destination, retries
+/// and dead-letter sink of `config`, returning whether they were all delivered or persisted to
+/// the dead-letter sink
+///
+/// The spans of delivered batches are recorded for span deduplication.
+pub(crate) async fn compact_and_deliver_signal<C: HttpOtlpForwarderClient>(
+    signal: Signal,
+    items: Vec<TelemetryData>,
+    http_client: &C,
+    config: &ForwarderConfig,
+    batch_outcome: &mut BatchOutcome,
+) -> bool {
+    let (compacted_batches, span_keys) = match compact_telemetry_payloads(items, &config.compaction)
+    {
+        Ok(compacted) => compacted,
+        Err(_) => {
+            error!(%signal, "Failed to compact telemetry items.");
+            return false;
+        }
+    };
+    let delivered = deliver_signal(
+        compacted_batches,
+        http_client,
+        &Delivery::from(config),
+        batch_outcome,
+    )
+    .await
+    .is_ok();
+    // The batch ids are recorded by the caller, which knows the items they belong to
+    if let (true, Some(span_deduplicator)) = (delivered, &config.compaction.span_deduplicator) {
+        span_deduplicator.record(span_keys);
+    }
+    delivered
+}
+
+/// Hands a batch that could not be delivered to the dead-letter sink, returning whether it was
+/// persisted
+pub(crate) async fn persist_dead_letter(
+    sink: &dyn DeadLetterSink,
+    telemetry: TelemetryData,
+    destination: &Destination,
+) -> bool {
+    let dead_letter = DeadLetter::new(telemetry, destination);
+    let signal = dead_letter.signal;
+    let payload_size_bytes = dead_letter.payload.len() as u64;
+    match sink.persist(&dead_letter).await {
+        Ok(()) => {
+            warn!(
+                %signal,
+                payload_size_bytes,
+                "Persisted undelivered telemetry batch to the dead-letter sink."
+            );
+            true
+        }
+        Err(_) => {
+            error!(
+                %signal,
+                payload_size_bytes,
+                "Failed to persist undelivered telemetry batch to the dead-letter sink."
+            );
+            false
+        }
+    }
+}
+
+/// Processes a batch of events like [`process_event_batch`], sending the compacted batch to
+/// several destinations concurrently.
+///
+/// Each destination is retried independently according to [`RetryConfig::from_env`]. The
+/// returned [`FanOutReport`] holds the outcome of every destination, which is also logged.
+/// An error is returned only if no destination accepted the batch, so that a partial failure
+/// does not make the event source redeliver the batch to the destinations that already have it.
+/// For the same reason, batch ids and spans are recorded for deduplication as soon as one
+/// destination accepted the batch.
+///
+/// An event holding several signals is sent as one batch per signal, or more when a batch exceeds
+/// the maximum payload size, and the report combines their outcomes: a destination fails if it
+/// did not accept one of the batches.
+///
+/// When the event yields nothing to send (no telemetry, or only duplicates), the report is empty.
+/// The [`BatchOutcome`] of the report counts the spans and bytes of the batches accepted by at
+/// least one destination, and the requests made to all of them.
+#[instrument(
+    name = "processor/process_event_batch",
+    skip_all,
+    fields(
+        otlp.destinations.count = destinations.len(),
+        forwarder.records.parsed = field::Empty,
+        forwarder.records.skipped = field::Empty,
+        forwarder.records.failed = field::Empty,
+        forwarder.spans.forwarded = field::Empty,
+        forwarder.bytes.sent = field::Empty,
+        forwarder.send.attempts = field::Empty
+    )
+)]
+pub async fn process_event_batch_to_destinations<
+    E,
+    P: EventParser<EventInput = E> + Sync + Send,
+    C: HttpOtlpForwarderClient,
+>(
+    event_payload: E,
+    parser: &P,
+    source_identifier: &str,
+    http_client: &C,
+    compaction_config: &SpanCompactionConfig,
+    destinations: &[Destination],
+) -> Result<FanOutReport> {
+    process_event_batch_to_destinations_with_dedup(
+        event_payload,
+        parser,
+        source_identifier,
+        http_client,
+        compaction_config,
+        destinations,
+        BatchDeduplicator::global(),
+    )
+    .await
+}
+
+async fn process_event_batch_to_destinations_with_dedup<
+    E,
+    P: EventParser<EventInput = E> + Sync + Send,
+    C: HttpOtlpForwarderClient,
+>(
+    event_payload: E,
+    parser: &P,
+    source_identifier: &str,
+    http_client: &C,
+    compaction_config: &SpanCompactionConfig,
+    destinations: &[Destination],
+    deduplicator: Option<&BatchDeduplicator>,
+) -> Result<FanOutReport> {
+    if destinations.is_empty() {
+        return Err(anyhow::anyhow!("No OTLP destinations configured"));
+    }
+
+    let mut batch_outcome = BatchOutcome::default();
+    let batches = prepare_batches(
+        event_payload,
+        parser,
+        source_identifier,
+        compaction_config,
+        deduplicator,
+        &mut batch_outcome,
+    )?;
+
+    // 3. Send each compacted batch to every destination, paced by the shared rate limiter
+    let rate_limited_client = RateLimitedClient::new(http_client, AdaptiveRateLimiter::global());
+    let http_client = &AttemptCountingClient::new(&rate_limited_client);
+    let retry_config = RetryConfig::from_env();
+    let mut report = FanOutReport::default();
+    for batches in batches {
+        for compacted_telemetry in batches.compacted {
+            let signal = compacted_telemetry.signal;
+            let span_count = compacted_telemetry.span_count.unwrap_or(0);
+            let payload_size = compacted_telemetry.payload.len();
+            let batch_report = send_telemetry_batch_to_all(
+                http_client,
+                compacted_telemetry,
+                destinations,
+                &retry_config,
+            )
+            .await;
+
+            if !batch_report.any_succeeded() {
+                error!(
+                    %signal,
+                    failed_destinations_count = batch_report.failed_count() as i64,
+                    "Failed to send telemetry batch to any destination."
+                );
+                return Err(anyhow::anyhow!(
+                    "Sending telemetry batch failed for all {} destinations",
+                    batch_report.failed_count()
+                ));
+            }
+            batch_outcome.spans_forwarded += span_count;
+            batch_outcome.bytes_sent += payload_size;
+            report = report.merge(batch_report);
+        }
+        record_delivered(
+            batches.batch_ids,
+            batches.span_keys,
+            deduplicator,
+            compaction_config,
+        );
+    }
+    batch_outcome.send_attempts = http_client.attempts();
+    batch_outcome.record_on_current_span();
+    report.outcome = batch_outcome;
+    if report.outcomes.is_empty() {
+        return Ok(report);
+    }
+
+    if report.all_succeeded() {
+        info!(
+            destinations_count = report.succeeded_count() as i64,
+            "Successfully sent telemetry batch to all destinations."
+        );
+    } else {
+        warn!(
+            succeeded_destinations_count = report.succeeded_count() as i64,
+            failed_destinations_count = report.failed_count() as i64,
+            "Telemetry batch was not accepted by all destinations."
+        );
+    }
+    Ok(report)
+}
+
+/// Processes a batch of events like [`process_event_batch`], delivering the compacted batches to
+/// a [`TelemetryDestination`] instead of an OTLP endpoint.
+///
+/// This lets a processor archive telemetry to S3 or relay it to a Kinesis stream of another
+/// account. The batches are sent one after the other; batch ids and spans are recorded for
+/// deduplication only once all the batches of their signal were delivered. `send_attempts` of the returned
+/// [`BatchOutcome`] counts the batches handed to the destination, which retries them as it sees fit.
+#[instrument(
+    name = "processor/process_event_batch",
+    skip_all,
+    fields(
+        otlp.destination.name = destination.name(),
+        forwarder.records.parsed = field::Empty,
+        forwarder.records.skipped = field::Empty,
+        forwarder.records.failed = field::Empty,
+        forwarder.spans.forwarded = field::Empty,
+        forwarder.bytes.sent = field::Empty,
+        forwarder.send.attempts = field::Empty
+    )
+)]
+pub async fn process_event_batch_to_telemetry_destination<
+    E,
+    P: EventParser<EventInput = E> + Sync + Send,
+>(
+    event_payload: E,
+    parser: &P,
+    source_identifier: &str,
+    destination: &dyn TelemetryDestination,
+    compaction_config: &SpanCompactionConfig,
+) -> Result<BatchOutcome> {
+    process_event_batch_to_telemetry_destination_with_dedup(
+        event_payload,
+        parser,
+        source_identifier,
+        destination,
+        compaction_config,
+        BatchDeduplicator::global(),
+    )
+    .await
+}
+
+async fn process_event_batch_to_telemetry_destination_with_dedup<
+    E,
+    P: EventParser<EventInput = E> + Sync + Send,
+>(
+    event_payload: E,
+    parser: &P,
+    source_identifier: &str,
+    destination: &dyn TelemetryDestination,
+    compaction_config: &SpanCompactionConfig,
+    deduplicator: Option<&BatchDeduplicator>,
+) -> Result<BatchOutcome> {
+    let mut batch_outcome = BatchOutcome::default();
+    let batches = prepare_batches(
+        event_payload,
+        parser,
+        source_identifier,
+        compaction_config,
+        deduplicator,
+        &mut batch_outcome,
+    )?;
+
+    // 3. Deliver the compacted batches of each signal
+    let mut first_error = None;
+    for batches in batches {
+        let mut delivered = true;
+        for compacted_telemetry in batches.compacted {
+            let signal = compacted_telemetry.signal;
+            let span_count = compacted_telemetry.span_count.unwrap_or(0);
+            let payload_size = compacted_telemetry.payload.len();
+            batch_outcome.send_attempts += 1;
+            match destination.send(compacted_telemetry).await {
+                Ok(outcome) => {
+                    info!(
+                        %signal,
+                        rejected_items = outcome.rejected_items(),
+                        "Successfully sent telemetry batch."
+                    );
+                    batch_outcome.spans_forwarded += span_count
+                        .saturating_sub(usize::try_from(outcome.rejected_items()).unwrap_or(0));
+                    batch_outcome.bytes_sent += payload_size;
+                }
+                Err(e) => {
+                    error!(%signal, "Failed to send telemetry batch.");
+                    delivered = false;
+                    first_error.get_or_insert(e.context("Sending telemetry batch failed"));
+                }
+            }
+        }
+        if delivered {
+            record_delivered(
+                batches.batch_ids,
+                batches.span_keys,
+                deduplicator,
+                compaction_config,
+            );
+        }
+    }
+    batch_outcome.record_on_current_span();
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(batch_outcome),
+    }
+}
+
+/// The compacted batches of one signal, with the batch ids and span keys to record for
+/// deduplication once they are all delivered
+struct SignalBatches {
+    compacted: Vec<TelemetryData>,
+    batch_ids: Vec<String>,
+    span_keys: Vec<SpanKey>,
+}
+
+/// Remembers delivered batches and spans, so that their redeliveries are dropped
+///
+/// Nothing is recorded before delivery, so a batch that failed to send is forwarded again when
+/// the event source redelivers it.
+fn record_delivered(
+    batch_ids: Vec<String>,
+    span_keys: Vec<SpanKey>,
+    deduplicator: Option<&BatchDeduplicator>,
+    compaction_config: &SpanCompactionConfig,
+) {
+    if let Some(deduplicator) = deduplicator {
+        deduplicator.record(batch_ids.iter().map(String::as_str));
+    }
+    if let Some(span_deduplicator) = &compaction_config.span_deduplicator {
+        span_deduplicator.record(span_keys);
+    }
+}
+
+/// Parses, deduplicates and compacts an event into batches grouped by signal.
+///
+/// The batches of each signal come with the batch ids and span keys to record once they are all
+/// delivered.
+/// The result is empty when there is nothing to send. The parsed and skipped item counts are
+/// added to `batch_outcome`.
+fn prepare_batches<E, P: EventParser<EventInput = E> + Sync + Send>(
+    event_payload: E,
+    parser: &P,
+    source_identifier: &str,
+    compaction_config: &SpanCompactionConfig,
+    deduplicator: Option<&BatchDeduplicator>,
+    batch_outcome: &mut BatchOutcome,
+) -> Result<Vec<SignalBatches>> {
     info!("Starting to process event batch.");
 
     // 1. Parse the event payload
-    let telemetry_items = match parser.parse(event_payload, source_identifier) {
-        Ok(items) => items,
+    let mut telemetry_items = match parser.parse_counting_failures(event_payload, source_identifier)
+    {
+        Ok((items, failed_count)) => {
+            batch_outcome.records_failed = failed_count;
+            items
+        }
         Err(_) => {
             error!("Failed to parse event payload.");
             return Err(anyhow::anyhow!("Event parsing failed"));
         }
     };
 
+    batch_outcome.records_parsed = telemetry_items.len();
     if telemetry_items.is_empty() {
         info!("No telemetry items to process after parsing.");
-        return Ok(());
+        return Ok(Vec::new());
     }
     debug!(
         telemetry_items_count = telemetry_items.len() as i64,
         "Parsed telemetry items"
     );
 
-    // 2. Compact the telemetry items into a single TelemetryData object
-    let compacted_telemetry = match compact_telemetry_payloads(telemetry_items, compaction_config) {
-        Ok(compacted) => compacted,
-        Err(e) => {
-            error!("Failed to compact telemetry items.");
-            return Err(e.context("Telemetry compaction failed"));
-        }
-    };
-    debug!("Compacted telemetry items.");
+    // Drop batches already forwarded within the window, or repeated within this event
+    if let Some(deduplicator) = deduplicator {
+        let parsed_count = telemetry_items.len();
+        let mut pending = HashSet::new();
+        telemetry_items.retain(|item| match item.batch_id.as_deref() {
+            Some(batch_id) => {
+                !deduplicator.contains(batch_id) && pending.insert(batch_id.to_string())
+            }
+            None => true,
+        });
 
-    // 3. Send the compacted telemetry batch
-    match send_telemetry_batch(http_client, compacted_telemetry).await {
-        Ok(_) => {
-            info!("Successfully sent telemetry batch.");
-            Ok(())
+        let duplicate_count = parsed_count - telemetry_items.len();
+        batch_outcome.records_skipped = duplicate_count;
+        if duplicate_count > 0 {
+            info!(
+                duplicate_items_count = duplicate_count as i64,
+                "Dropped duplicate telemetry batches."
+            );
+        }
+        if telemetry_items.is_empty() {
+            info!("No telemetry items to process after deduplication.");
+            return Ok(Vec::new());
         }
-        Err(e) => {
-            error!("Failed to send telemetry batch.");
-            Err(e.context("Sending telemetry batch failed"))
+    }
+
+    // 2. Compact the telemetry items of each signal into as few TelemetryData objects as the size limit allows
+    let mut items_by_signal: Vec<(Signal, Vec<TelemetryData>)> = Vec::new();
+    for item in telemetry_items {
+        match items_by_signal
+            .iter_mut()
+            .find(|(signal, _)| *signal == item.signal)
+        {
+            Some((_, items)) => items.push(item),
+            None => items_by_signal.push((item.signal, vec![item])),
         }
     }
+
+    let mut batches = Vec::with_capacity(items_by_signal.len());
+    for (signal, items) in items_by_signal {
+        let batch_ids = items
+            .iter()
+            .filter_map(|item| item.batch_id.clone())
+            .collect();
+        let (compacted_batches, span_keys) =
+            match compact_telemetry_payloads(items, compaction_config) {
+                Ok(compacted) => compacted,
+                Err(e) => {
+                    error!(%signal, "Failed to compact telemetry items.");
+                    return Err(e.context("Telemetry compaction failed"));
+                }
+            };
+        debug!(
+            %signal,
+            compacted_batches_count = compacted_batches.len() as i64,
+            "Compacted telemetry items."
+        );
+        batches.push(SignalBatches {
+            compacted: compacted_batches,
+            batch_ids,
+            span_keys,
+        });
+    }
+
+    Ok(batches)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::core_parser::EventParser;
-    use crate::telemetry::TelemetryData;
     use anyhow::anyhow;
+    use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
     use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
     use prost::Message;
     use reqwest::Client as ReqwestClient;
@@ -132,6 +858,7 @@ mod tests {
     struct MockEventInput {
         records: Vec<String>,
         produce_valid_otlp_for_compaction: bool,
+        batch_ids: Vec<String>,
     }
 
     struct MockSuccessfulParser;
@@ -156,6 +883,8 @@ mod tests {
                                             spans: vec![
                                                 opentelemetry_proto::tonic::trace::v1::Span {
                                                     name: format!("test-span-{r}-{i}"),
+                                                    trace_id: vec![1; 16],
+                                                    span_id: vec![i as u8 + 1; 8],
                                                     ..Default::default()
                                                 },
                                             ],
@@ -177,6 +906,9 @@ mod tests {
                         endpoint: "mock_endpoint".to_string(),
                         content_type: "application/x-protobuf".to_string(),
                         content_encoding: None,
+                        batch_id: event_payload.batch_ids.get(i).cloned(),
+                        signal: Signal::Traces,
+                        span_count: None,
                     }
                 })
                 .collect();
@@ -184,6 +916,37 @@ mod tests {
         }
     }
 
+    /// Yields one span and one log record
+    struct MockMixedSignalParser;
+    impl EventParser for MockMixedSignalParser {
+        type EventInput = ();
+        fn parse(
+            &self,
+            _event_payload: Self::EventInput,
+            _source_identifier: &str,
+        ) -> Result<Vec<TelemetryData>> {
+            let spans = ExportTraceServiceRequest {
+                resource_spans: vec![Default::default()],
+            };
+            let logs = ExportLogsServiceRequest {
+                resource_logs: vec![Default::default()],
+            };
+            Ok(vec![
+                TelemetryData {
+                    payload: spans.encode_to_vec(),
+                    content_type: "application/x-protobuf".to_string(),
+                    ..Default::default()
+                },
+                TelemetryData {
+                    payload: logs.encode_to_vec(),
+                    content_type: "application/x-protobuf".to_string(),
+                    signal: Signal::Logs,
+                    ..Default::default()
+                },
+            ])
+        }
+    }
+
     struct MockFailingParser;
     impl EventParser for MockFailingParser {
         type EventInput = MockEventInput;
@@ -217,6 +980,7 @@ mod tests {
         let event = MockEventInput {
             records: vec!["data1".to_string(), "data2".to_string()],
             produce_valid_otlp_for_compaction: true,
+            batch_ids: vec![],
         };
         let compaction_config = SpanCompactionConfig::default();
 
@@ -248,6 +1012,76 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_process_event_batch_with_config() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // The resolved configuration is used instead of the environment
+        let _g = EnvVarGuard::set("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT", "not a url");
+        let config = ForwarderConfig::resolve(&crate::ForwarderSettings {
+            endpoint: Some(format!("{}/v1/traces", server.uri())),
+            ..Default::default()
+        })
+        .unwrap();
+        let event = MockEventInput {
+            records: vec!["data1".to_string(), "data2".to_string()],
+            produce_valid_otlp_for_compaction: true,
+            batch_ids: vec![],
+        };
+
+        let outcome = process_event_batch_with_config(
+            event,
+            &MockSuccessfulParser,
+            "test_source",
+            &ReqwestClient::new(),
+            &config,
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome.records_parsed, 2);
+        assert_eq!(outcome.records_skipped, 0);
+        assert_eq!(outcome.spans_forwarded, 2);
+        assert_eq!(outcome.send_attempts, 1);
+        assert!(outcome.bytes_sent > 0);
+    }
+
+    #[tokio::test]
+    async fn test_process_event_batch_sends_one_batch_per_signal() {
+        let server = MockServer::start().await;
+        for signal_path in ["/v1/traces", "/v1/logs"] {
+            Mock::given(method("POST"))
+                .and(path(signal_path))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        let config = ForwarderConfig::resolve(&crate::ForwarderSettings {
+            endpoint: Some(format!("{}/v1/traces", server.uri())),
+            ..Default::default()
+        })
+        .unwrap();
+
+        process_event_batch_with_dedup(
+            (),
+            &MockMixedSignalParser,
+            "test_source",
+            &ReqwestClient::new(),
+            &config.compaction,
+            Some(&config),
+            None,
+        )
+        .await
+        .unwrap();
+    }
+
     #[tokio::test]
     #[sealed_test]
     async fn test_process_event_batch_parser_fails() {
@@ -256,6 +1090,7 @@ mod tests {
         let event = MockEventInput {
             records: vec!["data1".to_string()],
             produce_valid_otlp_for_compaction: false,
+            batch_ids: vec![],
         };
         let compaction_config = SpanCompactionConfig::default();
 
@@ -285,6 +1120,7 @@ mod tests {
         let event = MockEventInput {
             records: vec![],
             produce_valid_otlp_for_compaction: false,
+            batch_ids: vec![],
         };
         let compaction_config = SpanCompactionConfig::default();
 
@@ -315,6 +1151,33 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_process_event_batch_counts_rejected_records() {
+        let config = ForwarderConfig::resolve(&crate::ForwarderSettings {
+            endpoint: Some("http://localhost:4318/v1/traces".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        let records = vec![
+            serde_json::json!({"__otel_otlp_stdout": "broken"}).to_string(),
+            "plain application log".to_string(),
+        ];
+
+        let outcome = process_event_batch_with_dedup(
+            records,
+            &crate::core_parser::ParserRegistry::with_defaults(),
+            "test_source",
+            &ReqwestClient::new(),
+            &config.compaction,
+            Some(&config),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome.records_parsed, 0);
+        assert_eq!(outcome.records_failed, 1);
+    }
+
     #[tokio::test]
     #[sealed_test]
     async fn test_process_event_batch_sender_fails() {
@@ -324,6 +1187,7 @@ mod tests {
         let event = MockEventInput {
             records: vec!["data1".to_string()],
             produce_valid_otlp_for_compaction: true,
+            batch_ids: vec![],
         };
         let compaction_config = SpanCompactionConfig::default();
 
@@ -338,6 +1202,7 @@ mod tests {
             "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
             &format!("{}/v1/traces", server.uri()),
         );
+        let _g_retry = EnvVarGuard::set(crate::retry::MAX_ATTEMPTS_ENV_VAR, "1");
 
         let result = process_event_batch(
             event,
@@ -354,4 +1219,382 @@ mod tests {
             .to_string()
             .contains("Sending telemetry batch failed"));
     }
+
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_process_event_batch_drops_duplicate_batches() {
+        let server = MockServer::start().await;
+        let http_client = ReqwestClient::new();
+        let parser = MockSuccessfulParser;
+        let compaction_config = SpanCompactionConfig::default();
+        let deduplicator = BatchDeduplicator::new(std::time::Duration::from_secs(60), 100);
+        let event = MockEventInput {
+            records: vec!["data1".to_string(), "data1".to_string()],
+            produce_valid_otlp_for_compaction: true,
+            batch_ids: vec!["batch-1".to_string(), "batch-1".to_string()],
+        };
+
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let _g = EnvVarGuard::set(
+            "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+            &format!("{}/v1/traces", server.uri()),
+        );
+
+        // The repeated id within the same event is dropped, the first one is sent
+        let outcome = process_event_batch_with_dedup(
+            event.clone(),
+            &parser,
+            "test_source",
+            &http_client,
+            &compaction_config,
+            None,
+            Some(&deduplicator),
+        )
+        .await
+        .unwrap();
+        assert!(deduplicator.contains("batch-1"));
+        assert_eq!(outcome.records_parsed, 2);
+        assert_eq!(outcome.records_skipped, 1);
+        assert_eq!(outcome.spans_forwarded, 1);
+
+        // A redelivery of the same event is dropped entirely
+        let outcome = process_event_batch_with_dedup(
+            event,
+            &parser,
+            "test_source",
+            &http_client,
+            &compaction_config,
+            None,
+            Some(&deduplicator),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            outcome,
+            BatchOutcome {
+                records_parsed: 2,
+                records_skipped: 2,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_process_event_batch_does_not_record_failed_batches() {
+        let server = MockServer::start().await;
+        let http_client = ReqwestClient::new();
+        let parser = MockSuccessfulParser;
+        let compaction_config = SpanCompactionConfig::default();
+        let deduplicator = BatchDeduplicator::new(std::time::Duration::from_secs(60), 100);
+        let event = MockEventInput {
+            records: vec!["data1".to_string()],
+            produce_valid_otlp_for_compaction: true,
+            batch_ids: vec!["batch-1".to_string()],
+        };
+
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let _g = EnvVarGuard::set(
+            "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+            &format!("{}/v1/traces", server.uri()),
+        );
+        let _g_retry = EnvVarGuard::set(crate::retry::MAX_ATTEMPTS_ENV_VAR, "1");
+
+        let result = process_event_batch_with_dedup(
+            event,
+            &parser,
+            "test_source",
+            &http_client,
+            &compaction_config,
+            None,
+            Some(&deduplicator),
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert!(!deduplicator.contains("batch-1"));
+    }
+
+    #[tokio::test]
+    async fn test_process_event_batch_forwards_spans_of_failed_batches_again() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let mut config = ForwarderConfig::resolve(&crate::ForwarderSettings {
+            endpoint: Some(format!("{}/v1/traces", server.uri())),
+            retry_max_attempts: Some(1),
+            ..Default::default()
+        })
+        .unwrap();
+        config.compaction.span_deduplicator =
+            Some(std::sync::Arc::new(crate::SpanDeduplicator::new(100)));
+        let event = || MockEventInput {
+            records: vec!["data1".to_string(), "data2".to_string()],
+            produce_valid_otlp_for_compaction: true,
+            batch_ids: vec![],
+        };
+        let http_client = ReqwestClient::new();
+        let process = || {
+            process_event_batch_with_dedup(
+                event(),
+                &MockSuccessfulParser,
+                "test_source",
+                &http_client,
+                &config.compaction,
+                Some(&config),
+                None,
+            )
+        };
+
+        // The spans of the failed send are not recorded, so the redelivery forwards them
+        assert!(process().await.is_err());
+        let outcome = process().await.unwrap();
+        assert_eq!(outcome.spans_forwarded, 2);
+
+        // Once delivered, a further redelivery is dropped
+        let outcome = process().await.unwrap();
+        assert_eq!(outcome.spans_forwarded, 0);
+        assert_eq!(outcome.send_attempts, 0);
+    }
+
+    /// Records dead letters, or fails to persist them
+    #[derive(Debug, Default)]
+    struct RecordingSink {
+        fail: bool,
+        dead_letters: std::sync::Mutex<Vec<DeadLetter>>,
+    }
+
+    #[async_trait::async_trait]
+    impl DeadLetterSink for RecordingSink {
+        async fn persist(&self, dead_letter: &DeadLetter) -> Result<()> {
+            if self.fail {
+                return Err(anyhow!("sink unavailable"));
+            }
+            self.dead_letters.lock().unwrap().push(dead_letter.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_event_batch_persists_undelivered_batches() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&server)
+            .await;
+        let settings = crate::ForwarderSettings {
+            endpoint: Some(format!("{}/v1/traces", server.uri())),
+            ..Default::default()
+        };
+        let event = || MockEventInput {
+            records: vec!["data1".to_string()],
+            produce_valid_otlp_for_compaction: true,
+            batch_ids: vec!["batch-1".to_string()],
+        };
+
+        // A persisted batch is handled and recorded as delivered
+        let sink = std::sync::Arc::new(RecordingSink::default());
+        let mut config = ForwarderConfig::resolve(&settings).unwrap();
+        config.dead_letter_sink = Some(sink.clone());
+        let deduplicator = BatchDeduplicator::new(std::time::Duration::from_secs(60), 100);
+        process_event_batch_with_dedup(
+            event(),
+            &MockSuccessfulParser,
+            "test_source",
+            &ReqwestClient::new(),
+            &config.compaction,
+            Some(&config),
+            Some(&deduplicator),
+        )
+        .await
+        .unwrap();
+        let dead_letters = sink.dead_letters.lock().unwrap().clone();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].destination, config.destination.name());
+        assert_eq!(dead_letters[0].signal, Signal::Traces);
+        assert!(deduplicator.contains("batch-1"));
+
+        // A batch that cannot be persisted either fails the invocation
+        let config = ForwarderConfig::resolve(&settings)
+            .unwrap()
+            .with_dead_letter_sink(RecordingSink {
+                fail: true,
+                ..Default::default()
+            });
+        let deduplicator = BatchDeduplicator::new(std::time::Duration::from_secs(60), 100);
+        let result = process_event_batch_with_dedup(
+            event(),
+            &MockSuccessfulParser,
+            "test_source",
+            &ReqwestClient::new(),
+            &config.compaction,
+            Some(&config),
+            Some(&deduplicator),
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(!deduplicator.contains("batch-1"));
+    }
+
+    #[tokio::test]
+    #[sealed_test]
+    async fn test_process_event_batch_to_destinations() {
+        let healthy = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&healthy)
+            .await;
+        let failing = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(2)
+            .mount(&failing)
+            .await;
+        let _g_retry = EnvVarGuard::set(crate::retry::MAX_ATTEMPTS_ENV_VAR, "1");
+
+        let destination = |name: &str, server: &MockServer| {
+            Destination::new(
+                name,
+                url::Url::parse(&format!("{}/v1/traces", server.uri())).unwrap(),
+            )
+        };
+        let deduplicator = BatchDeduplicator::new(std::time::Duration::from_secs(60), 100);
+        let event = MockEventInput {
+            records: vec!["data1".to_string()],
+            produce_valid_otlp_for_compaction: true,
+            batch_ids: vec!["batch-1".to_string()],
+        };
+
+        // A partial failure is reported, not returned as an error
+        let report = process_event_batch_to_destinations_with_dedup(
+            event.clone(),
+            &MockSuccessfulParser,
+            "test_source",
+            &ReqwestClient::new(),
+            &SpanCompactionConfig::default(),
+            &[
+                destination("vendor", &healthy),
+                destination("self-hosted", &failing),
+            ],
+            Some(&deduplicator),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.succeeded_count(), 1);
+        assert_eq!(report.failures().next().unwrap().name, "self-hosted");
+        assert_eq!(report.outcome.records_parsed, 1);
+        assert_eq!(report.outcome.spans_forwarded, 1);
+        assert_eq!(report.outcome.send_attempts, 2);
+        assert!(report.outcome.bytes_sent > 0);
+        assert!(deduplicator.contains("batch-1"));
+
+        // Failing everywhere is an error
+        let result = process_event_batch_to_destinations_with_dedup(
+            event,
+            &MockSuccessfulParser,
+            "test_source",
+            &ReqwestClient::new(),
+            &SpanCompactionConfig::default(),
+            &[destination("self-hosted", &failing)],
+            None,
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    /// Collects the batches it is sent, or fails to deliver them
+    #[derive(Debug, Default)]
+    struct RecordingDestination {
+        fail: bool,
+        sent: std::sync::Mutex<Vec<TelemetryData>>,
+    }
+
+    #[async_trait::async_trait]
+    impl TelemetryDestination for RecordingDestination {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn send(
+            &self,
+            telemetry_data: TelemetryData,
+        ) -> Result<crate::http_sender::ExportOutcome> {
+            if self.fail {
+                return Err(anyhow!("destination unavailable"));
+            }
+            self.sent.lock().unwrap().push(telemetry_data);
+            Ok(crate::http_sender::ExportOutcome::Accepted)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_event_batch_to_telemetry_destination() {
+        let deduplicator = BatchDeduplicator::new(std::time::Duration::from_secs(60), 100);
+        let event = MockEventInput {
+            records: vec!["data1".to_string(), "data2".to_string()],
+            produce_valid_otlp_for_compaction: true,
+            batch_ids: vec!["batch-1".to_string()],
+        };
+
+        let failing = RecordingDestination {
+            fail: true,
+            ..Default::default()
+        };
+        let err = process_event_batch_to_telemetry_destination_with_dedup(
+            event.clone(),
+            &MockSuccessfulParser,
+            "test_source",
+            &failing,
+            &SpanCompactionConfig::default(),
+            Some(&deduplicator),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Sending telemetry batch failed");
+        assert!(!deduplicator.contains("batch-1"));
+
+        let destination = RecordingDestination::default();
+        let outcome = process_event_batch_to_telemetry_destination_with_dedup(
+            event,
+            &MockSuccessfulParser,
+            "test_source",
+            &destination,
+            &SpanCompactionConfig::default(),
+            Some(&deduplicator),
+        )
+        .await
+        .unwrap();
+        let sent = destination.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(outcome.send_attempts, 1);
+        assert_eq!(outcome.bytes_sent, sent[0].payload.len());
+        assert!(deduplicator.contains("batch-1"));
+    }
 }
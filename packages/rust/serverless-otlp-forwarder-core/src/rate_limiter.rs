@@ -0,0 +1,325 @@
+//! Adaptive rate limiting of OTLP exports.
+//!
+//! Collectors and vendor endpoints shed load with `429 Too Many Requests` or
+//! `503 Service Unavailable`. Retrying each batch on its own only spreads the load over the retry
+//! budget; concurrent invocations keep hitting the endpoint at full speed. [`AdaptiveRateLimiter`]
+//! keeps a minimum interval between sends per endpoint instead: every throttled response doubles
+//! it (or raises it to the server's `Retry-After`), every accepted export halves it, until sends
+//! are no longer delayed at all.
+//!
+//! The processor applies the process-wide limiter from [`AdaptiveRateLimiter::global`] to every
+//! attempt, so the pace learned by one invocation carries over to the next ones handled by the
+//! same Lambda execution environment.
+
+use crate::circuit_breaker::endpoint_key;
+use crate::http_sender::{ExportOutcome, HttpForwarderResponse, HttpOtlpForwarderClient};
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+use url::Url;
+
+/// Environment variable for the longest interval between sends to a throttling endpoint, in
+/// milliseconds (`0` disables rate limiting)
+pub const MAX_INTERVAL_ENV_VAR: &str = "OTLP_FORWARDER_RATE_LIMIT_MAX_INTERVAL_MS";
+/// Default longest interval between sends to a throttling endpoint
+pub const DEFAULT_MAX_INTERVAL: Duration = Duration::from_secs(5);
+/// Interval applied after the first throttled response; shorter intervals are dropped
+pub const MIN_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Pace of the sends to a single endpoint
+#[derive(Debug, Default, Clone, Copy)]
+struct EndpointPace {
+    /// Minimum time between two sends
+    interval: Duration,
+    /// Earliest time of the next send
+    next_send: Option<Instant>,
+}
+
+/// Per-endpoint send pacing that adapts to throttling responses
+#[derive(Debug)]
+pub struct AdaptiveRateLimiter {
+    max_interval: Duration,
+    endpoints: Mutex<HashMap<String, EndpointPace>>,
+}
+
+impl AdaptiveRateLimiter {
+    /// Creates a limiter that never spaces sends by more than `max_interval`
+    pub fn new(max_interval: Duration) -> Self {
+        Self {
+            max_interval,
+            endpoints: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Creates a limiter from `OTLP_FORWARDER_RATE_LIMIT_MAX_INTERVAL_MS`
+    ///
+    /// Returns `None` when the interval is set to `0`. Invalid values fall back to the default.
+    pub fn from_env() -> Option<Self> {
+        let max_interval = match env::var(MAX_INTERVAL_ENV_VAR) {
+            Ok(value) => value
+                .trim()
+                .parse::<u64>()
+                .map(Duration::from_millis)
+                .unwrap_or_else(|_| {
+                    warn!(
+                        env_var = MAX_INTERVAL_ENV_VAR,
+                        default_max_interval_ms = DEFAULT_MAX_INTERVAL.as_millis() as u64,
+                        "Failed to parse rate limit interval; using default"
+                    );
+                    DEFAULT_MAX_INTERVAL
+                }),
+            Err(_) => DEFAULT_MAX_INTERVAL,
+        };
+
+        (!max_interval.is_zero()).then(|| Self::new(max_interval))
+    }
+
+    /// Returns the process-wide limiter, initialised from the environment on first use
+    pub fn global() -> Option<&'static Self> {
+        static GLOBAL: OnceLock<Option<AdaptiveRateLimiter>> = OnceLock::new();
+        GLOBAL.get_or_init(Self::from_env).as_ref()
+    }
+
+    /// Returns the current minimum interval between sends to `endpoint`
+    pub fn interval(&self, endpoint: &Url) -> Duration {
+        self.endpoints
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&endpoint_key(endpoint))
+            .map_or(Duration::ZERO, |pace| pace.interval)
+    }
+
+    /// Reserves the next send slot for `endpoint` and waits for it
+    pub async fn until_ready(&self, endpoint: &Url) {
+        let delay = self.reserve_at(&endpoint_key(endpoint), Instant::now());
+        if !delay.is_zero() {
+            debug!(
+                delay_ms = delay.as_millis() as u64,
+                "Delaying OTLP export to a throttling endpoint"
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Adapts the pace of `endpoint` to the outcome of an export
+    pub fn observe(&self, endpoint: &Url, outcome: &ExportOutcome) {
+        self.observe_at(&endpoint_key(endpoint), outcome, Instant::now());
+    }
+
+    fn reserve_at(&self, endpoint: &str, now: Instant) -> Duration {
+        let mut endpoints = self.endpoints.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(pace) = endpoints.get_mut(endpoint) else {
+            return Duration::ZERO;
+        };
+        let start = pace.next_send.map_or(now, |next_send| next_send.max(now));
+        // Queued sends never wait longer than the maximum interval
+        let delay = start.duration_since(now).min(self.max_interval);
+        pace.next_send = Some(now + delay + pace.interval);
+        delay
+    }
+
+    fn observe_at(&self, endpoint: &str, outcome: &ExportOutcome, now: Instant) {
+        let mut endpoints = self.endpoints.lock().unwrap_or_else(|e| e.into_inner());
+        match outcome {
+            ExportOutcome::Throttled { retry_after } => {
+                let pace = endpoints.entry(endpoint.to_string()).or_default();
+                pace.interval = pace
+                    .interval
+                    .saturating_mul(2)
+                    .max(MIN_INTERVAL)
+                    .min(self.max_interval);
+                let hold_off = retry_after
+                    .unwrap_or_default()
+                    .min(self.max_interval)
+                    .max(pace.interval);
+                pace.next_send = Some(
+                    pace.next_send
+                        .map_or(now + hold_off, |next_send| next_send.max(now + hold_off)),
+                );
+                debug!(
+                    interval_ms = pace.interval.as_millis() as u64,
+                    hold_off_ms = hold_off.as_millis() as u64,
+                    "OTLP endpoint throttled exports; slowing down"
+                );
+            }
+            ExportOutcome::Accepted | ExportOutcome::PartiallyRejected { .. } => {
+                let Some(pace) = endpoints.get_mut(endpoint) else {
+                    return;
+                };
+                pace.interval /= 2;
+                if pace.interval < MIN_INTERVAL {
+                    endpoints.remove(endpoint);
+                    debug!("OTLP endpoint no longer throttled");
+                }
+            }
+        }
+    }
+}
+
+/// An [`HttpOtlpForwarderClient`] that paces every attempt through an optional
+/// [`AdaptiveRateLimiter`]
+pub(crate) struct RateLimitedClient<'a, C> {
+    inner: &'a C,
+    limiter: Option<&'a AdaptiveRateLimiter>,
+}
+
+impl<'a, C> RateLimitedClient<'a, C> {
+    pub(crate) fn new(inner: &'a C, limiter: Option<&'a AdaptiveRateLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+#[async_trait]
+impl<C: HttpOtlpForwarderClient> HttpOtlpForwarderClient for RateLimitedClient<'_, C> {
+    async fn post_telemetry(
+        &self,
+        target_url: Url,
+        headers: HeaderMap,
+        payload: Bytes,
+        timeout: Duration,
+    ) -> Result<HttpForwarderResponse> {
+        let Some(limiter) = self.limiter else {
+            return self
+                .inner
+                .post_telemetry(target_url, headers, payload, timeout)
+                .await;
+        };
+
+        limiter.until_ready(&target_url).await;
+        let endpoint = target_url.clone();
+        let result = self
+            .inner
+            .post_telemetry(target_url, headers, payload, timeout)
+            .await;
+        if let Some(outcome) = result.as_ref().ok().and_then(ExportOutcome::from_response) {
+            limiter.observe(&endpoint, &outcome);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::StatusCode;
+    use serial_test::serial;
+
+    const ENDPOINT: &str = "https://collector.example.com/v1/traces";
+
+    fn throttled(retry_after: Option<Duration>) -> ExportOutcome {
+        ExportOutcome::Throttled { retry_after }
+    }
+
+    #[test]
+    fn test_throttling_slows_down_and_success_recovers() {
+        let limiter = AdaptiveRateLimiter::new(Duration::from_secs(1));
+        let now = Instant::now();
+        assert_eq!(limiter.reserve_at(ENDPOINT, now), Duration::ZERO);
+
+        limiter.observe_at(ENDPOINT, &throttled(None), now);
+        limiter.observe_at(ENDPOINT, &throttled(None), now);
+        assert_eq!(
+            limiter.interval(&Url::parse(ENDPOINT).unwrap()),
+            MIN_INTERVAL * 2
+        );
+        // Sends are spaced by the interval, one after the other
+        assert_eq!(limiter.reserve_at(ENDPOINT, now), MIN_INTERVAL * 2);
+        assert_eq!(limiter.reserve_at(ENDPOINT, now), MIN_INTERVAL * 4);
+
+        limiter.observe_at(ENDPOINT, &ExportOutcome::Accepted, now);
+        assert_eq!(
+            limiter.interval(&Url::parse(ENDPOINT).unwrap()),
+            MIN_INTERVAL
+        );
+        limiter.observe_at(
+            ENDPOINT,
+            &ExportOutcome::PartiallyRejected { rejected_items: 1 },
+            now,
+        );
+        assert_eq!(limiter.reserve_at(ENDPOINT, now), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_retry_after_and_max_interval() {
+        let limiter = AdaptiveRateLimiter::new(Duration::from_secs(2));
+        let now = Instant::now();
+
+        limiter.observe_at(ENDPOINT, &throttled(Some(Duration::from_secs(1))), now);
+        assert_eq!(limiter.reserve_at(ENDPOINT, now), Duration::from_secs(1));
+        // Other endpoints are not affected
+        assert_eq!(
+            limiter.reserve_at("https://other.example.com/v1/traces", now),
+            Duration::ZERO
+        );
+
+        limiter.observe_at(ENDPOINT, &throttled(Some(Duration::from_secs(60))), now);
+        assert_eq!(limiter.reserve_at(ENDPOINT, now), Duration::from_secs(2));
+        for _ in 0..10 {
+            limiter.observe_at(ENDPOINT, &throttled(None), now);
+        }
+        assert_eq!(
+            limiter.interval(&Url::parse(ENDPOINT).unwrap()),
+            Duration::from_secs(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_client_observes_responses() {
+        struct ThrottlingClient;
+
+        #[async_trait]
+        impl HttpOtlpForwarderClient for ThrottlingClient {
+            async fn post_telemetry(
+                &self,
+                _target_url: Url,
+                _headers: HeaderMap,
+                _payload: Bytes,
+                _timeout: Duration,
+            ) -> Result<HttpForwarderResponse> {
+                Ok(HttpForwarderResponse::new(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    String::new(),
+                ))
+            }
+        }
+
+        let limiter = AdaptiveRateLimiter::new(Duration::from_secs(1));
+        let client = RateLimitedClient::new(&ThrottlingClient, Some(&limiter));
+        let endpoint = Url::parse(ENDPOINT).unwrap();
+        client
+            .post_telemetry(
+                endpoint.clone(),
+                HeaderMap::new(),
+                Bytes::new(),
+                Duration::from_secs(1),
+            )
+            .await
+            .unwrap();
+        assert_eq!(limiter.interval(&endpoint), MIN_INTERVAL);
+    }
+
+    #[test]
+    #[serial]
+    fn test_from_env() {
+        env::set_var(MAX_INTERVAL_ENV_VAR, "0");
+        assert!(AdaptiveRateLimiter::from_env().is_none());
+
+        env::set_var(MAX_INTERVAL_ENV_VAR, "250");
+        let limiter = AdaptiveRateLimiter::from_env().unwrap();
+        assert_eq!(limiter.max_interval, Duration::from_millis(250));
+
+        env::set_var(MAX_INTERVAL_ENV_VAR, "soon");
+        let limiter = AdaptiveRateLimiter::from_env().unwrap();
+        assert_eq!(limiter.max_interval, DEFAULT_MAX_INTERVAL);
+
+        env::remove_var(MAX_INTERVAL_ENV_VAR);
+        let limiter = AdaptiveRateLimiter::from_env().unwrap();
+        assert_eq!(limiter.max_interval, DEFAULT_MAX_INTERVAL);
+    }
+}
@@ -0,0 +1,198 @@
+//! Retry policy for OTLP exports.
+//!
+//! [`send_telemetry_batch`](crate::send_telemetry_batch) retries transient failures (transport
+//! errors, `5xx` responses and `429 Too Many Requests`) with exponential backoff, so a single
+//! network blip or collector restart does not lose the whole batch. The number of attempts, the
+//! base delay, jitter and an overall time budget are configurable through environment variables
+//! or programmatically with [`send_telemetry_batch_with_retry`](crate::send_telemetry_batch_with_retry).
+
+use crate::config::ForwarderSettings;
+use http::StatusCode;
+use rand::Rng;
+use std::time::Duration;
+
+/// Environment variable for the maximum number of attempts, including the first one (`1` disables retries)
+pub const MAX_ATTEMPTS_ENV_VAR: &str = "OTLP_FORWARDER_RETRY_MAX_ATTEMPTS";
+/// Environment variable for the base backoff delay in milliseconds
+pub const BASE_DELAY_ENV_VAR: &str = "OTLP_FORWARDER_RETRY_BASE_DELAY_MS";
+/// Environment variable enabling full jitter on the backoff delay (`true` or `false`)
+pub const JITTER_ENV_VAR: &str = "OTLP_FORWARDER_RETRY_JITTER";
+/// Environment variable for the retry budget in milliseconds
+pub const BUDGET_ENV_VAR: &str = "OTLP_FORWARDER_RETRY_BUDGET_MS";
+
+/// Default maximum number of attempts
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+/// Default base backoff delay
+pub const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(100);
+/// Default retry budget
+pub const DEFAULT_RETRY_BUDGET: Duration = Duration::from_secs(15);
+
+/// Retry policy applied to a single OTLP export
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// Maximum number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every following retry
+    pub base_delay: Duration,
+    /// Whether to randomize each delay between zero and the computed backoff
+    pub jitter: bool,
+    /// Wall-clock time, measured from the first attempt, after which no retry is started
+    pub retry_budget: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: DEFAULT_BASE_DELAY,
+            jitter: true,
+            retry_budget: DEFAULT_RETRY_BUDGET,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// A policy that makes a single attempt
+    pub fn disabled() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Resolves the policy from the `OTLP_FORWARDER_RETRY_*` environment variables
+    ///
+    /// Unset variables use the defaults; invalid values are logged and fall back to the defaults.
+    pub fn from_env() -> Self {
+        ForwarderSettings::retry_from_env().retry_config()
+    }
+
+    /// Returns the delay before the next attempt, or `None` if the export should not be retried
+    ///
+    /// `attempt` is the number of the attempt that just failed (starting at 1) and `elapsed` the
+    /// time since the first attempt started. A server provided `Retry-After` delay is used as is;
+    /// otherwise the delay grows exponentially from `base_delay`.
+    pub fn next_delay(
+        &self,
+        attempt: u32,
+        retry_after: Option<Duration>,
+        elapsed: Duration,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let delay = retry_after.unwrap_or_else(|| self.backoff_delay(attempt));
+        (elapsed.saturating_add(delay) <= self.retry_budget).then_some(delay)
+    }
+
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor);
+        if self.jitter {
+            let max_ms = delay.as_millis().min(u64::MAX as u128) as u64;
+            Duration::from_millis(rand::rng().random_range(0..=max_ms))
+        } else {
+            delay
+        }
+    }
+}
+
+/// Returns true for HTTP statuses worth retrying: `429 Too Many Requests` and any `5xx`
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::env;
+
+    fn no_jitter() -> RetryConfig {
+        RetryConfig {
+            jitter: false,
+            ..RetryConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_next_delay_backs_off_exponentially() {
+        let config = RetryConfig {
+            max_attempts: 4,
+            ..no_jitter()
+        };
+        assert_eq!(
+            config.next_delay(1, None, Duration::ZERO),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            config.next_delay(2, None, Duration::ZERO),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(
+            config.next_delay(3, None, Duration::ZERO),
+            Some(Duration::from_millis(400))
+        );
+        assert_eq!(config.next_delay(4, None, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn test_next_delay_honors_retry_after_and_budget() {
+        let config = no_jitter();
+        assert_eq!(
+            config.next_delay(1, Some(Duration::from_secs(2)), Duration::ZERO),
+            Some(Duration::from_secs(2))
+        );
+        // The delay would exceed the budget
+        assert_eq!(
+            config.next_delay(1, Some(Duration::from_secs(2)), Duration::from_secs(14)),
+            None
+        );
+        assert_eq!(config.next_delay(1, None, Duration::from_secs(15)), None);
+    }
+
+    #[test]
+    fn test_jitter_stays_within_backoff() {
+        let config = RetryConfig::default();
+        for _ in 0..100 {
+            let delay = config.next_delay(2, None, Duration::ZERO).unwrap();
+            assert!(delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn test_is_retryable_status() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable_status(StatusCode::BAD_REQUEST));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+    }
+
+    #[test]
+    #[serial]
+    fn test_from_env() {
+        env::set_var(MAX_ATTEMPTS_ENV_VAR, "5");
+        env::set_var(BASE_DELAY_ENV_VAR, "20");
+        env::set_var(JITTER_ENV_VAR, "false");
+        env::set_var(BUDGET_ENV_VAR, "not-a-number");
+
+        let config = RetryConfig::from_env();
+        assert_eq!(config.max_attempts, 5);
+        assert_eq!(config.base_delay, Duration::from_millis(20));
+        assert!(!config.jitter);
+        assert_eq!(config.retry_budget, DEFAULT_RETRY_BUDGET);
+
+        env::set_var(MAX_ATTEMPTS_ENV_VAR, "0");
+        assert_eq!(RetryConfig::from_env().max_attempts, 1);
+
+        for var in [
+            MAX_ATTEMPTS_ENV_VAR,
+            BASE_DELAY_ENV_VAR,
+            JITTER_ENV_VAR,
+            BUDGET_ENV_VAR,
+        ] {
+            env::remove_var(var);
+        }
+        assert_eq!(RetryConfig::from_env(), RetryConfig::default());
+    }
+}
@@ -0,0 +1,455 @@
+//! S3 object ingestion, for batch and backfill forwarding.
+//!
+//! Logs exported to S3, rather than streamed through a live subscription, can be forwarded by a
+//! Lambda function triggered by S3 event notifications. [`S3Ingestor`] downloads each object of an
+//! [`S3Event`] with a SigV4-signed request and streams it: the object is decompressed on the fly
+//! when it is gzip-compressed, split into newline-delimited records, and handed to the parser in
+//! chunks of [`S3Ingestor::with_chunk_lines`] records. Each chunk is then compacted and sent like
+//! a regular event batch, so memory stays bounded regardless of the object size.
+//!
+//! Records are expected to be exporter envelopes or OTLP/JSON requests, one per line. Lines in the
+//! CloudWatch Logs export format, where the record follows a timestamp, are accepted as well.
+//!
+//! Requires the `s3-ingest` feature.
+
+use crate::config::ForwarderConfig;
+use crate::core_parser::EventParser;
+use crate::http_sender::HttpOtlpForwarderClient;
+use crate::kinesis::is_gzip;
+use crate::processor::process_event_batch_with_config;
+use crate::sigv4::SigV4Signer;
+use anyhow::{anyhow, Context, Result};
+use aws_credential_types::provider::ProvideCredentials;
+use aws_lambda_events::event::s3::S3Event;
+use flate2::write::GzDecoder;
+use reqwest::header::HeaderMap;
+use reqwest::Client as ReqwestClient;
+use std::fmt;
+use std::io::Write;
+use std::mem;
+use tracing::{debug, info, instrument, warn};
+use url::Url;
+
+/// Default number of records parsed and forwarded together
+pub const DEFAULT_CHUNK_LINES: usize = 1000;
+
+/// Downloads S3 objects of newline-delimited records and forwards them in chunks
+#[derive(Clone)]
+pub struct S3Ingestor {
+    client: ReqwestClient,
+    endpoint: Option<Url>,
+    signer: SigV4Signer,
+    chunk_lines: usize,
+}
+
+impl S3Ingestor {
+    /// Creates an ingestor reading from S3 in `region`, signing with `credentials`
+    pub fn new(credentials: impl ProvideCredentials + 'static, region: impl Into<String>) -> Self {
+        Self {
+            client: ReqwestClient::new(),
+            endpoint: None,
+            signer: SigV4Signer::for_s3(credentials, region),
+            chunk_lines: DEFAULT_CHUNK_LINES,
+        }
+    }
+
+    /// Reads from an S3-compatible endpoint with path-style URLs instead of the regional
+    /// virtual-hosted S3 endpoint
+    pub fn with_endpoint(mut self, endpoint: Url) -> Self {
+        self.endpoint = Some(endpoint);
+        self
+    }
+
+    /// Sets the number of records parsed and forwarded together (at least 1)
+    pub fn with_chunk_lines(mut self, chunk_lines: usize) -> Self {
+        self.chunk_lines = chunk_lines.max(1);
+        self
+    }
+
+    fn object_url(&self, bucket: &str, key: &str) -> Result<Url> {
+        let url = match &self.endpoint {
+            Some(endpoint) => {
+                let mut url = endpoint.clone();
+                url.set_path(&format!(
+                    "{}/{}/{}",
+                    endpoint.path().trim_end_matches('/'),
+                    bucket,
+                    key
+                ));
+                url
+            }
+            None => Url::parse(&format!(
+                "https://{}.s3.{}.amazonaws.com/{}",
+                bucket,
+                self.signer.region(),
+                key
+            ))
+            .context("Invalid S3 bucket name")?,
+        };
+        Ok(url)
+    }
+
+    /// Forwards the records of every object of an S3 event notification, returning the number of
+    /// records read
+    ///
+    /// Objects are processed in order and the first failure is returned, so that the invocation
+    /// is retried. Chunks already forwarded are then skipped by batch deduplication when their
+    /// records carry a batch id.
+    #[instrument(name = "processor/ingest_s3_event", skip_all, fields(s3.objects.count = event.records.len()))]
+    pub async fn ingest_event<P, C>(
+        &self,
+        event: S3Event,
+        parser: &P,
+        http_client: &C,
+        config: &ForwarderConfig,
+    ) -> Result<usize>
+    where
+        P: EventParser<EventInput = Vec<String>> + Sync + Send,
+        C: HttpOtlpForwarderClient,
+    {
+        let mut records_count = 0;
+        for record in event.records {
+            let object = record.s3.object;
+            let bucket = record
+                .s3
+                .bucket
+                .name
+                .ok_or_else(|| anyhow!("S3 event record has no bucket name"))?;
+            let key = match object.url_decoded_key {
+                Some(key) => key,
+                None => decode_object_key(
+                    &object
+                        .key
+                        .ok_or_else(|| anyhow!("S3 event record has no object key"))?,
+                ),
+            };
+            records_count += self
+                .ingest_object(&bucket, &key, parser, http_client, config)
+                .await?;
+        }
+        Ok(records_count)
+    }
+
+    /// Forwards the records of one object, returning the number of records read
+    pub async fn ingest_object<P, C>(
+        &self,
+        bucket: &str,
+        key: &str,
+        parser: &P,
+        http_client: &C,
+        config: &ForwarderConfig,
+    ) -> Result<usize>
+    where
+        P: EventParser<EventInput = Vec<String>> + Sync + Send,
+        C: HttpOtlpForwarderClient,
+    {
+        let target_url = self.object_url(bucket, key)?;
+        let headers = self
+            .signer
+            .signature_headers("GET", &target_url, &HeaderMap::new(), &[])
+            .await?;
+        let mut response = self
+            .client
+            .get(target_url)
+            .headers(headers)
+            .send()
+            .await
+            .context("Failed to read object from S3")?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "S3 rejected object read with status {}",
+                response.status().as_u16()
+            ));
+        }
+
+        let source = format!("s3://{bucket}/{key}");
+        let mut decoder = RecordDecoder::default();
+        let mut chunk = Vec::with_capacity(self.chunk_lines);
+        let mut records_count = 0;
+        let mut chunks_count = 0;
+        loop {
+            let bytes = response
+                .chunk()
+                .await
+                .context("Failed to read object from S3")?;
+            let records = match &bytes {
+                Some(bytes) => decoder.push(bytes)?,
+                None => decoder.finish()?,
+            };
+            for record in records {
+                chunk.push(record);
+                if chunk.len() >= self.chunk_lines {
+                    records_count += chunk.len();
+                    chunks_count += 1;
+                    let records = mem::replace(&mut chunk, Vec::with_capacity(self.chunk_lines));
+                    process_event_batch_with_config(records, parser, &source, http_client, config)
+                        .await?;
+                }
+            }
+            if bytes.is_none() {
+                break;
+            }
+        }
+        if !chunk.is_empty() {
+            records_count += chunk.len();
+            chunks_count += 1;
+            process_event_batch_with_config(chunk, parser, &source, http_client, config).await?;
+        }
+
+        if decoder.invalid_count > 0 {
+            warn!(
+                skipped_count = decoder.invalid_count as u64,
+                "Skipped S3 object lines that are not valid UTF-8"
+            );
+        }
+        info!(
+            records_count = records_count as u64,
+            chunks_count = chunks_count as u64,
+            "Forwarded S3 object records"
+        );
+        Ok(records_count)
+    }
+}
+
+impl fmt::Debug for S3Ingestor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Ingestor")
+            .field("region", &self.signer.region())
+            .field("chunk_lines", &self.chunk_lines)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Decodes an object key from an S3 event notification, where it is form URL-encoded
+pub fn decode_object_key(key: &str) -> String {
+    url::form_urlencoded::parse(key.as_bytes())
+        .next()
+        .map(|(decoded, _)| decoded.into_owned())
+        .unwrap_or_default()
+}
+
+/// Splits a stream of object bytes, gzip-compressed or not, into records
+#[derive(Default)]
+struct RecordDecoder {
+    state: DecoderState,
+    /// Decompressed bytes not yet split into lines
+    buffer: Vec<u8>,
+    invalid_count: usize,
+}
+
+#[derive(Default)]
+enum DecoderState {
+    /// Waiting for enough bytes to detect the gzip magic
+    #[default]
+    Detecting,
+    Gzip(Box<GzDecoder<Vec<u8>>>),
+    Plain,
+}
+
+impl RecordDecoder {
+    /// Appends object bytes, returning the records completed by them
+    fn push(&mut self, bytes: &[u8]) -> Result<Vec<String>> {
+        match &mut self.state {
+            DecoderState::Detecting => {
+                self.buffer.extend_from_slice(bytes);
+                if self.buffer.len() < 2 {
+                    return Ok(Vec::new());
+                }
+                if is_gzip(&self.buffer) {
+                    debug!("Decompressing gzip S3 object");
+                    let mut gzip = GzDecoder::new(Vec::new());
+                    gzip.write_all(&mem::take(&mut self.buffer))
+                        .context("Failed to decompress S3 object")?;
+                    self.buffer = mem::take(gzip.get_mut());
+                    self.state = DecoderState::Gzip(Box::new(gzip));
+                } else {
+                    self.state = DecoderState::Plain;
+                }
+            }
+            DecoderState::Gzip(gzip) => {
+                gzip.write_all(bytes)
+                    .context("Failed to decompress S3 object")?;
+                self.buffer.append(gzip.get_mut());
+            }
+            DecoderState::Plain => self.buffer.extend_from_slice(bytes),
+        }
+
+        let Some(end) = self.buffer.iter().rposition(|byte| *byte == b'\n') else {
+            return Ok(Vec::new());
+        };
+        let rest = self.buffer.split_off(end + 1);
+        let complete = mem::replace(&mut self.buffer, rest);
+        Ok(self.split_records(&complete))
+    }
+
+    /// Flushes the decompressor, returning the last records
+    fn finish(&mut self) -> Result<Vec<String>> {
+        if let DecoderState::Gzip(gzip) = &mut self.state {
+            gzip.try_finish()
+                .context("Failed to decompress S3 object")?;
+            self.buffer.append(gzip.get_mut());
+        }
+        let remaining = mem::take(&mut self.buffer);
+        Ok(self.split_records(&remaining))
+    }
+
+    fn split_records(&mut self, bytes: &[u8]) -> Vec<String> {
+        let mut records = Vec::new();
+        for line in bytes.split(|byte| *byte == b'\n') {
+            let Ok(line) = std::str::from_utf8(line) else {
+                self.invalid_count += 1;
+                continue;
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            // CloudWatch Logs exports prefix each record with its timestamp
+            let record = match line.find('{') {
+                Some(start) if start > 0 => &line[start..],
+                _ => line,
+            };
+            records.push(record.to_string());
+        }
+        records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_parser::ParserRegistry;
+    use crate::test_fixtures::otlp_stdout_record;
+    use crate::ForwarderSettings;
+    use aws_credential_types::Credentials;
+    use flate2::{write::GzEncoder, Compression};
+    use serde_json::json;
+    use wiremock::matchers::{header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_decode_object_key() {
+        assert_eq!(
+            decode_object_key("exports/2026/my+log%3Dgroup.gz"),
+            "exports/2026/my log=group.gz"
+        );
+    }
+
+    #[test]
+    fn test_record_decoder_splits_chunks() {
+        let object = format!(
+            "{}\n\n2026-01-01T00:00:00.000Z {}\nplain line",
+            otlp_stdout_record("a"),
+            otlp_stdout_record("b")
+        );
+        for data in [object.as_bytes().to_vec(), gzip(object.as_bytes())] {
+            let mut decoder = RecordDecoder::default();
+            let mut records = Vec::new();
+            for chunk in data.chunks(7) {
+                records.extend(decoder.push(chunk).unwrap());
+            }
+            records.extend(decoder.finish().unwrap());
+            assert_eq!(
+                records,
+                vec![
+                    otlp_stdout_record("a"),
+                    otlp_stdout_record("b"),
+                    "plain line".to_string()
+                ]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ingest_event_forwards_object_in_chunks() {
+        let server = MockServer::start().await;
+        let lines: Vec<String> = (0..3)
+            .map(|i| otlp_stdout_record(&format!("s{i}")))
+            .collect();
+        Mock::given(method("GET"))
+            .and(path("/exports/logs%20group/000000.gz"))
+            .and(header_exists("authorization"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_bytes(gzip(lines.join("\n").as_bytes())),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(3)
+            .mount(&server)
+            .await;
+        let config = ForwarderConfig::resolve(&ForwarderSettings {
+            endpoint: Some(format!("{}/v1/traces", server.uri())),
+            ..Default::default()
+        })
+        .unwrap();
+        let event: S3Event = serde_json::from_value(json!({
+            "Records": [{
+                "eventTime": "2026-01-01T00:00:00.000Z",
+                "userIdentity": {},
+                "requestParameters": {},
+                "responseElements": {},
+                "s3": {
+                    "bucket": {"name": "exports"},
+                    "object": {"key": "logs+group/000000.gz", "size": 1}
+                }
+            }]
+        }))
+        .unwrap();
+
+        let ingestor = S3Ingestor::new(
+            Credentials::new("AKIDEXAMPLE", "test-secret-key", None, None, "test"),
+            "us-east-1",
+        )
+        .with_endpoint(Url::parse(&server.uri()).unwrap())
+        .with_chunk_lines(1);
+        let records_count = ingestor
+            .ingest_event(
+                event,
+                &ParserRegistry::with_defaults(),
+                &ReqwestClient::new(),
+                &config,
+            )
+            .await
+            .unwrap();
+        assert_eq!(records_count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_object_reports_status_only() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(403).set_body_string("secret-detail"))
+            .mount(&server)
+            .await;
+        let config = ForwarderConfig::resolve(&ForwarderSettings::default()).unwrap();
+        let ingestor = S3Ingestor::new(
+            Credentials::new("AKIDEXAMPLE", "test-secret-key", None, None, "test"),
+            "us-east-1",
+        )
+        .with_endpoint(Url::parse(&server.uri()).unwrap());
+
+        let error = ingestor
+            .ingest_object(
+                "bucket",
+                "key",
+                &ParserRegistry::with_defaults(),
+                &ReqwestClient::new(),
+                &config,
+            )
+            .await
+            .unwrap_err();
+        let message = format!("{error:#}");
+        assert!(message.contains("403"));
+        assert!(!message.contains("secret-detail"));
+    }
+}
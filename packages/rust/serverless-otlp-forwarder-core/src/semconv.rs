@@ -0,0 +1,220 @@
+//! Normalization of deprecated semantic-convention attribute names.
+//!
+//! Producers built with different OpenTelemetry SDK versions report the same fact under
+//! different attribute names, such as `http.status_code` and `http.response.status_code`, so the
+//! backend splits their telemetry. A [`SemconvNormalizer`] renames the deprecated attributes of
+//! [`LEGACY_ATTRIBUTE_RENAMES`] to their current names on resources, spans, span events and log
+//! records. When a producer already sets both names, the current one is kept and the deprecated
+//! one dropped. Metric data points are left unchanged, as their metric names changed too.
+//!
+//! Normalization is opt-in, through the `normalize_semconv` setting of the configuration file or
+//! the `OTLP_FORWARDER_NORMALIZE_SEMCONV` environment variable (`true` or `false`). It runs
+//! before the span filter and the attribute transform, so their rules can use the current names.
+
+use opentelemetry_proto::tonic::common::v1::KeyValue;
+use opentelemetry_proto::tonic::logs::v1::ResourceLogs;
+use opentelemetry_proto::tonic::metrics::v1::ResourceMetrics;
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use opentelemetry_proto::tonic::trace::v1::ResourceSpans;
+
+/// Environment variable enabling semantic-convention normalization (`true` or `false`)
+pub const NORMALIZE_SEMCONV_ENV_VAR: &str = "OTLP_FORWARDER_NORMALIZE_SEMCONV";
+
+/// Deprecated attribute names and the names that replaced them
+pub const LEGACY_ATTRIBUTE_RENAMES: &[(&str, &str)] = &[
+    ("http.method", "http.request.method"),
+    ("http.status_code", "http.response.status_code"),
+    ("http.url", "url.full"),
+    ("http.scheme", "url.scheme"),
+    ("http.user_agent", "user_agent.original"),
+    ("http.client_ip", "client.address"),
+    ("http.flavor", "network.protocol.version"),
+    ("http.request_content_length", "http.request.body.size"),
+    ("http.response_content_length", "http.response.body.size"),
+    ("net.protocol.name", "network.protocol.name"),
+    ("net.protocol.version", "network.protocol.version"),
+    ("net.sock.peer.addr", "network.peer.address"),
+    ("net.sock.peer.port", "network.peer.port"),
+    ("net.transport", "network.transport"),
+    ("db.statement", "db.query.text"),
+    ("db.operation", "db.operation.name"),
+    ("messaging.destination", "messaging.destination.name"),
+    ("faas.execution", "faas.invocation_id"),
+    ("faas.id", "cloud.resource_id"),
+];
+
+/// Renames deprecated semantic-convention attributes to their current names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SemconvNormalizer {
+    enabled: bool,
+}
+
+impl SemconvNormalizer {
+    /// Creates a normalizer, which changes nothing unless `enabled`
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    /// Returns true if the normalizer renames attributes
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Renames the deprecated attributes of a list, returning the number of attributes renamed
+    /// or dropped
+    pub fn normalize(&self, attributes: &mut Vec<KeyValue>) -> usize {
+        if !self.enabled {
+            return 0;
+        }
+        let mut changed = 0;
+        for (legacy, current) in LEGACY_ATTRIBUTE_RENAMES {
+            let Some(position) = attributes
+                .iter()
+                .position(|attribute| attribute.key == *legacy)
+            else {
+                continue;
+            };
+            if attributes.iter().any(|attribute| attribute.key == *current) {
+                attributes.remove(position);
+            } else {
+                attributes[position].key = (*current).to_string();
+            }
+            changed += 1;
+        }
+        changed
+    }
+
+    fn normalize_resource(&self, resource: &mut Option<Resource>) {
+        if let Some(resource) = resource {
+            self.normalize(&mut resource.attributes);
+        }
+    }
+
+    /// Normalizes the resources, spans and span events of a trace request
+    pub fn apply_to_spans(&self, resource_spans: &mut [ResourceSpans]) {
+        if !self.enabled {
+            return;
+        }
+        for resource in resource_spans {
+            self.normalize_resource(&mut resource.resource);
+            for span in resource
+                .scope_spans
+                .iter_mut()
+                .flat_map(|scope| scope.spans.iter_mut())
+            {
+                self.normalize(&mut span.attributes);
+                for event in &mut span.events {
+                    self.normalize(&mut event.attributes);
+                }
+            }
+        }
+    }
+
+    /// Normalizes the resources and log records of a logs request
+    pub fn apply_to_logs(&self, resource_logs: &mut [ResourceLogs]) {
+        if !self.enabled {
+            return;
+        }
+        for resource in resource_logs {
+            self.normalize_resource(&mut resource.resource);
+            for log_record in resource
+                .scope_logs
+                .iter_mut()
+                .flat_map(|scope| scope.log_records.iter_mut())
+            {
+                self.normalize(&mut log_record.attributes);
+            }
+        }
+    }
+
+    /// Normalizes the resources of a metrics request
+    pub fn apply_to_metrics(&self, resource_metrics: &mut [ResourceMetrics]) {
+        if !self.enabled {
+            return;
+        }
+        for resource in resource_metrics {
+            self.normalize_resource(&mut resource.resource);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue};
+    use opentelemetry_proto::tonic::trace::v1::{span::Event, ScopeSpans, Span};
+
+    fn attribute(key: &str, value: i64) -> KeyValue {
+        KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::IntValue(value)),
+            }),
+        }
+    }
+
+    fn keys(attributes: &[KeyValue]) -> Vec<&str> {
+        attributes
+            .iter()
+            .map(|attribute| attribute.key.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_normalize_renames_legacy_attributes() {
+        let mut attributes = vec![
+            attribute("http.status_code", 200),
+            attribute("faas.execution", 1),
+            attribute("http.method", 2),
+            attribute("http.request.method", 3),
+            attribute("custom", 4),
+        ];
+
+        assert_eq!(SemconvNormalizer::default().normalize(&mut attributes), 0);
+        assert_eq!(SemconvNormalizer::new(true).normalize(&mut attributes), 3);
+        // The current name wins over the deprecated one
+        assert_eq!(
+            keys(&attributes),
+            [
+                "http.response.status_code",
+                "faas.invocation_id",
+                "http.request.method",
+                "custom"
+            ]
+        );
+        assert_eq!(attributes[0].value, attribute("", 200).value);
+        assert_eq!(attributes[2].value, attribute("", 3).value);
+    }
+
+    #[test]
+    fn test_apply_to_spans_normalizes_spans_and_events() {
+        let mut resource_spans = vec![ResourceSpans {
+            resource: Some(Resource {
+                attributes: vec![attribute("faas.id", 1)],
+                ..Default::default()
+            }),
+            scope_spans: vec![ScopeSpans {
+                spans: vec![Span {
+                    attributes: vec![attribute("http.status_code", 500)],
+                    events: vec![Event {
+                        attributes: vec![attribute("db.statement", 1)],
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }];
+
+        SemconvNormalizer::new(true).apply_to_spans(&mut resource_spans);
+        let resource = &resource_spans[0];
+        let span = &resource.scope_spans[0].spans[0];
+        assert_eq!(
+            keys(&resource.resource.as_ref().unwrap().attributes),
+            ["cloud.resource_id"]
+        );
+        assert_eq!(keys(&span.attributes), ["http.response.status_code"]);
+        assert_eq!(keys(&span.events[0].attributes), ["db.query.text"]);
+    }
+}
@@ -0,0 +1,350 @@
+//! AWS SigV4 request signing for OTLP endpoints.
+//!
+//! AWS-managed OTLP endpoints, such as the CloudWatch/X-Ray OTLP endpoint
+//! (`https://xray.<region>.amazonaws.com/v1/traces`), require every request to be signed with
+//! AWS Signature Version 4. [`SigV4SigningClient`] wraps any [`HttpOtlpForwarderClient`] and adds
+//! the signature headers to each POST before handing it to the inner client.
+//!
+//! Credentials are resolved on every request through the configured provider, so a provider
+//! from `aws-config` keeps refreshing (and caching) the Lambda execution role credentials.
+//!
+//! Requires the `sigv4` feature.
+
+use crate::http_sender::{HttpForwarderResponse, HttpOtlpForwarderClient};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use aws_sigv4::http_request::{sign, SignableBody, SignableRequest, SigningSettings};
+#[cfg(any(feature = "dlq", feature = "s3-ingest", feature = "aws-destinations"))]
+use aws_sigv4::http_request::{PayloadChecksumKind, UriPathNormalizationMode};
+use aws_sigv4::sign::v4;
+use aws_smithy_runtime_api::client::identity::Identity;
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+use tracing::debug;
+use url::Url;
+
+/// Default signing service name, used by the CloudWatch/X-Ray OTLP endpoint
+pub const DEFAULT_SIGNING_SERVICE: &str = "xray";
+
+/// Signs requests to an AWS service with SigV4
+#[derive(Clone)]
+pub(crate) struct SigV4Signer {
+    credentials: SharedCredentialsProvider,
+    region: String,
+    service: String,
+    settings: SigningSettings,
+}
+
+impl SigV4Signer {
+    pub(crate) fn new(
+        credentials: impl ProvideCredentials + 'static,
+        region: impl Into<String>,
+        service: impl Into<String>,
+    ) -> Self {
+        Self {
+            credentials: SharedCredentialsProvider::new(credentials),
+            region: region.into(),
+            service: service.into(),
+            settings: SigningSettings::default(),
+        }
+    }
+
+    /// Returns a signer for S3, which signs the payload hash into `x-amz-content-sha256`
+    /// and does not normalize paths
+    #[cfg(any(feature = "dlq", feature = "s3-ingest", feature = "aws-destinations"))]
+    pub(crate) fn for_s3(
+        credentials: impl ProvideCredentials + 'static,
+        region: impl Into<String>,
+    ) -> Self {
+        let mut signer = Self::new(credentials, region, "s3");
+        signer.settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+        signer.settings.uri_path_normalization_mode = UriPathNormalizationMode::Disabled;
+        signer
+    }
+
+    pub(crate) fn region(&self) -> &str {
+        &self.region
+    }
+
+    pub(crate) fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// Computes the SigV4 headers for a request of `payload` to `target_url`
+    pub(crate) async fn signature_headers(
+        &self,
+        method: &str,
+        target_url: &Url,
+        headers: &HeaderMap,
+        payload: &[u8],
+    ) -> Result<HeaderMap> {
+        let credentials = self
+            .credentials
+            .provide_credentials()
+            .await
+            .context("Failed to resolve AWS credentials for SigV4 signing")?;
+        let identity: Identity = credentials.into();
+
+        let signing_params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&self.region)
+            .name(&self.service)
+            .time(SystemTime::now())
+            .settings(self.settings.clone())
+            .build()
+            .context("Failed to build SigV4 signing parameters")?
+            .into();
+
+        let header_pairs = headers
+            .iter()
+            .map(|(name, value)| Ok((name.as_str(), value.to_str()?)))
+            .collect::<std::result::Result<Vec<_>, reqwest::header::ToStrError>>()
+            .context("Request header value cannot be signed with SigV4")?;
+
+        let signable_request = SignableRequest::new(
+            method,
+            target_url.as_str(),
+            header_pairs.into_iter(),
+            SignableBody::Bytes(payload),
+        )
+        .context("Failed to prepare request for SigV4 signing")?;
+
+        let (instructions, _) = sign(signable_request, &signing_params)
+            .context("Failed to sign request with SigV4")?
+            .into_parts();
+        let (signed_headers, _) = instructions.into_parts();
+
+        let mut signature = HeaderMap::new();
+        for header in signed_headers {
+            signature.insert(
+                HeaderName::from_bytes(header.name().as_bytes())
+                    .context("SigV4 signing produced an invalid header name")?,
+                HeaderValue::from_str(header.value())
+                    .context("SigV4 signing produced an invalid header value")?,
+            );
+        }
+        Ok(signature)
+    }
+}
+
+impl fmt::Debug for SigV4Signer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigV4Signer")
+            .field("region", &self.region)
+            .field("service", &self.service)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An [`HttpOtlpForwarderClient`] that signs OTLP requests with AWS SigV4
+pub struct SigV4SigningClient<C> {
+    inner: C,
+    signer: SigV4Signer,
+}
+
+impl<C> SigV4SigningClient<C> {
+    /// Wraps `inner`, signing requests for the given region and service
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use serverless_otlp_forwarder_core::{client_builder, SigV4SigningClient};
+    ///
+    /// let aws_config = aws_config::load_from_env().await;
+    /// let http_client = SigV4SigningClient::new(
+    ///     client_builder::simple(),
+    ///     aws_config.credentials_provider().expect("no AWS credentials provider"),
+    ///     aws_config.region().expect("no AWS region").to_string(),
+    ///     "xray",
+    /// );
+    /// ```
+    pub fn new(
+        inner: C,
+        credentials: impl ProvideCredentials + 'static,
+        region: impl Into<String>,
+        service: impl Into<String>,
+    ) -> Self {
+        Self {
+            inner,
+            signer: SigV4Signer::new(credentials, region, service),
+        }
+    }
+
+    /// Returns the wrapped client
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Returns the signing region
+    pub fn region(&self) -> &str {
+        self.signer.region()
+    }
+
+    /// Returns the signing service name
+    pub fn service(&self) -> &str {
+        self.signer.service()
+    }
+}
+
+impl<C> fmt::Debug for SigV4SigningClient<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigV4SigningClient")
+            .field("region", &self.region())
+            .field("service", &self.service())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<C: HttpOtlpForwarderClient> HttpOtlpForwarderClient for SigV4SigningClient<C> {
+    async fn post_telemetry(
+        &self,
+        target_url: Url,
+        mut headers: HeaderMap,
+        payload: Bytes,
+        timeout: Duration,
+    ) -> Result<HttpForwarderResponse> {
+        let signature = self
+            .signer
+            .signature_headers("POST", &target_url, &headers, &payload)
+            .await?;
+        debug!(
+            service = %self.service(),
+            region = %self.region(),
+            signed_headers = signature.len(),
+            "Signed OTLP request with SigV4"
+        );
+        headers.extend(signature);
+
+        self.inner
+            .post_telemetry(target_url, headers, payload, timeout)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_credential_types::provider::error::CredentialsError;
+    use aws_credential_types::provider::future;
+    use aws_credential_types::Credentials;
+    use http::StatusCode;
+    use std::sync::Mutex;
+
+    /// Records the headers of the last request and answers `200 OK`
+    #[derive(Default)]
+    struct RecordingClient {
+        headers: Mutex<Option<HeaderMap>>,
+    }
+
+    #[async_trait]
+    impl HttpOtlpForwarderClient for RecordingClient {
+        async fn post_telemetry(
+            &self,
+            _target_url: Url,
+            headers: HeaderMap,
+            _payload: Bytes,
+            _timeout: Duration,
+        ) -> Result<HttpForwarderResponse> {
+            *self.headers.lock().unwrap() = Some(headers);
+            Ok(HttpForwarderResponse::new(StatusCode::OK, String::new()))
+        }
+    }
+
+    #[derive(Debug)]
+    struct FailingProvider;
+
+    impl ProvideCredentials for FailingProvider {
+        fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+        where
+            Self: 'a,
+        {
+            future::ProvideCredentials::ready(Err(CredentialsError::not_loaded(
+                "secret-provider-detail",
+            )))
+        }
+    }
+
+    fn test_credentials() -> Credentials {
+        Credentials::new("AKIDEXAMPLE", "test-secret-key", None, None, "test")
+    }
+
+    #[tokio::test]
+    async fn test_signs_request_and_keeps_headers() {
+        let client = SigV4SigningClient::new(
+            RecordingClient::default(),
+            test_credentials(),
+            "us-east-1",
+            DEFAULT_SIGNING_SERVICE,
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "content-type",
+            HeaderValue::from_static("application/x-protobuf"),
+        );
+        headers.insert("content-encoding", HeaderValue::from_static("gzip"));
+
+        let response = client
+            .post_telemetry(
+                Url::parse("https://xray.us-east-1.amazonaws.com/v1/traces").unwrap(),
+                headers,
+                Bytes::from_static(b"payload"),
+                Duration::from_secs(1),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let sent = client.inner().headers.lock().unwrap().take().unwrap();
+        assert_eq!(sent["content-type"], "application/x-protobuf");
+        assert_eq!(sent["content-encoding"], "gzip");
+        assert!(sent.contains_key("x-amz-date"));
+
+        let authorization = sent["authorization"].to_str().unwrap();
+        assert!(authorization.starts_with("AWS4-HMAC-SHA256"));
+        assert!(authorization.contains("AKIDEXAMPLE/"));
+        assert!(authorization.contains("/us-east-1/xray/aws4_request"));
+        assert!(authorization.contains("content-encoding"));
+    }
+
+    #[tokio::test]
+    async fn test_credentials_error_does_not_send() {
+        let client = SigV4SigningClient::new(
+            RecordingClient::default(),
+            FailingProvider,
+            "us-east-1",
+            DEFAULT_SIGNING_SERVICE,
+        );
+
+        let err = client
+            .post_telemetry(
+                Url::parse("https://xray.us-east-1.amazonaws.com/v1/traces").unwrap(),
+                HeaderMap::new(),
+                Bytes::new(),
+                Duration::from_secs(1),
+            )
+            .await
+            .map(|r| r.status())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to resolve AWS credentials for SigV4 signing"
+        );
+        assert!(client.inner().headers.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_debug_hides_credentials() {
+        let client = SigV4SigningClient::new(
+            RecordingClient::default(),
+            test_credentials(),
+            "eu-west-1",
+            "xray",
+        );
+        let debug = format!("{client:?}");
+        assert!(debug.contains("eu-west-1"));
+        assert!(!debug.contains("test-secret-key"));
+    }
+}
@@ -0,0 +1,117 @@
+//! Enrichment of forwarded telemetry with the CloudWatch Logs source it was read from.
+//!
+//! A forwarder receives the log group, log stream and account of the records it forwards with
+//! the CloudWatch Logs event, but producers rarely report them, so the telemetry cannot be traced
+//! back to the log stream it came from. [`SourceMetadata`] holds them, and
+//! [`ForwarderConfig::with_source_metadata`] adds them as resource attributes of every signal
+//! through the [`AttributeTransform`] of the compaction:
+//!
+//! - `aws.cloudwatch.log_group`
+//! - `aws.cloudwatch.log_stream`
+//! - `cloud.account.id`
+//!
+//! Enrichment is opt-in, through the `enrich_source_metadata` setting of the configuration file
+//! or the `OTLP_FORWARDER_ENRICH_SOURCE_METADATA` environment variable (`true` or `false`).
+//! Attributes set by the attribute transform of the configuration take precedence.
+//!
+//! [`ForwarderConfig::with_source_metadata`]: crate::config::ForwarderConfig::with_source_metadata
+
+use crate::span_compactor::SpanCompactionConfig;
+use crate::transform::{AttributeTransform, AttributeValue};
+
+/// Environment variable enabling source metadata enrichment (`true` or `false`)
+pub const ENRICH_SOURCE_METADATA_ENV_VAR: &str = "OTLP_FORWARDER_ENRICH_SOURCE_METADATA";
+
+/// Resource attribute holding the log group the telemetry was read from
+pub const LOG_GROUP_ATTRIBUTE: &str = "aws.cloudwatch.log_group";
+/// Resource attribute holding the log stream the telemetry was read from
+pub const LOG_STREAM_ATTRIBUTE: &str = "aws.cloudwatch.log_stream";
+/// Resource attribute holding the account owning the log group
+pub const ACCOUNT_ID_ATTRIBUTE: &str = "cloud.account.id";
+
+/// CloudWatch Logs source of the records of an event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMetadata {
+    /// Log group the records were written to
+    pub log_group: Option<String>,
+    /// Log stream the records were written to
+    pub log_stream: Option<String>,
+    /// Account owning the log group
+    pub account_id: Option<String>,
+}
+
+impl SourceMetadata {
+    /// Creates the metadata of the records of a CloudWatch Logs subscription event, from the
+    /// `logGroup`, `logStream` and `owner` fields of its data
+    pub fn new(
+        log_group: impl Into<String>,
+        log_stream: impl Into<String>,
+        account_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            log_group: Some(log_group.into()),
+            log_stream: Some(log_stream.into()),
+            account_id: Some(account_id.into()),
+        }
+    }
+
+    /// Returns true if no metadata is known
+    pub fn is_empty(&self) -> bool {
+        self.log_group.is_none() && self.log_stream.is_none() && self.account_id.is_none()
+    }
+
+    /// Adds the known metadata to the resource attributes set by `transform`, keeping the
+    /// attributes it already sets
+    pub fn apply_to_transform(&self, transform: &mut AttributeTransform) {
+        for (key, value) in [
+            (LOG_GROUP_ATTRIBUTE, &self.log_group),
+            (LOG_STREAM_ATTRIBUTE, &self.log_stream),
+            (ACCOUNT_ID_ATTRIBUTE, &self.account_id),
+        ] {
+            if let Some(value) = value {
+                transform
+                    .resource
+                    .set
+                    .entry(key.to_string())
+                    .or_insert_with(|| AttributeValue::String(value.clone()));
+            }
+        }
+    }
+
+    /// Returns a copy of `compaction` whose attribute transform adds the known metadata
+    pub fn apply_to_compaction(&self, compaction: &SpanCompactionConfig) -> SpanCompactionConfig {
+        let mut compaction = compaction.clone();
+        self.apply_to_transform(&mut compaction.attribute_transform);
+        compaction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_to_transform_keeps_configured_attributes() {
+        let mut transform = AttributeTransform::parse(
+            "resource:\n  set:\n    cloud.account.id: shared\n  delete: [aws.log.group.names]\n",
+        )
+        .unwrap();
+        let metadata = SourceMetadata {
+            log_stream: None,
+            ..SourceMetadata::new("/aws/lambda/checkout", "stream", "123456789012")
+        };
+
+        metadata.apply_to_transform(&mut transform);
+        let set = &transform.resource.set;
+        assert_eq!(
+            set.get(LOG_GROUP_ATTRIBUTE),
+            Some(&AttributeValue::String("/aws/lambda/checkout".to_string()))
+        );
+        assert!(!set.contains_key(LOG_STREAM_ATTRIBUTE));
+        assert_eq!(
+            set.get(ACCOUNT_ID_ATTRIBUTE),
+            Some(&AttributeValue::String("shared".to_string()))
+        );
+        assert_eq!(transform.resource.delete, ["aws.log.group.names"]);
+    }
+}
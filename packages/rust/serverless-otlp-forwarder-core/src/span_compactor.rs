@@ -1,13 +1,33 @@
-//! Module for compacting multiple OTLP span payloads into a single request
+//! Module for compacting multiple OTLP span (or log, or metric) payloads into a single request
 
 use anyhow::Result; // Changed from LambdaError
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
 use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::metrics::v1::{
+    metric::Data, Metric, ResourceMetrics, ScopeMetrics,
+};
+use opentelemetry_proto::tonic::trace::v1::ResourceSpans;
 use prost::Message;
-use std::env;
+use serde::{Deserialize, Serialize};
 use std::fmt;
-use tracing::{self, instrument}; // For reading environment variables
+use std::mem;
+use std::sync::Arc;
+use tracing::{self, instrument};
 
-use crate::telemetry::TelemetryData; // This should be correct once telemetry.rs is in the same crate
+use crate::config::ForwarderSettings;
+use crate::semconv::SemconvNormalizer;
+use crate::span_dedup::{span_keys, SpanDeduplicator, SpanKey};
+use crate::span_filter::SpanFilter;
+use crate::trace_sampler::TraceSampler;
+use crate::transform::AttributeTransform;
+
+use crate::telemetry::{Signal, TelemetryData}; // This should be correct once telemetry.rs is in the same crate
+
+/// Environment variable bounding the uncompressed size of a compacted payload, in bytes
+pub const MAX_PAYLOAD_SIZE_ENV_VAR: &str = "OTLP_FORWARDER_MAX_PAYLOAD_SIZE";
+/// Default maximum uncompressed size of a compacted payload (4 MiB)
+pub const DEFAULT_MAX_PAYLOAD_SIZE: usize = 4 * 1024 * 1024;
 
 /// Decodes a protobuf-serialized OTLP payload
 ///
@@ -25,8 +45,277 @@ fn encode_otlp_payload(request: &ExportTraceServiceRequest) -> Vec<u8> {
     request.encode_to_vec()
 }
 
+/// Counts the spans of trace resource entries
+fn count_spans(resource_spans: &[ResourceSpans]) -> usize {
+    resource_spans
+        .iter()
+        .flat_map(|resource| &resource.scope_spans)
+        .map(|scope| scope.spans.len())
+        .sum()
+}
+
+/// Decodes every payload of a single-signal batch and merges their resource entries.
+/// Payloads that fail to decode are skipped.
+fn merge_payloads<R, T>(
+    batch: Vec<TelemetryData>,
+    decode: impl Fn(&[u8]) -> Result<R>,
+    mut into_resources: impl FnMut(R) -> Vec<T>,
+) -> Result<Vec<T>> {
+    let mut merged_resources = Vec::new();
+    let mut decoded_count = 0;
+    for telemetry_item in batch {
+        // Consume batch
+        match decode(&telemetry_item.payload) {
+            Ok(request) => {
+                decoded_count += 1;
+                merged_resources.extend(into_resources(request));
+            }
+            Err(_) => {
+                tracing::warn!("Failed to decode telemetry payload for compaction; skipping item");
+            }
+        }
+    }
+
+    if decoded_count == 0 {
+        return Err(anyhow::anyhow!(
+            "All payloads in batch failed to decode for compaction"
+        ));
+    }
+    Ok(merged_resources)
+}
+
+/// Converts OTLP/JSON items to uncompressed protobuf so they can be merged with the others.
+/// Items that fail to parse are skipped.
+fn convert_json_items(batch: Vec<TelemetryData>) -> Result<Vec<TelemetryData>> {
+    let converted: Vec<TelemetryData> = batch
+        .into_iter()
+        .filter_map(|mut item| {
+            if !item.content_type.starts_with("application/json") {
+                return Some(item);
+            }
+            match TelemetryData::convert_to_protobuf(
+                mem::take(&mut item.payload),
+                "application/json",
+                item.content_encoding.as_deref(),
+                item.signal,
+            ) {
+                Ok(payload) => Some(TelemetryData {
+                    payload,
+                    content_type: "application/x-protobuf".to_string(),
+                    content_encoding: None,
+                    ..item
+                }),
+                Err(_) => {
+                    tracing::warn!(
+                        signal = %item.signal,
+                        "Failed to parse OTLP/JSON telemetry payload for compaction; skipping item"
+                    );
+                    None
+                }
+            }
+        })
+        .collect();
+
+    if converted.is_empty() {
+        return Err(anyhow::anyhow!(
+            "All payloads in batch failed to decode for compaction"
+        ));
+    }
+    Ok(converted)
+}
+
+/// Splits resource entries into groups whose encoded export request fits in `max_size` bytes
+///
+/// Groups are halved by leaf items (spans, log records or metrics) until they fit, so each
+/// resource and scope is repeated in every group holding some of its items. A single item larger
+/// than `max_size` cannot be split and is kept in a group of its own.
+fn split_resources<R, S, I>(
+    resources: Vec<R>,
+    max_size: usize,
+    scopes: &impl Fn(&mut R) -> &mut Vec<S>,
+    items: &impl Fn(&mut S) -> &mut Vec<I>,
+) -> Vec<Vec<R>>
+where
+    R: Message + Clone,
+    S: Message + Clone,
+{
+    // Resources are field 1 of every OTLP export request
+    if max_size == 0 || prost::encoding::message::encoded_len_repeated(1, &resources) <= max_size {
+        return vec![resources];
+    }
+    match halve_resources(resources, scopes, items) {
+        Ok((first, second)) => {
+            let mut groups = split_resources(first, max_size, scopes, items);
+            groups.extend(split_resources(second, max_size, scopes, items));
+            groups
+        }
+        Err(resources) => {
+            tracing::warn!(
+                max_payload_size = max_size as u64,
+                "Telemetry item exceeds the maximum payload size; sending it unsplit"
+            );
+            vec![resources]
+        }
+    }
+}
+
+/// Moves about half of the leaf items into a second group of resources, or returns the
+/// resources unchanged if they hold fewer than two items
+fn halve_resources<R: Clone, S: Clone, I>(
+    mut resources: Vec<R>,
+    scopes: &impl Fn(&mut R) -> &mut Vec<S>,
+    items: &impl Fn(&mut S) -> &mut Vec<I>,
+) -> std::result::Result<(Vec<R>, Vec<R>), Vec<R>> {
+    let total: usize = resources
+        .iter_mut()
+        .flat_map(|resource| scopes(resource).iter_mut())
+        .map(|scope| items(scope).len())
+        .sum();
+    if total < 2 {
+        return Err(resources);
+    }
+
+    let mut remaining = total / 2;
+    let mut first = Vec::new();
+    let mut second = Vec::new();
+    for mut resource in resources {
+        let resource_items: usize = scopes(&mut resource)
+            .iter_mut()
+            .map(|scope| items(scope).len())
+            .sum();
+        if remaining == 0 {
+            second.push(resource);
+            continue;
+        }
+        if resource_items <= remaining {
+            remaining -= resource_items;
+            first.push(resource);
+            continue;
+        }
+
+        // This resource straddles the split point, so its scopes are divided
+        let resource_scopes = mem::take(scopes(&mut resource));
+        let mut second_resource = resource.clone();
+        for mut scope in resource_scopes {
+            let scope_items = items(&mut scope).len();
+            if remaining == 0 {
+                scopes(&mut second_resource).push(scope);
+            } else if scope_items <= remaining {
+                remaining -= scope_items;
+                scopes(&mut resource).push(scope);
+            } else {
+                let mut head = mem::take(items(&mut scope));
+                let tail = head.split_off(remaining);
+                let mut second_scope = scope.clone();
+                *items(&mut scope) = head;
+                *items(&mut second_scope) = tail;
+                remaining = 0;
+                scopes(&mut resource).push(scope);
+                scopes(&mut second_resource).push(second_scope);
+            }
+        }
+        first.push(resource);
+        second.push(second_resource);
+    }
+    Ok((first, second))
+}
+
+/// Merges resource metrics with the same resource, then scope metrics with the same scope, and
+/// appends the data points of identical metric streams to a single metric
+///
+/// Data points are only concatenated, never aggregated, so no measurement is lost.
+fn merge_resource_metrics(resource_metrics: Vec<ResourceMetrics>) -> Vec<ResourceMetrics> {
+    let mut merged: Vec<ResourceMetrics> = Vec::new();
+    for mut resource in resource_metrics {
+        let scope_metrics = mem::take(&mut resource.scope_metrics);
+        let index = match merged.iter().position(|existing| {
+            existing.resource == resource.resource && existing.schema_url == resource.schema_url
+        }) {
+            Some(index) => index,
+            None => {
+                merged.push(resource);
+                merged.len() - 1
+            }
+        };
+        merge_scope_metrics(&mut merged[index].scope_metrics, scope_metrics);
+    }
+    merged
+}
+
+fn merge_scope_metrics(merged: &mut Vec<ScopeMetrics>, scope_metrics: Vec<ScopeMetrics>) {
+    for mut scope in scope_metrics {
+        let metrics = mem::take(&mut scope.metrics);
+        let index = match merged.iter().position(|existing| {
+            existing.scope == scope.scope && existing.schema_url == scope.schema_url
+        }) {
+            Some(index) => index,
+            None => {
+                merged.push(scope);
+                merged.len() - 1
+            }
+        };
+        let merged_metrics = &mut merged[index].metrics;
+        for metric in metrics {
+            match merged_metrics
+                .iter_mut()
+                .find(|existing| is_same_metric_stream(existing, &metric))
+            {
+                Some(existing) => append_data_points(existing, metric),
+                None => merged_metrics.push(metric),
+            }
+        }
+    }
+}
+
+/// Returns true if both metrics describe the same stream: same identity, type and temporality
+fn is_same_metric_stream(a: &Metric, b: &Metric) -> bool {
+    let same_type = match (&a.data, &b.data) {
+        (Some(Data::Gauge(_)), Some(Data::Gauge(_)))
+        | (Some(Data::Summary(_)), Some(Data::Summary(_))) => true,
+        (Some(Data::Sum(a)), Some(Data::Sum(b))) => {
+            a.aggregation_temporality == b.aggregation_temporality
+                && a.is_monotonic == b.is_monotonic
+        }
+        (Some(Data::Histogram(a)), Some(Data::Histogram(b))) => {
+            a.aggregation_temporality == b.aggregation_temporality
+        }
+        (Some(Data::ExponentialHistogram(a)), Some(Data::ExponentialHistogram(b))) => {
+            a.aggregation_temporality == b.aggregation_temporality
+        }
+        _ => false,
+    };
+    same_type
+        && a.name == b.name
+        && a.description == b.description
+        && a.unit == b.unit
+        && a.metadata == b.metadata
+}
+
+fn append_data_points(target: &mut Metric, other: Metric) {
+    match (&mut target.data, other.data) {
+        (Some(Data::Gauge(target)), Some(Data::Gauge(other))) => {
+            target.data_points.extend(other.data_points)
+        }
+        (Some(Data::Sum(target)), Some(Data::Sum(other))) => {
+            target.data_points.extend(other.data_points)
+        }
+        (Some(Data::Histogram(target)), Some(Data::Histogram(other))) => {
+            target.data_points.extend(other.data_points)
+        }
+        (Some(Data::ExponentialHistogram(target)), Some(Data::ExponentialHistogram(other))) => {
+            target.data_points.extend(other.data_points)
+        }
+        (Some(Data::Summary(target)), Some(Data::Summary(other))) => {
+            target.data_points.extend(other.data_points)
+        }
+        // Callers only merge metrics of the same stream, hence of the same type
+        _ => {}
+    }
+}
+
 /// Enum to represent OTLP compression preference
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum CompressionPreference {
     Gzip,
     None,
@@ -54,69 +343,68 @@ pub struct SpanCompactionConfig {
     pub compression: CompressionPreference,
     /// GZIP compression level (0-9) if Gzip compression is used
     pub gzip_compression_level: u32,
+    /// Maximum uncompressed size of a compacted payload in bytes; larger batches are split.
+    /// `0` disables splitting.
+    pub max_payload_size: usize,
+    /// Attribute changes applied to every decoded request before merging
+    pub attribute_transform: AttributeTransform,
+    /// Rules dropping spans from every decoded trace request, before the attribute transform
+    pub span_filter: SpanFilter,
+    /// Tail-based sampling of the spans of a trace batch, after the span filter
+    pub trace_sampler: TraceSampler,
+    /// Cache of the spans already forwarded, dropping redelivered spans before sampling
+    pub span_deduplicator: Option<Arc<SpanDeduplicator>>,
+    /// Renaming of deprecated semantic-convention attributes, before the span filter
+    pub semconv_normalizer: SemconvNormalizer,
 }
 
 impl Default for SpanCompactionConfig {
+    /// Reads `OTEL_EXPORTER_OTLP_TRACES_COMPRESSION` (or `OTEL_EXPORTER_OTLP_COMPRESSION`),
+    /// `OTEL_EXPORTER_OTLP_COMPRESSION_LEVEL`, `OTLP_FORWARDER_MAX_PAYLOAD_SIZE` and
+    /// `OTLP_FORWARDER_ATTRIBUTE_TRANSFORM`, `OTLP_FORWARDER_SPAN_FILTER`,
+    /// `OTLP_FORWARDER_SAMPLING_PERCENTAGE`, `OTLP_FORWARDER_SPAN_DEDUP_CAPACITY` and
+    /// `OTLP_FORWARDER_NORMALIZE_SEMCONV`, defaulting to no compression, level 9, a 4 MiB payload
+    /// size limit, no attribute transform, no span filter, no sampling, no span deduplication and
+    /// no semantic-convention normalization
     fn default() -> Self {
-        let compression_setting = env::var("OTEL_EXPORTER_OTLP_TRACES_COMPRESSION")
-            .map(|val| ("OTEL_EXPORTER_OTLP_TRACES_COMPRESSION", val))
-            .or_else(|_| {
-                env::var("OTEL_EXPORTER_OTLP_COMPRESSION")
-                    .map(|val| ("OTEL_EXPORTER_OTLP_COMPRESSION", val))
-            })
-            .ok();
-
-        let compression_preference =
-            compression_setting.map_or(CompressionPreference::None, |(env_var, val)| {
-                match val.to_lowercase().as_str() {
-                    "gzip" => CompressionPreference::Gzip,
-                    "none" => CompressionPreference::None, // Explicitly none
-                    _ => {
-                        tracing::warn!(
-                            env_var,
-                            "Invalid OTLP compression setting; defaulting to no compression"
-                        );
-                        CompressionPreference::None
-                    }
-                }
-            });
-
-        let default_compression_level = 9;
-        let gzip_compression_level = env::var("OTEL_EXPORTER_OTLP_COMPRESSION_LEVEL")
-            .ok()
-            .and_then(|val_str| {
-                match val_str.parse::<u32>() {
-                    Ok(level) if (0..=9).contains(&level) => Some(level),
-                    Ok(_) => {
-                        tracing::warn!(
-                            env_var = "OTEL_EXPORTER_OTLP_COMPRESSION_LEVEL",
-                            default_compression_level,
-                            "Invalid OTLP compression level; defaulting to configured fallback"
-                        );
-                        None // Will cause fallback to default_compression_level
-                    }
-                    Err(_) => {
-                        tracing::warn!(
-                            env_var = "OTEL_EXPORTER_OTLP_COMPRESSION_LEVEL",
-                            default_compression_level,
-                            "Failed to parse OTLP compression level; defaulting to configured fallback"
-                        );
-                        None // Will cause fallback to default_compression_level
-                    }
-                }
-            })
-            .unwrap_or(default_compression_level);
-
-        Self {
-            compression: compression_preference,
-            gzip_compression_level, // Use the determined level
-        }
+        ForwarderSettings::compression_from_env()
+            .merge(ForwarderSettings::payload_size_from_env())
+            .merge(ForwarderSettings::attribute_transform_from_env())
+            .merge(ForwarderSettings::span_filter_from_env())
+            .merge(ForwarderSettings::sampling_from_env())
+            .merge(ForwarderSettings::span_dedup_from_env())
+            .merge(ForwarderSettings::semconv_from_env())
+            .compaction_config()
     }
 }
 
-/// Compacts multiple telemetry payloads into a single payload
+/// Compacts multiple telemetry payloads into as few payloads as the size limit allows
 /// Since all log events in a single Lambda invocation come from the same log group,
 /// we can assume they all have the same metadata (source, endpoint, headers)
+///
+/// Items are expected to hold uncompressed protobuf; OTLP/JSON items (`application/json`, possibly
+/// gzipped) are converted to protobuf first, and items that fail to parse are skipped.
+///
+/// All items must carry the same [`Signal`]; trace payloads are merged into one
+/// `ExportTraceServiceRequest`, log payloads into one `ExportLogsServiceRequest` and metric
+/// payloads into one `ExportMetricsServiceRequest`, where the data points of identical metric
+/// streams are combined.
+///
+/// A merged request whose encoded size exceeds [`SpanCompactionConfig::max_payload_size`] is
+/// split into several requests that each fit, so the result holds one payload or more. The limit
+/// applies to the uncompressed protobuf encoding, so it also bounds the compressed size.
+///
+/// The [`SpanCompactionConfig::semconv_normalizer`] renames deprecated attributes of every
+/// decoded request, the [`SpanCompactionConfig::span_filter`] drops spans from every decoded
+/// trace request, the
+/// [`SpanCompactionConfig::span_deduplicator`] drops spans already forwarded and the
+/// [`SpanCompactionConfig::trace_sampler`] drops unsampled traces, then the
+/// [`SpanCompactionConfig::attribute_transform`] is applied to every decoded request, so a
+/// single item is decoded and re-encoded as well when any of them is enabled. If every span is
+/// dropped, no payload is returned.
+///
+/// The payloads come with the keys of the spans they hold when span deduplication is enabled,
+/// to pass to [`SpanDeduplicator::record`] once the payloads are delivered.
 #[instrument(
     name = "span_compactor/compact_telemetry_payloads",
     skip_all,
@@ -128,17 +416,46 @@ impl Default for SpanCompactionConfig {
 pub fn compact_telemetry_payloads(
     batch: Vec<TelemetryData>,
     config: &SpanCompactionConfig,
-) -> Result<TelemetryData> {
+) -> Result<(Vec<TelemetryData>, Vec<SpanKey>)> {
     // Changed from LambdaError
     if batch.is_empty() {
         return Err(anyhow::anyhow!(
             "Cannot compact an empty batch of telemetry data."
         ));
     }
+    let signal = batch[0].signal;
+    if batch.iter().any(|item| item.signal != signal) {
+        return Err(anyhow::anyhow!(
+            "Cannot compact telemetry of different signals into one batch"
+        ));
+    }
+
+    let batch = convert_json_items(batch)?;
 
-    // If only one item, just apply compression preference based on config and return
-    if batch.len() == 1 {
+    // If only one item within the size limit, just apply compression preference based on config and return
+    let within_limit = |item: &TelemetryData| {
+        config.max_payload_size == 0 || item.payload.len() <= config.max_payload_size
+    };
+    let transform = &config.attribute_transform;
+    let normalizer = &config.semconv_normalizer;
+    let span_filter = &config.span_filter;
+    let trace_sampler = &config.trace_sampler;
+    let span_deduplicator = config.span_deduplicator.as_deref();
+    let filter_spans = signal == Signal::Traces
+        && (!span_filter.is_empty() || trace_sampler.is_enabled() || span_deduplicator.is_some());
+    if batch.len() == 1
+        && within_limit(&batch[0])
+        && transform.is_empty()
+        && !normalizer.is_enabled()
+        && !filter_spans
+    {
         let mut telemetry_to_return = batch.into_iter().next().unwrap();
+        if signal == Signal::Traces {
+            // Counted before compression, so senders never decode the payload again
+            telemetry_to_return.span_count = decode_otlp_payload(&telemetry_to_return.payload)
+                .ok()
+                .map(|request| count_spans(&request.resource_spans));
+        }
         // Input TelemetryData.payload is expected to be uncompressed protobuf.
         // TelemetryData.content_encoding is expected to be None.
         match config.compression {
@@ -154,73 +471,146 @@ pub fn compact_telemetry_payloads(
                 telemetry_to_return.content_encoding = None;
             }
         }
-        return Ok(telemetry_to_return);
+        return Ok((vec![telemetry_to_return], Vec::new()));
     }
 
-    // Proceed with structural compaction for batch.len() > 1
+    // Proceed with structural compaction for batch.len() > 1, or an oversized single item
     let original_count = batch.len();
-    let mut decoded_requests = Vec::new();
 
     // Get metadata from the first element before consuming the batch by value.
     let first_item_source = batch[0].source.clone();
     let first_item_endpoint = batch[0].endpoint.clone();
 
-    for telemetry_item in batch {
-        // Consume batch
-        match decode_otlp_payload(&telemetry_item.payload) {
-            Ok(request) => decoded_requests.push(request),
-            Err(_) => {
-                tracing::warn!("Failed to decode telemetry payload for compaction; skipping item");
+    let max_size = config.max_payload_size;
+    let mut dropped_spans = 0;
+    let mut kept_span_keys = Vec::new();
+    // Merged payloads, with their span count for traces
+    let merged_payloads: Vec<(Vec<u8>, Option<usize>)> = match signal {
+        Signal::Traces => {
+            let mut resource_spans = merge_payloads(batch, decode_otlp_payload, |mut request| {
+                normalizer.apply_to_spans(&mut request.resource_spans);
+                dropped_spans += span_filter.apply(&mut request.resource_spans);
+                transform.apply_to_spans(&mut request.resource_spans);
+                request.resource_spans
+            })?;
+            if let Some(span_deduplicator) = span_deduplicator {
+                dropped_spans += span_deduplicator.filter(&mut resource_spans);
+            }
+            // Traces are sampled once merged, so an error span of any item keeps its trace
+            dropped_spans += trace_sampler.apply(&mut resource_spans);
+            if span_deduplicator.is_some() {
+                kept_span_keys = span_keys(&resource_spans);
+            }
+            if resource_spans.is_empty() && filter_spans {
+                // Every span was filtered out, duplicated or unsampled, so there is nothing to send
+                Vec::new()
+            } else {
+                split_resources(
+                    resource_spans,
+                    max_size,
+                    &|resource| &mut resource.scope_spans,
+                    &|scope| &mut scope.spans,
+                )
+                .into_iter()
+                .map(|resource_spans| {
+                    let span_count = count_spans(&resource_spans);
+                    (
+                        encode_otlp_payload(&ExportTraceServiceRequest { resource_spans }),
+                        Some(span_count),
+                    )
+                })
+                .collect()
             }
         }
-    }
-
-    if decoded_requests.is_empty() {
-        return Err(anyhow::anyhow!(
-            "All payloads in batch failed to decode for compaction"
-        ));
-    }
-
-    let mut merged_resource_spans = Vec::new();
-    for request in decoded_requests {
-        merged_resource_spans.extend(request.resource_spans);
-    }
-
-    let merged_request = ExportTraceServiceRequest {
-        resource_spans: merged_resource_spans,
+        Signal::Logs => split_resources(
+            merge_payloads(
+                batch,
+                |payload| {
+                    ExportLogsServiceRequest::decode(payload)
+                        .map_err(|_| anyhow::anyhow!("Failed to decode protobuf payload"))
+                },
+                |mut request| {
+                    normalizer.apply_to_logs(&mut request.resource_logs);
+                    transform.apply_to_logs(&mut request.resource_logs);
+                    request.resource_logs
+                },
+            )?,
+            max_size,
+            &|resource| &mut resource.scope_logs,
+            &|scope| &mut scope.log_records,
+        )
+        .into_iter()
+        .map(|resource_logs| {
+            (
+                ExportLogsServiceRequest { resource_logs }.encode_to_vec(),
+                None,
+            )
+        })
+        .collect(),
+        Signal::Metrics => split_resources(
+            merge_resource_metrics(merge_payloads(
+                batch,
+                |payload| {
+                    ExportMetricsServiceRequest::decode(payload)
+                        .map_err(|_| anyhow::anyhow!("Failed to decode protobuf payload"))
+                },
+                |mut request| {
+                    normalizer.apply_to_metrics(&mut request.resource_metrics);
+                    transform.apply_to_metrics(&mut request.resource_metrics);
+                    request.resource_metrics
+                },
+            )?),
+            max_size,
+            &|resource| &mut resource.scope_metrics,
+            &|scope| &mut scope.metrics,
+        )
+        .into_iter()
+        .map(|resource_metrics| {
+            (
+                ExportMetricsServiceRequest { resource_metrics }.encode_to_vec(),
+                None,
+            )
+        })
+        .collect(),
     };
 
-    let merged_payload = encode_otlp_payload(&merged_request);
-
-    let mut result_telemetry_data = TelemetryData {
-        source: first_item_source,     // Use cloned metadata
-        endpoint: first_item_endpoint, // Use cloned metadata
-        payload: merged_payload,
-        content_type: "application/x-protobuf".to_string(),
-        content_encoding: None, // Start as uncompressed before final compression decision
-    };
+    let payload_count = merged_payloads.len();
+    let mut results = Vec::with_capacity(payload_count);
+    for (merged_payload, span_count) in merged_payloads {
+        let mut result_telemetry_data = TelemetryData {
+            source: first_item_source.clone(),
+            endpoint: first_item_endpoint.clone(),
+            payload: merged_payload,
+            content_type: "application/x-protobuf".to_string(),
+            content_encoding: None, // Start as uncompressed before final compression decision
+            batch_id: None,         // A merged batch no longer matches any single exporter batch
+            signal,
+            span_count,
+        };
 
-    match config.compression {
-        CompressionPreference::Gzip => {
-            result_telemetry_data
-                .compress(config.gzip_compression_level)
-                .map_err(|_| anyhow::anyhow!("Failed to compress merged payload"))?;
-        }
-        CompressionPreference::None => {
-            // Ensure content_encoding is None (already set as default if not compressed)
-            result_telemetry_data.content_encoding = None;
+        match config.compression {
+            CompressionPreference::Gzip => {
+                result_telemetry_data
+                    .compress(config.gzip_compression_level)
+                    .map_err(|_| anyhow::anyhow!("Failed to compress merged payload"))?;
+            }
+            CompressionPreference::None => {
+                // Ensure content_encoding is None (already set as default if not compressed)
+                result_telemetry_data.content_encoding = None;
+            }
         }
+        results.push(result_telemetry_data);
     }
 
     tracing::info!(
         compact_telemetry_payloads.records.count = original_count as i64,
-        compression = result_telemetry_data
-            .content_encoding
-            .as_deref()
-            .unwrap_or("none"),
+        compact_telemetry_payloads.payloads.count = payload_count as i64,
+        compact_telemetry_payloads.dropped_spans.count = dropped_spans as i64,
+        signal = %signal,
+        compression = config.compression.as_str(),
         "Compacted telemetry items"
     );
-    Ok(result_telemetry_data)
+    Ok((results, kept_span_keys))
 }
 
 #[cfg(test)]
@@ -229,7 +619,13 @@ mod tests {
     use crate::telemetry::TelemetryData; // Ensure TelemetryData is in scope for tests
     use crate::tracing_capture::EventCaptureLayer;
     use flate2::read::GzDecoder;
-    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+    use opentelemetry_proto::tonic::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
+    use opentelemetry_proto::tonic::metrics::v1::{
+        number_data_point, AggregationTemporality, NumberDataPoint, Sum,
+    };
+    use opentelemetry_proto::tonic::trace::v1::{
+        status::StatusCode, ResourceSpans, ScopeSpans, Span, Status,
+    };
     use serial_test::serial;
     use std::io::Read; // For tests that modify environment variables
     use tracing_subscriber::{prelude::*, registry::Registry};
@@ -266,9 +662,18 @@ mod tests {
             payload,
             content_type: "application/x-protobuf".to_string(),
             content_encoding: None, // Uncompressed for testing
+            batch_id: None,
+            signal: Signal::Traces,
+            span_count: None,
         }
     }
 
+    fn compact_into_one(batch: Vec<TelemetryData>, config: &SpanCompactionConfig) -> TelemetryData {
+        let (mut results, _) = compact_telemetry_payloads(batch, config).unwrap();
+        assert_eq!(results.len(), 1);
+        results.remove(0)
+    }
+
     #[test]
     fn test_decode_encode_roundtrip() {
         // Create a simple request
@@ -414,9 +819,16 @@ mod tests {
         let config = SpanCompactionConfig {
             compression: CompressionPreference::Gzip,
             gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
         };
-        let result = compact_telemetry_payloads(vec![telemetry.clone()], &config).unwrap();
+        let result = compact_into_one(vec![telemetry.clone()], &config);
         assert_eq!(result.content_encoding, Some("gzip".to_string()));
+        assert_eq!(result.span_count, Some(1));
     }
 
     #[test]
@@ -425,8 +837,14 @@ mod tests {
         let config = SpanCompactionConfig {
             compression: CompressionPreference::None,
             gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
         };
-        let result = compact_telemetry_payloads(vec![telemetry.clone()], &config).unwrap();
+        let result = compact_into_one(vec![telemetry.clone()], &config);
         assert_eq!(result.content_encoding, None);
         assert_eq!(result.payload, telemetry.payload); // Payload should be unchanged if already uncompressed
     }
@@ -438,9 +856,16 @@ mod tests {
         let config = SpanCompactionConfig {
             compression: CompressionPreference::Gzip,
             gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
         };
-        let result = compact_telemetry_payloads(vec![telemetry1, telemetry2], &config).unwrap();
+        let result = compact_into_one(vec![telemetry1, telemetry2], &config);
         assert_eq!(result.content_encoding, Some("gzip".to_string()));
+        assert_eq!(result.span_count, Some(5));
         let mut decoder = GzDecoder::new(&result.payload[..]);
         let mut decompressed = Vec::new();
         decoder.read_to_end(&mut decompressed).unwrap();
@@ -459,8 +884,14 @@ mod tests {
         let config = SpanCompactionConfig {
             compression: CompressionPreference::None,
             gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
         };
-        let result = compact_telemetry_payloads(vec![telemetry1, telemetry2], &config).unwrap();
+        let result = compact_into_one(vec![telemetry1, telemetry2], &config);
         assert_eq!(result.content_encoding, None);
         let decoded_request = ExportTraceServiceRequest::decode(result.payload.as_slice()).unwrap();
         assert_eq!(
@@ -478,6 +909,12 @@ mod tests {
         let config = SpanCompactionConfig {
             compression: CompressionPreference::Gzip,
             gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
         };
         let capture_layer = EventCaptureLayer::new();
         let captured_events = capture_layer.events();
@@ -486,7 +923,9 @@ mod tests {
         let result = tracing::subscriber::with_default(subscriber, || {
             compact_telemetry_payloads(vec![telemetry1, telemetry2], &config)
         })
-        .unwrap();
+        .unwrap()
+        .0
+        .remove(0);
 
         assert_eq!(result.content_encoding.as_deref(), Some("gzip"));
 
@@ -521,6 +960,86 @@ mod tests {
             .all(|value| !value.contains("Some(")));
     }
 
+    fn span_names(payload: &[u8]) -> Vec<String> {
+        decode_otlp_payload(payload)
+            .unwrap()
+            .resource_spans
+            .into_iter()
+            .flat_map(|resource| resource.scope_spans)
+            .flat_map(|scope| scope.spans)
+            .map(|span| span.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_compact_splits_payloads_exceeding_max_size() {
+        let single_span_size = create_test_telemetry_uncompressed(1, "s1").payload.len();
+        let config = SpanCompactionConfig {
+            compression: CompressionPreference::None,
+            gzip_compression_level: 9,
+            max_payload_size: single_span_size * 8,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
+        };
+
+        let (results, _) = compact_telemetry_payloads(
+            vec![
+                create_test_telemetry_uncompressed(20, "s1"),
+                create_test_telemetry_uncompressed(15, "s2"),
+            ],
+            &config,
+        )
+        .unwrap();
+
+        assert!(results.len() > 1);
+        let mut names = Vec::new();
+        for result in &results {
+            assert!(result.payload.len() <= config.max_payload_size);
+            assert_eq!(result.signal, Signal::Traces);
+            assert_eq!(result.span_count, Some(span_names(&result.payload).len()));
+            names.extend(span_names(&result.payload));
+        }
+        // Every span is sent exactly once, in order
+        let mut expected: Vec<String> = (0..20).map(|i| format!("test-span-{i}")).collect();
+        expected.extend((0..15).map(|i| format!("test-span-{i}")));
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn test_compact_splits_single_oversized_payload() {
+        let telemetry = create_test_telemetry_uncompressed(10, "s1");
+        let config = SpanCompactionConfig {
+            compression: CompressionPreference::Gzip,
+            gzip_compression_level: 9,
+            max_payload_size: telemetry.payload.len() / 2,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
+        };
+
+        let (results, _) = compact_telemetry_payloads(vec![telemetry], &config).unwrap();
+
+        assert!(results.len() > 1);
+        assert!(results
+            .iter()
+            .all(|result| result.content_encoding.as_deref() == Some("gzip")));
+
+        // A single span over the limit cannot be split and is sent on its own
+        let config = SpanCompactionConfig {
+            max_payload_size: 1,
+            ..config
+        };
+        let (results, _) =
+            compact_telemetry_payloads(vec![create_test_telemetry_uncompressed(1, "s1")], &config)
+                .unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
     #[test]
     fn test_compact_empty_batch_returns_error() {
         let config = SpanCompactionConfig::default();
@@ -538,10 +1057,14 @@ mod tests {
         let config = SpanCompactionConfig {
             compression: CompressionPreference::None,
             gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
         };
-        let result =
-            compact_telemetry_payloads(vec![telemetry_good, telemetry_bad_payload], &config)
-                .unwrap();
+        let result = compact_into_one(vec![telemetry_good, telemetry_bad_payload], &config);
         // Should compact the good one, skipping the bad one
         let decoded_request = ExportTraceServiceRequest::decode(result.payload.as_slice()).unwrap();
         assert_eq!(
@@ -550,6 +1073,264 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_compact_applies_attribute_transform() {
+        let config = SpanCompactionConfig {
+            compression: CompressionPreference::None,
+            gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::parse(
+                r#"{"resource": {"set": {"deployment.environment": "prod"}}}"#,
+            )
+            .unwrap(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
+        };
+
+        // A single item is transformed too, and merged items are transformed before merging
+        for batch in [
+            vec![create_test_telemetry_uncompressed(1, "s1")],
+            vec![
+                create_test_telemetry_uncompressed(1, "s1"),
+                create_test_telemetry_uncompressed(2, "s2"),
+            ],
+        ] {
+            let result = compact_into_one(batch, &config);
+            let decoded = ExportTraceServiceRequest::decode(result.payload.as_slice()).unwrap();
+            for resource_spans in &decoded.resource_spans {
+                let attributes = &resource_spans.resource.as_ref().unwrap().attributes;
+                assert!(attributes
+                    .iter()
+                    .any(|attribute| attribute.key == "deployment.environment"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_compact_normalizes_semconv_before_transform() {
+        let config = SpanCompactionConfig {
+            compression: CompressionPreference::None,
+            gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::parse(
+                r#"{"span": {"rename": {"http.response.status_code": "status"}}}"#,
+            )
+            .unwrap(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::new(true),
+        };
+        let mut request = create_test_request(1);
+        request.resource_spans[0].scope_spans[0].spans[0]
+            .attributes
+            .push(opentelemetry_proto::tonic::common::v1::KeyValue {
+                key: "http.status_code".to_string(),
+                value: None,
+            });
+        let telemetry = TelemetryData {
+            payload: encode_otlp_payload(&request),
+            ..create_test_telemetry_uncompressed(0, "s1")
+        };
+
+        let result = compact_into_one(vec![telemetry], &config);
+        let decoded = ExportTraceServiceRequest::decode(result.payload.as_slice()).unwrap();
+        let attributes = &decoded.resource_spans[0].scope_spans[0].spans[0].attributes;
+        assert!(attributes.iter().any(|attribute| attribute.key == "status"));
+    }
+
+    #[test]
+    fn test_compact_applies_span_filter() {
+        let config = SpanCompactionConfig {
+            compression: CompressionPreference::None,
+            gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::parse(r#"[{"name": "test-span-0"}]"#).unwrap(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
+        };
+
+        let (result, _) = compact_telemetry_payloads(
+            vec![
+                create_test_telemetry_uncompressed(1, "s1"),
+                create_test_telemetry_uncompressed(2, "s2"),
+            ],
+            &config,
+        )
+        .unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(span_names(&result[0].payload), vec!["test-span-1"]);
+
+        // A batch whose spans are all filtered out yields no payload
+        let (result, _) =
+            compact_telemetry_payloads(vec![create_test_telemetry_uncompressed(1, "s1")], &config)
+                .unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_compact_drops_redelivered_spans() {
+        let config = SpanCompactionConfig {
+            compression: CompressionPreference::None,
+            gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: Some(Arc::new(SpanDeduplicator::new(100))),
+            semconv_normalizer: SemconvNormalizer::default(),
+        };
+        let item = |span_ids: &[u8]| {
+            let mut request = create_test_request(span_ids.len());
+            for (span, id) in request.resource_spans[0].scope_spans[0]
+                .spans
+                .iter_mut()
+                .zip(span_ids)
+            {
+                span.trace_id = vec![1; 16];
+                span.span_id = vec![*id; 8];
+            }
+            TelemetryData {
+                payload: encode_otlp_payload(&request),
+                ..create_test_telemetry_uncompressed(0, "s1")
+            }
+        };
+
+        let (result, span_keys) =
+            compact_telemetry_payloads(vec![item(&[1, 2]), item(&[2])], &config).unwrap();
+        assert_eq!(
+            span_names(&result[0].payload),
+            vec!["test-span-0", "test-span-1"]
+        );
+        assert_eq!(span_keys.len(), 2);
+
+        // Until the spans are recorded as delivered, a redelivery is compacted again
+        let (result, _) = compact_telemetry_payloads(vec![item(&[1, 2])], &config).unwrap();
+        assert_eq!(result.len(), 1);
+
+        // A redelivery in a later invocation is dropped once delivered
+        config.span_deduplicator.as_ref().unwrap().record(span_keys);
+        let (result, span_keys) = compact_telemetry_payloads(vec![item(&[1, 2])], &config).unwrap();
+        assert!(result.is_empty());
+        assert!(span_keys.is_empty());
+        let (result, _) = compact_telemetry_payloads(vec![item(&[2, 3])], &config).unwrap();
+        assert_eq!(span_names(&result[0].payload), vec!["test-span-1"]);
+    }
+
+    #[test]
+    fn test_compact_samples_traces_across_items() {
+        let config = SpanCompactionConfig {
+            compression: CompressionPreference::None,
+            gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::new(0),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
+        };
+        // One span per item; the error span of the second item keeps the first one
+        let item = |name: &str, trace_id: u8, code: StatusCode| {
+            let mut request = create_test_request(1);
+            let span = &mut request.resource_spans[0].scope_spans[0].spans[0];
+            span.name = name.to_string();
+            span.trace_id = vec![trace_id; 16];
+            span.status = Some(Status {
+                code: code as i32,
+                ..Default::default()
+            });
+            TelemetryData {
+                payload: encode_otlp_payload(&request),
+                ..create_test_telemetry_uncompressed(0, "s1")
+            }
+        };
+
+        let (result, _) = compact_telemetry_payloads(
+            vec![
+                item("root", 1, StatusCode::Unset),
+                item("child", 1, StatusCode::Error),
+                item("healthy", 2, StatusCode::Ok),
+            ],
+            &config,
+        )
+        .unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(span_names(&result[0].payload), vec!["root", "child"]);
+
+        let (result, _) =
+            compact_telemetry_payloads(vec![item("healthy", 2, StatusCode::Ok)], &config).unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_compact_converts_json_payloads() {
+        let json_item = |span_name: &str| TelemetryData {
+            payload: serde_json::to_vec(&serde_json::json!({
+                "resourceSpans": [{
+                    "scopeSpans": [{
+                        "spans": [{
+                            "traceId": "5b8efff798038103d269b633813fc60c",
+                            "spanId": "eee19b7ec3c1b174",
+                            "name": span_name,
+                            "kind": 2,
+                            "startTimeUnixNano": "1544712660000000000",
+                            "endTimeUnixNano": "1544712661000000000"
+                        }]
+                    }]
+                }]
+            }))
+            .unwrap(),
+            content_type: "application/json".to_string(),
+            ..create_test_telemetry_uncompressed(0, "json")
+        };
+        let config = SpanCompactionConfig {
+            compression: CompressionPreference::None,
+            gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
+        };
+
+        // A single JSON item is converted even though nothing is merged
+        let result = compact_into_one(vec![json_item("alone")], &config);
+        assert_eq!(result.content_type, "application/x-protobuf");
+        let decoded = ExportTraceServiceRequest::decode(result.payload.as_slice()).unwrap();
+        assert_eq!(
+            decoded.resource_spans[0].scope_spans[0].spans[0].name,
+            "alone"
+        );
+
+        // JSON and protobuf items are merged; unparsable JSON is skipped
+        let invalid_json = TelemetryData {
+            payload: b"{not json".to_vec(),
+            ..json_item("invalid")
+        };
+        let result = compact_into_one(
+            vec![
+                json_item("from-json"),
+                create_test_telemetry_uncompressed(2, "protobuf"),
+                invalid_json,
+            ],
+            &config,
+        );
+        let decoded = ExportTraceServiceRequest::decode(result.payload.as_slice()).unwrap();
+        let names: Vec<&str> = decoded
+            .resource_spans
+            .iter()
+            .flat_map(|resource| &resource.scope_spans)
+            .flat_map(|scope| &scope.spans)
+            .map(|span| span.name.as_str())
+            .collect();
+        assert_eq!(names, ["from-json", "test-span-0", "test-span-1"]);
+    }
+
     #[test]
     fn test_compact_all_decode_failures() {
         let telemetry_bad1 = TelemetryData {
@@ -568,4 +1349,149 @@ mod tests {
             .to_string()
             .contains("All payloads in batch failed to decode"));
     }
+
+    fn create_test_logs_telemetry(record_count: usize) -> TelemetryData {
+        let request = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                scope_logs: vec![ScopeLogs {
+                    log_records: vec![LogRecord::default(); record_count],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        TelemetryData {
+            endpoint: "http://example.com/v1/logs".to_string(),
+            payload: request.encode_to_vec(),
+            signal: Signal::Logs,
+            ..create_test_telemetry_uncompressed(0, "logs")
+        }
+    }
+
+    #[test]
+    fn test_compact_multiple_log_payloads() {
+        let config = SpanCompactionConfig {
+            compression: CompressionPreference::None,
+            gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
+        };
+        let result = compact_into_one(
+            vec![create_test_logs_telemetry(2), create_test_logs_telemetry(1)],
+            &config,
+        );
+
+        assert_eq!(result.signal, Signal::Logs);
+        let decoded_request = ExportLogsServiceRequest::decode(result.payload.as_slice()).unwrap();
+        assert_eq!(decoded_request.resource_logs.len(), 2);
+        assert_eq!(
+            decoded_request.resource_logs[0].scope_logs[0]
+                .log_records
+                .len()
+                + decoded_request.resource_logs[1].scope_logs[0]
+                    .log_records
+                    .len(),
+            3
+        );
+    }
+
+    fn create_test_metrics_telemetry(metric: Metric) -> TelemetryData {
+        let request = ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                scope_metrics: vec![ScopeMetrics {
+                    metrics: vec![metric],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        TelemetryData {
+            endpoint: "http://example.com/v1/metrics".to_string(),
+            payload: request.encode_to_vec(),
+            signal: Signal::Metrics,
+            ..create_test_telemetry_uncompressed(0, "metrics")
+        }
+    }
+
+    fn sum_metric(name: &str, value: i64) -> Metric {
+        Metric {
+            name: name.to_string(),
+            unit: "1".to_string(),
+            data: Some(Data::Sum(Sum {
+                data_points: vec![NumberDataPoint {
+                    value: Some(number_data_point::Value::AsInt(value)),
+                    ..Default::default()
+                }],
+                aggregation_temporality: AggregationTemporality::Delta as i32,
+                is_monotonic: true,
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compact_metrics_merges_identical_streams() {
+        let config = SpanCompactionConfig {
+            compression: CompressionPreference::None,
+            gzip_compression_level: 9,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            attribute_transform: AttributeTransform::default(),
+            span_filter: SpanFilter::default(),
+            trace_sampler: TraceSampler::default(),
+            span_deduplicator: None,
+            semconv_normalizer: SemconvNormalizer::default(),
+        };
+        let mut cumulative = sum_metric("requests", 5);
+        if let Some(Data::Sum(sum)) = &mut cumulative.data {
+            sum.aggregation_temporality = AggregationTemporality::Cumulative as i32;
+        }
+        let result = compact_into_one(
+            vec![
+                create_test_metrics_telemetry(sum_metric("requests", 1)),
+                create_test_metrics_telemetry(sum_metric("requests", 2)),
+                create_test_metrics_telemetry(sum_metric("errors", 3)),
+                create_test_metrics_telemetry(cumulative),
+            ],
+            &config,
+        );
+
+        assert_eq!(result.signal, Signal::Metrics);
+        let decoded_request =
+            ExportMetricsServiceRequest::decode(result.payload.as_slice()).unwrap();
+        assert_eq!(decoded_request.resource_metrics.len(), 1);
+        assert_eq!(decoded_request.resource_metrics[0].scope_metrics.len(), 1);
+        let metrics = &decoded_request.resource_metrics[0].scope_metrics[0].metrics;
+        let data_point_counts: Vec<_> = metrics
+            .iter()
+            .map(|metric| match &metric.data {
+                Some(Data::Sum(sum)) => (metric.name.as_str(), sum.data_points.len()),
+                _ => panic!("unexpected metric type"),
+            })
+            .collect();
+        // A stream with another temporality is kept apart
+        assert_eq!(
+            data_point_counts,
+            vec![("requests", 2), ("errors", 1), ("requests", 1)]
+        );
+    }
+
+    #[test]
+    fn test_compact_mixed_signals_returns_error() {
+        let config = SpanCompactionConfig::default();
+        let result = compact_telemetry_payloads(
+            vec![
+                create_test_telemetry_uncompressed(1, "s1"),
+                create_test_logs_telemetry(1),
+            ],
+            &config,
+        );
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("different signals"));
+    }
 }
@@ -0,0 +1,206 @@
+//! Deduplication of redelivered spans.
+//!
+//! CloudWatch Logs subscriptions deliver at least once, and a redelivered record without a
+//! `batch_id` escapes [`BatchDeduplicator`]. A [`SpanDeduplicator`] remembers the
+//! `(trace_id, span_id)` pairs of the spans forwarded in a bounded LRU cache, and
+//! [`compact_telemetry_payloads`] drops the spans it has already seen, or repeated within a
+//! batch, across the invocations handled by the same Lambda execution environment.
+//!
+//! Compaction only filters; the spans it kept are recorded once their batch is delivered, so a
+//! batch that failed to send and is redelivered is forwarded again. Deduplication is disabled by
+//! default, and enabled with the
+//! `span_dedup_capacity` setting of the configuration file or the
+//! `OTLP_FORWARDER_SPAN_DEDUP_CAPACITY` environment variable.
+//!
+//! [`BatchDeduplicator`]: crate::batch_dedup::BatchDeduplicator
+//! [`compact_telemetry_payloads`]: crate::span_compactor::compact_telemetry_payloads
+
+use crate::span_filter::retain_spans;
+use opentelemetry_proto::tonic::trace::v1::ResourceSpans;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Environment variable holding the number of spans remembered for deduplication (`0` disables it)
+pub const SPAN_DEDUP_CAPACITY_ENV_VAR: &str = "OTLP_FORWARDER_SPAN_DEDUP_CAPACITY";
+
+/// Trace ID followed by span ID
+pub type SpanKey = [u8; 24];
+
+fn span_key(trace_id: &[u8], span_id: &[u8]) -> Option<SpanKey> {
+    if trace_id.len() != 16 || span_id.len() != 8 {
+        return None;
+    }
+    let mut key = [0; 24];
+    key[..16].copy_from_slice(trace_id);
+    key[16..].copy_from_slice(span_id);
+    Some(key)
+}
+
+#[derive(Debug, Default)]
+struct SeenSpans {
+    /// Span key to the tick of its last use
+    ticks: HashMap<SpanKey, u64>,
+    /// Uses in order; entries whose tick is no longer current are stale
+    order: VecDeque<(SpanKey, u64)>,
+    next_tick: u64,
+}
+
+impl SeenSpans {
+    /// Marks the key as most recently used, returning true if it was already present
+    fn touch(&mut self, key: SpanKey) -> bool {
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        self.order.push_back((key, tick));
+        self.ticks.insert(key, tick).is_some()
+    }
+
+    /// Evicts the least recently used keys beyond `capacity`
+    fn evict(&mut self, capacity: usize) {
+        while self.ticks.len() > capacity {
+            let Some((key, tick)) = self.order.pop_front() else {
+                break;
+            };
+            if self.ticks.get(&key) == Some(&tick) {
+                self.ticks.remove(&key);
+            }
+        }
+        // Drop stale uses so the queue stays bounded too
+        if self.order.len() > capacity.saturating_mul(2) {
+            let ticks = &self.ticks;
+            self.order
+                .retain(|(key, tick)| ticks.get(key) == Some(tick));
+        }
+    }
+}
+
+/// Bounded LRU set of the spans that have already been forwarded
+#[derive(Debug)]
+pub struct SpanDeduplicator {
+    capacity: usize,
+    seen: Mutex<SeenSpans>,
+}
+
+impl SpanDeduplicator {
+    /// Creates a deduplicator remembering up to `capacity` spans
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: Mutex::new(SeenSpans::default()),
+        }
+    }
+
+    /// Returns the process-wide deduplicator, created with `capacity` on first use
+    ///
+    /// The cache is shared by every configuration of the process, so the capacity passed on the
+    /// first call applies.
+    pub fn global(capacity: usize) -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<SpanDeduplicator>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::new(capacity))).clone()
+    }
+
+    /// Returns the maximum number of spans remembered
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Drops the spans of a trace request that were already recorded, or repeated within it,
+    /// returning the number of spans dropped
+    ///
+    /// Nothing is recorded: pass the [`span_keys`] of the request to [`Self::record`] once it is
+    /// delivered. Spans without a valid trace or span ID are always kept. Scopes and resources
+    /// left without spans are removed as well.
+    pub fn filter(&self, resource_spans: &mut Vec<ResourceSpans>) -> usize {
+        let seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        let mut kept = HashSet::new();
+        retain_spans(resource_spans, |span| {
+            span_key(&span.trace_id, &span.span_id)
+                .is_none_or(|key| !seen.ticks.contains_key(&key) && kept.insert(key))
+        })
+    }
+
+    /// Records delivered spans, so their redeliveries are dropped
+    pub fn record(&self, keys: impl IntoIterator<Item = SpanKey>) {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        for key in keys {
+            seen.touch(key);
+        }
+        seen.evict(self.capacity);
+    }
+}
+
+/// Returns the keys of the spans of a trace request with a valid trace and span ID
+pub fn span_keys(resource_spans: &[ResourceSpans]) -> Vec<SpanKey> {
+    resource_spans
+        .iter()
+        .flat_map(|resource| &resource.scope_spans)
+        .flat_map(|scope| &scope.spans)
+        .filter_map(|span| span_key(&span.trace_id, &span.span_id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::trace::v1::{ScopeSpans, Span};
+
+    fn request(span_ids: &[u8]) -> Vec<ResourceSpans> {
+        vec![ResourceSpans {
+            scope_spans: vec![ScopeSpans {
+                spans: span_ids
+                    .iter()
+                    .map(|id| Span {
+                        trace_id: vec![1; 16],
+                        span_id: vec![*id; 8],
+                        ..Default::default()
+                    })
+                    .chain([Span::default()])
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }]
+    }
+
+    /// Filters a request, then records the spans kept, as if it was delivered
+    fn deliver(dedup: &SpanDeduplicator, span_ids: &[u8]) -> usize {
+        let mut resource_spans = request(span_ids);
+        let dropped = dedup.filter(&mut resource_spans);
+        dedup.record(span_keys(&resource_spans));
+        dropped
+    }
+
+    #[test]
+    fn test_drops_recorded_spans() {
+        let dedup = SpanDeduplicator::new(10);
+
+        // Duplicates within a request are dropped too; spans without IDs are kept
+        let mut first = request(&[1, 2, 2]);
+        assert_eq!(dedup.filter(&mut first), 1);
+        assert_eq!(first[0].scope_spans[0].spans.len(), 3);
+        assert_eq!(span_keys(&first).len(), 2);
+
+        // Filtering records nothing, so an undelivered request is kept when redelivered
+        assert_eq!(dedup.filter(&mut request(&[1, 2])), 0);
+
+        dedup.record(span_keys(&first));
+        let mut redelivered = request(&[1, 2, 3]);
+        assert_eq!(dedup.filter(&mut redelivered), 2);
+        assert_eq!(redelivered[0].scope_spans[0].spans.len(), 2);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let dedup = SpanDeduplicator::new(2);
+        deliver(&dedup, &[1]);
+        deliver(&dedup, &[2]);
+        // Recording 1 again makes 2 the least recently used
+        dedup.record(span_keys(&request(&[1])));
+        deliver(&dedup, &[3]);
+
+        assert_eq!(deliver(&dedup, &[2]), 0);
+        assert_eq!(deliver(&dedup, &[3]), 1);
+        let seen = dedup.seen.lock().unwrap();
+        assert_eq!(seen.ticks.len(), 2);
+        assert!(seen.order.len() <= 4);
+    }
+}
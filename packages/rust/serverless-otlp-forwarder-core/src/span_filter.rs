@@ -0,0 +1,365 @@
+//! Rules dropping spans before they are sent.
+//!
+//! A [`SpanFilter`] keeps health checks and noisy internal spans out of the backend. It holds a
+//! list of rules; a span matching any rule is dropped. A rule matches when all of its conditions
+//! hold:
+//!
+//! - `name` / `name_regex`: the span name equals the value, or matches the regular expression;
+//! - `status`: the span status code is `unset`, `ok` or `error`;
+//! - `attributes`: each span attribute equals the value, or matches `{regex: ...}` for strings;
+//! - `resource_attributes`: the same, for the attributes of the resource of the span.
+//!
+//! A rule with only `resource_attributes` drops the whole resource spans, and a rule without any
+//! condition matches nothing. The filter is applied by [`compact_telemetry_payloads`] to every
+//! decoded trace request, and configured through the `span_filter` setting of the configuration
+//! file or the `OTLP_FORWARDER_SPAN_FILTER` environment variable, holding the same document as
+//! JSON or YAML:
+//!
+//! ```yaml
+//! - name: GET /health
+//! - attributes:
+//!     url.path:
+//!       regex: ^/internal/
+//!   status: ok
+//! - resource_attributes:
+//!     service.name: canary
+//! ```
+//!
+//! [`compact_telemetry_payloads`]: crate::span_compactor::compact_telemetry_payloads
+
+use crate::transform::AttributeValue;
+use opentelemetry_proto::tonic::common::v1::{any_value, KeyValue};
+use opentelemetry_proto::tonic::trace::v1::{status::StatusCode, ResourceSpans, Span};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+/// Environment variable holding a span filter document, as JSON or YAML
+pub const SPAN_FILTER_ENV_VAR: &str = "OTLP_FORWARDER_SPAN_FILTER";
+
+/// Rules dropping spans before compaction; a span matching any rule is dropped
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SpanFilter {
+    pub rules: Vec<SpanFilterRule>,
+}
+
+/// Conditions a span must all meet to be dropped
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpanFilterRule {
+    /// Exact span name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Regular expression the span name matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_regex: Option<Pattern>,
+    /// Status code of the span
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<SpanStatus>,
+    /// Conditions on the span attributes
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, AttributeMatch>,
+    /// Conditions on the attributes of the resource of the span
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub resource_attributes: BTreeMap<String, AttributeMatch>,
+}
+
+/// Span status code matched by a [`SpanFilterRule`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpanStatus {
+    Unset,
+    Ok,
+    Error,
+}
+
+/// Condition on the value of an attribute
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AttributeMatch {
+    /// The attribute is a string matching the regular expression
+    Regex { regex: Pattern },
+    /// The attribute equals the value
+    Equals(AttributeValue),
+}
+
+/// Regular expression, compared by its source
+#[derive(Debug, Clone)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    /// Compiles a regular expression
+    pub fn new(pattern: &str) -> anyhow::Result<Self> {
+        Regex::new(pattern)
+            .map(Self)
+            .map_err(|_| anyhow::anyhow!("Invalid span filter regular expression"))
+    }
+
+    /// Returns true if the expression matches anywhere in `value`
+    pub fn is_match(&self, value: &str) -> bool {
+        self.0.is_match(value)
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for Pattern {}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Self::new(&pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+impl AttributeMatch {
+    fn matches(&self, attributes: &[KeyValue], key: &str) -> bool {
+        let Some(value) = attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .and_then(|attribute| attribute.value.as_ref())
+        else {
+            return false;
+        };
+        match self {
+            Self::Regex { regex } => match &value.value {
+                Some(any_value::Value::StringValue(value)) => regex.is_match(value),
+                _ => false,
+            },
+            Self::Equals(expected) => *value == expected.to_any_value(),
+        }
+    }
+}
+
+impl SpanFilterRule {
+    /// Returns true if the rule has no condition, so it matches nothing
+    pub fn is_empty(&self) -> bool {
+        self.resource_attributes.is_empty() && !self.has_span_conditions()
+    }
+
+    fn has_span_conditions(&self) -> bool {
+        self.name.is_some()
+            || self.name_regex.is_some()
+            || self.status.is_some()
+            || !self.attributes.is_empty()
+    }
+
+    fn matches_resource(&self, attributes: &[KeyValue]) -> bool {
+        self.resource_attributes
+            .iter()
+            .all(|(key, condition)| condition.matches(attributes, key))
+    }
+
+    fn matches_span(&self, span: &Span) -> bool {
+        let status = match span.status.as_ref().map(|status| status.code()) {
+            Some(StatusCode::Ok) => SpanStatus::Ok,
+            Some(StatusCode::Error) => SpanStatus::Error,
+            Some(StatusCode::Unset) | None => SpanStatus::Unset,
+        };
+        self.name.as_ref().is_none_or(|name| *name == span.name)
+            && self
+                .name_regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(&span.name))
+            && self.status.is_none_or(|expected| expected == status)
+            && self
+                .attributes
+                .iter()
+                .all(|(key, condition)| condition.matches(&span.attributes, key))
+    }
+}
+
+/// Keeps the spans for which `keep` returns true, returning the number of spans dropped
+///
+/// Scopes and resources left without spans are removed as well.
+pub(crate) fn retain_spans(
+    resource_spans: &mut Vec<ResourceSpans>,
+    mut keep: impl FnMut(&Span) -> bool,
+) -> usize {
+    let mut dropped = 0;
+    resource_spans.retain_mut(|resource| {
+        resource.scope_spans.retain_mut(|scope| {
+            let count = scope.spans.len();
+            scope.spans.retain(&mut keep);
+            dropped += count - scope.spans.len();
+            count == scope.spans.len() || !scope.spans.is_empty()
+        });
+        !resource.scope_spans.is_empty()
+    });
+    dropped
+}
+
+impl SpanFilter {
+    /// Parses a filter document, as JSON or YAML
+    pub fn parse(document: &str) -> anyhow::Result<Self> {
+        // JSON documents are valid YAML; parse errors may quote values, so they are not reported
+        serde_yaml::from_str(document).map_err(|_| anyhow::anyhow!("Invalid span filter document"))
+    }
+
+    /// Returns true if the filter drops nothing
+    pub fn is_empty(&self) -> bool {
+        self.rules.iter().all(SpanFilterRule::is_empty)
+    }
+
+    /// Drops the matching spans of a trace request, returning the number of spans dropped
+    ///
+    /// Scopes and resources left without spans are removed as well.
+    pub fn apply(&self, resource_spans: &mut Vec<ResourceSpans>) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+        let mut dropped = 0;
+        resource_spans.retain_mut(|resource| {
+            let resource_attributes = resource
+                .resource
+                .as_ref()
+                .map_or(&[][..], |resource| &resource.attributes[..]);
+            let rules: Vec<&SpanFilterRule> = self
+                .rules
+                .iter()
+                .filter(|rule| !rule.is_empty() && rule.matches_resource(resource_attributes))
+                .collect();
+            if rules.is_empty() {
+                return true;
+            }
+            if rules.iter().any(|rule| !rule.has_span_conditions()) {
+                dropped += resource
+                    .scope_spans
+                    .iter()
+                    .map(|scope| scope.spans.len())
+                    .sum::<usize>();
+                return false;
+            }
+            resource.scope_spans.retain_mut(|scope| {
+                let count = scope.spans.len();
+                scope
+                    .spans
+                    .retain(|span| !rules.iter().any(|rule| rule.matches_span(span)));
+                dropped += count - scope.spans.len();
+                count == scope.spans.len() || !scope.spans.is_empty()
+            });
+            !resource.scope_spans.is_empty()
+        });
+        dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::common::v1::AnyValue;
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+    use opentelemetry_proto::tonic::trace::v1::{ScopeSpans, Status};
+
+    fn attribute(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value.to_string())),
+            }),
+        }
+    }
+
+    fn span(name: &str, path: &str, code: StatusCode) -> Span {
+        Span {
+            name: name.to_string(),
+            attributes: vec![attribute("url.path", path)],
+            status: Some(Status {
+                code: code as i32,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn resource_spans(service: &str, spans: Vec<Span>) -> ResourceSpans {
+        ResourceSpans {
+            resource: Some(Resource {
+                attributes: vec![attribute("service.name", service)],
+                ..Default::default()
+            }),
+            scope_spans: vec![ScopeSpans {
+                spans,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_span_filter() {
+        let filter = SpanFilter::parse(
+            "- name: GET /health\n- attributes:\n    url.path:\n      regex: ^/internal/\n    http.response.status_code: 200\n  status: ok\n",
+        )
+        .unwrap();
+        assert_eq!(filter.rules.len(), 2);
+        assert_eq!(
+            filter.rules[1].attributes["http.response.status_code"],
+            AttributeMatch::Equals(AttributeValue::Number(200.into()))
+        );
+        assert!(!filter.is_empty());
+        assert!(SpanFilter::parse("[{}]").unwrap().is_empty());
+
+        let err = SpanFilter::parse(r#"[{"name_regex": "(secret"}]"#).unwrap_err();
+        assert!(!err.to_string().contains("secret"));
+        assert!(SpanFilter::parse(r#"[{"names": "GET /health"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_apply_drops_matching_spans_and_resources() {
+        let filter = SpanFilter::parse(
+            r#"[
+                {"name": "GET /health"},
+                {"attributes": {"url.path": {"regex": "^/internal/"}}, "status": "ok"},
+                {"resource_attributes": {"service.name": "canary"}},
+                {"resource_attributes": {"service.name": "checkout"}, "name_regex": "^db\\."}
+            ]"#,
+        )
+        .unwrap();
+        let mut request = vec![
+            resource_spans(
+                "checkout",
+                vec![
+                    span("GET /health", "/health", StatusCode::Unset),
+                    span("GET /internal/metrics", "/internal/metrics", StatusCode::Ok),
+                    span("GET /internal/debug", "/internal/debug", StatusCode::Error),
+                    span("db.query", "/orders", StatusCode::Unset),
+                    span("POST /orders", "/orders", StatusCode::Ok),
+                ],
+            ),
+            resource_spans(
+                "canary",
+                vec![span("POST /orders", "/orders", StatusCode::Ok)],
+            ),
+            resource_spans(
+                "payments",
+                vec![
+                    span("db.query", "/pay", StatusCode::Unset),
+                    span("GET /health", "/health", StatusCode::Ok),
+                ],
+            ),
+            resource_spans("idle", vec![span("GET /health", "/health", StatusCode::Ok)]),
+        ];
+
+        assert_eq!(filter.apply(&mut request), 6);
+        assert_eq!(request.len(), 2);
+        let names: Vec<&str> = request[0].scope_spans[0]
+            .spans
+            .iter()
+            .map(|span| span.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["GET /internal/debug", "POST /orders"]);
+        assert_eq!(request[1].scope_spans[0].spans[0].name, "db.query");
+    }
+}
@@ -0,0 +1,55 @@
+//! SQS event source.
+//!
+//! Producers can send exporter output to an SQS queue read by the forwarder, each message body
+//! holding one log record, such as an otlp-stdout envelope. [`sqs_batch_items`] unwraps the
+//! messages of an event into [`BatchItem`]s, for
+//! [`process_batch_items`](crate::partial_batch::process_batch_items) to report the messages
+//! that failed.
+
+use crate::partial_batch::BatchItem;
+use aws_lambda_events::event::sqs::SqsEvent;
+
+/// Unwraps each message of an SQS event into a [`BatchItem`] identified by its message id
+///
+/// The body of a message is a single log record with `queue_name` as source. A message without a
+/// body yields an undecodable item.
+pub fn sqs_batch_items(event_payload: SqsEvent, queue_name: &str) -> Vec<BatchItem> {
+    event_payload
+        .records
+        .into_iter()
+        .map(|message| {
+            let message_id = message.message_id.unwrap_or_default();
+            match message.body {
+                Some(body) => BatchItem::new(message_id, queue_name, vec![body]),
+                None => BatchItem::undecodable(message_id),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sqs_batch_items() {
+        let event: SqsEvent = serde_json::from_value(json!({
+            "Records": [
+                {"messageId": "m1", "body": "log record", "eventSource": "aws:sqs"},
+                {"messageId": "m2", "eventSource": "aws:sqs"}
+            ]
+        }))
+        .unwrap();
+
+        let items = sqs_batch_items(event, "test-queue");
+        assert_eq!(
+            items,
+            vec![
+                BatchItem::new("m1", "test-queue", vec!["log record".to_string()]),
+                BatchItem::undecodable("m2"),
+            ]
+        );
+        assert!(sqs_batch_items(SqsEvent::default(), "test-queue").is_empty());
+    }
+}
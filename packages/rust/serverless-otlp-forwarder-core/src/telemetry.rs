@@ -1,12 +1,71 @@
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose, Engine};
 use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
 use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
 use otlp_stdout_span_exporter::ExporterOutput;
 use prost::Message;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::fmt;
 use std::io::{Read, Write};
 use tracing;
+use url::Url;
+
+/// OTLP signal carried by a [`TelemetryData`] payload
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Signal {
+    /// `ExportTraceServiceRequest` payloads, sent to `/v1/traces`
+    #[default]
+    Traces,
+    /// `ExportLogsServiceRequest` payloads, sent to `/v1/logs`
+    Logs,
+    /// `ExportMetricsServiceRequest` payloads, sent to `/v1/metrics`
+    Metrics,
+}
+
+impl Signal {
+    /// Returns the OTLP/HTTP path of the signal
+    pub const fn path(self) -> &'static str {
+        match self {
+            Self::Traces => "/v1/traces",
+            Self::Logs => "/v1/logs",
+            Self::Metrics => "/v1/metrics",
+        }
+    }
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Traces => "traces",
+            Self::Logs => "logs",
+            Self::Metrics => "metrics",
+        }
+    }
+
+    /// Infers the signal from the OTLP/HTTP endpoint (URL or path) a payload was meant for
+    ///
+    /// Endpoints ending in `/v1/logs` carry logs and endpoints ending in `/v1/metrics` carry
+    /// metrics; anything else is treated as traces.
+    pub fn from_endpoint(endpoint: &str) -> Self {
+        let path = Url::parse(endpoint)
+            .map(|url| url.path().to_string())
+            .unwrap_or_else(|_| endpoint.to_string());
+        let path = path.trim_end_matches('/');
+        [Self::Logs, Self::Metrics]
+            .into_iter()
+            .find(|signal| path.ends_with(signal.path()))
+            .unwrap_or(Self::Traces)
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Core structure representing telemetry data to be forwarded
 #[derive(Clone, Debug)]
 pub struct TelemetryData {
@@ -20,6 +79,16 @@ pub struct TelemetryData {
     pub content_type: String,
     /// Optional content encoding (e.g., gzip)
     pub content_encoding: Option<String>,
+    /// Optional content-addressable batch id carried over from the exporter envelope
+    pub batch_id: Option<String>,
+    /// OTLP signal of the payload, which selects the collector path it is sent to
+    pub signal: Signal,
+    /// Number of spans of a trace payload, when known
+    ///
+    /// Set by [`compact_telemetry_payloads`](crate::compact_telemetry_payloads), which decodes
+    /// the spans anyway, so the spans forwarded can be counted without decoding the payload
+    /// again.
+    pub span_count: Option<usize>,
 }
 
 impl Default for TelemetryData {
@@ -31,6 +100,9 @@ impl Default for TelemetryData {
             payload: Vec::new(),
             content_type: "application/x-protobuf".to_string(),
             content_encoding: None, // No compression by default
+            batch_id: None,
+            signal: Signal::Traces,
+            span_count: None,
         }
     }
 }
@@ -46,18 +118,21 @@ impl TelemetryData {
     /// * `payload` - The raw payload bytes
     /// * `content_type` - The content type of the payload
     /// * `content_encoding` - The optional content encoding of the payload
+    /// * `signal` - The OTLP signal of the payload, which selects the JSON schema
     ///
     /// # Returns
     ///
     /// The binary protobuf payload
-    fn convert_to_protobuf(
+    pub(crate) fn convert_to_protobuf(
         payload: Vec<u8>,
         content_type: &str,
         content_encoding: Option<&str>,
+        signal: Signal,
     ) -> Result<Vec<u8>> {
         tracing::debug!(
             content_type = %content_type,
             content_encoding = %content_encoding.unwrap_or("none"),
+            signal = %signal,
             "Converting payload to protobuf"
         );
 
@@ -84,7 +159,7 @@ impl TelemetryData {
             "application/json" => {
                 // Convert JSON to protobuf
                 tracing::debug!("Converting JSON to protobuf");
-                Self::convert_json_to_protobuf(&decompressed)
+                Self::convert_json_to_protobuf(&decompressed, signal)
             }
             _ => {
                 // Unknown format, log warning and return as-is
@@ -102,13 +177,19 @@ impl TelemetryData {
     /// Since the JSON schema matches the OTLP protobuf schema, we can directly
     /// deserialize the JSON into the protobuf structure and then serialize it back
     /// to binary protobuf format.
-    fn convert_json_to_protobuf(json_bytes: &[u8]) -> Result<Vec<u8>> {
-        // Parse the JSON into an ExportTraceServiceRequest
-        let request: ExportTraceServiceRequest = serde_json::from_slice(json_bytes)
-            .context("Failed to parse JSON as ExportTraceServiceRequest")?;
-
-        // Serialize to protobuf binary format
-        let protobuf_bytes = request.encode_to_vec();
+    fn convert_json_to_protobuf(json_bytes: &[u8], signal: Signal) -> Result<Vec<u8>> {
+        // Parse the JSON into the export request of the signal, then serialize to protobuf
+        let protobuf_bytes = match signal {
+            Signal::Traces => serde_json::from_slice::<ExportTraceServiceRequest>(json_bytes)
+                .context("Failed to parse JSON as ExportTraceServiceRequest")?
+                .encode_to_vec(),
+            Signal::Logs => serde_json::from_slice::<ExportLogsServiceRequest>(json_bytes)
+                .context("Failed to parse JSON as ExportLogsServiceRequest")?
+                .encode_to_vec(),
+            Signal::Metrics => serde_json::from_slice::<ExportMetricsServiceRequest>(json_bytes)
+                .context("Failed to parse JSON as ExportMetricsServiceRequest")?
+                .encode_to_vec(),
+        };
 
         tracing::debug!(
             payload_size_bytes = protobuf_bytes.len() as u64,
@@ -148,7 +229,13 @@ impl TelemetryData {
     }
 
     /// Creates a TelemetryData instance from a LogRecord
+    ///
+    /// If the record carries a `payload_sha256` checksum, it is verified against the
+    /// decoded payload before any decompression or protobuf conversion is attempted.
+    /// The signal is inferred from the record endpoint, see [`Signal::from_endpoint`].
     pub fn from_log_record(record: ExporterOutput) -> Result<Self> {
+        let signal = Signal::from_endpoint(&record.endpoint);
+
         // Decode base64 payload
         let raw_payload = if record.base64 {
             general_purpose::STANDARD
@@ -158,11 +245,20 @@ impl TelemetryData {
             record.payload.as_bytes().to_vec()
         };
 
+        if !record.verify_payload_sha256(&raw_payload) {
+            tracing::warn!(
+                payload_size_bytes = raw_payload.len() as u64,
+                "Payload checksum mismatch; record was corrupted in transit"
+            );
+            return Err(anyhow::anyhow!("Payload checksum mismatch"));
+        }
+
         // Convert to uncompressed protobuf format
         let protobuf_payload = Self::convert_to_protobuf(
             raw_payload,
             &record.content_type,
             Some(&record.content_encoding),
+            signal,
         )?;
 
         Ok(Self {
@@ -171,9 +267,37 @@ impl TelemetryData {
             payload: protobuf_payload,
             content_type: "application/x-protobuf".to_string(),
             content_encoding: None, // Decompressed at this stage
+            batch_id: record.batch_id,
+            signal,
+            span_count: None,
         })
     }
 
+    /// Wraps the payload in an exporter envelope, the inverse of [`Self::from_log_record`]
+    ///
+    /// The payload is base64 encoded with its checksum, and the endpoint is the local OTLP/HTTP
+    /// endpoint of the signal, so the envelope can be parsed again by a forwarder downstream.
+    pub fn to_log_record(&self) -> ExporterOutput {
+        ExporterOutput {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            source: self.source.clone(),
+            endpoint: format!("http://localhost:4318{}", self.signal.path()),
+            method: "POST".to_string(),
+            content_type: self.content_type.clone(),
+            content_encoding: self
+                .content_encoding
+                .clone()
+                .unwrap_or_else(|| "identity".to_string()),
+            headers: None,
+            payload: general_purpose::STANDARD.encode(&self.payload),
+            base64: true,
+            level: None,
+            payload_sha256: Some(ExporterOutput::compute_payload_sha256(&self.payload)),
+            batch_id: self.batch_id.clone(),
+            compression_backend: None,
+        }
+    }
+
     /// Creates a TelemetryData instance from a raw span (as serialized JSON)
     pub fn from_raw_span(span: Value, log_group: &str) -> Result<Self> {
         // Serialize the span data
@@ -183,7 +307,8 @@ impl TelemetryData {
         let raw_payload = json_string.as_bytes().to_vec();
 
         // Convert to protobuf format (uncompressed)
-        let protobuf_payload = Self::convert_to_protobuf(raw_payload, "application/json", None)?;
+        let protobuf_payload =
+            Self::convert_to_protobuf(raw_payload, "application/json", None, Signal::Traces)?;
 
         Ok(Self {
             source: log_group.to_string(),
@@ -192,6 +317,9 @@ impl TelemetryData {
             payload: protobuf_payload,
             content_type: "application/x-protobuf".to_string(),
             content_encoding: None, // No compression at this stage
+            batch_id: None,
+            signal: Signal::Traces,
+            span_count: None,
         })
     }
 }
@@ -238,6 +366,9 @@ mod tests {
             content_encoding: "gzip".to_string(),
             base64: true,
             level: Some("info".to_string()),
+            payload_sha256: None,
+            batch_id: Some("abc123".to_string()),
+            compression_backend: None,
         };
 
         let telemetry = TelemetryData::from_log_record(record).unwrap();
@@ -246,6 +377,68 @@ mod tests {
         assert_eq!(telemetry.content_type, "application/x-protobuf");
         // Since we're decompressing at from_log_record level, it should be None
         assert_eq!(telemetry.content_encoding, None);
+        assert_eq!(telemetry.batch_id.as_deref(), Some("abc123"));
+    }
+
+    fn create_test_record_with_checksum(payload_sha256: Option<String>) -> ExporterOutput {
+        ExporterOutput {
+            version: "test".to_string(),
+            source: "test-service".to_string(),
+            endpoint: "http://example.com".to_string(),
+            method: "POST".to_string(),
+            payload: create_test_payload(),
+            headers: None,
+            content_type: "application/x-protobuf".to_string(),
+            content_encoding: "gzip".to_string(),
+            base64: true,
+            level: None,
+            payload_sha256,
+            batch_id: None,
+            compression_backend: None,
+        }
+    }
+
+    #[test]
+    fn test_from_log_record_with_valid_checksum() {
+        let decoded = general_purpose::STANDARD
+            .decode(create_test_payload())
+            .unwrap();
+        let record = create_test_record_with_checksum(Some(
+            ExporterOutput::compute_payload_sha256(&decoded),
+        ));
+
+        let telemetry = TelemetryData::from_log_record(record).unwrap();
+        assert_eq!(telemetry.source, "test-service");
+    }
+
+    #[test]
+    fn test_from_log_record_with_checksum_mismatch() {
+        let record = create_test_record_with_checksum(Some(
+            ExporterOutput::compute_payload_sha256(b"something else"),
+        ));
+
+        let err = TelemetryData::from_log_record(record).unwrap_err();
+        assert_eq!(err.to_string(), "Payload checksum mismatch");
+    }
+
+    #[test]
+    fn test_to_log_record_round_trip() {
+        let telemetry = TelemetryData {
+            source: "test-service".to_string(),
+            payload: b"protobuf".to_vec(),
+            batch_id: Some("batch-1".to_string()),
+            signal: Signal::Metrics,
+            ..Default::default()
+        };
+
+        let record = telemetry.to_log_record();
+        assert_eq!(record.endpoint, "http://localhost:4318/v1/metrics");
+        assert_eq!(record.content_encoding, "identity");
+
+        let parsed = TelemetryData::from_log_record(record).unwrap();
+        assert_eq!(parsed.signal, Signal::Metrics);
+        assert_eq!(parsed.payload, b"protobuf");
+        assert_eq!(parsed.batch_id.as_deref(), Some("batch-1"));
     }
 
     #[test]
@@ -270,6 +463,9 @@ mod tests {
             payload: vec![1, 2, 3, 4, 5],
             content_type: "application/x-protobuf".to_string(),
             content_encoding: None,
+            batch_id: None,
+            signal: Signal::Traces,
+            span_count: None,
         };
 
         // Compress it
@@ -294,6 +490,7 @@ mod tests {
             original_payload.clone(),
             "application/x-protobuf",
             None,
+            Signal::Traces,
         )
         .unwrap();
 
@@ -308,8 +505,13 @@ mod tests {
         });
         let json_bytes = serde_json::to_vec(&json_data).unwrap();
 
-        let converted =
-            TelemetryData::convert_to_protobuf(json_bytes, "application/json", None).unwrap();
+        let converted = TelemetryData::convert_to_protobuf(
+            json_bytes,
+            "application/json",
+            None,
+            Signal::Traces,
+        )
+        .unwrap();
 
         // Verify we can decode it as an ExportTraceServiceRequest
         let request = ExportTraceServiceRequest::decode(converted.as_slice()).unwrap();
@@ -329,12 +531,72 @@ mod tests {
         encoder.write_all(&json_bytes).unwrap();
         let compressed = encoder.finish().unwrap();
 
-        let converted =
-            TelemetryData::convert_to_protobuf(compressed, "application/json", Some("gzip"))
-                .unwrap();
+        let converted = TelemetryData::convert_to_protobuf(
+            compressed,
+            "application/json",
+            Some("gzip"),
+            Signal::Traces,
+        )
+        .unwrap();
 
         // Verify we can decode it as an ExportTraceServiceRequest
         let request = ExportTraceServiceRequest::decode(converted.as_slice()).unwrap();
         assert_eq!(request.resource_spans.len(), 0);
     }
+
+    #[test]
+    fn test_signal_from_endpoint() {
+        assert_eq!(
+            Signal::from_endpoint("http://localhost:4318/v1/logs"),
+            Signal::Logs
+        );
+        assert_eq!(Signal::from_endpoint("/v1/logs/"), Signal::Logs);
+        assert_eq!(
+            Signal::from_endpoint("http://localhost:4318/v1/traces"),
+            Signal::Traces
+        );
+        assert_eq!(Signal::from_endpoint("http://example.com"), Signal::Traces);
+        assert_eq!(
+            Signal::from_endpoint("http://example.com/v1/logs?tenant=a"),
+            Signal::Logs
+        );
+        assert_eq!(
+            Signal::from_endpoint("http://localhost:4318/v1/metrics"),
+            Signal::Metrics
+        );
+    }
+
+    #[test]
+    fn test_from_log_record_with_logs_envelope() {
+        let json_payload = json!({
+            "resourceLogs": [{
+                "scopeLogs": [{
+                    "logRecords": [{ "severityText": "INFO" }]
+                }]
+            }]
+        });
+        let record = ExporterOutput {
+            version: "test".to_string(),
+            source: "test-service".to_string(),
+            endpoint: "http://localhost:4318/v1/logs".to_string(),
+            method: "POST".to_string(),
+            payload: json_payload.to_string(),
+            headers: None,
+            content_type: "application/json".to_string(),
+            content_encoding: "none".to_string(),
+            base64: false,
+            level: None,
+            payload_sha256: None,
+            batch_id: None,
+            compression_backend: None,
+        };
+
+        let telemetry = TelemetryData::from_log_record(record).unwrap();
+        assert_eq!(telemetry.signal, Signal::Logs);
+        let request = ExportLogsServiceRequest::decode(telemetry.payload.as_slice()).unwrap();
+        assert_eq!(
+            request.resource_logs[0].scope_logs[0].log_records[0].severity_text,
+            "INFO"
+        );
+    }
 }
@@ -0,0 +1,505 @@
+//! Destinations compacted telemetry can be delivered to, besides OTLP collectors.
+//!
+//! A [`TelemetryDestination`] delivers a compacted batch somewhere. [`HttpDestination`] sends it
+//! to an OTLP/HTTP collector like [`send_telemetry_batch_to`]; with the `aws-destinations`
+//! feature, [`KinesisDestination`] relays it to a Kinesis data stream, possibly in another
+//! account, and [`S3Destination`] archives it to an S3 bucket.
+//! [`process_event_batch_to_telemetry_destination`](crate::processor::process_event_batch_to_telemetry_destination)
+//! forwards an event to any of them.
+//!
+//! The Kinesis and S3 destinations write each batch as an exporter envelope (see
+//! [`TelemetryData::to_log_record`]), the format the forwarders read from CloudWatch Logs, so a
+//! relayed stream can be consumed by the Kinesis forwarder and an archive replayed with the
+//! `s3-ingest` feature.
+
+use crate::destination::Destination;
+use crate::http_sender::{send_telemetry_batch_to, ExportOutcome, HttpOtlpForwarderClient};
+use crate::retry::RetryConfig;
+use crate::telemetry::TelemetryData;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::fmt;
+
+#[cfg(feature = "aws-destinations")]
+use crate::sigv4::SigV4Signer;
+#[cfg(feature = "aws-destinations")]
+use anyhow::{anyhow, Context};
+#[cfg(feature = "aws-destinations")]
+use aws_credential_types::provider::ProvideCredentials;
+#[cfg(feature = "aws-destinations")]
+use base64::{engine::general_purpose, Engine};
+#[cfg(feature = "aws-destinations")]
+use rand::Rng;
+#[cfg(feature = "aws-destinations")]
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+#[cfg(feature = "aws-destinations")]
+use reqwest::Client as ReqwestClient;
+#[cfg(feature = "aws-destinations")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "aws-destinations")]
+use tracing::debug;
+#[cfg(feature = "aws-destinations")]
+use url::Url;
+
+/// Timeout of each request to Kinesis or S3
+#[cfg(feature = "aws-destinations")]
+const AWS_DESTINATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A place compacted telemetry batches are delivered to
+#[async_trait]
+pub trait TelemetryDestination: Send + Sync + fmt::Debug {
+    /// Returns the name identifying the destination in logs; it must not contain secrets
+    fn name(&self) -> &str;
+
+    /// Delivers a compacted batch
+    async fn send(&self, telemetry_data: TelemetryData) -> Result<ExportOutcome>;
+}
+
+/// A [`TelemetryDestination`] sending batches to an OTLP/HTTP collector with retries
+#[derive(Debug)]
+pub struct HttpDestination<C> {
+    client: C,
+    destination: Destination,
+    retry_config: RetryConfig,
+}
+
+impl<C: HttpOtlpForwarderClient> HttpDestination<C> {
+    /// Creates a destination sending with `client` to `destination`
+    pub fn new(client: C, destination: Destination, retry_config: RetryConfig) -> Self {
+        Self {
+            client,
+            destination,
+            retry_config,
+        }
+    }
+}
+
+#[async_trait]
+impl<C: HttpOtlpForwarderClient + fmt::Debug> TelemetryDestination for HttpDestination<C> {
+    fn name(&self) -> &str {
+        self.destination.name()
+    }
+
+    async fn send(&self, telemetry_data: TelemetryData) -> Result<ExportOutcome> {
+        send_telemetry_batch_to(
+            &self.client,
+            telemetry_data,
+            &self.destination,
+            &self.retry_config,
+        )
+        .await
+    }
+}
+
+/// A [`TelemetryDestination`] relaying each batch as one record of a Kinesis data stream
+///
+/// The stream is named by its name, or by its ARN for a stream of another account, in which case
+/// the credentials must be allowed to write to it. Records are limited to 1 MiB, so keep
+/// [`max_payload_size`](crate::SpanCompactionConfig::max_payload_size) below that. Requires the
+/// `aws-destinations` feature.
+#[cfg(feature = "aws-destinations")]
+pub struct KinesisDestination {
+    client: ReqwestClient,
+    stream: String,
+    endpoint: Option<Url>,
+    signer: SigV4Signer,
+}
+
+#[cfg(feature = "aws-destinations")]
+impl KinesisDestination {
+    /// Creates a destination writing to the stream named `stream` (a name or an ARN) in
+    /// `region`, signing with `credentials`
+    pub fn new(
+        stream: impl Into<String>,
+        credentials: impl ProvideCredentials + 'static,
+        region: impl Into<String>,
+    ) -> Self {
+        Self {
+            client: ReqwestClient::new(),
+            stream: stream.into(),
+            endpoint: None,
+            signer: SigV4Signer::new(credentials, region, "kinesis"),
+        }
+    }
+
+    /// Writes to a Kinesis-compatible endpoint instead of the regional Kinesis endpoint
+    pub fn with_endpoint(mut self, endpoint: Url) -> Self {
+        self.endpoint = Some(endpoint);
+        self
+    }
+
+    fn target_url(&self) -> Result<Url> {
+        match &self.endpoint {
+            Some(endpoint) => Ok(endpoint.clone()),
+            None => Url::parse(&format!(
+                "https://kinesis.{}.amazonaws.com/",
+                self.signer.region()
+            ))
+            .context("Invalid Kinesis region"),
+        }
+    }
+}
+
+// The stream ARN names the account, so it is left out
+#[cfg(feature = "aws-destinations")]
+impl fmt::Debug for KinesisDestination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KinesisDestination")
+            .field("region", &self.signer.region())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "aws-destinations")]
+#[async_trait]
+impl TelemetryDestination for KinesisDestination {
+    fn name(&self) -> &str {
+        "kinesis"
+    }
+
+    async fn send(&self, telemetry_data: TelemetryData) -> Result<ExportOutcome> {
+        let record = serde_json::to_vec(&telemetry_data.to_log_record())
+            .context("Failed to serialize telemetry envelope")?;
+        let stream_field = if self.stream.starts_with("arn:") {
+            "StreamARN"
+        } else {
+            "StreamName"
+        };
+        let body = serde_json::to_vec(&serde_json::json!({
+            stream_field: self.stream,
+            "Records": [{
+                "Data": general_purpose::STANDARD.encode(&record),
+                "PartitionKey": format!("{:016x}", rand::rng().random::<u64>()),
+            }],
+        }))
+        .context("Failed to serialize Kinesis request")?;
+
+        let target_url = self.target_url()?;
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-amz-json-1.1"),
+        );
+        headers.insert(
+            "x-amz-target",
+            HeaderValue::from_static("Kinesis_20131202.PutRecords"),
+        );
+        let signature = self
+            .signer
+            .signature_headers("POST", &target_url, &headers, &body)
+            .await?;
+        headers.extend(signature);
+
+        let response = self
+            .client
+            .post(target_url)
+            .headers(headers)
+            .body(body)
+            .timeout(AWS_DESTINATION_TIMEOUT)
+            .send()
+            .await
+            .context("Failed to send telemetry to Kinesis")?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!(
+                "Kinesis rejected telemetry with status {}",
+                status.as_u16()
+            ));
+        }
+        // PutRecords answers 200 even when its records were throttled
+        let failed_record_count = response
+            .json::<serde_json::Value>()
+            .await
+            .ok()
+            .and_then(|body| body["FailedRecordCount"].as_u64())
+            .unwrap_or(0);
+        if failed_record_count > 0 {
+            return Err(anyhow!("Kinesis did not accept the telemetry record"));
+        }
+        debug!(
+            record_size_bytes = record.len() as u64,
+            "Sent telemetry to Kinesis"
+        );
+        Ok(ExportOutcome::Accepted)
+    }
+}
+
+/// A [`TelemetryDestination`] archiving each batch as an S3 object
+///
+/// Objects are named `<prefix><signal>/<time_ms>-<random>.json` and hold one exporter envelope
+/// line. Requires the `aws-destinations` feature.
+#[cfg(feature = "aws-destinations")]
+pub struct S3Destination {
+    client: ReqwestClient,
+    bucket: String,
+    prefix: String,
+    endpoint: Option<Url>,
+    signer: SigV4Signer,
+}
+
+#[cfg(feature = "aws-destinations")]
+impl S3Destination {
+    /// Creates a destination writing to `bucket` in `region` under `prefix`, signing with
+    /// `credentials`
+    pub fn new(
+        bucket: impl Into<String>,
+        prefix: impl Into<String>,
+        credentials: impl ProvideCredentials + 'static,
+        region: impl Into<String>,
+    ) -> Self {
+        Self {
+            client: ReqwestClient::new(),
+            bucket: bucket.into(),
+            prefix: prefix.into(),
+            endpoint: None,
+            signer: SigV4Signer::for_s3(credentials, region),
+        }
+    }
+
+    /// Writes to an S3-compatible endpoint with path-style URLs instead of the regional
+    /// virtual-hosted S3 endpoint
+    pub fn with_endpoint(mut self, endpoint: Url) -> Self {
+        self.endpoint = Some(endpoint);
+        self
+    }
+
+    fn object_url(&self, key: &str) -> Result<Url> {
+        let url = match &self.endpoint {
+            Some(endpoint) => {
+                let mut url = endpoint.clone();
+                url.set_path(&format!(
+                    "{}/{}/{}",
+                    endpoint.path().trim_end_matches('/'),
+                    self.bucket,
+                    key
+                ));
+                url
+            }
+            None => Url::parse(&format!(
+                "https://{}.s3.{}.amazonaws.com/{}",
+                self.bucket,
+                self.signer.region(),
+                key
+            ))
+            .context("Invalid S3 destination bucket name")?,
+        };
+        Ok(url)
+    }
+}
+
+#[cfg(feature = "aws-destinations")]
+impl fmt::Debug for S3Destination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Destination")
+            .field("bucket", &self.bucket)
+            .field("prefix", &self.prefix)
+            .field("region", &self.signer.region())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "aws-destinations")]
+#[async_trait]
+impl TelemetryDestination for S3Destination {
+    fn name(&self) -> &str {
+        "s3"
+    }
+
+    async fn send(&self, telemetry_data: TelemetryData) -> Result<ExportOutcome> {
+        let sent_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let key = format!(
+            "{}{}/{:013}-{:016x}.json",
+            self.prefix,
+            telemetry_data.signal,
+            sent_at_ms,
+            rand::rng().random::<u64>()
+        );
+        let target_url = self.object_url(&key)?;
+        let mut body = serde_json::to_vec(&telemetry_data.to_log_record())
+            .context("Failed to serialize telemetry envelope")?;
+        body.push(b'\n');
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-ndjson"),
+        );
+        let signature = self
+            .signer
+            .signature_headers("PUT", &target_url, &headers, &body)
+            .await?;
+        headers.extend(signature);
+
+        let object_size_bytes = body.len() as u64;
+        let response = self
+            .client
+            .put(target_url)
+            .headers(headers)
+            .body(body)
+            .timeout(AWS_DESTINATION_TIMEOUT)
+            .send()
+            .await
+            .context("Failed to write telemetry to S3")?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "S3 rejected telemetry with status {}",
+                response.status().as_u16()
+            ));
+        }
+        debug!(object_size_bytes, "Wrote telemetry to S3");
+        Ok(ExportOutcome::Accepted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::Signal;
+    use reqwest::Client as ReqwestClient;
+    use url::Url;
+    use wiremock::matchers::{body_bytes, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn telemetry() -> TelemetryData {
+        TelemetryData {
+            source: "/aws/lambda/checkout".to_string(),
+            payload: b"payload".to_vec(),
+            content_encoding: Some("gzip".to_string()),
+            signal: Signal::Logs,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_destination_sends_to_signal_endpoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/logs"))
+            .and(body_bytes(b"payload".to_vec()))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let destination = HttpDestination::new(
+            ReqwestClient::new(),
+            Destination::new(
+                "vendor",
+                Url::parse(&format!("{}/v1/traces", server.uri())).unwrap(),
+            ),
+            RetryConfig::disabled(),
+        );
+
+        assert_eq!(destination.name(), "vendor");
+        let outcome = destination.send(telemetry()).await.unwrap();
+        assert_eq!(outcome, ExportOutcome::Accepted);
+    }
+
+    #[cfg(feature = "aws-destinations")]
+    mod aws {
+        use super::*;
+        use crate::telemetry::TelemetryData;
+        use aws_credential_types::Credentials;
+        use otlp_stdout_span_exporter::ExporterOutput;
+        use wiremock::matchers::{header, header_exists, path_regex};
+
+        fn test_credentials() -> Credentials {
+            Credentials::new("AKIDEXAMPLE", "test-secret-key", None, None, "test")
+        }
+
+        /// Parses an envelope back, as a downstream forwarder would
+        fn parse_envelope(bytes: &[u8]) -> TelemetryData {
+            let record: ExporterOutput = serde_json::from_slice(bytes).unwrap();
+            assert_eq!(record.source, "/aws/lambda/checkout");
+            TelemetryData::from_log_record(record).unwrap()
+        }
+
+        #[tokio::test]
+        async fn test_kinesis_destination_puts_signed_record() {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/"))
+                .and(header("x-amz-target", "Kinesis_20131202.PutRecords"))
+                .and(header_exists("authorization"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({"FailedRecordCount": 0})),
+                )
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+            Mock::given(method("POST"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({"FailedRecordCount": 1})),
+                )
+                .mount(&server)
+                .await;
+            let stream = "arn:aws:kinesis:us-east-1:210987654321:stream/otlp-relay";
+            let destination = KinesisDestination::new(stream, test_credentials(), "us-east-1")
+                .with_endpoint(Url::parse(&server.uri()).unwrap());
+
+            let mut payload = Vec::new();
+            {
+                use flate2::{write::GzEncoder, Compression};
+                use std::io::Write;
+                let mut encoder = GzEncoder::new(&mut payload, Compression::default());
+                encoder.write_all(b"payload").unwrap();
+                encoder.finish().unwrap();
+            }
+            let compressed = TelemetryData {
+                payload,
+                ..telemetry()
+            };
+            destination.send(compressed).await.unwrap();
+
+            let requests = server.received_requests().await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+            assert_eq!(body["StreamARN"], stream);
+            let data = general_purpose::STANDARD
+                .decode(body["Records"][0]["Data"].as_str().unwrap())
+                .unwrap();
+            let relayed = parse_envelope(&data);
+            assert_eq!(relayed.signal, Signal::Logs);
+            assert_eq!(relayed.payload, b"payload");
+
+            // Throttled records are reported in the body of a 200 response
+            let err = destination.send(telemetry()).await.unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Kinesis did not accept the telemetry record"
+            );
+        }
+
+        #[tokio::test]
+        async fn test_s3_destination_archives_envelope() {
+            let server = MockServer::start().await;
+            Mock::given(method("PUT"))
+                .and(path_regex(
+                    r"^/archive-bucket/otlp/logs/\d{13}-[0-9a-f]{16}\.json$",
+                ))
+                .and(header_exists("x-amz-content-sha256"))
+                .and(header_exists("authorization"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+            let destination =
+                S3Destination::new("archive-bucket", "otlp/", test_credentials(), "us-east-1")
+                    .with_endpoint(Url::parse(&server.uri()).unwrap());
+            let telemetry = TelemetryData {
+                content_encoding: None,
+                ..telemetry()
+            };
+
+            destination.send(telemetry).await.unwrap();
+
+            let requests = server.received_requests().await.unwrap();
+            let body = &requests[0].body;
+            assert_eq!(body.last(), Some(&b'\n'));
+            assert_eq!(parse_envelope(body).payload, b"payload");
+        }
+    }
+}
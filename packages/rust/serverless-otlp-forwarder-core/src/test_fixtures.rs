@@ -0,0 +1,29 @@
+//! Log records shared by the tests of the event sources and parsers.
+
+use serde_json::{json, Value};
+
+/// An otlp-stdout record of an empty, gzipped trace export request from `source`
+pub(crate) fn otlp_stdout_record(source: &str) -> String {
+    otlp_stdout_envelope(source, "http://collector/v1/traces").to_string()
+}
+
+/// An otlp-stdout record like [`otlp_stdout_record`], sent to `endpoint` with a batch id
+pub(crate) fn otlp_stdout_batch_record(endpoint: &str, batch_id: &str) -> String {
+    let mut envelope = otlp_stdout_envelope("service-a", endpoint);
+    envelope["batch_id"] = json!(batch_id);
+    envelope.to_string()
+}
+
+fn otlp_stdout_envelope(source: &str, endpoint: &str) -> Value {
+    json!({
+        "__otel_otlp_stdout": "otlp-stdout-span-exporter@0.2.2",
+        "source": source,
+        "endpoint": endpoint,
+        "method": "POST",
+        "payload": "H4sIAAAAAAAAAAMAAAAAAAAAAAA=",
+        "headers": {"content-type": "application/x-protobuf"},
+        "content-type": "application/x-protobuf",
+        "content-encoding": "gzip",
+        "base64": true
+    })
+}
@@ -0,0 +1,170 @@
+//! Tail-based sampling of the traces of a batch.
+//!
+//! A [`TraceSampler`] keeps a percentage of the traces of every compacted trace batch, but always
+//! keeps the traces holding an error span, so failures stay visible while the backend receives
+//! less data. Spans are grouped by trace ID within the batch being compacted; a trace spread
+//! over several batches or invocations gets the same decision everywhere, as the decision is
+//! derived from the trace ID itself, like the `traceidratio` sampler of OpenTelemetry SDKs. Its
+//! error status is only known from the spans of the batch, though.
+//!
+//! The percentage is set with the `sampling_percentage` setting of the configuration file or the
+//! `OTLP_FORWARDER_SAMPLING_PERCENTAGE` environment variable, from `0` to `100` (the default,
+//! which keeps every trace).
+
+use crate::span_filter::retain_spans;
+use opentelemetry_proto::tonic::trace::v1::{status::StatusCode, ResourceSpans, Span};
+use std::collections::HashSet;
+
+/// Environment variable holding the percentage of non-error traces to keep, from 0 to 100
+pub const SAMPLING_PERCENTAGE_ENV_VAR: &str = "OTLP_FORWARDER_SAMPLING_PERCENTAGE";
+
+/// Keeps a percentage of the non-error traces of a batch, and every error trace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceSampler {
+    percentage: u32,
+}
+
+impl Default for TraceSampler {
+    /// Keeps every trace
+    fn default() -> Self {
+        Self { percentage: 100 }
+    }
+}
+
+fn is_error(span: &Span) -> bool {
+    span.status
+        .as_ref()
+        .is_some_and(|status| status.code() == StatusCode::Error)
+}
+
+impl TraceSampler {
+    /// Creates a sampler keeping `percentage` percent of the non-error traces; values above
+    /// `100` keep every trace
+    pub fn new(percentage: u32) -> Self {
+        Self {
+            percentage: percentage.min(100),
+        }
+    }
+
+    /// Returns the percentage of non-error traces kept
+    pub fn percentage(&self) -> u32 {
+        self.percentage
+    }
+
+    /// Returns true if the sampler may drop traces
+    pub fn is_enabled(&self) -> bool {
+        self.percentage < 100
+    }
+
+    /// Returns true if the trace is sampled, regardless of its spans
+    ///
+    /// The decision compares the lower 8 bytes of the trace ID with the percentage, so it is the
+    /// same for every span of the trace. Malformed trace IDs are always kept.
+    pub fn samples(&self, trace_id: &[u8]) -> bool {
+        let Some(low) = trace_id
+            .get(8..16)
+            .and_then(|bytes| <[u8; 8]>::try_from(bytes).ok())
+        else {
+            return true;
+        };
+        // low / 2^64 < percentage / 100
+        u128::from(u64::from_be_bytes(low)) * 100 < u128::from(self.percentage) << 64
+    }
+
+    /// Drops the spans of the unsampled traces of a trace request, keeping every trace that has
+    /// an error span, and returns the number of spans dropped
+    ///
+    /// Scopes and resources left without spans are removed as well.
+    pub fn apply(&self, resource_spans: &mut Vec<ResourceSpans>) -> usize {
+        if !self.is_enabled() {
+            return 0;
+        }
+        let error_traces: HashSet<Vec<u8>> = resource_spans
+            .iter()
+            .flat_map(|resource| &resource.scope_spans)
+            .flat_map(|scope| &scope.spans)
+            .filter(|span| is_error(span))
+            .map(|span| span.trace_id.clone())
+            .collect();
+
+        retain_spans(resource_spans, |span| {
+            error_traces.contains(&span.trace_id) || self.samples(&span.trace_id)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::trace::v1::{ScopeSpans, Status};
+
+    fn trace_id(low: u64) -> Vec<u8> {
+        let mut trace_id = vec![0xab; 8];
+        trace_id.extend_from_slice(&low.to_be_bytes());
+        trace_id
+    }
+
+    fn span(trace_id: Vec<u8>, code: StatusCode) -> Span {
+        Span {
+            trace_id,
+            status: Some(Status {
+                code: code as i32,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_samples_by_trace_id() {
+        let sampler = TraceSampler::new(25);
+        assert!(sampler.samples(&trace_id(0)));
+        assert!(sampler.samples(&trace_id(u64::MAX / 4 - 1)));
+        assert!(!sampler.samples(&trace_id(u64::MAX / 4 + 1)));
+        assert!(sampler.samples(b"short"));
+
+        assert!(!TraceSampler::new(0).samples(&trace_id(0)));
+        assert!(TraceSampler::new(100).samples(&trace_id(u64::MAX)));
+        assert_eq!(TraceSampler::new(250), TraceSampler::default());
+        assert!(!TraceSampler::default().is_enabled());
+    }
+
+    #[test]
+    fn test_apply_keeps_error_traces() {
+        let sampled = trace_id(0);
+        let unsampled = trace_id(u64::MAX);
+        let failed = trace_id(u64::MAX - 1);
+        let mut request = vec![
+            ResourceSpans {
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![
+                        span(sampled.clone(), StatusCode::Ok),
+                        span(unsampled.clone(), StatusCode::Ok),
+                        span(failed.clone(), StatusCode::Unset),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            ResourceSpans {
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![
+                        span(unsampled, StatusCode::Unset),
+                        span(failed.clone(), StatusCode::Error),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ];
+
+        assert_eq!(TraceSampler::new(50).apply(&mut request), 2);
+        let kept: Vec<&[u8]> = request
+            .iter()
+            .flat_map(|resource| &resource.scope_spans)
+            .flat_map(|scope| &scope.spans)
+            .map(|span| span.trace_id.as_slice())
+            .collect();
+        assert_eq!(kept, vec![&sampled[..], &failed[..], &failed[..]]);
+    }
+}
@@ -0,0 +1,269 @@
+//! Attribute transformation applied to telemetry before compaction.
+//!
+//! An [`AttributeTransform`] deletes, renames and sets resource and span attributes, for example
+//! to inject `deployment.environment` or to strip `aws.log.group.names` before telemetry leaves
+//! the account. It is applied by [`compact_telemetry_payloads`] to every decoded request, and
+//! configured through the `attribute_transform` setting of the configuration file or the
+//! `OTLP_FORWARDER_ATTRIBUTE_TRANSFORM` environment variable, holding the same document as JSON
+//! or YAML:
+//!
+//! ```yaml
+//! resource:
+//!   set:
+//!     deployment.environment: production
+//!   delete:
+//!     - aws.log.group.names
+//! span:
+//!   rename:
+//!     http.method: http.request.method
+//! ```
+//!
+//! Within each rule set, attributes are deleted first, then renamed, then set.
+//!
+//! [`compact_telemetry_payloads`]: crate::span_compactor::compact_telemetry_payloads
+
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+use opentelemetry_proto::tonic::logs::v1::ResourceLogs;
+use opentelemetry_proto::tonic::metrics::v1::ResourceMetrics;
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use opentelemetry_proto::tonic::trace::v1::ResourceSpans;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Environment variable holding an attribute transform document, as JSON or YAML
+pub const ATTRIBUTE_TRANSFORM_ENV_VAR: &str = "OTLP_FORWARDER_ATTRIBUTE_TRANSFORM";
+
+/// Attribute changes applied to telemetry before compaction
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttributeTransform {
+    /// Rules applied to the resource attributes of every signal
+    pub resource: AttributeRules,
+    /// Rules applied to the attributes of every span
+    pub span: AttributeRules,
+}
+
+/// Changes applied to one set of attributes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttributeRules {
+    /// Keys of the attributes to delete
+    pub delete: Vec<String>,
+    /// Attributes to rename, from the current key to the new one; an attribute already holding
+    /// the new key is replaced
+    pub rename: BTreeMap<String, String>,
+    /// Attributes to add, replacing any attribute with the same key
+    pub set: BTreeMap<String, AttributeValue>,
+}
+
+/// Value of an attribute set by an [`AttributeRules`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    Bool(bool),
+    Number(serde_json::Number),
+    String(String),
+}
+
+impl AttributeValue {
+    pub(crate) fn to_any_value(&self) -> AnyValue {
+        let value = match self {
+            Self::Bool(value) => any_value::Value::BoolValue(*value),
+            Self::Number(number) => match number.as_i64() {
+                Some(value) => any_value::Value::IntValue(value),
+                None => any_value::Value::DoubleValue(number.as_f64().unwrap_or_default()),
+            },
+            Self::String(value) => any_value::Value::StringValue(value.clone()),
+        };
+        AnyValue { value: Some(value) }
+    }
+}
+
+impl AttributeRules {
+    /// Returns true if the rules change nothing
+    pub fn is_empty(&self) -> bool {
+        self.delete.is_empty() && self.rename.is_empty() && self.set.is_empty()
+    }
+
+    /// Applies the rules to a list of attributes
+    pub fn apply(&self, attributes: &mut Vec<KeyValue>) {
+        if self.is_empty() {
+            return;
+        }
+        attributes.retain(|attribute| !self.delete.contains(&attribute.key));
+        for (from, to) in &self.rename {
+            let Some(position) = attributes
+                .iter()
+                .position(|attribute| &attribute.key == from)
+            else {
+                continue;
+            };
+            let mut attribute = attributes.remove(position);
+            attributes.retain(|existing| &existing.key != to);
+            attribute.key = to.clone();
+            attributes.push(attribute);
+        }
+        for (key, value) in &self.set {
+            let value = Some(value.to_any_value());
+            match attributes
+                .iter_mut()
+                .find(|attribute| &attribute.key == key)
+            {
+                Some(attribute) => attribute.value = value,
+                None => attributes.push(KeyValue {
+                    key: key.clone(),
+                    value,
+                }),
+            }
+        }
+    }
+}
+
+impl AttributeTransform {
+    /// Parses a transform document, as JSON or YAML
+    pub fn parse(document: &str) -> anyhow::Result<Self> {
+        // JSON documents are valid YAML; parse errors may quote values, so they are not reported
+        serde_yaml::from_str(document)
+            .map_err(|_| anyhow::anyhow!("Invalid attribute transform document"))
+    }
+
+    /// Returns true if the transform changes nothing
+    pub fn is_empty(&self) -> bool {
+        self.resource.is_empty() && self.span.is_empty()
+    }
+
+    fn apply_to_resource(&self, resource: &mut Option<Resource>) {
+        if self.resource.is_empty() {
+            return;
+        }
+        self.resource
+            .apply(&mut resource.get_or_insert_with(Resource::default).attributes);
+    }
+
+    /// Applies the transform to the resources and spans of a trace request
+    pub fn apply_to_spans(&self, resource_spans: &mut [ResourceSpans]) {
+        for resource in resource_spans {
+            self.apply_to_resource(&mut resource.resource);
+            if self.span.is_empty() {
+                continue;
+            }
+            for span in resource
+                .scope_spans
+                .iter_mut()
+                .flat_map(|scope| scope.spans.iter_mut())
+            {
+                self.span.apply(&mut span.attributes);
+            }
+        }
+    }
+
+    /// Applies the resource rules of the transform to a logs request
+    pub fn apply_to_logs(&self, resource_logs: &mut [ResourceLogs]) {
+        for resource in resource_logs {
+            self.apply_to_resource(&mut resource.resource);
+        }
+    }
+
+    /// Applies the resource rules of the transform to a metrics request
+    pub fn apply_to_metrics(&self, resource_metrics: &mut [ResourceMetrics]) {
+        for resource in resource_metrics {
+            self.apply_to_resource(&mut resource.resource);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::trace::v1::{ScopeSpans, Span};
+
+    fn attribute(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value.to_string())),
+            }),
+        }
+    }
+
+    fn value_of<'a>(attributes: &'a [KeyValue], key: &str) -> Option<&'a any_value::Value> {
+        attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .and_then(|attribute| attribute.value.as_ref()?.value.as_ref())
+    }
+
+    #[test]
+    fn test_parse_json_and_yaml_documents() {
+        let yaml = AttributeTransform::parse(
+            "resource:\n  set:\n    deployment.environment: prod\n    sample.rate: 0.5\n  delete: [aws.log.group.names]\n",
+        )
+        .unwrap();
+        let json = AttributeTransform::parse(
+            r#"{"resource": {"set": {"deployment.environment": "prod", "sample.rate": 0.5}, "delete": ["aws.log.group.names"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(yaml, json);
+        assert!(yaml.span.is_empty());
+
+        let err = AttributeTransform::parse(r#"{"resources": {"secret": "value"}}"#).unwrap_err();
+        assert!(!err.to_string().contains("secret"));
+    }
+
+    #[test]
+    fn test_apply_to_spans() {
+        let transform = AttributeTransform::parse(
+            r#"{
+                "resource": {"set": {"deployment.environment": "prod", "replicas": 3}, "delete": ["aws.log.group.names"]},
+                "span": {"rename": {"http.method": "http.request.method"}, "set": {"sampled": true}}
+            }"#,
+        )
+        .unwrap();
+        let mut resource_spans = vec![ResourceSpans {
+            resource: Some(Resource {
+                attributes: vec![
+                    attribute("service.name", "checkout"),
+                    attribute("aws.log.group.names", "/aws/lambda/checkout"),
+                    attribute("deployment.environment", "dev"),
+                ],
+                ..Default::default()
+            }),
+            scope_spans: vec![ScopeSpans {
+                spans: vec![Span {
+                    attributes: vec![
+                        attribute("http.method", "GET"),
+                        attribute("http.request.method", "stale"),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }];
+
+        transform.apply_to_spans(&mut resource_spans);
+
+        let resource = &resource_spans[0].resource.as_ref().unwrap().attributes;
+        assert!(value_of(resource, "aws.log.group.names").is_none());
+        assert_eq!(
+            value_of(resource, "deployment.environment"),
+            Some(&any_value::Value::StringValue("prod".to_string()))
+        );
+        assert_eq!(
+            value_of(resource, "replicas"),
+            Some(&any_value::Value::IntValue(3))
+        );
+        assert!(value_of(resource, "service.name").is_some());
+
+        let span = &resource_spans[0].scope_spans[0].spans[0].attributes;
+        assert_eq!(span.len(), 2);
+        assert_eq!(
+            value_of(span, "http.request.method"),
+            Some(&any_value::Value::StringValue("GET".to_string()))
+        );
+        assert_eq!(
+            value_of(span, "sampled"),
+            Some(&any_value::Value::BoolValue(true))
+        );
+    }
+}